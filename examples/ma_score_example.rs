@@ -0,0 +1,65 @@
+use aipriceaction_proxy::analysis::ma_score::{self, MA_PERIODS};
+use aipriceaction_proxy::vci::OhlcvData;
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+use std::collections::HashMap;
+
+// Deterministic fixture: a trending series with a weekly wave, so no network is needed
+fn fixture_series(symbol: &str, base: f64, trend: f64, days: i64) -> Vec<OhlcvData> {
+    let start = Utc.with_ymd_and_hms(2025, 6, 2, 7, 0, 0).unwrap();
+    (0..days)
+        .map(|day| {
+            let close = base * (1.0 + trend * day as f64) + (day as f64 * 0.9).sin() * base * 0.02;
+            OhlcvData {
+                time: start + Duration::days(day),
+                open: close * 0.995,
+                high: close * 1.01,
+                low: close * 0.99,
+                close,
+                volume: 1_000_000 + (day as u64 % 5) * 100_000,
+                symbol: Some(symbol.to_string()),
+            }
+        })
+        .collect()
+}
+
+fn main() {
+    println!("MA Score Example");
+    println!("================");
+
+    let fixtures = [("VCB", 60.0, 0.002), ("BID", 45.0, -0.001), ("CTG", 35.0, 0.004)];
+
+    // 1. Per-symbol MA scores
+    let mut scores_by_symbol = HashMap::new();
+    for (symbol, base, trend) in fixtures {
+        let series = fixture_series(symbol, base, trend, 80);
+        let points = ma_score::calculate_ma_scores(&series);
+
+        if let Some(latest) = points.last() {
+            println!(
+                "\n📈 {} on {}: close {:.2} | MA10 {:+.2}% | MA20 {:+.2}% | MA50 {:+.2}%",
+                symbol,
+                latest.date,
+                latest.close,
+                latest.ma10_score.unwrap_or_default(),
+                latest.ma20_score.unwrap_or_default(),
+                latest.ma50_score.unwrap_or_default(),
+            );
+        }
+        scores_by_symbol.insert(symbol.to_string(), points);
+    }
+
+    // 2. Group distribution, excluding CTG before a (made up) membership date
+    let ctg_joined = NaiveDate::from_ymd_opt(2025, 8, 1).unwrap();
+    let is_member = |symbol: &str, date: NaiveDate| symbol != "CTG" || date >= ctg_joined;
+
+    for period in MA_PERIODS {
+        let distribution = ma_score::ma_score_distribution(&scores_by_symbol, period, None, None, is_member);
+        println!("\n📊 MA{} distribution ({} dates), last 3:", period, distribution.len());
+        for day in distribution.iter().rev().take(3).rev() {
+            println!(
+                "  {}: n={} median {:+.2}% [{:+.2}%, {:+.2}%] positive {:.0}%",
+                day.date, day.count, day.median, day.min, day.max, day.percent_positive
+            );
+        }
+    }
+}
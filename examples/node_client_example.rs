@@ -0,0 +1,58 @@
+use aipriceaction_proxy::data_structures::{HealthStats, SymbolMetadata};
+use std::collections::HashMap;
+use std::env;
+
+// Consume a running node over HTTP, e.g. NODE_URL=https://api.aipriceaction.com
+#[tokio::main]
+async fn main() -> Result<(), reqwest::Error> {
+    let node_url = env::var("NODE_URL").unwrap_or_else(|_| "http://localhost:8888".to_string());
+    let client = reqwest::Client::new();
+
+    println!("Node Client Example ({})", node_url);
+    println!("{}", "=".repeat(40));
+
+    // 1. Health and readiness
+    let health: HealthStats = client.get(format!("{}/health", node_url)).send().await?.json().await?;
+    println!(
+        "\n🩺 {} ({}) iteration {} | {} active tickers | initial load complete: {}",
+        health.node_name, health.environment, health.iteration_count, health.active_tickers_count, health.initial_load_complete
+    );
+
+    // 2. Symbol metadata for frontend boot
+    let symbols: Vec<SymbolMetadata> = client.get(format!("{}/symbols", node_url)).send().await?.json().await?;
+    let vn30_count = symbols.iter().filter(|s| s.indices.iter().any(|i| i == "VN30")).count();
+    println!("\n🏷️  {} symbols, {} in VN30", symbols.len(), vn30_count);
+
+    // 3. Latest bars for a few symbols (time is serialized as a YYYY-MM-DD date)
+    let tickers: HashMap<String, Vec<serde_json::Value>> = client
+        .get(format!("{}/tickers", node_url))
+        .query(&[("symbol", "VCB"), ("symbol", "FPT"), ("symbol", "VNINDEX")])
+        .send()
+        .await?
+        .json()
+        .await?;
+    println!("\n💹 Latest prices:");
+    for (symbol, bars) in &tickers {
+        if let Some(latest) = bars.last() {
+            println!("  {}: {} on {} (vol {})", symbol, latest["close"], latest["time"], latest["volume"]);
+        }
+    }
+
+    // 4. Group MA score distribution (may be 503 on nodes with STRICT_READINESS while loading)
+    let response = client
+        .get(format!("{}/analysis/ma-distribution", node_url))
+        .query(&[("group", "NGAN_HANG"), ("period", "20")])
+        .send()
+        .await?;
+    if response.status().is_success() {
+        let body: serde_json::Value = response.json().await?;
+        println!("\n📊 NGAN_HANG MA20 meta: {}", body["meta"]);
+        if let Some(latest) = body["distribution"].as_array().and_then(|d| d.last()) {
+            println!("  Latest: {}", latest);
+        }
+    } else {
+        println!("\n⏳ MA distribution not ready: {} (Retry-After: {:?})", response.status(), response.headers().get("retry-after"));
+    }
+
+    Ok(())
+}
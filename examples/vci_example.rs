@@ -1,3 +1,4 @@
+use aipriceaction_proxy::utils::rate_limiter::{self, HostBudget};
 use aipriceaction_proxy::vci::{VciClient, VciError};
 
 #[tokio::main]
@@ -5,7 +6,9 @@ async fn main() -> Result<(), VciError> {
     println!("VCI Client Example");
     println!("==================");
 
-    let mut client = VciClient::new(true, 6)?;
+    // Go easy on the API: 6 requests a minute
+    rate_limiter::set_budgets([("trading.vietcap.com.vn".to_string(), HostBudget::new(6))].into());
+    let client = VciClient::new(true)?;
     let test_symbol = "VCI";
 
     // 1. Test company info
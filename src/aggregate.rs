@@ -0,0 +1,161 @@
+use chrono::{Duration, NaiveDate};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Windows produced by default, matching the files published in the data repo
+pub const DEFAULT_WINDOWS: [i64; 3] = [60, 180, 365];
+const DEFAULT_HEADER: &str = "ticker,time,open,high,low,close,volume";
+
+/// One per-ticker CSV: ticker name (from the file name) and its content
+pub struct TickerFile {
+    pub ticker: String,
+    pub content: String,
+}
+
+pub(crate) fn column_index(header: &[&str], names: &[&str]) -> Option<usize> {
+    header.iter().position(|column| names.contains(&column.trim().to_lowercase().as_str()))
+}
+
+fn row_date(row: &str, time_index: usize) -> Option<NaiveDate> {
+    let value = row.split(',').nth(time_index)?.trim();
+    NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// Build the aggregated CSV for the last `days` calendar days, counted back from the
+/// newest date across all files. Output is sorted by ticker then time, so the same
+/// inputs always produce byte-identical output.
+pub fn aggregate(files: &[TickerFile], days: i64) -> String {
+    // ticker -> time-sorted (date, row) pairs, with a ticker column added if the file lacks one
+    let mut rows_by_ticker: BTreeMap<&str, Vec<(NaiveDate, String)>> = BTreeMap::new();
+    let mut header_line: Option<String> = None;
+
+    for file in files {
+        let mut lines = file.content.lines();
+        let Some(header) = lines.next() else { continue };
+        let columns: Vec<&str> = header.split(',').collect();
+        let Some(time_index) = column_index(&columns, &["time", "date"]) else { continue };
+        let has_ticker = column_index(&columns, &["ticker", "symbol"]).is_some();
+
+        if header_line.is_none() {
+            header_line = Some(if has_ticker { header.trim().to_string() } else { format!("ticker,{}", header.trim()) });
+        }
+
+        let rows = rows_by_ticker.entry(file.ticker.as_str()).or_default();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            if let Some(date) = row_date(line, time_index) {
+                let row = if has_ticker { line.trim().to_string() } else { format!("{},{}", file.ticker, line.trim()) };
+                rows.push((date, row));
+            }
+        }
+    }
+
+    let Some(latest) = rows_by_ticker.values().flatten().map(|(date, _)| *date).max() else {
+        return format!("{}\n", header_line.unwrap_or_else(|| DEFAULT_HEADER.to_string()));
+    };
+    let cutoff = latest - Duration::days(days - 1);
+
+    let mut output = header_line.unwrap_or_else(|| DEFAULT_HEADER.to_string());
+    output.push('\n');
+    for rows in rows_by_ticker.values_mut() {
+        rows.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+        rows.dedup_by(|a, b| a.1 == b.1);
+        for (_, row) in rows.iter().filter(|(date, _)| *date >= cutoff) {
+            output.push_str(row);
+            output.push('\n');
+        }
+    }
+    output
+}
+
+/// Read every `*.csv` in a directory as a per-ticker file
+pub fn read_ticker_files(input_dir: &Path) -> io::Result<Vec<TickerFile>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(input_dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("csv") {
+            continue;
+        }
+        let Some(ticker) = path.file_stem().and_then(|s| s.to_str()) else { continue };
+        files.push(TickerFile { ticker: ticker.to_string(), content: fs::read_to_string(&path)? });
+    }
+    files.sort_by(|a, b| a.ticker.cmp(&b.ticker));
+    Ok(files)
+}
+
+const USAGE: &str = "Usage: aipriceaction-proxy aggregate --input <dir> [--output <dir>] [--days 60,180,365]";
+
+/// `aggregate` subcommand: writes ticker_<N>_days.csv for each window. Returns the exit code.
+pub fn run_cli(args: &[String]) -> i32 {
+    let mut input: Option<PathBuf> = None;
+    let mut output = PathBuf::from(".");
+    let mut windows: Vec<i64> = DEFAULT_WINDOWS.to_vec();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--input", Some(value)) => input = Some(PathBuf::from(value)),
+            ("--output", Some(value)) => output = PathBuf::from(value),
+            ("--days", Some(value)) => match value.split(',').map(|d| d.trim().parse::<i64>()).collect::<Result<Vec<_>, _>>() {
+                Ok(parsed) if parsed.iter().all(|d| *d > 0) => windows = parsed,
+                _ => {
+                    eprintln!("Invalid --days value: {}\n{}", value, USAGE);
+                    return 2;
+                }
+            },
+            _ => {
+                eprintln!("{}", USAGE);
+                return 2;
+            }
+        }
+    }
+
+    let Some(input) = input else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+
+    let files = match read_ticker_files(&input) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", input.display(), e);
+            return 1;
+        }
+    };
+    if let Err(e) = fs::create_dir_all(&output) {
+        eprintln!("Failed to create {}: {}", output.display(), e);
+        return 1;
+    }
+
+    for days in windows {
+        let path = output.join(format!("ticker_{}_days.csv", days));
+        let content = aggregate(&files, days);
+        if let Err(e) = fs::write(&path, &content) {
+            eprintln!("Failed to write {}: {}", path.display(), e);
+            return 1;
+        }
+        println!("Wrote {} ({} rows from {} tickers)", path.display(), content.lines().count() - 1, files.len());
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregate_window_and_ordering() {
+        let files = vec![
+            TickerFile { ticker: "VCB".to_string(), content: "time,open,high,low,close,volume\n2025-08-14,1,1,1,60,10\n2025-08-01,1,1,1,58,10\n2025-08-15,1,1,1,61,10\n".to_string() },
+            TickerFile { ticker: "ACB".to_string(), content: "time,open,high,low,close,volume\n2025-08-15,1,1,1,25,10\n2025-08-15,1,1,1,25,10\n".to_string() },
+        ];
+
+        let output = aggregate(&files, 2);
+        assert_eq!(
+            output,
+            "ticker,time,open,high,low,close,volume\nACB,2025-08-15,1,1,1,25,10\nVCB,2025-08-14,1,1,1,60,10\nVCB,2025-08-15,1,1,1,61,10\n"
+        );
+        assert_eq!(aggregate(&files, 60).lines().count(), 5);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::utils::market_time::market_date;
+use crate::vci::OhlcvData;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Front-month VN30 index futures and their underlying index
+pub const FUTURES_SYMBOL: &str = "VN30F1M";
+pub const INDEX_SYMBOL: &str = "VN30";
+// Continuous VN30 futures the worker fetches: front and next month
+pub const FUTURES_SYMBOLS: [&str; 2] = [FUTURES_SYMBOL, "VN30F2M"];
+// ticker_group.json group listing the futures; a market of its own, not a sector
+pub const DERIVATIVES_GROUP: &str = "PHAI_SINH";
+// Sessions of basis history the z-score is measured against
+pub const DEFAULT_ZSCORE_WINDOW: usize = 20;
+
+/// VN30 index futures, continuous (VN30F1M) or a dated contract code (VN30F2509)
+pub fn is_futures(symbol: &str) -> bool {
+    symbol.starts_with("VN30F")
+}
+
+// Where the futures traded relative to the index
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BasisPosition {
+    Premium,  // Above the index: traders lean long
+    Discount, // Below the index: traders lean short or hedge
+    Par,
+}
+
+impl BasisPosition {
+    fn of(basis: f64) -> Self {
+        if basis > 0.0 {
+            BasisPosition::Premium
+        } else if basis < 0.0 {
+            BasisPosition::Discount
+        } else {
+            BasisPosition::Par
+        }
+    }
+}
+
+// Futures basis for one session both the futures and the index traded
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BasisPoint {
+    pub date: NaiveDate,
+    pub futures_close: f64,
+    pub index_close: f64,
+    pub basis: f64,              // futures_close - index_close, in index points
+    pub basis_pct: f64,          // basis / index_close * 100
+    pub position: BasisPosition, // Premium or discount to the index
+    pub zscore: Option<f64>,     // Basis against the mean and deviation of the last `window` sessions
+}
+
+/// Futures basis per session from time-sorted daily futures and index series, matched by market date
+pub fn calculate_basis(futures: &[OhlcvData], index: &[OhlcvData], window: usize) -> Vec<BasisPoint> {
+    let index_closes: HashMap<NaiveDate, f64> = index.iter().map(|bar| (market_date(bar.time), bar.close)).collect();
+    let mut points: Vec<BasisPoint> = futures.iter()
+        .filter_map(|bar| {
+            let date = market_date(bar.time);
+            let index_close = *index_closes.get(&date).filter(|close| **close != 0.0)?;
+            let basis = bar.close - index_close;
+            Some(BasisPoint {
+                date,
+                futures_close: bar.close,
+                index_close,
+                basis,
+                basis_pct: basis / index_close * 100.0,
+                position: BasisPosition::of(basis),
+                zscore: None,
+            })
+        })
+        .collect();
+
+    let basis: Vec<f64> = points.iter().map(|p| p.basis).collect();
+    for (i, point) in points.iter_mut().enumerate() {
+        if window < 2 || i + 1 < window {
+            continue;
+        }
+        let history = &basis[i + 1 - window..=i];
+        let mean = history.iter().sum::<f64>() / window as f64;
+        let stddev = (history.iter().map(|b| (b - mean).powi(2)).sum::<f64>() / window as f64).sqrt();
+        if stddev > 0.0 {
+            point.zscore = Some((point.basis - mean) / stddev);
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_bars;
+
+    fn bars(symbol: &str, closes: &[(i64, f64)]) -> Vec<OhlcvData> {
+        closes.iter().map(|(day, close)| OhlcvData { symbol: Some(symbol.to_string()), ..test_bars::bar(*day, *close, 100) }).collect()
+    }
+
+    #[test]
+    fn test_basis_matched_by_date_with_zscore() {
+        let futures = bars(FUTURES_SYMBOL, &[(0, 1302.0), (1, 1298.0), (2, 1310.0), (3, 1320.0), (5, 1330.0)]);
+        // No index bar on day 3; day 4 has no futures bar
+        let index = bars(INDEX_SYMBOL, &[(0, 1300.0), (1, 1300.0), (2, 1300.0), (4, 1310.0), (5, 1310.0)]);
+        let points = calculate_basis(&futures, &index, 3);
+        let basis: Vec<f64> = points.iter().map(|p| p.basis).collect();
+        assert_eq!(basis, vec![2.0, -2.0, 10.0, 20.0]);
+        assert!((points[3].basis_pct - 20.0 / 1310.0 * 100.0).abs() < 1e-9);
+        let positions: Vec<BasisPosition> = points.iter().map(|p| p.position).collect();
+        assert_eq!(positions, vec![BasisPosition::Premium, BasisPosition::Discount, BasisPosition::Premium, BasisPosition::Premium]);
+        assert!(FUTURES_SYMBOLS.iter().all(|symbol| is_futures(symbol)) && !is_futures(INDEX_SYMBOL));
+
+        assert!(points[1].zscore.is_none());
+        // Window [2, -2, 10]: mean 10/3, population deviation sqrt(224/9)
+        let expected = (10.0 - 10.0 / 3.0) / (224.0f64 / 9.0).sqrt();
+        assert!((points[2].zscore.unwrap() - expected).abs() < 1e-9);
+        assert!(points[3].zscore.unwrap() > 1.0);
+    }
+}
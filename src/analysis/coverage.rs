@@ -0,0 +1,74 @@
+use crate::utils::market_time::market_date;
+use crate::vci::OhlcvData;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+// Exchange of symbols ticker_info.json has no exchange for, such as indices
+pub const UNKNOWN_EXCHANGE: &str = "UNKNOWN";
+
+// How far behind a set of symbols is on its latest session
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Coverage {
+    pub latest_date: Option<NaiveDate>, // Most recent session any of the symbols has
+    pub symbols: usize,                 // Symbols with at least one bar
+    pub updated: usize,                 // Symbols with a bar on latest_date
+    pub lagging_1_plus: usize,          // Symbols missing at least the latest session
+    pub lagging_2_plus: usize,          // Symbols missing at least the latest two sessions
+}
+
+/// Coverage of a set of time-sorted daily series. Lag is counted in sessions, the dates any of
+/// the series has a bar on, so weekends and holidays never make a symbol look behind.
+pub fn coverage<'a>(series: impl IntoIterator<Item = &'a [OhlcvData]>) -> Coverage {
+    let mut last_dates: Vec<NaiveDate> = Vec::new();
+    // A symbol two sessions behind is still current on the third latest, so only the last
+    // three bars of each series can be among the latest two sessions
+    let mut sessions: BTreeSet<NaiveDate> = BTreeSet::new();
+    for series in series {
+        let Some(last) = series.last() else { continue };
+        last_dates.push(market_date(last.time));
+        for bar in &series[series.len().saturating_sub(3)..] {
+            sessions.insert(market_date(bar.time));
+            if sessions.len() > 3 {
+                sessions.pop_first();
+            }
+        }
+    }
+    let latest: Vec<NaiveDate> = sessions.iter().rev().take(2).copied().collect();
+    let behind = |date: &NaiveDate| latest.iter().filter(|session| *session > date).count();
+    Coverage {
+        latest_date: latest.first().copied(),
+        symbols: last_dates.len(),
+        updated: last_dates.iter().filter(|date| behind(date) == 0).count(),
+        lagging_1_plus: last_dates.iter().filter(|date| behind(date) >= 1).count(),
+        lagging_2_plus: last_dates.iter().filter(|date| behind(date) >= 2).count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_bars::bar;
+
+    #[test]
+    fn test_lag_counts_sessions_not_calendar_days() {
+        // Thursday 14th, Friday 15th, Monday 18th of August 2025
+        let series = |days: &[u32]| -> Vec<OhlcvData> {
+            days.iter().map(|day| bar(*day as i64 - 1, 10.0, 100)).collect()
+        };
+        // Nobody's last bar is on the 15th, yet it is still a session the 14th is behind on
+        let all = [series(&[14, 15, 18]), series(&[14, 15, 18]), series(&[13, 14]), Vec::new()];
+        let summary = coverage(all.iter().map(Vec::as_slice));
+        assert_eq!(summary, Coverage {
+            latest_date: NaiveDate::from_ymd_opt(2025, 8, 18),
+            symbols: 3,
+            updated: 2,
+            lagging_1_plus: 1,
+            lagging_2_plus: 1,
+        });
+        // Friday's bar is current over a weekend until a Monday bar shows up
+        let friday = [series(&[14, 15]), series(&[14])];
+        assert_eq!(coverage(friday.iter().map(Vec::as_slice)).lagging_2_plus, 0);
+        assert_eq!(coverage(std::iter::empty()), Coverage::default());
+    }
+}
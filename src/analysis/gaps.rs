@@ -0,0 +1,124 @@
+use crate::utils::market_time::market_date;
+use crate::vci::OhlcvData;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+// Overnight gap: today's open versus the previous session's close
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GapPoint {
+    pub date: NaiveDate,
+    pub prev_close: f64,
+    pub open: f64,
+    pub close: f64,
+    pub gap_pct: f64,             // (open - prev_close) / prev_close * 100, the overnight return
+    pub intraday_return_pct: f64, // (close - open) / open * 100
+    pub filled: bool,             // Price traded back to prev_close during the session
+}
+
+// Gap behaviour of one ticker over its history
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GapStats {
+    pub symbol: String,
+    pub sessions: usize,
+    pub min_gap_pct: f64, // Gaps smaller than this (absolute) are ignored in the counts below
+    pub gap_up_count: usize,
+    pub gap_down_count: usize,
+    pub gap_up_fill_rate: Option<f64>,   // Percent of gap-ups filled the same session
+    pub gap_down_fill_rate: Option<f64>, // Percent of gap-downs filled the same session
+    pub avg_gap_pct: f64,                // Mean overnight return over all sessions
+    pub avg_abs_gap_pct: f64,
+    pub largest_gap_up: Option<GapPoint>,
+    pub largest_gap_down: Option<GapPoint>,
+    pub gaps: Vec<GapPoint>,
+}
+
+/// Overnight gaps for a time-sorted daily series, one per session after the first
+pub fn calculate_gaps(series: &[OhlcvData]) -> Vec<GapPoint> {
+    series.windows(2)
+        .filter(|pair| pair[0].close != 0.0 && pair[1].open != 0.0)
+        .map(|pair| {
+            let (prev, bar) = (&pair[0], &pair[1]);
+            let gap_pct = (bar.open - prev.close) / prev.close * 100.0;
+            let filled = if bar.open > prev.close {
+                bar.low <= prev.close
+            } else if bar.open < prev.close {
+                bar.high >= prev.close
+            } else {
+                true
+            };
+
+            GapPoint {
+                date: market_date(bar.time),
+                prev_close: prev.close,
+                open: bar.open,
+                close: bar.close,
+                gap_pct,
+                intraday_return_pct: (bar.close - bar.open) / bar.open * 100.0,
+                filled,
+            }
+        })
+        .collect()
+}
+
+fn fill_rate(gaps: &[&GapPoint]) -> Option<f64> {
+    if gaps.is_empty() {
+        return None;
+    }
+    Some(gaps.iter().filter(|g| g.filled).count() as f64 / gaps.len() as f64 * 100.0)
+}
+
+/// Summarise gaps; only gaps of at least `min_gap_pct` (absolute) count as gap-ups/downs
+pub fn summarize_gaps(symbol: &str, gaps: Vec<GapPoint>, min_gap_pct: f64) -> GapStats {
+    let ups: Vec<&GapPoint> = gaps.iter().filter(|g| g.gap_pct >= min_gap_pct && g.gap_pct > 0.0).collect();
+    let downs: Vec<&GapPoint> = gaps.iter().filter(|g| g.gap_pct <= -min_gap_pct && g.gap_pct < 0.0).collect();
+    let sessions = gaps.len();
+    let mean = |f: fn(&GapPoint) -> f64| if sessions == 0 { 0.0 } else { gaps.iter().map(f).sum::<f64>() / sessions as f64 };
+
+    GapStats {
+        symbol: symbol.to_string(),
+        sessions,
+        min_gap_pct,
+        gap_up_count: ups.len(),
+        gap_down_count: downs.len(),
+        gap_up_fill_rate: fill_rate(&ups),
+        gap_down_fill_rate: fill_rate(&downs),
+        avg_gap_pct: mean(|g| g.gap_pct),
+        avg_abs_gap_pct: mean(|g| g.gap_pct.abs()),
+        largest_gap_up: ups.iter().max_by(|a, b| a.gap_pct.total_cmp(&b.gap_pct)).map(|g| (*g).clone()),
+        largest_gap_down: downs.iter().min_by(|a, b| a.gap_pct.total_cmp(&b.gap_pct)).map(|g| (*g).clone()),
+        gaps,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_bars;
+
+    fn bar(day: i64, open: f64, high: f64, low: f64, close: f64) -> OhlcvData {
+        OhlcvData { open, high, low, ..test_bars::bar(day, close, 100) }
+    }
+
+    #[test]
+    fn test_gap_fill_statistics() {
+        let series = vec![
+            bar(0, 100.0, 101.0, 99.0, 100.0),
+            bar(1, 102.0, 103.0, 99.5, 101.0),  // +2% gap up, filled (low below 100)
+            bar(2, 103.0, 105.0, 102.0, 104.0), // ~+2% gap up, not filled
+            bar(3, 104.05, 104.5, 103.5, 104.0), // tiny gap, below threshold
+            bar(4, 100.0, 104.5, 99.0, 104.0),  // ~-3.8% gap down, filled (high back to 104)
+        ];
+
+        let gaps = calculate_gaps(&series);
+        assert_eq!(gaps.len(), 4);
+        assert!((gaps[0].gap_pct - 2.0).abs() < 1e-9);
+        assert!((gaps[0].intraday_return_pct - (-1.0 / 102.0 * 100.0)).abs() < 1e-9);
+
+        let stats = summarize_gaps("VCB", gaps, 0.5);
+        assert_eq!((stats.gap_up_count, stats.gap_down_count), (2, 1));
+        assert_eq!(stats.gap_up_fill_rate, Some(50.0));
+        assert_eq!(stats.gap_down_fill_rate, Some(100.0));
+        assert_eq!(stats.largest_gap_down.map(|g| g.date), NaiveDate::from_ymd_opt(2025, 8, 5));
+        assert_eq!(summarize_gaps("VCB", Vec::new(), 0.5).gap_up_fill_rate, None);
+    }
+}
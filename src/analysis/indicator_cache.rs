@@ -0,0 +1,546 @@
+use crate::analysis::indicators::{self, IndicatorPoint};
+use crate::analysis::ma_score::{self, MaScorePoint};
+use crate::analysis::money_flow::{self, DivergencePoint, MoneyFlowPoint};
+use crate::analysis::sector::SectorPoint;
+use crate::analysis::strength::{self, StrengthPoint, StrengthWeights};
+use crate::analysis::warmup;
+use crate::utils::market_time::market_date;
+use crate::utils::metrics;
+use crate::vci::OhlcvData;
+use chrono::NaiveDate;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+use tracing::{debug, info, warn};
+
+// Cached series per indicator; beyond this the least recently used one is evicted
+const MAX_ENTRIES_PER_INDICATOR: usize = 4096;
+// Bumped when the persisted cache layout changes; older files are ignored
+const PERSIST_FORMAT_VERSION: u32 = 1;
+
+/// Indicator output keyed by market date
+pub trait Dated {
+    fn date(&self) -> NaiveDate;
+}
+
+impl Dated for MaScorePoint {
+    fn date(&self) -> NaiveDate {
+        self.date
+    }
+}
+
+impl Dated for StrengthPoint {
+    fn date(&self) -> NaiveDate {
+        self.date
+    }
+}
+
+impl Dated for IndicatorPoint {
+    fn date(&self) -> NaiveDate {
+        self.date
+    }
+}
+
+impl Dated for DivergencePoint {
+    fn date(&self) -> NaiveDate {
+        self.date
+    }
+}
+
+impl Dated for MoneyFlowPoint {
+    fn date(&self) -> NaiveDate {
+        self.date
+    }
+}
+
+impl Dated for SectorPoint {
+    fn date(&self) -> NaiveDate {
+        self.date
+    }
+}
+
+/// Index range of the points dated within `start..=end` in a date-sorted series
+pub fn date_range<T: Dated>(points: &[T], start: Option<NaiveDate>, end: Option<NaiveDate>) -> Range<usize> {
+    let from = start.map_or(0, |start| points.partition_point(|p| p.date() < start));
+    let to = end.map_or(points.len(), |end| points.partition_point(|p| p.date() <= end));
+    from..to.max(from)
+}
+
+/// Points dated within `start..=end` in a date-sorted series
+pub fn in_range<T: Dated>(points: &[T], start: Option<NaiveDate>, end: Option<NaiveDate>) -> &[T] {
+    &points[date_range(points, start, end)]
+}
+
+/// Hash of each bar plus one of the whole series, so any change to a symbol's history
+/// invalidates its indicators and the first changed bar can be found
+#[derive(Clone)]
+struct SeriesHashes {
+    bars: Arc<[u64]>,
+    fingerprint: u64,
+}
+
+impl SeriesHashes {
+    fn of(series: &[OhlcvData]) -> Self {
+        let bars: Arc<[u64]> = series.iter().map(|bar| {
+            let mut hasher = DefaultHasher::new();
+            bar.time.timestamp_millis().hash(&mut hasher);
+            [bar.open, bar.high, bar.low, bar.close].map(f64::to_bits).hash(&mut hasher);
+            bar.volume.hash(&mut hasher);
+            hasher.finish()
+        }).collect();
+        let fingerprint = params_hash(&*bars);
+        Self { bars, fingerprint }
+    }
+
+    /// Index of the first bar that differs from `previous`. Bars removed from the end count
+    /// as a change to the whole series, since indicators only know how to recompute forwards.
+    fn first_changed_since(&self, previous: &[u64]) -> usize {
+        if self.bars.len() < previous.len() {
+            return 0;
+        }
+        previous.iter().zip(self.bars.iter()).position(|(old, new)| old != new).unwrap_or(previous.len())
+    }
+}
+
+fn params_hash(params: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    params.hash(&mut hasher);
+    hasher.finish()
+}
+
+struct Entry<T> {
+    series: SeriesHashes,
+    points: Arc<[T]>,
+    last_used: Instant,
+}
+
+/// A store lookup: the cached points, or the outdated ones to update from
+enum Lookup<T> {
+    Hit(Arc<[T]>),
+    Stale(Arc<[T]>, usize), // Points for an older version of the series, and its first changed bar
+    Miss,
+}
+
+/// One indicator's output per (symbol, params hash), valid while the symbol's bars are unchanged
+struct Store<T> {
+    name: &'static str,
+    entries: HashMap<(String, u64), Entry<T>>,
+    dirty: bool, // Changed since it was last persisted
+}
+
+impl<T> Store<T> {
+    fn new(name: &'static str) -> Self {
+        Self { name, entries: HashMap::new(), dirty: false }
+    }
+
+    fn get(&mut self, symbol: &str, params: u64, series: &SeriesHashes) -> Lookup<T> {
+        let Some(entry) = self.entries.get_mut(&(symbol.to_string(), params)) else { return Lookup::Miss };
+        entry.last_used = Instant::now();
+        if entry.series.fingerprint == series.fingerprint {
+            Lookup::Hit(entry.points.clone())
+        } else {
+            Lookup::Stale(entry.points.clone(), series.first_changed_since(&entry.series.bars))
+        }
+    }
+
+    fn insert(&mut self, symbol: &str, params: u64, series: SeriesHashes, points: Arc<[T]>) {
+        if self.entries.len() >= MAX_ENTRIES_PER_INDICATOR
+            && let Some(oldest) = self.entries.iter().min_by_key(|(_, entry)| entry.last_used).map(|(key, _)| key.clone())
+        {
+            self.entries.remove(&oldest);
+        }
+        self.entries.insert((symbol.to_string(), params), Entry { series, points, last_used: Instant::now() });
+        self.dirty = true;
+    }
+}
+
+impl<T: Dated + Clone> Store<T> {
+    /// Forget the symbol's points dated `from` or later and the bars from `first_bar` on, so the
+    /// next lookup recomputes only those. Returns how many entries were cut back.
+    fn invalidate_from(&mut self, symbol: &str, first_bar: usize, from: NaiveDate) -> usize {
+        let mut invalidated = 0;
+        for ((entry_symbol, _), entry) in self.entries.iter_mut() {
+            if entry_symbol != symbol || first_bar >= entry.series.bars.len() {
+                continue;
+            }
+            let bars: Arc<[u64]> = entry.series.bars[..first_bar].into();
+            let fingerprint = params_hash(&*bars);
+            entry.series = SeriesHashes { bars, fingerprint };
+            entry.points = entry.points.iter().take_while(|p| p.date() < from).cloned().collect();
+            invalidated += 1;
+        }
+        self.dirty |= invalidated > 0;
+        invalidated
+    }
+}
+
+/// Cached lookup: the stored points when the bars match, else `compute` them from the points of
+/// the previous version of the series and its first changed bar (nothing and 0 when there is none).
+/// The lock is not held while computing.
+fn get_or_compute<T>(store: &Mutex<Store<T>>, symbol: &str, params: u64, series: &SeriesHashes, compute: impl FnOnce(&[T], usize) -> Vec<T>) -> Arc<[T]> {
+    let lookup = store.lock().unwrap_or_else(|e| e.into_inner()).get(symbol, params, series);
+    let (previous, first_changed) = match lookup {
+        Lookup::Hit(points) => {
+            metrics::increment_counter("indicator_cache.hits", 1);
+            return points;
+        }
+        Lookup::Stale(previous, first_changed) => {
+            metrics::increment_counter("indicator_cache.updates", 1);
+            (previous, first_changed)
+        }
+        Lookup::Miss => {
+            metrics::increment_counter("indicator_cache.misses", 1);
+            (Arc::from(Vec::new()), 0)
+        }
+    };
+    let points: Arc<[T]> = compute(&previous, first_changed).into();
+    let mut store = store.lock().unwrap_or_else(|e| e.into_inner());
+    debug!(indicator = store.name, symbol, points = points.len(), first_changed, reused = previous.len().min(first_changed), "Computed indicator series");
+    store.insert(symbol, params, series.clone(), points.clone());
+    points
+}
+
+struct IndicatorCache {
+    ma_scores: Mutex<Store<MaScorePoint>>,
+    strength: Mutex<Store<StrengthPoint>>,
+    divergences: Mutex<Store<DivergencePoint>>,
+    indicators: Mutex<Store<IndicatorPoint>>,
+}
+
+impl IndicatorCache {
+    fn new() -> Self {
+        Self {
+            ma_scores: Mutex::new(Store::new("ma_score")),
+            strength: Mutex::new(Store::new("strength")),
+            divergences: Mutex::new(Store::new("money_flow_divergence")),
+            indicators: Mutex::new(Store::new("indicators")),
+        }
+    }
+}
+
+fn cache() -> &'static IndicatorCache {
+    static CACHE: OnceLock<IndicatorCache> = OnceLock::new();
+    CACHE.get_or_init(IndicatorCache::new)
+}
+
+/// `ma_score::calculate_ma_scores` for a symbol's full series, computed once per change to its bars
+/// and then only from the first changed bar on
+pub fn ma_scores(symbol: &str, series: &[OhlcvData]) -> Arc<[MaScorePoint]> {
+    ma_scores_with(symbol, series, &SeriesHashes::of(series))
+}
+
+fn ma_scores_with(symbol: &str, series: &[OhlcvData], hashes: &SeriesHashes) -> Arc<[MaScorePoint]> {
+    let params = params_hash((ma_score::MA_PERIODS, ma_score::TREND_WINDOW, warmup::mode()));
+    get_or_compute(&cache().ma_scores, symbol, params, hashes, |previous, first_changed| {
+        ma_score::update_ma_scores(series, previous, first_changed)
+    })
+}
+
+/// `strength::calculate_strength` for a symbol's full series, reusing its cached MA scores
+pub fn strength(symbol: &str, series: &[OhlcvData], weights: &StrengthWeights) -> Arc<[StrengthPoint]> {
+    let hashes = SeriesHashes::of(series);
+    let scores = ma_scores_with(symbol, series, &hashes);
+    let params = params_hash(([weights.money_flow, weights.ma_score, weights.relative_volume, weights.trend].map(f64::to_bits), warmup::mode()));
+    get_or_compute(&cache().strength, symbol, params, &hashes, |previous, first_changed| {
+        strength::update_strength(series, &scores, weights, previous, first_changed)
+    })
+}
+
+/// `money_flow::detect_divergences` for a symbol's full series
+pub fn divergences(symbol: &str, series: &[OhlcvData], lookback: usize, provisional_date: Option<NaiveDate>) -> Arc<[DivergencePoint]> {
+    let params = params_hash((lookback, provisional_date));
+    get_or_compute(&cache().divergences, symbol, params, &SeriesHashes::of(series), |previous, first_changed| {
+        money_flow::update_divergences(symbol, series, lookback, provisional_date, previous, first_changed)
+    })
+}
+
+/// `indicators::calculate_indicators` (RSI, MACD, Bollinger Bands) for a symbol's full series
+pub fn indicators(symbol: &str, series: &[OhlcvData]) -> Arc<[IndicatorPoint]> {
+    let params = params_hash((indicators::RSI_PERIOD, indicators::MACD_FAST, indicators::MACD_SLOW, indicators::MACD_SIGNAL, indicators::BOLLINGER_PERIOD));
+    get_or_compute(&cache().indicators, symbol, params, &SeriesHashes::of(series), |previous, first_changed| {
+        indicators::update_indicators(series, previous, first_changed)
+    })
+}
+
+/// Encoding of the persisted cache, picked from the file extension: JSON for `.json`
+/// (readable, for debugging), bincode otherwise
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheFormat {
+    Bincode,
+    Json,
+}
+
+impl CacheFormat {
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => CacheFormat::Json,
+            _ => CacheFormat::Bincode,
+        }
+    }
+
+    fn encode<T: Serialize>(self, value: &T) -> io::Result<Vec<u8>> {
+        match self {
+            CacheFormat::Bincode => bincode::serialize(value).map_err(io::Error::other),
+            CacheFormat::Json => serde_json::to_vec(value).map_err(io::Error::other),
+        }
+    }
+
+    fn decode<T: DeserializeOwned>(self, bytes: &[u8]) -> io::Result<T> {
+        match self {
+            CacheFormat::Bincode => bincode::deserialize(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            CacheFormat::Json => serde_json::from_slice(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PersistedEntry<T> {
+    symbol: String,
+    params: u64,
+    series: u64, // Fingerprint of the bars the points were computed from, a key into `PersistedCache::series`
+    points: Vec<T>,
+}
+
+/// The MA score, strength and money flow divergence stores with the bar hashes they were computed
+/// from, so a restart reuses every date whose inputs are unchanged. Technical indicators are not
+/// persisted: their points carry smoothing state that is not serialized.
+#[derive(Serialize, Deserialize)]
+struct PersistedCache {
+    format_version: u32,
+    algorithms: [u32; 3], // ma_score, strength and money_flow versions the points were computed with
+    series: HashMap<u64, Vec<u64>>, // Bar hashes by fingerprint, shared by a symbol's entries
+    ma_scores: Vec<PersistedEntry<MaScorePoint>>,
+    strength: Vec<PersistedEntry<StrengthPoint>>,
+    divergences: Vec<PersistedEntry<DivergencePoint>>,
+}
+
+fn algorithm_versions() -> [u32; 3] {
+    [ma_score::ALGORITHM_VERSION, strength::ALGORITHM_VERSION, money_flow::ALGORITHM_VERSION]
+}
+
+impl<T: Clone> Store<T> {
+    fn persisted(&self, series: &mut HashMap<u64, Vec<u64>>) -> Vec<PersistedEntry<T>> {
+        self.entries.iter().map(|((symbol, params), entry)| {
+            series.entry(entry.series.fingerprint).or_insert_with(|| entry.series.bars.to_vec());
+            PersistedEntry { symbol: symbol.clone(), params: *params, series: entry.series.fingerprint, points: entry.points.to_vec() }
+        }).collect()
+    }
+
+    /// Add persisted entries whose bar hashes were saved with them; returns how many were restored
+    fn restore(&mut self, entries: Vec<PersistedEntry<T>>, series: &HashMap<u64, Vec<u64>>) -> usize {
+        let mut restored = 0;
+        for entry in entries {
+            let Some(bars) = series.get(&entry.series) else { continue };
+            let hashes = SeriesHashes { bars: bars.as_slice().into(), fingerprint: entry.series };
+            self.insert(&entry.symbol, entry.params, hashes, entry.points.into());
+            restored += 1;
+        }
+        self.dirty = false;
+        restored
+    }
+}
+
+fn persist_path() -> &'static Mutex<Option<PathBuf>> {
+    static PATH: OnceLock<Mutex<Option<PathBuf>>> = OnceLock::new();
+    PATH.get_or_init(|| Mutex::new(None))
+}
+
+fn read_cache(cache: &IndicatorCache, path: &Path) -> io::Result<usize> {
+    let persisted: PersistedCache = CacheFormat::for_path(path).decode(&fs::read(path)?)?;
+    if persisted.format_version != PERSIST_FORMAT_VERSION || persisted.algorithms != algorithm_versions() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "written by a different cache format or indicator version"));
+    }
+    Ok(cache.ma_scores.lock().unwrap_or_else(|e| e.into_inner()).restore(persisted.ma_scores, &persisted.series)
+        + cache.strength.lock().unwrap_or_else(|e| e.into_inner()).restore(persisted.strength, &persisted.series)
+        + cache.divergences.lock().unwrap_or_else(|e| e.into_inner()).restore(persisted.divergences, &persisted.series))
+}
+
+/// Write the persisted stores if any changed since the last write (temp file + rename).
+/// Returns whether anything was written.
+fn write_cache(cache: &IndicatorCache, path: &Path) -> io::Result<bool> {
+    let mut ma_scores = cache.ma_scores.lock().unwrap_or_else(|e| e.into_inner());
+    let mut strength = cache.strength.lock().unwrap_or_else(|e| e.into_inner());
+    let mut divergences = cache.divergences.lock().unwrap_or_else(|e| e.into_inner());
+    if !(ma_scores.dirty || strength.dirty || divergences.dirty) {
+        return Ok(false);
+    }
+    let mut series = HashMap::new();
+    let persisted = PersistedCache {
+        format_version: PERSIST_FORMAT_VERSION,
+        algorithms: algorithm_versions(),
+        ma_scores: ma_scores.persisted(&mut series),
+        strength: strength.persisted(&mut series),
+        divergences: divergences.persisted(&mut series),
+        series,
+    };
+    (ma_scores.dirty, strength.dirty, divergences.dirty) = (false, false, false);
+    drop((ma_scores, strength, divergences));
+
+    let content = CacheFormat::for_path(path).encode(&persisted)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, content)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(true)
+}
+
+/// Load indicator results persisted by a previous run; `None` keeps them in memory only. Call once
+/// at startup, before the first lookup. Bar hashes come from `DefaultHasher`, so after a toolchain
+/// upgrade that changes it the restored entries simply recompute.
+pub fn init(path: Option<PathBuf>) {
+    if let Some(path) = &path
+        && path.exists()
+    {
+        match read_cache(cache(), path) {
+            Ok(entries) => info!(?path, entries, "Loaded persisted indicator cache"),
+            Err(e) => warn!(?path, error = %e, "Persisted indicator cache is unusable, starting empty"),
+        }
+    }
+    *persist_path().lock().unwrap_or_else(|e| e.into_inner()) = path;
+}
+
+/// Persist the cache if it changed since the last save
+pub fn save() -> io::Result<()> {
+    let Some(path) = persist_path().lock().unwrap_or_else(|e| e.into_inner()).clone() else { return Ok(()) };
+    if write_cache(cache(), &path)? {
+        debug!(?path, "Saved indicator cache");
+    }
+    Ok(())
+}
+
+/// Drop every cached indicator of `symbol` from `from` on, e.g. after bad upstream bars were found.
+/// Earlier points are kept and the rest are recomputed on the next lookup. Returns the entries cut back.
+pub fn invalidate_from(symbol: &str, series: &[OhlcvData], from: NaiveDate) -> usize {
+    let first_bar = series.partition_point(|bar| market_date(bar.time) < from);
+    let cache = cache();
+    let invalidated = cache.ma_scores.lock().unwrap_or_else(|e| e.into_inner()).invalidate_from(symbol, first_bar, from)
+        + cache.strength.lock().unwrap_or_else(|e| e.into_inner()).invalidate_from(symbol, first_bar, from)
+        + cache.divergences.lock().unwrap_or_else(|e| e.into_inner()).invalidate_from(symbol, first_bar, from)
+        + cache.indicators.lock().unwrap_or_else(|e| e.into_inner()).invalidate_from(symbol, first_bar, from);
+    metrics::increment_counter("indicator_cache.invalidations", invalidated as u64);
+    invalidated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_bars::series;
+    use chrono::Duration;
+
+    #[test]
+    fn test_cached_until_bars_change_and_sliced_by_date() {
+        let mut bars = series(&(0..60).map(|i| 100.0 + i as f64).collect::<Vec<_>>());
+        let first = ma_scores("CACHETEST", &bars);
+        assert!(Arc::ptr_eq(&first, &ma_scores("CACHETEST", &bars)));
+
+        // An intraday update of the last bar is a new series
+        bars.last_mut().unwrap().close = 50.0;
+        let updated = ma_scores("CACHETEST", &bars);
+        assert!(!Arc::ptr_eq(&first, &updated));
+        assert!(updated.last().unwrap().ma10_score.unwrap() < 0.0);
+
+        // Strength reuses the cached MA scores and is keyed by its weights
+        let weights = StrengthWeights::default();
+        let strength_points = strength("CACHETEST", &bars, &weights);
+        assert!(Arc::ptr_eq(&strength_points, &strength("CACHETEST", &bars, &weights)));
+        let other = StrengthWeights { trend: 0.0, ..weights };
+        assert!(!Arc::ptr_eq(&strength_points, &strength("CACHETEST", &bars, &other)));
+
+        let date = |day: u32| NaiveDate::from_ymd_opt(2025, 1, day);
+        let slice = in_range(&updated, date(10), date(19));
+        assert_eq!((slice.len(), slice[0].date, slice[9].date), (10, date(10).unwrap(), date(19).unwrap()));
+        assert_eq!(in_range(&updated, None, None).len(), 60);
+        assert!(in_range(&updated, date(20), date(10)).is_empty());
+    }
+
+    // The points have no PartialEq; compare their JSON instead
+    fn json<T: serde::Serialize>(value: &T) -> serde_json::Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_incremental_update_matches_full_recalculation() {
+        let mut bars: Vec<OhlcvData> = series(&(0..120).map(|i| 100.0 + 10.0 * (i as f64 * 0.7).sin() + i as f64 * 0.1).collect::<Vec<_>>());
+        for (i, bar) in bars.iter_mut().enumerate() {
+            // Heavy volume on the way down, so new highs come with net outflow
+            bar.volume = if (i as f64 * 0.7).cos() < 0.0 { 5000 } else { 100 + i as u64 };
+            bar.symbol = Some("INCRTEST".to_string());
+        }
+        let weights = StrengthWeights::default();
+        let provisional = Some(NaiveDate::from_ymd_opt(2025, 4, 30).unwrap());
+        ma_scores("INCRTEST", &bars);
+        strength("INCRTEST", &bars, &weights);
+        assert!(!divergences("INCRTEST", &bars, 5, provisional).is_empty());
+
+        // Revise a bar mid-series, update the last one intraday, and append a session
+        bars[90].close *= 1.1;
+        bars[90].volume = 9000;
+        bars.last_mut().unwrap().close = 80.0;
+        let mut next = bars.last().unwrap().clone();
+        next.time += Duration::days(1);
+        next.close = 130.0;
+        bars.push(next);
+        assert_eq!(SeriesHashes::of(&bars).first_changed_since(&SeriesHashes::of(&bars[..119]).bars), 119);
+
+        let full_scores = ma_score::calculate_ma_scores(&bars);
+        assert_eq!(json(&ma_scores("INCRTEST", &bars).to_vec()), json(&full_scores));
+        assert_eq!(json(&strength("INCRTEST", &bars, &weights).to_vec()), json(&strength::calculate_strength(&bars, &full_scores, &weights)));
+        assert_eq!(
+            json(&divergences("INCRTEST", &bars, 5, provisional).to_vec()),
+            json(&money_flow::detect_divergences("INCRTEST", &bars, 5, provisional)),
+        );
+
+        // Invalidating from a date keeps the earlier points and recomputes the rest
+        let revised = NaiveDate::from_ymd_opt(2025, 4, 1).unwrap();
+        let cached = ma_scores("INCRTEST", &bars);
+        assert_eq!(invalidate_from("INCRTEST", &bars, revised), 3);
+        let recomputed = ma_scores("INCRTEST", &bars);
+        assert!(!Arc::ptr_eq(&cached, &recomputed));
+        assert_eq!(json(&recomputed.to_vec()), json(&full_scores));
+        assert_eq!(invalidate_from("INCRTEST", &bars, NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()), 0);
+
+        // Dropping bars recomputes from scratch
+        bars.truncate(100);
+        assert_eq!(json(&divergences("INCRTEST", &bars, 5, provisional).to_vec()), json(&money_flow::detect_divergences("INCRTEST", &bars, 5, provisional)));
+    }
+
+    #[test]
+    fn test_persisted_cache_restores_and_updates_changed_dates() {
+        let bars = series(&(0..80).map(|i| 100.0 + (i as f64 * 0.3).sin() * 5.0).collect::<Vec<_>>());
+        let params = params_hash((ma_score::MA_PERIODS, ma_score::TREND_WINDOW, warmup::mode()));
+        for extension in ["bin", "json"] {
+            let path = std::env::temp_dir().join(format!("indicator-cache-{}.{}", std::process::id(), extension));
+            let original = IndicatorCache::new();
+            let hashes = SeriesHashes::of(&bars);
+            let scores = get_or_compute(&original.ma_scores, "PERSIST", params, &hashes, |_, _| ma_score::calculate_ma_scores(&bars));
+            assert!(write_cache(&original, &path).unwrap());
+            assert!(!write_cache(&original, &path).unwrap());
+
+            let restored = IndicatorCache::new();
+            assert_eq!(read_cache(&restored, &path).unwrap(), 1);
+            let hit = get_or_compute(&restored.ma_scores, "PERSIST", params, &hashes, |_, _| panic!("unchanged bars recomputed"));
+            assert_eq!(json(&hit.to_vec()), json(&scores.to_vec()));
+
+            // A revised last bar resumes from the restored points
+            let mut revised = bars.clone();
+            revised.last_mut().unwrap().close = 90.0;
+            let updated = get_or_compute(&restored.ma_scores, "PERSIST", params, &SeriesHashes::of(&revised), |previous, first_changed| {
+                assert_eq!((previous.len(), first_changed), (80, 79));
+                ma_score::update_ma_scores(&revised, previous, first_changed)
+            });
+            assert_eq!(json(&updated.to_vec()), json(&ma_score::calculate_ma_scores(&revised)));
+            fs::remove_file(&path).unwrap();
+        }
+        assert_eq!(CacheFormat::for_path(Path::new("cache/indicators.JSON")), CacheFormat::Json);
+        assert_eq!(CacheFormat::for_path(Path::new("cache/indicators")), CacheFormat::Bincode);
+    }
+}
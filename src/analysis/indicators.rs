@@ -0,0 +1,166 @@
+use crate::analysis::vwap::{rolling_vwap, VWAP_PERIOD};
+use crate::utils::market_time::market_date;
+use crate::vci::OhlcvData;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+pub const RSI_PERIOD: usize = 14;
+pub const MACD_FAST: usize = 12;
+pub const MACD_SLOW: usize = 26;
+pub const MACD_SIGNAL: usize = 9;
+pub const BOLLINGER_PERIOD: usize = 20;
+pub const BOLLINGER_STDDEV: f64 = 2.0;
+// Bumped when RSI, MACD, Bollinger or VWAP values would come out differently
+pub const ALGORITHM_VERSION: u32 = 1;
+
+// Recursive averages carried from one session to the next, so an update can resume mid-series
+#[derive(Clone, Debug, Default)]
+struct Smoothing {
+    ema_fast: Option<f64>,
+    ema_slow: Option<f64>,
+    avg_gain: Option<f64>,
+    avg_loss: Option<f64>,
+}
+
+// Standard technical indicators for one session; None until the series is long enough
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct IndicatorPoint {
+    pub date: NaiveDate,
+    pub rsi14: Option<f64>,          // Wilder's RSI, 0-100
+    pub macd: Option<f64>,           // EMA12 - EMA26
+    pub macd_signal: Option<f64>,    // EMA9 of the MACD line
+    pub macd_histogram: Option<f64>, // MACD - signal
+    pub bb_upper: Option<f64>,       // SMA20 + 2 standard deviations
+    pub bb_middle: Option<f64>,      // SMA20
+    pub bb_lower: Option<f64>,       // SMA20 - 2 standard deviations
+    #[serde(default)]
+    pub vwap20: Option<f64>,         // Volume-weighted typical price of the last 20 sessions
+    #[serde(skip)]
+    smoothing: Smoothing,
+}
+
+/// Next value of an EMA seeded with the simple average of its first `period` values
+fn next_ema(previous: Option<f64>, values: &[f64], index: usize, period: usize) -> Option<f64> {
+    if index + 1 < period {
+        return None;
+    }
+    match previous {
+        Some(previous) => {
+            let k = 2.0 / (period as f64 + 1.0);
+            Some(previous + k * (values[index] - previous))
+        }
+        None => Some(values[index + 1 - period..=index].iter().sum::<f64>() / period as f64),
+    }
+}
+
+fn rsi(avg_gain: f64, avg_loss: f64) -> f64 {
+    if avg_loss == 0.0 {
+        return if avg_gain == 0.0 { 50.0 } else { 100.0 };
+    }
+    100.0 - 100.0 / (1.0 + avg_gain / avg_loss)
+}
+
+/// Middle, upper and lower Bollinger bands of the window of closes ending at `index`
+fn bollinger(closes: &[f64], index: usize) -> Option<(f64, f64, f64)> {
+    if index + 1 < BOLLINGER_PERIOD {
+        return None;
+    }
+    let window = &closes[index + 1 - BOLLINGER_PERIOD..=index];
+    let mean = window.iter().sum::<f64>() / BOLLINGER_PERIOD as f64;
+    let variance = window.iter().map(|close| (close - mean).powi(2)).sum::<f64>() / BOLLINGER_PERIOD as f64;
+    let width = BOLLINGER_STDDEV * variance.sqrt();
+    Some((mean, mean + width, mean - width))
+}
+
+/// Calculate RSI, MACD, Bollinger Bands and the 20-session VWAP for a time-sorted daily series
+pub fn calculate_indicators(series: &[OhlcvData]) -> Vec<IndicatorPoint> {
+    update_indicators(series, &[], 0)
+}
+
+/// `calculate_indicators` for a series whose bars before `first_changed` are the ones `previous`
+/// was calculated from. Those points are reused; only the later ones are recalculated.
+pub fn update_indicators(series: &[OhlcvData], previous: &[IndicatorPoint], first_changed: usize) -> Vec<IndicatorPoint> {
+    let keep = first_changed.min(previous.len()).min(series.len());
+    let closes: Vec<f64> = series.iter().map(|d| d.close).collect();
+    let mut macd_line: Vec<f64> = Vec::new(); // MACD values so far, for seeding the signal line
+    let mut points = previous[..keep].to_vec();
+    macd_line.extend(points.iter().filter_map(|p| p.macd));
+
+    for (i, bar) in series.iter().enumerate().skip(keep) {
+        let last = points.last().map(|p| p.smoothing.clone()).unwrap_or_default();
+        let ema_fast = next_ema(last.ema_fast, &closes, i, MACD_FAST);
+        let ema_slow = next_ema(last.ema_slow, &closes, i, MACD_SLOW);
+        let macd = ema_fast.zip(ema_slow).map(|(fast, slow)| fast - slow);
+        let mut macd_signal = None;
+        if let Some(macd) = macd {
+            macd_line.push(macd);
+            let previous_signal = points.last().and_then(|p| p.macd_signal);
+            macd_signal = next_ema(previous_signal, &macd_line, macd_line.len() - 1, MACD_SIGNAL);
+        }
+
+        // Wilder's smoothing, seeded with the plain average of the first RSI_PERIOD changes
+        let (avg_gain, avg_loss) = if i < RSI_PERIOD {
+            (None, None)
+        } else if let (Some(gain), Some(loss)) = (last.avg_gain, last.avg_loss) {
+            let change = closes[i] - closes[i - 1];
+            let period = RSI_PERIOD as f64;
+            (Some((gain * (period - 1.0) + change.max(0.0)) / period), Some((loss * (period - 1.0) + (-change).max(0.0)) / period))
+        } else {
+            let changes = closes[i - RSI_PERIOD..=i].windows(2).map(|pair| pair[1] - pair[0]);
+            let (gains, losses) = changes.fold((0.0, 0.0), |(gains, losses), change| (gains + change.max(0.0), losses + (-change).max(0.0)));
+            (Some(gains / RSI_PERIOD as f64), Some(losses / RSI_PERIOD as f64))
+        };
+
+        let bands = bollinger(&closes, i);
+        points.push(IndicatorPoint {
+            date: market_date(bar.time),
+            rsi14: avg_gain.zip(avg_loss).map(|(gain, loss)| rsi(gain, loss)),
+            macd,
+            macd_signal,
+            macd_histogram: macd.zip(macd_signal).map(|(macd, signal)| macd - signal),
+            bb_upper: bands.map(|(_, upper, _)| upper),
+            bb_middle: bands.map(|(middle, _, _)| middle),
+            bb_lower: bands.map(|(_, _, lower)| lower),
+            vwap20: rolling_vwap(series, i, VWAP_PERIOD),
+            smoothing: Smoothing { ema_fast, ema_slow, avg_gain, avg_loss },
+        });
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_bars::series;
+
+    #[test]
+    fn test_indicator_warmup_and_values() {
+        // Steady rise: every change is a gain, the fast EMA leads the slow one
+        let rising = calculate_indicators(&series(&(0..40).map(|i| 100.0 + i as f64).collect::<Vec<_>>()));
+        assert!(rising[RSI_PERIOD - 1].rsi14.is_none());
+        assert_eq!(rising[RSI_PERIOD].rsi14, Some(100.0));
+        assert!(rising[MACD_SLOW - 2].macd.is_none());
+        assert!(rising[MACD_SLOW - 1].macd.unwrap() > 0.0);
+        assert!(rising[MACD_SLOW + MACD_SIGNAL - 3].macd_signal.is_none());
+        assert!(rising[MACD_SLOW + MACD_SIGNAL - 2].macd_signal.is_some());
+
+        // Bollinger: mean 109.5 over closes 100..=119, population deviation sqrt(33.25)
+        let bands = &rising[BOLLINGER_PERIOD - 1];
+        assert_eq!(bands.bb_middle, Some(109.5));
+        assert!((bands.bb_upper.unwrap() - (109.5 + 2.0 * 33.25f64.sqrt())).abs() < 1e-9);
+        assert!(rising[BOLLINGER_PERIOD - 2].bb_middle.is_none());
+        // Equal volumes, so the VWAP of the flat bars is their average close
+        assert_eq!(rising[VWAP_PERIOD - 1].vwap20, Some(109.5));
+        assert!(rising[VWAP_PERIOD - 2].vwap20.is_none());
+
+        // Updating from the middle of a changed series matches a full recalculation
+        let mut bars = series(&(0..80).map(|i| 100.0 + 10.0 * (i as f64 * 0.4).sin()).collect::<Vec<_>>());
+        let before = calculate_indicators(&bars);
+        bars[60].close = 90.0;
+        bars.push(series(&[104.0; 81])[80].clone());
+        let updated = update_indicators(&bars, &before, 60);
+        let full = calculate_indicators(&bars);
+        assert_eq!(serde_json::to_value(&updated).unwrap(), serde_json::to_value(&full).unwrap());
+        assert!(full[70].rsi14.is_some_and(|rsi| (0.0..=100.0).contains(&rsi)));
+    }
+}
@@ -0,0 +1,193 @@
+use crate::analysis::ma_score::MaScorePoint;
+use crate::analysis::strength::StrengthPoint;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+pub const DEFAULT_LEADERBOARD_WEEKS: usize = 12;
+pub const MAX_LEADERBOARD_WEEKS: usize = 104;
+pub const DEFAULT_LEADERBOARD_TOP: usize = 10;
+pub const MAX_LEADERBOARD_TOP: usize = 50;
+// Forward return horizons, in weeks after the ranking date
+pub const FORWARD_HORIZONS_WEEKS: [usize; 2] = [1, 4];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardMetric {
+    Composite, // Strength score
+    MoneyFlow, // Strength money flow component
+    Ma10Score,
+    Ma20Score,
+    Ma50Score,
+}
+
+impl LeaderboardMetric {
+    pub fn name(self) -> &'static str {
+        match self {
+            LeaderboardMetric::Composite => "composite",
+            LeaderboardMetric::MoneyFlow => "money_flow",
+            LeaderboardMetric::Ma10Score => "ma10_score",
+            LeaderboardMetric::Ma20Score => "ma20_score",
+            LeaderboardMetric::Ma50Score => "ma50_score",
+        }
+    }
+
+    pub fn value(self, score: &MaScorePoint, strength: &StrengthPoint) -> Option<f64> {
+        match self {
+            LeaderboardMetric::Composite => strength.strength,
+            LeaderboardMetric::MoneyFlow => strength.components.money_flow,
+            LeaderboardMetric::Ma10Score => score.ma10_score,
+            LeaderboardMetric::Ma20Score => score.ma20_score,
+            LeaderboardMetric::Ma50Score => score.ma50_score,
+        }
+    }
+}
+
+impl std::str::FromStr for LeaderboardMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        [
+            LeaderboardMetric::Composite,
+            LeaderboardMetric::MoneyFlow,
+            LeaderboardMetric::Ma10Score,
+            LeaderboardMetric::Ma20Score,
+            LeaderboardMetric::Ma50Score,
+        ]
+        .into_iter()
+        .find(|metric| metric.name() == name)
+        .ok_or_else(|| format!("Invalid metric '{}'. Expected composite, money_flow, ma10_score, ma20_score or ma50_score", s.trim()))
+    }
+}
+
+// Metric value and close of one symbol on one session
+#[derive(Clone, Copy, Debug)]
+pub struct MetricPoint {
+    pub date: NaiveDate,
+    pub value: Option<f64>,
+    pub close: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub rank: usize,
+    pub symbol: String,
+    pub metric_value: f64,
+    pub percentile: f64, // Share of ranked symbols with a lower value, 0..100
+    pub close: f64,
+    pub forward_return_1w_pct: Option<f64>, // None until that week has closed
+    pub forward_return_4w_pct: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LeaderboardWeek {
+    pub week_ending: NaiveDate, // Last session of the week
+    pub ranked: usize,          // Symbols with a metric value that session
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+/// Close on `date`, or the latest close before it
+fn close_on_or_before(points: &[MetricPoint], date: NaiveDate) -> Option<f64> {
+    let index = points.partition_point(|p| p.date <= date);
+    index.checked_sub(1).map(|i| points[i].close)
+}
+
+/// Last trading session of every ISO week present in the data, ascending
+pub fn week_endings(points_by_symbol: &HashMap<String, Vec<MetricPoint>>) -> Vec<NaiveDate> {
+    let mut by_week: BTreeMap<(i32, u32), NaiveDate> = BTreeMap::new();
+    for point in points_by_symbol.values().flatten() {
+        let week = point.date.iso_week();
+        let entry = by_week.entry((week.year(), week.week())).or_insert(point.date);
+        *entry = (*entry).max(point.date);
+    }
+    by_week.into_values().collect()
+}
+
+/// Top `top` symbols by metric at each of the last `weeks` week endings, newest first,
+/// with returns from the ranking close to the close 1 and 4 week endings later.
+/// Points must be sorted by date per symbol. `is_member` decides whether a symbol belonged to the
+/// group on a week ending, so each week only ranks that week's members (no survivorship bias).
+pub fn build_leaderboard<F>(points_by_symbol: &HashMap<String, Vec<MetricPoint>>, weeks: usize, top: usize, is_member: F) -> Vec<LeaderboardWeek>
+where
+    F: Fn(&str, NaiveDate) -> bool,
+{
+    let endings = week_endings(points_by_symbol);
+    let first = endings.len().saturating_sub(weeks);
+
+    (first..endings.len()).rev().map(|week_index| {
+        let date = endings[week_index];
+        let mut ranked: Vec<(&String, f64, f64)> = points_by_symbol.iter()
+            .filter(|(symbol, _)| is_member(symbol, date))
+            .filter_map(|(symbol, points)| {
+                let index = points.binary_search_by_key(&date, |p| p.date).ok()?;
+                points[index].value.filter(|v| v.is_finite()).map(|value| (symbol, value, points[index].close))
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        let count = ranked.len();
+
+        let forward_return = |symbol: &String, close: f64, horizon: usize| {
+            let target = *endings.get(week_index + horizon)?;
+            let later = close_on_or_before(&points_by_symbol[symbol], target)?;
+            (close != 0.0).then(|| (later - close) / close * 100.0)
+        };
+
+        let entries = ranked.iter().take(top).enumerate().map(|(position, (symbol, value, close))| {
+            let below = ranked.iter().filter(|(_, other, _)| other < value).count();
+            LeaderboardEntry {
+                rank: position + 1,
+                symbol: (*symbol).clone(),
+                metric_value: *value,
+                percentile: if count > 1 { below as f64 / (count - 1) as f64 * 100.0 } else { 100.0 },
+                close: *close,
+                forward_return_1w_pct: forward_return(symbol, *close, FORWARD_HORIZONS_WEEKS[0]),
+                forward_return_4w_pct: forward_return(symbol, *close, FORWARD_HORIZONS_WEEKS[1]),
+            }
+        }).collect();
+
+        LeaderboardWeek { week_ending: date, ranked: count, entries }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weekly_ranking_and_forward_returns() {
+        // Mon 2025-08-04 .. Fri 2025-09-05: five weeks of sessions
+        let dates: Vec<NaiveDate> = NaiveDate::from_ymd_opt(2025, 8, 4).unwrap()
+            .iter_days()
+            .take_while(|d| *d <= NaiveDate::from_ymd_opt(2025, 9, 5).unwrap())
+            .filter(|d| d.weekday().num_days_from_monday() < 5)
+            .collect();
+        let series = |value: f64, growth: f64| -> Vec<MetricPoint> {
+            dates.iter().enumerate().map(|(i, date)| MetricPoint { date: *date, value: Some(value), close: 100.0 + growth * i as f64 }).collect()
+        };
+        let mut points = HashMap::new();
+        points.insert("AAA".to_string(), series(90.0, 1.0));
+        points.insert("BBB".to_string(), series(50.0, 0.0));
+        points.insert("CCC".to_string(), series(10.0, -1.0));
+
+        let board = build_leaderboard(&points, 3, 2, |_, _| true);
+        assert_eq!(board.len(), 3);
+        assert_eq!(board[0].week_ending, NaiveDate::from_ymd_opt(2025, 9, 5).unwrap());
+
+        // Week ending 2025-08-22 closes AAA at 114 and the next week at 119; 4 weeks later is past the data
+        let week = &board[2];
+        assert_eq!((week.week_ending, week.ranked, week.entries.len()), (NaiveDate::from_ymd_opt(2025, 8, 22).unwrap(), 3, 2));
+        let leader = &week.entries[0];
+        assert_eq!((leader.rank, leader.symbol.as_str(), leader.percentile), (1, "AAA", 100.0));
+        assert!((leader.forward_return_1w_pct.unwrap() - 5.0 / 114.0 * 100.0).abs() < 1e-9);
+        assert_eq!(leader.forward_return_4w_pct, None);
+        assert_eq!((week.entries[1].symbol.as_str(), week.entries[1].percentile), ("BBB", 50.0));
+        assert!(board[0].entries[0].forward_return_1w_pct.is_none());
+
+        // AAA only joined the group for the last week ending
+        let joined = NaiveDate::from_ymd_opt(2025, 9, 1).unwrap();
+        let board = build_leaderboard(&points, 3, 2, |symbol, date| symbol != "AAA" || date >= joined);
+        assert_eq!(board[0].entries[0].symbol, "AAA");
+        assert_eq!((board[1].ranked, board[1].entries[0].symbol.as_str()), (2, "BBB"));
+    }
+}
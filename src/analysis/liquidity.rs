@@ -0,0 +1,103 @@
+use crate::vci::OhlcvData;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+// Minimum average daily turnover (close × volume, in VND) over a symbol's latest sessions
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LiquidityPreset {
+    pub min_adtv: f64,
+    pub window: usize, // Sessions averaged
+}
+
+// Named presets, selected with `liquidity=<name>` on the screener and analysis endpoints
+pub type LiquidityPresets = BTreeMap<String, LiquidityPreset>;
+
+/// Built-in presets: "investable" is ADTV above 5bn VND over 20 sessions
+pub fn default_presets() -> LiquidityPresets {
+    BTreeMap::from([("investable".to_string(), LiquidityPreset { min_adtv: 5_000_000_000.0, window: 20 })])
+}
+
+/// Parse `LIQUIDITY_PRESETS` ("investable=5000000000:20,tradable=1000000000"), where the window
+/// defaults to 20 sessions. Invalid entries are skipped.
+pub fn parse_presets(value: &str) -> LiquidityPresets {
+    value.split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .filter_map(|entry| {
+            let parsed = entry.split_once('=').and_then(|(name, spec)| {
+                let (min_adtv, window) = spec.split_once(':').unwrap_or((spec, "20"));
+                let preset = LiquidityPreset { min_adtv: min_adtv.trim().parse().ok()?, window: window.trim().parse().ok().filter(|w| *w > 0)? };
+                Some((name.trim().to_lowercase(), preset))
+            });
+            if parsed.is_none() {
+                warn!(entry, "Ignoring invalid liquidity preset");
+            }
+            parsed
+        })
+        .collect()
+}
+
+static PRESETS: OnceLock<LiquidityPresets> = OnceLock::new();
+
+/// Set the liquidity presets offered by the API; call once at startup
+pub fn set_presets(presets: LiquidityPresets) {
+    if PRESETS.set(presets.clone()).is_err() {
+        warn!("Liquidity presets already set, ignoring new settings");
+    } else {
+        info!(presets = ?presets.keys().collect::<Vec<_>>(), "Configured liquidity presets");
+    }
+}
+
+pub fn presets() -> &'static LiquidityPresets {
+    PRESETS.get_or_init(default_presets)
+}
+
+/// The preset named by a `liquidity` parameter, case-insensitively, with its configured name
+pub fn preset(name: &str) -> Result<(&'static str, &'static LiquidityPreset), String> {
+    presets().get_key_value(&name.to_lowercase()).map(|(name, preset)| (name.as_str(), preset)).ok_or_else(|| {
+        format!("Unknown liquidity preset '{}'. Expected one of {:?}", name, presets().keys().collect::<Vec<_>>())
+    })
+}
+
+/// Mean close × volume of the last `window` bars, None with fewer bars than that
+pub fn average_daily_turnover(series: &[OhlcvData], window: usize) -> Option<f64> {
+    if window == 0 || series.len() < window {
+        return None;
+    }
+    let recent = &series[series.len() - window..];
+    Some(recent.iter().map(|bar| bar.close * bar.volume as f64).sum::<f64>() / window as f64)
+}
+
+impl LiquidityPreset {
+    /// Whether a time-sorted daily series is liquid enough today; symbols without a full window are not
+    pub fn passes(&self, series: &[OhlcvData]) -> bool {
+        average_daily_turnover(series, self.window).is_some_and(|adtv| adtv >= self.min_adtv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_bars;
+
+    fn series(turnovers: &[(f64, u64)]) -> Vec<OhlcvData> {
+        turnovers.iter().enumerate().map(|(day, (close, volume))| test_bars::bar(day as i64, *close, *volume)).collect()
+    }
+
+    #[test]
+    fn test_presets_and_turnover_filter() {
+        let presets = parse_presets("Investable=5000000000:20, tradable=1e9,bad=x,zero=1:0");
+        assert_eq!(presets.len(), 2);
+        assert_eq!(presets["tradable"], LiquidityPreset { min_adtv: 1e9, window: 20 });
+        assert_eq!(presets["investable"], default_presets()["investable"]);
+
+        // Only the latest window counts: an old illiquid stretch doesn't hold a symbol back
+        let preset = LiquidityPreset { min_adtv: 5e9, window: 3 };
+        let liquid = series(&[(10_000.0, 1_000), (60_000.0, 100_000), (60_000.0, 100_000), (50_000.0, 100_000)]);
+        assert_eq!(average_daily_turnover(&liquid, 3), Some(17e9 / 3.0));
+        assert!(preset.passes(&liquid));
+        assert!(!preset.passes(&liquid[..2]));
+        assert!(!preset.passes(&series(&[(60_000.0, 100_000), (60_000.0, 100_000), (10_000.0, 1_000)])));
+    }
+}
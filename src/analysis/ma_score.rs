@@ -0,0 +1,640 @@
+use crate::analysis::market_cap::{weighted_mean, MarketCaps};
+use crate::analysis::warmup;
+use crate::utils::market_time::market_date;
+use crate::vci::OhlcvData;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+// Moving average periods used for MA scores
+pub const MA_PERIODS: [usize; 3] = [10, 20, 50];
+// Sessions (two trading weeks) of MA20 scores the trend score is fitted over
+pub const TREND_WINDOW: usize = 10;
+// Bumped whenever a change alters MA, MA score, streak or trend score values
+pub const ALGORITHM_VERSION: u32 = 3;
+// Longest extra moving average, about two trading years
+pub const MAX_AVERAGE_PERIOD: usize = 500;
+
+// MA score = percentage distance of close from its moving average
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MaScorePoint {
+    pub date: NaiveDate,
+    pub close: f64,
+    pub ma10: Option<f64>,
+    pub ma20: Option<f64>,
+    pub ma50: Option<f64>,
+    pub ma10_score: Option<f64>,
+    pub ma20_score: Option<f64>,
+    pub ma50_score: Option<f64>,
+    // Sessions in a row, ending on this one, closing above (below) the MA. A close on the MA,
+    // or a missing MA, resets both counts; before the MA exists they are None in strict warm-up.
+    #[serde(default)]
+    pub consecutive_days_above_ma10: Option<u32>,
+    #[serde(default)]
+    pub consecutive_days_above_ma20: Option<u32>,
+    #[serde(default)]
+    pub consecutive_days_above_ma50: Option<u32>,
+    #[serde(default)]
+    pub consecutive_days_below_ma10: Option<u32>,
+    #[serde(default)]
+    pub consecutive_days_below_ma20: Option<u32>,
+    #[serde(default)]
+    pub consecutive_days_below_ma50: Option<u32>,
+    // Least-squares slope of ma20_score over the last TREND_WINDOW sessions, in score points
+    // per session. Positive: the close is pulling away above MA20 (or recovering from below).
+    #[serde(default)]
+    pub trend_score: Option<f64>,
+}
+
+impl MaScorePoint {
+    pub fn score(&self, period: usize) -> Option<f64> {
+        match period {
+            10 => self.ma10_score,
+            20 => self.ma20_score,
+            50 => self.ma50_score,
+            _ => None,
+        }
+    }
+
+    pub fn days_above(&self, period: usize) -> Option<u32> {
+        match period {
+            10 => self.consecutive_days_above_ma10,
+            20 => self.consecutive_days_above_ma20,
+            50 => self.consecutive_days_above_ma50,
+            _ => None,
+        }
+    }
+
+    pub fn days_below(&self, period: usize) -> Option<u32> {
+        match period {
+            10 => self.consecutive_days_below_ma10,
+            20 => self.consecutive_days_below_ma20,
+            50 => self.consecutive_days_below_ma50,
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AverageKind {
+    Sma,
+    Ema,
+}
+
+/// A moving average beyond the built-in MA10/20/50, written `sma200` or `ema20`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct MovingAverage {
+    pub kind: AverageKind,
+    pub period: usize,
+}
+
+impl FromStr for MovingAverage {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().to_lowercase();
+        let (kind, period) = if let Some(period) = value.strip_prefix("sma") {
+            (AverageKind::Sma, period)
+        } else if let Some(period) = value.strip_prefix("ema") {
+            (AverageKind::Ema, period)
+        } else {
+            return Err(format!("Invalid moving average '{}', expected sma<period> or ema<period>", value));
+        };
+        match period.parse() {
+            Ok(period) if (2..=MAX_AVERAGE_PERIOD).contains(&period) => Ok(Self { kind, period }),
+            _ => Err(format!("Invalid moving average '{}', the period must be between 2 and {}", value, MAX_AVERAGE_PERIOD)),
+        }
+    }
+}
+
+impl TryFrom<String> for MovingAverage {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<MovingAverage> for String {
+    fn from(average: MovingAverage) -> Self {
+        average.to_string()
+    }
+}
+
+impl fmt::Display for MovingAverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            AverageKind::Sma => "sma",
+            AverageKind::Ema => "ema",
+        };
+        write!(f, "{}{}", kind, self.period)
+    }
+}
+
+impl MovingAverage {
+    /// The average at each of `closes`, None until it is warmed up
+    pub fn calculate(&self, closes: &[f64]) -> Vec<Option<f64>> {
+        match self.kind {
+            AverageKind::Sma => simple_moving_averages(closes, self.period),
+            AverageKind::Ema => exponential_moving_averages(closes, self.period),
+        }
+    }
+}
+
+/// Parse a comma-separated list such as "ema20,sma200", dropping repeats
+pub fn parse_moving_averages(value: &str) -> Result<Vec<MovingAverage>, String> {
+    let mut averages: Vec<MovingAverage> = Vec::new();
+    for average in value.split(',').filter(|s| !s.trim().is_empty()) {
+        let average: MovingAverage = average.parse()?;
+        if !averages.contains(&average) {
+            averages.push(average);
+        }
+    }
+    Ok(averages)
+}
+
+static MOVING_AVERAGES: OnceLock<Vec<MovingAverage>> = OnceLock::new();
+
+/// Set the extra moving averages enhanced rows carry; call once at startup. None by default.
+pub fn set_moving_averages(averages: Vec<MovingAverage>) {
+    let names: Vec<String> = averages.iter().map(MovingAverage::to_string).collect();
+    if MOVING_AVERAGES.set(averages).is_err() {
+        warn!("Moving averages already set, ignoring new settings");
+    } else {
+        info!(?names, "Configured extra moving averages");
+    }
+}
+
+pub fn moving_averages() -> &'static [MovingAverage] {
+    MOVING_AVERAGES.get().map_or(&[], Vec::as_slice)
+}
+
+// Values and MA scores of extra moving averages on one session, keyed `<name>` and `<name>_score`
+pub type AverageValues = BTreeMap<String, Option<f64>>;
+
+/// Each of `averages` and the close's MA score against it, for every bar of a time-sorted daily series
+pub fn calculate_averages(series: &[OhlcvData], averages: &[MovingAverage]) -> Vec<AverageValues> {
+    let closes: Vec<f64> = series.iter().map(|bar| bar.close).collect();
+    let mut values = vec![AverageValues::new(); series.len()];
+    for average in averages {
+        let name = average.to_string();
+        for ((row, ma), close) in values.iter_mut().zip(average.calculate(&closes)).zip(&closes) {
+            row.insert(format!("{}_score", name), ma_score(*close, ma));
+            row.insert(name.clone(), ma);
+        }
+    }
+    values
+}
+
+/// An MA score point with extra moving averages alongside the built-in ones
+#[derive(Clone, Debug, Serialize)]
+pub struct ExtendedPoint {
+    #[serde(flatten)]
+    pub point: MaScorePoint,
+    #[serde(flatten)]
+    pub averages: AverageValues,
+}
+
+// Distribution of MA scores across a group of tickers on one date
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScoreDistribution {
+    pub date: NaiveDate,
+    pub count: usize,
+    pub min: f64,
+    pub q1: f64,
+    pub median: f64,
+    pub q3: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub percent_positive: f64,
+    pub provisional: bool, // Date is today's still-open session, final after close
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weighted_mean: Option<f64>, // Market-cap-weighted mean when requested; symbols without a market cap are left out
+}
+
+// Full distribution for a group and period, independent of any requested date range
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GroupDistribution {
+    pub symbols: usize,  // Symbols with price data
+    pub coverage: f64,   // Share of group symbols with a full MA window
+    pub calculated: bool,
+    pub distribution: Vec<ScoreDistribution>,
+}
+
+impl GroupDistribution {
+    /// Slice the distribution to a date range (inclusive)
+    pub fn in_range(&self, start: Option<NaiveDate>, end: Option<NaiveDate>) -> Vec<ScoreDistribution> {
+        self.distribution.iter()
+            .filter(|d| start.is_none_or(|start| d.date >= start) && end.is_none_or(|end| d.date <= end))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Exact running sum kept as non-overlapping partials (Shewchuk's algorithm, as in Python's
+/// `math.fsum`), so values can be added and removed again without rounding error building up
+#[derive(Default)]
+struct ExactSum {
+    partials: Vec<f64>,
+}
+
+impl ExactSum {
+    fn add(&mut self, mut x: f64) {
+        let mut kept = 0;
+        for j in 0..self.partials.len() {
+            let mut y = self.partials[j];
+            if x.abs() < y.abs() {
+                std::mem::swap(&mut x, &mut y);
+            }
+            let hi = x + y;
+            let lo = y - (hi - x);
+            if lo != 0.0 {
+                self.partials[kept] = lo;
+                kept += 1;
+            }
+            x = hi;
+        }
+        self.partials.truncate(kept);
+        self.partials.push(x);
+    }
+
+    /// The sum correctly rounded to the nearest f64
+    fn value(&self) -> f64 {
+        let Some((&last, rest)) = self.partials.split_last() else { return 0.0 };
+        let (mut hi, mut lo, mut n) = (last, 0.0, rest.len());
+        while n > 0 {
+            n -= 1;
+            let x = hi;
+            hi = x + rest[n];
+            lo = rest[n] - (hi - x);
+            if lo != 0.0 {
+                break;
+            }
+        }
+        // Round half-way cases the way the partials below would tip them
+        if n > 0 && ((lo < 0.0 && rest[n - 1] < 0.0) || (lo > 0.0 && rest[n - 1] > 0.0)) {
+            let y = lo * 2.0;
+            let x = hi + y;
+            if y == x - hi {
+                hi = x;
+            }
+        }
+        hi
+    }
+}
+
+/// Simple moving average of `closes` ending at each index, or None until a full window is available.
+/// The window sum is carried from one session to the next, so the cost doesn't grow with the period,
+/// and it is exact, so a session's average doesn't depend on where the computation started. A window
+/// holding a non-finite close has no average; the close stops counting once it leaves the window.
+fn simple_moving_averages(closes: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut sum = ExactSum::default();
+    let mut non_finite = 0;
+    closes.iter().enumerate().map(|(i, close)| {
+        if close.is_finite() { sum.add(*close) } else { non_finite += 1 }
+        if let Some(dropped) = i.checked_sub(period).map(|j| closes[j]) {
+            if dropped.is_finite() { sum.add(-dropped) } else { non_finite -= 1 }
+        }
+        (period > 0 && i + 1 >= period && non_finite == 0).then(|| sum.value() / period as f64)
+    }).collect()
+}
+
+/// Exponential moving average of `closes` seeded with the simple average of its first `period`
+/// closes. A non-finite close breaks the average, which is seeded again from the closes after it.
+fn exponential_moving_averages(closes: &[f64], period: usize) -> Vec<Option<f64>> {
+    let k = 2.0 / (period as f64 + 1.0);
+    let mut ema: Option<f64> = None;
+    let mut seed = Vec::with_capacity(period);
+    closes.iter().map(|close| {
+        if !close.is_finite() {
+            ema = None;
+            seed.clear();
+            return None;
+        }
+        ema = match ema {
+            Some(previous) => Some(previous + k * (close - previous)),
+            None => {
+                seed.push(*close);
+                (seed.len() == period).then(|| seed.iter().sum::<f64>() / period as f64)
+            }
+        };
+        ema
+    }).collect()
+}
+
+fn ma_score(close: f64, ma: Option<f64>) -> Option<f64> {
+    ma.filter(|ma| *ma != 0.0).map(|ma| (close - ma) / ma * 100.0)
+}
+
+/// Least-squares slope of evenly spaced values against their index
+fn slope(values: &[f64]) -> f64 {
+    let n = values.len() as f64;
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = values.iter().sum::<f64>() / n;
+    let (covariance, variance) = values.iter().enumerate().fold((0.0, 0.0), |(cov, var), (x, y)| {
+        let dx = x as f64 - mean_x;
+        (cov + dx * (y - mean_y), var + dx * dx)
+    });
+    if variance == 0.0 { 0.0 } else { covariance / variance }
+}
+
+/// Advance the (above, below) run counts with one session's close against its MA
+fn next_streak((above, below): (u32, u32), close: f64, ma: Option<f64>) -> (u32, u32) {
+    match ma {
+        Some(ma) if close > ma => (above + 1, 0),
+        Some(ma) if close < ma => (0, below + 1),
+        _ => (0, 0),
+    }
+}
+
+/// Calculate MA10/20/50 and their scores for a time-sorted daily series
+pub fn calculate_ma_scores(series: &[OhlcvData]) -> Vec<MaScorePoint> {
+    update_ma_scores(series, &[], 0)
+}
+
+/// `calculate_ma_scores` for a series whose bars before `first_changed` are the ones `previous`
+/// was calculated from. Those points are reused; only the later ones are recalculated.
+pub fn update_ma_scores(series: &[OhlcvData], previous: &[MaScorePoint], first_changed: usize) -> Vec<MaScorePoint> {
+    let keep = first_changed.min(previous.len()).min(series.len());
+    // The longest MA reaches this far back from the first recalculated session
+    let base = keep.saturating_sub(MA_PERIODS[2] - 1);
+    let closes: Vec<f64> = series[base..].iter().map(|d| d.close).collect();
+    let [ma10s, ma20s, ma50s] = MA_PERIODS.map(|period| simple_moving_averages(&closes, period));
+    // Running (above, below) counts per MA period, carried across sessions
+    let mut streaks = previous[..keep].last().map_or([(0u32, 0u32); 3], |last| [
+        (last.consecutive_days_above_ma10, last.consecutive_days_below_ma10),
+        (last.consecutive_days_above_ma20, last.consecutive_days_below_ma20),
+        (last.consecutive_days_above_ma50, last.consecutive_days_below_ma50),
+    ].map(|(above, below)| (above.unwrap_or(0), below.unwrap_or(0))));
+    let mode = warmup::mode();
+
+    let mut points = previous[..keep].to_vec();
+    points.extend(series.iter().enumerate().skip(keep).map(|(i, bar)| {
+        let (ma10, ma20, ma50) = (ma10s[i - base], ma20s[i - base], ma50s[i - base]);
+        for (streak, ma) in streaks.iter_mut().zip([ma10, ma20, ma50]) {
+            *streak = next_streak(*streak, bar.close, ma);
+        }
+
+        MaScorePoint {
+            date: market_date(bar.time),
+            close: bar.close,
+            ma10,
+            ma20,
+            ma50,
+            ma10_score: ma_score(bar.close, ma10),
+            ma20_score: ma_score(bar.close, ma20),
+            ma50_score: ma_score(bar.close, ma50),
+            consecutive_days_above_ma10: mode.run_count(streaks[0].0, ma10),
+            consecutive_days_above_ma20: mode.run_count(streaks[1].0, ma20),
+            consecutive_days_above_ma50: mode.run_count(streaks[2].0, ma50),
+            consecutive_days_below_ma10: mode.run_count(streaks[0].1, ma10),
+            consecutive_days_below_ma20: mode.run_count(streaks[1].1, ma20),
+            consecutive_days_below_ma50: mode.run_count(streaks[2].1, ma50),
+            trend_score: None,
+        }
+    }));
+
+    // Needs a full window of MA20 scores, so the first value appears at session 20 + TREND_WINDOW - 1
+    for i in keep.max(TREND_WINDOW.saturating_sub(1))..points.len() {
+        let window: Option<Vec<f64>> = points[i + 1 - TREND_WINDOW..=i].iter().map(|p| p.ma20_score).collect();
+        points[i].trend_score = window.map(|scores| slope(&scores));
+    }
+    points
+}
+
+/// Linear-interpolated quantile of an ascending-sorted slice
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.len() == 1 {
+        return sorted[0];
+    }
+    let position = q * (sorted.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    let fraction = position - lower as f64;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * fraction
+}
+
+/// Summarise a set of scores, or None if empty
+pub fn summarize_scores(date: NaiveDate, scores: &mut [f64]) -> Option<ScoreDistribution> {
+    if scores.is_empty() {
+        return None;
+    }
+    scores.sort_by(|a, b| a.total_cmp(b));
+    let count = scores.len();
+    let positive = scores.iter().filter(|s| **s > 0.0).count();
+
+    Some(ScoreDistribution {
+        date,
+        count,
+        min: scores[0],
+        q1: quantile(scores, 0.25),
+        median: quantile(scores, 0.5),
+        q3: quantile(scores, 0.75),
+        max: scores[count - 1],
+        mean: scores.iter().sum::<f64>() / count as f64,
+        percent_positive: positive as f64 / count as f64 * 100.0,
+        provisional: false,
+        weighted_mean: None,
+    })
+}
+
+type WeightedScores = Vec<(f64, f64)>;
+
+/// Per-date distribution of MA scores for a group.
+/// `is_member` decides whether a symbol belongs to the group on a given date,
+/// so index groups can use point-in-time membership. With `market_caps`, each date
+/// also gets a mean weighted by the market cap at that date's close.
+pub fn ma_score_distribution<S, F>(
+    scores_by_symbol: &HashMap<String, S>,
+    period: usize,
+    provisional_date: Option<NaiveDate>,
+    market_caps: Option<&MarketCaps>,
+    is_member: F,
+) -> Vec<ScoreDistribution>
+where
+    S: AsRef<[MaScorePoint]>,
+    F: Fn(&str, NaiveDate) -> bool,
+{
+    // Every member score, plus (score, market cap) for members with a known market cap
+    let mut by_date: HashMap<NaiveDate, (Vec<f64>, WeightedScores)> = HashMap::new();
+
+    for (symbol, points) in scores_by_symbol {
+        for point in points.as_ref() {
+            if let Some(score) = point.score(period)
+                && is_member(symbol, point.date)
+            {
+                let (scores, weighted) = by_date.entry(point.date).or_default();
+                scores.push(score);
+                if let Some(cap) = market_caps.and_then(|caps| caps.market_cap(symbol, Some(point.close))) {
+                    weighted.push((score, cap));
+                }
+            }
+        }
+    }
+
+    let mut distribution: Vec<ScoreDistribution> = by_date
+        .into_iter()
+        .filter_map(|(date, (mut scores, weighted))| {
+            let mut summary = summarize_scores(date, &mut scores)?;
+            summary.provisional = Some(summary.date) == provisional_date;
+            summary.weighted_mean = market_caps.and_then(|_| weighted_mean(&weighted));
+            Some(summary)
+        })
+        .collect();
+    distribution.sort_by_key(|d| d.date);
+    distribution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::warmup::WarmupMode;
+    use crate::utils::test_bars::series;
+
+    #[test]
+    fn test_ma_scores_require_full_window() {
+        let closes: Vec<f64> = (1..=20).map(|v| v as f64).collect();
+        let points = calculate_ma_scores(&series(&closes));
+
+        assert!(points[8].ma10.is_none());
+        assert_eq!(points[9].ma10, Some(5.5));
+        assert_eq!(points[19].ma20, Some(10.5));
+        assert!((points[19].ma20_score.unwrap() - (20.0 - 10.5) / 10.5 * 100.0).abs() < 1e-9);
+        assert!(points[19].ma50.is_none());
+    }
+
+    #[test]
+    fn test_rolling_averages_match_window_sums() {
+        // Multi-year random walk with cent-level prices, against each window summed from scratch
+        let mut close = 25.0;
+        let closes: Vec<f64> = (0..2000u64).map(|i| {
+            close = (close + (i.wrapping_mul(2654435761) % 201) as f64 / 100.0 - 1.0).max(1.0);
+            (close * 100.0).round() / 100.0
+        }).collect();
+        for period in MA_PERIODS {
+            let rolling = simple_moving_averages(&closes, period);
+            for (i, ma) in rolling.iter().enumerate() {
+                let expected = (i + 1 >= period).then(|| closes[i + 1 - period..=i].iter().sum::<f64>() / period as f64);
+                assert_eq!(ma.is_some(), expected.is_some());
+                assert!(ma.zip(expected).is_none_or(|(ma, expected)| (ma - expected).abs() < 1e-12 * expected));
+            }
+        }
+        // Starting mid-series gives bit-identical averages, so incremental updates match full runs
+        let tail = simple_moving_averages(&closes[1234..], 50);
+        assert_eq!(tail[49..], simple_moving_averages(&closes, 50)[1234 + 49..]);
+
+        // A NaN close only blanks the windows that contain it
+        let mut gapped: Vec<f64> = (1..=15).map(|v| v as f64).collect();
+        gapped[4] = f64::NAN;
+        let averages = simple_moving_averages(&gapped, 3);
+        assert_eq!(averages[..8], [None, None, Some(2.0), Some(3.0), None, None, None, Some(7.0)]);
+        assert_eq!(averages[14], Some(14.0));
+        assert!(simple_moving_averages(&gapped, 0).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_extra_moving_averages() {
+        assert_eq!(parse_moving_averages(" EMA20, sma200,ema20,").unwrap(), vec![
+            MovingAverage { kind: AverageKind::Ema, period: 20 },
+            MovingAverage { kind: AverageKind::Sma, period: 200 },
+        ]);
+        assert!(parse_moving_averages("wma20").is_err());
+        assert!(parse_moving_averages("sma1").is_err());
+        assert!(parse_moving_averages("ema501").is_err());
+
+        let closes: Vec<f64> = (1..=12).map(|v| v as f64).collect();
+        let averages = calculate_averages(&series(&closes), &parse_moving_averages("ema3,sma10").unwrap());
+        // Seeded with the SMA3 of 1, 2 and 3, then moving halfway to each close
+        assert_eq!((averages[1]["ema3"], averages[2]["ema3"], averages[3]["ema3"]), (None, Some(2.0), Some(3.0)));
+        assert_eq!(averages[9]["sma10"], calculate_ma_scores(&series(&closes))[9].ma10);
+        assert!((averages[11]["sma10_score"].unwrap() - (12.0 - 7.5) / 7.5 * 100.0).abs() < 1e-9);
+
+        // A NaN close restarts the EMA seed
+        let mut gapped = closes.clone();
+        gapped[4] = f64::NAN;
+        let ema = exponential_moving_averages(&gapped, 3);
+        assert_eq!(ema[4..8], [None, None, None, Some(7.0)]);
+    }
+
+    #[test]
+    fn test_consecutive_days_streaks() {
+        // 10 rising closes, then 3 sharp drops below MA10, then back above
+        let mut closes: Vec<f64> = (1..=12).map(|v| v as f64).collect();
+        closes.extend([2.0, 1.0, 0.5, 20.0]);
+        let points = calculate_ma_scores(&series(&closes));
+
+        // No MA10 yet: no counts in strict warm-up, zero in lenient
+        assert_eq!((points[8].consecutive_days_above_ma10, points[8].consecutive_days_below_ma10), (None, None));
+        assert_eq!(WarmupMode::Lenient.run_count(0, None), Some(0));
+        assert_eq!(points[9].consecutive_days_above_ma10, Some(1));
+        assert_eq!(points[11].days_above(10), Some(3));
+        assert_eq!((points[14].consecutive_days_above_ma10, points[14].consecutive_days_below_ma10), (Some(0), Some(3)));
+        assert_eq!((points[15].days_above(10), points[15].days_below(10)), (Some(1), Some(0)));
+        assert_eq!(points[15].consecutive_days_above_ma20, None);
+        assert_eq!(points[15].days_above(30), None);
+
+        // A close exactly on the MA resets both counts
+        assert_eq!(next_streak((4, 0), 10.0, Some(10.0)), (0, 0));
+        assert_eq!(next_streak((0, 2), 9.0, Some(10.0)), (0, 3));
+    }
+
+    #[test]
+    fn test_trend_score_is_slope_of_ma20_score() {
+        assert!((slope(&[1.0, 3.0, 5.0, 7.0]) - 2.0).abs() < 1e-12);
+        assert_eq!(slope(&[4.0]), 0.0);
+
+        let closes: Vec<f64> = (1..=40).map(|v| (v * v) as f64).collect();
+        let points = calculate_ma_scores(&series(&closes));
+        assert!(points[27].trend_score.is_none());
+        let window: Vec<f64> = points[19..=28].iter().map(|p| p.ma20_score.unwrap()).collect();
+        assert_eq!(points[28].trend_score, Some(slope(&window)));
+
+        let falling: Vec<f64> = closes.iter().rev().cloned().collect();
+        assert!(calculate_ma_scores(&series(&falling))[39].trend_score.unwrap() < 0.0);
+    }
+
+    #[test]
+    fn test_range_switch_reuses_full_distribution() {
+        let closes: Vec<f64> = (1..=40).map(|v| v as f64).collect();
+        let mut scores_by_symbol = HashMap::new();
+        scores_by_symbol.insert("A".to_string(), calculate_ma_scores(&series(&closes)));
+        scores_by_symbol.insert("B".to_string(), calculate_ma_scores(&series(&closes.iter().rev().cloned().collect::<Vec<_>>())));
+
+        let full = GroupDistribution {
+            symbols: 2,
+            coverage: 1.0,
+            calculated: true,
+            distribution: ma_score_distribution(&scores_by_symbol, 10, None, None, |_, _| true),
+        };
+        assert!(full.distribution.iter().all(|d| d.weighted_mean.is_none()));
+        let date = |day: u32| NaiveDate::from_ymd_opt(2025, 1, day).unwrap();
+
+        // Narrow range, then a superset: both are slices of the same computation
+        let narrow = full.in_range(Some(date(20)), Some(date(25)));
+        let wide = full.in_range(Some(date(10)), None);
+        assert_eq!(narrow.len(), 6);
+        assert_eq!(wide.len(), full.distribution.len());
+        assert!(narrow.iter().all(|n| wide.iter().any(|w| w.date == n.date && w.median == n.median)));
+        assert!(full.in_range(Some(date(30)), Some(date(5))).is_empty());
+    }
+
+    #[test]
+    fn test_summarize_scores_quantiles() {
+        let date = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let mut scores = vec![4.0, -1.0, 2.0, 3.0, 1.0];
+        let summary = summarize_scores(date, &mut scores).unwrap();
+
+        assert_eq!(summary.count, 5);
+        assert_eq!(summary.min, -1.0);
+        assert_eq!(summary.q1, 1.0);
+        assert_eq!(summary.median, 2.0);
+        assert_eq!(summary.q3, 3.0);
+        assert_eq!(summary.max, 4.0);
+        assert_eq!(summary.percent_positive, 80.0);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::company::SharedCompanyService;
+use crate::ticker_info::{for_each_symbol_object, SharedTickerDirectory, TickerDirectory};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+// How a group aggregate weights its members
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Weighting {
+    #[default]
+    Equal,
+    MarketCap,
+}
+
+impl Weighting {
+    pub fn name(self) -> &'static str {
+        match self {
+            Weighting::Equal => "equal",
+            Weighting::MarketCap => "market_cap",
+        }
+    }
+}
+
+impl std::str::FromStr for Weighting {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "equal" => Ok(Weighting::Equal),
+            "market_cap" => Ok(Weighting::MarketCap),
+            other => Err(format!("Invalid weighting '{}'. Expected equal or market_cap", other)),
+        }
+    }
+}
+
+/// Share counts and reported market caps per symbol. A symbol's market cap on a session is
+/// its share count times that session's close, else the last reported market cap.
+#[derive(Clone, Debug, Default)]
+pub struct MarketCaps {
+    shares: HashMap<String, f64>,
+    reported: HashMap<String, f64>,
+}
+
+impl MarketCaps {
+    /// Seed from ticker_info.json; company info added later takes precedence
+    pub fn from_directory(directory: &TickerDirectory) -> Self {
+        let mut caps = Self::default();
+        for info in directory.entries() {
+            caps.insert(&info.symbol, info.outstanding_shares, info.market_cap);
+        }
+        caps
+    }
+
+    pub fn insert(&mut self, symbol: &str, shares: Option<f64>, reported: Option<f64>) {
+        if let Some(shares) = shares.filter(|s| *s > 0.0) {
+            self.shares.insert(symbol.to_string(), shares);
+        }
+        if let Some(reported) = reported.filter(|c| *c > 0.0) {
+            self.reported.insert(symbol.to_string(), reported);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.shares.keys().chain(self.reported.keys().filter(|s| !self.shares.contains_key(*s))).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shares.is_empty() && self.reported.is_empty()
+    }
+
+    pub fn market_cap(&self, symbol: &str, close: Option<f64>) -> Option<f64> {
+        match (self.shares.get(symbol), close) {
+            (Some(shares), Some(close)) if close > 0.0 => Some(shares * close),
+            _ => self.reported.get(symbol).copied(),
+        }
+    }
+
+    /// Add `market_cap` to every JSON object carrying a `symbol`, priced at the object's `close` when present
+    pub fn annotate(&self, value: &mut Value) {
+        for_each_symbol_object(value, &mut |symbol, map| {
+            let close = map.get("close").and_then(Value::as_f64);
+            map.insert("market_cap".to_string(), self.market_cap(symbol, close).map_or(Value::Null, Value::from));
+        });
+    }
+}
+
+/// Where market caps come from: ticker_info.json, overridden by any company info already cached
+#[derive(Clone)]
+pub struct MarketCapSources {
+    pub tickers: SharedTickerDirectory,
+    pub company: SharedCompanyService,
+}
+
+impl MarketCapSources {
+    pub async fn load(&self) -> MarketCaps {
+        let mut caps = MarketCaps::from_directory(&*self.tickers.lock().await);
+        for (symbol, shares, reported) in self.company.cached_market_caps().await {
+            caps.insert(&symbol, shares, reported);
+        }
+        caps
+    }
+
+    /// Add company names and market caps next to every `symbol` in a response body
+    pub async fn annotate(&self, body: &mut Value) {
+        let caps = self.load().await;
+        self.tickers.lock().await.annotate(body);
+        caps.annotate(body);
+    }
+}
+
+/// Mean of `(value, weight)` pairs, or None without positive total weight
+pub fn weighted_mean(pairs: &[(f64, f64)]) -> Option<f64> {
+    let total: f64 = pairs.iter().map(|(_, weight)| weight).sum();
+    (total > 0.0).then(|| pairs.iter().map(|(value, weight)| value * weight).sum::<f64>() / total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_market_cap_resolution_and_weighting() {
+        let directory = TickerDirectory::parse(r#"[
+            {"symbol": "VCB", "outstanding_shares": 1000.0},
+            {"symbol": "FPT", "market_cap": 5000.0}
+        ]"#).unwrap();
+        let mut caps = MarketCaps::from_directory(&directory);
+        assert_eq!(caps.market_cap("VCB", Some(60.0)), Some(60_000.0));
+        assert_eq!(caps.market_cap("VCB", None), None);
+        assert_eq!(caps.market_cap("FPT", Some(100.0)), Some(5000.0));
+        assert_eq!(caps.market_cap("HPG", Some(25.0)), None);
+
+        // Company info share counts override ticker_info.json
+        caps.insert("VCB", Some(2000.0), None);
+        assert_eq!(caps.market_cap("VCB", Some(60.0)), Some(120_000.0));
+        assert_eq!(caps.len(), 2);
+
+        let mut rows = serde_json::json!([{ "symbol": "VCB", "close": 50.0 }, { "symbol": "HPG", "close": 25.0 }]);
+        caps.annotate(&mut rows);
+        assert_eq!(rows[0]["market_cap"], 100_000.0);
+        assert!(rows[1]["market_cap"].is_null());
+
+        assert_eq!(weighted_mean(&[(10.0, 3.0), (-2.0, 1.0)]), Some(7.0));
+        assert_eq!(weighted_mean(&[]), None);
+        assert_eq!("market_cap".parse::<Weighting>(), Ok(Weighting::MarketCap));
+        assert!("cap".parse::<Weighting>().is_err());
+    }
+}
@@ -0,0 +1,16 @@
+pub mod basis;
+pub mod coverage;
+pub mod gaps;
+pub mod indicator_cache;
+pub mod indicators;
+pub mod leaderboard;
+pub mod liquidity;
+pub mod market_cap;
+pub mod ma_score;
+pub mod money_flow;
+pub mod screener;
+pub mod sector;
+pub mod strength;
+pub mod synthetic_index;
+pub mod vwap;
+pub mod warmup;
@@ -0,0 +1,205 @@
+use crate::utils::market_time::market_date;
+use crate::vci::OhlcvData;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+// Lookback (sessions) for the divergence N-day high/low and its flow window
+pub const DEFAULT_DIVERGENCE_LOOKBACK: usize = 20;
+pub const MIN_DIVERGENCE_LOOKBACK: usize = 5;
+pub const MAX_DIVERGENCE_LOOKBACK: usize = 120;
+// Sessions summed into the rolling money flow series
+pub const DEFAULT_WINDOW: usize = 20;
+pub const MIN_WINDOW: usize = 5;
+pub const MAX_WINDOW: usize = 120;
+// Half-life (sessions) of the optional decay weighting behind weighted_share
+pub const MIN_HALF_LIFE: usize = 1;
+pub const MAX_HALF_LIFE: usize = 120;
+// Version of the flow and divergence calculations, bumped when their output changes
+pub const ALGORITHM_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DivergenceKind {
+    Bearish, // New N-day high while money flows out
+    Bullish, // New N-day low while money flows in
+}
+
+// One session where price and signed dollar flow disagree
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DivergencePoint {
+    pub symbol: String,
+    pub date: NaiveDate,
+    pub kind: DivergenceKind,
+    pub close: f64,
+    pub prior_extreme: f64, // Highest (bearish) or lowest (bullish) close of the prior N sessions
+    pub net_flow: f64,      // Signed dollar flow summed over the last N sessions, this one included
+    pub provisional: bool,  // Date is today's still-open session
+}
+
+/// Close times volume, signed by the close-to-close direction (flat sessions count as zero)
+pub fn signed_dollar_flow(series: &[OhlcvData]) -> Vec<f64> {
+    std::iter::once(0.0)
+        .chain(series.windows(2).map(|pair| {
+            let direction = match pair[1].close.partial_cmp(&pair[0].close) {
+                Some(std::cmp::Ordering::Greater) => 1.0,
+                Some(std::cmp::Ordering::Less) => -1.0,
+                _ => 0.0,
+            };
+            direction * pair[1].close * pair[1].volume as f64
+        }))
+        .take(series.len())
+        .collect()
+}
+
+/// Inflow share of signed dollar flows, 0..1; None when nothing traded
+pub fn inflow_share(flows: &[f64]) -> Option<f64> {
+    let inflow: f64 = flows.iter().filter(|f| **f > 0.0).sum();
+    let outflow: f64 = flows.iter().filter(|f| **f < 0.0).map(|f| -f).sum();
+    let total = inflow + outflow;
+    (total > 0.0).then(|| inflow / total)
+}
+
+/// `inflow_share` with each flow weighted by 0.5^(sessions before the last / half_life), so the
+/// latest session counts fully and one `half_life` sessions earlier counts half
+pub fn weighted_inflow_share(flows: &[f64], half_life: usize) -> Option<f64> {
+    let weighted: Vec<f64> = flows.iter().rev().enumerate()
+        .map(|(age, flow)| flow * 0.5f64.powf(age as f64 / half_life as f64))
+        .collect();
+    inflow_share(&weighted)
+}
+
+// Signed dollar flow of one session and over the `window` sessions ending on it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MoneyFlowPoint {
+    pub date: NaiveDate,
+    pub close: f64,
+    pub dollar_flow: f64,          // Close × volume, signed by the close-to-close direction
+    pub net_flow: Option<f64>,     // Summed over the window; None until the window is full
+    pub inflow_share: Option<f64>, // Share of the window's flow that was inflow, 0..1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weighted_share: Option<f64>, // inflow_share with recent sessions weighted up; only with a half-life
+}
+
+/// Money flow per session of a time-sorted daily series. The first session has no prior close,
+/// so its flow is zero and it never counts towards a window. `half_life` adds `weighted_share`.
+pub fn calculate_money_flow(series: &[OhlcvData], window: usize, half_life: Option<usize>) -> Vec<MoneyFlowPoint> {
+    let flows = signed_dollar_flow(series);
+    series.iter().zip(&flows).enumerate()
+        .map(|(i, (bar, dollar_flow))| {
+            let window_flows = (window > 0 && i >= window).then(|| &flows[i + 1 - window..=i]);
+            MoneyFlowPoint {
+                date: market_date(bar.time),
+                close: bar.close,
+                dollar_flow: *dollar_flow,
+                net_flow: window_flows.map(|flows| flows.iter().sum()),
+                inflow_share: window_flows.and_then(inflow_share),
+                weighted_share: half_life.filter(|h| *h > 0).and_then(|h| window_flows.and_then(|flows| weighted_inflow_share(flows, h))),
+            }
+        })
+        .collect()
+}
+
+/// Every session of a time-sorted daily series where the close breaks the prior
+/// `lookback`-session high (low) while net signed flow over the same window is negative (positive)
+pub fn detect_divergences(symbol: &str, series: &[OhlcvData], lookback: usize, provisional_date: Option<NaiveDate>) -> Vec<DivergencePoint> {
+    update_divergences(symbol, series, lookback, provisional_date, &[], 0)
+}
+
+/// `detect_divergences` for a series whose bars before `first_changed` are the ones `previous`
+/// was detected in, with the same lookback and provisional date. Earlier sessions are kept from
+/// `previous`; only sessions from `first_changed` on are checked again.
+pub fn update_divergences(
+    symbol: &str,
+    series: &[OhlcvData],
+    lookback: usize,
+    provisional_date: Option<NaiveDate>,
+    previous: &[DivergencePoint],
+    first_changed: usize,
+) -> Vec<DivergencePoint> {
+    if lookback == 0 || series.len() <= lookback {
+        return Vec::new();
+    }
+    let keep = |point: &&DivergencePoint| match series.get(first_changed) {
+        Some(bar) => point.date < market_date(bar.time),
+        None => point.date <= market_date(series[series.len() - 1].time),
+    };
+    let mut points: Vec<DivergencePoint> = previous.iter().take_while(keep).cloned().collect();
+    let start = first_changed.max(lookback);
+    // Flows of the window before the first checked session; the flow at `base` itself is never used
+    let base = start - lookback;
+    let flows = signed_dollar_flow(&series[base..]);
+
+    points.extend((start..series.len())
+        .filter_map(|index| {
+            let bar = &series[index];
+            let prior = &series[index - lookback..index];
+            let prior_high = prior.iter().map(|b| b.close).fold(f64::MIN, f64::max);
+            let prior_low = prior.iter().map(|b| b.close).fold(f64::MAX, f64::min);
+            // Summed per window rather than from running totals, so results don't depend on where the update started
+            let net_flow: f64 = flows[index + 1 - lookback - base..=index - base].iter().sum();
+
+            let (kind, prior_extreme) = if bar.close > prior_high && net_flow < 0.0 {
+                (DivergenceKind::Bearish, prior_high)
+            } else if bar.close < prior_low && net_flow > 0.0 {
+                (DivergenceKind::Bullish, prior_low)
+            } else {
+                return None;
+            };
+            let date = market_date(bar.time);
+            Some(DivergencePoint {
+                symbol: symbol.to_string(),
+                date,
+                kind,
+                close: bar.close,
+                prior_extreme,
+                net_flow,
+                provisional: provisional_date == Some(date),
+            })
+        }));
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_bars::bar;
+
+    #[test]
+    fn test_divergence_on_new_high_with_outflow() {
+        // Heavy selling at 100 -> 95, then a thin drift up to a new 5-day high
+        let series = vec![
+            bar(0, 100.0, 100), bar(1, 96.0, 5000), bar(2, 95.0, 5000),
+            bar(3, 96.0, 10), bar(4, 98.0, 10), bar(5, 101.0, 10),
+        ];
+        assert_eq!(signed_dollar_flow(&series)[1], -96.0 * 5000.0);
+
+        let divergences = detect_divergences("VCB", &series, 5, NaiveDate::from_ymd_opt(2025, 8, 6));
+        assert_eq!(divergences.len(), 1);
+        let point = &divergences[0];
+        assert_eq!((point.kind, point.date, point.prior_extreme), (DivergenceKind::Bearish, NaiveDate::from_ymd_opt(2025, 8, 6).unwrap(), 100.0));
+        assert!(point.net_flow < 0.0 && point.provisional);
+
+        // Per-session flow agrees with the divergence window
+        let flow = calculate_money_flow(&series, 5, None);
+        assert!(flow[4].net_flow.is_none());
+        assert_eq!(flow[5].net_flow, Some(point.net_flow));
+        let inflow = (96.0 + 98.0 + 101.0) * 10.0;
+        assert_eq!(flow[5].inflow_share, Some(inflow / (inflow + (96.0 + 95.0) * 5000.0)));
+        assert!(flow[5].weighted_share.is_none());
+
+        // Decay weighting: the selling, 3 and 4 sessions back, counts 1/8 and 1/16 at a one-session half-life
+        let weighted = calculate_money_flow(&series, 5, Some(1));
+        let recent = (101.0 + 98.0 / 2.0 + 96.0 / 4.0) * 10.0;
+        let expected = recent / (recent + 95.0 * 5000.0 / 8.0 + 96.0 * 5000.0 / 16.0);
+        assert!((weighted[5].weighted_share.unwrap() - expected).abs() < 1e-12);
+        assert!(weighted[5].weighted_share > flow[5].inflow_share);
+        assert_eq!(weighted[5].inflow_share, flow[5].inflow_share);
+
+        // Same breakout on rising volume is confirmation, not divergence
+        let confirmed: Vec<OhlcvData> = series.iter().enumerate()
+            .map(|(day, b)| bar(day as i64, b.close, if day >= 3 { 50_000 } else { b.volume }))
+            .collect();
+        assert!(detect_divergences("VCB", &confirmed, 5, None).is_empty());
+        assert!(detect_divergences("VCB", &series[..5], 5, None).is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::analysis::money_flow::calculate_money_flow;
+use crate::schema::{FieldType, ENHANCED_ROW_FIELDS};
+use crate::vci::OhlcvData;
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+
+// Sessions behind volume_avg20 and the money_flow sum
+pub const SCREENER_WINDOW: usize = 20;
+pub const MAX_SCREENER_FILTERS: usize = 16;
+pub const DEFAULT_SCREENER_LIMIT: usize = 50;
+pub const MAX_SCREENER_LIMIT: usize = 500;
+// Added to each enhanced row before filtering
+pub const DERIVED_FIELDS: [&str; 3] = ["volume_avg20", "money_flow", "money_flow_percentile"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+impl Comparison {
+    // Two-character operators first, so ">=" isn't read as ">" followed by "=0"
+    const OPERATORS: [(&'static str, Comparison); 4] = [
+        (">=", Comparison::GreaterOrEqual),
+        ("<=", Comparison::LessOrEqual),
+        (">", Comparison::Greater),
+        ("<", Comparison::Less),
+    ];
+
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Greater => ordering == Ordering::Greater,
+            Comparison::GreaterOrEqual => ordering != Ordering::Less,
+            Comparison::Less => ordering == Ordering::Less,
+            Comparison::LessOrEqual => ordering != Ordering::Greater,
+        }
+    }
+}
+
+// Right-hand side of a filter: a constant or another field of the same row
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operand {
+    Value(f64),
+    Field(String),
+}
+
+/// One screener condition such as `ma20_score>0`, `close>ma50` or `volume>volume_avg20`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Filter {
+    pub field: String,
+    pub comparison: Comparison,
+    pub operand: Operand,
+}
+
+/// Whether `name` can be screened on: a numeric enhanced row field or a derived field
+pub fn screenable_field(name: &str) -> bool {
+    DERIVED_FIELDS.contains(&name)
+        || ENHANCED_ROW_FIELDS.iter().any(|(field, field_type)| {
+            *field == name && matches!(field_type, FieldType::Number | FieldType::NullableNumber | FieldType::Integer | FieldType::NullableInteger)
+        })
+}
+
+fn field_name(name: &str) -> Result<String, String> {
+    let name = name.trim().to_lowercase();
+    if screenable_field(&name) {
+        Ok(name)
+    } else {
+        Err(format!("Unknown screener field '{}'", name))
+    }
+}
+
+impl std::str::FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (field, comparison, rest) = Comparison::OPERATORS.iter()
+            .find_map(|(operator, comparison)| s.split_once(operator).map(|(field, rest)| (field, *comparison, rest)))
+            .ok_or_else(|| format!("Invalid filter '{}'. Expected <field><op><number or field> with op one of >, >=, <, <=", s.trim()))?;
+        let operand = match rest.trim().parse::<f64>() {
+            Ok(value) if value.is_finite() => Operand::Value(value),
+            _ => Operand::Field(field_name(rest)?),
+        };
+        Ok(Filter { field: field_name(field)?, comparison, operand })
+    }
+}
+
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let operator = Comparison::OPERATORS.iter().find(|(_, comparison)| *comparison == self.comparison).map_or("", |(operator, _)| operator);
+        match &self.operand {
+            Operand::Value(value) => write!(f, "{}{}{}", self.field, operator, value),
+            Operand::Field(field) => write!(f, "{}{}{}", self.field, operator, field),
+        }
+    }
+}
+
+fn number(row: &Map<String, Value>, field: &str) -> Option<f64> {
+    row.get(field).and_then(Value::as_f64)
+}
+
+impl Filter {
+    /// Whether the row passes; a null field on either side never does
+    pub fn matches(&self, row: &Map<String, Value>) -> bool {
+        let right = match &self.operand {
+            Operand::Value(value) => Some(*value),
+            Operand::Field(field) => number(row, field),
+        };
+        match (number(row, &self.field), right) {
+            (Some(left), Some(right)) => left.partial_cmp(&right).is_some_and(|ordering| self.comparison.holds(ordering)),
+            _ => false,
+        }
+    }
+}
+
+/// Add `volume_avg20` and `money_flow` (net signed dollar flow over the window) from the symbol's
+/// latest bars, oldest first, ending on the row's session. Both are null until the window is full.
+pub fn add_derived_fields(row: &mut Map<String, Value>, bars: &[OhlcvData]) {
+    let recent = &bars[bars.len().saturating_sub(SCREENER_WINDOW)..];
+    let volume_avg = (recent.len() == SCREENER_WINDOW).then(|| recent.iter().map(|bar| bar.volume as f64).sum::<f64>() / SCREENER_WINDOW as f64);
+    let money_flow = calculate_money_flow(bars, SCREENER_WINDOW, None).last().and_then(|point| point.net_flow);
+    row.insert("volume_avg20".to_string(), volume_avg.map_or(Value::Null, Value::from));
+    row.insert("money_flow".to_string(), money_flow.map_or(Value::Null, Value::from));
+}
+
+/// Set `money_flow_percentile` on every row: the share of other rows with a lower `money_flow`,
+/// 0..100. Ranked across the whole screened universe, before any filter is applied.
+pub fn add_money_flow_percentiles<'a>(rows: impl IntoIterator<Item = &'a mut Map<String, Value>>) {
+    let mut rows: Vec<&mut Map<String, Value>> = rows.into_iter().collect();
+    let flows: Vec<f64> = rows.iter().filter_map(|row| number(row, "money_flow")).collect();
+    for row in rows.iter_mut() {
+        let percentile = number(row, "money_flow").map(|flow| {
+            let below = flows.iter().filter(|other| **other < flow).count();
+            if flows.len() > 1 { below as f64 / (flows.len() - 1) as f64 * 100.0 } else { 100.0 }
+        });
+        row.insert("money_flow_percentile".to_string(), percentile.map_or(Value::Null, Value::from));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_bars;
+    use serde_json::json;
+
+    #[test]
+    fn test_filters_parse_and_match() {
+        let filter: Filter = "ma20_score >= 0".parse().unwrap();
+        assert_eq!((filter.field.as_str(), filter.comparison, &filter.operand), ("ma20_score", Comparison::GreaterOrEqual, &Operand::Value(0.0)));
+        assert_eq!(filter.to_string(), "ma20_score>=0");
+        let above_ma: Filter = "close>MA50".parse().unwrap();
+        assert_eq!(above_ma.operand, Operand::Field("ma50".to_string()));
+        assert!("symbol>0".parse::<Filter>().is_err());
+        assert!("close=ma50".parse::<Filter>().is_err());
+        assert!("close>nonsense".parse::<Filter>().is_err());
+
+        let mut rows: Vec<Map<String, Value>> = [
+            json!({ "close": 12.0, "ma50": 10.0, "ma20_score": 1.5, "money_flow": 300.0 }),
+            json!({ "close": 9.0, "ma50": 10.0, "ma20_score": -2.0, "money_flow": -50.0 }),
+            json!({ "close": 11.0, "ma50": null, "ma20_score": 0.0, "money_flow": 100.0 }),
+        ]
+        .into_iter()
+        .map(|row| row.as_object().cloned().unwrap())
+        .collect();
+        add_money_flow_percentiles(rows.iter_mut());
+        let percentiles: Vec<f64> = rows.iter().map(|row| number(row, "money_flow_percentile").unwrap()).collect();
+        assert_eq!(percentiles, vec![100.0, 0.0, 50.0]);
+
+        // A null MA never passes a comparison against it
+        let passing: Vec<bool> = rows.iter().map(|row| above_ma.matches(row) && filter.matches(row)).collect();
+        assert_eq!(passing, vec![true, false, false]);
+        assert!("money_flow_percentile>40".parse::<Filter>().unwrap().matches(&rows[2]));
+
+        let bars: Vec<OhlcvData> = (0..SCREENER_WINDOW + 1).map(|i| test_bars::bar(i as i64, 10.0 + i as f64, 100 * (i as u64 + 1))).collect();
+        let mut row = Map::new();
+        add_derived_fields(&mut row, &bars[1..]);
+        assert_eq!(number(&row, "volume_avg20"), Some(1150.0));
+        assert!(row["money_flow"].is_null());
+        add_derived_fields(&mut row, &bars);
+        assert_eq!(number(&row, "money_flow"), Some((1..=20).map(|i| (10.0 + i as f64) * 100.0 * (i as f64 + 1.0)).sum()));
+    }
+}
@@ -0,0 +1,157 @@
+use crate::analysis::ma_score::MaScorePoint;
+use crate::analysis::money_flow::{inflow_share, signed_dollar_flow};
+use crate::utils::market_time::market_date;
+use crate::vci::OhlcvData;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+// One sector (ticker group) on one session: money flow summed over its members, and breadth
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SectorPoint {
+    pub date: NaiveDate,
+    pub symbols: usize,                   // Members with a bar this session and the one before
+    pub inflow: f64,                      // Dollar flow of members that closed up
+    pub outflow: f64,                     // Dollar flow of members that closed down, as a positive amount
+    pub net_flow: f64,                    // inflow - outflow
+    pub inflow_share: Option<f64>,        // inflow / (inflow + outflow), 0..1; None when nothing moved
+    pub turnover_share: Option<f64>,      // Sector's share of the day's traded value across all sectors, 0..1
+    pub window_net_flow: Option<f64>,     // net_flow summed over the window; None until the window is full
+    pub window_inflow_share: Option<f64>, // Inflow share over the window
+    pub advancing: usize,
+    pub declining: usize,
+    pub above_ma: usize,                  // Members closing above the MA of the requested period
+    pub percent_above_ma: Option<f64>,    // Share of members with that MA that close above it, 0-100
+    pub mean_ma_score: Option<f64>,       // Equal-weighted mean MA score of those members
+    pub provisional: bool,                // Date is today's still-open session
+}
+
+/// A member's daily series with its MA scores, index-aligned
+pub struct MemberSeries<'a> {
+    pub series: &'a [OhlcvData],
+    pub scores: &'a [MaScorePoint],
+}
+
+// Running per-session sums while members are added
+#[derive(Default)]
+struct SessionTotals {
+    symbols: usize,
+    inflow: f64,
+    outflow: f64,
+    advancing: usize,
+    declining: usize,
+    with_ma: usize,
+    above_ma: usize,
+    score_sum: f64,
+}
+
+/// Daily money flow and breadth of one sector, in date order. A member's first session has no prior
+/// close and is left out. Window sums need `window` sessions; MA breadth uses `period`; members without that MA yet don't count towards it.
+pub fn aggregate_sector(members: &[MemberSeries], period: usize, window: usize, provisional_date: Option<NaiveDate>) -> Vec<SectorPoint> {
+    let mut totals: BTreeMap<NaiveDate, SessionTotals> = BTreeMap::new();
+    for member in members {
+        let flows = signed_dollar_flow(member.series);
+        for (i, (bar, flow)) in member.series.iter().zip(&flows).enumerate().skip(1) {
+            let session = totals.entry(market_date(bar.time)).or_default();
+            session.symbols += 1;
+            if *flow > 0.0 {
+                session.inflow += flow;
+                session.advancing += 1;
+            } else if *flow < 0.0 {
+                session.outflow -= flow;
+                session.declining += 1;
+            }
+            if let Some(score) = member.scores.get(i).and_then(|point| point.score(period)) {
+                session.with_ma += 1;
+                session.above_ma += usize::from(score > 0.0);
+                session.score_sum += score;
+            }
+        }
+    }
+
+    let mut points: Vec<SectorPoint> = totals.into_iter()
+        .map(|(date, session)| SectorPoint {
+            date,
+            symbols: session.symbols,
+            inflow: session.inflow,
+            outflow: session.outflow,
+            net_flow: session.inflow - session.outflow,
+            inflow_share: inflow_share(&[session.inflow, -session.outflow]),
+            advancing: session.advancing,
+            declining: session.declining,
+            above_ma: session.above_ma,
+            percent_above_ma: (session.with_ma > 0).then(|| session.above_ma as f64 / session.with_ma as f64 * 100.0),
+            mean_ma_score: (session.with_ma > 0).then(|| session.score_sum / session.with_ma as f64),
+            provisional: provisional_date == Some(date),
+            ..Default::default()
+        })
+        .collect();
+    let window = window.max(1);
+    for i in window - 1..points.len() {
+        let (inflow, outflow) = points[i + 1 - window..=i].iter().fold((0.0, 0.0), |(inflow, outflow), point| (inflow + point.inflow, outflow + point.outflow));
+        points[i].window_net_flow = Some(inflow - outflow);
+        points[i].window_inflow_share = inflow_share(&[inflow, -outflow]);
+    }
+    points
+}
+
+/// Each sector's share of every session's traded value (inflow plus outflow) across the given sectors,
+/// so money moving from one sector into another shows up even on days the whole market is flat
+pub fn fill_turnover_shares(sectors: &mut HashMap<String, Vec<SectorPoint>>) {
+    let mut market: HashMap<NaiveDate, f64> = HashMap::new();
+    for point in sectors.values().flatten() {
+        *market.entry(point.date).or_default() += point.inflow + point.outflow;
+    }
+    for point in sectors.values_mut().flatten() {
+        let total = market.get(&point.date).copied().unwrap_or_default();
+        point.turnover_share = (total > 0.0).then(|| (point.inflow + point.outflow) / total);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::ma_score::calculate_ma_scores;
+    use crate::utils::test_bars;
+
+    fn series(closes: &[f64], volume: u64) -> Vec<OhlcvData> {
+        closes.iter().enumerate().map(|(day, close)| test_bars::bar(day as i64, *close, volume)).collect()
+    }
+
+    #[test]
+    fn test_sector_flow_breadth_and_rotation() {
+        let rising: Vec<f64> = (1..=12).map(|v| v as f64).collect();
+        let falling: Vec<f64> = rising.iter().rev().cloned().collect();
+        let (up, down) = (series(&rising, 100), series(&falling, 10));
+        let (up_scores, down_scores) = (calculate_ma_scores(&up), calculate_ma_scores(&down));
+        let members = [MemberSeries { series: &up, scores: &up_scores }, MemberSeries { series: &down, scores: &down_scores }];
+        let last_date = market_date(up[11].time);
+
+        let points = aggregate_sector(&members, 10, 3, Some(last_date));
+        // The first session has no prior close for anyone
+        assert_eq!(points.len(), 11);
+        let last = &points[10];
+        assert_eq!((last.symbols, last.advancing, last.declining), (2, 1, 1));
+        assert_eq!((last.inflow, last.outflow, last.net_flow), (1200.0, 10.0, 1190.0));
+        assert_eq!(last.inflow_share, Some(1200.0 / 1210.0));
+        assert!(last.provisional && !points[9].provisional);
+        // MA10 exists from the tenth session: one member above it, one below
+        assert_eq!((points[7].percent_above_ma, points[8].percent_above_ma), (None, Some(50.0)));
+        assert_eq!(last.above_ma, 1);
+        assert!(points[1].window_net_flow.is_none());
+        let window: f64 = points[8..=10].iter().map(|p| p.net_flow).sum();
+        assert_eq!(last.window_net_flow, Some(window));
+
+        // A second sector that only trades early takes most of the turnover on those days
+        let flat = series(&[5.0, 6.0, 5.0], 1000);
+        let flat_scores = calculate_ma_scores(&flat);
+        let mut sectors = HashMap::new();
+        sectors.insert("A".to_string(), points);
+        sectors.insert("B".to_string(), aggregate_sector(&[MemberSeries { series: &flat, scores: &flat_scores }], 10, 3, None));
+        fill_turnover_shares(&mut sectors);
+        let first = (&sectors["A"][0], &sectors["B"][0]);
+        assert_eq!(first.0.inflow + first.0.outflow, 310.0);
+        assert_eq!(first.1.turnover_share, Some(6000.0 / 6310.0));
+        assert_eq!(sectors["A"][5].turnover_share, Some(1.0));
+    }
+}
@@ -0,0 +1,175 @@
+use crate::analysis::ma_score::MaScorePoint;
+use crate::analysis::money_flow::{inflow_share, signed_dollar_flow};
+use crate::analysis::warmup::{self, WarmupMode};
+use crate::vci::OhlcvData;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+// Sessions used for the money flow share and the relative volume baseline
+pub const STRENGTH_WINDOW: usize = 20;
+// MA scores of -10%..+10% map linearly onto 0..1
+const MA_SCORE_SPAN: f64 = 10.0;
+// Relative volume of 2x the average or more counts as full strength
+const RELATIVE_VOLUME_CAP: f64 = 2.0;
+// Bumped when the components or how they combine change; weights and the warm-up mode are reported separately
+pub const ALGORITHM_VERSION: u32 = 2;
+
+// Relative weight of each component in the strength score. Weights need not sum to 1;
+// the score is the weighted mean of the components available on a date.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StrengthWeights {
+    pub money_flow: f64,
+    pub ma_score: f64,
+    pub relative_volume: f64,
+    pub trend: f64,
+}
+
+impl Default for StrengthWeights {
+    fn default() -> Self {
+        Self { money_flow: 0.3, ma_score: 0.3, relative_volume: 0.2, trend: 0.2 }
+    }
+}
+
+static WEIGHTS: OnceLock<StrengthWeights> = OnceLock::new();
+
+/// Set the component weights used by every strength score; call once at startup
+pub fn set_weights(weights: StrengthWeights) {
+    if WEIGHTS.set(weights.clone()).is_err() {
+        warn!("Strength weights already set, ignoring new settings");
+    } else {
+        info!(?weights, "Configured strength score weights");
+    }
+}
+
+pub fn weights() -> &'static StrengthWeights {
+    WEIGHTS.get_or_init(StrengthWeights::default)
+}
+
+// Each component normalized to 0..1, None until enough history exists
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct StrengthComponents {
+    pub money_flow: Option<f64>,      // Inflow share of signed dollar flow over the window
+    pub ma_score: Option<f64>,        // Mean of the MA10/20/50 scores (of those available, in lenient warm-up)
+    pub relative_volume: Option<f64>, // Volume versus the prior window's average
+    pub trend: Option<f64>,           // Share of close > MA10 > MA20 > MA50 that holds
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct StrengthPoint {
+    pub date: NaiveDate,
+    pub strength: Option<f64>, // 0..100
+    pub components: StrengthComponents,
+}
+
+fn clamp_unit(value: f64) -> f64 {
+    value.clamp(0.0, 1.0)
+}
+
+fn ma_component(score: &MaScorePoint, mode: WarmupMode) -> Option<f64> {
+    let scores: Vec<f64> = [score.ma10_score, score.ma20_score, score.ma50_score].into_iter().flatten().collect();
+    if scores.is_empty() || (mode == WarmupMode::Strict && scores.len() < 3) {
+        return None;
+    }
+    let mean = scores.iter().sum::<f64>() / scores.len() as f64;
+    Some(clamp_unit((mean + MA_SCORE_SPAN) / (2.0 * MA_SCORE_SPAN)))
+}
+
+fn trend_component(score: &MaScorePoint) -> Option<f64> {
+    let (ma10, ma20, ma50) = (score.ma10?, score.ma20?, score.ma50?);
+    let aligned = [score.close > ma10, ma10 > ma20, ma20 > ma50].iter().filter(|holds| **holds).count();
+    Some(aligned as f64 / 3.0)
+}
+
+/// Weighted mean of the available components, scaled to 0..100. In strict warm-up every weighted
+/// component must be available.
+pub fn combine(components: &StrengthComponents, weights: &StrengthWeights, mode: WarmupMode) -> Option<f64> {
+    let weighted = [
+        (components.money_flow, weights.money_flow),
+        (components.ma_score, weights.ma_score),
+        (components.relative_volume, weights.relative_volume),
+        (components.trend, weights.trend),
+    ];
+    if mode == WarmupMode::Strict && weighted.iter().any(|(value, weight)| value.is_none() && *weight > 0.0) {
+        return None;
+    }
+    let (sum, total_weight) = weighted.iter()
+        .filter_map(|(value, weight)| value.filter(|_| *weight > 0.0).map(|value| (value * weight, *weight)))
+        .fold((0.0, 0.0), |(sum, total), (value, weight)| (sum + value, total + weight));
+    (total_weight > 0.0).then(|| sum / total_weight * 100.0)
+}
+
+/// Strength score per session of a time-sorted daily series.
+/// `scores` must be `calculate_ma_scores` over the same series.
+pub fn calculate_strength(series: &[OhlcvData], scores: &[MaScorePoint], weights: &StrengthWeights) -> Vec<StrengthPoint> {
+    update_strength(series, scores, weights, &[], 0)
+}
+
+/// `calculate_strength` for a series whose bars before `first_changed` are the ones `previous`
+/// was calculated from, with the same weights. Only the later sessions are recalculated.
+pub fn update_strength(series: &[OhlcvData], scores: &[MaScorePoint], weights: &StrengthWeights, previous: &[StrengthPoint], first_changed: usize) -> Vec<StrengthPoint> {
+    let keep = first_changed.min(previous.len()).min(series.len());
+    // Flows of the window before the first recalculated session; the flow at `base` itself is never used
+    let base = keep.saturating_sub(STRENGTH_WINDOW);
+    let flows = signed_dollar_flow(&series[base..]);
+    let mode = warmup::mode();
+
+    let mut points = previous[..keep].to_vec();
+    points.extend(series.iter().zip(scores).enumerate().skip(keep).map(|(i, (bar, score))| {
+        let window_start = (i + 1).saturating_sub(STRENGTH_WINDOW).max(1);
+        let relative_volume = (i >= STRENGTH_WINDOW).then(|| {
+            series[i - STRENGTH_WINDOW..i].iter().map(|b| b.volume as f64).sum::<f64>() / STRENGTH_WINDOW as f64
+        })
+        .filter(|average| *average > 0.0)
+        .map(|average| clamp_unit(bar.volume as f64 / average / RELATIVE_VOLUME_CAP));
+
+        // The first session has no flow, so a full window of flows ends on session STRENGTH_WINDOW
+        let flows_warm = i >= STRENGTH_WINDOW || (mode == WarmupMode::Lenient && i > 0);
+        let components = StrengthComponents {
+            money_flow: if flows_warm { inflow_share(&flows[window_start - base..=i - base]) } else { None },
+            ma_score: ma_component(score, mode),
+            relative_volume,
+            trend: trend_component(score),
+        };
+        StrengthPoint { date: score.date, strength: combine(&components, weights, mode), components }
+    }));
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::ma_score::calculate_ma_scores;
+    use crate::utils::test_bars;
+
+    #[test]
+    fn test_components_and_weighting() {
+        // Steady uptrend on constant volume, then a double-volume up day
+        let mut series = test_bars::series(&(0..60).map(|day| 100.0 + day as f64).collect::<Vec<_>>());
+        series[59].volume = 2000;
+        let points = calculate_strength(&series, &calculate_ma_scores(&series), &StrengthWeights::default());
+
+        assert_eq!(points[0].components, StrengthComponents { ma_score: None, ..Default::default() });
+        assert_eq!(points[0].strength, None);
+        assert_eq!(points[5].components.money_flow, None);
+        assert!(points[5].components.trend.is_none() && points[5].components.relative_volume.is_none());
+        // Strict warm-up: the flow window fills on session 20, MA50 (and so the score) on session 49
+        assert_eq!(points[STRENGTH_WINDOW].components.money_flow, Some(1.0));
+        assert_eq!((points[48].components.ma_score, points[48].strength), (None, None));
+        assert!(points[49].strength.is_some());
+
+        let last = &points[59].components;
+        assert_eq!((last.money_flow, last.relative_volume, last.trend), (Some(1.0), Some(1.0), Some(1.0)));
+        assert!(last.ma_score.unwrap() > 0.5);
+
+        // In lenient warm-up only weighted, available components count; strict needs every weighted one
+        let partial = StrengthComponents { money_flow: Some(0.5), trend: Some(1.0), ..Default::default() };
+        let weights = StrengthWeights { money_flow: 1.0, ma_score: 1.0, relative_volume: 1.0, trend: 0.0 };
+        assert_eq!(combine(&partial, &weights, WarmupMode::Lenient), Some(50.0));
+        assert_eq!(combine(&partial, &weights, WarmupMode::Strict), None);
+        let only_flow = StrengthWeights { money_flow: 1.0, ma_score: 0.0, relative_volume: 0.0, trend: 0.0 };
+        assert_eq!(combine(&partial, &only_flow, WarmupMode::Strict), Some(50.0));
+        assert_eq!(combine(&StrengthComponents::default(), &weights, WarmupMode::Lenient), None);
+    }
+}
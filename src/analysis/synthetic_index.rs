@@ -0,0 +1,123 @@
+use crate::analysis::market_cap::MarketCaps;
+use crate::constituents::IndexConstituents;
+use crate::data_structures::InMemoryData;
+use crate::utils::market_time::{market_date, market_day_start};
+use crate::vci::OhlcvData;
+use chrono::NaiveDate;
+use std::collections::BTreeSet;
+
+/// Level of a synthetic index on its first session
+pub const BASE_LEVEL: f64 = 1000.0;
+
+fn bar_on(series: &[OhlcvData], date: NaiveDate) -> Option<&OhlcvData> {
+    let i = series.partition_point(|bar| market_date(bar.time) < date);
+    series.get(i).filter(|bar| market_date(bar.time) == date)
+}
+
+// Close of the last session before `date`, else the first one
+fn close_before(series: &[OhlcvData], date: NaiveDate) -> Option<f64> {
+    let i = series.partition_point(|bar| market_date(bar.time) < date);
+    let bar = if i > 0 { series.get(i - 1) } else { series.first() };
+    bar.map(|bar| bar.close).filter(|close| *close > 0.0)
+}
+
+/// Cap-weighted daily series of `index_name` built from its constituents' bars, with membership
+/// taken point in time on each session. Members are weighted by market cap at the previous close;
+/// when a membership snapshot publishes a weight for every member, those weights are used instead,
+/// drifted with each member's price since the close before the snapshot took effect. Each session
+/// moves the level by the weighted return of the members that traded on it and on the previous
+/// session, so joins, leaves and suspensions never make the level jump. Sessions no member can be
+/// weighted on are left out.
+pub fn build(index_name: &str, constituents: &IndexConstituents, data: &InMemoryData, caps: &MarketCaps) -> Vec<OhlcvData> {
+    let Some(snapshots) = constituents.0.get(&index_name.to_uppercase()) else { return Vec::new() };
+    let Some(first_effective) = snapshots.iter().map(|snapshot| snapshot.effective_date).min() else { return Vec::new() };
+    let members: BTreeSet<&str> = snapshots.iter().flat_map(|snapshot| snapshot.constituents.iter().map(|c| c.symbol.as_str())).collect();
+    let sessions: BTreeSet<NaiveDate> = members.iter()
+        .filter_map(|symbol| data.get(*symbol))
+        .flat_map(|series| series.iter().map(|bar| market_date(bar.time)))
+        .filter(|date| *date >= first_effective)
+        .collect();
+
+    let mut index: Vec<OhlcvData> = Vec::new();
+    let mut previous: Option<NaiveDate> = None;
+    for date in sessions {
+        let Some(snapshot) = constituents.membership_at(index_name, date) else { continue };
+        let published = snapshot.constituents.iter().all(|c| c.weight.is_some_and(|weight| weight > 0.0));
+        // Weighted open, high, low and close returns over the previous close
+        let mut returns = [0.0; 4];
+        let mut total_weight = 0.0;
+        let mut volume = 0;
+        for constituent in &snapshot.constituents {
+            let Some(series) = data.get(&constituent.symbol) else { continue };
+            let Some(bar) = bar_on(series, date) else { continue };
+            volume += bar.volume;
+            let Some(previous_close) = previous.and_then(|previous| bar_on(series, previous)).map(|bar| bar.close).filter(|close| *close > 0.0) else { continue };
+            let weight = if published {
+                constituent.weight.zip(close_before(series, snapshot.effective_date)).map(|(weight, base)| weight * previous_close / base)
+            } else {
+                caps.market_cap(&constituent.symbol, Some(previous_close))
+            };
+            let Some(weight) = weight.filter(|weight| weight.is_finite() && *weight > 0.0) else { continue };
+            total_weight += weight;
+            for (sum, price) in returns.iter_mut().zip([bar.open, bar.high, bar.low, bar.close]) {
+                *sum += weight * (price / previous_close - 1.0);
+            }
+        }
+        previous = Some(date);
+
+        let [open, high, low, close] = match index.last() {
+            None => [BASE_LEVEL; 4],
+            Some(_) if total_weight <= 0.0 => continue,
+            Some(last) => returns.map(|sum| last.close * (1.0 + sum / total_weight)),
+        };
+        index.push(OhlcvData { time: market_day_start(date), open, high, low, close, volume, symbol: Some(index_name.to_uppercase()) });
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constituents::{Constituent, MembershipSnapshot};
+    use crate::ticker_info::TickerDirectory;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_cap_weighted_point_in_time_index() {
+        let date = |day: u32| NaiveDate::from_ymd_opt(2025, 8, day).unwrap();
+        let bar = |day: u32, close: f64| OhlcvData { time: market_day_start(date(day)), open: close, high: close * 1.1, low: close * 0.9, close, volume: 10, symbol: None };
+        let data: InMemoryData = HashMap::from([
+            ("AAA".to_string(), vec![bar(4, 10.0), bar(5, 11.0), bar(6, 11.0), bar(7, 11.0)]),
+            ("BBB".to_string(), vec![bar(4, 20.0), bar(5, 20.0), bar(6, 18.0), bar(7, 18.0)]),
+            ("CCC".to_string(), vec![bar(1, 5.0), bar(6, 5.0), bar(7, 6.0)]),
+        ]);
+        let member = |symbol: &str, weight: Option<f64>| Constituent { symbol: symbol.to_string(), weight };
+        let constituents = IndexConstituents(HashMap::from([("SYN".to_string(), vec![
+            MembershipSnapshot { effective_date: date(4), constituents: vec![member("AAA", None), member("BBB", None)] },
+            // CCC replaces BBB on the 7th, with published weights
+            MembershipSnapshot { effective_date: date(7), constituents: vec![member("AAA", Some(50.0)), member("CCC", Some(50.0))] },
+        ])]));
+        let directory = TickerDirectory::parse(r#"[
+            {"symbol": "AAA", "outstanding_shares": 100.0},
+            {"symbol": "BBB", "outstanding_shares": 50.0}
+        ]"#).unwrap();
+        let index = build("syn", &constituents, &data, &MarketCaps::from_directory(&directory));
+
+        // CCC's bar on the 1st predates the index; the 6th is before CCC joined
+        assert_eq!(index.iter().map(|bar| market_date(bar.time)).collect::<Vec<_>>(), vec![date(4), date(5), date(6), date(7)]);
+        assert_eq!(index[0].close, BASE_LEVEL);
+        assert_eq!(index[0].volume, 20);
+        // Equal caps of 1000 on the 4th: +10% and flat average to +5%
+        assert!((index[1].close - 1050.0).abs() < 1e-9);
+        assert!((index[1].high - 1050.0 * 1.1).abs() < 1e-9);
+        // Caps of 1100 and 1000 at the previous close: (1100·0 + 1000·-10%) / 2100
+        assert!((index[2].close - 1050.0 * (1.0 - 100.0 / 2100.0)).abs() < 1e-9);
+        // Published 50/50 as of the close before the 7th, so AAA flat and CCC +20% average to +10%
+        assert!((index[3].close - index[2].close * 1.1).abs() < 1e-9);
+        assert_eq!(index[3].symbol.as_deref(), Some("SYN"));
+
+        assert!(build("VN30", &constituents, &data, &MarketCaps::default()).is_empty());
+        // Without market caps only the base session and those with published weights are built
+        assert_eq!(build("SYN", &constituents, &data, &MarketCaps::default()).len(), 2);
+    }
+}
@@ -0,0 +1,65 @@
+use crate::utils::market_time::market_date;
+use crate::vci::OhlcvData;
+use chrono::NaiveDate;
+
+/// Sessions in the rolling VWAP of enhanced rows
+pub const VWAP_PERIOD: usize = 20;
+
+/// (high + low + close) / 3, the price a daily bar's volume is assumed to have traded at
+pub fn typical_price(bar: &OhlcvData) -> f64 {
+    (bar.high + bar.low + bar.close) / 3.0
+}
+
+/// Volume-weighted typical price of the `period` sessions ending at `index`. None until the series
+/// is long enough, and when the window traded nothing.
+pub fn rolling_vwap(series: &[OhlcvData], index: usize, period: usize) -> Option<f64> {
+    if period == 0 || index + 1 < period || index >= series.len() {
+        return None;
+    }
+    let window = &series[index + 1 - period..=index];
+    let (value, volume) = window.iter().fold((0.0, 0.0), |(value, volume), bar| (value + typical_price(bar) * bar.volume as f64, volume + bar.volume as f64));
+    (volume > 0.0).then(|| value / volume)
+}
+
+/// VWAP anchored to `anchor`: the volume-weighted typical price of every session from the first one
+/// on or after `anchor` through each session. None before the anchor and until volume has traded.
+pub fn anchored_vwap<'a>(bars: impl IntoIterator<Item = &'a OhlcvData>, anchor: NaiveDate) -> Vec<Option<f64>> {
+    let (mut value, mut volume) = (0.0, 0.0);
+    bars.into_iter()
+        .map(|bar| {
+            if market_date(bar.time) < anchor {
+                return None;
+            }
+            value += typical_price(bar) * bar.volume as f64;
+            volume += bar.volume as f64;
+            (volume > 0.0).then(|| value / volume)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::market_time::market_day_start;
+
+    #[test]
+    fn test_rolling_and_anchored_vwap() {
+        let date = |day: u32| NaiveDate::from_ymd_opt(2025, 8, day).unwrap();
+        let bar = |day: u32, high: f64, low: f64, close: f64, volume: u64| OhlcvData { time: market_day_start(date(day)), open: close, high, low, close, volume, symbol: None };
+        // Typical prices 10, 20, 30 and 40
+        let series = vec![bar(4, 12.0, 8.0, 10.0, 100), bar(5, 22.0, 18.0, 20.0, 300), bar(6, 33.0, 27.0, 30.0, 0), bar(7, 41.0, 39.0, 40.0, 100)];
+
+        assert_eq!(typical_price(&series[1]), 20.0);
+        assert_eq!(rolling_vwap(&series, 0, 2), None);
+        assert_eq!(rolling_vwap(&series, 1, 2), Some((10.0 * 100.0 + 20.0 * 300.0) / 400.0));
+        assert_eq!(rolling_vwap(&series, 3, 2), Some(40.0));
+        assert_eq!(rolling_vwap(&series[..3], 2, 1), None); // No volume traded
+        assert_eq!(rolling_vwap(&series, 4, 2), None);
+
+        // Sessions before the anchor are None; one without volume keeps the average
+        let anchored = anchored_vwap(&series, date(5));
+        assert_eq!(anchored, vec![None, Some(20.0), Some(20.0), Some((20.0 * 300.0 + 40.0 * 100.0) / 400.0)]);
+        assert_eq!(anchored_vwap(&series, date(6)), vec![None, None, None, Some(40.0)]);
+        assert!(anchored_vwap(&series, date(8)).iter().all(Option::is_none));
+    }
+}
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+/// What indicators report for sessions before their full window of history is available
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarmupMode {
+    #[default]
+    Strict,  // null until every window the value depends on is full
+    Lenient, // computed from the part of the window available; run counts start at zero
+}
+
+impl FromStr for WarmupMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "strict" => Ok(WarmupMode::Strict),
+            "lenient" => Ok(WarmupMode::Lenient),
+            other => Err(format!("Invalid warm-up mode '{}'. Expected strict or lenient", other)),
+        }
+    }
+}
+
+impl WarmupMode {
+    /// A run of sessions closing above or below an MA; strict mode has no count before the MA exists
+    pub fn run_count(self, count: u32, ma: Option<f64>) -> Option<u32> {
+        match self {
+            WarmupMode::Strict if ma.is_none() => None,
+            _ => Some(count),
+        }
+    }
+}
+
+static MODE: OnceLock<WarmupMode> = OnceLock::new();
+
+/// Set the warm-up mode every indicator is computed with; call once at startup. Strict by default.
+pub fn set_mode(mode: WarmupMode) {
+    if MODE.set(mode).is_err() {
+        warn!("Warm-up mode already set, ignoring new settings");
+    } else {
+        info!(?mode, "Configured indicator warm-up mode");
+    }
+}
+
+pub fn mode() -> WarmupMode {
+    MODE.get().copied().unwrap_or_default()
+}
@@ -1,18 +1,60 @@
-use crate::config::SharedTokenConfig;
-use crate::data_structures::{LastInternalUpdate, SharedData, SharedReputation, SharedTickerGroups, SharedHealthStats};
-use crate::vci::OhlcvData;
+use crate::company::SharedCompanyService;
+use crate::quote::{self, SharedQuoteService};
+use crate::build_info;
+use crate::auth::{self, SharedTokenRegistry, TokenRole};
+use crate::constituents::SharedIndexConstituents;
+use crate::error::{ApiError, ErrorCode};
+use crate::events::{self, SharedEventBus, SymbolFilter, TickerUpdate, UpdateSource};
+use crate::schema;
+use crate::gossip;
+use crate::wire;
+use crate::ticker_info::{self, SharedTickerDirectory};
+use crate::export::{self, CsvColumn, CsvLayout, SharedEnhancedSnapshots, TableFormat};
+use crate::foreign_flow;
+use crate::intraday::{Interval, SharedIntradayData};
+use crate::analysis::{basis, coverage, gaps, indicator_cache, leaderboard, liquidity, ma_score, money_flow, screener, sector, strength, vwap, warmup};
+use crate::analysis::market_cap::{MarketCapSources, Weighting};
+use crate::data_structures::{ActorMetadata, ActorStatus, ActorSummary, InMemoryData, LastInternalUpdate, SymbolMetadata, SharedData, SharedReputation, SharedGossipStaging, StagedContribution, MAX_STAGED_PER_SYMBOL, SharedTickerGroups, TickerGroups, SharedHealthStats, SharedAnalysisCache, SharedOfficeHoursConfig, ANALYSIS_CACHE_TTL_SECS, get_provisional_date, insert_analysis};
+use crate::vci::{OhlcvData, VciError};
 use crate::utils::cache;
+use crate::utils::change_log;
+use crate::utils::corrections::{self, Correction, CorrectionSource};
+use crate::utils::freshness;
+use crate::utils::gossip_dedup;
+use crate::utils::http_range::{self, RangeRequest};
+use crate::utils::json_stream;
+use crate::utils::integrity;
+use crate::utils::market_time;
+use crate::utils::merge_policy::{self, BarOrigin};
+use crate::utils::metrics::{self, Timer};
+use crate::utils::precision::{self, PrecisionMode};
+use crate::utils::provider_quota;
+use crate::utils::query_cost::{self, CostRejection, QueryCost};
+use crate::utils::recalculation;
+use crate::utils::symbol_stats;
+use crate::utils::transitions;
+use crate::utils::mirrors::{MirrorFetchError, SharedRawMirrors};
+use crate::utils::object_store::SharedObjectStore;
 use axum::{
+    body::Body,
     extract::{ConnectInfo, State, Json, Path},
-    http::{HeaderMap, StatusCode, header::CACHE_CONTROL},
-    response::{IntoResponse, Response},
+    http::{HeaderMap, HeaderValue, StatusCode, Uri, header::{ACCEPT, ACCEPT_RANGES, CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, LAST_MODIFIED, RANGE, VARY}},
+    response::{IntoResponse, Response, sse::{Event, KeepAlive, Sse}},
 };
 use axum_extra::extract::Query;
-use serde::Deserialize;
-use std::net::SocketAddr;
-use std::time::Duration;
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::CancellationToken;
 use tracing::{info, debug, warn, error, instrument};
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
 
 // Define the struct to hold the query parameters.
 // `symbol` will hold all values passed for the "symbol" key.
@@ -22,25 +64,67 @@ pub struct TickerParams {
     start_date: Option<String>,
     end_date: Option<String>,
     all: Option<bool>,
+    format: Option<String>,       // "json" (default), "csv", "parquet" or "arrow"
+    columns: Option<Vec<String>>, // CSV, Parquet and Arrow: columns to include, in order
+    header: Option<bool>,         // CSV only: emit the header row (default true)
+    enhanced: Option<bool>,       // JSON only: add MA indicators and strength, with snapshot metadata
+    vwap_anchor: Option<String>,  // Enhanced JSON only: add the VWAP anchored to this date (YYYY-MM-DD)
+    precision: Option<String>,    // "full" skips rounding
+    interval: Option<String>,     // "1D" (default), or an intraday interval this node collects: "1m", "15m", "1H"
+    limit: Option<usize>,         // Symbols per page, in symbol order
+    cursor: Option<String>,       // Start after this symbol, the previous page's X-Next-Cursor
 }
 
-#[instrument(skip(state))]
+// Shared by endpoints that only take the precision toggle
+#[derive(Debug, Deserialize)]
+pub struct PrecisionParams {
+    precision: Option<String>,
+}
+
+fn parse_precision(value: Option<&str>) -> Result<PrecisionMode, String> {
+    value.map(str::parse).transpose().map(Option::unwrap_or_default)
+}
+
+// Fallback Retry-After when the worker interval is not known yet
+const DEFAULT_RETRY_AFTER_SECS: u64 = 30;
+
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip(state, health_state, ticker_state, snapshots_state, intraday_state, token_state, request_headers))]
 pub async fn get_all_tickers_handler(
     State(state): State<SharedData>,
+    State(health_state): State<SharedHealthStats>,
+    State(ticker_state): State<SharedTickerDirectory>,
+    State(snapshots_state): State<SharedEnhancedSnapshots>,
+    State(intraday_state): State<SharedIntradayData>,
+    State(token_state): State<SharedTokenRegistry>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    uri: Uri,
+    request_headers: HeaderMap,
     Query(params): Query<TickerParams>
 ) -> impl IntoResponse {
     debug!("Received request for tickers with params: {:?}", params);
-    
-    let data = state.lock().await;
+    // Public nodes ask for binary bars; CSV and enhanced rows are JSON-only
+    let binary = wire::negotiate(request_headers.get(ACCEPT).and_then(|value| value.to_str().ok()));
+    let cost_key = query_cost_key(&token_state, &request_headers, addr).await;
+
+    let (initial_load_complete, interval_secs) = {
+        let health = health_state.lock().await;
+        (health.initial_load_complete, health.current_interval_secs)
+    };
+
+    let interval = match params.interval.as_deref().map(str::parse::<Interval>).transpose() {
+        Ok(interval) => interval.unwrap_or(Interval::OneDay),
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
     
     // Parse date filters
     let start_date_filter = match &params.start_date {
         Some(date_str) => {
             match NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-                Ok(date) => Some(date.and_hms_opt(0, 0, 0).unwrap().and_utc()),
+                Ok(date) => Some(market_time::market_day_start(date)),
                 Err(_) => {
                     warn!(start_date = %date_str, "Invalid start_date format, expected YYYY-MM-DD");
-                    return (StatusCode::BAD_REQUEST, Json("Invalid start_date format. Expected YYYY-MM-DD")).into_response();
+                    return ApiError::invalid("Invalid start_date format. Expected YYYY-MM-DD").into_response();
                 }
             }
         }
@@ -50,49 +134,271 @@ pub async fn get_all_tickers_handler(
     let end_date_filter = match &params.end_date {
         Some(date_str) => {
             match NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-                Ok(date) => Some(date.and_hms_opt(23, 59, 59).unwrap().and_utc()),
+                Ok(date) => Some(market_time::market_day_end(date)),
                 Err(_) => {
                     warn!(end_date = %date_str, "Invalid end_date format, expected YYYY-MM-DD");
-                    return (StatusCode::BAD_REQUEST, Json("Invalid end_date format. Expected YYYY-MM-DD")).into_response();
+                    return ApiError::invalid("Invalid end_date format. Expected YYYY-MM-DD").into_response();
                 }
             }
         }
         None => None,
     };
 
+    let precision_mode = match parse_precision(params.precision.as_deref()) {
+        Ok(mode) => mode,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+
+    let vwap_anchor = match params.vwap_anchor.as_deref().map(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d")).transpose() {
+        Ok(anchor) => anchor,
+        Err(_) => return ApiError::invalid("Invalid vwap_anchor format. Expected YYYY-MM-DD").into_response(),
+    };
+    if vwap_anchor.is_some() && (params.format.as_deref().is_some_and(|format| format != "json") || !params.enhanced.unwrap_or(false)) {
+        return ApiError::invalid("vwap_anchor requires enhanced=true JSON").into_response();
+    }
+
+    // Tabular formats share the column selection; only CSV has a header row to toggle
+    let table = match params.format.as_deref() {
+        None | Some("json") => None,
+        Some(format) => {
+            let Ok(format) = format.parse::<TableFormat>() else {
+                warn!(format, "Unsupported format");
+                return ApiError::invalid("Invalid format. Expected json, csv, parquet or arrow").into_response();
+            };
+            match CsvLayout::from_params(params.columns.as_deref(), params.header) {
+                Ok(layout) => Some((format, layout)),
+                Err(e) => {
+                    warn!(error = %e, "Invalid columns");
+                    return ApiError::invalid(format!("Invalid columns parameter: {}", e)).into_response();
+                }
+            }
+        }
+    };
+
+    if params.limit == Some(0) {
+        return ApiError::invalid("limit must be at least 1").into_response();
+    }
+    let page = Page { limit: params.limit, cursor: params.cursor.map(|cursor| cursor.trim().to_uppercase()) };
+
     // If no date filters provided and all=true is not set, default to last day only
     let use_last_day_only = start_date_filter.is_none() && end_date_filter.is_none() && !params.all.unwrap_or(false);
-    
-    // Filter data by symbols first
-    let symbol_filtered_data = match params.symbol {
-        Some(symbols) if !symbols.is_empty() => {
-            // Filter data to only include requested symbols
-            let mut filtered = std::collections::HashMap::new();
-            for symbol in symbols {
-                if let Some(ticker_data) = data.get(&symbol) {
-                    filtered.insert(symbol, ticker_data.clone());
+
+    // Intraday candles are plain OHLCV from their own store, without CSV or enhanced indicators
+    if interval != Interval::OneDay {
+        if table.is_some() || params.enhanced.unwrap_or(false) {
+            return ApiError::invalid(format!("format=csv/parquet/arrow and enhanced=true are only available for 1D, not {}", interval.name())).into_response();
+        }
+        let intraday = intraday_state.lock().await;
+        let Some(interval_data) = intraday.get(&interval) else {
+            return ApiError::invalid(format!("Interval {} is not collected on this node", interval.name())).into_response();
+        };
+        let (symbol_filtered_data, next_cursor) = select_symbols(interval_data, params.symbol, &page);
+        drop(intraday);
+        let response = ticker_data_response(symbol_filtered_data, interval, use_last_day_only, start_date_filter, end_date_filter, precision_mode, binary, initial_load_complete, interval_secs, &cost_key, &request_headers);
+        return with_next_cursor(response, next_cursor);
+    }
+
+    let data = state.lock().await;
+
+    if table.is_some() || params.enhanced.unwrap_or(false) {
+        // Enhanced rows come from the last complete snapshot, whose indicators cover each full series;
+        // a stale snapshot keeps being served while its replacement builds. The data lock is only
+        // needed to get it, and rows are rendered straight from the shared snapshot.
+        let snapshot = export::current_snapshot(&snapshots_state, &state, &data).await;
+        drop(data);
+        let (symbols, next_cursor) = page_symbols(snapshot.series.keys(), params.symbol.as_deref(), &page);
+        let selection: Vec<(String, Range<usize>)> = symbols.into_iter()
+            .filter_map(|symbol| {
+                let series = &snapshot.series[&symbol];
+                let rows = if use_last_day_only {
+                    series.len().saturating_sub(1)..series.len()
+                } else {
+                    series.partition_point(|row| start_date_filter.is_some_and(|start| row.bar.time < start))
+                        ..series.partition_point(|row| end_date_filter.is_none_or(|end| row.bar.time <= end))
+                };
+                (!rows.is_empty()).then_some((symbol, rows))
+            })
+            .collect();
+        let total_rows: usize = selection.iter().map(|(_, rows)| rows.len()).sum();
+        let columns = table.as_ref().map_or(CsvColumn::ALL.len(), |(_, layout)| layout.columns.len());
+        if let Some(rejection) = reject_costly_query(&cost_key, QueryCost { symbols: selection.len(), rows: total_rows, columns }) {
+            return rejection;
+        }
+        let age_secs = snapshot.age_secs(Utc::now());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CACHE_CONTROL, "max-age=30".parse().unwrap());
+        headers.insert("x-data-ready", HeaderValue::from_static(if initial_load_complete { "true" } else { "false" }));
+        headers.insert("x-snapshot-version", HeaderValue::from(snapshot.version));
+        headers.insert("x-snapshot-built-at", snapshot.built_at.to_rfc3339().parse().unwrap());
+        headers.insert("refresh-after", HeaderValue::from(freshness::refresh_after_secs(Utc::now(), interval_secs)));
+        // Rows only change when a new snapshot is built, so its identity validates every query on it.
+        // A table export is byte-for-byte the same for one snapshot and query, so its tag is strong
+        // and `If-Range` can resume a download against it; JSON rows carry per-response fields.
+        let loading = if initial_load_complete { "" } else { "-loading" };
+        let etag = match &table {
+            Some(_) => {
+                let query_hash = integrity::sha256_hex(uri.query().unwrap_or_default().as_bytes());
+                format!("\"snapshot-{}-{:x}-{}{}\"", snapshot.version, snapshot.built_at.timestamp_millis(), &query_hash[..16], loading)
+            }
+            None => format!("W/\"snapshot-{}-{:x}{}\"", snapshot.version, snapshot.built_at.timestamp_millis(), loading),
+        };
+        if let Some(not_modified) = check_not_modified(&request_headers, &mut headers, &etag, Some(snapshot.built_at)) {
+            return with_next_cursor(not_modified, next_cursor);
+        }
+
+        if let Some((format, layout)) = table {
+            info!(symbol_count = selection.len(), total_rows, columns = layout.columns.len(), format = format.extension(), snapshot_version = snapshot.version, age_secs, "Returning ticker data as a table");
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(format.media_type()));
+            if format == TableFormat::Csv {
+                // Only the names of the exported symbols are copied out of the directory
+                let names: HashMap<String, String> = if layout.columns.contains(&CsvColumn::Name) {
+                    let directory = ticker_state.lock().await;
+                    selection.iter().map(|(symbol, _)| (symbol.clone(), directory.name_for(symbol).to_string())).collect()
+                } else {
+                    HashMap::new()
+                };
+                let body = export::stream_enhanced_csv(snapshot, selection, layout, names);
+                if request_headers.contains_key(RANGE) {
+                    // Slicing a range needs the whole body, so only resumed downloads are buffered
+                    let chunks: Vec<Result<String, Infallible>> = body.collect().await;
+                    let content: String = chunks.into_iter().flatten().collect();
+                    return with_next_cursor(ranged_response("/tickers", headers, &etag, content.into_bytes(), &request_headers), next_cursor);
                 }
+                headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+                return with_next_cursor((StatusCode::OK, headers, Body::from_stream(body)).into_response(), next_cursor);
             }
-            filtered
+            // Parquet and Arrow are written as one batch
+            let rows: BTreeMap<String, Vec<export::EnhancedRow>> = selection.into_iter()
+                .map(|(symbol, rows)| {
+                    let rows = snapshot.series[&symbol][rows].to_vec();
+                    (symbol, rows)
+                })
+                .collect();
+            return match export::encode_enhanced(&rows, &layout, &*ticker_state.lock().await, format) {
+                Ok(body) => with_next_cursor(ranged_response("/tickers", headers, &etag, body, &request_headers), next_cursor),
+                Err(e) => {
+                    error!(error = %e, format = format.extension(), "Failed to encode ticker data");
+                    ApiError::new(ErrorCode::Internal, "Failed to encode ticker data").into_response()
+                }
+            };
         }
-        _ => {
-            // Return all data if no symbols specified or empty vector
-            data.clone()
+
+        info!(symbol_count = selection.len(), total_rows, snapshot_version = snapshot.version, age_secs, "Returning enhanced ticker data");
+        let mut meta = serde_json::json!({
+            "snapshot_version": snapshot.version,
+            "built_at": snapshot.built_at,
+            "age_secs": age_secs,
+            "data_ready": initial_load_complete,
+        });
+        if let Some(anchor) = vwap_anchor {
+            meta["vwap_anchor"] = serde_json::json!(anchor);
         }
+        let now = Utc::now();
+        let body = json_stream::object(format!("{{\"meta\":{},\"data\":", meta), selection, "}", move |symbol, range| {
+            let series = &snapshot.series[symbol];
+            let mut rows: Vec<serde_json::Value> = series[range.clone()].iter().map(export::EnhancedRow::to_json).collect();
+            if let Some(anchor) = vwap_anchor {
+                // Accumulated from the anchor, which may come before the first returned row
+                let anchored = vwap::anchored_vwap(series[..range.end].iter().map(|row| &row.bar), anchor);
+                for (row, value) in rows.iter_mut().zip(&anchored[range.start..]) {
+                    row["anchored_vwap"] = value.map_or(serde_json::Value::Null, serde_json::Value::from);
+                }
+            }
+            let mut rows = precision::to_json(&rows, precision_mode);
+            if use_last_day_only {
+                add_quote_age(symbol, &mut rows, now);
+            }
+            rows
+        });
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        return with_next_cursor((StatusCode::OK, headers, Body::from_stream(body)).into_response(), next_cursor);
+    }
+
+    let (symbol_filtered_data, next_cursor) = select_symbols(&data, params.symbol, &page);
+    drop(data);
+
+    let response = ticker_data_response(symbol_filtered_data, interval, use_last_day_only, start_date_filter, end_date_filter, precision_mode, binary, initial_load_complete, interval_secs, &cost_key, &request_headers);
+    with_next_cursor(response, next_cursor)
+}
+
+/// `limit`/`cursor` paging of /tickers over symbols in order
+#[derive(Debug, Default)]
+struct Page {
+    limit: Option<usize>,
+    cursor: Option<String>,
+}
+
+/// The page of the requested symbols among `available` (all of them when no symbol was given), in
+/// symbol order, and the cursor of the next page when symbols remain after it
+fn page_symbols<'a>(available: impl Iterator<Item = &'a String>, symbols: Option<&[String]>, page: &Page) -> (Vec<String>, Option<String>) {
+    let mut selected: Vec<&String> = match symbols {
+        Some(symbols) if !symbols.is_empty() => available.filter(|symbol| symbols.contains(symbol)).collect(),
+        // Return all data if no symbols specified or empty vector
+        _ => available.collect(),
     };
+    selected.sort();
+    if let Some(cursor) = &page.cursor {
+        selected.retain(|symbol| symbol.as_str() > cursor.as_str());
+    }
+    let next_cursor = page.limit.filter(|limit| selected.len() > *limit).map(|limit| selected[limit - 1].clone());
+    selected.truncate(page.limit.unwrap_or(usize::MAX));
+    (selected.into_iter().cloned().collect(), next_cursor)
+}
 
+/// The page of the requested symbols' series, see `page_symbols`. Only the page's series are cloned.
+fn select_symbols(data: &InMemoryData, symbols: Option<Vec<String>>, page: &Page) -> (InMemoryData, Option<String>) {
+    let (selected, next_cursor) = page_symbols(data.keys(), symbols.as_deref(), page);
+    let page = selected.into_iter().map(|symbol| {
+        let series = data[&symbol].clone();
+        (symbol, series)
+    }).collect();
+    (page, next_cursor)
+}
+
+/// Point a successful /tickers page at the next one
+fn with_next_cursor(mut response: Response, next_cursor: Option<String>) -> Response {
+    let status = response.status();
+    if let Some(cursor) = next_cursor
+        && (status.is_success() || status == StatusCode::NOT_MODIFIED)
+        && let Ok(value) = HeaderValue::from_str(&cursor)
+    {
+        response.headers_mut().insert("x-next-cursor", value);
+    }
+    response
+}
+
+/// Plain OHLCV `/tickers` response: date filtering, the query cost check and freshness headers
+#[allow(clippy::too_many_arguments)]
+fn ticker_data_response(
+    symbol_filtered_data: InMemoryData,
+    interval: Interval,
+    use_last_day_only: bool,
+    start_date_filter: Option<chrono::DateTime<Utc>>,
+    end_date_filter: Option<chrono::DateTime<Utc>>,
+    precision_mode: PrecisionMode,
+    binary: Option<wire::Encoding>,
+    initial_load_complete: bool,
+    interval_secs: u64,
+    cost_key: &str,
+    request_headers: &HeaderMap,
+) -> Response {
     // Apply date filtering
     let mut date_filtered_data = std::collections::HashMap::new();
     for (symbol, ticker_data) in symbol_filtered_data {
-        let filtered_data: Vec<_> = if use_last_day_only {
+        let filtered_data: Vec<_> = if use_last_day_only && interval == Interval::OneDay {
             // Return only the most recent data point
             ticker_data.into_iter().rev().take(1).collect()
+        } else if use_last_day_only {
+            // Every intraday candle of the latest session
+            let latest_date = ticker_data.last().map(|bar| market_time::market_date(bar.time));
+            ticker_data.into_iter().filter(|bar| Some(market_time::market_date(bar.time)) == latest_date).collect()
         } else {
             // Filter by date range
             ticker_data.into_iter()
                 .filter(|ohlcv| {
-                    let time_matches_start = start_date_filter.map_or(true, |start| ohlcv.time >= start);
-                    let time_matches_end = end_date_filter.map_or(true, |end| ohlcv.time <= end);
+                    let time_matches_start = start_date_filter.is_none_or(|start| ohlcv.time >= start);
+                    let time_matches_end = end_date_filter.is_none_or(|end| ohlcv.time <= end);
                     time_matches_start && time_matches_end
                 })
                 .collect()
@@ -106,82 +412,419 @@ pub async fn get_all_tickers_handler(
     let symbol_count = date_filtered_data.len();
     let symbols: Vec<_> = date_filtered_data.keys().cloned().collect();
     let total_data_points: usize = date_filtered_data.values().map(|v| v.len()).sum();
+    if let Some(rejection) = reject_costly_query(cost_key, QueryCost { symbols: symbol_count, rows: total_data_points, columns: query_cost::OHLCV_FIELDS }) {
+        return rejection;
+    }
     
     if use_last_day_only {
-        info!(symbol_count, symbols = ?symbols, total_data_points, "Returning ticker data (last day only)");
-    } else if params.all.unwrap_or(false) && start_date_filter.is_none() && end_date_filter.is_none() {
-        info!(symbol_count, symbols = ?symbols, total_data_points, "Returning all ticker data (all=true)");
+        info!(symbol_count, symbols = ?symbols, total_data_points, interval = interval.name(), "Returning ticker data (last day only)");
+    } else if start_date_filter.is_none() && end_date_filter.is_none() {
+        info!(symbol_count, symbols = ?symbols, total_data_points, interval = interval.name(), "Returning all ticker data (all=true)");
     } else {
-        info!(symbol_count, symbols = ?symbols, total_data_points, start_date = ?params.start_date, end_date = ?params.end_date, "Returning ticker data with date filters");
+        info!(symbol_count, symbols = ?symbols, total_data_points, interval = interval.name(), start_date = ?start_date_filter, end_date = ?end_date_filter, "Returning ticker data with date filters");
     }
     
     let mut headers = HeaderMap::new();
     headers.insert(CACHE_CONTROL, "max-age=30".parse().unwrap());
-    (StatusCode::OK, headers, Json(date_filtered_data)).into_response()
+    // Lets clients tell a partially loaded node apart from missing history
+    headers.insert("x-data-ready", HeaderValue::from_static(if initial_load_complete { "true" } else { "false" }));
+    // When the next worker refresh should have landed, so pollers need not guess
+    headers.insert("refresh-after", HeaderValue::from(freshness::refresh_after_secs(Utc::now(), interval_secs)));
+    headers.insert(VARY, HeaderValue::from_static("accept"));
+    // Daily bars change when they are ingested; intraday candles are not tracked
+    let last_modified = if interval == Interval::OneDay {
+        date_filtered_data.keys().filter_map(|symbol| freshness::ingested_at(symbol)).max()
+    } else {
+        None
+    };
+    // Binary bars are always at full precision and without `age_ms`
+    if let Some(encoding) = binary {
+        return match wire::encode(&date_filtered_data, encoding) {
+            Ok(body) => {
+                if let Some(not_modified) = check_not_modified(request_headers, &mut headers, &http_range::compute_etag(&body), last_modified) {
+                    return not_modified;
+                }
+                metrics::increment_counter("tickers.binary_responses", 1);
+                headers.insert(CONTENT_TYPE, HeaderValue::from_static(encoding.media_type()));
+                (StatusCode::OK, headers, body).into_response()
+            }
+            Err(e) => {
+                error!(error = %e, "Failed to encode binary ticker data");
+                ApiError::new(ErrorCode::Internal, "Failed to encode ticker data").into_response()
+            }
+        };
+    }
+    // Streamed one symbol at a time in symbol order, so the tag hashes the bars rather than the text
+    let mut entries: Vec<(String, Vec<OhlcvData>)> = date_filtered_data.into_iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    if let Some(not_modified) = check_not_modified(request_headers, &mut headers, &bars_etag(&entries, precision_mode), last_modified) {
+        return not_modified;
+    }
+    let with_ages = use_last_day_only && interval == Interval::OneDay;
+    let now = Utc::now();
+    let body = json_stream::object(String::new(), entries, "", move |symbol, bars| {
+        let mut bars = precision::to_json(&bars, precision_mode);
+        if with_ages {
+            add_quote_age(symbol, &mut bars, now);
+        }
+        bars
+    });
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    (StatusCode::OK, headers, Body::from_stream(body)).into_response()
+}
+
+/// Weak validator of JSON bars, hashed from the bars so a streamed body needs no buffering.
+/// Weak because `age_ms` changes with every response. SHA-256 keeps the tag stable across builds.
+fn bars_etag(entries: &[(String, Vec<OhlcvData>)], precision_mode: PrecisionMode) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update([u8::from(precision_mode == PrecisionMode::Full)]);
+    for (symbol, bars) in entries {
+        hasher.update((symbol.len() as u64).to_le_bytes());
+        hasher.update(symbol.as_bytes());
+        for bar in bars {
+            hasher.update(bar.time.timestamp().to_le_bytes());
+            for price in [bar.open, bar.high, bar.low, bar.close] {
+                hasher.update(price.to_bits().to_le_bytes());
+            }
+            hasher.update(bar.volume.to_le_bytes());
+        }
+    }
+    let digest: String = hasher.finalize().iter().take(8).map(|b| format!("{:02x}", b)).collect();
+    format!("W/\"{}\"", digest)
+}
+
+/// Add the `ETag` and `Last-Modified` validators of a /tickers response to `headers`. Returns the
+/// 304 to send instead when the client's copy is still current.
+fn check_not_modified(request_headers: &HeaderMap, headers: &mut HeaderMap, etag: &str, last_modified: Option<DateTime<Utc>>) -> Option<Response> {
+    headers.insert(ETAG, etag.parse().unwrap());
+    if let Some(last_modified) = last_modified {
+        headers.insert(LAST_MODIFIED, http_range::http_date(last_modified).parse().unwrap());
+    }
+    let header = |name| request_headers.get(name).and_then(|value: &HeaderValue| value.to_str().ok());
+    if !http_range::not_modified(header(IF_NONE_MATCH), header(IF_MODIFIED_SINCE), etag, last_modified) {
+        return None;
+    }
+    debug!(etag, "Ticker data not modified");
+    metrics::increment_counter("tickers.not_modified", 1);
+    Some((StatusCode::NOT_MODIFIED, headers.clone()).into_response())
+}
+
+/// Add `age_ms` (time since ingestion) to the latest of `symbol`'s JSON bars.
+/// Bars only restored from disk have no known ingestion time and are left without it.
+fn add_quote_age(symbol: &str, bars: &mut serde_json::Value, now: DateTime<Utc>) {
+    if let Some(serde_json::Value::Object(bar)) = bars.as_array_mut().and_then(|bars| bars.last_mut())
+        && let Some(ingested_at) = freshness::ingested_at(symbol)
+    {
+        bar.insert("age_ms".to_string(), freshness::age_ms(ingested_at, now).into());
+    }
+}
+
+/// Who a /tickers query is charged to: the authenticated token account, or the client IP for anonymous
+/// callers. Clients behind one NAT or reverse proxy share an IP, so tokens give them budgets of their own.
+async fn query_cost_key(token_state: &SharedTokenRegistry, headers: &HeaderMap, addr: SocketAddr) -> String {
+    match token_state.lock().await.authenticate(auth::bearer_token(headers), TokenRole::Peer, Utc::now()) {
+        Ok(account) => format!("account:{}", account.name),
+        Err(_) => format!("ip:{}", addr.ip()),
+    }
+}
+
+/// Charge a /tickers response against the cost ceilings before it is serialized,
+/// returning the rejection to send when it is over a ceiling
+fn reject_costly_query(cost_key: &str, cost: QueryCost) -> Option<Response> {
+    let guidance = [
+        "Request fewer symbols with symbol=",
+        "Use start_date/end_date to narrow the range, or split it into several requests",
+        "Select fewer CSV columns with columns=",
+        "Download full history files from /raw/{path}",
+    ];
+    query_cost::check(cost_key, &cost).err().map(|rejection| {
+        metrics::increment_counter("query_cost.rejected", 1);
+        match rejection {
+            CostRejection::TooLarge { cells, limit } => {
+                ApiError::new(ErrorCode::QueryTooLarge, format!("Query would return {} cells, above the limit of {}", cells, limit))
+                    .with_details(serde_json::json!({ "cells": cells, "limit": limit, "cost": cost, "guidance": guidance }))
+            }
+            CostRejection::BudgetExceeded { used, limit, retry_after_secs } => {
+                ApiError::new(ErrorCode::RateLimited, format!("Query budget of {} cells per minute exhausted, retry in {}s", limit, retry_after_secs))
+                    .with_details(serde_json::json!({ "cells": cost.cells(), "used": used, "limit": limit, "cost": cost, "guidance": guidance }))
+                    .with_retry_after(retry_after_secs)
+            }
+        }
+        .into_response()
+    })
+}
+
+/// Client-chosen `Idempotency-Key` of a gossip request, if any
+fn idempotency_key(headers: &HeaderMap) -> Option<String> {
+    headers.get(gossip_dedup::IDEMPOTENCY_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(|key| format!("key:{}", key))
 }
 
 #[instrument(skip(data_state, token_state, last_update_state, headers), fields(symbol = %payload.symbol.as_deref().unwrap_or("unknown")))]
 pub async fn internal_gossip_handler(
     State(data_state): State<SharedData>,
-    State(token_state): State<SharedTokenConfig>,
+    State(token_state): State<SharedTokenRegistry>,
     State(last_update_state): State<LastInternalUpdate>,
+    State(event_bus): State<SharedEventBus>,
     headers: HeaderMap,
     Json(payload): Json<OhlcvData>,
 ) -> impl IntoResponse {
     debug!("Received internal gossip request");
+    metrics::increment_counter("gossip.internal_received", 1);
     
-    let auth_header = headers.get("Authorization").and_then(|h| h.to_str().ok());
-    let token_is_valid = match auth_header {
-        Some(header) => {
-            let token = header.trim_start_matches("Bearer ");
-            let is_primary = token == token_state.primary;
-            let is_secondary = token == token_state.secondary;
-            debug!(has_auth_header = true, is_primary, is_secondary, "Token validation");
-            is_primary || is_secondary
+    let auth_result = token_state.lock().await
+        .authenticate(auth::bearer_token(&headers), TokenRole::Peer, Utc::now())
+        .map(|account| account.name.clone());
+    let account = match auth_result {
+        Ok(account) => {
+            debug!(account, "Token validation");
+            account
         }
-        None => {
-            debug!(has_auth_header = false, "No authorization header provided");
-            false
+        Err(failure) => {
+            warn!(?failure, "Unauthorized internal gossip attempt");
+            return ApiError::from(failure).into_response();
         }
     };
 
-    if !token_is_valid {
-        warn!("Unauthorized internal gossip attempt");
-        return (StatusCode::UNAUTHORIZED, "Invalid or missing token").into_response();
-    }
-
     *last_update_state.lock().await = std::time::Instant::now();
     debug!("Updated last internal update timestamp");
 
+    // A retried request (same Idempotency-Key, or the same bar resent) is acknowledged without reapplying it
+    let request_key = idempotency_key(&headers).unwrap_or_else(|| gossip_dedup::bar_key(&payload));
+    if gossip_dedup::check_and_remember(&account, &request_key) {
+        gossip_dedup::record_duplicate("gossip.internal_duplicates", &account);
+        debug!(account, "Duplicate internal gossip request, skipping update");
+        return (StatusCode::OK, "OK").into_response();
+    }
+
+    // When the sending peer fetched the bar; bars from peers that don't say count as observed on receipt
+    let observed_at = headers.get(merge_policy::OBSERVED_AT_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+        .map_or_else(Utc::now, |at| at.with_timezone(&Utc));
+
     let mut data_guard = data_state.lock().await;
     if let Some(symbol) = &payload.symbol {
         let entry = data_guard.entry(symbol.clone()).or_default();
-        let should_update = entry.last().map_or(true, |last| payload.time > last.time);
-        
-        if should_update {
-            entry.push(payload.clone());
+        let date = market_time::market_date(payload.time);
+        // Gossip only updates the latest session or starts a new one, never history
+        let current = entry.last().is_none_or(|last| date >= market_time::market_date(last.time));
+        let admitted = current && !merge_policy::admit_bars(symbol, vec![payload.clone()], observed_at, |_| BarOrigin::Gossip).is_empty();
+        let stored = entry.iter().rposition(|bar| market_time::market_date(bar.time) == date);
+
+        if !admitted {
+            gossip_dedup::forget(&account, &request_key);
+            debug!(symbol, %date, "Received older or lower-precedence data, skipping update");
+        } else if stored.is_some_and(|index| entry[index] == payload) {
+            gossip_dedup::forget(&account, &request_key);
+            debug!(symbol, %date, "Received data already stored, skipping update");
+        } else {
+            match stored {
+                Some(index) => {
+                    corrections::record_bar(symbol, &entry[index], &payload, CorrectionSource::InternalGossip);
+                    entry[index] = payload.clone();
+                }
+                None => entry.push(payload.clone()),
+            }
             entry.sort_by_key(|d| d.time);
+            change_log::record(symbol, date);
+            events::publish(&event_bus, symbol, UpdateSource::InternalGossip, payload.clone());
+            metrics::increment_counter("gossip.internal_applied", 1);
             info!(symbol, close_price = payload.close, volume = payload.volume, "Updated symbol data from internal gossip");
-        } else {
-            debug!(symbol, "Received older data, skipping update");
         }
     } else {
+        gossip_dedup::forget(&account, &request_key);
         warn!("Received gossip payload without symbol");
     }
 
     (StatusCode::OK, "OK").into_response()
 }
 
-#[instrument(skip(data_state, reputation_state, last_update_state), fields(source_ip = %addr.ip(), symbol = %payload.symbol.as_deref().unwrap_or("unknown")))]
+/// Authenticate an admin request, returning the account name
+async fn require_admin(token_state: &SharedTokenRegistry, headers: &HeaderMap) -> Result<String, ApiError> {
+    token_state.lock().await
+        .authenticate(auth::bearer_token(headers), TokenRole::Admin, Utc::now())
+        .map(|account| account.name.clone())
+        .map_err(|failure| {
+            warn!(?failure, "Unauthorized admin request");
+            ApiError::from(failure)
+        })
+}
+
+#[instrument(skip(token_state, headers))]
+pub async fn admin_tokens_handler(
+    State(token_state): State<SharedTokenRegistry>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(error) = require_admin(&token_state, &headers).await {
+        return error.into_response();
+    }
+    let accounts = token_state.lock().await.summaries(Utc::now());
+    (StatusCode::OK, Json(serde_json::json!({ "accounts": accounts }))).into_response()
+}
+
+#[instrument(skip(token_state, headers))]
+pub async fn admin_revoke_token_handler(
+    State(token_state): State<SharedTokenRegistry>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let admin = match require_admin(&token_state, &headers).await {
+        Ok(admin) => admin,
+        Err(error) => return error.into_response(),
+    };
+    let revoked_at = token_state.lock().await.revoke(&name, Utc::now());
+    match revoked_at {
+        Some(revoked_at) => {
+            warn!(name, admin, "Token account revoked");
+            metrics::increment_counter("auth.revocations", 1);
+            (StatusCode::OK, Json(serde_json::json!({ "name": name, "revoked_at": revoked_at }))).into_response()
+        }
+        None => ApiError::new(ErrorCode::NotFound, "Unknown token account")
+            .with_details(serde_json::json!({ "name": name }))
+            .into_response(),
+    }
+}
+
+#[instrument(skip(token_state, headers))]
+pub async fn admin_restore_token_handler(
+    State(token_state): State<SharedTokenRegistry>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let admin = match require_admin(&token_state, &headers).await {
+        Ok(admin) => admin,
+        Err(error) => return error.into_response(),
+    };
+    if token_state.lock().await.restore(&name) {
+        info!(name, admin, "Token account revocation lifted");
+        (StatusCode::OK, Json(serde_json::json!({ "name": name, "revoked_at": null }))).into_response()
+    } else {
+        ApiError::new(ErrorCode::NotFound, "Unknown token account")
+            .with_details(serde_json::json!({ "name": name }))
+            .into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransitionsParams {
+    format: Option<String>, // "json" (default) or "jsonl" to download for a post-mortem
+}
+
+/// The node's recent state transitions, oldest first
+#[instrument(skip(token_state, headers))]
+pub async fn admin_transitions_handler(
+    State(token_state): State<SharedTokenRegistry>,
+    Query(params): Query<TransitionsParams>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(error) = require_admin(&token_state, &headers).await {
+        return error.into_response();
+    }
+    let history = transitions::export();
+    match params.format.as_deref() {
+        None | Some("json") => (StatusCode::OK, Json(serde_json::json!({ "transitions": history }))).into_response(),
+        Some("jsonl") => {
+            let body: String = history.iter()
+                .filter_map(|transition| serde_json::to_string(transition).ok())
+                .map(|line| line + "\n")
+                .collect();
+            let mut response_headers = HeaderMap::new();
+            response_headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/x-ndjson"));
+            response_headers.insert(CONTENT_DISPOSITION, HeaderValue::from_static("attachment; filename=\"transitions.jsonl\""));
+            (StatusCode::OK, response_headers, body).into_response()
+        }
+        Some(_) => ApiError::invalid("Invalid format. Expected json or jsonl").into_response(),
+    }
+}
+
+// Limits per `/admin/recalculate` request, so one call can't queue a full history refetch
+const MAX_RECALCULATE_DATES: usize = 31;
+const MAX_RECALCULATE_SYMBOLS: usize = 200;
+
+#[derive(Debug, Deserialize)]
+pub struct RecalculateRequest {
+    dates: Vec<NaiveDate>,
+    symbols: Vec<String>,
+}
+
+/// Invalidate the cached calculations for some dates of some symbols and queue a refetch of
+/// those dates. Progress is reported by `GET /admin/recalculate`.
+#[instrument(skip(token_state, data_state, cache_state, headers, request))]
+pub async fn admin_recalculate_handler(
+    State(token_state): State<SharedTokenRegistry>,
+    State(data_state): State<SharedData>,
+    State(cache_state): State<SharedAnalysisCache>,
+    headers: HeaderMap,
+    Json(request): Json<RecalculateRequest>,
+) -> impl IntoResponse {
+    let admin = match require_admin(&token_state, &headers).await {
+        Ok(admin) => admin,
+        Err(error) => return error.into_response(),
+    };
+    let mut symbols: Vec<String> = request.symbols.iter().map(|symbol| symbol.trim().to_uppercase()).filter(|symbol| !symbol.is_empty()).collect();
+    symbols.sort();
+    symbols.dedup();
+    if request.dates.is_empty() || symbols.is_empty() {
+        return ApiError::invalid("Both dates and symbols are required").into_response();
+    }
+    if request.dates.len() > MAX_RECALCULATE_DATES || symbols.len() > MAX_RECALCULATE_SYMBOLS {
+        return ApiError::invalid(format!("At most {} dates and {} symbols per request", MAX_RECALCULATE_DATES, MAX_RECALCULATE_SYMBOLS)).into_response();
+    }
+    let today = market_time::market_today();
+    if let Some(future) = request.dates.iter().find(|date| **date > today) {
+        return ApiError::invalid(format!("Date {} is in the future", future)).into_response();
+    }
+
+    let from = request.dates.iter().min().copied().unwrap_or(today);
+    let invalidated = {
+        let data_guard = data_state.lock().await;
+        let unknown: Vec<&String> = symbols.iter().filter(|symbol| !data_guard.contains_key(*symbol)).collect();
+        if !unknown.is_empty() {
+            return ApiError::new(ErrorCode::SymbolNotFound, "No data for some symbols")
+                .with_details(serde_json::json!({ "symbols": unknown }))
+                .into_response();
+        }
+        symbols.iter()
+            .map(|symbol| indicator_cache::invalidate_from(symbol, &data_guard[symbol], from))
+            .sum::<usize>()
+    };
+    // Group distributions and rankings mix every symbol, so none of them can be kept
+    cache_state.lock().await.clear();
+
+    let job = recalculation::schedule(&admin, request.dates, symbols, invalidated);
+    warn!(admin, job = job.id, symbols = job.symbols.len(), dates = job.dates.len(), invalidated, "Recalculation requested");
+    (StatusCode::ACCEPTED, Json(serde_json::json!({ "job": job }))).into_response()
+}
+
+/// Recent recalculation jobs and their progress, oldest first
+#[instrument(skip(token_state, headers))]
+pub async fn admin_recalculations_handler(
+    State(token_state): State<SharedTokenRegistry>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(error) = require_admin(&token_state, &headers).await {
+        return error.into_response();
+    }
+    (StatusCode::OK, Json(serde_json::json!({ "jobs": recalculation::jobs() }))).into_response()
+}
+
+#[instrument(skip(data_state, reputation_state, staging_state, last_update_state, headers), fields(source_ip = %addr.ip(), symbol = %payload.symbol.as_deref().unwrap_or("unknown")))]
 pub async fn public_gossip_handler(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(data_state): State<SharedData>,
     State(reputation_state): State<SharedReputation>,
+    State(staging_state): State<SharedGossipStaging>,
     State(last_update_state): State<LastInternalUpdate>,
+    headers: HeaderMap,
     Json(payload): Json<OhlcvData>,
 ) -> Response {
     let source_ip = addr.ip();
     debug!("Received public gossip request");
+    metrics::increment_counter("gossip.public_received", 1);
     
     let mut reputation_guard = reputation_state.lock().await;
     let actor = reputation_guard.entry(source_ip).or_default();
@@ -189,73 +832,198 @@ pub async fn public_gossip_handler(
     debug!(
         successful_updates = actor.successful_updates,
         failed_updates = actor.failed_updates,
+        score = actor.score(),
         status = ?actor.status,
         "Actor reputation status"
     );
 
-    if actor.status == crate::data_structures::ActorStatus::Banned {
+    if actor.status == ActorStatus::Banned {
         warn!("Rejected request from banned IP");
-        return (StatusCode::FORBIDDEN, "Source IP is banned").into_response();
+        return ApiError::new(ErrorCode::Forbidden, "Source IP is banned").into_response();
+    }
+
+    // Low-scoring actors get a smaller allowance; going over it counts against the score too
+    if let Err(retry_after_secs) = actor.admit_request(Instant::now()) {
+        actor.rate_limited += 1;
+        update_actor_status(actor);
+        metrics::increment_counter("gossip.public_rate_limited", 1);
+        debug!(requests_per_minute = actor.requests_per_minute(), "Public gossip rate limit exceeded");
+        return ApiError::new(ErrorCode::RateLimited, format!("Too many requests, retry in {}s", retry_after_secs))
+            .with_retry_after(retry_after_secs)
+            .into_response();
+    }
+
+    // Shadow-banned actors are answered as usual so they don't just move to another address
+    if actor.status == ActorStatus::ShadowBanned {
+        metrics::increment_counter("gossip.public_shadow_banned", 1);
+        debug!("Discarded contribution from shadow-banned IP");
+        return (StatusCode::ACCEPTED, "Staged").into_response();
     }
 
+    // A retried contribution is acknowledged again without being staged twice
+    let sender = source_ip.to_string();
+    let request_key = idempotency_key(&headers).unwrap_or_else(|| gossip_dedup::bar_key(&payload));
+    if gossip_dedup::check_and_remember(&sender, &request_key) {
+        gossip_dedup::record_duplicate("gossip.public_duplicates", &sender);
+        debug!("Duplicate public gossip request, skipping");
+        return (StatusCode::ACCEPTED, "Staged").into_response();
+    }
+
+    // Every rejection below forgets the key, so a corrected retry is checked again
     let last_internal_update = last_update_state.lock().await;
     let time_since_update = last_internal_update.elapsed();
     debug!(time_since_last_internal_update = ?time_since_update, "Checking system trust status");
     
     if time_since_update > Duration::from_secs(300) {
         warn!(time_since_update = ?time_since_update, "System running on untrusted data too long");
-        return (StatusCode::SERVICE_UNAVAILABLE, "System is running on untrusted data").into_response();
+        gossip_dedup::forget(&sender, &request_key);
+        return ApiError::new(ErrorCode::Degraded, "System is running on untrusted data").into_response();
     }
 
-    let mut data_guard = data_state.lock().await;
-    if let Some(symbol) = &payload.symbol {
-        if let Some(entry) = data_guard.get(symbol.as_str()) {
-            if let Some(last_data) = entry.last() {
-                let price_change_percent = (payload.close - last_data.close).abs() / last_data.close;
-                debug!(
-                    symbol,
-                    old_price = last_data.close,
-                    new_price = payload.close,
-                    price_change_percent,
-                    "Price change validation"
-                );
-                
-                if price_change_percent > 0.10 {
-                    actor.failed_updates += 1;
-                    warn!(
-                        symbol,
-                        price_change_percent,
-                        failed_updates = actor.failed_updates,
-                        "Implausible price change detected"
-                    );
-                    
-                    if actor.failed_updates > 5 {
-                        actor.status = crate::data_structures::ActorStatus::Banned;
-                        error!("Banning IP due to repeated implausible data");
-                    }
-                    return (StatusCode::BAD_REQUEST, "Implausible price change").into_response();
-                }
-            }
+    let Some(symbol) = &payload.symbol else {
+        warn!("Received public gossip payload without symbol");
+        gossip_dedup::forget(&sender, &request_key);
+        actor.invalid_payloads += 1;
+        update_actor_status(actor);
+        return ApiError::invalid("Missing symbol").into_response();
+    };
+    let prices = [payload.open, payload.high, payload.low, payload.close];
+    if prices.iter().any(|price| !price.is_finite() || *price <= 0.0) || payload.high < payload.low {
+        warn!(symbol, "Received public gossip bar with invalid prices");
+        gossip_dedup::forget(&sender, &request_key);
+        actor.invalid_payloads += 1;
+        update_actor_status(actor);
+        return ApiError::invalid("Invalid prices").with_details(serde_json::json!({ "symbol": symbol })).into_response();
+    }
+    // A bar from a session that hasn't started would outrank every real one that follows
+    if market_time::market_date(payload.time) > market_time::market_today() {
+        warn!(symbol, time = %payload.time, "Received public gossip bar dated in the future");
+        gossip_dedup::forget(&sender, &request_key);
+        actor.invalid_payloads += 1;
+        update_actor_status(actor);
+        return ApiError::invalid("Future bar")
+            .with_details(serde_json::json!({ "symbol": symbol, "date": market_time::format_market_date(payload.time) }))
+            .into_response();
+    }
+
+    let data_guard = data_state.lock().await;
+    // Only symbols with authoritative data can be checked against the next fetch
+    let Some(entry) = data_guard.get(symbol.as_str()) else {
+        debug!(symbol, "Rejected contribution for untracked symbol");
+        gossip_dedup::forget(&sender, &request_key);
+        return ApiError::symbol_not_found(symbol).into_response();
+    };
+    if let Some(last_data) = entry.last() {
+        // The authoritative source already has newer sessions; this bar adds nothing
+        if market_time::market_date(payload.time) < market_time::market_date(last_data.time) {
+            gossip_dedup::forget(&sender, &request_key);
+            actor.stale_updates += 1;
+            update_actor_status(actor);
+            debug!(symbol, stale_updates = actor.stale_updates, "Rejected stale contribution");
+            return ApiError::invalid("Stale bar")
+                .with_details(serde_json::json!({ "symbol": symbol, "latest_date": market_time::format_market_date(last_data.time) }))
+                .into_response();
         }
-        
-        actor.successful_updates += 1;
-        let entry = data_guard.entry(symbol.clone()).or_default();
-        entry.push(payload.clone());
-        entry.sort_by_key(|d| d.time);
-        
-        info!(
+
+        let price_change_percent = (payload.close - last_data.close).abs() / last_data.close;
+        debug!(
             symbol,
-            close_price = payload.close,
-            volume = payload.volume,
-            successful_updates = actor.successful_updates,
-            "Accepted public gossip data"
+            old_price = last_data.close,
+            new_price = payload.close,
+            price_change_percent,
+            "Price change validation"
         );
-    } else {
-        warn!("Received public gossip payload without symbol");
-        actor.failed_updates += 1;
+        
+        if price_change_percent > 0.10 {
+            gossip_dedup::forget(&sender, &request_key);
+            actor.failed_updates += 1;
+            warn!(
+                symbol,
+                price_change_percent,
+                failed_updates = actor.failed_updates,
+                "Implausible price change detected"
+            );
+            update_actor_status(actor);
+            return ApiError::invalid("Implausible price change")
+                .with_details(serde_json::json!({ "symbol": symbol, "price_change_percent": price_change_percent * 100.0 }))
+                .into_response();
+        }
     }
+    
+    drop(data_guard);
 
-    (StatusCode::OK, "OK").into_response()
+    // Served history is never touched here: the worker merges staged data only after
+    // the next authoritative fetch confirms it, and updates reputation then
+    let mut staging_guard = staging_state.lock().await;
+    let staged = staging_guard.entry(symbol.clone()).or_default();
+    // One contribution per source and bar: a resend with new values replaces the earlier one
+    match staged.iter_mut().find(|c| c.source_ip == source_ip && c.data.time == payload.time) {
+        Some(contribution) => contribution.data = payload.clone(),
+        None => staged.push(StagedContribution { data: payload.clone(), source_ip }),
+    }
+    if staged.len() > MAX_STAGED_PER_SYMBOL {
+        staged.remove(0);
+    }
+
+    metrics::increment_counter("gossip.public_staged", 1);
+    info!(
+        symbol,
+        close_price = payload.close,
+        volume = payload.volume,
+        staged = staged.len(),
+        "Staged public gossip data"
+    );
+
+    (StatusCode::ACCEPTED, "Staged").into_response()
+}
+
+/// Re-derive a public actor's status after a penalty, logging any change
+fn update_actor_status(actor: &mut ActorMetadata) {
+    match actor.update_status() {
+        Some(ActorStatus::Banned) => error!(failed_updates = actor.failed_updates, "Banning IP due to repeated implausible data"),
+        Some(status) => warn!(?status, score = actor.score(), "Public actor status changed"),
+        None => {}
+    }
+}
+
+#[instrument(skip(token_state, reputation_state, headers))]
+pub async fn admin_reputation_handler(
+    State(token_state): State<SharedTokenRegistry>,
+    State(reputation_state): State<SharedReputation>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(error) = require_admin(&token_state, &headers).await {
+        return error.into_response();
+    }
+    // Lowest standing first, the actors an admin is most likely looking for
+    let mut actors: Vec<ActorSummary> = reputation_state.lock().await.iter().map(|(ip, actor)| actor.summary(*ip)).collect();
+    actors.sort_by(|a, b| a.score.cmp(&b.score).then_with(|| a.ip.cmp(&b.ip)));
+    (StatusCode::OK, Json(serde_json::json!({ "actors": actors }))).into_response()
+}
+
+#[instrument(skip(token_state, reputation_state, headers))]
+pub async fn admin_reset_reputation_handler(
+    State(token_state): State<SharedTokenRegistry>,
+    State(reputation_state): State<SharedReputation>,
+    Path(ip): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let admin = match require_admin(&token_state, &headers).await {
+        Ok(admin) => admin,
+        Err(error) => return error.into_response(),
+    };
+    let Ok(ip) = ip.parse::<IpAddr>() else {
+        return ApiError::invalid(format!("Invalid IP address '{}'", ip)).into_response();
+    };
+    match reputation_state.lock().await.remove(&ip) {
+        Some(previous) => {
+            warn!(%ip, admin, status = ?previous.status, score = previous.score(), "Public actor reputation reset");
+            (StatusCode::OK, Json(serde_json::json!({ "reset": previous.summary(ip) }))).into_response()
+        }
+        None => ApiError::new(ErrorCode::NotFound, "Unknown public actor")
+            .with_details(serde_json::json!({ "ip": ip }))
+            .into_response(),
+    }
 }
 
 #[instrument(skip(state))]
@@ -269,54 +1037,1774 @@ pub async fn get_ticker_groups_handler(State(state): State<SharedTickerGroups>)
     (StatusCode::OK, Json(state.0.clone()))
 }
 
-#[instrument(skip(health_state, data_state))]
-pub async fn health_handler(
-    State(health_state): State<SharedHealthStats>,
-    State(data_state): State<SharedData>,
-) -> impl IntoResponse {
-    debug!("Received request for health stats");
-    
-    let mut health_stats = health_state.lock().await.clone();
-    
-    // Calculate current memory usage dynamically
-    {
-        let data_guard = data_state.lock().await;
-        let memory_bytes = crate::data_structures::estimate_memory_usage(&*data_guard);
-        let memory_mb = memory_bytes as f64 / (1024.0 * 1024.0);
-        let memory_percent = (memory_bytes as f64 / crate::data_structures::MAX_MEMORY_BYTES as f64) * 100.0;
-        
-        health_stats.memory_usage_bytes = memory_bytes;
-        health_stats.memory_usage_mb = memory_mb;
-        health_stats.memory_usage_percent = memory_percent;
-        health_stats.active_tickers_count = data_guard.len();
-    }
-    
-    info!(
-        is_office_hours = health_stats.is_office_hours,
-        current_interval_secs = health_stats.current_interval_secs,
-        active_tickers = health_stats.active_tickers_count,
-        total_tickers = health_stats.total_tickers_count,
-        memory_mb = format!("{:.2}", health_stats.memory_usage_mb),
-        memory_percent = format!("{:.1}%", health_stats.memory_usage_percent),
-        iteration_count = health_stats.iteration_count,
-        "Returning health stats"
-    );
-    
-    (StatusCode::OK, Json(health_stats))
-}
+#[instrument(skip(groups_state, constituents_state, ticker_state, request_headers))]
+pub async fn symbols_handler(
+    State(groups_state): State<SharedTickerGroups>,
+    State(constituents_state): State<SharedIndexConstituents>,
+    State(ticker_state): State<SharedTickerDirectory>,
+    request_headers: HeaderMap,
+) -> Response {
+    debug!("Received request for symbol metadata");
 
-#[derive(Debug, Deserialize)]
-pub struct ClearCacheParams {
-    #[serde(rename = "clearCache")]
-    clear_cache: Option<bool>,
-}
+    // BTreeMap keeps the output order stable so the ETag only changes with the content
+    let mut metadata: BTreeMap<String, SymbolMetadata> = BTreeMap::new();
+    for (group, symbols) in groups_state.0.iter() {
+        for symbol in symbols {
+            metadata.entry(symbol.clone())
+                .or_insert_with(|| SymbolMetadata { symbol: symbol.clone(), name: symbol.clone(), name_vi: None, groups: Vec::new(), indices: Vec::new() })
+                .groups.push(group.clone());
+        }
+    }
 
-const GITHUB_RAW_BASE_URL: &str = "https://raw.githubusercontent.com/quanhua92/aipriceaction-data/refs/heads/main/";
+    let today = market_time::market_today();
+    for index_name in constituents_state.index_names() {
+        for symbol in constituents_state.symbols_at(&index_name, today).unwrap_or_default() {
+            metadata.entry(symbol.clone())
+                .or_insert_with(|| SymbolMetadata { symbol: symbol.clone(), name: symbol.clone(), name_vi: None, groups: Vec::new(), indices: Vec::new() })
+                .indices.push(index_name.clone());
+        }
+    }
+
+    let directory = ticker_state.lock().await;
+    let symbols: Vec<SymbolMetadata> = metadata.into_values()
+        .map(|mut entry| {
+            entry.name = directory.name_for(&entry.symbol).to_string();
+            entry.name_vi = directory.get(&entry.symbol).and_then(|info| info.name_vi.clone());
+            entry.groups.sort();
+            entry.indices.sort();
+            entry
+        })
+        .collect();
+    drop(directory);
+
+    let body = serde_json::to_vec(&symbols).unwrap_or_default();
+    let etag = http_range::compute_etag(&body);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("max-age=300"));
+    headers.insert(ETAG, etag.parse().unwrap());
+
+    let if_none_match = request_headers.get(IF_NONE_MATCH).and_then(|v| v.to_str().ok());
+    if http_range::if_none_match_matches(if_none_match, &etag) {
+        debug!(etag, "Symbol metadata not modified");
+        return (StatusCode::NOT_MODIFIED, headers).into_response();
+    }
+
+    info!(symbol_count = symbols.len(), "Returning symbol metadata");
+    headers.insert("content-type", HeaderValue::from_static("application/json"));
+    (StatusCode::OK, headers, body).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    q: Option<String>,
+    limit: Option<usize>,
+}
+
+#[instrument(skip(groups_state, ticker_state))]
+pub async fn search_handler(
+    State(groups_state): State<SharedTickerGroups>,
+    State(ticker_state): State<SharedTickerDirectory>,
+    Query(params): Query<SearchParams>,
+) -> impl IntoResponse {
+    debug!("Received symbol search request");
+
+    let query = params.q.unwrap_or_default();
+    if query.trim().is_empty() {
+        return ApiError::invalid("Missing search query. Use ?q=<symbol or company name>").into_response();
+    }
+    let limit = params.limit.unwrap_or(ticker_info::DEFAULT_SEARCH_LIMIT);
+    if !(1..=ticker_info::MAX_SEARCH_LIMIT).contains(&limit) {
+        return ApiError::invalid(format!("limit must be between 1 and {}", ticker_info::MAX_SEARCH_LIMIT)).into_response();
+    }
+
+    // Symbols from ticker groups are searchable by code even without a ticker_info.json entry
+    let results = ticker_state.lock().await.search(&query, groups_state.0.values().flatten(), limit);
+    info!(query, results = results.len(), "Returning symbol search results");
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, "max-age=300".parse().unwrap());
+    (StatusCode::OK, headers, Json(serde_json::json!({ "query": query, "results": results }))).into_response()
+}
+
+#[instrument(skip(data_state, ticker_state, office_hours_state))]
+pub async fn coverage_handler(
+    State(data_state): State<SharedData>,
+    State(ticker_state): State<SharedTickerDirectory>,
+    State(office_hours_state): State<SharedOfficeHoursConfig>,
+) -> impl IntoResponse {
+    debug!("Received request for data coverage");
+
+    let exchanges = ticker_state.lock().await.exchanges();
+    let (total, by_exchange) = {
+        let data = data_state.lock().await;
+        let mut grouped: BTreeMap<&str, Vec<&[OhlcvData]>> = BTreeMap::new();
+        for (symbol, series) in data.iter() {
+            let exchange = exchanges.get(symbol).map_or(coverage::UNKNOWN_EXCHANGE, String::as_str);
+            grouped.entry(exchange).or_default().push(series.as_slice());
+        }
+        let by_exchange: BTreeMap<String, coverage::Coverage> = grouped.into_iter()
+            .map(|(exchange, series)| (exchange.to_string(), coverage::coverage(series)))
+            .collect();
+        (coverage::coverage(data.values().map(Vec::as_slice)), by_exchange)
+    };
+    let provisional_date = get_provisional_date(&office_hours_state);
+    info!(latest_date = ?total.latest_date, symbols = total.symbols, lagging = total.lagging_1_plus, exchanges = by_exchange.len(), "Returning data coverage");
+
+    let body = serde_json::json!({
+        "latest_date": total.latest_date,
+        "provisional": total.latest_date.is_some() && total.latest_date == provisional_date,
+        "symbols": total.symbols,
+        "updated": total.updated,
+        "lagging_1_plus": total.lagging_1_plus,
+        "lagging_2_plus": total.lagging_2_plus,
+        "exchanges": by_exchange,
+    });
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, "max-age=30".parse().unwrap());
+    (StatusCode::OK, headers, Json(body)).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConstituentsParams {
+    date: Option<String>,
+}
+
+#[instrument(skip(state))]
+pub async fn index_constituents_handler(
+    State(state): State<SharedIndexConstituents>,
+    Path(name): Path<String>,
+    Query(params): Query<ConstituentsParams>,
+) -> impl IntoResponse {
+    debug!("Received request for index constituents");
+
+    let date = match &params.date {
+        Some(date_str) => match NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
+            Ok(date) => date,
+            Err(_) => {
+                warn!(date = %date_str, "Invalid date format, expected YYYY-MM-DD");
+                return ApiError::invalid("Invalid date format. Expected YYYY-MM-DD").into_response();
+            }
+        },
+        None => market_time::market_today(),
+    };
+
+    match state.membership_at(&name, date) {
+        Some(snapshot) => {
+            info!(index = %name, %date, effective_date = %snapshot.effective_date, constituents = snapshot.constituents.len(), "Returning index constituents");
+            let body = serde_json::json!({
+                "index": name.to_uppercase(),
+                "date": date.format("%Y-%m-%d").to_string(),
+                "effective_date": snapshot.effective_date.format("%Y-%m-%d").to_string(),
+                "constituents": snapshot.constituents,
+            });
+            (StatusCode::OK, Json(body)).into_response()
+        }
+        None => {
+            warn!(index = %name, %date, known_indices = ?state.index_names(), "No constituents found for index");
+            ApiError::new(ErrorCode::NotFound, "No constituents found for index on the requested date")
+                .with_details(serde_json::json!({ "index": name.to_uppercase(), "date": date }))
+                .into_response()
+        }
+    }
+}
+
+/// Parse an optional YYYY-MM-DD query parameter, returning the error message on bad input
+fn parse_date_param(name: &str, value: Option<&String>) -> Result<Option<NaiveDate>, String> {
+    match value {
+        Some(date_str) => match NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
+            Ok(date) => Ok(Some(date)),
+            Err(_) => {
+                warn!(param = name, value = %date_str, "Invalid date format, expected YYYY-MM-DD");
+                Err(format!("Invalid {} format. Expected YYYY-MM-DD", name))
+            }
+        },
+        None => Ok(None),
+    }
+}
+
+/// Members of a ticker group, index or explicit symbol list. Ticker groups and symbol lists are
+/// static; an index follows its constituent snapshots, so history only counts the symbols that were
+/// members at the time.
+struct GroupMembers {
+    symbols: HashSet<String>, // Every symbol that is a member on some date
+    index: Option<(String, SharedIndexConstituents)>,
+}
+
+impl GroupMembers {
+    fn fixed(symbols: HashSet<String>) -> Self {
+        Self { symbols, index: None }
+    }
+
+    /// Whether `symbol` is a member on any date; narrows what gets computed before dates are known
+    fn may_contain(&self, symbol: &str) -> bool {
+        self.symbols.contains(symbol)
+    }
+
+    /// Whether `symbol` was a member on `date`
+    fn contains(&self, symbol: &str, date: NaiveDate) -> bool {
+        self.symbols.contains(symbol)
+            && self.index.as_ref().is_none_or(|(name, constituents)| {
+                constituents.membership_at(name, date).is_some_and(|snapshot| snapshot.constituents.iter().any(|c| c.symbol == symbol))
+            })
+    }
+}
+
+/// Resolve a `group` parameter to a ticker group or index with constituents, 404 when it's neither
+#[allow(clippy::result_large_err)] // Handlers return the error response as is
+fn resolve_group(group: &str, groups: &TickerGroups, constituents: &SharedIndexConstituents) -> Result<GroupMembers, Response> {
+    match (groups.0.get(group), constituents.0.get(group)) {
+        (Some(symbols), _) => Ok(GroupMembers::fixed(symbols.iter().cloned().collect())),
+        (None, Some(snapshots)) => Ok(GroupMembers {
+            symbols: snapshots.iter().flat_map(|snapshot| snapshot.constituents.iter().map(|c| c.symbol.clone())).collect(),
+            index: Some((group.to_string(), Arc::clone(constituents))),
+        }),
+        (None, None) => {
+            warn!(group, "Unknown group or index");
+            Err(ApiError::new(ErrorCode::NotFound, "Unknown group or index").with_details(serde_json::json!({ "group": group })).into_response())
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MaDistributionParams {
+    group: String,
+    period: Option<usize>,
+    weighting: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    precision: Option<String>,
+    liquidity: Option<String>, // Preset name; only symbols above its ADTV count
+}
+
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip(data_state, groups_state, constituents_state, cache_state, office_hours_state, health_state, sources_state))]
+pub async fn ma_distribution_handler(
+    State(data_state): State<SharedData>,
+    State(groups_state): State<SharedTickerGroups>,
+    State(constituents_state): State<SharedIndexConstituents>,
+    State(cache_state): State<SharedAnalysisCache>,
+    State(office_hours_state): State<SharedOfficeHoursConfig>,
+    State(health_state): State<SharedHealthStats>,
+    State(sources_state): State<MarketCapSources>,
+    Query(params): Query<MaDistributionParams>,
+) -> impl IntoResponse {
+    debug!("Received request for MA score distribution");
+
+    let period = params.period.unwrap_or(20);
+    if !ma_score::MA_PERIODS.contains(&period) {
+        warn!(period, "Unsupported MA period");
+        return ApiError::invalid(format!("Unsupported period. Expected one of {:?}", ma_score::MA_PERIODS)).into_response();
+    }
+    let weighting = match params.weighting.as_deref().map(str::parse::<Weighting>).transpose() {
+        Ok(weighting) => weighting.unwrap_or_default(),
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let precision_mode = match parse_precision(params.precision.as_deref()) {
+        Ok(mode) => mode,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let liquidity = match params.liquidity.as_deref().map(liquidity::preset).transpose() {
+        Ok(liquidity) => liquidity,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+
+    let start_date = match parse_date_param("start_date", params.start_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let end_date = match parse_date_param("end_date", params.end_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+
+    let group = params.group.to_uppercase();
+    let (initial_load_complete, strict_readiness, retry_after_secs) = {
+        let health = health_state.lock().await;
+        (health.initial_load_complete, health.strict_readiness, health.current_interval_secs)
+    };
+
+    // The cache holds the full, range-independent distribution per (group, period).
+    // Date ranges are sliced from it, so switching ranges never recomputes known dates.
+    let liquidity_name = liquidity.map(|(name, _)| name);
+    let cache_key = format!("ma-distribution:{}:{}:{}:{}", group, period, weighting.name(), liquidity_name.unwrap_or("*"));
+    let cached = cache_state.lock().await.get(&cache_key)
+        .filter(|(cached_at, _)| cached_at.elapsed() < Duration::from_secs(ANALYSIS_CACHE_TTL_SECS))
+        .and_then(|(_, value)| serde_json::from_value::<ma_score::GroupDistribution>(value.clone()).ok());
+
+    let group_distribution = match cached {
+        Some(cached) => {
+            debug!(cache_key, "Using cached MA distribution");
+            cached
+        }
+        None => {
+            // Ticker groups have static membership; indices use point-in-time constituents
+            let members = match resolve_group(&group, &groups_state, &constituents_state) {
+                Ok(members) => members,
+                Err(response) => return response,
+            };
+            let mut candidate_symbols = members.symbols.clone();
+
+            let compute_timer = Timer::start("analysis.ma_distribution");
+            let scores_by_symbol: HashMap<String, Arc<[ma_score::MaScorePoint]>> = {
+                let data = data_state.lock().await;
+                // Illiquid members leave the group; members without data yet still count against coverage
+                if let Some((_, preset)) = liquidity {
+                    candidate_symbols.retain(|symbol| data.get(symbol).is_none_or(|series| preset.passes(series)));
+                }
+                candidate_symbols.iter()
+                    .filter_map(|symbol| data.get(symbol).map(|series| (symbol.clone(), indicator_cache::ma_scores(symbol, series))))
+                    .collect()
+            };
+
+            // Today's bar is refreshed every worker cycle until the session closes
+            let provisional_date = get_provisional_date(&office_hours_state);
+            let market_caps = match weighting {
+                Weighting::Equal => None,
+                Weighting::MarketCap => Some(sources_state.load().await),
+            };
+            let distribution = ma_score::ma_score_distribution(&scores_by_symbol, period, provisional_date, market_caps.as_ref(), |symbol, date| {
+                members.contains(symbol, date)
+            });
+            compute_timer.stop();
+
+            // Readiness: share of group symbols with a full MA window, and whether the first load finished
+            let calculated_symbols = scores_by_symbol.values()
+                .filter(|points| points.iter().any(|p| p.score(period).is_some()))
+                .count();
+            let computed = ma_score::GroupDistribution {
+                symbols: scores_by_symbol.len(),
+                coverage: if candidate_symbols.is_empty() { 0.0 } else { calculated_symbols as f64 / candidate_symbols.len() as f64 },
+                calculated: initial_load_complete && !distribution.is_empty(),
+                distribution,
+            };
+
+            // Only cache complete results so clients see data as soon as the load finishes
+            if computed.calculated
+                && let Ok(value) = serde_json::to_value(&computed)
+            {
+                insert_analysis(&mut *cache_state.lock().await, cache_key, value);
+            }
+            computed
+        }
+    };
+
+    if !group_distribution.calculated && strict_readiness {
+        let retry_after_secs = if retry_after_secs == 0 { DEFAULT_RETRY_AFTER_SECS } else { retry_after_secs };
+        warn!(group, period, initial_load_complete, coverage = group_distribution.coverage, retry_after_secs, "MA distribution not ready");
+        return ApiError::not_ready(retry_after_secs).into_response();
+    }
+
+    let distribution = group_distribution.in_range(start_date, end_date);
+    info!(group, period, symbols_with_data = group_distribution.symbols, dates = distribution.len(), calculated = group_distribution.calculated, "Returning MA score distribution");
+
+    let body = serde_json::json!({
+        "group": group,
+        "period": period,
+        "weighting": weighting.name(),
+        "liquidity": liquidity_name,
+        "symbols": group_distribution.symbols,
+        "meta": {
+            "calculated": group_distribution.calculated,
+            "coverage": group_distribution.coverage,
+            "asof": distribution.last().map(|d| d.date),
+        },
+        "distribution": precision::to_json(&distribution, precision_mode),
+    });
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, "max-age=30".parse().unwrap());
+    (StatusCode::OK, headers, Json(body)).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MaStreakParams {
+    group: Option<String>,
+    period: Option<usize>,
+    direction: Option<String>,
+    min_days: Option<u32>,
+    precision: Option<String>,
+    liquidity: Option<String>,
+}
+
+#[instrument(skip(data_state, groups_state, constituents_state, sources_state))]
+pub async fn ma_streaks_handler(
+    State(data_state): State<SharedData>,
+    State(groups_state): State<SharedTickerGroups>,
+    State(constituents_state): State<SharedIndexConstituents>,
+    State(sources_state): State<MarketCapSources>,
+    Query(params): Query<MaStreakParams>,
+) -> impl IntoResponse {
+    debug!("Received request for MA streaks");
+
+    let period = params.period.unwrap_or(20);
+    if !ma_score::MA_PERIODS.contains(&period) {
+        warn!(period, "Unsupported MA period");
+        return ApiError::invalid(format!("Unsupported period. Expected one of {:?}", ma_score::MA_PERIODS)).into_response();
+    }
+    let above = match params.direction.as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("above") => true,
+        Some("below") => false,
+        Some(other) => return ApiError::invalid(format!("Invalid direction '{}'. Expected above or below", other)).into_response(),
+    };
+    let min_days = params.min_days.unwrap_or(1);
+    let precision_mode = match parse_precision(params.precision.as_deref()) {
+        Ok(mode) => mode,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let liquidity = match params.liquidity.as_deref().map(liquidity::preset).transpose() {
+        Ok(liquidity) => liquidity,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+
+    let group = params.group.map(|group| group.to_uppercase());
+    let members = match &group {
+        None => None,
+        Some(group) => match resolve_group(group, &groups_state, &constituents_state) {
+            Ok(members) => Some(members),
+            Err(response) => return response,
+        },
+    };
+
+    // Each symbol's latest session, longest streak first
+    let mut rows: Vec<(u32, serde_json::Value)> = {
+        let data = data_state.lock().await;
+        data.iter()
+            .filter(|(symbol, _)| members.as_ref().is_none_or(|members| members.may_contain(symbol)))
+            .filter(|(_, series)| liquidity.is_none_or(|(_, preset)| preset.passes(series)))
+            .filter_map(|(symbol, series)| {
+                let latest = indicator_cache::ma_scores(symbol, series).last().cloned()?;
+                if members.as_ref().is_some_and(|members| !members.contains(symbol, latest.date)) {
+                    return None;
+                }
+                let days = if above { latest.days_above(period)? } else { latest.days_below(period)? };
+                (days >= min_days).then(|| (days, serde_json::json!({
+                    "symbol": symbol,
+                    "date": latest.date,
+                    "days": days,
+                    "close": latest.close,
+                    "ma_score": latest.score(period),
+                    "trend_score": latest.trend_score,
+                })))
+            })
+            .collect()
+    };
+    rows.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1["symbol"].as_str().cmp(&b.1["symbol"].as_str())));
+    let rows: Vec<serde_json::Value> = rows.into_iter().map(|(_, row)| row).collect();
+    info!(group, period, above, min_days, matches = rows.len(), "Returning MA streaks");
+
+    let mut body = serde_json::json!({
+        "group": group,
+        "period": period,
+        "direction": if above { "above" } else { "below" },
+        "min_days": min_days,
+        "liquidity": liquidity.map(|(name, _)| name),
+        "symbols": precision::to_json(&rows, precision_mode),
+    });
+    sources_state.annotate(&mut body).await;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, "max-age=30".parse().unwrap());
+    (StatusCode::OK, headers, Json(body)).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DivergenceParams {
+    group: Option<String>,
+    lookback: Option<usize>,
+    kind: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    precision: Option<String>,
+    liquidity: Option<String>,
+}
+
+#[instrument(skip(data_state, groups_state, constituents_state, cache_state, office_hours_state, sources_state))]
+pub async fn money_flow_divergence_handler(
+    State(data_state): State<SharedData>,
+    State(groups_state): State<SharedTickerGroups>,
+    State(constituents_state): State<SharedIndexConstituents>,
+    State(sources_state): State<MarketCapSources>,
+    State(cache_state): State<SharedAnalysisCache>,
+    State(office_hours_state): State<SharedOfficeHoursConfig>,
+    Query(params): Query<DivergenceParams>,
+) -> impl IntoResponse {
+    debug!("Received request for money flow divergences");
+
+    let lookback = params.lookback.unwrap_or(money_flow::DEFAULT_DIVERGENCE_LOOKBACK);
+    if !(money_flow::MIN_DIVERGENCE_LOOKBACK..=money_flow::MAX_DIVERGENCE_LOOKBACK).contains(&lookback) {
+        warn!(lookback, "Unsupported divergence lookback");
+        return ApiError::invalid(format!(
+            "lookback must be between {} and {}",
+            money_flow::MIN_DIVERGENCE_LOOKBACK,
+            money_flow::MAX_DIVERGENCE_LOOKBACK
+        )).into_response();
+    }
+    let kind = match params.kind.as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("all") => None,
+        Some("bearish") => Some(money_flow::DivergenceKind::Bearish),
+        Some("bullish") => Some(money_flow::DivergenceKind::Bullish),
+        Some(other) => return ApiError::invalid(format!("Invalid kind '{}'. Expected bearish, bullish or all", other)).into_response(),
+    };
+    let precision_mode = match parse_precision(params.precision.as_deref()) {
+        Ok(mode) => mode,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let liquidity = match params.liquidity.as_deref().map(liquidity::preset).transpose() {
+        Ok(liquidity) => liquidity,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let start_date = match parse_date_param("start_date", params.start_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let end_date = match parse_date_param("end_date", params.end_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+
+    // Without a group the screener covers every symbol held in memory
+    let group = params.group.map(|group| group.to_uppercase());
+    let members = match &group {
+        None => None,
+        Some(group) => match resolve_group(group, &groups_state, &constituents_state) {
+            Ok(members) => Some(members),
+            Err(response) => return response,
+        },
+    };
+
+    // Divergences are cached for every date per (group, lookback); ranges and kinds are filtered from it
+    let liquidity_name = liquidity.map(|(name, _)| name);
+    let cache_key = format!("money-flow-divergence:{}:{}:{}", group.as_deref().unwrap_or("*"), lookback, liquidity_name.unwrap_or("*"));
+    let cached = cache_state.lock().await.get(&cache_key)
+        .filter(|(cached_at, _)| cached_at.elapsed() < Duration::from_secs(ANALYSIS_CACHE_TTL_SECS))
+        .and_then(|(_, value)| serde_json::from_value::<Vec<money_flow::DivergencePoint>>(value.clone()).ok());
+
+    let divergences = match cached {
+        Some(cached) => cached,
+        None => {
+            let compute_timer = Timer::start("analysis.money_flow_divergence");
+            let provisional_date = get_provisional_date(&office_hours_state);
+            let mut computed: Vec<money_flow::DivergencePoint> = {
+                let data = data_state.lock().await;
+                data.iter()
+                    .filter(|(symbol, _)| members.as_ref().is_none_or(|members| members.may_contain(symbol)))
+                    .filter(|(_, series)| liquidity.is_none_or(|(_, preset)| preset.passes(series)))
+                    .flat_map(|(symbol, series)| indicator_cache::divergences(symbol, series, lookback, provisional_date).to_vec())
+                    .filter(|d| members.as_ref().is_none_or(|members| members.contains(&d.symbol, d.date)))
+                    .collect()
+            };
+            computed.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.symbol.cmp(&b.symbol)));
+            compute_timer.stop();
+            if let Ok(value) = serde_json::to_value(&computed) {
+                insert_analysis(&mut *cache_state.lock().await, cache_key, value);
+            }
+            computed
+        }
+    };
+
+    // Screener default: only the latest date with any divergence
+    let (start_date, end_date) = match (start_date, end_date) {
+        (None, None) => {
+            let latest = divergences.last().map(|d| d.date);
+            (latest, latest)
+        }
+        range => range,
+    };
+    let matches: Vec<&money_flow::DivergencePoint> = divergences.iter()
+        .filter(|d| start_date.is_none_or(|start| d.date >= start) && end_date.is_none_or(|end| d.date <= end))
+        .filter(|d| kind.is_none_or(|kind| d.kind == kind))
+        .collect();
+    info!(group, lookback, divergences = matches.len(), "Returning money flow divergences");
+
+    let mut body = serde_json::json!({
+        "group": group,
+        "lookback": lookback,
+        "liquidity": liquidity_name,
+        "asof": divergences.last().map(|d| d.date),
+        "divergences": precision::to_json(&matches, precision_mode),
+    });
+    sources_state.annotate(&mut body).await;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, "max-age=30".parse().unwrap());
+    (StatusCode::OK, headers, Json(body)).into_response()
+}
+
+// Shared by the per-symbol analysis series endpoints
+#[derive(Debug, Deserialize)]
+pub struct AnalysisSeriesParams {
+    group: Option<String>,
+    symbol: Option<Vec<String>>,
+    window: Option<usize>, // Money and foreign flow only: sessions summed into net_flow and inflow_share, or net_value_window
+    half_life: Option<usize>, // Money flow only: half-life (sessions) of the decay weighting behind weighted_share
+    ma: Option<String>,    // MA scores only: extra moving averages, e.g. "ema20,sma200"
+    start_date: Option<String>,
+    end_date: Option<String>,
+    precision: Option<String>,
+    liquidity: Option<String>,
+}
+
+/// Per-symbol series for the analysis series endpoints: each symbol's latest session by default,
+/// otherwise the sessions within `start`..=`end`. Symbols without points in range are left out.
+fn select_points<T: indicator_cache::Dated + Clone>(points: &[T], start: Option<NaiveDate>, end: Option<NaiveDate>) -> Vec<T> {
+    if start.is_none() && end.is_none() {
+        points.last().cloned().into_iter().collect()
+    } else {
+        indicator_cache::in_range(points, start, end).to_vec()
+    }
+}
+
+#[instrument(skip(data_state, groups_state, constituents_state))]
+pub async fn ma_score_handler(
+    State(data_state): State<SharedData>,
+    State(groups_state): State<SharedTickerGroups>,
+    State(constituents_state): State<SharedIndexConstituents>,
+    Query(params): Query<AnalysisSeriesParams>,
+) -> impl IntoResponse {
+    debug!("Received request for MA scores");
+
+    let averages = match params.ma.as_deref().map(ma_score::parse_moving_averages).transpose() {
+        Ok(averages) => averages.unwrap_or_default(),
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let precision_mode = match parse_precision(params.precision.as_deref()) {
+        Ok(mode) => mode,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let liquidity = match params.liquidity.as_deref().map(liquidity::preset).transpose() {
+        Ok(liquidity) => liquidity,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let start_date = match parse_date_param("start_date", params.start_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let end_date = match parse_date_param("end_date", params.end_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    // Explicit symbols win over a group; with neither, every symbol is included
+    let group = params.group.map(|group| group.to_uppercase());
+    let members = match (&params.symbol, &group) {
+        (Some(symbols), _) => Some(GroupMembers::fixed(symbols.iter().map(|s| s.to_uppercase()).collect())),
+        (None, None) => None,
+        (None, Some(group)) => match resolve_group(group, &groups_state, &constituents_state) {
+            Ok(members) => Some(members),
+            Err(response) => return response,
+        },
+    };
+
+    let compute_timer = Timer::start("analysis.ma_score");
+    let scores: BTreeMap<String, Vec<ma_score::ExtendedPoint>> = {
+        let data = data_state.lock().await;
+        data.iter()
+            .filter(|(symbol, _)| members.as_ref().is_none_or(|members| members.may_contain(symbol)))
+            .filter(|(_, series)| liquidity.is_none_or(|(_, preset)| preset.passes(series)))
+            .map(|(symbol, series)| {
+                let all = indicator_cache::ma_scores(symbol, series);
+                let points = select_points(&all, start_date, end_date);
+                // Selected points are consecutive sessions, so the extra averages line up from the first one
+                let first = points.first().map_or(0, |first| all.partition_point(|p| p.date < first.date));
+                let mut extra = if averages.is_empty() { Vec::new() } else { ma_score::calculate_averages(series, &averages) }.into_iter().skip(first);
+                let points = points.into_iter()
+                    .map(|point| ma_score::ExtendedPoint { point, averages: extra.next().unwrap_or_default() })
+                    .filter(|extended| members.as_ref().is_none_or(|members| members.contains(symbol, extended.point.date)))
+                    .collect::<Vec<_>>();
+                (symbol.clone(), points)
+            })
+            .filter(|(_, points)| !points.is_empty())
+            .collect()
+    };
+    compute_timer.stop();
+    let asof = scores.values().filter_map(|points| points.last()).map(|extended| extended.point.date).max();
+    info!(group, symbols = scores.len(), averages = averages.len(), "Returning MA scores");
+
+    let body = serde_json::json!({
+        "group": group,
+        "asof": asof,
+        "liquidity": liquidity.map(|(name, _)| name),
+        "symbols": precision::to_json(&scores, precision_mode),
+    });
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, "max-age=30".parse().unwrap());
+    (StatusCode::OK, headers, Json(body)).into_response()
+}
+
+#[instrument(skip(data_state, groups_state, constituents_state))]
+pub async fn money_flow_handler(
+    State(data_state): State<SharedData>,
+    State(groups_state): State<SharedTickerGroups>,
+    State(constituents_state): State<SharedIndexConstituents>,
+    Query(params): Query<AnalysisSeriesParams>,
+) -> impl IntoResponse {
+    debug!("Received request for money flow");
+
+    let window = params.window.unwrap_or(money_flow::DEFAULT_WINDOW);
+    if !(money_flow::MIN_WINDOW..=money_flow::MAX_WINDOW).contains(&window) {
+        warn!(window, "Unsupported money flow window");
+        return ApiError::invalid(format!("window must be between {} and {}", money_flow::MIN_WINDOW, money_flow::MAX_WINDOW)).into_response();
+    }
+    if let Some(half_life) = params.half_life
+        && !(money_flow::MIN_HALF_LIFE..=money_flow::MAX_HALF_LIFE).contains(&half_life)
+    {
+        warn!(half_life, "Unsupported money flow half-life");
+        return ApiError::invalid(format!("half_life must be between {} and {}", money_flow::MIN_HALF_LIFE, money_flow::MAX_HALF_LIFE)).into_response();
+    }
+    let precision_mode = match parse_precision(params.precision.as_deref()) {
+        Ok(mode) => mode,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let liquidity = match params.liquidity.as_deref().map(liquidity::preset).transpose() {
+        Ok(liquidity) => liquidity,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let start_date = match parse_date_param("start_date", params.start_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let end_date = match parse_date_param("end_date", params.end_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    // Explicit symbols win over a group; with neither, every symbol is included
+    let group = params.group.map(|group| group.to_uppercase());
+    let members = match (&params.symbol, &group) {
+        (Some(symbols), _) => Some(GroupMembers::fixed(symbols.iter().map(|s| s.to_uppercase()).collect())),
+        (None, None) => None,
+        (None, Some(group)) => match resolve_group(group, &groups_state, &constituents_state) {
+            Ok(members) => Some(members),
+            Err(response) => return response,
+        },
+    };
+
+    let compute_timer = Timer::start("analysis.money_flow");
+    let flows: BTreeMap<String, Vec<money_flow::MoneyFlowPoint>> = {
+        let data = data_state.lock().await;
+        data.iter()
+            .filter(|(symbol, _)| members.as_ref().is_none_or(|members| members.may_contain(symbol)))
+            .filter(|(_, series)| liquidity.is_none_or(|(_, preset)| preset.passes(series)))
+            .map(|(symbol, series)| {
+                let mut points = select_points(&money_flow::calculate_money_flow(series, window, params.half_life), start_date, end_date);
+                points.retain(|point| members.as_ref().is_none_or(|members| members.contains(symbol, point.date)));
+                (symbol.clone(), points)
+            })
+            .filter(|(_, points)| !points.is_empty())
+            .collect()
+    };
+    compute_timer.stop();
+    let asof = flows.values().filter_map(|points| points.last()).map(|point| point.date).max();
+    info!(group, window, half_life = params.half_life, symbols = flows.len(), "Returning money flow");
+
+    let body = serde_json::json!({
+        "group": group,
+        "window": window,
+        "half_life": params.half_life,
+        "asof": asof,
+        "liquidity": liquidity.map(|(name, _)| name),
+        "symbols": precision::to_json(&flows, precision_mode),
+    });
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, "max-age=30".parse().unwrap());
+    (StatusCode::OK, headers, Json(body)).into_response()
+}
+
+/// Foreign investors' daily buy and sell flow, with the net value over a trailing window
+#[instrument(skip(data_state, groups_state, constituents_state))]
+pub async fn foreign_flow_handler(
+    State(data_state): State<SharedData>,
+    State(groups_state): State<SharedTickerGroups>,
+    State(constituents_state): State<SharedIndexConstituents>,
+    Query(params): Query<AnalysisSeriesParams>,
+) -> impl IntoResponse {
+    debug!("Received request for foreign flow");
+
+    let window = params.window.unwrap_or(foreign_flow::DEFAULT_WINDOW);
+    if !(foreign_flow::MIN_WINDOW..=foreign_flow::MAX_WINDOW).contains(&window) {
+        warn!(window, "Unsupported foreign flow window");
+        return ApiError::invalid(format!("window must be between {} and {}", foreign_flow::MIN_WINDOW, foreign_flow::MAX_WINDOW)).into_response();
+    }
+    let precision_mode = match parse_precision(params.precision.as_deref()) {
+        Ok(mode) => mode,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let liquidity = match params.liquidity.as_deref().map(liquidity::preset).transpose() {
+        Ok(liquidity) => liquidity,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let start_date = match parse_date_param("start_date", params.start_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let end_date = match parse_date_param("end_date", params.end_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    // Explicit symbols win over a group; with neither, every symbol is included
+    let group = params.group.map(|group| group.to_uppercase());
+    let members = match (&params.symbol, &group) {
+        (Some(symbols), _) => Some(GroupMembers::fixed(symbols.iter().map(|s| s.to_uppercase()).collect())),
+        (None, None) => None,
+        (None, Some(group)) => match resolve_group(group, &groups_state, &constituents_state) {
+            Ok(members) => Some(members),
+            Err(response) => return response,
+        },
+    };
+
+    let mut symbols = foreign_flow::symbols();
+    symbols.retain(|symbol| members.as_ref().is_none_or(|members| members.may_contain(symbol)));
+    if let Some((_, preset)) = liquidity {
+        let data = data_state.lock().await;
+        symbols.retain(|symbol| data.get(symbol).is_some_and(|series| preset.passes(series)));
+    }
+    let flows: BTreeMap<String, Vec<foreign_flow::ForeignFlowView>> = symbols.into_iter()
+        .map(|symbol| {
+            let mut points = select_points(&foreign_flow::with_window(&foreign_flow::series(&symbol), window, warmup::mode()), start_date, end_date);
+            points.retain(|view| members.as_ref().is_none_or(|members| members.contains(&symbol, view.point.date)));
+            (symbol, points)
+        })
+        .filter(|(_, points)| !points.is_empty())
+        .collect();
+    let asof = flows.values().filter_map(|points| points.last()).map(|view| view.point.date).max();
+    info!(group, window, symbols = flows.len(), "Returning foreign flow");
+
+    let body = serde_json::json!({
+        "group": group,
+        "window": window,
+        "asof": asof,
+        "liquidity": liquidity.map(|(name, _)| name),
+        "symbols": precision::to_json(&flows, precision_mode),
+    });
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, "max-age=30".parse().unwrap());
+    (StatusCode::OK, headers, Json(body)).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SectorParams {
+    group: Option<String>, // One ticker group; all groups when absent
+    period: Option<usize>, // MA period behind the breadth fields
+    window: Option<usize>, // Sessions summed into window_net_flow and window_inflow_share
+    start_date: Option<String>,
+    end_date: Option<String>,
+    precision: Option<String>,
+    liquidity: Option<String>,
+}
+
+#[instrument(skip(data_state, groups_state, office_hours_state))]
+pub async fn sectors_handler(
+    State(data_state): State<SharedData>,
+    State(groups_state): State<SharedTickerGroups>,
+    State(office_hours_state): State<SharedOfficeHoursConfig>,
+    Query(params): Query<SectorParams>,
+) -> impl IntoResponse {
+    debug!("Received request for sector aggregation");
+
+    let period = params.period.unwrap_or(20);
+    if !ma_score::MA_PERIODS.contains(&period) {
+        warn!(period, "Unsupported MA period");
+        return ApiError::invalid(format!("Unsupported period. Expected one of {:?}", ma_score::MA_PERIODS)).into_response();
+    }
+    let window = params.window.unwrap_or(money_flow::DEFAULT_WINDOW);
+    if !(money_flow::MIN_WINDOW..=money_flow::MAX_WINDOW).contains(&window) {
+        warn!(window, "Unsupported sector window");
+        return ApiError::invalid(format!("window must be between {} and {}", money_flow::MIN_WINDOW, money_flow::MAX_WINDOW)).into_response();
+    }
+    let precision_mode = match parse_precision(params.precision.as_deref()) {
+        Ok(mode) => mode,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let liquidity = match params.liquidity.as_deref().map(liquidity::preset).transpose() {
+        Ok(liquidity) => liquidity,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let start_date = match parse_date_param("start_date", params.start_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let end_date = match parse_date_param("end_date", params.end_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let group = params.group.map(|group| group.to_uppercase());
+    if let Some(group) = &group
+        && (!groups_state.0.contains_key(group) || group == basis::DERIVATIVES_GROUP)
+    {
+        warn!(group, "Unknown sector");
+        return ApiError::new(ErrorCode::NotFound, "Unknown group")
+            .with_details(serde_json::json!({ "group": group }))
+            .into_response();
+    }
+
+    // Turnover shares compare every sector, so all of them are aggregated even when one is asked for.
+    // Futures turnover isn't comparable with stocks, so their group is no sector.
+    let compute_timer = Timer::start("analysis.sectors");
+    let provisional_date = get_provisional_date(&office_hours_state);
+    let mut sectors: HashMap<String, Vec<sector::SectorPoint>> = {
+        let data = data_state.lock().await;
+        groups_state.0.iter()
+            .filter(|(name, _)| name.as_str() != basis::DERIVATIVES_GROUP)
+            .map(|(name, symbols)| {
+                let members: Vec<(&Vec<OhlcvData>, Arc<[ma_score::MaScorePoint]>)> = symbols.iter()
+                    .filter_map(|symbol| data.get(symbol).map(|series| (series, indicator_cache::ma_scores(symbol, series))))
+                    .filter(|(series, _)| liquidity.is_none_or(|(_, preset)| preset.passes(series)))
+                    .collect();
+                let members: Vec<sector::MemberSeries> = members.iter()
+                    .map(|(series, scores)| sector::MemberSeries { series, scores })
+                    .collect();
+                (name.clone(), sector::aggregate_sector(&members, period, window, provisional_date))
+            })
+            .collect()
+    };
+    sector::fill_turnover_shares(&mut sectors);
+    let sectors: BTreeMap<String, Vec<sector::SectorPoint>> = sectors.into_iter()
+        .filter(|(name, _)| group.as_ref().is_none_or(|group| group == name))
+        .map(|(name, points)| (name, select_points(&points, start_date, end_date)))
+        .filter(|(_, points)| !points.is_empty())
+        .collect();
+    compute_timer.stop();
+    let asof = sectors.values().filter_map(|points| points.last()).map(|point| point.date).max();
+    info!(group, period, window, sectors = sectors.len(), "Returning sector aggregation");
+
+    let body = serde_json::json!({
+        "group": group,
+        "period": period,
+        "window": window,
+        "asof": asof,
+        "liquidity": liquidity.map(|(name, _)| name),
+        "sectors": precision::to_json(&sectors, precision_mode),
+    });
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, "max-age=30".parse().unwrap());
+    (StatusCode::OK, headers, Json(body)).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScreenerParams {
+    filter: Option<Vec<String>>, // Conditions a symbol's latest row must all pass, e.g. ma20_score>0
+    group: Option<String>,
+    sort: Option<String>,        // Field to order matches by, highest first unless order=asc
+    order: Option<String>,
+    limit: Option<usize>,
+    precision: Option<String>,
+    liquidity: Option<String>,
+}
+
+#[instrument(skip(data_state, groups_state, constituents_state, snapshots_state))]
+pub async fn screener_handler(
+    State(data_state): State<SharedData>,
+    State(groups_state): State<SharedTickerGroups>,
+    State(constituents_state): State<SharedIndexConstituents>,
+    State(snapshots_state): State<SharedEnhancedSnapshots>,
+    Query(params): Query<ScreenerParams>,
+) -> impl IntoResponse {
+    debug!("Received request for screener");
+
+    let filters: Vec<screener::Filter> = match params.filter.unwrap_or_default().iter().map(|filter| filter.parse()).collect() {
+        Ok(filters) => filters,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    if filters.len() > screener::MAX_SCREENER_FILTERS {
+        warn!(filters = filters.len(), "Too many screener filters");
+        return ApiError::invalid(format!("At most {} filters are allowed", screener::MAX_SCREENER_FILTERS)).into_response();
+    }
+    let sort = match params.sort.as_deref().map(|field| field.trim().to_lowercase()) {
+        Some(field) if !screener::screenable_field(&field) => return ApiError::invalid(format!("Unknown screener field '{}'", field)).into_response(),
+        sort => sort,
+    };
+    let ascending = match params.order.as_deref().map(|order| order.trim().to_lowercase()).as_deref() {
+        None | Some("desc") => false,
+        Some("asc") => true,
+        Some(order) => return ApiError::invalid(format!("Invalid order '{}'. Expected asc or desc", order)).into_response(),
+    };
+    let limit = params.limit.unwrap_or(screener::DEFAULT_SCREENER_LIMIT);
+    if !(1..=screener::MAX_SCREENER_LIMIT).contains(&limit) {
+        warn!(limit, "Screener limit out of range");
+        return ApiError::invalid(format!("limit must be between 1 and {}", screener::MAX_SCREENER_LIMIT)).into_response();
+    }
+    let precision_mode = match parse_precision(params.precision.as_deref()) {
+        Ok(mode) => mode,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let liquidity = match params.liquidity.as_deref().map(liquidity::preset).transpose() {
+        Ok(liquidity) => liquidity,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let group = params.group.map(|group| group.to_uppercase());
+    let members = match &group {
+        None => None,
+        Some(group) => match resolve_group(group, &groups_state, &constituents_state) {
+            Ok(members) => Some(members),
+            Err(response) => return response,
+        },
+    };
+
+    // Filters run over each symbol's latest enhanced row, from the same snapshot /tickers?enhanced=true serves
+    let (snapshot, universe) = {
+        let data = data_state.lock().await;
+        let universe: HashSet<String> = data.iter()
+            .filter(|(symbol, _)| members.as_ref().is_none_or(|members| members.may_contain(symbol)))
+            .filter(|(_, series)| liquidity.is_none_or(|(_, preset)| preset.passes(series)))
+            .map(|(symbol, _)| symbol.clone())
+            .collect();
+        (export::current_snapshot(&snapshots_state, &data_state, &data).await, universe)
+    };
+    let compute_timer = Timer::start("analysis.screener");
+    let mut rows: Vec<(String, serde_json::Map<String, serde_json::Value>)> = snapshot.series.iter()
+        .filter(|(symbol, _)| universe.contains(*symbol))
+        .filter_map(|(symbol, series)| {
+            let latest = series.last()?;
+            if members.as_ref().is_some_and(|members| !members.contains(symbol, market_time::market_date(latest.bar.time))) {
+                return None;
+            }
+            let serde_json::Value::Object(mut row) = latest.to_json() else {
+                return None;
+            };
+            let bars: Vec<OhlcvData> = series[series.len().saturating_sub(screener::SCREENER_WINDOW + 1)..].iter().map(|row| row.bar.clone()).collect();
+            screener::add_derived_fields(&mut row, &bars);
+            Some((symbol.clone(), row))
+        })
+        .collect();
+    let screened = rows.len();
+    screener::add_money_flow_percentiles(rows.iter_mut().map(|(_, row)| row));
+    rows.retain(|(_, row)| filters.iter().all(|filter| filter.matches(row)));
+    rows.sort_by(|(a_symbol, a), (b_symbol, b)| {
+        let value = |row: &serde_json::Map<String, serde_json::Value>| sort.as_ref().and_then(|field| row.get(field)).and_then(serde_json::Value::as_f64);
+        // Rows without the sort field go last either way
+        let by_field = match (value(a), value(b)) {
+            (Some(a), Some(b)) if ascending => a.total_cmp(&b),
+            (Some(a), Some(b)) => b.total_cmp(&a),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        };
+        by_field.then_with(|| a_symbol.cmp(b_symbol))
+    });
+    let matched = rows.len();
+    rows.truncate(limit);
+    compute_timer.stop();
+    let asof = rows.iter().filter_map(|(_, row)| row.get("time").and_then(serde_json::Value::as_str)).max().map(str::to_string);
+    info!(group, filters = filters.len(), screened, matched, snapshot_version = snapshot.version, "Returning screener matches");
+
+    let matches: Vec<serde_json::Value> = rows.into_iter()
+        .map(|(symbol, mut row)| {
+            row.insert("symbol".to_string(), serde_json::Value::from(symbol));
+            serde_json::Value::Object(row)
+        })
+        .collect();
+    let body = serde_json::json!({
+        "group": group,
+        "filters": filters.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "sort": sort,
+        "asof": asof,
+        "liquidity": liquidity.map(|(name, _)| name),
+        "snapshot_version": snapshot.version,
+        "screened": screened,
+        "matched": matched,
+        "matches": precision::to_json(&matches, precision_mode),
+    });
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, "max-age=30".parse().unwrap());
+    (StatusCode::OK, headers, Json(body)).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StrengthParams {
+    group: Option<String>,
+    symbol: Option<Vec<String>>,
+    min_strength: Option<f64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    precision: Option<String>,
+    liquidity: Option<String>,
+}
+
+#[instrument(skip(data_state, groups_state, constituents_state, sources_state))]
+pub async fn strength_handler(
+    State(data_state): State<SharedData>,
+    State(groups_state): State<SharedTickerGroups>,
+    State(constituents_state): State<SharedIndexConstituents>,
+    State(sources_state): State<MarketCapSources>,
+    Query(params): Query<StrengthParams>,
+) -> impl IntoResponse {
+    debug!("Received request for strength scores");
+
+    let min_strength = params.min_strength.unwrap_or(0.0);
+    if !(0.0..=100.0).contains(&min_strength) {
+        warn!(min_strength, "Invalid min_strength");
+        return ApiError::invalid("min_strength must be between 0 and 100").into_response();
+    }
+    let precision_mode = match parse_precision(params.precision.as_deref()) {
+        Ok(mode) => mode,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let liquidity = match params.liquidity.as_deref().map(liquidity::preset).transpose() {
+        Ok(liquidity) => liquidity,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let start_date = match parse_date_param("start_date", params.start_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let end_date = match parse_date_param("end_date", params.end_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+
+    // Explicit symbols win over a group; with neither, every symbol is scored
+    let group = params.group.map(|group| group.to_uppercase());
+    let members = match (&params.symbol, &group) {
+        (Some(symbols), _) => Some(GroupMembers::fixed(symbols.iter().map(|s| s.to_uppercase()).collect())),
+        (None, None) => None,
+        (None, Some(group)) => match resolve_group(group, &groups_state, &constituents_state) {
+            Ok(members) => Some(members),
+            Err(response) => return response,
+        },
+    };
+
+    let compute_timer = Timer::start("analysis.strength");
+    let weights = strength::weights();
+    let mut rows: Vec<serde_json::Value> = Vec::new();
+    let mut asof: Option<NaiveDate> = None;
+    {
+        let data = data_state.lock().await;
+        for (symbol, series) in data.iter() {
+            if members.as_ref().is_some_and(|members| !members.may_contain(symbol))
+                || liquidity.is_some_and(|(_, preset)| !preset.passes(series))
+            {
+                continue;
+            }
+            let points = indicator_cache::strength(symbol, series, weights);
+            // Screener default: each symbol's latest session only
+            let range = if start_date.is_none() && end_date.is_none() {
+                points.len().saturating_sub(1)..points.len()
+            } else {
+                indicator_cache::date_range(&points, start_date, end_date)
+            };
+            let selected: Vec<(&strength::StrengthPoint, &OhlcvData)> = points[range.clone()].iter().zip(&series[range]).collect();
+            let selected = selected.into_iter()
+                .filter(|(p, _)| p.strength.is_some_and(|s| s >= min_strength))
+                .filter(|(p, _)| members.as_ref().is_none_or(|members| members.contains(symbol, p.date)));
+            for (point, bar) in selected {
+                asof = asof.max(Some(point.date));
+                rows.push(serde_json::json!({
+                    "symbol": symbol,
+                    "date": point.date,
+                    "close": bar.close,
+                    "strength": point.strength,
+                    "components": point.components,
+                }));
+            }
+        }
+    }
+    compute_timer.stop();
+
+    // Newest first, strongest first within a date
+    rows.sort_by(|a, b| {
+        b["date"].as_str().cmp(&a["date"].as_str())
+            .then_with(|| b["strength"].as_f64().unwrap_or(0.0).total_cmp(&a["strength"].as_f64().unwrap_or(0.0)))
+    });
+    info!(group, rows = rows.len(), "Returning strength scores");
+
+    let mut body = serde_json::json!({
+        "group": group,
+        "asof": asof,
+        "liquidity": liquidity.map(|(name, _)| name),
+        "weights": weights,
+        "scores": precision::to_json(&rows, precision_mode),
+    });
+    sources_state.annotate(&mut body).await;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, "max-age=30".parse().unwrap());
+    (StatusCode::OK, headers, Json(body)).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardParams {
+    metric: Option<String>,
+    weeks: Option<usize>,
+    top: Option<usize>,
+    group: Option<String>,
+    precision: Option<String>,
+    liquidity: Option<String>,
+}
+
+#[instrument(skip(data_state, groups_state, constituents_state, cache_state, sources_state))]
+pub async fn leaderboard_handler(
+    State(data_state): State<SharedData>,
+    State(groups_state): State<SharedTickerGroups>,
+    State(constituents_state): State<SharedIndexConstituents>,
+    State(sources_state): State<MarketCapSources>,
+    State(cache_state): State<SharedAnalysisCache>,
+    Query(params): Query<LeaderboardParams>,
+) -> impl IntoResponse {
+    debug!("Received request for leaderboard history");
+
+    let metric = match params.metric.as_deref().unwrap_or("composite").parse::<leaderboard::LeaderboardMetric>() {
+        Ok(metric) => metric,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let weeks = params.weeks.unwrap_or(leaderboard::DEFAULT_LEADERBOARD_WEEKS);
+    let top = params.top.unwrap_or(leaderboard::DEFAULT_LEADERBOARD_TOP);
+    if !(1..=leaderboard::MAX_LEADERBOARD_WEEKS).contains(&weeks) || !(1..=leaderboard::MAX_LEADERBOARD_TOP).contains(&top) {
+        warn!(weeks, top, "Leaderboard size out of range");
+        return ApiError::invalid(format!(
+            "weeks must be between 1 and {} and top between 1 and {}",
+            leaderboard::MAX_LEADERBOARD_WEEKS,
+            leaderboard::MAX_LEADERBOARD_TOP
+        )).into_response();
+    }
+    let precision_mode = match parse_precision(params.precision.as_deref()) {
+        Ok(mode) => mode,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let liquidity = match params.liquidity.as_deref().map(liquidity::preset).transpose() {
+        Ok(liquidity) => liquidity,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+
+    let group = params.group.map(|group| group.to_uppercase());
+    let members = match &group {
+        None => None,
+        Some(group) => match resolve_group(group, &groups_state, &constituents_state) {
+            Ok(members) => Some(members),
+            Err(response) => return response,
+        },
+    };
+
+    let liquidity_name = liquidity.map(|(name, _)| name);
+    let cache_key = format!("leaderboard:{}:{}:{}:{}:{}", group.as_deref().unwrap_or("*"), metric.name(), weeks, top, liquidity_name.unwrap_or("*"));
+    let cached = cache_state.lock().await.get(&cache_key)
+        .filter(|(cached_at, _)| cached_at.elapsed() < Duration::from_secs(ANALYSIS_CACHE_TTL_SECS))
+        .and_then(|(_, value)| serde_json::from_value::<Vec<leaderboard::LeaderboardWeek>>(value.clone()).ok());
+
+    let board = match cached {
+        Some(cached) => cached,
+        None => {
+            let compute_timer = Timer::start("analysis.leaderboard");
+            let points_by_symbol: HashMap<String, Vec<leaderboard::MetricPoint>> = {
+                let data = data_state.lock().await;
+                data.iter()
+                    .filter(|(symbol, _)| members.as_ref().is_none_or(|members| members.may_contain(symbol)))
+                    .filter(|(_, series)| liquidity.is_none_or(|(_, preset)| preset.passes(series)))
+                    .map(|(symbol, series)| {
+                        let scores = indicator_cache::ma_scores(symbol, series);
+                        let strengths = indicator_cache::strength(symbol, series, strength::weights());
+                        let points = scores.iter().zip(strengths.iter())
+                            .map(|(score, strength)| leaderboard::MetricPoint {
+                                date: score.date,
+                                value: metric.value(score, strength),
+                                close: score.close,
+                            })
+                            .collect();
+                        (symbol.clone(), points)
+                    })
+                    .collect()
+            };
+            // Index members are ranked only for the weeks they were in the index
+            let computed = leaderboard::build_leaderboard(&points_by_symbol, weeks, top, |symbol, week_ending| {
+                members.as_ref().is_none_or(|members| members.contains(symbol, week_ending))
+            });
+            compute_timer.stop();
+            if let Ok(value) = serde_json::to_value(&computed) {
+                insert_analysis(&mut *cache_state.lock().await, cache_key, value);
+            }
+            computed
+        }
+    };
+    info!(group, metric = metric.name(), weeks = board.len(), "Returning leaderboard history");
+
+    let mut body = serde_json::json!({
+        "metric": metric.name(),
+        "group": group,
+        "liquidity": liquidity_name,
+        "top": top,
+        "forward_horizons_weeks": leaderboard::FORWARD_HORIZONS_WEEKS,
+        "weeks": precision::to_json(&board, precision_mode),
+    });
+    sources_state.annotate(&mut body).await;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, "max-age=300".parse().unwrap());
+    (StatusCode::OK, headers, Json(body)).into_response()
+}
+
+// Default minimum absolute gap (percent) counted as a gap-up or gap-down
+const DEFAULT_MIN_GAP_PCT: f64 = 0.5;
+
+#[derive(Debug, Deserialize)]
+pub struct GapStatsParams {
+    min_gap: Option<f64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    precision: Option<String>,
+}
+
+#[instrument]
+pub async fn symbol_stats_handler(Path(symbol): Path<String>) -> impl IntoResponse {
+    debug!("Received request for symbol statistics");
+    let symbol = symbol.to_uppercase();
+    match symbol_stats::get(&symbol, market_time::market_today()) {
+        Some(stats) => Json(stats).into_response(),
+        None => ApiError::symbol_not_found(&symbol).into_response(),
+    }
+}
+
+#[instrument(skip(data_state))]
+pub async fn gap_stats_handler(
+    State(data_state): State<SharedData>,
+    Path(symbol): Path<String>,
+    Query(params): Query<GapStatsParams>,
+) -> impl IntoResponse {
+    debug!("Received request for gap statistics");
+
+    let min_gap_pct = params.min_gap.unwrap_or(DEFAULT_MIN_GAP_PCT);
+    if !min_gap_pct.is_finite() || min_gap_pct < 0.0 {
+        warn!(min_gap_pct, "Invalid min_gap");
+        return ApiError::invalid("min_gap must be a non-negative number").into_response();
+    }
+    let precision_mode = match parse_precision(params.precision.as_deref()) {
+        Ok(mode) => mode,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let start_date = match parse_date_param("start_date", params.start_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let end_date = match parse_date_param("end_date", params.end_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+
+    let symbol = symbol.to_uppercase();
+    let gap_points = {
+        let data = data_state.lock().await;
+        match data.get(&symbol) {
+            Some(series) => gaps::calculate_gaps(series),
+            None => {
+                warn!(symbol, "No data for symbol");
+                return ApiError::symbol_not_found(&symbol).into_response();
+            }
+        }
+    };
+    let gap_points: Vec<gaps::GapPoint> = gap_points.into_iter()
+        .filter(|g| start_date.is_none_or(|start| g.date >= start) && end_date.is_none_or(|end| g.date <= end))
+        .collect();
+
+    let stats = gaps::summarize_gaps(&symbol, gap_points, min_gap_pct);
+    info!(symbol, sessions = stats.sessions, gap_ups = stats.gap_up_count, gap_downs = stats.gap_down_count, "Returning gap statistics");
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, "max-age=30".parse().unwrap());
+    (StatusCode::OK, headers, Json(precision::to_json(&stats, precision_mode))).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BasisParams {
+    symbol: Option<String>, // Futures contract, front month by default
+    window: Option<usize>,  // Sessions in the z-score window
+    start_date: Option<String>,
+    end_date: Option<String>,
+    precision: Option<String>,
+}
+
+/// VN30 futures basis (the contract minus the index) per session, with its z-score
+#[instrument(skip(data_state))]
+pub async fn derivatives_basis_handler(
+    State(data_state): State<SharedData>,
+    Query(params): Query<BasisParams>,
+) -> impl IntoResponse {
+    debug!("Received request for derivatives basis");
+
+    let futures_symbol = params.symbol.as_deref().map_or_else(|| basis::FUTURES_SYMBOL.to_string(), str::to_uppercase);
+    if !basis::is_futures(&futures_symbol) {
+        return ApiError::invalid(format!("{} is not a VN30 futures contract", futures_symbol)).into_response();
+    }
+    let window = params.window.unwrap_or(basis::DEFAULT_ZSCORE_WINDOW);
+    if !(2..=250).contains(&window) {
+        return ApiError::invalid("window must be between 2 and 250").into_response();
+    }
+    let precision_mode = match parse_precision(params.precision.as_deref()) {
+        Ok(mode) => mode,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let start_date = match parse_date_param("start_date", params.start_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    let end_date = match parse_date_param("end_date", params.end_date.as_ref()) {
+        Ok(date) => date,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+
+    // The z-score needs the history before start_date, so filter after calculating
+    let points = {
+        let data = data_state.lock().await;
+        let (Some(futures), Some(index)) = (data.get(&futures_symbol), data.get(basis::INDEX_SYMBOL)) else {
+            let missing = if data.contains_key(&futures_symbol) { basis::INDEX_SYMBOL } else { futures_symbol.as_str() };
+            warn!(missing, "No data for basis");
+            return ApiError::symbol_not_found(missing).into_response();
+        };
+        basis::calculate_basis(futures, index, window)
+    };
+    let points: Vec<basis::BasisPoint> = points.into_iter()
+        .filter(|p| start_date.is_none_or(|start| p.date >= start) && end_date.is_none_or(|end| p.date <= end))
+        .collect();
+    info!(futures_symbol, sessions = points.len(), window, "Returning derivatives basis");
+
+    let body = serde_json::json!({
+        "futures_symbol": futures_symbol,
+        "index_symbol": basis::INDEX_SYMBOL,
+        "window": window,
+        "latest": points.last(),
+        "points": points,
+    });
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, "max-age=30".parse().unwrap());
+    (StatusCode::OK, headers, Json(precision::to_json(&body, precision_mode))).into_response()
+}
+
+#[instrument(skip(company_state))]
+pub async fn company_handler(
+    State(company_state): State<SharedCompanyService>,
+    Path(symbol): Path<String>,
+    Query(params): Query<PrecisionParams>,
+) -> impl IntoResponse {
+    debug!("Received request for company info");
+    let precision_mode = match parse_precision(params.precision.as_deref()) {
+        Ok(mode) => mode,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+
+    match company_state.get(&symbol).await {
+        Ok(company) => {
+            info!(symbol = %company.info.symbol, "Returning company info");
+            let mut headers = HeaderMap::new();
+            headers.insert(CACHE_CONTROL, "max-age=300".parse().unwrap());
+            (StatusCode::OK, headers, Json(precision::to_json(&company, precision_mode))).into_response()
+        }
+        Err(VciError::NoData) => {
+            warn!(symbol, "No company info for symbol");
+            ApiError::symbol_not_found(&symbol.to_uppercase()).into_response()
+        }
+        Err(e) => {
+            error!(symbol, error = ?e, "Failed to fetch company info");
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+#[instrument(skip(data_state, quote_state))]
+pub async fn quote_handler(
+    State(data_state): State<SharedData>,
+    State(quote_state): State<SharedQuoteService>,
+    Path(symbol): Path<String>,
+    Query(params): Query<PrecisionParams>,
+) -> impl IntoResponse {
+    debug!("Received request for quote");
+    let precision_mode = match parse_precision(params.precision.as_deref()) {
+        Ok(mode) => mode,
+        Err(message) => return ApiError::invalid(message).into_response(),
+    };
+    // Only tracked tickers are proxied
+    let symbol = symbol.to_uppercase();
+    if !data_state.lock().await.contains_key(&symbol) {
+        return ApiError::symbol_not_found(&symbol).into_response();
+    }
+
+    match quote_state.get(&symbol).await {
+        Ok(quote) => {
+            info!(symbol, age_ms = quote.age_ms, stale = quote.stale, "Returning quote");
+            let mut headers = HeaderMap::new();
+            headers.insert(CACHE_CONTROL, format!("max-age={}", quote::QUOTE_TTL_SECS).parse().unwrap());
+            (StatusCode::OK, headers, Json(precision::to_json(&quote, precision_mode))).into_response()
+        }
+        Err(VciError::NoData) => {
+            warn!(symbol, "No price board row for symbol");
+            ApiError::symbol_not_found(&symbol).into_response()
+        }
+        Err(e) => {
+            error!(symbol, error = ?e, "Failed to fetch quote");
+            ApiError::from(e).into_response()
+        }
+    }
+}
+
+// Comment line sent on idle SSE connections so proxies keep them open
+const SSE_HEARTBEAT_SECS: u64 = 15;
+
+#[derive(Debug, Deserialize)]
+pub struct SseParams {
+    symbols: Option<Vec<String>>, // Repeated and/or comma-separated
+}
+
+// Streamed update with its age at the moment it is sent
+#[derive(Serialize)]
+struct StreamedUpdate<'a> {
+    #[serde(flatten)]
+    update: &'a TickerUpdate,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    age_ms: Option<u64>,
+}
+
+fn ticker_event(kind: &str, update: &TickerUpdate) -> Event {
+    let age_ms = update.ingested_at.map(|ingested_at| freshness::age_ms(ingested_at, Utc::now()));
+    Event::default()
+        .event(kind)
+        .json_data(StreamedUpdate { update, age_ms })
+        .unwrap_or_else(|e| Event::default().event("error").data(e.to_string()))
+}
+
+/// Server-Sent Events stream of ticker updates for clients that cannot hold a WebSocket
+pub async fn sse_tickers_handler(
+    State(data_state): State<SharedData>,
+    State(event_bus): State<SharedEventBus>,
+    State(shutdown): State<CancellationToken>,
+    Query(params): Query<SseParams>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let filter = SymbolFilter::from_params(params.symbols.as_deref().unwrap_or_default());
+    // Subscribe before taking the snapshot so no update falls between the two
+    let receiver = event_bus.subscribe();
+    info!(symbols = filter.len(), "Opened SSE ticker stream");
+    metrics::increment_counter("sse.connections", 1);
+
+    // Current latest bar per matching symbol, so clients start with a full view
+    let snapshot: Vec<Event> = {
+        let data_guard = data_state.lock().await;
+        data_guard.iter()
+            .filter(|(symbol, _)| filter.matches(symbol))
+            .filter_map(|(symbol, series)| series.last().map(|bar| TickerUpdate {
+                symbol: symbol.clone(),
+                source: UpdateSource::Snapshot,
+                bar: bar.clone(),
+                ingested_at: freshness::ingested_at(symbol),
+            }))
+            .map(|update| ticker_event("snapshot", &update))
+            .collect()
+    };
+
+    let updates = stream::unfold((receiver, filter), |(mut receiver, filter)| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(update) if filter.matches(&update.symbol) => {
+                    return Some((ticker_event("ticker", &update), (receiver, filter)));
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    // Slow client: tell it how many updates it missed and carry on
+                    warn!(skipped, "SSE client lagging, dropped updates");
+                    let event = Event::default().event("lagged").data(skipped.to_string());
+                    return Some((event, (receiver, filter)));
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    // Open streams would otherwise hold off graceful shutdown indefinitely
+    let events = stream::iter(snapshot).chain(updates).take_until(shutdown.cancelled_owned()).map(Ok);
+    Sse::new(events).keep_alive(
+        KeepAlive::new()
+            .interval(Duration::from_secs(SSE_HEARTBEAT_SECS))
+            .text("heartbeat"),
+    )
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncChangesParams {
+    epoch: Option<u64>,
+    since: Option<u64>,
+}
+
+/// Symbols changed since a replica's last sync, with the first market date to re-fetch for each
+#[instrument]
+pub async fn sync_changes_handler(Query(params): Query<SyncChangesParams>) -> impl IntoResponse {
+    let change_set = change_log::since(params.epoch, params.since);
+    debug!(version = change_set.version, full_resync = change_set.full_resync, changes = change_set.changes.len(), "Returning sync changes");
+    Json(change_set)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CorrectionParams {
+    since: Option<String>, // RFC 3339 timestamp, or YYYY-MM-DD for the start of that market day
+}
+
+#[derive(Serialize)]
+struct CorrectionFeed {
+    symbol: String,
+    since: Option<DateTime<Utc>>,
+    corrections: Vec<Correction>,
+}
+
+/// Field-level corrections to a symbol's settled bars, oldest first
+#[instrument(skip(data_state))]
+pub async fn corrections_handler(
+    State(data_state): State<SharedData>,
+    Path(symbol): Path<String>,
+    Query(params): Query<CorrectionParams>,
+) -> impl IntoResponse {
+    debug!("Received request for symbol corrections");
+    let since = match params.since.as_deref() {
+        None => None,
+        Some(value) => match DateTime::parse_from_rfc3339(value) {
+            Ok(at) => Some(at.with_timezone(&Utc)),
+            Err(_) => match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+                Ok(date) => Some(market_time::market_day_start(date)),
+                Err(_) => return ApiError::invalid("Invalid since format. Expected an RFC 3339 timestamp or YYYY-MM-DD").into_response(),
+            },
+        },
+    };
+
+    let symbol = symbol.to_uppercase();
+    if !data_state.lock().await.contains_key(&symbol) {
+        warn!(symbol, "No data for symbol");
+        return ApiError::symbol_not_found(&symbol).into_response();
+    }
+    let corrections = corrections::since(&symbol, since);
+    info!(symbol, corrections = corrections.len(), "Returning symbol corrections");
+    Json(CorrectionFeed { symbol, since, corrections }).into_response()
+}
+
+/// JSON Schema of the enhanced `/tickers` response, for frontends to validate against
+pub async fn enhanced_schema_handler() -> impl IntoResponse {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/schema+json"));
+    headers.insert(CACHE_CONTROL, "max-age=3600".parse().unwrap());
+    (StatusCode::OK, headers, Json(schema::enhanced_schema()))
+}
+
+/// Build manifest: crate and dependency versions, features, profile and algorithm versions
+pub async fn version_handler(State(health_state): State<SharedHealthStats>) -> impl IntoResponse {
+    let health = health_state.lock().await;
+    Json(build_info::manifest(health.build_date.clone(), health.git_commit.clone(), health.profile.clone()))
+}
+
+#[instrument(skip(health_state, data_state, company_state, quote_state))]
+pub async fn health_handler(
+    State(health_state): State<SharedHealthStats>,
+    State(data_state): State<SharedData>,
+    State(company_state): State<SharedCompanyService>,
+    State(quote_state): State<SharedQuoteService>,
+) -> impl IntoResponse {
+    debug!("Received request for health stats");
+    
+    let mut health_stats = health_state.lock().await.clone();
+    
+    // Calculate current memory usage dynamically
+    {
+        let data_guard = data_state.lock().await;
+        let memory_bytes = crate::data_structures::estimate_memory_usage(&data_guard);
+        let memory_mb = memory_bytes as f64 / (1024.0 * 1024.0);
+        let memory_percent = (memory_bytes as f64 / crate::data_structures::MAX_MEMORY_BYTES as f64) * 100.0;
+        
+        health_stats.memory_usage_bytes = memory_bytes;
+        health_stats.memory_usage_mb = memory_mb;
+        health_stats.memory_usage_percent = memory_percent;
+        health_stats.active_tickers_count = data_guard.len();
+    }
+    health_stats.provider_quota = provider_quota::usage_report();
+    health_stats.internal_peers = gossip::peer_report();
+    health_stats.company_cache = company_state.stats().await;
+    health_stats.quote_cache = quote_state.stats().await;
+    
+    info!(
+        is_office_hours = health_stats.is_office_hours,
+        current_interval_secs = health_stats.current_interval_secs,
+        active_tickers = health_stats.active_tickers_count,
+        total_tickers = health_stats.total_tickers_count,
+        memory_mb = format!("{:.2}", health_stats.memory_usage_mb),
+        memory_percent = format!("{:.1}%", health_stats.memory_usage_percent),
+        iteration_count = health_stats.iteration_count,
+        "Returning health stats"
+    );
+    
+    (StatusCode::OK, Json(health_stats))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MetricsParams {
+    format: Option<String>, // "json" (default) or "prometheus"
+}
+
+#[instrument(skip(health_state, request_headers))]
+pub async fn metrics_handler(
+    State(health_state): State<SharedHealthStats>,
+    Query(params): Query<MetricsParams>,
+    request_headers: HeaderMap,
+) -> Response {
+    debug!("Received request for metrics");
+    // Prometheus scrapers ask for text/plain or OpenMetrics; browsers and scripts get JSON
+    let accept = request_headers.get(ACCEPT).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    let prometheus = match params.format.as_deref() {
+        Some("prometheus") => true,
+        Some("json") => false,
+        None => accept.contains("openmetrics") || (accept.contains("text/plain") && !accept.contains("application/json")),
+        Some(other) => return ApiError::invalid(format!("Invalid format '{}'. Expected json or prometheus", other)).into_response(),
+    };
+
+    let report = metrics::get_performance_report();
+    let providers = provider_quota::usage_report();
+    let gossip_duplicates = gossip_dedup::duplicates_by_sender();
+    if !prometheus {
+        let body = serde_json::json!({
+            "timings": report.timings,
+            "counters": report.counters,
+            "providers": providers,
+            "gossip_duplicates": gossip_duplicates,
+        });
+        return (StatusCode::OK, Json(body)).into_response();
+    }
+
+    let (memory_usage_bytes, memory_limit_mb, active_tickers) = {
+        let health = health_state.lock().await;
+        (health.memory_usage_bytes, health.memory_limit_mb, health.active_tickers_count)
+    };
+    let mut out = metrics::PrometheusText::default();
+    report.write_prometheus(&mut out);
+    let provider = |name: &String| vec![("provider", name.clone())];
+    out.family("provider_requests_total", "counter", "Requests sent to each upstream provider",
+        providers.iter().map(|(name, usage)| ("", provider(name), usage.total_requests as f64)));
+    out.family("provider_failures_total", "counter", "Failed upstream requests by kind",
+        providers.iter().flat_map(|(name, usage)| [
+            ("status_403", usage.status_403),
+            ("status_429", usage.status_429),
+            ("server_error", usage.server_errors),
+            ("transport_error", usage.transport_errors),
+        ].map(|(kind, count)| ("", vec![("provider", name.clone()), ("kind", kind.to_string())], count as f64))));
+    out.family("provider_requests_last_minute", "gauge", "Upstream requests in the last minute",
+        providers.iter().map(|(name, usage)| ("", provider(name), usage.requests_last_minute as f64)));
+    out.family("provider_in_backoff", "gauge", "1 while retries to the provider are backing off",
+        providers.iter().map(|(name, usage)| ("", provider(name), if usage.in_backoff { 1.0 } else { 0.0 })));
+    out.family("provider_under_pressure", "gauge", "1 while a quota warning threshold is exceeded",
+        providers.iter().map(|(name, usage)| ("", provider(name), if usage.under_pressure { 1.0 } else { 0.0 })));
+    out.family("gossip_duplicates_total", "counter", "Duplicate gossip requests by sending peer account or IP",
+        gossip_duplicates.iter().map(|(sender, count)| ("", vec![("sender", sender.clone())], *count as f64)));
+    out.family("memory_usage_bytes", "gauge", "Estimated size of the in-memory ticker data",
+        [("", Vec::new(), memory_usage_bytes as f64)]);
+    out.family("memory_limit_bytes", "gauge", "Memory limit for ticker data before eviction",
+        [("", Vec::new(), (memory_limit_mb * 1024 * 1024) as f64)]);
+    out.family("active_tickers", "gauge", "Symbols held in memory",
+        [("", Vec::new(), active_tickers as f64)]);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/plain; version=0.0.4; charset=utf-8"));
+    (StatusCode::OK, headers, out.finish()).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClearCacheParams {
+    #[serde(rename = "clearCache")]
+    clear_cache: Option<bool>,
+}
 
 #[instrument(skip_all, fields(path = %path))]
 pub async fn raw_proxy_handler(
+    State(mirrors_state): State<SharedRawMirrors>,
+    State(object_store_state): State<SharedObjectStore>,
     Path(path): Path<String>,
     Query(params): Query<ClearCacheParams>,
+    request_headers: HeaderMap,
 ) -> impl IntoResponse {
     debug!(path, clear_cache = ?params.clear_cache, "Received raw proxy request");
 
@@ -328,11 +2816,7 @@ pub async fn raw_proxy_handler(
         match cache::read_cache(&path) {
             Ok(content) => {
                 info!(path, content_size = content.len(), "Serving from cache");
-                let content_type = get_content_type(&path);
-                let mut headers = HeaderMap::new();
-                headers.insert(CACHE_CONTROL, "max-age=30".parse().unwrap());
-                headers.insert("content-type", content_type.parse().unwrap());
-                return (StatusCode::OK, headers, content).into_response();
+                return build_raw_response(&path, content, &request_headers);
             }
             Err(e) => {
                 warn!(path, ?e, "Failed to read from cache, fetching from GitHub");
@@ -340,52 +2824,124 @@ pub async fn raw_proxy_handler(
         }
     }
 
-    // If clearCache is requested, remove the cached file
-    if should_clear_cache {
-        if let Err(e) = cache::clear_cache(&path) {
-            warn!(path, ?e, "Failed to clear cache");
+    // Object storage is a persistent tier shared across restarts and replicas
+    let stored = match &object_store_state {
+        Some(store) => match store.get(&path).await {
+            Ok(stored) => stored,
+            Err(e) => {
+                warn!(path, error = %e, "Failed to read from object storage");
+                None
+            }
+        },
+        None => None,
+    };
+    if !should_clear_cache
+        && let Some(stored) = &stored
+        && stored.last_modified.is_some_and(|modified| (Utc::now() - modified).num_seconds() < cache::CACHE_TTL_SECS as i64)
+    {
+        info!(path, content_size = stored.content.len(), "Serving from object storage");
+        if let Err(e) = cache::write_cache(&path, &stored.content) {
+            warn!(path, ?e, "Failed to write to cache");
+        }
+        return build_raw_response(&path, stored.content.clone(), &request_headers);
+    }
+
+    // Fetch from the data repository, falling back through mirrors when throttled.
+    // The cached copy (even with clearCache) is only replaced once the download validates.
+    let previous = cache::read_cache(&path).ok().or(stored.map(|stored| stored.content));
+    match mirrors_state.fetch(&path).await {
+        Ok(content) => {
+            if let Err(e) = integrity::validate_download(&mirrors_state, &path, &content, previous.as_deref()).await {
+                return match previous {
+                    Some(previous) => {
+                        warn!(path, error = %e, "Downloaded file failed validation, serving previous cached copy");
+                        build_raw_response(&path, previous, &request_headers)
+                    }
+                    None => {
+                        error!(path, error = %e, "Downloaded file failed validation");
+                        ApiError::from(e).into_response()
+                    }
+                };
+            }
+
+            // Cache the content
+            if let Err(e) = cache::write_cache(&path, &content) {
+                warn!(path, ?e, "Failed to write to cache");
+            }
+            if let Some(store) = object_store_state.clone() {
+                let (path, content) = (path.clone(), content.clone());
+                tokio::spawn(async move {
+                    if let Err(e) = store.put(&path, content).await {
+                        warn!(path, error = %e, "Failed to write to object storage");
+                    }
+                });
+            }
+
+            build_raw_response(&path, content, &request_headers)
+        }
+        Err(MirrorFetchError::NotFound) => {
+            warn!(path, "File not found on GitHub");
+            // Drop any copy of a file that no longer exists upstream
+            if let Err(e) = cache::clear_cache(&path) {
+                warn!(path, ?e, "Failed to clear cache");
+            }
+            ApiError::from(MirrorFetchError::NotFound).into_response()
         }
+        Err(MirrorFetchError::AllMirrorsFailed) => match previous {
+            Some(previous) => {
+                warn!(path, "All mirrors failed, serving stale cached copy");
+                build_raw_response(&path, previous, &request_headers)
+            }
+            None => {
+                error!(path, "All mirrors failed");
+                ApiError::from(MirrorFetchError::AllMirrorsFailed).into_response()
+            }
+        },
     }
+}
 
-    // Fetch from GitHub
-    let github_url = format!("{}{}", GITHUB_RAW_BASE_URL, path);
-    debug!(path, github_url, "Fetching from GitHub");
+/// Build the response for a raw file, honouring `Range` / `If-Range` so large
+/// downloads can be resumed against the same content version (identified by ETag)
+fn build_raw_response(path: &str, content: Vec<u8>, request_headers: &HeaderMap) -> Response {
+    let content_type = get_content_type(path);
+    let etag = http_range::compute_etag(&content);
 
-    match reqwest::get(&github_url).await {
-        Ok(response) => {
-            if response.status().is_success() {
-                match response.bytes().await {
-                    Ok(bytes) => {
-                        let content = bytes.to_vec();
-                        info!(path, content_size = content.len(), "Fetched from GitHub");
+    let mut headers = HeaderMap::new();
+    headers.insert(CACHE_CONTROL, "max-age=30".parse().unwrap());
+    headers.insert("content-type", content_type.parse().unwrap());
+    ranged_response(path, headers, &etag, content, request_headers)
+}
 
-                        // Cache the content
-                        if let Err(e) = cache::write_cache(&path, &content) {
-                            warn!(path, ?e, "Failed to write to cache");
-                        }
+/// Serve `content` whole, or the byte range a `Range` header asks for while `If-Range` (if sent)
+/// still matches the strong `etag`
+fn ranged_response(path: &str, mut headers: HeaderMap, etag: &str, content: Vec<u8>, request_headers: &HeaderMap) -> Response {
+    headers.insert(ACCEPT_RANGES, "bytes".parse().unwrap());
+    headers.insert(ETAG, etag.parse().unwrap());
 
-                        let content_type = get_content_type(&path);
-                        let mut headers = HeaderMap::new();
-                        headers.insert(CACHE_CONTROL, "max-age=30".parse().unwrap());
-                        headers.insert("content-type", content_type.parse().unwrap());
-                        (StatusCode::OK, headers, content).into_response()
-                    }
-                    Err(e) => {
-                        error!(path, ?e, "Failed to read response body");
-                        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read response body").into_response()
-                    }
-                }
-            } else if response.status() == reqwest::StatusCode::NOT_FOUND {
-                warn!(path, "File not found on GitHub");
-                (StatusCode::NOT_FOUND, "File not found").into_response()
-            } else {
-                error!(path, status = ?response.status(), "GitHub request failed");
-                (StatusCode::BAD_GATEWAY, "Failed to fetch from GitHub").into_response()
-            }
+    let range_header = request_headers.get(RANGE).and_then(|h| h.to_str().ok());
+    let if_range = request_headers.get(IF_RANGE).and_then(|h| h.to_str().ok());
+
+    let range_request = match range_header {
+        Some(value) if http_range::if_range_matches(if_range, etag) => http_range::parse_range(value, content.len()),
+        Some(_) => {
+            debug!(path, "If-Range does not match current ETag, serving full content");
+            RangeRequest::Full
         }
-        Err(e) => {
-            error!(path, ?e, "Failed to fetch from GitHub");
-            (StatusCode::BAD_GATEWAY, "Failed to fetch from GitHub").into_response()
+        None => RangeRequest::Full,
+    };
+
+    match range_request {
+        RangeRequest::Full => (StatusCode::OK, headers, content).into_response(),
+        RangeRequest::Partial(range) => {
+            debug!(path, start = range.start, end = range.end, total = content.len(), "Serving partial content");
+            let content_range = format!("bytes {}-{}/{}", range.start, range.end - 1, content.len());
+            headers.insert(CONTENT_RANGE, content_range.parse().unwrap());
+            (StatusCode::PARTIAL_CONTENT, headers, content[range].to_vec()).into_response()
+        }
+        RangeRequest::Unsatisfiable => {
+            warn!(path, range = ?range_header, total = content.len(), "Unsatisfiable range requested");
+            headers.insert(CONTENT_RANGE, format!("bytes */{}", content.len()).parse().unwrap());
+            (StatusCode::RANGE_NOT_SATISFIABLE, headers).into_response()
         }
     }
 }
@@ -0,0 +1,208 @@
+use crate::analysis::indicator_cache;
+use crate::data_structures::InMemoryData;
+use crate::utils::integrity::sha256_hex;
+use crate::utils::market_time::{format_market_date, market_date};
+use crate::utils::object_store::ObjectStore;
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const PARTITION_FILE: &str = "bars.csv";
+const CSV_HEADER: &str = "symbol,time,open,high,low,close,volume,ma10,ma20,ma50,ma10_score,ma20_score,ma50_score";
+
+// One immutable day partition
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartitionEntry {
+    pub date: NaiveDate,
+    pub path: String, // Relative to the archive directory
+    pub rows: usize,
+    pub sha256: String,
+    pub created_at: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub partitions: Vec<PartitionEntry>,
+}
+
+fn partition_dir(archive_dir: &Path, date: NaiveDate) -> PathBuf {
+    archive_dir.join(format!("date={}", date.format("%Y-%m-%d")))
+}
+
+pub fn load_manifest(archive_dir: &Path) -> io::Result<ArchiveManifest> {
+    let manifest_path = archive_dir.join(MANIFEST_FILE);
+    if !manifest_path.exists() {
+        return Ok(ArchiveManifest::default());
+    }
+    let content = fs::read_to_string(&manifest_path)?;
+    serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Write a file atomically (temp file + rename) so readers never see a partial write
+fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, content)?;
+    fs::rename(&tmp_path, path)
+}
+
+fn format_optional(value: Option<f64>) -> String {
+    value.map(|v| format!("{:.4}", v)).unwrap_or_default()
+}
+
+/// Render the CSV rows for each date, with MA indicators computed over the full series
+fn render_partitions(data: &InMemoryData, dates: &[NaiveDate]) -> BTreeMap<NaiveDate, Vec<String>> {
+    let mut rows_by_date: BTreeMap<NaiveDate, Vec<String>> = dates.iter().map(|d| (*d, Vec::new())).collect();
+
+    let mut symbols: Vec<&String> = data.keys().collect();
+    symbols.sort();
+
+    for symbol in symbols {
+        let series = &data[symbol];
+        let scores = indicator_cache::ma_scores(symbol, series);
+        for (bar, score) in series.iter().zip(scores.iter()) {
+            if let Some(rows) = rows_by_date.get_mut(&score.date) {
+                rows.push(format!(
+                    "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                    symbol,
+                    format_market_date(bar.time),
+                    bar.open,
+                    bar.high,
+                    bar.low,
+                    bar.close,
+                    bar.volume,
+                    format_optional(score.ma10),
+                    format_optional(score.ma20),
+                    format_optional(score.ma50),
+                    format_optional(score.ma10_score),
+                    format_optional(score.ma20_score),
+                    format_optional(score.ma50_score),
+                ));
+            }
+        }
+    }
+
+    rows_by_date
+}
+
+/// Write a partition for every finalized date not yet archived.
+/// `is_finalized` decides which dates are complete; existing partitions are never rewritten.
+/// Returns the dates written.
+pub fn archive_finalized_days<F>(data: &InMemoryData, archive_dir: &Path, is_finalized: F) -> io::Result<Vec<NaiveDate>>
+where
+    F: Fn(NaiveDate) -> bool,
+{
+    fs::create_dir_all(archive_dir)?;
+    let mut manifest = load_manifest(archive_dir)?;
+    let archived: HashSet<NaiveDate> = manifest.partitions.iter().map(|p| p.date).collect();
+
+    let mut pending: Vec<NaiveDate> = data.values()
+        .flat_map(|series| series.iter().map(|bar| market_date(bar.time)))
+        .filter(|date| is_finalized(*date) && !archived.contains(date))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    pending.sort();
+
+    if pending.is_empty() {
+        debug!("No new finalized days to archive");
+        return Ok(Vec::new());
+    }
+
+    let mut written = Vec::new();
+    for (date, rows) in render_partitions(data, &pending) {
+        let dir = partition_dir(archive_dir, date);
+        let file_path = dir.join(PARTITION_FILE);
+        // Partitions are immutable: never overwrite one left by an earlier run
+        if file_path.exists() {
+            debug!(%date, "Partition already exists on disk, skipping");
+            continue;
+        }
+        fs::create_dir_all(&dir)?;
+
+        let content = format!("{}\n{}\n", CSV_HEADER, rows.join("\n"));
+        write_atomic(&file_path, content.as_bytes())?;
+
+        manifest.partitions.push(PartitionEntry {
+            date,
+            path: format!("date={}/{}", date.format("%Y-%m-%d"), PARTITION_FILE),
+            rows: rows.len(),
+            sha256: sha256_hex(content.as_bytes()),
+            created_at: Utc::now().to_rfc3339(),
+        });
+        written.push(date);
+    }
+
+    manifest.partitions.sort_by_key(|p| p.date);
+    let manifest_json = serde_json::to_vec_pretty(&manifest).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    write_atomic(&archive_dir.join(MANIFEST_FILE), &manifest_json)?;
+
+    info!(partitions = written.len(), first = ?written.first(), last = ?written.last(), "Archived finalized days");
+    Ok(written)
+}
+
+/// Copy newly written partitions and the manifest to object storage under `archive/`
+pub async fn upload_partitions(store: &ObjectStore, archive_dir: &Path, dates: &[NaiveDate]) {
+    let files = dates.iter()
+        .map(|date| format!("date={}/{}", date.format("%Y-%m-%d"), PARTITION_FILE))
+        .chain(std::iter::once(MANIFEST_FILE.to_string()));
+
+    for relative_path in files {
+        let content = match fs::read(archive_dir.join(&relative_path)) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!(relative_path, error = ?e, "Failed to read archive file for upload");
+                continue;
+            }
+        };
+        if let Err(e) = store.put(&format!("archive/{}", relative_path), content).await {
+            warn!(relative_path, error = %e, "Failed to upload archive file");
+        }
+    }
+    info!(partitions = dates.len(), "Uploaded archive partitions to object storage");
+}
+
+/// Index of archived partitions by date, for readers of the archive
+pub fn partition_index(archive_dir: &Path) -> io::Result<HashMap<NaiveDate, PathBuf>> {
+    Ok(load_manifest(archive_dir)?
+        .partitions
+        .into_iter()
+        .map(|p| (p.date, archive_dir.join(p.path)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_bars::bar;
+
+    #[test]
+    fn test_archive_writes_finalized_days_once() {
+        let archive_dir = std::env::temp_dir().join(format!("aipriceaction-archive-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&archive_dir);
+
+        let mut data = InMemoryData::new();
+        data.insert("VCB".to_string(), (0..3).map(|d| bar(d, 60.0 + d as f64, 100)).collect());
+        let cutoff = NaiveDate::from_ymd_opt(2025, 8, 3).unwrap();
+
+        let written = archive_finalized_days(&data, &archive_dir, |date| date < cutoff).unwrap();
+        assert_eq!(written.len(), 2);
+
+        // Re-running, even with a later cutoff for old days, leaves existing partitions untouched
+        data.get_mut("VCB").unwrap()[0].close = 99.0;
+        let written = archive_finalized_days(&data, &archive_dir, |_| true).unwrap();
+        assert_eq!(written, vec![cutoff]);
+
+        let index = partition_index(&archive_dir).unwrap();
+        assert_eq!(index.len(), 3);
+        let first = fs::read_to_string(&index[&NaiveDate::from_ymd_opt(2025, 8, 1).unwrap()]).unwrap();
+        assert!(first.starts_with(CSV_HEADER));
+        assert!(first.contains("VCB,2025-08-01,60,60,60,60,100"));
+
+        fs::remove_dir_all(&archive_dir).unwrap();
+    }
+}
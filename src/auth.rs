@@ -0,0 +1,163 @@
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::config::TokenConfig;
+
+// What a token may do
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenRole {
+    Peer,  // Internal gossip between trusted nodes
+    Admin, // Peer access plus the /admin endpoints
+}
+
+impl TokenRole {
+    fn allows(self, required: TokenRole) -> bool {
+        self == TokenRole::Admin || self == required
+    }
+}
+
+// A named credential, so each peer node can be revoked on its own
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TokenAccount {
+    pub name: String,
+    pub token: String,
+    pub role: TokenRole,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Account as listed by `/admin/tokens`; the token value is never returned
+#[derive(Clone, Debug, Serialize)]
+pub struct TokenSummary {
+    pub name: String,
+    pub role: TokenRole,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub expired: bool,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuthFailure {
+    Missing,
+    Invalid,
+    Expired(String),
+    Revoked(String),
+    Forbidden(String), // Valid token without the required role
+}
+
+/// Configured accounts plus revocations made at runtime. Revocations live in
+/// memory only: remove the account from configuration before the next restart.
+#[derive(Debug, Default)]
+pub struct TokenRegistry {
+    accounts: Vec<TokenAccount>,
+    revoked: HashMap<String, DateTime<Utc>>,
+}
+
+pub type SharedTokenRegistry = Arc<Mutex<TokenRegistry>>;
+
+impl TokenRegistry {
+    /// The legacy primary/secondary pair become peer accounts named "primary" and "secondary"
+    pub fn from_config(config: &TokenConfig) -> Self {
+        let mut accounts: Vec<TokenAccount> = [("primary", &config.primary), ("secondary", &config.secondary)]
+            .into_iter()
+            .filter(|(_, token)| !token.is_empty())
+            .map(|(name, token)| TokenAccount { name: name.to_string(), token: token.clone(), role: TokenRole::Peer, expires_at: None })
+            .collect();
+        for account in &config.accounts {
+            if accounts.iter().any(|existing| existing.name == account.name) {
+                warn!(name = account.name, "Duplicate token account name, ignoring");
+                continue;
+            }
+            accounts.push(account.clone());
+        }
+        info!(accounts = accounts.len(), admins = accounts.iter().filter(|a| a.role == TokenRole::Admin).count(), "Loaded token accounts");
+        Self { accounts, revoked: HashMap::new() }
+    }
+
+    /// Resolve a bearer token to its account, checking expiry, revocation and role
+    pub fn authenticate(&self, token: Option<&str>, required: TokenRole, now: DateTime<Utc>) -> Result<&TokenAccount, AuthFailure> {
+        let token = token.filter(|t| !t.is_empty()).ok_or(AuthFailure::Missing)?;
+        let account = self.accounts.iter().find(|a| a.token == token).ok_or(AuthFailure::Invalid)?;
+        if self.revoked.contains_key(&account.name) {
+            return Err(AuthFailure::Revoked(account.name.clone()));
+        }
+        if account.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(AuthFailure::Expired(account.name.clone()));
+        }
+        if !account.role.allows(required) {
+            return Err(AuthFailure::Forbidden(account.name.clone()));
+        }
+        Ok(account)
+    }
+
+    /// Revoke an account by name; returns the revocation time, or None if unknown
+    pub fn revoke(&mut self, name: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.accounts.iter().any(|a| a.name == name)
+            .then(|| *self.revoked.entry(name.to_string()).or_insert(now))
+    }
+
+    /// Lift a runtime revocation; returns false if the account is unknown
+    pub fn restore(&mut self, name: &str) -> bool {
+        self.revoked.remove(name);
+        self.accounts.iter().any(|a| a.name == name)
+    }
+
+    pub fn summaries(&self, now: DateTime<Utc>) -> Vec<TokenSummary> {
+        self.accounts.iter().map(|account| TokenSummary {
+            name: account.name.clone(),
+            role: account.role,
+            expires_at: account.expires_at,
+            expired: account.expires_at.is_some_and(|expires_at| expires_at <= now),
+            revoked_at: self.revoked.get(&account.name).copied(),
+        }).collect()
+    }
+}
+
+/// Token from an `Authorization: Bearer <token>` header
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers.get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .map(|header| header.trim_start_matches("Bearer ").trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_roles_expiry_and_revocation() {
+        let now = Utc::now();
+        let config = TokenConfig {
+            primary: "legacy-a".to_string(),
+            secondary: "legacy-b".to_string(),
+            node_token: None,
+            accounts: vec![
+                TokenAccount { name: "node-02".to_string(), token: "peer-2".to_string(), role: TokenRole::Peer, expires_at: None },
+                TokenAccount { name: "old".to_string(), token: "peer-old".to_string(), role: TokenRole::Peer, expires_at: Some(now - Duration::days(1)) },
+                TokenAccount { name: "ops".to_string(), token: "admin-1".to_string(), role: TokenRole::Admin, expires_at: None },
+            ],
+        };
+        let mut registry = TokenRegistry::from_config(&config);
+
+        assert_eq!(registry.authenticate(Some("legacy-b"), TokenRole::Peer, now).unwrap().name, "secondary");
+        assert_eq!(registry.authenticate(Some("admin-1"), TokenRole::Peer, now).unwrap().name, "ops");
+        assert_eq!(registry.authenticate(Some("peer-2"), TokenRole::Admin, now).unwrap_err(), AuthFailure::Forbidden("node-02".to_string()));
+        assert_eq!(registry.authenticate(Some("peer-old"), TokenRole::Peer, now).unwrap_err(), AuthFailure::Expired("old".to_string()));
+        assert_eq!(registry.authenticate(Some("nope"), TokenRole::Peer, now).unwrap_err(), AuthFailure::Invalid);
+        assert_eq!(registry.authenticate(None, TokenRole::Peer, now).unwrap_err(), AuthFailure::Missing);
+
+        assert_eq!(registry.revoke("node-02", now), Some(now));
+        assert_eq!(registry.authenticate(Some("peer-2"), TokenRole::Peer, now).unwrap_err(), AuthFailure::Revoked("node-02".to_string()));
+        assert!(registry.authenticate(Some("legacy-a"), TokenRole::Peer, now).is_ok());
+        assert_eq!(registry.revoke("ghost", now), None);
+        assert!(registry.restore("node-02"));
+        assert!(registry.authenticate(Some("peer-2"), TokenRole::Peer, now).is_ok());
+    }
+}
@@ -0,0 +1,247 @@
+use crate::aggregate::column_index;
+use crate::config::load_ticker_groups;
+use crate::export::{self, CsvColumn, CsvLayout, EnhancedRow, TableFormat};
+use crate::ticker_info::TickerDirectory;
+use crate::utils::market_time::{market_date, market_day_start, market_today};
+use crate::utils::mirrors::{MirrorFetchError, RawMirrors, DEFAULT_RAW_MIRRORS};
+use crate::vci::{OhlcvData, VciClient};
+use chrono::{Duration, NaiveDate};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "Usage: aipriceaction-proxy backfill --from <YYYY-MM-DD> [--to <YYYY-MM-DD>] [--output <dir>] [--symbols VCB,FPT] [--format csv|parquet|arrow]";
+const BATCH_SIZE: usize = 10;
+// Always fetched: VNINDEX also supplies the trading calendar continuity is checked against
+const CALENDAR_SYMBOL: &str = "VNINDEX";
+const INDEX_SYMBOLS: [&str; 2] = [CALENDAR_SYMBOL, "VN30"];
+const OHLCV_COLUMNS: [CsvColumn; 6] = [CsvColumn::Time, CsvColumn::Open, CsvColumn::High, CsvColumn::Low, CsvColumn::Close, CsvColumn::Volume];
+
+/// Daily bars from a data repository CSV (`time` or `date` plus OHLCV columns) within `from..=to`
+pub fn parse_history_csv(content: &str, from: NaiveDate, to: NaiveDate) -> Vec<OhlcvData> {
+    let mut lines = content.lines();
+    let Some(header) = lines.next() else { return Vec::new() };
+    let columns: Vec<&str> = header.split(',').collect();
+    let indices = ["time|date", "open", "high", "low", "close", "volume"]
+        .map(|names| column_index(&columns, &names.split('|').collect::<Vec<_>>()));
+    let [Some(time), Some(open), Some(high), Some(low), Some(close), Some(volume)] = indices else { return Vec::new() };
+
+    let mut bars: Vec<OhlcvData> = lines
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let date = NaiveDate::parse_from_str(fields.get(time)?.get(..10)?, "%Y-%m-%d").ok()?;
+            let price = |index: usize| fields.get(index)?.parse::<f64>().ok();
+            Some(OhlcvData {
+                time: market_day_start(date),
+                open: price(open)?,
+                high: price(high)?,
+                low: price(low)?,
+                close: price(close)?,
+                volume: fields.get(volume)?.parse::<f64>().ok()? as u64,
+                symbol: None,
+            })
+        })
+        .filter(|bar| (from..=to).contains(&market_date(bar.time)))
+        .collect();
+    bars.sort_by_key(|bar| bar.time);
+    bars.dedup_by_key(|bar| bar.time);
+    bars
+}
+
+/// Trading days in `calendar` between a series' first and last bar that it has no bar for
+pub fn missing_days(series: &[OhlcvData], calendar: &BTreeSet<NaiveDate>) -> Vec<NaiveDate> {
+    let dates: BTreeSet<NaiveDate> = series.iter().map(|bar| market_date(bar.time)).collect();
+    let (Some(first), Some(last)) = (dates.first(), dates.last()) else { return Vec::new() };
+    calendar.range(first..=last).filter(|date| !dates.contains(date)).copied().collect()
+}
+
+/// A series as a per-ticker file with `time` and OHLCV columns; as CSV it is the input format of `aggregate`
+pub fn to_table(ticker: &str, series: &[OhlcvData], format: TableFormat) -> Result<Vec<u8>, String> {
+    let rows: Vec<EnhancedRow> = series.iter()
+        .map(|bar| EnhancedRow { bar: bar.clone(), score: Default::default(), strength: Default::default(), indicators: Default::default(), averages: Default::default(), foreign_net_value: None, basis: None, basis_pct: None })
+        .collect();
+    let layout = CsvLayout { columns: OHLCV_COLUMNS.to_vec(), header: true };
+    export::encode_enhanced(&BTreeMap::from([(ticker.to_string(), rows)]), &layout, &TickerDirectory::default(), format)
+}
+
+// Where one ticker's bars came from
+#[derive(Default)]
+struct TickerHistory {
+    bars: Vec<OhlcvData>,
+    csv_bars: usize,
+    vci_bars: usize,
+}
+
+/// `backfill` subcommand: downloads daily history for every ticker from the data repository CSVs,
+/// fills what they lack up to `--to` from VCI, checks continuity against the VNINDEX calendar and
+/// writes `<TICKER>.csv` (or `.parquet`/`.arrow` with `--format`) plus `continuity.csv`. Returns the exit code.
+pub async fn run_cli(args: &[String]) -> i32 {
+    let mut from: Option<NaiveDate> = None;
+    let mut to = market_today();
+    let mut output = PathBuf::from("backfill");
+    let mut symbols: Option<Vec<String>> = None;
+    let mut format = TableFormat::Csv;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let parse_date = |value: &str| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok();
+        match (arg.as_str(), iter.next()) {
+            ("--from", Some(value)) if parse_date(value).is_some() => from = parse_date(value),
+            ("--to", Some(value)) if parse_date(value).is_some() => to = parse_date(value).unwrap_or(to),
+            ("--output", Some(value)) => output = PathBuf::from(value),
+            ("--format", Some(value)) if value.parse::<TableFormat>().is_ok() => format = value.parse().unwrap_or(format),
+            ("--symbols", Some(value)) => symbols = Some(value.split(',').map(|s| s.trim().to_uppercase()).filter(|s| !s.is_empty()).collect()),
+            _ => {
+                eprintln!("{}", USAGE);
+                return 2;
+            }
+        }
+    }
+    let Some(from) = from.filter(|from| *from <= to) else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+
+    let mut tickers: Vec<String> = symbols.unwrap_or_else(|| load_ticker_groups().0.values().flatten().cloned().collect());
+    tickers.extend(INDEX_SYMBOLS.map(String::from));
+    tickers.sort();
+    tickers.dedup();
+    if let Err(e) = fs::create_dir_all(&output) {
+        eprintln!("Failed to create {}: {}", output.display(), e);
+        return 1;
+    }
+    println!("Backfilling {} tickers from {} to {}", tickers.len(), from, to);
+
+    // Data repository CSVs first; they are built from the same VCI feed
+    let mirror_urls: Vec<String> = DEFAULT_RAW_MIRRORS.iter().map(|s| s.to_string()).collect();
+    let mirrors = RawMirrors::new(&mirror_urls, None);
+    let mut histories: BTreeMap<String, TickerHistory> = BTreeMap::new();
+    for ticker in &tickers {
+        let bars = match mirrors.fetch(&format!("market_data/{}.csv", ticker)).await {
+            Ok(content) => parse_history_csv(&String::from_utf8_lossy(&content), from, to),
+            Err(MirrorFetchError::NotFound) => Vec::new(),
+            Err(MirrorFetchError::AllMirrorsFailed) => {
+                eprintln!("{}: data repository unavailable, using VCI only", ticker);
+                Vec::new()
+            }
+        };
+        histories.insert(ticker.clone(), TickerHistory { csv_bars: bars.len(), bars, vci_bars: 0 });
+    }
+
+    // Then VCI for whatever the CSVs don't reach, batched by the date each ticker needs bars from
+    let client = match VciClient::new(true) {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Failed to create VCI client: {:?}", e);
+            return 1;
+        }
+    };
+    let mut by_start: BTreeMap<NaiveDate, Vec<String>> = BTreeMap::new();
+    for (ticker, history) in &histories {
+        let start = history.bars.last().map_or(from, |bar| market_date(bar.time) + Duration::days(1));
+        if start <= to {
+            by_start.entry(start).or_default().push(ticker.clone());
+        }
+    }
+    let end = to.format("%Y-%m-%d").to_string();
+    for (start, batch_tickers) in by_start {
+        let start = start.format("%Y-%m-%d").to_string();
+        for batch in batch_tickers.chunks(BATCH_SIZE) {
+            match client.get_batch_history(batch, &start, Some(&end), "1D").await {
+                Ok(fetched) => {
+                    for (ticker, bars) in fetched {
+                        let Some(history) = histories.get_mut(&ticker) else { continue };
+                        let bars: Vec<OhlcvData> = bars.unwrap_or_default().into_iter()
+                            .filter(|bar| (from..=to).contains(&market_date(bar.time)))
+                            .map(|bar| OhlcvData { time: market_day_start(market_date(bar.time)), symbol: None, ..bar })
+                            .collect();
+                        history.vci_bars = bars.len();
+                        history.bars.extend(bars);
+                        history.bars.sort_by_key(|bar| bar.time);
+                        history.bars.dedup_by_key(|bar| bar.time);
+                    }
+                }
+                Err(e) => eprintln!("VCI batch {} failed: {:?}", batch.join(","), e),
+            }
+        }
+    }
+
+    let calendar: BTreeSet<NaiveDate> = match histories.get(CALENDAR_SYMBOL).filter(|history| !history.bars.is_empty()) {
+        Some(history) => history.bars.iter().map(|bar| market_date(bar.time)).collect(),
+        None => histories.values().flat_map(|history| history.bars.iter().map(|bar| market_date(bar.time))).collect(),
+    };
+    let mut report = String::from("ticker,first_date,last_date,bars,csv_bars,vci_bars,missing_days,missing_dates\n");
+    let mut empty = Vec::new();
+    for (ticker, history) in &histories {
+        if history.bars.is_empty() {
+            empty.push(ticker.as_str());
+            report.push_str(&format!("{},,,0,0,0,0,\n", ticker));
+            continue;
+        }
+        if let Err(e) = write_ticker(&output, ticker, &history.bars, format) {
+            eprintln!("Failed to write {}: {}", ticker, e);
+            return 1;
+        }
+        let missing = missing_days(&history.bars, &calendar);
+        report.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            ticker,
+            market_date(history.bars[0].time),
+            market_date(history.bars[history.bars.len() - 1].time),
+            history.bars.len(),
+            history.csv_bars,
+            history.vci_bars,
+            missing.len(),
+            missing.iter().map(NaiveDate::to_string).collect::<Vec<_>>().join(" "),
+        ));
+    }
+    let report_path = output.join("continuity.csv");
+    if let Err(e) = fs::write(&report_path, report) {
+        eprintln!("Failed to write {}: {}", report_path.display(), e);
+        return 1;
+    }
+
+    println!("Wrote {} tickers to {}, continuity report in {}", histories.len() - empty.len(), output.display(), report_path.display());
+    if empty.is_empty() {
+        0
+    } else {
+        eprintln!("No data for {} tickers: {}", empty.len(), empty.join(", "));
+        1
+    }
+}
+
+fn write_ticker(output: &Path, ticker: &str, bars: &[OhlcvData], format: TableFormat) -> Result<(), String> {
+    let content = to_table(ticker, bars, format)?;
+    fs::write(output.join(format!("{}.{}", ticker, format.extension())), content).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_parsing_and_continuity() {
+        let date = |day: u32| NaiveDate::from_ymd_opt(2025, 8, day).unwrap();
+        let content = "ticker,time,open,high,low,close,volume\n\
+            VCB,2025-08-04,1,2,0.5,1.5,100\n\
+            VCB,2025-08-01,1,2,0.5,1.4,90\n\
+            VCB,2025-08-07,1,2,0.5,1.6,110\n\
+            VCB,2025-08-07,1,2,0.5,1.6,110\n\
+            VCB,2025-08-08,bad,2,0.5,1.6,110\n\
+            VCB,2025-08-11,1,2,0.5,1.7,120\n";
+        let bars = parse_history_csv(content, date(1), date(10));
+        assert_eq!(bars.iter().map(|bar| market_date(bar.time)).collect::<Vec<_>>(), vec![date(1), date(4), date(7)]);
+        assert_eq!(bars[1].volume, 100);
+        assert!(parse_history_csv("time,close\n2025-08-01,1\n", date(1), date(10)).is_empty());
+
+        // Sessions the calendar has between the first and last bar; the 8th is outside this range
+        let calendar: BTreeSet<NaiveDate> = [1, 4, 5, 6, 7, 8].map(date).into();
+        assert_eq!(missing_days(&bars, &calendar), vec![date(5), date(6)]);
+        assert!(missing_days(&[], &calendar).is_empty());
+
+        let csv = |bars: &[OhlcvData]| String::from_utf8(to_table("VCB", bars, TableFormat::Csv).unwrap()).unwrap();
+        assert_eq!(csv(&bars[..1]), "time,open,high,low,close,volume\n2025-08-01,1,2,0.5,1.4,90\n");
+        let round_trip = parse_history_csv(&csv(&bars), date(1), date(31));
+        assert_eq!(round_trip.len(), 3);
+    }
+}
@@ -0,0 +1,400 @@
+//! Latency benchmark for the API layer: serves the real `/tickers`, `/screener` and analysis handlers
+//! in-process over a fixture market, drives each endpoint with concurrent clients while a writer keeps
+//! revising bars under the data lock, and fails when an endpoint's p95 latency is over its budget.
+//!
+//! cargo run --release --features latency --bin latency -- [--symbols 300] [--days 500] [--report latency-report.json] ...
+
+use aipriceaction_proxy::api;
+use aipriceaction_proxy::auth::{SharedTokenRegistry, TokenRegistry};
+use aipriceaction_proxy::config::TokenConfig;
+use aipriceaction_proxy::constituents::{IndexConstituents, SharedIndexConstituents};
+use aipriceaction_proxy::data_structures::{merge_and_track_changes, HealthStats, InMemoryData, SharedData, SharedHealthStats, SharedTickerGroups, TickerGroups};
+use aipriceaction_proxy::export::{EnhancedSnapshots, SharedEnhancedSnapshots};
+use aipriceaction_proxy::intraday::SharedIntradayData;
+use aipriceaction_proxy::ticker_info::{SharedTickerDirectory, TickerDirectory};
+use aipriceaction_proxy::utils::corrections::CorrectionSource;
+use aipriceaction_proxy::utils::market_time::{market_date, market_day_start};
+use aipriceaction_proxy::utils::query_cost::{self, QueryCostLimits};
+use aipriceaction_proxy::vci::OhlcvData;
+use axum::{extract::FromRef, routing::get, Router};
+use chrono::{Duration as ChronoDuration, NaiveDate};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+const USAGE: &str = "Usage: latency [--symbols N] [--days N] [--requests N] [--concurrency N] [--update-ms MS] [--budget ENDPOINT=MS]... [--report PATH]";
+
+// Symbols per fixture ticker group; the first group stands in for VN30
+const GROUP_SIZE: usize = 30;
+// Sessions the range endpoints ask for, about three months
+const RANGE_SESSIONS: i64 = 60;
+
+struct Options {
+    symbols: usize,
+    days: usize,
+    requests: usize,
+    concurrency: usize,
+    update_ms: u64,
+    budgets: HashMap<String, f64>,
+    report: String,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Options {
+            symbols: 300,
+            days: 500,
+            requests: 200,
+            // One client per core keeps the load per core, and so the budgets, comparable across machines
+            concurrency: std::thread::available_parallelism().map_or(4, usize::from),
+            update_ms: 100,
+            budgets: HashMap::new(),
+            report: "latency-report.json".to_string(),
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let value = iter.next().ok_or_else(|| format!("Missing value for {}", arg))?;
+            let invalid = |_| format!("Invalid {} value: {}", arg, value);
+            match arg.as_str() {
+                "--symbols" => options.symbols = value.parse().map_err(invalid)?,
+                "--days" => options.days = value.parse().map_err(invalid)?,
+                "--requests" => options.requests = value.parse().map_err(invalid)?,
+                "--concurrency" => options.concurrency = value.parse().map_err(invalid)?,
+                "--update-ms" => options.update_ms = value.parse().map_err(invalid)?,
+                "--budget" => {
+                    let (endpoint, ms) = value.split_once('=').ok_or_else(|| format!("Invalid --budget value: {}, expected ENDPOINT=MS", value))?;
+                    let ms: f64 = ms.parse().map_err(|_| format!("Invalid --budget value: {}", value))?;
+                    options.budgets.insert(endpoint.to_string(), ms);
+                }
+                "--report" => options.report = value.clone(),
+                _ => return Err(format!("Unknown option {}", arg)),
+            }
+        }
+        if options.symbols == 0 || options.days < RANGE_SESSIONS as usize || options.requests == 0 || options.concurrency == 0 {
+            return Err(format!("--symbols, --requests and --concurrency must be positive, and --days at least {}", RANGE_SESSIONS));
+        }
+        Ok(options)
+    }
+}
+
+/// An endpoint under test and its default p95 budget, for a release build with one client per core
+struct Endpoint {
+    name: &'static str,
+    path: String,
+    budget_p95_ms: f64,
+}
+
+fn endpoints(range_start: NaiveDate) -> Vec<Endpoint> {
+    let endpoint = |name, path: String, budget_p95_ms| Endpoint { name, path, budget_p95_ms };
+    vec![
+        endpoint("tickers_latest", "/tickers".to_string(), 150.0),
+        endpoint("tickers_range", format!("/tickers?start_date={}", range_start), 250.0),
+        endpoint("tickers_enhanced", "/tickers?enhanced=true".to_string(), 100.0),
+        endpoint("tickers_csv", format!("/tickers?format=csv&start_date={}", range_start), 750.0),
+        endpoint("screener", "/screener?filter=ma20_score>0&filter=volume>volume_avg20&sort=ma20_score&limit=20".to_string(), 50.0),
+        endpoint("ma_score", format!("/analysis/ma-score?group=G00&start_date={}", range_start), 100.0),
+    ]
+}
+
+#[derive(Serialize)]
+struct EndpointReport {
+    name: &'static str,
+    path: String,
+    requests: usize,
+    errors: usize,
+    p50_ms: f64,
+    p95_ms: f64,
+    p99_ms: f64,
+    max_ms: f64,
+    budget_p95_ms: f64,
+    passed: bool,
+}
+
+#[derive(Serialize)]
+struct Report {
+    symbols: usize,
+    days: usize,
+    requests_per_endpoint: usize,
+    concurrency: usize,
+    update_ms: u64,
+    passed: bool,
+    endpoints: Vec<EndpointReport>,
+}
+
+// The state the benchmarked handlers extract
+#[derive(Clone)]
+struct BenchState {
+    data: SharedData,
+    health: SharedHealthStats,
+    directory: SharedTickerDirectory,
+    snapshots: SharedEnhancedSnapshots,
+    intraday: SharedIntradayData,
+    groups: SharedTickerGroups,
+    constituents: SharedIndexConstituents,
+    tokens: SharedTokenRegistry,
+}
+
+impl FromRef<BenchState> for SharedData {
+    fn from_ref(state: &BenchState) -> SharedData {
+        state.data.clone()
+    }
+}
+
+impl FromRef<BenchState> for SharedHealthStats {
+    fn from_ref(state: &BenchState) -> SharedHealthStats {
+        state.health.clone()
+    }
+}
+
+impl FromRef<BenchState> for SharedTickerDirectory {
+    fn from_ref(state: &BenchState) -> SharedTickerDirectory {
+        state.directory.clone()
+    }
+}
+
+impl FromRef<BenchState> for SharedEnhancedSnapshots {
+    fn from_ref(state: &BenchState) -> SharedEnhancedSnapshots {
+        state.snapshots.clone()
+    }
+}
+
+impl FromRef<BenchState> for SharedIntradayData {
+    fn from_ref(state: &BenchState) -> SharedIntradayData {
+        state.intraday.clone()
+    }
+}
+
+impl FromRef<BenchState> for SharedTickerGroups {
+    fn from_ref(state: &BenchState) -> SharedTickerGroups {
+        state.groups.clone()
+    }
+}
+
+impl FromRef<BenchState> for SharedIndexConstituents {
+    fn from_ref(state: &BenchState) -> SharedIndexConstituents {
+        state.constituents.clone()
+    }
+}
+
+impl FromRef<BenchState> for SharedTokenRegistry {
+    fn from_ref(state: &BenchState) -> SharedTokenRegistry {
+        state.tokens.clone()
+    }
+}
+
+/// `days` daily bars for each of `symbols` fixture symbols ending on `last`, as a deterministic random walk
+fn fixture_market(symbols: usize, days: usize, last: NaiveDate) -> (InMemoryData, TickerGroups) {
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed % 10_000) as f64 / 10_000.0
+    };
+    let names: Vec<String> = (0..symbols).map(|i| format!("LT{:03}", i)).collect();
+    let data = names.iter().map(|symbol| {
+        let mut close = 10.0 + next() * 90.0;
+        let bars = (0..days).map(|day| {
+            let open = close;
+            close = (close * (0.97 + next() * 0.06)).max(1.0);
+            let date = last - ChronoDuration::days((days - 1 - day) as i64);
+            OhlcvData {
+                time: market_day_start(date),
+                open,
+                high: open.max(close) * (1.0 + next() * 0.01),
+                low: open.min(close) * (1.0 - next() * 0.01),
+                close,
+                volume: 10_000 + (next() * 1_000_000.0) as u64,
+                symbol: Some(symbol.clone()),
+            }
+        }).collect();
+        (symbol.clone(), bars)
+    }).collect();
+    let groups = names.chunks(GROUP_SIZE).enumerate().map(|(i, members)| (format!("G{:02}", i), members.to_vec())).collect();
+    (data, TickerGroups(groups))
+}
+
+/// Revise every symbol's latest bar each `interval`, holding the data lock like a worker merge does
+async fn run_writer(data: SharedData, interval: Duration, stop: CancellationToken) {
+    let mut tick = 0u64;
+    while !stop.is_cancelled() {
+        tokio::time::sleep(interval).await;
+        tick += 1;
+        let mut data = data.lock().await;
+        for (symbol, series) in data.iter_mut() {
+            let Some(mut bar) = series.last().cloned() else { continue };
+            bar.close *= if tick.is_multiple_of(2) { 1.001 } else { 0.999 };
+            bar.high = bar.high.max(bar.close);
+            bar.low = bar.low.min(bar.close);
+            bar.volume += 100;
+            merge_and_track_changes(symbol, series, vec![bar], CorrectionSource::Vci);
+        }
+    }
+}
+
+/// Nearest-rank percentile of ascending-sorted latencies
+fn percentile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (q * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Issue `requests` requests to `url` from `concurrency` clients at once, returning each latency
+/// (including reading the full body) and the number of failed or non-2xx requests
+async fn measure(client: &reqwest::Client, url: &str, requests: usize, concurrency: usize) -> (Vec<f64>, usize) {
+    let tasks: Vec<_> = (0..concurrency).map(|worker| {
+        let client = client.clone();
+        let url = url.to_string();
+        // Spread the requests evenly, the first workers taking the remainder
+        let count = requests / concurrency + usize::from(worker < requests % concurrency);
+        tokio::spawn(async move {
+            let mut latencies = Vec::with_capacity(count);
+            let mut errors = 0;
+            for _ in 0..count {
+                let started = Instant::now();
+                let ok = match client.get(&url).send().await {
+                    Ok(response) => response.status().is_success() && response.bytes().await.is_ok(),
+                    Err(_) => false,
+                };
+                latencies.push(started.elapsed().as_secs_f64() * 1000.0);
+                errors += usize::from(!ok);
+            }
+            (latencies, errors)
+        })
+    }).collect();
+
+    let mut latencies = Vec::with_capacity(requests);
+    let mut errors = 0;
+    for task in tasks {
+        let (task_latencies, task_errors) = task.await.expect("Benchmark client failed");
+        latencies.extend(task_latencies);
+        errors += task_errors;
+    }
+    (latencies, errors)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match Options::parse(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "error".into()))
+        .with_target(false)
+        .init();
+
+    let today = market_date(chrono::Utc::now());
+    let (market, groups) = fixture_market(options.symbols, options.days, today);
+    let range_start = today - ChronoDuration::days(RANGE_SESSIONS);
+    let endpoints = endpoints(range_start);
+    if let Some(unknown) = options.budgets.keys().find(|name| !endpoints.iter().any(|endpoint| endpoint.name == name.as_str())) {
+        let names: Vec<&str> = endpoints.iter().map(|endpoint| endpoint.name).collect();
+        eprintln!("Unknown endpoint {} in --budget, expected one of {}\n{}", unknown, names.join(", "), USAGE);
+        return ExitCode::from(2);
+    }
+    // Every request comes from 127.0.0.1, which the per-client cell budget would otherwise throttle
+    query_cost::set_limits(QueryCostLimits { max_cells_per_request: 0, max_cells_per_key_per_minute: 0 });
+
+    let health = HealthStats { initial_load_complete: true, ..Default::default() };
+    let state = BenchState {
+        data: Arc::new(Mutex::new(market)),
+        health: Arc::new(Mutex::new(health)),
+        directory: Arc::new(Mutex::new(TickerDirectory::default())),
+        snapshots: Arc::new(Mutex::new(EnhancedSnapshots::default())),
+        intraday: Arc::new(Mutex::new(HashMap::new())),
+        groups: Arc::new(groups),
+        constituents: Arc::new(IndexConstituents(HashMap::new())),
+        tokens: Arc::new(Mutex::new(TokenRegistry::from_config(&TokenConfig { primary: String::new(), secondary: String::new(), node_token: None, accounts: Vec::new() }))),
+    };
+    let app = Router::new()
+        .route("/tickers", get(api::get_all_tickers_handler))
+        .route("/screener", get(api::screener_handler))
+        .route("/analysis/ma-score", get(api::ma_score_handler))
+        .with_state(state.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind the benchmark server");
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let shutdown = CancellationToken::new();
+    let server_shutdown = shutdown.clone();
+    tokio::spawn(async move {
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(server_shutdown.cancelled_owned())
+            .await
+            .expect("Benchmark server failed");
+    });
+    if options.update_ms > 0 {
+        tokio::spawn(run_writer(state.data.clone(), Duration::from_millis(options.update_ms), shutdown.clone()));
+    }
+
+    println!(
+        "Benchmarking {} endpoints over {} symbols x {} days: {} requests each from {} clients, bars revised every {}",
+        endpoints.len(), options.symbols, options.days, options.requests, options.concurrency,
+        if options.update_ms > 0 { format!("{}ms", options.update_ms) } else { "never".to_string() },
+    );
+    let client = reqwest::Client::new();
+    let mut reports = Vec::new();
+    for endpoint in &endpoints {
+        let url = format!("{}{}", base_url, endpoint.path);
+        // Untimed first pass so one-off work, like the first enhanced snapshot, is not measured
+        measure(&client, &url, options.concurrency, options.concurrency).await;
+        let (mut latencies, errors) = measure(&client, &url, options.requests, options.concurrency).await;
+        latencies.sort_by(|a, b| a.total_cmp(b));
+
+        let budget_p95_ms = options.budgets.get(endpoint.name).copied().unwrap_or(endpoint.budget_p95_ms);
+        let p95_ms = percentile(&latencies, 0.95);
+        let report = EndpointReport {
+            name: endpoint.name,
+            path: endpoint.path.clone(),
+            requests: latencies.len(),
+            errors,
+            p50_ms: percentile(&latencies, 0.5),
+            p95_ms,
+            p99_ms: percentile(&latencies, 0.99),
+            max_ms: latencies.last().copied().unwrap_or_default(),
+            budget_p95_ms,
+            passed: errors == 0 && p95_ms <= budget_p95_ms,
+        };
+        println!(
+            "{:<18} p50 {:>8.2}ms  p95 {:>8.2}ms  p99 {:>8.2}ms  max {:>8.2}ms  budget {:>6.0}ms  errors {}  {}",
+            report.name, report.p50_ms, report.p95_ms, report.p99_ms, report.max_ms, report.budget_p95_ms, report.errors,
+            if report.passed { "ok" } else { "FAIL" },
+        );
+        reports.push(report);
+    }
+    shutdown.cancel();
+
+    let report = Report {
+        symbols: options.symbols,
+        days: options.days,
+        requests_per_endpoint: options.requests,
+        concurrency: options.concurrency,
+        update_ms: options.update_ms,
+        passed: reports.iter().all(|report| report.passed),
+        endpoints: reports,
+    };
+    match serde_json::to_vec_pretty(&report).map_err(std::io::Error::other).and_then(|json| std::fs::write(&options.report, json)) {
+        Ok(()) => println!("Report written to {}", options.report),
+        Err(e) => eprintln!("Failed to write the report to {}: {}", options.report, e),
+    }
+
+    if report.passed {
+        println!("Latency benchmark passed");
+        ExitCode::SUCCESS
+    } else {
+        for endpoint in report.endpoints.iter().filter(|endpoint| !endpoint.passed) {
+            eprintln!("FAIL: {} p95 {:.2}ms over its {:.0}ms budget, {} errors", endpoint.name, endpoint.p95_ms, endpoint.budget_p95_ms, endpoint.errors);
+        }
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,347 @@
+//! Soak test for the public node sync loop: runs the real worker against an in-process fixture core
+//! for thousands of accelerated iterations and fails on unbounded memory growth, a dead or stalled
+//! worker task, or a replica that drifted from the core.
+//!
+//! cargo run --release --features soak --bin soak -- [--iterations 5000] [--symbols 100] ...
+
+use aipriceaction_proxy::analysis::indicator_cache;
+use aipriceaction_proxy::config::AppConfig;
+use aipriceaction_proxy::data_structures::{estimate_memory_usage, merge_and_track_changes, HealthStats, InMemoryData, PublicActorReputation};
+use aipriceaction_proxy::intraday::{self, Interval};
+use aipriceaction_proxy::utils::corrections::CorrectionSource;
+use aipriceaction_proxy::utils::{change_log, market_time, metrics};
+use aipriceaction_proxy::vci::OhlcvData;
+use aipriceaction_proxy::ticker_info::TickerDirectory;
+use aipriceaction_proxy::{events, wire, worker};
+use axum::{extract::State, http::{header::{ACCEPT, CONTENT_TYPE}, HeaderMap}, response::{IntoResponse, Response}, routing::get, Json, Router};
+use axum_extra::extract::Query;
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, TimeZone, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+const USAGE: &str = "Usage: soak [--iterations N] [--warmup N] [--symbols N] [--ticks-per-session N] [--max-growth-mb MB] [--report-every N]";
+
+// Sessions the fixture core keeps, like a core node after cleanup
+const FIXTURE_DAILY_BARS: usize = 250;
+const FIXTURE_HOURLY_BARS: usize = 150;
+// Recent bars each simulated API read looks at, enough for MA50
+const READ_WINDOW_BARS: usize = 60;
+// Replica budget for 1H candles, small enough that trimming runs during the soak
+const INTRADAY_BUDGET_MB: usize = 1;
+// A sync cycle taking longer than this counts as a stalled worker
+const STALL_TIMEOUT: Duration = Duration::from_secs(15);
+
+struct Options {
+    iterations: u64,
+    warmup: u64,
+    symbols: usize,
+    ticks_per_session: u64,
+    max_growth_mb: f64,
+    report_every: u64,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Options { iterations: 5000, warmup: 1000, symbols: 100, ticks_per_session: 10, max_growth_mb: 16.0, report_every: 500 };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let value = iter.next().ok_or_else(|| format!("Missing value for {}", arg))?;
+            let invalid = |_| format!("Invalid {} value: {}", arg, value);
+            match arg.as_str() {
+                "--iterations" => options.iterations = value.parse().map_err(invalid)?,
+                "--warmup" => options.warmup = value.parse().map_err(invalid)?,
+                "--symbols" => options.symbols = value.parse().map_err(invalid)?,
+                "--ticks-per-session" => options.ticks_per_session = value.parse().map_err(invalid)?,
+                "--max-growth-mb" => options.max_growth_mb = value.parse().map_err(|_| format!("Invalid {} value: {}", arg, value))?,
+                "--report-every" => options.report_every = value.parse().map_err(invalid)?,
+                _ => return Err(format!("Unknown option {}", arg)),
+            }
+        }
+        if options.symbols == 0 || options.ticks_per_session == 0 || options.report_every == 0 || options.warmup >= options.iterations {
+            return Err("--symbols, --ticks-per-session and --report-every must be positive, and --warmup below --iterations".to_string());
+        }
+        Ok(options)
+    }
+}
+
+/// Synthetic core node: every tick updates each symbol's forming bar, and every `ticks_per_session`
+/// ticks a new session starts, so one soak run covers years of simulated trading
+struct FixtureCore {
+    symbols: Vec<String>,
+    ticks_per_session: u64,
+    tick: u64,
+    daily: InMemoryData,
+    hourly: InMemoryData,
+}
+
+impl FixtureCore {
+    fn new(symbol_count: usize, ticks_per_session: u64) -> Self {
+        let symbols = (0..symbol_count).map(|i| format!("SK{:03}", i)).collect();
+        let mut core = Self { symbols, ticks_per_session, tick: 0, daily: InMemoryData::new(), hourly: InMemoryData::new() };
+        core.advance();
+        core
+    }
+
+    fn session_start(&self) -> DateTime<Utc> {
+        // 09:00 in Vietnam on consecutive days
+        Utc.with_ymd_and_hms(2020, 1, 1, 2, 0, 0).unwrap() + ChronoDuration::days((self.tick / self.ticks_per_session) as i64)
+    }
+
+    fn advance(&mut self) {
+        self.tick += 1;
+        let session_start = self.session_start();
+        let hour = (self.tick % self.ticks_per_session) * Interval::OneHour.bars_per_session() as u64 / self.ticks_per_session;
+        for (i, symbol) in self.symbols.iter().enumerate() {
+            let close = 10_000.0 + ((self.tick + i as u64 * 7) % 97) as f64 * 50.0;
+            let bar = |time| OhlcvData { time, open: close - 50.0, high: close + 100.0, low: close - 100.0, close, volume: 1_000 + self.tick, symbol: Some(symbol.clone()) };
+
+            let series = self.daily.entry(symbol.clone()).or_default();
+            merge_and_track_changes(symbol, series, vec![bar(session_start + ChronoDuration::hours(5))], CorrectionSource::Vci);
+            if series.len() > FIXTURE_DAILY_BARS {
+                series.drain(..series.len() - FIXTURE_DAILY_BARS);
+            }
+            let candles = self.hourly.entry(symbol.clone()).or_default();
+            intraday::merge_bars(candles, vec![bar(session_start + ChronoDuration::hours(hour as i64))]);
+            if candles.len() > FIXTURE_HOURLY_BARS {
+                candles.drain(..candles.len() - FIXTURE_HOURLY_BARS);
+            }
+        }
+    }
+}
+
+type SharedFixture = Arc<Mutex<FixtureCore>>;
+
+#[derive(Deserialize)]
+struct FixtureTickerParams {
+    symbol: Option<Vec<String>>,
+    start_date: Option<NaiveDate>,
+    interval: Option<Interval>,
+}
+
+#[derive(Deserialize)]
+struct FixtureChangesParams {
+    epoch: Option<u64>,
+    since: Option<u64>,
+}
+
+// Same selection and formats as the core's `/tickers`: the latest bar per symbol unless a start date is given
+async fn fixture_tickers(State(core): State<SharedFixture>, headers: HeaderMap, Query(params): Query<FixtureTickerParams>) -> Response {
+    let core = core.lock().unwrap();
+    let source = match params.interval.unwrap_or(Interval::OneDay) {
+        Interval::OneDay => &core.daily,
+        _ => &core.hourly,
+    };
+    let data = source.iter()
+        .filter(|(symbol, _)| params.symbol.as_ref().is_none_or(|symbols| symbols.contains(symbol)))
+        .map(|(symbol, series)| {
+            let bars = match params.start_date {
+                Some(start) => series.iter().filter(|bar| market_time::market_date(bar.time) >= start).cloned().collect(),
+                None => series.last().cloned().into_iter().collect(),
+            };
+            (symbol.clone(), bars)
+        })
+        .collect();
+    match wire::negotiate(headers.get(ACCEPT).and_then(|value| value.to_str().ok())) {
+        Some(encoding) => ([(CONTENT_TYPE, encoding.media_type())], wire::encode(&data, encoding).unwrap()).into_response(),
+        None => Json(data).into_response(),
+    }
+}
+
+async fn fixture_changes(Query(params): Query<FixtureChangesParams>) -> Json<change_log::ChangeSet> {
+    Json(change_log::since(params.epoch, params.since))
+}
+
+fn write_config(core_url: &str) -> std::io::Result<std::path::PathBuf> {
+    let path = std::env::temp_dir().join(format!("aipriceaction-soak-{}.yml", std::process::id()));
+    let yaml = format!(
+        "node_name: soak\ntokens:\n  primary: soak-primary\n  secondary: soak-secondary\ninternal_peers: []\npublic_peers: []\n\
+         core_network_url: \"{}\"\npublic_refresh_interval_secs: 0\ncore_worker_interval_secs: 0\n\
+         intraday:\n  intervals: [\"1H\"]\n  memory_mb:\n    1H: {}\nenvironment: soak\nport: 0\n",
+        core_url, INTRADAY_BUDGET_MB,
+    );
+    std::fs::write(&path, yaml)?;
+    Ok(path)
+}
+
+/// Resident set size from /proc, None on platforms without it
+fn rss_bytes() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kb: usize = status.lines().find_map(|line| line.strip_prefix("VmRSS:"))?.trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kb * 1024)
+}
+
+fn completed_syncs() -> u64 {
+    let counters = metrics::get_performance_report().counters;
+    counters.get("worker.core_sync_full").copied().unwrap_or(0) + counters.get("worker.core_sync_incremental").copied().unwrap_or(0)
+}
+
+fn mb(bytes: usize) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match Options::parse(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "error".into()))
+        .with_target(false)
+        .init();
+
+    let fixture: SharedFixture = Arc::new(Mutex::new(FixtureCore::new(options.symbols, options.ticks_per_session)));
+    let app = Router::new()
+        .route("/tickers", get(fixture_tickers))
+        .route("/sync/changes", get(fixture_changes))
+        .with_state(fixture.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind the fixture core");
+    let core_url = format!("http://{}", listener.local_addr().unwrap());
+    let shutdown = CancellationToken::new();
+    let server_shutdown = shutdown.clone();
+    tokio::spawn(async move {
+        axum::serve(listener, app).with_graceful_shutdown(server_shutdown.cancelled_owned()).await.expect("Fixture core failed");
+    });
+
+    let config_path = write_config(&core_url).expect("Failed to write the soak config");
+    let mut config = AppConfig::from_yaml(config_path.to_str().unwrap());
+    let _ = std::fs::remove_file(&config_path);
+    config.public_refresh_interval = Duration::from_millis(1);
+    let intraday_budget = config.intraday.memory_budget_bytes(Interval::OneHour);
+
+    let data = Arc::new(tokio::sync::Mutex::new(InMemoryData::new()));
+    let intraday_data = Arc::new(tokio::sync::Mutex::new(HashMap::from([(Interval::OneHour, InMemoryData::new())])));
+    let event_bus = events::new_event_bus();
+    // A streaming client that keeps up, so published updates are consumed like in production
+    let mut subscriber = event_bus.subscribe();
+    tokio::spawn(async move { while !matches!(subscriber.recv().await, Err(tokio::sync::broadcast::error::RecvError::Closed)) {} });
+
+    let worker_handle = tokio::spawn(worker::run(
+        data.clone(),
+        config,
+        Arc::new(tokio::sync::Mutex::new(HealthStats::default())),
+        None,
+        Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+        Arc::new(tokio::sync::Mutex::new(PublicActorReputation::new())),
+        event_bus,
+        intraday_data.clone(),
+        Arc::new(tokio::sync::Mutex::new(TickerDirectory::default())),
+        shutdown.clone(),
+    ));
+
+    println!("Soaking {} iterations over {} symbols against {} (warmup {})", options.iterations, options.symbols, core_url, options.warmup);
+    let started = Instant::now();
+    let mut failures = Vec::new();
+    let mut baseline: Option<(usize, usize)> = None; // RSS and replica data size after warmup
+    let mut peak_overhead_growth = 0.0f64;
+    let mut last_syncs = completed_syncs();
+
+    for iteration in 1..=options.iterations {
+        // One iteration is one completed sync cycle of the worker, after which the core moves on
+        let waiting_since = Instant::now();
+        while completed_syncs() == last_syncs {
+            if worker_handle.is_finished() {
+                failures.push(format!("Worker task died at iteration {}", iteration));
+                break;
+            }
+            if waiting_since.elapsed() > STALL_TIMEOUT {
+                failures.push(format!("Worker stalled for {:?} at iteration {}", STALL_TIMEOUT, iteration));
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        if !failures.is_empty() {
+            break;
+        }
+        last_syncs = completed_syncs();
+        fixture.lock().unwrap().advance();
+
+        // API reads go through the indicator caches, which must stay bounded too. They read the same
+        // number of recent bars every time, so cached series don't grow along with the replica's history.
+        if iteration % 10 == 0 {
+            let replica = data.lock().await;
+            for (symbol, series) in replica.iter() {
+                let recent = &series[series.len().saturating_sub(READ_WINDOW_BARS)..];
+                indicator_cache::ma_scores(symbol, recent);
+                indicator_cache::indicators(symbol, recent);
+            }
+        }
+
+        if iteration == options.warmup || iteration % options.report_every == 0 || iteration == options.iterations {
+            let data_bytes = estimate_memory_usage(&*data.lock().await);
+            let intraday_bytes = intraday_data.lock().await.get(&Interval::OneHour).map_or(0, estimate_memory_usage);
+            let rss = rss_bytes();
+            if intraday_bytes > intraday_budget {
+                failures.push(format!("1H candles use {:.1} MB, over the {:.1} MB budget", mb(intraday_bytes), mb(intraday_budget)));
+            }
+            if iteration == options.warmup {
+                baseline = rss.map(|rss| (rss, data_bytes));
+            }
+            // Growth not explained by the replica's own history, which legitimately grows by a bar per session
+            let overhead_growth = match (rss, baseline) {
+                (Some(rss), Some((base_rss, base_data))) => mb(rss.saturating_sub(base_rss)) - mb(data_bytes.saturating_sub(base_data)),
+                _ => 0.0,
+            };
+            peak_overhead_growth = peak_overhead_growth.max(overhead_growth);
+            println!(
+                "iteration {:>6}: rss {} MB, daily {:.1} MB, 1H {:.2} MB, growth beyond data {:+.1} MB, {:.0} iterations/s",
+                iteration,
+                rss.map_or("n/a".to_string(), |rss| format!("{:.1}", mb(rss))),
+                mb(data_bytes),
+                mb(intraday_bytes),
+                overhead_growth,
+                iteration as f64 / started.elapsed().as_secs_f64(),
+            );
+        }
+    }
+
+    // Let the worker pick up the last tick, then compare every symbol's latest bar with the core
+    if failures.is_empty() {
+        let target = completed_syncs() + 2;
+        let waiting_since = Instant::now();
+        while completed_syncs() < target && waiting_since.elapsed() < STALL_TIMEOUT {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        let replica = data.lock().await;
+        let core = fixture.lock().unwrap();
+        // Bars travel as market dates, so compare dates rather than timestamps
+        let key = |bar: &OhlcvData| (market_time::market_date(bar.time), bar.close, bar.volume);
+        let drifted = core.daily.iter()
+            .filter(|(symbol, series)| replica.get(*symbol).and_then(|local| local.last()).map(key) != series.last().map(key))
+            .count();
+        if drifted > 0 {
+            failures.push(format!("{} symbols' latest bar differs from the core", drifted));
+        }
+    }
+    if worker_handle.is_finished() && failures.is_empty() {
+        failures.push("Worker task exited before shutdown".to_string());
+    }
+
+    shutdown.cancel();
+    if tokio::time::timeout(Duration::from_secs(10), worker_handle).await.is_err() {
+        failures.push("Worker did not stop within 10s of shutdown".to_string());
+    }
+    if baseline.is_none() {
+        println!("RSS is not available on this platform; memory growth was not checked");
+    } else if peak_overhead_growth > options.max_growth_mb {
+        failures.push(format!("Memory grew {:.1} MB beyond the replica data, over the {:.1} MB limit", peak_overhead_growth, options.max_growth_mb));
+    }
+
+    if failures.is_empty() {
+        println!("Soak passed: {} iterations in {:.1}s, peak growth beyond data {:+.1} MB", options.iterations, started.elapsed().as_secs_f64(), peak_overhead_growth);
+        ExitCode::SUCCESS
+    } else {
+        for failure in &failures {
+            eprintln!("FAIL: {}", failure);
+        }
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,119 @@
+use crate::analysis::{indicators, ma_score, money_flow, strength, warmup};
+use crate::analysis::strength::StrengthWeights;
+use crate::analysis::warmup::WarmupMode;
+use crate::export;
+use crate::profile::IndicatorGroup;
+use crate::wire;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+// The lock file the binary was built from, so dependency versions are exactly what was compiled
+const CARGO_LOCK: &str = include_str!("../Cargo.lock");
+// Dependencies whose versions shape responses or wire formats
+const REPORTED_LIBRARIES: [&str; 9] = ["axum", "bincode", "chrono", "chrono-tz", "reqwest", "serde", "serde_json", "tokio", "zstd"];
+
+/// Cargo features this binary was compiled with
+fn enabled_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "soak") {
+        features.push("soak");
+    }
+    features
+}
+
+/// What produced this node's responses, as served by `/version`
+#[derive(Debug, Serialize)]
+pub struct BuildManifest {
+    pub name: &'static str,
+    pub version: &'static str, // Version of this proxy crate
+    pub build_date: Option<String>,
+    pub git_commit: Option<String>,
+    pub features: Vec<&'static str>,
+    pub libraries: BTreeMap<String, String>, // Locked versions of key dependencies
+    pub profile: Option<String>,
+    pub indicator_groups: Vec<IndicatorGroup>,
+    pub algorithms: BTreeMap<&'static str, u32>, // Formula versions behind computed columns
+    pub strength_weights: StrengthWeights,
+    pub warmup_mode: WarmupMode,
+    pub formats: BTreeMap<&'static str, u32>,
+}
+
+/// Versions of `names` in a Cargo.lock; several locked versions of one crate are comma-separated
+pub fn locked_versions(lock: &str, names: &[&str]) -> BTreeMap<String, String> {
+    let mut versions: BTreeMap<String, String> = BTreeMap::new();
+    for package in lock.split("[[package]]").skip(1) {
+        let field = |key: &str| package.lines()
+            .find_map(|line| line.strip_prefix(key)?.trim_start().strip_prefix('=')?.trim().strip_prefix('"')?.strip_suffix('"'));
+        if let (Some(name), Some(version)) = (field("name"), field("version"))
+            && names.contains(&name)
+        {
+            versions.entry(name.to_string())
+                .and_modify(|existing| *existing = format!("{}, {}", existing, version))
+                .or_insert_with(|| version.to_string());
+        }
+    }
+    versions
+}
+
+pub fn manifest(build_date: Option<String>, git_commit: Option<String>, profile: Option<String>) -> BuildManifest {
+    BuildManifest {
+        name: env!("CARGO_PKG_NAME"),
+        version: env!("CARGO_PKG_VERSION"),
+        build_date,
+        git_commit,
+        features: enabled_features(),
+        libraries: locked_versions(CARGO_LOCK, &REPORTED_LIBRARIES),
+        profile,
+        indicator_groups: export::indicator_groups(),
+        algorithms: BTreeMap::from([
+            ("ma_score", ma_score::ALGORITHM_VERSION),
+            ("strength", strength::ALGORITHM_VERSION),
+            ("indicators", indicators::ALGORITHM_VERSION),
+            ("money_flow", money_flow::ALGORITHM_VERSION),
+        ]),
+        strength_weights: strength::weights().clone(),
+        warmup_mode: warmup::mode(),
+        formats: BTreeMap::from([("binary_bars", wire::FORMAT_VERSION as u32)]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_reads_locked_versions() {
+        let lock = r#"
+version = 4
+
+[[package]]
+name = "axum"
+version = "0.8.4"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "axum-core"
+version = "0.5.2"
+
+[[package]]
+name = "zstd"
+version = "0.13.3"
+
+[[package]]
+name = "zstd"
+version = "0.14.0"
+"#;
+        let versions = locked_versions(lock, &["axum", "zstd", "tokio"]);
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions["axum"], "0.8.4");
+        assert_eq!(versions["zstd"], "0.13.3, 0.14.0");
+
+        let manifest = manifest(None, Some("abc123".to_string()), Some("full".to_string()));
+        assert_eq!(manifest.version, env!("CARGO_PKG_VERSION"));
+        assert!(REPORTED_LIBRARIES.iter().all(|name| manifest.libraries.contains_key(*name)));
+        assert_eq!(manifest.algorithms["strength"], strength::ALGORITHM_VERSION);
+        let json = serde_json::to_value(&manifest).unwrap();
+        assert_eq!((json["git_commit"].as_str(), json["profile"].as_str()), (Some("abc123"), Some("full")));
+        assert_eq!(json["indicator_groups"][0], "ma_score");
+    }
+}
@@ -0,0 +1,288 @@
+use crate::vci::{CompanyInfo, VciClient, VciError};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+// Parts of company info that change at different rates, each with its own staleness
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompanySection {
+    Price,        // current_price, market_cap
+    Profile,      // exchange, industry, company type, profile, website, headcount, shares
+    Shareholders, // shareholders, officers
+}
+
+impl CompanySection {
+    pub const ALL: [CompanySection; 3] = [CompanySection::Price, CompanySection::Profile, CompanySection::Shareholders];
+
+    pub fn max_age(self) -> Duration {
+        match self {
+            CompanySection::Price => Duration::days(1),
+            CompanySection::Profile => Duration::days(30),
+            CompanySection::Shareholders => Duration::days(90),
+        }
+    }
+
+    /// Whether a fetched record carries data for this section
+    fn has_data(self, info: &CompanyInfo) -> bool {
+        match self {
+            CompanySection::Price => info.current_price.is_some() || info.market_cap.is_some(),
+            CompanySection::Profile => info.exchange.is_some() || info.industry.is_some() || info.company_profile.is_some(),
+            CompanySection::Shareholders => !info.shareholders.is_empty() || !info.officers.is_empty(),
+        }
+    }
+
+    fn copy(self, target: &mut CompanyInfo, source: &CompanyInfo) {
+        match self {
+            CompanySection::Price => {
+                target.current_price = source.current_price;
+                target.market_cap = source.market_cap;
+            }
+            CompanySection::Profile => {
+                target.exchange = source.exchange.clone();
+                target.industry = source.industry.clone();
+                target.company_type = source.company_type.clone();
+                target.established_year = source.established_year;
+                target.employees = source.employees;
+                target.outstanding_shares = source.outstanding_shares;
+                target.company_profile = source.company_profile.clone();
+                target.website = source.website.clone();
+            }
+            CompanySection::Shareholders => {
+                target.shareholders = source.shareholders.clone();
+                target.officers = source.officers.clone();
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SectionFreshness {
+    pub fetched_at: DateTime<Utc>,
+    pub age_secs: i64,
+    pub stale: bool,
+}
+
+/// Company info as served, with the age of each section
+#[derive(Clone, Debug, Serialize)]
+pub struct CompanyView {
+    #[serde(flatten)]
+    pub info: CompanyInfo,
+    pub freshness: BTreeMap<CompanySection, SectionFreshness>,
+}
+
+#[derive(Clone, Debug)]
+struct CachedCompany {
+    info: CompanyInfo,
+    fetched_at: HashMap<CompanySection, DateTime<Utc>>,
+}
+
+impl CachedCompany {
+    /// Sections past their max age; sections never fetched count as stale
+    fn stale_sections(&self, now: DateTime<Utc>) -> Vec<CompanySection> {
+        CompanySection::ALL.into_iter()
+            .filter(|section| self.fetched_at.get(section).is_none_or(|at| now - *at > section.max_age()))
+            .collect()
+    }
+
+    fn view(&self, now: DateTime<Utc>) -> CompanyView {
+        let freshness = self.fetched_at.iter()
+            .map(|(section, at)| (*section, SectionFreshness {
+                fetched_at: *at,
+                age_secs: (now - *at).num_seconds(),
+                stale: now - *at > section.max_age(),
+            }))
+            .collect();
+        CompanyView { info: self.info.clone(), freshness }
+    }
+
+    /// Take sections the fetch returned; keep cached values (and their age) for the rest
+    fn merge(&mut self, fetched: &CompanyInfo, now: DateTime<Utc>) {
+        for section in CompanySection::ALL {
+            if section.has_data(fetched) {
+                section.copy(&mut self.info, fetched);
+                self.fetched_at.insert(section, now);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CompanyCacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub stale_served: u64, // Served from cache while a background refresh ran
+    pub refreshes: u64,
+    pub refresh_errors: u64,
+}
+
+#[derive(Default)]
+pub struct CompanyInfoCache {
+    entries: HashMap<String, CachedCompany>,
+    refreshing: HashSet<String>,
+    stats: CompanyCacheStats,
+}
+
+pub enum CacheLookup {
+    Fresh(CompanyView),
+    Stale(CompanyView, Vec<CompanySection>),
+    Miss,
+}
+
+impl CompanyInfoCache {
+    pub fn lookup(&mut self, symbol: &str, now: DateTime<Utc>) -> CacheLookup {
+        let Some(cached) = self.entries.get(symbol) else {
+            self.stats.misses += 1;
+            return CacheLookup::Miss;
+        };
+        let stale = cached.stale_sections(now);
+        if stale.is_empty() {
+            self.stats.hits += 1;
+            CacheLookup::Fresh(cached.view(now))
+        } else {
+            self.stats.stale_served += 1;
+            CacheLookup::Stale(cached.view(now), stale)
+        }
+    }
+
+    pub fn store(&mut self, symbol: &str, fetched: &CompanyInfo, now: DateTime<Utc>) -> CompanyView {
+        let cached = self.entries.entry(symbol.to_string()).or_insert_with(|| CachedCompany {
+            info: fetched.clone(),
+            fetched_at: HashMap::new(),
+        });
+        cached.merge(fetched, now);
+        cached.view(now)
+    }
+
+    pub fn stats(&self) -> CompanyCacheStats {
+        CompanyCacheStats { entries: self.entries.len(), ..self.stats.clone() }
+    }
+}
+
+/// Read-through company info: misses are fetched inline, stale entries are served
+/// immediately and refreshed in the background
+pub struct CompanyService {
+    client: VciClient, // Shared, so lookups of different symbols fetch concurrently
+    cache: Mutex<CompanyInfoCache>,
+}
+
+pub type SharedCompanyService = Arc<CompanyService>;
+
+impl CompanyService {
+    pub fn new(client: VciClient) -> Self {
+        Self { client, cache: Mutex::new(CompanyInfoCache::default()) }
+    }
+
+    async fn fetch_and_store(&self, symbol: &str) -> Result<CompanyView, VciError> {
+        let result = self.client.company_info(symbol).await;
+        let mut cache = self.cache.lock().await;
+        cache.refreshing.remove(symbol);
+        match result {
+            Ok(fetched) => {
+                cache.stats.refreshes += 1;
+                Ok(cache.store(symbol, &fetched, Utc::now()))
+            }
+            Err(e) => {
+                cache.stats.refresh_errors += 1;
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn get(self: &Arc<Self>, symbol: &str) -> Result<CompanyView, VciError> {
+        let symbol = symbol.to_uppercase();
+        let lookup = self.cache.lock().await.lookup(&symbol, Utc::now());
+        match lookup {
+            CacheLookup::Fresh(view) => {
+                debug!(symbol, "Company info cache hit");
+                Ok(view)
+            }
+            CacheLookup::Stale(view, stale_sections) => {
+                let start_refresh = self.cache.lock().await.refreshing.insert(symbol.clone());
+                if start_refresh {
+                    info!(symbol, ?stale_sections, "Company info stale, refreshing in background");
+                    let service = Arc::clone(self);
+                    tokio::spawn(async move {
+                        if let Err(e) = service.fetch_and_store(&symbol).await {
+                            warn!(symbol, error = ?e, "Background company info refresh failed");
+                        }
+                    });
+                }
+                Ok(view)
+            }
+            CacheLookup::Miss => {
+                debug!(symbol, "Company info cache miss, fetching");
+                self.fetch_and_store(&symbol).await
+            }
+        }
+    }
+
+    pub async fn stats(&self) -> CompanyCacheStats {
+        self.cache.lock().await.stats()
+    }
+
+    /// Cached (symbol, outstanding shares, market cap) for every company looked up so far.
+    /// Never fetches and does not count as cache hits.
+    pub async fn cached_market_caps(&self) -> Vec<(String, Option<f64>, Option<f64>)> {
+        self.cache.lock().await.entries.iter()
+            .map(|(symbol, cached)| (symbol.clone(), cached.info.outstanding_shares.map(|s| s as f64), cached.info.market_cap))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vci::ShareholderInfo;
+
+    fn company(price: Option<f64>, with_shareholders: bool) -> CompanyInfo {
+        CompanyInfo {
+            symbol: "VCB".to_string(),
+            exchange: Some("HOSE".to_string()),
+            industry: Some("Banks".to_string()),
+            company_type: None,
+            established_year: None,
+            employees: None,
+            market_cap: None,
+            current_price: price,
+            outstanding_shares: None,
+            company_profile: None,
+            website: None,
+            shareholders: if with_shareholders {
+                vec![ShareholderInfo { name: "SBV".to_string(), percentage: 74.8 }]
+            } else {
+                Vec::new()
+            },
+            officers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_per_section_staleness_and_merge() {
+        let mut cache = CompanyInfoCache::default();
+        let start = Utc::now();
+        assert!(matches!(cache.lookup("VCB", start), CacheLookup::Miss));
+        cache.store("VCB", &company(Some(60.0), true), start);
+
+        assert!(matches!(cache.lookup("VCB", start + Duration::hours(1)), CacheLookup::Fresh(_)));
+        match cache.lookup("VCB", start + Duration::days(2)) {
+            CacheLookup::Stale(_, sections) => assert_eq!(sections, vec![CompanySection::Price]),
+            _ => panic!("expected stale price"),
+        }
+
+        // A refresh without shareholder data keeps the cached shareholders and their age
+        let later = start + Duration::days(2);
+        let view = cache.store("VCB", &company(Some(62.0), false), later);
+        assert_eq!(view.info.current_price, Some(62.0));
+        assert_eq!(view.info.shareholders.len(), 1);
+        assert_eq!(view.freshness[&CompanySection::Shareholders].fetched_at, start);
+        assert!(matches!(cache.lookup("VCB", later), CacheLookup::Fresh(_)));
+
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.hits, stats.misses, stats.stale_served), (1, 2, 1, 1));
+    }
+}
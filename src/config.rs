@@ -1,19 +1,47 @@
 use crate::data_structures::{SharedTickerGroups, TickerGroups};
+use crate::analysis::liquidity::{self, LiquidityPresets};
+use crate::analysis::strength::StrengthWeights;
+use crate::analysis::warmup::WarmupMode;
+use crate::auth::{TokenAccount, TokenRole};
+use crate::utils::header_profile::HeaderProfileConfig;
+use crate::utils::merge_policy::{self, BarOrigin};
+use crate::utils::http_client::HttpPoolConfig;
+use crate::utils::mirrors::DEFAULT_RAW_MIRRORS;
+use crate::utils::object_store::ObjectStoreConfig;
+use crate::utils::precision::PrecisionConfig;
+use crate::data_source::{DataSourceConfig, SourceKind};
+use crate::intraday::IntradayConfig;
+use crate::profile::{self, DeploymentProfile, Profiles};
+use crate::utils::provider_quota::QuotaThresholds;
+use crate::utils::query_cost::QueryCostLimits;
+use crate::utils::rate_limiter::{self, HostBudgets};
 use std::env;
 use std::fs;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use serde::{Deserialize, Serialize};
 
-// Holds tokens for zero-downtime rotation
+// Holds tokens for zero-downtime rotation, plus named per-node accounts
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TokenConfig {
     pub primary: String,
     pub secondary: String,
+    #[serde(default)]
+    pub node_token: Option<String>, // Presented to internal peers; defaults to primary
+    #[serde(default)]
+    pub accounts: Vec<TokenAccount>,
 }
 pub type SharedTokenConfig = Arc<TokenConfig>;
 
+impl TokenConfig {
+    /// Token this node sends in `Authorization` when gossiping to internal peers
+    pub fn outbound(&self) -> &str {
+        self.node_token.as_deref().unwrap_or(&self.primary)
+    }
+}
+
 // Holds URLs of peer servers (internal and public)
 pub type PeerList = Arc<Vec<String>>;
 
@@ -24,6 +52,10 @@ pub struct OfficeHours {
     pub end_hour: u32,       // e.g., 16 for 4pm
     pub timezone: String,    // e.g., "Asia/Ho_Chi_Minh"
     pub weekdays_only: bool, // true for Monday-Friday only
+    #[serde(default)]
+    pub start_minute: u32,   // e.g., 45 with start_hour 8 for 8:45am
+    #[serde(default)]
+    pub end_minute: u32,     // e.g., 45 with end_hour 14 for 2:45pm
 }
 
 impl Default for OfficeHours {
@@ -33,22 +65,56 @@ impl Default for OfficeHours {
             end_hour: 16,
             timezone: "Asia/Ho_Chi_Minh".to_string(),
             weekdays_only: true,
+            start_minute: 0,
+            end_minute: 0,
         }
     }
 }
 
+// Trading session for one exchange or instrument class (e.g. HNX, derivatives). Symbols join the
+// session named after their ticker_info exchange; the lists override that for unlisted instruments.
 #[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExchangeSession {
+    #[serde(flatten)]
+    pub hours: OfficeHours,
+    #[serde(default)]
+    pub symbols: Vec<String>,         // Exact symbols traded in this session
+    #[serde(default)]
+    pub symbol_prefixes: Vec<String>, // e.g. "VN30F" for VN30 index futures
+}
+
+impl ExchangeSession {
+    pub fn matches(&self, symbol: &str) -> bool {
+        self.symbols.iter().any(|s| s == symbol) || self.symbol_prefixes.iter().any(|prefix| symbol.starts_with(prefix.as_str()))
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct OfficeHoursConfig {
     pub default_office_hours: OfficeHours,
-    pub ticker_specific: HashMap<String, OfficeHours>, // Future: per-ticker hours
+    pub ticker_specific: HashMap<String, OfficeHours>, // Per-ticker hours, checked before sessions
+    #[serde(default)]
+    pub sessions: BTreeMap<String, ExchangeSession>, // Keyed by exchange or instrument class
 }
 
-impl Default for OfficeHoursConfig {
-    fn default() -> Self {
-        Self {
-            default_office_hours: OfficeHours::default(),
-            ticker_specific: HashMap::new(),
+impl OfficeHoursConfig {
+    /// Hours that apply to a symbol: ticker-specific, then a session listing the symbol, then the
+    /// session named after its exchange, then the default
+    pub fn hours_for_symbol(&self, symbol: &str, exchange: Option<&str>) -> &OfficeHours {
+        if let Some(hours) = self.ticker_specific.get(symbol) {
+            return hours;
         }
+        self.sessions.values()
+            .find(|session| session.matches(symbol))
+            .or_else(|| exchange.and_then(|exchange| self.sessions.iter().find(|(name, _)| name.eq_ignore_ascii_case(exchange)).map(|(_, session)| session)))
+            .map(|session| &session.hours)
+            .unwrap_or(&self.default_office_hours)
+    }
+
+    /// The default window plus every named session
+    pub fn all_hours(&self) -> impl Iterator<Item = (&str, &OfficeHours)> {
+        std::iter::once(("default", &self.default_office_hours))
+            .chain(self.sessions.iter().map(|(name, session)| (name.as_str(), &session.hours)))
     }
 }
 
@@ -61,10 +127,40 @@ pub struct ConfigYaml {
     pub public_peers: Vec<String>,
     pub core_network_url: Option<String>,
     pub public_refresh_interval_secs: u64,
+    pub standby_promote_after_secs: Option<u64>,
     pub core_worker_interval_secs: u64,
     pub non_office_hours_interval_secs: Option<u64>,
     pub enable_office_hours: Option<bool>,
     pub office_hours_config: Option<OfficeHoursConfig>,
+    pub strict_readiness: Option<bool>,
+    pub raw_mirror_urls: Option<Vec<String>>,
+    pub raw_checksum_manifest: Option<String>,
+    pub archive_dir: Option<String>,
+    pub object_store: Option<ObjectStoreConfig>,
+    pub load_shed_p95_ms: Option<u64>,
+    pub quota_thresholds: Option<QuotaThresholds>,
+    pub rate_limits: Option<HostBudgets>,
+    pub header_profiles: Option<HashMap<String, HeaderProfileConfig>>,
+    pub http_pool: Option<HttpPoolConfig>,
+    pub precision: Option<PrecisionConfig>,
+    pub symbol_stats_path: Option<String>,
+    pub indicator_cache_path: Option<String>,
+    pub strength_weights: Option<StrengthWeights>,
+    pub warmup_mode: Option<WarmupMode>,
+    pub liquidity_presets: Option<LiquidityPresets>,
+    pub ticker_info_url: Option<String>,
+    pub ticker_info_refresh_secs: Option<u64>,
+    pub foreign_flow_refresh_secs: Option<u64>,
+    pub query_cost_limits: Option<QueryCostLimits>,
+    pub wal_dir: Option<String>,
+    pub wal_checkpoint_secs: Option<u64>,
+    pub data_sources: Option<DataSourceConfig>,
+    pub transition_context: Option<bool>,
+    pub intraday: Option<IntradayConfig>,
+    pub merge_precedence: Option<Vec<BarOrigin>>,
+    pub synthetic_indices: Option<Vec<String>>,
+    pub profile: Option<String>,
+    pub profiles: Option<Profiles>,
     pub environment: String,
     pub port: u16,
 }
@@ -78,10 +174,40 @@ pub struct AppConfig {
     pub public_peers: PeerList,
     pub core_network_url: Option<String>,
     pub public_refresh_interval: Duration,
+    pub standby_promote_after: Option<Duration>, // Public nodes fetch from VCI after the core is unreachable this long
     pub core_worker_interval: Duration,
     pub non_office_hours_interval: Duration,
     pub enable_office_hours: bool,
     pub office_hours_config: OfficeHoursConfig,
+    pub strict_readiness: bool, // Return 503 + Retry-After from analysis endpoints until data is ready
+    pub raw_mirror_urls: Vec<String>, // Base URLs for /raw, tried in order
+    pub raw_checksum_manifest: Option<String>, // Optional sha256 manifest path for /raw downloads
+    pub archive_dir: Option<String>, // Write immutable daily partitions here after EOD (core nodes)
+    pub object_store: Option<ObjectStoreConfig>, // S3-compatible bucket for the raw cache and archive
+    pub load_shed_p95_ms: u64, // p95 latency SLO for protected routes; 0 disables load shedding
+    pub quota_thresholds: QuotaThresholds, // Warn when upstream usage approaches a ban
+    pub rate_limits: HostBudgets, // Requests per minute per upstream host, shared by all clients
+    pub header_profiles: HashMap<String, HeaderProfileConfig>, // Per-provider header overrides, keyed by provider ("vci")
+    pub http_pool: HttpPoolConfig, // Connection pool shared by provider clients
+    pub precision: PrecisionConfig, // Decimal places in JSON output unless a request asks for precision=full
+    pub symbol_stats_path: PathBuf, // Persisted per-symbol statistics used by the worker scheduler
+    pub indicator_cache_path: Option<PathBuf>, // Computed MA scores, strength and money flow reloaded on startup; None disables
+    pub strength_weights: StrengthWeights, // Component weights of the composite strength score
+    pub warmup_mode: WarmupMode, // Whether indicators are null or computed from partial windows before they warm up
+    pub liquidity_presets: LiquidityPresets, // Named ADTV thresholds for the `liquidity` filter; added to the built-in "investable"
+    pub ticker_info_url: Option<String>, // ticker_info.json with company names, refreshed in the background
+    pub ticker_info_refresh: Duration,
+    pub foreign_flow_refresh: Option<Duration>, // How often foreign buy/sell flow is refreshed; None (the default) disables
+    pub query_cost_limits: QueryCostLimits, // Cell ceilings for /tickers responses, per request and per client
+    pub wal_dir: Option<PathBuf>, // Checkpoint and write-ahead log of live updates, replayed on startup; None disables
+    pub wal_checkpoint_interval: Duration,
+    pub data_sources: DataSourceConfig, // Providers the core worker fetches from, and how it fails over between them
+    pub transition_context: bool, // Attach a worker state snapshot to each recorded state transition
+    pub intraday: IntradayConfig, // Intraday intervals the core worker collects next to daily bars
+    pub merge_precedence: Vec<BarOrigin>, // Which origin's bar is kept when two disagree, highest first
+    pub synthetic_indices: Vec<String>, // Indices built from index_constituents.json instead of fetched, e.g. VN30
+    pub profile_name: String,
+    pub profile: DeploymentProfile, // Universe, history, retention and indicators of this deployment
     pub environment: String,
     pub port: u16,
     pub build_date: Option<String>,
@@ -106,6 +232,9 @@ impl AppConfig {
         
         let yaml_config: ConfigYaml = serde_yaml::from_str(&yaml_content)
             .unwrap_or_else(|e| panic!("Failed to parse YAML config: {}", e));
+        // PROFILE picks among the file's profiles too, so one config file can serve every node
+        let profile_name = env::var("PROFILE").ok().or(yaml_config.profile.clone());
+        let (profile_name, profile) = profile::select(profile_name.as_deref(), yaml_config.profiles.clone().unwrap_or_default());
 
         Self {
             node_name: yaml_config.node_name,
@@ -114,10 +243,52 @@ impl AppConfig {
             public_peers: Arc::new(yaml_config.public_peers),
             core_network_url: yaml_config.core_network_url,
             public_refresh_interval: Duration::from_secs(yaml_config.public_refresh_interval_secs),
+            standby_promote_after: yaml_config.standby_promote_after_secs.filter(|secs| *secs > 0).map(Duration::from_secs),
             core_worker_interval: Duration::from_secs(yaml_config.core_worker_interval_secs),
             non_office_hours_interval: Duration::from_secs(yaml_config.non_office_hours_interval_secs.unwrap_or(300)),
             enable_office_hours: yaml_config.enable_office_hours.unwrap_or(true),
             office_hours_config: yaml_config.office_hours_config.unwrap_or_default(),
+            strict_readiness: yaml_config.strict_readiness.unwrap_or(false),
+            raw_mirror_urls: yaml_config.raw_mirror_urls.unwrap_or_else(default_raw_mirror_urls),
+            raw_checksum_manifest: yaml_config.raw_checksum_manifest,
+            archive_dir: yaml_config.archive_dir,
+            object_store: yaml_config.object_store,
+            load_shed_p95_ms: yaml_config.load_shed_p95_ms.unwrap_or(DEFAULT_LOAD_SHED_P95_MS),
+            quota_thresholds: yaml_config.quota_thresholds.unwrap_or_default(),
+            rate_limits: rate_limiter::default_budgets().into_iter()
+                .chain(yaml_config.rate_limits.unwrap_or_default().into_iter().map(|(host, budget)| (host.to_lowercase(), budget)))
+                .collect(),
+            header_profiles: yaml_config.header_profiles.unwrap_or_default(),
+            http_pool: yaml_config.http_pool.unwrap_or_default(),
+            precision: yaml_config.precision.unwrap_or_default(),
+            symbol_stats_path: yaml_config.symbol_stats_path.map(PathBuf::from).unwrap_or_else(default_symbol_stats_path),
+            indicator_cache_path: match yaml_config.indicator_cache_path {
+                Some(path) if path.is_empty() => None,
+                Some(path) => Some(PathBuf::from(path)),
+                None => Some(default_indicator_cache_path()),
+            },
+            strength_weights: yaml_config.strength_weights.unwrap_or_default(),
+            warmup_mode: yaml_config.warmup_mode.unwrap_or_default(),
+            liquidity_presets: liquidity::default_presets().into_iter()
+                .chain(yaml_config.liquidity_presets.unwrap_or_default().into_iter().map(|(name, preset)| (name.to_lowercase(), preset)))
+                .collect(),
+            ticker_info_url: yaml_config.ticker_info_url.filter(|url| !url.is_empty()),
+            ticker_info_refresh: Duration::from_secs(yaml_config.ticker_info_refresh_secs.unwrap_or(DEFAULT_TICKER_INFO_REFRESH_SECS)),
+            foreign_flow_refresh: foreign_flow_refresh(yaml_config.foreign_flow_refresh_secs),
+            query_cost_limits: yaml_config.query_cost_limits.unwrap_or_default(),
+            wal_dir: match yaml_config.wal_dir {
+                Some(dir) if dir.is_empty() => None,
+                Some(dir) => Some(PathBuf::from(dir)),
+                None => Some(default_wal_dir()),
+            },
+            wal_checkpoint_interval: Duration::from_secs(yaml_config.wal_checkpoint_secs.unwrap_or(DEFAULT_WAL_CHECKPOINT_SECS).max(1)),
+            data_sources: yaml_config.data_sources.filter(|sources| !sources.sources.is_empty()).unwrap_or_default(),
+            transition_context: yaml_config.transition_context.unwrap_or(false),
+            intraday: yaml_config.intraday.unwrap_or_default(),
+            merge_precedence: yaml_config.merge_precedence.map_or_else(merge_policy::default_precedence, merge_policy::checked_precedence),
+            synthetic_indices: parse_index_names(&yaml_config.synthetic_indices.unwrap_or_default().join(",")),
+            profile_name,
+            profile,
             environment: yaml_config.environment,
             port: yaml_config.port,
             build_date: env::var("BUILD_DATE").ok(),
@@ -132,6 +303,8 @@ impl AppConfig {
         let tokens = Arc::new(TokenConfig {
             primary: env::var("PRIMARY_TOKEN").expect("PRIMARY_TOKEN must be set"),
             secondary: env::var("SECONDARY_TOKEN").expect("SECONDARY_TOKEN must be set"),
+            node_token: env::var("NODE_TOKEN").ok().filter(|s| !s.is_empty()),
+            accounts: env::var("ACCESS_TOKENS").map(|s| parse_token_accounts(&s)).unwrap_or_default(),
         });
 
         let internal_peers = Arc::new(
@@ -159,6 +332,12 @@ impl AppConfig {
             .and_then(|s| s.parse().ok())
             .unwrap_or(300); // Default to 5 minutes
 
+        let standby_promote_after = env::var("STANDBY_PROMOTE_AFTER")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|secs| *secs > 0) // Unset or 0 keeps public nodes as pure mirrors
+            .map(Duration::from_secs);
+
         let core_worker_interval_secs = env::var("CORE_WORKER_INTERVAL")
             .ok()
             .and_then(|s| s.parse().ok())
@@ -185,6 +364,171 @@ impl AppConfig {
             .and_then(|s| s.parse().ok())
             .unwrap_or(true); // Default to true
 
+        let strict_readiness = env::var("STRICT_READINESS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(false); // Default to serving partial results with readiness metadata
+
+        let transition_context = env::var("TRANSITION_CONTEXT")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(false);
+
+        let raw_mirror_urls = env::var("RAW_MIRROR_URLS")
+            .ok()
+            .map(|s| s.split(',').filter(|s| !s.is_empty()).map(String::from).collect::<Vec<String>>())
+            .filter(|urls| !urls.is_empty())
+            .unwrap_or_else(default_raw_mirror_urls); // GitHub raw, then jsDelivr CDN
+
+        let raw_checksum_manifest = env::var("RAW_CHECKSUM_MANIFEST").ok().filter(|s| !s.is_empty());
+
+        let archive_dir = env::var("ARCHIVE_DIR").ok().filter(|s| !s.is_empty()); // Archiving disabled when unset
+
+        // Object storage is enabled when endpoint and bucket are both set
+        let object_store = match (env::var("OBJECT_STORE_ENDPOINT"), env::var("OBJECT_STORE_BUCKET")) {
+            (Ok(endpoint), Ok(bucket)) if !endpoint.is_empty() && !bucket.is_empty() => Some(ObjectStoreConfig {
+                endpoint,
+                bucket,
+                region: env::var("OBJECT_STORE_REGION").ok(),
+                access_key: env::var("OBJECT_STORE_ACCESS_KEY").unwrap_or_default(),
+                secret_key: env::var("OBJECT_STORE_SECRET_KEY").unwrap_or_default(),
+                prefix: env::var("OBJECT_STORE_PREFIX").ok(),
+            }),
+            _ => None,
+        };
+
+        let load_shed_p95_ms = env::var("LOAD_SHED_P95_MS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_LOAD_SHED_P95_MS); // 0 disables load shedding
+
+        let default_thresholds = QuotaThresholds::default();
+        let quota_thresholds = QuotaThresholds {
+            requests_per_minute: env::var("QUOTA_WARN_REQUESTS_PER_MINUTE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(default_thresholds.requests_per_minute),
+            blocked_per_hour: env::var("QUOTA_WARN_BLOCKED_PER_HOUR")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(default_thresholds.blocked_per_hour),
+        };
+
+        // e.g. "trading.vietcap.com.vn=30,apipubaws.tcbs.com.vn=20:5" (requests per minute, burst)
+        let rate_limits: HostBudgets = rate_limiter::default_budgets().into_iter()
+            .chain(rate_limiter::parse_budgets(&env::var("RATE_LIMITS").unwrap_or_default()))
+            .collect();
+
+        let header_profiles: HashMap<String, HeaderProfileConfig> = ["vci"].iter()
+            .filter_map(|provider| header_profile_from_env(provider).map(|profile| (provider.to_string(), profile)))
+            .collect();
+
+        let default_pool = HttpPoolConfig::default();
+        let http_pool = HttpPoolConfig {
+            pool_max_idle_per_host: env::var("HTTP_POOL_MAX_IDLE_PER_HOST").ok().and_then(|s| s.parse().ok()).unwrap_or(default_pool.pool_max_idle_per_host),
+            pool_idle_timeout_secs: env::var("HTTP_POOL_IDLE_TIMEOUT").ok().and_then(|s| s.parse().ok()).unwrap_or(default_pool.pool_idle_timeout_secs),
+            tcp_keepalive_secs: env::var("HTTP_TCP_KEEPALIVE").ok().and_then(|s| s.parse().ok()).unwrap_or(default_pool.tcp_keepalive_secs),
+            tcp_nodelay: env::var("HTTP_TCP_NODELAY").ok().and_then(|s| s.parse().ok()).unwrap_or(default_pool.tcp_nodelay),
+            http2_prior_knowledge: env::var("HTTP2_PRIOR_KNOWLEDGE").ok().and_then(|s| s.parse().ok()).unwrap_or(default_pool.http2_prior_knowledge),
+            timeout_secs: env::var("HTTP_TIMEOUT").ok().and_then(|s| s.parse().ok()).unwrap_or(default_pool.timeout_secs),
+        };
+
+        let default_precision = PrecisionConfig::default();
+        let precision = PrecisionConfig {
+            price_decimals: env::var("PRICE_DECIMALS").ok().and_then(|s| s.parse().ok()).unwrap_or(default_precision.price_decimals),
+            percent_decimals: env::var("PERCENT_DECIMALS").ok().and_then(|s| s.parse().ok()).unwrap_or(default_precision.percent_decimals),
+            score_decimals: env::var("SCORE_DECIMALS").ok().and_then(|s| s.parse().ok()).unwrap_or(default_precision.score_decimals),
+        };
+
+        let symbol_stats_path = env::var("SYMBOL_STATS_PATH")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(default_symbol_stats_path); // Next to the raw cache by default
+        let indicator_cache_path = match env::var("INDICATOR_CACHE_PATH") {
+            Ok(path) if path.is_empty() => None, // Explicitly disabled
+            Ok(path) => Some(PathBuf::from(path)),
+            Err(_) => Some(default_indicator_cache_path()),
+        };
+
+        let default_weights = StrengthWeights::default();
+        let strength_weights = StrengthWeights {
+            money_flow: env::var("STRENGTH_WEIGHT_MONEY_FLOW").ok().and_then(|s| s.parse().ok()).unwrap_or(default_weights.money_flow),
+            ma_score: env::var("STRENGTH_WEIGHT_MA_SCORE").ok().and_then(|s| s.parse().ok()).unwrap_or(default_weights.ma_score),
+            relative_volume: env::var("STRENGTH_WEIGHT_RELATIVE_VOLUME").ok().and_then(|s| s.parse().ok()).unwrap_or(default_weights.relative_volume),
+            trend: env::var("STRENGTH_WEIGHT_TREND").ok().and_then(|s| s.parse().ok()).unwrap_or(default_weights.trend),
+        };
+
+        // "strict" (default) or "lenient"
+        let warmup_mode = match env::var("WARMUP_MODE").ok().filter(|s| !s.is_empty()).map(|s| s.parse::<WarmupMode>()) {
+            Some(Ok(mode)) => mode,
+            Some(Err(e)) => {
+                tracing::warn!(error = %e, "Invalid WARMUP_MODE, using strict");
+                WarmupMode::Strict
+            }
+            None => WarmupMode::Strict,
+        };
+
+        // e.g. "investable=5000000000:20,tradable=1000000000:20" (min ADTV in VND, sessions)
+        let liquidity_presets: LiquidityPresets = liquidity::default_presets().into_iter()
+            .chain(liquidity::parse_presets(&env::var("LIQUIDITY_PRESETS").unwrap_or_default()))
+            .collect();
+
+        let ticker_info_url = env::var("TICKER_INFO_URL").ok().filter(|s| !s.is_empty());
+        let ticker_info_refresh_secs = env::var("TICKER_INFO_REFRESH_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_TICKER_INFO_REFRESH_SECS);
+
+        let foreign_flow_refresh_secs = env::var("FOREIGN_FLOW_REFRESH_SECS").ok().and_then(|s| s.parse().ok());
+
+        let default_cost_limits = QueryCostLimits::default();
+        let query_cost_limits = QueryCostLimits {
+            max_cells_per_request: env::var("QUERY_MAX_CELLS").ok().and_then(|s| s.parse().ok()).unwrap_or(default_cost_limits.max_cells_per_request),
+            max_cells_per_key_per_minute: env::var("QUERY_MAX_CELLS_PER_KEY_PER_MINUTE").ok().and_then(|s| s.parse().ok()).unwrap_or(default_cost_limits.max_cells_per_key_per_minute),
+        };
+
+        let wal_dir = match env::var("WAL_DIR") {
+            Ok(dir) if dir.is_empty() => None, // Explicitly disabled
+            Ok(dir) => Some(PathBuf::from(dir)),
+            Err(_) => Some(default_wal_dir()),
+        };
+        let wal_checkpoint_secs = env::var("WAL_CHECKPOINT_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_WAL_CHECKPOINT_SECS)
+            .max(1);
+
+        let default_sources = DataSourceConfig::default();
+        let data_sources = DataSourceConfig {
+            sources: env::var("DATA_SOURCES")
+                .ok()
+                .map(|s| s.split(',').filter_map(|source| source.parse::<SourceKind>().ok()).collect::<Vec<_>>())
+                .filter(|sources| !sources.is_empty())
+                .unwrap_or(default_sources.sources), // Priority order, e.g. "vci,tcbs"
+            strategy: env::var("DATA_SOURCE_STRATEGY").ok().and_then(|s| s.parse().ok()).unwrap_or(default_sources.strategy),
+            failover_after: env::var("DATA_SOURCE_FAILOVER_AFTER").ok().and_then(|s| s.parse().ok()).unwrap_or(default_sources.failover_after),
+        };
+
+        // Intraday collection is opt-in, e.g. INTRADAY_INTERVALS="1H,15m" and INTRADAY_MEMORY_MB="1H=32,15m=64"
+        let intraday = IntradayConfig::parse(
+            &env::var("INTRADAY_INTERVALS").unwrap_or_default(),
+            &env::var("INTRADAY_MEMORY_MB").unwrap_or_default(),
+        );
+
+        // Highest first, e.g. MERGE_PRECEDENCE="official,live,gossip"
+        let merge_precedence = env::var("MERGE_PRECEDENCE")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map_or_else(merge_policy::default_precedence, |s| merge_policy::parse_precedence(&s));
+
+        // e.g. SYNTHETIC_INDICES="VN30,VN100"
+        let synthetic_indices = parse_index_names(&env::var("SYNTHETIC_INDICES").unwrap_or_default());
+
+        // Built-in "full" and "vn30", plus any defined in PROFILES_FILE
+        let custom_profiles = env::var("PROFILES_FILE").ok().filter(|s| !s.is_empty()).map(|path| profile::load_profiles(&path)).unwrap_or_default();
+        let (profile_name, profile) = profile::select(env::var("PROFILE").ok().as_deref(), custom_profiles);
+
         Self {
             node_name,
             tokens,
@@ -192,10 +536,43 @@ impl AppConfig {
             public_peers,
             core_network_url,
             public_refresh_interval: Duration::from_secs(public_refresh_interval_secs),
+            standby_promote_after,
             core_worker_interval: Duration::from_secs(core_worker_interval_secs),
             non_office_hours_interval: Duration::from_secs(non_office_hours_interval_secs),
             enable_office_hours,
-            office_hours_config: OfficeHoursConfig::default(), // Use default Vietnam office hours
+            office_hours_config: OfficeHoursConfig {
+                sessions: env::var("OFFICE_HOURS_SESSIONS").map(|s| parse_office_hours_sessions(&s)).unwrap_or_default(),
+                ..OfficeHoursConfig::default() // Default Vietnam office hours outside the listed sessions
+            },
+            strict_readiness,
+            raw_mirror_urls,
+            raw_checksum_manifest,
+            archive_dir,
+            object_store,
+            load_shed_p95_ms,
+            quota_thresholds,
+            rate_limits,
+            header_profiles,
+            http_pool,
+            precision,
+            symbol_stats_path,
+            indicator_cache_path,
+            strength_weights,
+            warmup_mode,
+            liquidity_presets,
+            ticker_info_url,
+            ticker_info_refresh: Duration::from_secs(ticker_info_refresh_secs),
+            foreign_flow_refresh: foreign_flow_refresh(foreign_flow_refresh_secs),
+            query_cost_limits,
+            wal_dir,
+            wal_checkpoint_interval: Duration::from_secs(wal_checkpoint_secs),
+            data_sources,
+            transition_context,
+            intraday,
+            merge_precedence,
+            synthetic_indices,
+            profile_name,
+            profile,
             environment,
             port,
             build_date: env::var("BUILD_DATE").ok(),
@@ -204,6 +581,112 @@ impl AppConfig {
     }
 }
 
+/// Upper-cased, deduplicated index names from a comma-separated list
+fn parse_index_names(value: &str) -> Vec<String> {
+    let mut names: Vec<String> = value.split(',').map(|name| name.trim().to_uppercase()).filter(|name| !name.is_empty()).collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+const DEFAULT_LOAD_SHED_P95_MS: u64 = 0; // Off unless an SLO is configured
+const DEFAULT_TICKER_INFO_REFRESH_SECS: u64 = 86_400; // Company names change rarely
+const DEFAULT_WAL_CHECKPOINT_SECS: u64 = 300;
+
+/// Foreign flow refresh interval; off unless set, since a core pass makes one TCBS request per symbol
+fn foreign_flow_refresh(secs: Option<u64>) -> Option<Duration> {
+    secs.filter(|secs| *secs > 0).map(Duration::from_secs)
+}
+
+/// Header overrides for a provider from `<PROVIDER>_USER_AGENTS` (separated by `|`), `<PROVIDER>_REFERER`,
+/// `<PROVIDER>_ORIGIN`, `<PROVIDER>_ACCEPT_LANGUAGE` and `<PROVIDER>_UA_ROTATION`, or None if none are set
+fn header_profile_from_env(provider: &str) -> Option<HeaderProfileConfig> {
+    let prefix = provider.to_uppercase();
+    let var = |name: &str| env::var(format!("{}_{}", prefix, name)).ok().filter(|s| !s.is_empty());
+
+    let profile = HeaderProfileConfig {
+        user_agents: var("USER_AGENTS").map(|s| s.split('|').map(|ua| ua.trim().to_string()).filter(|ua| !ua.is_empty()).collect()),
+        referer: var("REFERER"),
+        origin: var("ORIGIN"),
+        accept_language: var("ACCEPT_LANGUAGE"),
+        rotation: var("UA_ROTATION").and_then(|s| s.parse().map_err(|e| tracing::warn!(provider, error = %e, "Ignoring invalid user agent rotation")).ok()),
+    };
+    let is_set = profile.user_agents.is_some() || profile.referer.is_some() || profile.origin.is_some()
+        || profile.accept_language.is_some() || profile.rotation.is_some();
+    is_set.then_some(profile)
+}
+
+/// Parse `NAME=HH:MM-HH:MM[@PREFIX|PREFIX]` sessions separated by commas, e.g.
+/// "HNX=09:00-14:45,DERIVATIVES=08:45-14:45@VN30F". Times are in the default timezone.
+pub fn parse_office_hours_sessions(value: &str) -> BTreeMap<String, ExchangeSession> {
+    let parse_time = |time: &str| -> Option<(u32, u32)> {
+        let (hour, minute) = time.trim().split_once(':')?;
+        Some((hour.parse().ok().filter(|h| *h < 24)?, minute.parse().ok().filter(|m| *m < 60)?))
+    };
+    value.split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .filter_map(|entry| {
+            let parsed = entry.split_once('=').and_then(|(name, spec)| {
+                let (window, prefixes) = spec.split_once('@').map_or((spec, ""), |(window, prefixes)| (window, prefixes));
+                let (start, end) = window.split_once('-')?;
+                let ((start_hour, start_minute), (end_hour, end_minute)) = (parse_time(start)?, parse_time(end)?);
+                let name = name.trim().to_uppercase();
+                (!name.is_empty()).then(|| (name, ExchangeSession {
+                    hours: OfficeHours { start_hour, start_minute, end_hour, end_minute, ..OfficeHours::default() },
+                    symbols: Vec::new(),
+                    symbol_prefixes: prefixes.split('|').map(|prefix| prefix.trim().to_uppercase()).filter(|prefix| !prefix.is_empty()).collect(),
+                }))
+            });
+            if parsed.is_none() {
+                tracing::warn!(entry, "Ignoring invalid office hours session");
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// Parse `name:role:token[:expires_at]` entries separated by commas (expiry is RFC 3339)
+fn parse_token_accounts(value: &str) -> Vec<TokenAccount> {
+    value.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let mut parts = entry.splitn(4, ':');
+            let (name, role, token) = match (parts.next(), parts.next(), parts.next()) {
+                (Some(name), Some(role), Some(token)) if !name.is_empty() && !token.is_empty() => (name, role, token),
+                _ => panic!("Invalid ACCESS_TOKENS entry, expected name:role:token[:expires_at]"),
+            };
+            let role = match role {
+                "peer" => TokenRole::Peer,
+                "admin" => TokenRole::Admin,
+                other => panic!("Invalid role '{}' for token account {}, expected peer or admin", other, name),
+            };
+            let expires_at = parts.next().map(|expires_at| {
+                chrono::DateTime::parse_from_rfc3339(expires_at)
+                    .unwrap_or_else(|e| panic!("Invalid expiry for token account {}: {}", name, e))
+                    .with_timezone(&chrono::Utc)
+            });
+            TokenAccount { name: name.to_string(), token: token.to_string(), role, expires_at }
+        })
+        .collect()
+}
+
+fn default_raw_mirror_urls() -> Vec<String> {
+    DEFAULT_RAW_MIRRORS.iter().map(|s| s.to_string()).collect()
+}
+
+fn default_symbol_stats_path() -> PathBuf {
+    crate::utils::cache::get_cache_dir().join("symbol_stats.json")
+}
+
+fn default_indicator_cache_path() -> PathBuf {
+    crate::utils::cache::get_cache_dir().join("indicator_cache.bin")
+}
+
+fn default_wal_dir() -> PathBuf {
+    crate::utils::cache::get_cache_dir().join("wal")
+}
+
 /// Load ticker groups from ticker_group.json file
 pub fn load_ticker_groups() -> SharedTickerGroups {
     let ticker_group_path = "ticker_group.json";
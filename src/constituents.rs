@@ -0,0 +1,105 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+
+const INDEX_CONSTITUENTS_PATH: &str = "index_constituents.json";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Constituent {
+    pub symbol: String,
+    pub weight: Option<f64>, // Index weight in percent, if published
+}
+
+// One membership list, valid from effective_date until the next snapshot
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MembershipSnapshot {
+    pub effective_date: NaiveDate,
+    pub constituents: Vec<Constituent>,
+}
+
+// Index name (e.g. "VN30") -> membership snapshots
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct IndexConstituents(pub HashMap<String, Vec<MembershipSnapshot>>);
+
+pub type SharedIndexConstituents = Arc<IndexConstituents>;
+
+impl IndexConstituents {
+    /// Get the membership snapshot in effect on the given date (point-in-time)
+    pub fn membership_at(&self, index_name: &str, date: NaiveDate) -> Option<&MembershipSnapshot> {
+        self.0
+            .get(&index_name.to_uppercase())?
+            .iter()
+            .filter(|snapshot| snapshot.effective_date <= date)
+            .max_by_key(|snapshot| snapshot.effective_date)
+    }
+
+    /// Get the member symbols of an index on the given date
+    pub fn symbols_at(&self, index_name: &str, date: NaiveDate) -> Option<Vec<String>> {
+        self.membership_at(index_name, date)
+            .map(|snapshot| snapshot.constituents.iter().map(|c| c.symbol.clone()).collect())
+    }
+
+    pub fn index_names(&self) -> Vec<String> {
+        self.0.keys().cloned().collect()
+    }
+}
+
+/// Load index constituents from index_constituents.json.
+/// The file is optional; a missing or invalid file yields an empty set.
+pub fn load_index_constituents() -> SharedIndexConstituents {
+    tracing::info!("Loading index constituents from: {}", INDEX_CONSTITUENTS_PATH);
+
+    let constituents = match fs::read_to_string(INDEX_CONSTITUENTS_PATH) {
+        Ok(json_content) => match serde_json::from_str::<IndexConstituents>(&json_content) {
+            Ok(parsed) => {
+                // Normalise index names so lookups are case-insensitive
+                let normalised = parsed.0.into_iter()
+                    .map(|(name, snapshots)| (name.to_uppercase(), snapshots))
+                    .collect();
+                IndexConstituents(normalised)
+            }
+            Err(e) => {
+                tracing::warn!("Failed to parse {}: {}", INDEX_CONSTITUENTS_PATH, e);
+                IndexConstituents::default()
+            }
+        },
+        Err(e) => {
+            tracing::warn!("Failed to read {}: {}", INDEX_CONSTITUENTS_PATH, e);
+            IndexConstituents::default()
+        }
+    };
+
+    tracing::info!("Loaded constituents for {} indices", constituents.0.len());
+
+    Arc::new(constituents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(date: &str, symbols: &[&str]) -> MembershipSnapshot {
+        MembershipSnapshot {
+            effective_date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            constituents: symbols.iter().map(|s| Constituent { symbol: s.to_string(), weight: None }).collect(),
+        }
+    }
+
+    #[test]
+    fn test_point_in_time_membership() {
+        let mut map = HashMap::new();
+        map.insert("VN30".to_string(), vec![
+            snapshot("2025-02-03", &["ACB", "VCB"]),
+            snapshot("2024-08-05", &["ACB", "PDR"]),
+        ]);
+        let constituents = IndexConstituents(map);
+
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        assert!(constituents.membership_at("VN30", date("2024-01-01")).is_none());
+        assert_eq!(constituents.symbols_at("vn30", date("2024-12-31")).unwrap(), vec!["ACB", "PDR"]);
+        assert_eq!(constituents.symbols_at("VN30", date("2025-02-03")).unwrap(), vec!["ACB", "VCB"]);
+        assert!(constituents.membership_at("VN100", date("2025-02-03")).is_none());
+    }
+}
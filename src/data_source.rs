@@ -0,0 +1,366 @@
+use crate::events::UpdateSource;
+use crate::intraday::Interval;
+use crate::tcbs::{self, TcbsClient};
+use crate::utils::metrics;
+use crate::utils::transitions;
+use crate::vci::{OhlcvData, VciClient};
+use chrono::{DateTime, NaiveDate, Utc};
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Instant;
+use tracing::{debug, info, warn};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceKind {
+    Vci,
+    Tcbs,
+}
+
+impl SourceKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            SourceKind::Vci => "vci",
+            SourceKind::Tcbs => "tcbs",
+        }
+    }
+
+    pub fn update_source(self) -> UpdateSource {
+        match self {
+            SourceKind::Vci => UpdateSource::Vci,
+            SourceKind::Tcbs => UpdateSource::Tcbs,
+        }
+    }
+}
+
+impl std::str::FromStr for SourceKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "vci" => Ok(SourceKind::Vci),
+            "tcbs" => Ok(SourceKind::Tcbs),
+            other => Err(format!("Unknown data source '{}'. Expected vci or tcbs", other)),
+        }
+    }
+}
+
+// How the core worker spreads batches over its data sources
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceStrategy {
+    #[default]
+    Fallback,   // First source; the next one only after repeated failures
+    RoundRobin, // Rotate batches across healthy sources
+}
+
+impl std::str::FromStr for SourceStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "fallback" => Ok(SourceStrategy::Fallback),
+            "round_robin" => Ok(SourceStrategy::RoundRobin),
+            other => Err(format!("Unknown data source strategy '{}'. Expected fallback or round_robin", other)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DataSourceConfig {
+    pub sources: Vec<SourceKind>, // In priority order
+    pub strategy: SourceStrategy,
+    pub failover_after: u32, // Consecutive failed batches before a source is considered down
+}
+
+impl Default for DataSourceConfig {
+    fn default() -> Self {
+        Self { sources: vec![SourceKind::Vci], strategy: SourceStrategy::Fallback, failover_after: 3 }
+    }
+}
+
+/// Reported per source in `/health`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SourceHealth {
+    pub source: SourceKind,
+    pub healthy: bool,
+    pub consecutive_failures: u32,
+    pub batches_ok: u64,
+    pub batches_failed: u64,
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+/// Reported in `/health` for core nodes
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DataSourceStatus {
+    pub strategy: SourceStrategy,
+    pub active: SourceKind,
+    pub failovers: u64,
+    pub sources: Vec<SourceHealth>,
+}
+
+/// Picks the source for each batch and tracks how each one is doing
+pub struct SourceSelector {
+    strategy: SourceStrategy,
+    failover_after: u32,
+    health: Vec<SourceHealth>,
+    active: usize,
+    next: usize,
+    probe: Option<usize>,
+    failovers: u64,
+}
+
+impl SourceSelector {
+    pub fn new(sources: &[SourceKind], strategy: SourceStrategy, failover_after: u32) -> Self {
+        Self {
+            strategy,
+            failover_after: failover_after.max(1),
+            health: sources.iter().map(|source| SourceHealth {
+                source: *source,
+                healthy: true,
+                consecutive_failures: 0,
+                batches_ok: 0,
+                batches_failed: 0,
+                last_success: None,
+                last_error: None,
+            }).collect(),
+            active: 0,
+            next: 0,
+            probe: None,
+            failovers: 0,
+        }
+    }
+
+    /// Start a fetch cycle. A source that is down gets one batch to prove it recovered:
+    /// the primary while a fallback is in use, or the first unhealthy source in a rotation.
+    pub fn begin_cycle(&mut self) {
+        self.probe = match self.strategy {
+            SourceStrategy::Fallback => (self.active != 0).then_some(0),
+            SourceStrategy::RoundRobin => self.health.iter().position(|health| !health.healthy),
+        };
+    }
+
+    /// Index of the source for the next batch
+    pub fn pick(&mut self) -> usize {
+        if let Some(probe) = self.probe.take() {
+            return probe;
+        }
+        match self.strategy {
+            SourceStrategy::Fallback => self.active,
+            SourceStrategy::RoundRobin => {
+                let count = self.health.len();
+                let start = self.next;
+                // Unhealthy sources are skipped unless none is healthy
+                let index = (0..count).map(|offset| (start + offset) % count)
+                    .find(|index| self.health[*index].healthy)
+                    .unwrap_or(start % count);
+                self.next = (index + 1) % count;
+                self.active = index;
+                index
+            }
+        }
+    }
+
+    /// Record a batch outcome. Returns the source to retry the batch with when this failure made the
+    /// selector fail over to another source.
+    pub fn record(&mut self, index: usize, result: Result<(), String>, now: DateTime<Utc>) -> Option<usize> {
+        let health = &mut self.health[index];
+        match result {
+            Ok(()) => {
+                health.healthy = true;
+                health.consecutive_failures = 0;
+                health.batches_ok += 1;
+                health.last_success = Some(now);
+                let source = health.source;
+                if self.strategy == SourceStrategy::Fallback && index < self.active {
+                    info!(source = source.name(), "Preferred data source recovered, switching back");
+                    transitions::record("data_source", self.health[self.active].source.name(), source.name(), "preferred source recovered");
+                    self.active = index;
+                }
+                None
+            }
+            Err(error) => {
+                health.consecutive_failures += 1;
+                health.batches_failed += 1;
+                health.last_error = Some(error);
+                if !health.healthy || health.consecutive_failures < self.failover_after {
+                    return None;
+                }
+                health.healthy = false;
+                let source = health.source;
+                let failover = (1..self.health.len())
+                    .map(|offset| (index + offset) % self.health.len())
+                    .find(|candidate| self.health[*candidate].healthy)?;
+                self.failovers += 1;
+                if self.strategy == SourceStrategy::Fallback {
+                    self.active = failover;
+                }
+                warn!(source = source.name(), failover = self.health[failover].source.name(), consecutive_failures = self.failover_after, "Data source failing, failing over");
+                transitions::record("data_source", source.name(), self.health[failover].source.name(), format!("{} consecutive failed batches", self.failover_after));
+                Some(failover)
+            }
+        }
+    }
+
+    pub fn status(&self) -> DataSourceStatus {
+        DataSourceStatus {
+            strategy: self.strategy,
+            active: self.health[self.active].source,
+            failovers: self.failovers,
+            sources: self.health.clone(),
+        }
+    }
+}
+
+pub type BatchHistory = HashMap<String, Option<Vec<OhlcvData>>>;
+
+/// A provider the core worker can fetch daily bars from
+pub enum DataSource {
+    Vci(VciClient),
+    Tcbs(TcbsClient),
+}
+
+impl DataSource {
+    pub fn kind(&self) -> SourceKind {
+        match self {
+            DataSource::Vci(_) => SourceKind::Vci,
+            DataSource::Tcbs(_) => SourceKind::Tcbs,
+        }
+    }
+
+    /// Bars of `interval` from `start` to `end` (YYYY-MM-DD) for each symbol; None where the provider has no data
+    pub async fn get_batch_history(&self, symbols: &[String], start: &str, end: &str, interval: Interval) -> Result<BatchHistory, String> {
+        match self {
+            DataSource::Vci(client) => client.get_batch_history(symbols, start, Some(end), interval.name()).await.map_err(|e| format!("{:?}", e)),
+            DataSource::Tcbs(client) => tcbs_batch_history(client, symbols, start, end, interval).await,
+        }
+    }
+}
+
+/// TCBS serves one symbol per request, so a batch's symbols are fetched concurrently, paced by
+/// the host's rate budget. The batch fails only when every symbol errors.
+async fn tcbs_batch_history(client: &TcbsClient, symbols: &[String], start: &str, end: &str, interval: Interval) -> Result<BatchHistory, String> {
+    let sessions_back = match (NaiveDate::parse_from_str(start, "%Y-%m-%d"), NaiveDate::parse_from_str(end, "%Y-%m-%d")) {
+        (Ok(start), Ok(end)) => (end - start).num_days().max(1) as u32 + 1,
+        _ => return Err(format!("Invalid date range {}..{}", start, end)),
+    };
+    let bars_back = sessions_back * interval.bars_per_session();
+    let fetches = symbols.iter().map(|symbol| async move {
+        (symbol, client.get_history(symbol, start, Some(end), interval.name(), bars_back).await)
+    });
+    let mut results = BatchHistory::new();
+    let mut last_error = None;
+    for (symbol, result) in join_all(fetches).await {
+        match result {
+            Ok(bars) => {
+                let bars: Vec<OhlcvData> = bars.into_iter().map(|bar: tcbs::OhlcvData| OhlcvData {
+                    time: bar.time,
+                    open: bar.open,
+                    high: bar.high,
+                    low: bar.low,
+                    close: bar.close,
+                    volume: bar.volume,
+                    symbol: Some(symbol.clone()),
+                }).collect();
+                results.insert(symbol.clone(), (!bars.is_empty()).then_some(bars));
+            }
+            Err(tcbs::TcbsError::NoData) => {
+                results.insert(symbol.clone(), None);
+            }
+            Err(e) => {
+                debug!(symbol, error = ?e, "TCBS history request failed");
+                last_error = Some(format!("{:?}", e));
+                results.insert(symbol.clone(), None);
+            }
+        }
+    }
+    match last_error {
+        Some(error) if results.values().all(Option::is_none) => Err(error),
+        _ => Ok(results),
+    }
+}
+
+/// The core worker's data sources and the policy choosing between them
+pub struct DataSources {
+    sources: Vec<DataSource>,
+    selector: SourceSelector,
+}
+
+impl DataSources {
+    pub fn new(sources: Vec<DataSource>, config: &DataSourceConfig) -> Self {
+        let kinds: Vec<SourceKind> = sources.iter().map(DataSource::kind).collect();
+        info!(sources = ?kinds, strategy = ?config.strategy, failover_after = config.failover_after, "Data sources configured");
+        let selector = SourceSelector::new(&kinds, config.strategy, config.failover_after);
+        Self { sources, selector }
+    }
+
+    pub fn begin_cycle(&mut self) {
+        self.selector.begin_cycle();
+    }
+
+    pub fn status(&self) -> DataSourceStatus {
+        self.selector.status()
+    }
+
+    /// Fetch one batch, retrying it once on another source when this batch tips its source into failover
+    pub async fn get_batch_history(&mut self, symbols: &[String], start: &str, end: &str, interval: Interval) -> (SourceKind, Result<BatchHistory, String>) {
+        let mut index = self.selector.pick();
+        loop {
+            let source = &self.sources[index];
+            let kind = source.kind();
+            let started = Instant::now();
+            let result = source.get_batch_history(symbols, start, end, interval).await;
+            metrics::record_duration(&format!("worker.{}_batch_fetch", kind.name()), started.elapsed());
+            metrics::increment_counter(&format!("worker.{}_batches_{}", kind.name(), if result.is_ok() { "ok" } else { "failed" }), 1);
+
+            let outcome = result.as_ref().map(|_| ()).map_err(Clone::clone);
+            match self.selector.record(index, outcome, Utc::now()) {
+                Some(failover) if result.is_err() => index = failover,
+                _ => return (kind, result),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback_and_round_robin_selection() {
+        let now = Utc::now();
+        let mut selector = SourceSelector::new(&[SourceKind::Vci, SourceKind::Tcbs], SourceStrategy::Fallback, 2);
+        assert_eq!(selector.pick(), 0);
+        assert_eq!(selector.record(0, Err("timeout".to_string()), now), None);
+        assert_eq!(selector.record(0, Err("timeout".to_string()), now), Some(1));
+        assert_eq!(selector.pick(), 1);
+        assert_eq!(selector.record(1, Ok(()), now), None);
+
+        // Each cycle probes the primary once, and a success switches back
+        selector.begin_cycle();
+        assert_eq!(selector.pick(), 0);
+        assert_eq!(selector.record(0, Err("timeout".to_string()), now), None);
+        assert_eq!(selector.pick(), 1);
+        selector.begin_cycle();
+        assert_eq!(selector.pick(), 0);
+        selector.record(0, Ok(()), now);
+        assert_eq!(selector.pick(), 0);
+        let status = selector.status();
+        assert_eq!((status.active, status.failovers, status.sources[0].batches_failed), (SourceKind::Vci, 1, 3));
+
+        let mut rotating = SourceSelector::new(&[SourceKind::Vci, SourceKind::Tcbs], SourceStrategy::RoundRobin, 1);
+        assert_eq!((rotating.pick(), rotating.pick(), rotating.pick()), (0, 1, 0));
+        // An unhealthy source drops out of the rotation until it succeeds again
+        assert_eq!(rotating.record(0, Err("403".to_string()), now), Some(1));
+        assert_eq!((rotating.pick(), rotating.pick()), (1, 1));
+        rotating.begin_cycle();
+        assert_eq!(rotating.pick(), 0);
+        rotating.record(0, Ok(()), now);
+        assert_eq!((rotating.pick(), rotating.pick()), (0, 1));
+
+        assert_eq!("TCBS".parse::<SourceKind>(), Ok(SourceKind::Tcbs));
+        assert!("ssi".parse::<SourceKind>().is_err());
+    }
+}
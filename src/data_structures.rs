@@ -1,12 +1,21 @@
 use crate::vci::OhlcvData;
-use crate::config::OfficeHoursConfig;
+use crate::config::{OfficeHours, OfficeHoursConfig};
+use crate::company::CompanyCacheStats;
+use crate::quote::QuoteCacheStats;
+use crate::data_source::DataSourceStatus;
+use crate::standby::StandbyStatus;
+use crate::gossip::PeerStatus;
+use crate::utils::change_log;
+use crate::utils::corrections::{self, CorrectionSource};
+use crate::utils::market_time::market_date;
+use crate::utils::provider_quota::ProviderUsage;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
-use chrono::{DateTime, Datelike, Timelike, Utc, Weekday};
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc, Weekday};
 use chrono_tz::Tz;
 
 // --- Core Data Structures ---
@@ -14,14 +23,21 @@ use chrono_tz::Tz;
 #[derive(Clone, Debug)]
 pub struct ActorMetadata {
     pub successful_updates: u32,
-    pub failed_updates: u32,
+    pub failed_updates: u32,   // Implausible or contradicted by authoritative data
+    pub invalid_payloads: u32, // Missing symbol, or prices that cannot be a bar
+    pub stale_updates: u32,    // Bars older than the symbol's latest authoritative session
+    pub rate_limited: u32,     // Requests over the actor's per-minute allowance
     pub status: ActorStatus,
+    window_start: Option<Instant>,
+    window_requests: u32,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ActorStatus {
     Probation,
     Trusted,
+    ShadowBanned, // Requests are acknowledged but never staged
     Banned,
 }
 
@@ -30,11 +46,107 @@ impl Default for ActorMetadata {
         Self {
             successful_updates: 0,
             failed_updates: 0,
+            invalid_payloads: 0,
+            stale_updates: 0,
+            rate_limited: 0,
             status: ActorStatus::Probation,
+            window_start: None,
+            window_requests: 0,
+        }
+    }
+}
+
+// A contradicted or implausible bar costs this many confirmed ones
+const FAILED_UPDATE_PENALTY: i64 = 3;
+// Score at which an actor is trusted, throttled, or shadow-banned
+const TRUSTED_SCORE: i64 = 20;
+const THROTTLED_SCORE: i64 = -3;
+const SHADOW_BAN_SCORE: i64 = -10;
+// Public gossip requests per minute by standing
+const PROBATION_REQUESTS_PER_MINUTE: u32 = 30;
+const TRUSTED_REQUESTS_PER_MINUTE: u32 = 120;
+const THROTTLED_REQUESTS_PER_MINUTE: u32 = 6;
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+impl ActorMetadata {
+    /// Confirmed contributions minus penalties for bad, stale and excessive ones
+    pub fn score(&self) -> i64 {
+        self.successful_updates as i64
+            - FAILED_UPDATE_PENALTY * self.failed_updates as i64
+            - self.invalid_payloads as i64
+            - self.stale_updates as i64
+            - self.rate_limited as i64
+    }
+
+    pub fn requests_per_minute(&self) -> u32 {
+        match self.status {
+            ActorStatus::Trusted => TRUSTED_REQUESTS_PER_MINUTE,
+            _ if self.score() <= THROTTLED_SCORE => THROTTLED_REQUESTS_PER_MINUTE,
+            _ => PROBATION_REQUESTS_PER_MINUTE,
+        }
+    }
+
+    /// Count a request against the per-minute allowance; over it, the seconds until the window resets
+    pub fn admit_request(&mut self, now: Instant) -> Result<(), u64> {
+        match self.window_start {
+            Some(start) if now.duration_since(start) < RATE_WINDOW => {}
+            _ => {
+                self.window_start = Some(now);
+                self.window_requests = 0;
+            }
+        }
+        if self.window_requests >= self.requests_per_minute() {
+            let elapsed = self.window_start.map_or(Duration::ZERO, |start| now.duration_since(start));
+            return Err(RATE_WINDOW.saturating_sub(elapsed).as_secs().max(1));
+        }
+        self.window_requests += 1;
+        Ok(())
+    }
+
+    /// Re-derive the status from the counters; returns the new status when it changed.
+    /// A ban is only lifted by an admin reset.
+    pub fn update_status(&mut self) -> Option<ActorStatus> {
+        let status = match self.score() {
+            _ if self.status == ActorStatus::Banned || self.failed_updates > MAX_FAILED_UPDATES => ActorStatus::Banned,
+            score if score <= SHADOW_BAN_SCORE => ActorStatus::ShadowBanned,
+            score if score >= TRUSTED_SCORE => ActorStatus::Trusted,
+            _ => ActorStatus::Probation,
+        };
+        (status != self.status).then(|| {
+            self.status = status;
+            status
+        })
+    }
+
+    pub fn summary(&self, ip: IpAddr) -> ActorSummary {
+        ActorSummary {
+            ip,
+            status: self.status,
+            score: self.score(),
+            requests_per_minute: self.requests_per_minute(),
+            successful_updates: self.successful_updates,
+            failed_updates: self.failed_updates,
+            invalid_payloads: self.invalid_payloads,
+            stale_updates: self.stale_updates,
+            rate_limited: self.rate_limited,
         }
     }
 }
 
+/// A public contributor's standing, as served by `/admin/reputation`
+#[derive(Clone, Debug, Serialize)]
+pub struct ActorSummary {
+    pub ip: IpAddr,
+    pub status: ActorStatus,
+    pub score: i64,
+    pub requests_per_minute: u32,
+    pub successful_updates: u32,
+    pub failed_updates: u32,
+    pub invalid_payloads: u32,
+    pub stale_updates: u32,
+    pub rate_limited: u32,
+}
+
 // --- Type Aliases for Shared State ---
 
 // Main in-memory cache for all stock data
@@ -43,7 +155,7 @@ pub type InMemoryData = HashMap<String, Vec<OhlcvData>>;
 // Memory management constants
 pub const MAX_MEMORY_MB: usize = 100;
 pub const MAX_MEMORY_BYTES: usize = MAX_MEMORY_MB * 1024 * 1024;
-pub const MAX_DATA_POINTS_PER_SYMBOL: usize = 100; // Limit historical data per symbol
+pub const MAX_DATA_POINTS_PER_SYMBOL: usize = 100; // Default limit of historical data per symbol
 
 // Memory estimation functions
 pub fn estimate_ohlcv_data_size(data: &OhlcvData) -> usize {
@@ -69,16 +181,16 @@ pub fn estimate_memory_usage(data: &InMemoryData) -> usize {
     total_size
 }
 
-pub fn cleanup_old_data(data: &mut InMemoryData) -> (usize, usize) {
+pub fn cleanup_old_data(data: &mut InMemoryData, max_points_per_symbol: usize) -> (usize, usize) {
     let mut cleaned_symbols = 0;
     let mut cleaned_data_points = 0;
     
     for (_symbol, ohlcv_vec) in data.iter_mut() {
-        if ohlcv_vec.len() > MAX_DATA_POINTS_PER_SYMBOL {
+        if ohlcv_vec.len() > max_points_per_symbol {
             // Sort by time and keep only the most recent data points
-            ohlcv_vec.sort_by(|a, b| b.time.cmp(&a.time)); // Newest first
+            ohlcv_vec.sort_by_key(|d| std::cmp::Reverse(d.time)); // Newest first
             let original_len = ohlcv_vec.len();
-            ohlcv_vec.truncate(MAX_DATA_POINTS_PER_SYMBOL);
+            ohlcv_vec.truncate(max_points_per_symbol);
             cleaned_data_points += original_len - ohlcv_vec.len();
             cleaned_symbols += 1;
         }
@@ -91,7 +203,7 @@ pub fn merge_and_deduplicate_data(existing_data: &mut Vec<OhlcvData>, new_data:
     if existing_data.is_empty() {
         let count = new_data.len();
         existing_data.extend(new_data);
-        existing_data.sort_by(|a, b| a.time.cmp(&b.time));
+        existing_data.sort_by_key(|a| a.time);
         return count;
     }
     
@@ -100,15 +212,15 @@ pub fn merge_and_deduplicate_data(existing_data: &mut Vec<OhlcvData>, new_data:
     }
     
     // Sort both datasets by time to make comparison easier
-    existing_data.sort_by(|a, b| a.time.cmp(&b.time));
+    existing_data.sort_by_key(|a| a.time);
     let mut sorted_new_data = new_data;
-    sorted_new_data.sort_by(|a, b| a.time.cmp(&b.time));
+    sorted_new_data.sort_by_key(|a| a.time);
     
     // Find yesterday's date based on the most recent date in the datasets
     let latest_date = sorted_new_data.iter()
-        .map(|p| p.time.date_naive())
+        .map(|p| market_date(p.time))
         .max()
-        .or_else(|| existing_data.iter().map(|p| p.time.date_naive()).max());
+        .or_else(|| existing_data.iter().map(|p| market_date(p.time)).max());
     
     let yesterday = match latest_date {
         Some(latest) => latest - chrono::Duration::days(1),
@@ -116,8 +228,8 @@ pub fn merge_and_deduplicate_data(existing_data: &mut Vec<OhlcvData>, new_data:
     };
     
     // Find yesterday's data in both existing and new datasets
-    let existing_yesterday = existing_data.iter().find(|p| p.time.date_naive() == yesterday);
-    let new_yesterday = sorted_new_data.iter().find(|p| p.time.date_naive() == yesterday);
+    let existing_yesterday = existing_data.iter().find(|p| market_date(p.time) == yesterday);
+    let new_yesterday = sorted_new_data.iter().find(|p| market_date(p.time) == yesterday);
     
     let dividend_detected = match (existing_yesterday, new_yesterday) {
         (Some(existing), Some(new)) => {
@@ -135,7 +247,7 @@ pub fn merge_and_deduplicate_data(existing_data: &mut Vec<OhlcvData>, new_data:
         _ => false, // If we don't have yesterday's data in both sets, assume no dividend
     };
     
-    let added_count = if dividend_detected {
+    if dividend_detected {
         // Dividend detected: completely replace existing data with new data
         tracing::info!("Dividend detected - replacing all existing data with new data");
         let count = sorted_new_data.len();
@@ -147,7 +259,7 @@ pub fn merge_and_deduplicate_data(existing_data: &mut Vec<OhlcvData>, new_data:
         let mut added = 0;
         
         for new_point in sorted_new_data {
-            let new_date = new_point.time.date_naive();
+            let new_date = market_date(new_point.time);
             
             // Skip yesterday's data - keep existing yesterday
             if new_date == yesterday {
@@ -157,7 +269,7 @@ pub fn merge_and_deduplicate_data(existing_data: &mut Vec<OhlcvData>, new_data:
             // For latest date (today), always replace. For other dates, check timestamp
             let is_latest_date = Some(new_date) == latest_date;
             
-            if let Some(existing_point) = existing_data.iter_mut().find(|p| p.time.date_naive() == new_date) {
+            if let Some(existing_point) = existing_data.iter_mut().find(|p| market_date(p.time) == new_date) {
                 // Always replace if it's the latest date, or if new timestamp is more recent
                 if is_latest_date || new_point.time > existing_point.time {
                     *existing_point = new_point;
@@ -170,18 +282,149 @@ pub fn merge_and_deduplicate_data(existing_data: &mut Vec<OhlcvData>, new_data:
         }
         
         // Sort by time after merging
-        existing_data.sort_by(|a, b| a.time.cmp(&b.time));
+        existing_data.sort_by_key(|a| a.time);
         added
-    };
-    
-    added_count
+    }
+}
+
+/// `merge_and_deduplicate_data`, noting the symbol in the sync change log when any bar changed and
+/// every replaced settled bar in the correction feed
+pub fn merge_and_track_changes(symbol: &str, existing_data: &mut Vec<OhlcvData>, new_data: Vec<OhlcvData>, source: CorrectionSource) -> usize {
+    let Some(from) = new_data.iter().map(|bar| market_date(bar.time)).min() else { return 0 };
+    let kept = existing_data.partition_point(|bar| market_date(bar.time) < from);
+    let window = existing_data[kept..].to_vec();
+    let added = merge_and_deduplicate_data(existing_data, new_data);
+    // A dividend replaces the whole series, which also moves the bars before `from`
+    let unchanged = existing_data.partition_point(|bar| market_date(bar.time) < from) == kept && existing_data[kept..] == window[..];
+    if !unchanged {
+        change_log::record(symbol, from);
+        corrections::record_changes(symbol, &window, existing_data, source);
+    }
+    added
+}
+
+/// Replace the bars dated on one of `dates` with the matching `fetched` bars, for dates whose
+/// upstream data turned out to be bad. Unlike a regular merge this overwrites older finalized bars;
+/// dates the fetch has no bar for are left alone. Returns the bars replaced or added.
+pub fn replace_bars(symbol: &str, existing_data: &mut Vec<OhlcvData>, fetched: Vec<OhlcvData>, dates: &[NaiveDate]) -> usize {
+    let mut earliest: Option<NaiveDate> = None;
+    let mut replaced = 0;
+    for bar in fetched {
+        let date = market_date(bar.time);
+        if !dates.contains(&date) {
+            continue;
+        }
+        match existing_data.iter_mut().find(|existing| market_date(existing.time) == date) {
+            Some(existing) if *existing == bar => continue,
+            Some(existing) => {
+                corrections::record_bar(symbol, existing, &bar, CorrectionSource::Recalculation);
+                *existing = bar;
+            }
+            None => existing_data.push(bar),
+        }
+        replaced += 1;
+        earliest = Some(earliest.map_or(date, |earliest| earliest.min(date)));
+    }
+    if let Some(from) = earliest {
+        existing_data.sort_by_key(|bar| bar.time);
+        change_log::record(symbol, from);
+    }
+    replaced
 }
+
+/// Replace a series derived from other symbols with its rebuilt version, noting the first changed
+/// date in the sync change log. Returns whether anything changed.
+pub fn replace_series(symbol: &str, existing_data: &mut Vec<OhlcvData>, series: Vec<OhlcvData>) -> bool {
+    let first_changed = existing_data.iter().zip(&series).position(|(old, new)| old != new)
+        .unwrap_or(existing_data.len().min(series.len()));
+    let Some(from) = series.get(first_changed).or(existing_data.get(first_changed)).map(|bar| market_date(bar.time)) else { return false };
+    corrections::record_changes(symbol, &existing_data[first_changed..], &series, CorrectionSource::Synthetic);
+    *existing_data = series;
+    change_log::record(symbol, from);
+    true
+}
+
 pub type SharedData = Arc<Mutex<InMemoryData>>;
 
 // Reputation tracker for public contributors
 pub type PublicActorReputation = HashMap<IpAddr, ActorMetadata>;
 pub type SharedReputation = Arc<Mutex<PublicActorReputation>>;
 
+// Contributors are banned after this many rejected updates
+pub const MAX_FAILED_UPDATES: u32 = 5;
+
+// --- Gossip Staging ---
+
+// Public contributions wait here until the next authoritative fetch confirms them
+pub const MAX_STAGED_PER_SYMBOL: usize = 20;
+// Largest close-price deviation from the authoritative bar for a contribution to be confirmed
+pub const STAGED_PRICE_TOLERANCE: f64 = 0.02;
+
+#[derive(Clone, Debug)]
+pub struct StagedContribution {
+    pub data: OhlcvData,
+    pub source_ip: IpAddr,
+}
+
+pub type GossipStaging = HashMap<String, Vec<StagedContribution>>;
+pub type SharedGossipStaging = Arc<Mutex<GossipStaging>>;
+
+#[derive(Debug, Default)]
+pub struct StagingOutcome {
+    pub confirmed: Vec<IpAddr>,
+    pub rejected: Vec<IpAddr>,
+    pub pending: Vec<StagedContribution>, // Newer than the authoritative data, left staged
+}
+
+/// Check staged contributions against a series that was just refreshed from an authoritative source.
+/// Contributions for dates the source already has are confirmed if their close agrees and are
+/// otherwise rejected; the authoritative bar is kept either way. Contributions newer than the
+/// source's latest bar stay pending, neither served nor credited, until a fetch covers their date.
+/// Older dates the source has no bar for are rejected.
+pub fn check_staged_contributions(series: &[OhlcvData], staged: Vec<StagedContribution>) -> StagingOutcome {
+    let mut outcome = StagingOutcome::default();
+    let Some(latest_date) = series.last().map(|bar| market_date(bar.time)) else {
+        outcome.rejected = staged.into_iter().map(|c| c.source_ip).collect();
+        return outcome;
+    };
+
+    for contribution in staged {
+        let date = market_date(contribution.data.time);
+        if date > latest_date {
+            outcome.pending.push(contribution);
+            continue;
+        }
+        let confirmed = series.iter()
+            .find(|bar| market_date(bar.time) == date)
+            .is_some_and(|bar| (contribution.data.close - bar.close).abs() / bar.close <= STAGED_PRICE_TOLERANCE);
+        if confirmed {
+            outcome.confirmed.push(contribution.source_ip);
+        } else {
+            outcome.rejected.push(contribution.source_ip);
+        }
+    }
+
+    outcome
+}
+
+/// Credit or penalize contributors once their staged data has been checked
+pub fn record_staging_outcome(reputation: &mut PublicActorReputation, outcome: &StagingOutcome) {
+    for ip in &outcome.confirmed {
+        reputation.entry(*ip).or_default().successful_updates += 1;
+    }
+    for ip in &outcome.rejected {
+        reputation.entry(*ip).or_default().failed_updates += 1;
+    }
+    for ip in outcome.confirmed.iter().chain(&outcome.rejected) {
+        let actor = reputation.entry(*ip).or_default();
+        match actor.update_status() {
+            Some(ActorStatus::Banned) => tracing::error!(source_ip = %ip, failed_updates = actor.failed_updates, "Banning IP after staged data was contradicted by authoritative data"),
+            Some(status) => tracing::info!(source_ip = %ip, ?status, score = actor.score(), "Public actor status changed"),
+            None => {}
+        }
+    }
+}
+
 // Timestamp of the last trusted internal update
 pub type LastInternalUpdate = Arc<Mutex<Instant>>;
 
@@ -236,10 +479,21 @@ pub struct HealthStats {
     // Peer counts (safe - no addresses)
     pub internal_peers_count: usize,
     pub public_peers_count: usize,
+    pub internal_peers: Vec<PeerStatus>, // Core nodes: gossip delivery and lag per internal peer
     
     // Worker statistics
     pub iteration_count: u64,
     pub last_update_timestamp: Option<String>, // ISO format
+    pub initial_load_complete: bool, // First full fetch/sync cycle has finished
+    pub strict_readiness: bool, // Analysis endpoints return 503 until data is ready
+
+    // Upstream request accounting (VCI), to spot quota pressure before a ban
+    pub provider_quota: BTreeMap<String, ProviderUsage>,
+    pub company_cache: CompanyCacheStats,
+    pub quote_cache: QuoteCacheStats,
+    pub standby: Option<StandbyStatus>, // Public nodes with standby enabled
+    pub data_sources: Option<DataSourceStatus>, // Core nodes: upstream providers and their health
+    pub open_sessions: Vec<String>, // Office-hours sessions open now ("default" plus named exchange sessions)
     
     // Debug info
     pub current_system_time: String, // Current system time (ISO format)
@@ -248,6 +502,7 @@ pub struct HealthStats {
     // Build info
     pub build_date: Option<String>, // Build timestamp from Docker
     pub git_commit: Option<String>, // Git commit hash
+    pub profile: Option<String>, // Deployment profile name
 }
 
 impl Default for HealthStats {
@@ -270,12 +525,22 @@ impl Default for HealthStats {
             memory_usage_percent: 0.0,
             internal_peers_count: 0,
             public_peers_count: 0,
+            internal_peers: Vec::new(),
             iteration_count: 0,
             last_update_timestamp: None,
+            initial_load_complete: false,
+            strict_readiness: false,
+            provider_quota: BTreeMap::new(),
+            company_cache: CompanyCacheStats::default(),
+            quote_cache: QuoteCacheStats::default(),
+            standby: None,
+            data_sources: None,
+            open_sessions: Vec::new(),
             current_system_time: Utc::now().to_rfc3339(),
             debug_time_override: None,
             build_date: None,
             git_commit: None,
+            profile: None,
         }
     }
 }
@@ -349,11 +614,46 @@ pub struct TickerGroups(pub HashMap<String, Vec<String>>);
 
 pub type SharedTickerGroups = Arc<TickerGroups>;
 
+// Compact per-symbol metadata served by /symbols for frontend boot
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SymbolMetadata {
+    pub symbol: String,
+    pub name: String,            // Company name, the symbol when ticker_info.json has none
+    pub name_vi: Option<String>, // Vietnamese company name
+    pub groups: Vec<String>,  // Ticker groups containing the symbol
+    pub indices: Vec<String>, // Indices the symbol is a constituent of today
+}
+
+// Office hours configuration shared with API handlers
+pub type SharedOfficeHoursConfig = Arc<OfficeHoursConfig>;
+
+// --- Analysis Cache ---
+
+// Short-lived cache of computed analysis responses, keyed by request parameters
+pub const ANALYSIS_CACHE_TTL_SECS: u64 = 30;
+// Keys carry client-chosen parameters, so the entry count is capped as well as aged out
+pub const MAX_ANALYSIS_CACHE_ENTRIES: usize = 256;
+pub type AnalysisCache = HashMap<String, (Instant, serde_json::Value)>;
+pub type SharedAnalysisCache = Arc<Mutex<AnalysisCache>>;
+
+/// Cache `value` under `key`, first dropping expired entries and then the oldest ones over the cap
+pub fn insert_analysis(cache: &mut AnalysisCache, key: String, value: serde_json::Value) {
+    let ttl = Duration::from_secs(ANALYSIS_CACHE_TTL_SECS);
+    cache.retain(|_, (cached_at, _)| cached_at.elapsed() < ttl);
+    cache.remove(&key);
+    if cache.len() >= MAX_ANALYSIS_CACHE_ENTRIES {
+        let mut by_age: Vec<(Instant, String)> = cache.iter().map(|(key, (cached_at, _))| (*cached_at, key.clone())).collect();
+        by_age.sort();
+        for (_, key) in by_age.into_iter().take(cache.len() + 1 - MAX_ANALYSIS_CACHE_ENTRIES) {
+            cache.remove(&key);
+        }
+    }
+    cache.insert(key, (Instant::now(), value));
+}
+
 // --- Office Hours Utility Functions ---
 
-pub fn is_within_office_hours(config: &OfficeHoursConfig) -> bool {
-    let office_hours = &config.default_office_hours;
-    
+fn is_open_at(office_hours: &OfficeHours, now_utc: DateTime<Utc>) -> bool {
     // Parse timezone
     let tz: Tz = match office_hours.timezone.parse() {
         Ok(tz) => tz,
@@ -363,8 +663,6 @@ pub fn is_within_office_hours(config: &OfficeHoursConfig) -> bool {
         }
     };
 
-    // Get current time (potentially debug-overridden) in the specified timezone
-    let now_utc = get_current_time();
     let now_local = now_utc.with_timezone(&tz);
     
     // Check weekday if weekdays_only is true
@@ -380,9 +678,61 @@ pub fn is_within_office_hours(config: &OfficeHoursConfig) -> bool {
         }
     }
     
-    // Check hour range
-    let current_hour = now_local.hour();
-    current_hour >= office_hours.start_hour && current_hour < office_hours.end_hour
+    // Check time range, in minutes since local midnight
+    let current_minute = now_local.hour() * 60 + now_local.minute();
+    let start = office_hours.start_hour * 60 + office_hours.start_minute;
+    let end = office_hours.end_hour * 60 + office_hours.end_minute;
+    current_minute >= start && current_minute < end
+}
+
+fn provisional_date_at(office_hours: &OfficeHours, now_utc: DateTime<Utc>) -> Option<NaiveDate> {
+    let tz: Tz = match office_hours.timezone.parse() {
+        Ok(tz) => tz,
+        Err(e) => {
+            tracing::warn!("Failed to parse timezone '{}': {}", office_hours.timezone, e);
+            return None;
+        }
+    };
+
+    let now_local = now_utc.with_timezone(&tz);
+
+    if office_hours.weekdays_only && matches!(now_local.weekday(), Weekday::Sat | Weekday::Sun) {
+        return None;
+    }
+
+    if now_local.hour() * 60 + now_local.minute() < office_hours.end_hour * 60 + office_hours.end_minute {
+        Some(now_local.date_naive())
+    } else {
+        None // Session closed - today's bar is final
+    }
+}
+
+/// Whether any configured session (the default window or a named one) is open
+pub fn is_within_office_hours(config: &OfficeHoursConfig) -> bool {
+    // Get current time (potentially debug-overridden)
+    let now_utc = get_current_time();
+    config.all_hours().any(|(_, hours)| is_open_at(hours, now_utc))
+}
+
+/// Whether the session that trades `symbol` is open
+pub fn is_symbol_in_session(config: &OfficeHoursConfig, symbol: &str, exchange: Option<&str>) -> bool {
+    is_open_at(config.hours_for_symbol(symbol, exchange), get_current_time())
+}
+
+/// Names of the sessions open right now, "default" being the default window
+pub fn open_sessions(config: &OfficeHoursConfig) -> Vec<String> {
+    let now_utc = get_current_time();
+    config.all_hours()
+        .filter(|(_, hours)| is_open_at(hours, now_utc))
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// Market date whose daily bar is still being built from intraday updates.
+/// Returns today's local date until the last session closes, None on non-trading days.
+pub fn get_provisional_date(config: &OfficeHoursConfig) -> Option<NaiveDate> {
+    let now_utc = get_current_time();
+    config.all_hours().filter_map(|(_, hours)| provisional_date_at(hours, now_utc)).max()
 }
 
 pub fn get_current_interval(
@@ -400,4 +750,132 @@ pub fn get_current_interval(
     } else {
         non_office_interval
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_bars;
+    use chrono::TimeZone;
+
+    // A bar on `day` of August 2025
+    fn bar(day: u32, close: f64) -> OhlcvData {
+        test_bars::bar(day as i64 - 1, close, 100)
+    }
+
+    #[test]
+    fn test_replace_bars_overwrites_only_requested_dates() {
+        let mut series = vec![bar(4, 100.0), bar(5, 1.0), bar(6, 102.0), bar(8, 104.0)];
+        let fetched = vec![bar(4, 99.0), bar(5, 101.0), bar(6, 102.0), bar(7, 103.0), bar(8, 105.0)];
+        let day = |day: u32| NaiveDate::from_ymd_opt(2025, 8, day).unwrap();
+        assert_eq!(replace_bars("VCB", &mut series, fetched, &[day(5), day(6), day(7)]), 2);
+        let closes: Vec<f64> = series.iter().map(|bar| bar.close).collect();
+        assert_eq!(closes, vec![100.0, 101.0, 102.0, 103.0, 104.0]);
+    }
+
+    #[test]
+    fn test_staged_contributions_checked_against_authoritative_series() {
+        let honest: IpAddr = "10.0.0.1".parse().unwrap();
+        let liar: IpAddr = "10.0.0.2".parse().unwrap();
+        let mut series = vec![bar(14, 60.0), bar(15, 61.0)];
+        let staged = vec![
+            StagedContribution { data: bar(15, 61.2), source_ip: honest },  // agrees with the source
+            StagedContribution { data: bar(15, 75.0), source_ip: liar },    // contradicts the source
+            StagedContribution { data: bar(13, 50.0), source_ip: liar },    // invents history
+            StagedContribution { data: bar(18, 61.5), source_ip: honest },  // newer than the source
+            StagedContribution { data: bar(19, 90.0), source_ip: liar },    // newer, and wrong once fetched
+        ];
+
+        let outcome = check_staged_contributions(&series, staged);
+        assert_eq!(outcome.confirmed, vec![honest]);
+        assert_eq!(outcome.rejected, vec![liar, liar]);
+        // Future-dated bars wait for the source: nothing is served or credited before then
+        assert_eq!(outcome.pending.iter().map(|c| c.data.close).collect::<Vec<_>>(), vec![61.5, 90.0]);
+
+        series.extend([bar(18, 61.4), bar(19, 61.6)]);
+        let later = check_staged_contributions(&series, outcome.pending.clone());
+        assert_eq!((later.confirmed, later.rejected.clone(), later.pending.len()), (vec![honest], vec![liar], 0));
+        let outcome = StagingOutcome { confirmed: vec![honest, honest], rejected: [outcome.rejected, later.rejected].concat(), pending: Vec::new() };
+
+        let mut reputation = PublicActorReputation::new();
+        reputation.entry(liar).or_default().failed_updates = MAX_FAILED_UPDATES - 1;
+        record_staging_outcome(&mut reputation, &outcome);
+        assert_eq!(reputation[&honest].successful_updates, 2);
+        assert_eq!(reputation[&liar].status, ActorStatus::Banned);
+    }
+
+    #[test]
+    fn test_reputation_score_sets_standing_and_allowance() {
+        let start = Instant::now();
+        let mut actor = ActorMetadata::default();
+        assert_eq!(actor.requests_per_minute(), PROBATION_REQUESTS_PER_MINUTE);
+        for _ in 0..PROBATION_REQUESTS_PER_MINUTE {
+            assert!(actor.admit_request(start).is_ok());
+        }
+        assert_eq!(actor.admit_request(start + Duration::from_secs(45)), Err(15));
+        assert!(actor.admit_request(start + RATE_WINDOW).is_ok());
+
+        actor.successful_updates = TRUSTED_SCORE as u32;
+        assert_eq!(actor.update_status(), Some(ActorStatus::Trusted));
+        assert_eq!(actor.requests_per_minute(), TRUSTED_REQUESTS_PER_MINUTE);
+        assert_eq!(actor.update_status(), None);
+
+        // Spam and junk wear the score down: first a tighter allowance, then a shadow ban
+        actor.successful_updates = 0;
+        actor.invalid_payloads = 2;
+        actor.stale_updates = 1;
+        assert_eq!(actor.update_status(), Some(ActorStatus::Probation));
+        assert_eq!(actor.requests_per_minute(), THROTTLED_REQUESTS_PER_MINUTE);
+        actor.rate_limited = 7;
+        assert_eq!(actor.update_status(), Some(ActorStatus::ShadowBanned));
+        assert_eq!(actor.summary("10.0.0.3".parse().unwrap()).score, -10);
+
+        // Only a reset lifts a ban
+        actor.failed_updates = MAX_FAILED_UPDATES + 1;
+        assert_eq!(actor.update_status(), Some(ActorStatus::Banned));
+        actor.failed_updates = 0;
+        actor.rate_limited = 0;
+        assert_eq!(actor.update_status(), None);
+    }
+
+    #[test]
+    fn test_analysis_cache_drops_expired_and_oldest_entries() {
+        let mut cache = AnalysisCache::new();
+        let expired = Instant::now() - Duration::from_secs(ANALYSIS_CACHE_TTL_SECS + 1);
+        cache.insert("expired".to_string(), (expired, serde_json::Value::Null));
+        for i in 0..MAX_ANALYSIS_CACHE_ENTRIES {
+            insert_analysis(&mut cache, format!("key{}", i), serde_json::Value::from(i));
+        }
+        assert!(!cache.contains_key("expired"));
+        assert_eq!(cache.len(), MAX_ANALYSIS_CACHE_ENTRIES);
+
+        insert_analysis(&mut cache, "newest".to_string(), serde_json::Value::Null);
+        assert_eq!(cache.len(), MAX_ANALYSIS_CACHE_ENTRIES);
+        assert!(cache.contains_key("newest") && !cache.contains_key("key0"));
+    }
+
+    #[test]
+    fn test_symbol_sessions_resolve_per_exchange() {
+        let mut config = OfficeHoursConfig::default();
+        config.sessions.insert("derivatives".to_string(), crate::config::ExchangeSession {
+            hours: OfficeHours { start_hour: 8, start_minute: 45, end_hour: 14, end_minute: 45, ..OfficeHours::default() },
+            symbols: Vec::new(),
+            symbol_prefixes: vec!["VN30F".to_string()],
+        });
+        config.sessions.extend(crate::config::parse_office_hours_sessions("hnx=09:00-14:45, BAD=9-15, UPCOM=09:00-15:00@VN30F2"));
+        assert_eq!(config.sessions.keys().collect::<Vec<_>>(), ["HNX", "UPCOM", "derivatives"]);
+        assert_eq!(config.hours_for_symbol("VN30F1M", None).start_minute, 45);
+        assert_eq!(config.hours_for_symbol("VCB", None).start_hour, 9);
+        // Sessions follow the ticker_info exchange; listed symbols and prefixes override it
+        assert_eq!(config.hours_for_symbol("SHS", Some("hnx")).end_minute, 45);
+        assert_eq!(config.hours_for_symbol("VCB", Some("HOSE")).end_hour, 16);
+        assert_eq!(config.hours_for_symbol("VN30F1M", Some("HNX")).start_hour, 8);
+
+        // Friday 2025-08-15, Ho Chi Minh time is UTC+7
+        let at = |hour: u32, minute: u32| Utc.with_ymd_and_hms(2025, 8, 15, hour - 7, minute, 0).unwrap();
+        let futures = config.hours_for_symbol("VN30F1M", None);
+        assert!(is_open_at(futures, at(8, 50)) && !is_open_at(&config.default_office_hours, at(8, 50)));
+        assert!(!is_open_at(futures, at(14, 45)) && is_open_at(&config.default_office_hours, at(14, 45)));
+        assert_eq!(provisional_date_at(futures, at(15, 0)), None);
+        assert!(provisional_date_at(&config.default_office_hours, at(15, 0)).is_some());
+    }
+}
@@ -0,0 +1,208 @@
+use crate::auth::AuthFailure;
+use crate::utils::integrity::IntegrityError;
+use crate::utils::mirrors::MirrorFetchError;
+use crate::vci::VciError;
+use axum::{
+    Json,
+    http::{HeaderMap, HeaderValue, StatusCode, header::{CACHE_CONTROL, RETRY_AFTER}},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use serde_json::Value;
+use tower_governor::GovernorError;
+
+// Stable, machine-readable error codes. Clients branch (and localize) on these,
+// so existing codes must never be renamed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidRequest,  // Malformed query parameter or payload
+    Unauthorized,    // Missing or invalid token
+    Forbidden,       // Caller is banned
+    SymbolNotFound,  // No data for the requested symbol
+    NotFound,        // Unknown group, index, file or date
+    RateLimited,     // Per-IP rate limit or query budget exceeded
+    QueryTooLarge,   // Response would exceed the per-request cost ceiling
+    NotReady,        // Initial data load still running
+    Overloaded,      // Load shedding is rejecting low-priority routes
+    Degraded,        // Node is running on untrusted data
+    UpstreamError,   // Provider or mirror failed, or returned invalid data
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::InvalidRequest => StatusCode::BAD_REQUEST,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::SymbolNotFound | ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::QueryTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::NotReady | ErrorCode::Overloaded | ErrorCode::Degraded => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::UpstreamError => StatusCode::BAD_GATEWAY,
+            ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Whether the same request may succeed if retried later
+    pub fn retryable(self) -> bool {
+        matches!(
+            self,
+            ErrorCode::RateLimited | ErrorCode::NotReady | ErrorCode::Overloaded | ErrorCode::Degraded | ErrorCode::UpstreamError
+        )
+    }
+}
+
+/// Error envelope returned by every endpoint: `{code, message, details, retryable}`
+#[derive(Clone, Debug, Serialize)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+    pub retryable: bool,
+    #[serde(skip)]
+    retry_after_secs: Option<u64>,
+    #[serde(skip)]
+    headers: HeaderMap,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: None,
+            retryable: code.retryable(),
+            retry_after_secs: None,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    pub fn invalid(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidRequest, message)
+    }
+
+    pub fn symbol_not_found(symbol: &str) -> Self {
+        Self::new(ErrorCode::SymbolNotFound, "No data for symbol").with_details(serde_json::json!({ "symbol": symbol }))
+    }
+
+    pub fn not_ready(retry_after_secs: u64) -> Self {
+        Self::new(ErrorCode::NotReady, "Data is still loading, retry later").with_retry_after(retry_after_secs)
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn with_retry_after(mut self, secs: u64) -> Self {
+        self.retry_after_secs = Some(secs);
+        self
+    }
+
+    fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let mut headers = self.headers.clone();
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+        if let Some(secs) = self.retry_after_secs {
+            headers.insert(RETRY_AFTER, HeaderValue::from(secs));
+        }
+        (self.code.status(), headers, Json(self)).into_response()
+    }
+}
+
+impl From<VciError> for ApiError {
+    fn from(error: VciError) -> Self {
+        match error {
+            VciError::NoData => ApiError::new(ErrorCode::SymbolNotFound, "Provider has no data for symbol"),
+            VciError::InvalidInterval(interval) => ApiError::invalid(format!("Invalid interval: {}", interval)),
+            VciError::RateLimit => ApiError::new(ErrorCode::UpstreamError, "Provider rate limit reached").with_retry_after(60),
+            VciError::Http(_) | VciError::Serialization(_) | VciError::InvalidResponse(_) => {
+                ApiError::new(ErrorCode::UpstreamError, "Failed to fetch data from provider")
+            }
+        }
+    }
+}
+
+impl From<AuthFailure> for ApiError {
+    fn from(failure: AuthFailure) -> Self {
+        match failure {
+            AuthFailure::Missing | AuthFailure::Invalid => ApiError::new(ErrorCode::Unauthorized, "Invalid or missing token"),
+            AuthFailure::Expired(_) => ApiError::new(ErrorCode::Unauthorized, "Token expired"),
+            AuthFailure::Revoked(_) => ApiError::new(ErrorCode::Unauthorized, "Token revoked"),
+            AuthFailure::Forbidden(_) => ApiError::new(ErrorCode::Forbidden, "Token lacks the required role"),
+        }
+    }
+}
+
+impl From<MirrorFetchError> for ApiError {
+    fn from(error: MirrorFetchError) -> Self {
+        match error {
+            MirrorFetchError::NotFound => ApiError::new(ErrorCode::NotFound, "File not found"),
+            MirrorFetchError::AllMirrorsFailed => ApiError::new(ErrorCode::UpstreamError, "Failed to fetch from all mirrors"),
+        }
+    }
+}
+
+impl From<IntegrityError> for ApiError {
+    fn from(error: IntegrityError) -> Self {
+        ApiError::new(ErrorCode::UpstreamError, "Downloaded file failed integrity validation")
+            .with_details(serde_json::json!({ "reason": error.to_string() }))
+    }
+}
+
+/// Rate limiter rejections in the same envelope, keeping the limiter's own headers
+pub fn governor_error_response(error: GovernorError) -> Response {
+    match error {
+        GovernorError::TooManyRequests { wait_time, headers } => {
+            ApiError::new(ErrorCode::RateLimited, format!("Too many requests, retry in {}s", wait_time))
+                .with_retry_after(wait_time)
+                .with_headers(headers.unwrap_or_default())
+                .into_response()
+        }
+        GovernorError::UnableToExtractKey => ApiError::new(ErrorCode::Internal, "Unable to identify client").into_response(),
+        GovernorError::Other { code, msg, headers } => {
+            let code = match code {
+                StatusCode::TOO_MANY_REQUESTS => ErrorCode::RateLimited,
+                StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+                _ => ErrorCode::Internal,
+            };
+            ApiError::new(code, msg.unwrap_or_else(|| "Request rejected".to_string()))
+                .with_headers(headers.unwrap_or_default())
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_and_mapping() {
+        let body = serde_json::to_value(ApiError::symbol_not_found("VCB")).unwrap();
+        assert_eq!(body, serde_json::json!({
+            "code": "symbol_not_found",
+            "message": "No data for symbol",
+            "details": { "symbol": "VCB" },
+            "retryable": false,
+        }));
+
+        let response = ApiError::not_ready(30).into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[RETRY_AFTER], "30");
+
+        assert_eq!(ApiError::from(VciError::NoData).code, ErrorCode::SymbolNotFound);
+        assert!(ApiError::from(MirrorFetchError::AllMirrorsFailed).retryable);
+        let limited = governor_error_response(GovernorError::TooManyRequests { wait_time: 2, headers: None });
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+}
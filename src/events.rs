@@ -0,0 +1,88 @@
+use crate::utils::freshness;
+use crate::vci::OhlcvData;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use tokio::sync::broadcast;
+
+// Updates buffered per subscriber before slow consumers start skipping
+const EVENT_BUS_CAPACITY: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateSource {
+    Vci,            // Core node fetch
+    Tcbs,           // Core node fetch from the fallback source
+    Core,           // Public node sync from the core network
+    InternalGossip, // Trusted peer
+    Snapshot,       // Already in memory when a stream subscribed
+    Synthetic,      // Rebuilt from constituents, such as a synthetic index
+}
+
+/// Latest bar for a symbol after it changed in memory
+#[derive(Clone, Debug, Serialize)]
+pub struct TickerUpdate {
+    pub symbol: String,
+    pub source: UpdateSource,
+    pub bar: OhlcvData,
+    #[serde(skip)]
+    pub ingested_at: Option<DateTime<Utc>>, // None for bars only restored from disk
+}
+
+// Broadcast bus for ticker updates; streaming endpoints subscribe to it
+pub type SharedEventBus = broadcast::Sender<TickerUpdate>;
+
+pub fn new_event_bus() -> SharedEventBus {
+    broadcast::channel(EVENT_BUS_CAPACITY).0
+}
+
+/// Publish an update and mark the bar as just ingested; dropped silently when nobody is subscribed
+pub fn publish(bus: &SharedEventBus, symbol: &str, source: UpdateSource, bar: OhlcvData) {
+    let ingested_at = Utc::now();
+    freshness::record(symbol, ingested_at);
+    let _ = bus.send(TickerUpdate { symbol: symbol.to_string(), source, bar, ingested_at: Some(ingested_at) });
+}
+
+/// Per-connection symbol filter; empty means all symbols
+#[derive(Clone, Debug, Default)]
+pub struct SymbolFilter {
+    symbols: HashSet<String>,
+}
+
+impl SymbolFilter {
+    /// Build from query values, each of which may hold a comma-separated list
+    pub fn from_params(values: &[String]) -> Self {
+        let symbols = values.iter()
+            .flat_map(|value| value.split(','))
+            .map(|symbol| symbol.trim().to_uppercase())
+            .filter(|symbol| !symbol.is_empty())
+            .collect();
+        Self { symbols }
+    }
+
+    pub fn matches(&self, symbol: &str) -> bool {
+        self.symbols.is_empty() || self.symbols.contains(symbol)
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_filter_parsing() {
+        let filter = SymbolFilter::from_params(&["vcb, FPT".to_string(), "HPG".to_string(), ",".to_string()]);
+        assert_eq!(filter.len(), 3);
+        assert!(filter.matches("VCB") && filter.matches("HPG"));
+        assert!(!filter.matches("ACB"));
+        assert!(SymbolFilter::from_params(&[]).matches("ACB"));
+    }
+}
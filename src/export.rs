@@ -0,0 +1,717 @@
+use crate::analysis::basis;
+use crate::analysis::indicator_cache;
+use crate::analysis::indicators::IndicatorPoint;
+use crate::analysis::ma_score::{self, AverageValues, MaScorePoint};
+use crate::analysis::strength::{self, StrengthPoint};
+use crate::data_structures::{InMemoryData, SharedData};
+use crate::foreign_flow;
+use crate::profile::{all_indicator_groups, IndicatorGroup};
+use crate::ticker_info::TickerDirectory;
+use crate::utils::market_time::{format_market_date, market_date};
+use crate::vci::OhlcvData;
+use arrow_array::types::Date32Type;
+use arrow_array::{ArrayRef, Date32Array, Float64Array, RecordBatch, StringArray, UInt32Array, UInt64Array};
+use arrow_ipc::writer::FileWriter;
+use arrow_schema::{Field, Schema};
+use chrono::{DateTime, NaiveDate, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use futures_util::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::ops::Range;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+// A snapshot older than this is rebuilt in the background while it keeps being served
+pub const ENHANCED_SNAPSHOT_TTL_SECS: i64 = 30;
+// Rows rendered per chunk of a streamed CSV export
+const CSV_CHUNK_ROWS: usize = 1000;
+
+// Columns available in the enhanced CSV export, in their default order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsvColumn {
+    Symbol,
+    Time,
+    Open,
+    High,
+    Low,
+    Close,
+    Volume,
+    Ma10,
+    Ma20,
+    Ma50,
+    Ma10Score,
+    Ma20Score,
+    Ma50Score,
+    DaysAboveMa10,
+    DaysAboveMa20,
+    DaysAboveMa50,
+    DaysBelowMa10,
+    DaysBelowMa20,
+    DaysBelowMa50,
+    TrendScore,
+    Strength,
+    Name, // Company name from ticker_info.json, the symbol when unknown
+    Rsi14,
+    Macd,
+    MacdSignal,
+    MacdHistogram,
+    BbUpper,
+    BbMiddle,
+    BbLower,
+    Vwap20,
+    ForeignNetValue,
+    Basis,
+    BasisPct,
+}
+
+impl CsvColumn {
+    pub const ALL: [CsvColumn; 33] = [
+        CsvColumn::Symbol, CsvColumn::Time, CsvColumn::Open, CsvColumn::High, CsvColumn::Low, CsvColumn::Close, CsvColumn::Volume,
+        CsvColumn::Ma10, CsvColumn::Ma20, CsvColumn::Ma50, CsvColumn::Ma10Score, CsvColumn::Ma20Score, CsvColumn::Ma50Score,
+        CsvColumn::DaysAboveMa10, CsvColumn::DaysAboveMa20, CsvColumn::DaysAboveMa50,
+        CsvColumn::DaysBelowMa10, CsvColumn::DaysBelowMa20, CsvColumn::DaysBelowMa50,
+        CsvColumn::TrendScore, CsvColumn::Strength, CsvColumn::Name,
+        CsvColumn::Rsi14, CsvColumn::Macd, CsvColumn::MacdSignal, CsvColumn::MacdHistogram,
+        CsvColumn::BbUpper, CsvColumn::BbMiddle, CsvColumn::BbLower, CsvColumn::Vwap20,
+        CsvColumn::ForeignNetValue, CsvColumn::Basis, CsvColumn::BasisPct,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CsvColumn::Symbol => "symbol",
+            CsvColumn::Time => "time",
+            CsvColumn::Open => "open",
+            CsvColumn::High => "high",
+            CsvColumn::Low => "low",
+            CsvColumn::Close => "close",
+            CsvColumn::Volume => "volume",
+            CsvColumn::Ma10 => "ma10",
+            CsvColumn::Ma20 => "ma20",
+            CsvColumn::Ma50 => "ma50",
+            CsvColumn::Ma10Score => "ma10_score",
+            CsvColumn::Ma20Score => "ma20_score",
+            CsvColumn::Ma50Score => "ma50_score",
+            CsvColumn::DaysAboveMa10 => "consecutive_days_above_ma10",
+            CsvColumn::DaysAboveMa20 => "consecutive_days_above_ma20",
+            CsvColumn::DaysAboveMa50 => "consecutive_days_above_ma50",
+            CsvColumn::DaysBelowMa10 => "consecutive_days_below_ma10",
+            CsvColumn::DaysBelowMa20 => "consecutive_days_below_ma20",
+            CsvColumn::DaysBelowMa50 => "consecutive_days_below_ma50",
+            CsvColumn::TrendScore => "trend_score",
+            CsvColumn::Strength => "strength",
+            CsvColumn::Name => "name",
+            CsvColumn::Rsi14 => "rsi14",
+            CsvColumn::Macd => "macd",
+            CsvColumn::MacdSignal => "macd_signal",
+            CsvColumn::MacdHistogram => "macd_histogram",
+            CsvColumn::BbUpper => "bb_upper",
+            CsvColumn::BbMiddle => "bb_middle",
+            CsvColumn::BbLower => "bb_lower",
+            CsvColumn::Vwap20 => "vwap20",
+            CsvColumn::ForeignNetValue => "foreign_net_value",
+            CsvColumn::Basis => "basis",
+            CsvColumn::BasisPct => "basis_pct",
+        }
+    }
+
+    fn value(self, symbol: &str, name: &str, row: &EnhancedRow) -> String {
+        match self {
+            CsvColumn::Symbol => symbol.to_string(),
+            CsvColumn::Time => format_market_date(row.bar.time),
+            CsvColumn::Open => row.bar.open.to_string(),
+            CsvColumn::High => row.bar.high.to_string(),
+            CsvColumn::Low => row.bar.low.to_string(),
+            CsvColumn::Close => row.bar.close.to_string(),
+            CsvColumn::Volume => row.bar.volume.to_string(),
+            CsvColumn::Ma10 => format_optional(row.score.ma10),
+            CsvColumn::Ma20 => format_optional(row.score.ma20),
+            CsvColumn::Ma50 => format_optional(row.score.ma50),
+            CsvColumn::Ma10Score => format_optional(row.score.ma10_score),
+            CsvColumn::Ma20Score => format_optional(row.score.ma20_score),
+            CsvColumn::Ma50Score => format_optional(row.score.ma50_score),
+            CsvColumn::DaysAboveMa10 => row.score.consecutive_days_above_ma10.map(|days| days.to_string()).unwrap_or_default(),
+            CsvColumn::DaysAboveMa20 => row.score.consecutive_days_above_ma20.map(|days| days.to_string()).unwrap_or_default(),
+            CsvColumn::DaysAboveMa50 => row.score.consecutive_days_above_ma50.map(|days| days.to_string()).unwrap_or_default(),
+            CsvColumn::DaysBelowMa10 => row.score.consecutive_days_below_ma10.map(|days| days.to_string()).unwrap_or_default(),
+            CsvColumn::DaysBelowMa20 => row.score.consecutive_days_below_ma20.map(|days| days.to_string()).unwrap_or_default(),
+            CsvColumn::DaysBelowMa50 => row.score.consecutive_days_below_ma50.map(|days| days.to_string()).unwrap_or_default(),
+            CsvColumn::TrendScore => format_optional(row.score.trend_score),
+            CsvColumn::Strength => format_optional(row.strength.strength),
+            CsvColumn::Name => quote_field(name),
+            CsvColumn::Rsi14 => format_optional(row.indicators.rsi14),
+            CsvColumn::Macd => format_optional(row.indicators.macd),
+            CsvColumn::MacdSignal => format_optional(row.indicators.macd_signal),
+            CsvColumn::MacdHistogram => format_optional(row.indicators.macd_histogram),
+            CsvColumn::BbUpper => format_optional(row.indicators.bb_upper),
+            CsvColumn::BbMiddle => format_optional(row.indicators.bb_middle),
+            CsvColumn::BbLower => format_optional(row.indicators.bb_lower),
+            CsvColumn::Vwap20 => format_optional(row.indicators.vwap20),
+            CsvColumn::ForeignNetValue => format_optional(row.foreign_net_value),
+            CsvColumn::Basis => format_optional(row.basis),
+            CsvColumn::BasisPct => format_optional(row.basis_pct),
+        }
+    }
+
+    // Columns that are null until a series is long enough, or when their indicator group is off
+    fn nullable(self) -> bool {
+        !matches!(
+            self,
+            CsvColumn::Symbol | CsvColumn::Time | CsvColumn::Open | CsvColumn::High | CsvColumn::Low | CsvColumn::Close | CsvColumn::Volume | CsvColumn::Name
+        )
+    }
+
+    /// The column as a typed Arrow array over (symbol, name, row) triples. Unlike CSV, values are not rounded
+    /// and `time` is a date.
+    fn array(self, rows: &[(&str, &str, &EnhancedRow)]) -> ArrayRef {
+        let number = |value: fn(&EnhancedRow) -> f64| -> ArrayRef {
+            Arc::new(Float64Array::from_iter_values(rows.iter().map(|(_, _, row)| value(row))))
+        };
+        let optional = |value: fn(&EnhancedRow) -> Option<f64>| -> ArrayRef {
+            Arc::new(rows.iter().map(|(_, _, row)| value(row)).collect::<Float64Array>())
+        };
+        let days = |value: fn(&EnhancedRow) -> Option<u32>| -> ArrayRef {
+            Arc::new(rows.iter().map(|(_, _, row)| value(row)).collect::<UInt32Array>())
+        };
+        match self {
+            CsvColumn::Symbol => Arc::new(StringArray::from_iter_values(rows.iter().map(|(symbol, _, _)| *symbol))),
+            CsvColumn::Name => Arc::new(StringArray::from_iter_values(rows.iter().map(|(_, name, _)| *name))),
+            CsvColumn::Time => Arc::new(Date32Array::from_iter_values(rows.iter().map(|(_, _, row)| Date32Type::from_naive_date(market_date(row.bar.time))))),
+            CsvColumn::Open => number(|row| row.bar.open),
+            CsvColumn::High => number(|row| row.bar.high),
+            CsvColumn::Low => number(|row| row.bar.low),
+            CsvColumn::Close => number(|row| row.bar.close),
+            CsvColumn::Volume => Arc::new(UInt64Array::from_iter_values(rows.iter().map(|(_, _, row)| row.bar.volume))),
+            CsvColumn::Ma10 => optional(|row| row.score.ma10),
+            CsvColumn::Ma20 => optional(|row| row.score.ma20),
+            CsvColumn::Ma50 => optional(|row| row.score.ma50),
+            CsvColumn::Ma10Score => optional(|row| row.score.ma10_score),
+            CsvColumn::Ma20Score => optional(|row| row.score.ma20_score),
+            CsvColumn::Ma50Score => optional(|row| row.score.ma50_score),
+            CsvColumn::DaysAboveMa10 => days(|row| row.score.consecutive_days_above_ma10),
+            CsvColumn::DaysAboveMa20 => days(|row| row.score.consecutive_days_above_ma20),
+            CsvColumn::DaysAboveMa50 => days(|row| row.score.consecutive_days_above_ma50),
+            CsvColumn::DaysBelowMa10 => days(|row| row.score.consecutive_days_below_ma10),
+            CsvColumn::DaysBelowMa20 => days(|row| row.score.consecutive_days_below_ma20),
+            CsvColumn::DaysBelowMa50 => days(|row| row.score.consecutive_days_below_ma50),
+            CsvColumn::TrendScore => optional(|row| row.score.trend_score),
+            CsvColumn::Strength => optional(|row| row.strength.strength),
+            CsvColumn::Rsi14 => optional(|row| row.indicators.rsi14),
+            CsvColumn::Macd => optional(|row| row.indicators.macd),
+            CsvColumn::MacdSignal => optional(|row| row.indicators.macd_signal),
+            CsvColumn::MacdHistogram => optional(|row| row.indicators.macd_histogram),
+            CsvColumn::BbUpper => optional(|row| row.indicators.bb_upper),
+            CsvColumn::BbMiddle => optional(|row| row.indicators.bb_middle),
+            CsvColumn::BbLower => optional(|row| row.indicators.bb_lower),
+            CsvColumn::Vwap20 => optional(|row| row.indicators.vwap20),
+            CsvColumn::ForeignNetValue => optional(|row| row.foreign_net_value),
+            CsvColumn::Basis => optional(|row| row.basis),
+            CsvColumn::BasisPct => optional(|row| row.basis_pct),
+        }
+    }
+}
+
+impl std::str::FromStr for CsvColumn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        CsvColumn::ALL.into_iter()
+            .find(|column| column.name() == name)
+            .ok_or_else(|| format!("unknown column: {}", s.trim()))
+    }
+}
+
+/// Tabular encodings of enhanced rows, all sharing the `columns=` selection
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableFormat {
+    Csv,
+    Parquet, // Zstd-compressed, one row group per response
+    Arrow,   // Arrow IPC file format
+}
+
+impl TableFormat {
+    pub fn media_type(self) -> &'static str {
+        match self {
+            TableFormat::Csv => "text/csv; charset=utf-8",
+            TableFormat::Parquet => "application/vnd.apache.parquet",
+            TableFormat::Arrow => "application/vnd.apache.arrow.file",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            TableFormat::Csv => "csv",
+            TableFormat::Parquet => "parquet",
+            TableFormat::Arrow => "arrow",
+        }
+    }
+}
+
+impl std::str::FromStr for TableFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "csv" => Ok(TableFormat::Csv),
+            "parquet" => Ok(TableFormat::Parquet),
+            "arrow" => Ok(TableFormat::Arrow),
+            other => Err(format!("Invalid format '{}'. Expected csv, parquet or arrow", other)),
+        }
+    }
+}
+
+/// Which columns to emit, in order, and whether to start with a header row (CSV only)
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvLayout {
+    pub columns: Vec<CsvColumn>,
+    pub header: bool,
+}
+
+impl Default for CsvLayout {
+    fn default() -> Self {
+        Self { columns: CsvColumn::ALL.to_vec(), header: true }
+    }
+}
+
+impl CsvLayout {
+    /// Build from `columns=` values (repeated and/or comma-separated) and the header toggle
+    pub fn from_params(columns: Option<&[String]>, header: Option<bool>) -> Result<Self, String> {
+        let columns: Vec<CsvColumn> = columns.unwrap_or_default().iter()
+            .flat_map(|value| value.split(','))
+            .filter(|name| !name.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            columns: if columns.is_empty() { CsvColumn::ALL.to_vec() } else { columns },
+            header: header.unwrap_or(true),
+        })
+    }
+}
+
+/// A bar with its MA indicators, strength score and RSI/MACD/Bollinger, computed over the symbol's full series
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EnhancedRow {
+    pub bar: OhlcvData,
+    pub score: MaScorePoint,
+    pub strength: StrengthPoint,
+    #[serde(default)] // Snapshots saved before these indicators existed
+    pub indicators: IndicatorPoint,
+    #[serde(default, skip_serializing_if = "AverageValues::is_empty")]
+    pub averages: AverageValues, // The profile's extra moving averages and their MA scores
+    #[serde(default)]
+    pub foreign_net_value: Option<f64>, // Foreign buy minus sell value in VND, when collected for the session
+    #[serde(default)]
+    pub basis: Option<f64>, // VN30 futures only: close minus the VN30 close, in index points
+    #[serde(default)]
+    pub basis_pct: Option<f64>, // The basis relative to the VN30 close; positive is a premium
+}
+
+static INDICATOR_GROUPS: OnceLock<Vec<IndicatorGroup>> = OnceLock::new();
+
+/// Set the indicator groups enhanced rows are computed with; call once at startup. All groups by default.
+pub fn set_indicator_groups(groups: Vec<IndicatorGroup>) {
+    if INDICATOR_GROUPS.set(groups.clone()).is_err() {
+        warn!("Indicator groups already set, ignoring new settings");
+    } else {
+        info!(?groups, "Configured enhanced indicator groups");
+    }
+}
+
+/// Indicator groups enhanced rows are computed with
+pub fn indicator_groups() -> Vec<IndicatorGroup> {
+    INDICATOR_GROUPS.get().cloned().unwrap_or_else(all_indicator_groups)
+}
+
+fn indicator_enabled(group: IndicatorGroup) -> bool {
+    INDICATOR_GROUPS.get().is_none_or(|groups| groups.contains(&group))
+}
+
+/// Pair every bar with its indicators, keeping the rows `keep` selects.
+/// Indicators need the full history, so filter here rather than before.
+/// Groups the profile leaves out are not computed and their columns are null.
+pub fn enhance_series<F>(symbol: &str, series: &[OhlcvData], keep: F) -> Vec<EnhancedRow>
+where
+    F: Fn(&OhlcvData) -> bool,
+{
+    let scores = indicator_enabled(IndicatorGroup::MaScore).then(|| indicator_cache::ma_scores(symbol, series));
+    let strengths = indicator_enabled(IndicatorGroup::Strength).then(|| indicator_cache::strength(symbol, series, strength::weights()));
+    let indicators = indicator_enabled(IndicatorGroup::Technical).then(|| indicator_cache::indicators(symbol, series));
+    let averages = indicator_enabled(IndicatorGroup::MaScore).then(|| ma_score::calculate_averages(series, ma_score::moving_averages()));
+    let foreign = foreign_flow::net_values(symbol);
+    series.iter()
+        .enumerate()
+        .filter(|(_, bar)| keep(bar))
+        .map(|(i, bar)| {
+            let date = market_date(bar.time);
+            EnhancedRow {
+                bar: bar.clone(),
+                score: scores.as_ref().map_or_else(|| MaScorePoint { date, close: bar.close, ..Default::default() }, |scores| scores[i].clone()),
+                strength: strengths.as_ref().map_or_else(|| StrengthPoint { date, ..Default::default() }, |strengths| strengths[i].clone()),
+                indicators: indicators.as_ref().map_or_else(|| {
+                    let mut blank = IndicatorPoint::default();
+                    blank.date = date;
+                    blank
+                }, |indicators| indicators[i].clone()),
+                averages: averages.as_ref().map(|averages| averages[i].clone()).unwrap_or_default(),
+                foreign_net_value: foreign.get(&date).copied(),
+                basis: None,
+                basis_pct: None,
+            }
+        })
+        .collect()
+}
+
+impl EnhancedRow {
+    /// Bar fields plus MA indicators and strength, as one flat JSON object
+    pub fn to_json(&self) -> Value {
+        let mut row = serde_json::to_value(&self.bar).unwrap_or_default();
+        if let (Value::Object(row), Ok(Value::Object(score))) = (&mut row, serde_json::to_value(&self.score)) {
+            // date and close repeat the bar's own fields
+            row.extend(score.into_iter().filter(|(key, _)| key != "date" && key != "close"));
+            row.insert("strength".to_string(), self.strength.strength.map_or(Value::Null, Value::from));
+        }
+        if let (Value::Object(row), Ok(Value::Object(indicators))) = (&mut row, serde_json::to_value(&self.indicators)) {
+            row.extend(indicators.into_iter().filter(|(key, _)| key != "date"));
+        }
+        if let Value::Object(row) = &mut row {
+            row.insert("foreign_net_value".to_string(), self.foreign_net_value.map_or(Value::Null, Value::from));
+            row.insert("basis".to_string(), self.basis.map_or(Value::Null, Value::from));
+            row.insert("basis_pct".to_string(), self.basis_pct.map_or(Value::Null, Value::from));
+            row.extend(self.averages.iter().map(|(key, value)| (key.clone(), value.map_or(Value::Null, Value::from))));
+        }
+        row
+    }
+}
+
+/// Enhanced rows for every symbol's full series, as of one build
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnhancedSnapshot {
+    pub version: u64, // Increases with every build
+    pub built_at: DateTime<Utc>,
+    pub series: HashMap<String, Vec<EnhancedRow>>,
+}
+
+impl EnhancedSnapshot {
+    pub fn build(data: &InMemoryData, version: u64) -> Self {
+        let mut series = data.iter().map(|(symbol, series)| (symbol.clone(), enhance_series(symbol, series, |_| true))).collect();
+        attach_basis(&mut series, data);
+        Self { version, built_at: Utc::now(), series }
+    }
+
+    pub fn age_secs(&self, now: DateTime<Utc>) -> i64 {
+        (now - self.built_at).num_seconds()
+    }
+}
+
+/// Fill in the basis of futures rows for the sessions the VN30 index also has a bar for
+fn attach_basis(series: &mut HashMap<String, Vec<EnhancedRow>>, data: &InMemoryData) {
+    let Some(index) = data.get(basis::INDEX_SYMBOL) else {
+        return;
+    };
+    for (symbol, rows) in series.iter_mut().filter(|(symbol, _)| basis::is_futures(symbol)) {
+        let Some(futures) = data.get(symbol) else {
+            continue;
+        };
+        // No z-score window: rows only carry the basis itself
+        let points: HashMap<NaiveDate, basis::BasisPoint> = basis::calculate_basis(futures, index, 0).into_iter()
+            .map(|point| (point.date, point))
+            .collect();
+        for row in rows.iter_mut() {
+            if let Some(point) = points.get(&market_date(row.bar.time)) {
+                row.basis = Some(point.basis);
+                row.basis_pct = Some(point.basis_pct);
+            }
+        }
+    }
+}
+
+/// Last complete snapshot plus whether a rebuild is running. Requests always get a
+/// complete snapshot: a stale one is served while its replacement builds.
+#[derive(Debug, Default)]
+pub struct EnhancedSnapshots {
+    current: Option<Arc<EnhancedSnapshot>>,
+    building: bool,
+}
+
+impl EnhancedSnapshots {
+    /// Serve `snapshot`, such as one restored from disk, until it goes stale and is rebuilt
+    pub fn restore(&mut self, snapshot: EnhancedSnapshot) {
+        self.current = Some(Arc::new(snapshot));
+    }
+
+    pub fn current(&self) -> Option<Arc<EnhancedSnapshot>> {
+        self.current.clone()
+    }
+}
+
+pub type SharedEnhancedSnapshots = Arc<Mutex<EnhancedSnapshots>>;
+
+/// The snapshot to serve. Only the very first request builds inline (from `data`, which the
+/// caller has locked); later ones get the current snapshot and start a background rebuild once it is stale.
+pub async fn current_snapshot(snapshots: &SharedEnhancedSnapshots, shared_data: &SharedData, data: &InMemoryData) -> Arc<EnhancedSnapshot> {
+    let mut state = snapshots.lock().await;
+    let Some(current) = state.current.clone() else {
+        let snapshot = Arc::new(EnhancedSnapshot::build(data, 1));
+        info!(version = snapshot.version, symbols = snapshot.series.len(), "Built first enhanced snapshot");
+        state.current = Some(snapshot.clone());
+        return snapshot;
+    };
+
+    if current.age_secs(Utc::now()) >= ENHANCED_SNAPSHOT_TTL_SECS && !state.building {
+        state.building = true;
+        debug!(version = current.version, "Enhanced snapshot stale, rebuilding in background");
+        let (snapshots, shared_data, version) = (snapshots.clone(), shared_data.clone(), current.version + 1);
+        tokio::spawn(async move {
+            let data = shared_data.lock().await.clone();
+            let built = tokio::task::spawn_blocking(move || EnhancedSnapshot::build(&data, version)).await;
+            let mut state = snapshots.lock().await;
+            state.building = false;
+            match built {
+                Ok(snapshot) => {
+                    debug!(version, symbols = snapshot.series.len(), "Enhanced snapshot rebuilt");
+                    state.current = Some(Arc::new(snapshot));
+                }
+                Err(e) => warn!(error = %e, "Enhanced snapshot rebuild failed, keeping previous snapshot"),
+            }
+        });
+    }
+    current
+}
+
+fn format_optional(value: Option<f64>) -> String {
+    value.map(|v| format!("{:.4}", v)).unwrap_or_default()
+}
+
+// Company names can contain commas and quotes
+fn quote_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl CsvLayout {
+    fn header_line(&self) -> String {
+        self.columns.iter().map(|column| column.name()).collect::<Vec<_>>().join(",")
+    }
+
+    fn line(&self, symbol: &str, name: &str, row: &EnhancedRow) -> String {
+        self.columns.iter().map(|column| column.value(symbol, name, row)).collect::<Vec<_>>().join(",")
+    }
+}
+
+/// Render enhanced rows as CSV, symbols in alphabetical order, naming symbols from `directory`
+pub fn format_enhanced_data_as_csv(data: &BTreeMap<String, Vec<EnhancedRow>>, layout: &CsvLayout, directory: &TickerDirectory) -> String {
+    let mut lines = Vec::new();
+    if layout.header {
+        lines.push(layout.header_line());
+    }
+    for (symbol, rows) in data {
+        let name = directory.name_for(symbol);
+        lines.extend(rows.iter().map(|row| layout.line(symbol, name, row)));
+    }
+    let mut csv = lines.join("\n");
+    csv.push('\n');
+    csv
+}
+
+/// Snapshot rows as a CSV body stream of at most `CSV_CHUNK_ROWS` rows per chunk, so an export is
+/// never held as one string. `selection` is each symbol with the range of its snapshot rows, in output
+/// order; `names` labels the symbols for the name column. Reads the shared snapshot, not InMemoryData,
+/// so no lock is held while rendering.
+pub fn stream_enhanced_csv(
+    snapshot: Arc<EnhancedSnapshot>,
+    selection: Vec<(String, Range<usize>)>,
+    layout: CsvLayout,
+    names: HashMap<String, String>,
+) -> impl Stream<Item = Result<String, Infallible>> + Send {
+    let header = layout.header.then(|| format!("{}\n", layout.header_line()));
+    let chunks = selection.into_iter()
+        .flat_map(|(symbol, rows)| {
+            let end = rows.end;
+            rows.step_by(CSV_CHUNK_ROWS).map(move |start| (symbol.clone(), start..end.min(start + CSV_CHUNK_ROWS)))
+        })
+        .map(move |(symbol, rows)| {
+            let name = names.get(&symbol).map_or(symbol.as_str(), String::as_str);
+            let series = snapshot.series.get(&symbol).map_or(&[][..], Vec::as_slice);
+            let mut chunk = String::new();
+            for row in &series[rows] {
+                chunk.push_str(&layout.line(&symbol, name, row));
+                chunk.push('\n');
+            }
+            chunk
+        });
+    stream::iter(header.into_iter().chain(chunks).map(Ok))
+}
+
+/// Enhanced rows as one Arrow record batch with the layout's columns, symbols in alphabetical order
+pub fn enhanced_record_batch(data: &BTreeMap<String, Vec<EnhancedRow>>, layout: &CsvLayout, directory: &TickerDirectory) -> Result<RecordBatch, String> {
+    let rows: Vec<(&str, &str, &EnhancedRow)> = data.iter()
+        .flat_map(|(symbol, rows)| {
+            let name = directory.name_for(symbol);
+            rows.iter().map(move |row| (symbol.as_str(), name, row))
+        })
+        .collect();
+    let arrays: Vec<ArrayRef> = layout.columns.iter().map(|column| column.array(&rows)).collect();
+    let fields: Vec<Field> = layout.columns.iter().zip(&arrays)
+        .map(|(column, array)| Field::new(column.name(), array.data_type().clone(), column.nullable()))
+        .collect();
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays).map_err(|e| e.to_string())
+}
+
+/// Encode enhanced rows in a tabular format, naming symbols from `directory`
+pub fn encode_enhanced(data: &BTreeMap<String, Vec<EnhancedRow>>, layout: &CsvLayout, directory: &TickerDirectory, format: TableFormat) -> Result<Vec<u8>, String> {
+    let mut buffer = Vec::new();
+    match format {
+        TableFormat::Csv => return Ok(format_enhanced_data_as_csv(data, layout, directory).into_bytes()),
+        TableFormat::Parquet => {
+            let batch = enhanced_record_batch(data, layout, directory)?;
+            let properties = WriterProperties::builder().set_compression(Compression::ZSTD(ZstdLevel::default())).build();
+            let mut writer = ArrowWriter::try_new(&mut buffer, batch.schema(), Some(properties)).map_err(|e| e.to_string())?;
+            writer.write(&batch).map_err(|e| e.to_string())?;
+            writer.close().map_err(|e| e.to_string())?;
+        }
+        TableFormat::Arrow => {
+            let batch = enhanced_record_batch(data, layout, directory)?;
+            let mut writer = FileWriter::try_new(&mut buffer, &batch.schema()).map_err(|e| e.to_string())?;
+            writer.write(&batch).map_err(|e| e.to_string())?;
+            writer.finish().map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_bars::{bar, series};
+
+    #[test]
+    fn test_column_projection_and_header_toggle() {
+        let series: Vec<OhlcvData> = (1..=3).map(|day| bar(day - 1, 10.0 + day as f64, 100)).collect();
+        let mut data = BTreeMap::new();
+        data.insert("VCB".to_string(), enhance_series("VCB", &series, |bar| bar.close > 11.0));
+
+        let layout = CsvLayout::from_params(Some(&["close, Symbol".to_string(), "ma10".to_string()]), None).unwrap();
+        let directory = TickerDirectory::default();
+        assert_eq!(format_enhanced_data_as_csv(&data, &layout, &directory), "close,symbol,ma10\n12,VCB,\n13,VCB,\n");
+
+        let no_header = CsvLayout::from_params(Some(&["time".to_string()]), Some(false)).unwrap();
+        assert_eq!(format_enhanced_data_as_csv(&data, &no_header, &directory), "2025-08-02\n2025-08-03\n");
+
+        let named = TickerDirectory::parse(r#"[{"symbol": "VCB", "name": "Vietcombank, JSC"}]"#).unwrap();
+        let name_layout = CsvLayout::from_params(Some(&["symbol,name".to_string()]), Some(false)).unwrap();
+        assert_eq!(format_enhanced_data_as_csv(&data, &name_layout, &named), "VCB,\"Vietcombank, JSC\"\nVCB,\"Vietcombank, JSC\"\n");
+
+        assert_eq!(CsvLayout::from_params(None, None).unwrap(), CsvLayout::default());
+        assert!(CsvLayout::from_params(Some(&["close,rsi".to_string()]), None).is_err());
+    }
+
+    #[test]
+    fn test_parquet_and_arrow_round_trip() {
+        use arrow_array::Array;
+        let series: Vec<OhlcvData> = (1..=3).map(|day| bar(day - 1, 10.0 + day as f64 / 3.0, 100 * day as u64)).collect();
+        let data = BTreeMap::from([("VCB".to_string(), enhance_series("VCB", &series, |_| true))]);
+        let layout = CsvLayout::from_params(Some(&["symbol,time,close,volume,ma10".to_string()]), None).unwrap();
+        let directory = TickerDirectory::default();
+        assert_eq!("Parquet".parse::<TableFormat>(), Ok(TableFormat::Parquet));
+        assert!("xlsx".parse::<TableFormat>().is_err());
+
+        let expected = enhanced_record_batch(&data, &layout, &directory).unwrap();
+        let names: Vec<String> = expected.schema().fields().iter().map(|field| field.name().clone()).collect();
+        assert_eq!(names, vec!["symbol", "time", "close", "volume", "ma10"]);
+        assert!(!expected.schema().field(2).is_nullable() && expected.schema().field(4).is_nullable());
+        // Values keep full precision, unlike the rounded CSV
+        let closes = expected.column(2).as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(closes.value(0), 10.0 + 1.0 / 3.0);
+        assert_eq!(expected.column(4).null_count(), 3);
+
+        let arrow = encode_enhanced(&data, &layout, &directory, TableFormat::Arrow).unwrap();
+        let batches: Vec<RecordBatch> = arrow_ipc::reader::FileReader::try_new(std::io::Cursor::new(arrow), None).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(batches, vec![expected.clone()]);
+
+        let parquet = encode_enhanced(&data, &layout, &directory, TableFormat::Parquet).unwrap();
+        let path = std::env::temp_dir().join(format!("export-round-trip-{}.parquet", std::process::id()));
+        std::fs::write(&path, parquet).unwrap();
+        let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap()).unwrap().build().unwrap();
+        let batches: Vec<RecordBatch> = reader.collect::<Result<_, _>>().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(batches, vec![expected]);
+    }
+
+    #[tokio::test]
+    async fn test_csv_stream_matches_buffered_csv() {
+        use futures_util::StreamExt;
+        let series = series(&(0..2500).map(|day| 10.0 + (day % 7) as f64).collect::<Vec<_>>());
+        let data = HashMap::from([("VCB".to_string(), series.clone()), ("FPT".to_string(), series[..3].to_vec())]);
+        let snapshot = Arc::new(EnhancedSnapshot::build(&data, 1));
+        let layout = CsvLayout::from_params(Some(&["symbol,name,time,close,ma10".to_string()]), None).unwrap();
+        let names = HashMap::from([("VCB".to_string(), "Vietcombank, JSC".to_string())]);
+
+        let selection = vec![("FPT".to_string(), 1..3), ("VCB".to_string(), 10..2100)];
+        let chunks: Vec<String> = stream_enhanced_csv(snapshot.clone(), selection, layout.clone(), names).map(|chunk| chunk.unwrap()).collect().await;
+        // Header, FPT's two rows, then VCB's 2090 rows in chunks of 1000
+        assert_eq!(chunks.iter().map(|chunk| chunk.lines().count()).collect::<Vec<_>>(), vec![1, 2, 1000, 1000, 90]);
+
+        let directory = TickerDirectory::parse(r#"[{"symbol": "VCB", "name": "Vietcombank, JSC"}]"#).unwrap();
+        let buffered = BTreeMap::from([
+            ("FPT".to_string(), snapshot.series["FPT"][1..3].to_vec()),
+            ("VCB".to_string(), snapshot.series["VCB"][10..2100].to_vec()),
+        ]);
+        assert_eq!(chunks.concat(), format_enhanced_data_as_csv(&buffered, &layout, &directory));
+    }
+
+    #[tokio::test]
+    async fn test_stale_snapshot_served_while_rebuilding() {
+        let shared_data: SharedData = Arc::new(Mutex::new(HashMap::from([("VCB".to_string(), vec![bar(0, 10.5, 100)])])));
+        let snapshots: SharedEnhancedSnapshots = Arc::default();
+
+        let data = shared_data.lock().await.clone();
+        let first = current_snapshot(&snapshots, &shared_data, &data).await;
+        assert_eq!((first.version, first.series["VCB"].len()), (1, 1));
+        let row = first.series["VCB"][0].to_json();
+        assert_eq!((row["close"].as_f64(), row["ma10"].is_null(), row.get("date")), (Some(10.5), true, None));
+
+        // Age the snapshot: the next request still gets version 1 and triggers one rebuild
+        let mut aged = EnhancedSnapshot::build(&data, 1);
+        aged.built_at -= chrono::Duration::seconds(ENHANCED_SNAPSHOT_TTL_SECS);
+        snapshots.lock().await.current = Some(Arc::new(aged));
+        assert_eq!(current_snapshot(&snapshots, &shared_data, &data).await.version, 1);
+        assert!(snapshots.lock().await.building);
+
+        for _ in 0..100 {
+            if snapshots.lock().await.current.as_ref().is_some_and(|s| s.version == 2) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let state = snapshots.lock().await;
+        assert_eq!((state.current.as_ref().map(|s| s.version), state.building), (Some(2), false));
+    }
+
+    #[test]
+    fn test_futures_rows_carry_basis() {
+        let bars = |closes: &[f64]| -> Vec<OhlcvData> {
+            closes.iter().enumerate().map(|(day, close)| bar(3 + day as i64, *close, 100)).collect()
+        };
+        let data = HashMap::from([
+            (basis::FUTURES_SYMBOL.to_string(), bars(&[1602.0, 1595.0, 1610.0])),
+            // The index has no bar on the third day yet
+            (basis::INDEX_SYMBOL.to_string(), bars(&[1600.0, 1600.0])),
+        ]);
+        let snapshot = EnhancedSnapshot::build(&data, 1);
+        let futures: Vec<Option<f64>> = snapshot.series[basis::FUTURES_SYMBOL].iter().map(|row| row.basis).collect();
+        assert_eq!(futures, vec![Some(2.0), Some(-5.0), None]);
+        assert_eq!(snapshot.series[basis::FUTURES_SYMBOL][0].basis_pct, Some(0.125));
+        assert!(snapshot.series[basis::INDEX_SYMBOL].iter().all(|row| row.basis.is_none()));
+        assert_eq!(snapshot.series[basis::FUTURES_SYMBOL][1].to_json()["basis"].as_f64(), Some(-5.0));
+    }
+}
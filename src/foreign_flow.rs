@@ -0,0 +1,236 @@
+use crate::analysis::indicator_cache::Dated;
+use crate::analysis::warmup::WarmupMode;
+use crate::data_structures::SharedData;
+use crate::tcbs::{ForeignTradingData, TcbsClient, TcbsError};
+use crate::utils::market_time;
+use crate::utils::metrics;
+use chrono::{Duration as ChronoDuration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+// Sessions fetched for a symbol without stored flow; later refreshes only fetch the latest few
+const HISTORY_SESSIONS: u32 = 150;
+const REFRESH_SESSIONS: u32 = 5;
+// Calendar days a public node asks its core for, first and on later syncs
+const HISTORY_SYNC_DAYS: i64 = 220;
+const REFRESH_SYNC_DAYS: i64 = 10;
+// Sessions kept per symbol, oldest dropped first
+const RETAINED_SESSIONS: usize = 500;
+
+// Sessions summed into net_value_window
+pub const DEFAULT_WINDOW: usize = 20;
+pub const MIN_WINDOW: usize = 1;
+pub const MAX_WINDOW: usize = 120;
+
+/// Foreign investors' matched trading in one symbol on one session
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ForeignFlowPoint {
+    pub date: NaiveDate,
+    pub buy_volume: u64,
+    pub sell_volume: u64,
+    pub buy_value: f64,  // VND
+    pub sell_value: f64, // VND
+}
+
+impl ForeignFlowPoint {
+    pub fn net_volume(&self) -> i64 {
+        self.buy_volume as i64 - self.sell_volume as i64
+    }
+
+    pub fn net_value(&self) -> f64 {
+        self.buy_value - self.sell_value
+    }
+}
+
+impl From<ForeignTradingData> for ForeignFlowPoint {
+    fn from(session: ForeignTradingData) -> Self {
+        Self {
+            date: session.date,
+            buy_volume: session.buy_volume,
+            sell_volume: session.sell_volume,
+            buy_value: session.buy_value,
+            sell_value: session.sell_value,
+        }
+    }
+}
+
+/// A session of foreign flow as served by `/analysis/foreign-flow`
+#[derive(Clone, Debug, Serialize)]
+pub struct ForeignFlowView {
+    #[serde(flatten)]
+    pub point: ForeignFlowPoint,
+    pub net_volume: i64,
+    pub net_value: f64,
+    pub net_value_window: Option<f64>, // Net value summed over the `window` sessions ending on this one
+}
+
+impl Dated for ForeignFlowView {
+    fn date(&self) -> NaiveDate {
+        self.point.date
+    }
+}
+
+/// Net figures of each session, plus the net value of the `window` sessions ending on it. Strict
+/// warm-up leaves that None until `window` sessions are stored; lenient sums the ones there are.
+pub fn with_window(points: &[ForeignFlowPoint], window: usize, mode: WarmupMode) -> Vec<ForeignFlowView> {
+    let net_values: Vec<f64> = points.iter().map(ForeignFlowPoint::net_value).collect();
+    points.iter().enumerate().map(|(i, point)| ForeignFlowView {
+        point: point.clone(),
+        net_volume: point.net_volume(),
+        net_value: net_values[i],
+        net_value_window: (i + 1 >= window || mode == WarmupMode::Lenient).then(|| net_values[(i + 1).saturating_sub(window)..=i].iter().sum()),
+    }).collect()
+}
+
+// Foreign flow per symbol, then per session
+type ForeignFlows = HashMap<String, BTreeMap<NaiveDate, ForeignFlowPoint>>;
+
+fn flows() -> &'static Mutex<ForeignFlows> {
+    static FLOWS: OnceLock<Mutex<ForeignFlows>> = OnceLock::new();
+    FLOWS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Store fetched sessions of `symbol`, replacing stored ones of the same date, and drop the oldest
+/// beyond the retained sessions. Returns how many sessions were added or changed.
+pub fn merge(symbol: &str, points: impl IntoIterator<Item = ForeignFlowPoint>) -> usize {
+    let mut flows = flows().lock().unwrap_or_else(|e| e.into_inner());
+    let sessions = flows.entry(symbol.to_string()).or_default();
+    let mut changed = 0;
+    for point in points {
+        if sessions.get(&point.date) != Some(&point) {
+            sessions.insert(point.date, point);
+            changed += 1;
+        }
+    }
+    while sessions.len() > RETAINED_SESSIONS {
+        sessions.pop_first();
+    }
+    changed
+}
+
+/// Stored sessions of `symbol`, oldest first
+pub fn series(symbol: &str) -> Vec<ForeignFlowPoint> {
+    flows().lock().unwrap_or_else(|e| e.into_inner()).get(symbol).map(|sessions| sessions.values().cloned().collect()).unwrap_or_default()
+}
+
+/// Symbols with stored foreign flow, in order
+pub fn symbols() -> Vec<String> {
+    let mut symbols: Vec<String> = flows().lock().unwrap_or_else(|e| e.into_inner()).keys().cloned().collect();
+    symbols.sort();
+    symbols
+}
+
+/// Net foreign value of `symbol` by session
+pub fn net_values(symbol: &str) -> BTreeMap<NaiveDate, f64> {
+    flows().lock().unwrap_or_else(|e| e.into_inner()).get(symbol)
+        .map(|sessions| sessions.iter().map(|(date, point)| (*date, point.net_value())).collect())
+        .unwrap_or_default()
+}
+
+fn has_history(symbol: &str) -> bool {
+    flows().lock().unwrap_or_else(|e| e.into_inner()).get(symbol).is_some_and(|sessions| !sessions.is_empty())
+}
+
+/// Where a node gets foreign flow: core nodes fetch it from TCBS, public nodes copy their core's
+pub enum ForeignFlowSource {
+    Tcbs(TcbsClient),
+    Core(String),
+}
+
+/// Refresh foreign flow every `interval` until `shutdown` is cancelled
+pub async fn run_refresh(data: SharedData, source: ForeignFlowSource, interval: Duration, shutdown: CancellationToken) {
+    loop {
+        match &source {
+            ForeignFlowSource::Tcbs(client) => refresh_from_tcbs(client, &data, &shutdown).await,
+            ForeignFlowSource::Core(core_url) => match sync_from_core(core_url).await {
+                Ok(changed) => info!(changed, "Synced foreign flow from the core node"),
+                Err(e) => {
+                    metrics::increment_counter("foreign_flow.sync_failed", 1);
+                    warn!(error = %e, "Failed to sync foreign flow from the core node");
+                }
+            },
+        }
+        debug!(interval_secs = interval.as_secs(), "Sleeping before next foreign flow refresh");
+        if !crate::shutdown::sleep(&shutdown, interval).await {
+            return;
+        }
+    }
+}
+
+/// One pass over every symbol the node has bars for. Indices and futures have no foreign flow.
+async fn refresh_from_tcbs(client: &TcbsClient, data: &SharedData, shutdown: &CancellationToken) {
+    let mut symbols: Vec<String> = data.lock().await.keys().cloned().collect();
+    symbols.sort();
+    let (mut changed, mut failed) = (0, 0);
+    for symbol in &symbols {
+        if shutdown.is_cancelled() {
+            return;
+        }
+        let count = if has_history(symbol) { REFRESH_SESSIONS } else { HISTORY_SESSIONS };
+        match client.foreign_trading(symbol, count).await {
+            Ok(sessions) => changed += merge(symbol, sessions.into_iter().map(ForeignFlowPoint::from)),
+            Err(TcbsError::NoData) => debug!(symbol, "No foreign flow"),
+            Err(e) => {
+                failed += 1;
+                metrics::increment_counter("foreign_flow.fetch_failed", 1);
+                debug!(symbol, error = ?e, "Failed to fetch foreign flow");
+            }
+        }
+    }
+    if failed > 0 {
+        warn!(symbols = symbols.len(), changed, failed, "Refreshed foreign flow with failures");
+    } else {
+        info!(symbols = symbols.len(), changed, "Refreshed foreign flow");
+    }
+}
+
+#[derive(Deserialize)]
+struct CoreForeignFlow {
+    symbols: BTreeMap<String, Vec<ForeignFlowPoint>>,
+}
+
+/// Copy the core node's recent foreign flow, unrounded
+async fn sync_from_core(core_url: &str) -> Result<usize, String> {
+    let days = if symbols().is_empty() { HISTORY_SYNC_DAYS } else { REFRESH_SYNC_DAYS };
+    let start_date = (market_time::market_today() - ChronoDuration::days(days)).format("%Y-%m-%d").to_string();
+    let response = crate::utils::http_client::shared_client()
+        .get(format!("{}/analysis/foreign-flow", core_url))
+        .query(&[("start_date", start_date.as_str()), ("precision", "full")])
+        .send().await
+        .map_err(|e| format!("Foreign flow request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Core network responded with {} to the foreign flow request", response.status()));
+    }
+    let core: CoreForeignFlow = response.json().await.map_err(|e| format!("Invalid foreign flow response: {}", e))?;
+    Ok(core.symbols.into_iter().map(|(symbol, points)| merge(&symbol, points)).sum())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_and_window() {
+        let date = |day: u32| NaiveDate::from_ymd_opt(2025, 8, day).unwrap();
+        let point = |day: u32, buy_value: f64, sell_value: f64| ForeignFlowPoint { date: date(day), buy_volume: 100, sell_volume: 40, buy_value, sell_value };
+        assert_eq!(merge("FFTEST", [point(13, 5.0, 2.0), point(14, 1.0, 4.0)]), 2);
+        // A refetch changes only the revised session
+        assert_eq!(merge("FFTEST", [point(14, 1.0, 4.0), point(15, 3.0, 3.0), point(14, 2.0, 4.0)]), 2);
+        assert_eq!(net_values("FFTEST"), BTreeMap::from([(date(13), 3.0), (date(14), -2.0), (date(15), 0.0)]));
+        assert!(symbols().contains(&"FFTEST".to_string()));
+
+        let views = with_window(&series("FFTEST"), 2, WarmupMode::Strict);
+        assert_eq!(views.iter().map(|view| view.net_value_window).collect::<Vec<_>>(), vec![None, Some(1.0), Some(-2.0)]);
+        assert_eq!(with_window(&series("FFTEST"), 2, WarmupMode::Lenient)[0].net_value_window, Some(3.0));
+        assert_eq!(views[0].net_volume, 60);
+
+        merge("FFTRIM", (0..RETAINED_SESSIONS as i64 + 5).map(|i| ForeignFlowPoint { date: date(1) + ChronoDuration::days(i), ..Default::default() }));
+        let kept = series("FFTRIM");
+        assert_eq!(kept.len(), RETAINED_SESSIONS);
+        assert_eq!(kept[0].date, date(6));
+    }
+}
@@ -0,0 +1,243 @@
+use crate::shutdown;
+use crate::utils::merge_policy;
+use crate::utils::metrics;
+use crate::vci::OhlcvData;
+use chrono::{DateTime, Utc};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+// Sends of one bar before it is put back and the peer rests
+const MAX_ATTEMPTS: u32 = 4;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+// A peer still failing after every retry is left alone this long before the next try
+const PEER_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Delivery to one internal peer, as reported in `/health`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PeerStatus {
+    pub peer: usize, // Position in INTERNAL_PEER_URLS; addresses are not exposed
+    pub pending: usize, // Symbols with a bar not yet delivered
+    pub delivered: u64,
+    pub retries: u64,
+    pub rejected: u64, // Bars the peer refused (4xx other than 429) and that were not resent
+    pub consecutive_failures: u32, // Bars given up on in a row; 0 once one is delivered
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub lag_secs: u64, // Age of the oldest undelivered bar; 0 when caught up
+}
+
+struct Pending {
+    bar: OhlcvData,
+    observed_at: DateTime<Utc>,
+}
+
+/// Latest undelivered bar per symbol: a newer bar replaces one still waiting, so a slow peer
+/// catches up on current prices instead of replaying every intermediate update
+#[derive(Default)]
+struct PeerQueue {
+    pending: HashMap<String, Pending>,
+    status: PeerStatus,
+}
+
+impl PeerQueue {
+    fn push(&mut self, symbol: &str, bar: OhlcvData, observed_at: DateTime<Utc>) {
+        self.pending.insert(symbol.to_string(), Pending { bar, observed_at });
+    }
+
+    /// Put back a bar that could not be delivered, unless a newer one arrived meanwhile
+    fn requeue(&mut self, symbol: String, pending: Pending) {
+        self.pending.entry(symbol).or_insert(pending);
+    }
+
+    fn take_oldest(&mut self) -> Option<(String, Pending)> {
+        let symbol = self.pending.iter().min_by_key(|(_, pending)| pending.observed_at).map(|(symbol, _)| symbol.clone())?;
+        self.pending.remove_entry(&symbol)
+    }
+
+    fn report(&self, now: DateTime<Utc>) -> PeerStatus {
+        let oldest = self.pending.values().map(|pending| pending.observed_at).min();
+        PeerStatus {
+            pending: self.pending.len(),
+            lag_secs: oldest.map_or(0, |oldest| (now - oldest).num_seconds().max(0) as u64),
+            ..self.status.clone()
+        }
+    }
+}
+
+struct Peer {
+    url: String,
+    queue: Mutex<PeerQueue>,
+    wake: Notify,
+}
+
+impl Peer {
+    fn queue(&self) -> std::sync::MutexGuard<'_, PeerQueue> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn registry() -> &'static Mutex<Vec<Arc<Peer>>> {
+    static PEERS: OnceLock<Mutex<Vec<Arc<Peer>>>> = OnceLock::new();
+    PEERS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Delivery status of every internal peer the broadcaster sends to; empty on nodes that do not gossip
+pub fn peer_report() -> Vec<PeerStatus> {
+    let now = Utc::now();
+    registry().lock().unwrap_or_else(|e| e.into_inner()).iter().map(|peer| peer.queue().report(now)).collect()
+}
+
+enum Delivery {
+    Delivered,
+    Rejected(String), // The peer refused the bar; resending will not help
+    Failed(String),   // Still failing after every retry, or shutdown interrupted the retries
+}
+
+fn retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::REQUEST_TIMEOUT
+}
+
+/// POST one bar to a peer's `/gossip`, retrying with exponential backoff. Retries resend the same
+/// bar, which the peer's gossip deduplication recognises, so a send that timed out after being
+/// applied is not applied twice.
+async fn deliver(client: &Client, peer: &Peer, token: &str, pending: &Pending, shutdown: &CancellationToken) -> Delivery {
+    let url = format!("{}/gossip", peer.url);
+    let mut backoff = INITIAL_BACKOFF;
+    let mut last_error = String::new();
+    for attempt in 1..=MAX_ATTEMPTS {
+        if attempt > 1 {
+            peer.queue().status.retries += 1;
+            metrics::increment_counter("gossip.internal_retries", 1);
+            if !shutdown::sleep(shutdown, backoff).await {
+                break;
+            }
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+        let request = client.post(&url)
+            .header("Authorization", token)
+            .header(merge_policy::OBSERVED_AT_HEADER, pending.observed_at.to_rfc3339())
+            .json(&pending.bar);
+        match request.send().await {
+            Ok(response) if response.status().is_success() => return Delivery::Delivered,
+            Ok(response) if retryable(response.status()) => last_error = format!("HTTP {}", response.status()),
+            Ok(response) => return Delivery::Rejected(format!("HTTP {}", response.status())),
+            Err(e) => last_error = e.to_string(),
+        }
+        debug!(peer = %peer.url, attempt, error = %last_error, "Internal peer send failed");
+    }
+    Delivery::Failed(last_error)
+}
+
+async fn run_peer(peer: Arc<Peer>, client: Client, token: String, shutdown: CancellationToken) {
+    loop {
+        let next = peer.queue().take_oldest();
+        let Some((symbol, pending)) = next else {
+            tokio::select! {
+                _ = shutdown.cancelled() => return,
+                _ = peer.wake.notified() => continue,
+            }
+        };
+        match deliver(&client, &peer, &token, &pending, &shutdown).await {
+            Delivery::Delivered => {
+                let mut queue = peer.queue();
+                queue.status.delivered += 1;
+                queue.status.consecutive_failures = 0;
+                queue.status.last_success_at = Some(Utc::now());
+                metrics::increment_counter("gossip.internal_delivered", 1);
+            }
+            Delivery::Rejected(error) => {
+                warn!(peer = %peer.url, symbol, error = %error, "Internal peer rejected gossip");
+                let mut queue = peer.queue();
+                queue.status.rejected += 1;
+                queue.status.last_error = Some(error);
+                metrics::increment_counter("gossip.internal_rejected", 1);
+            }
+            Delivery::Failed(error) => {
+                let failures = {
+                    let mut queue = peer.queue();
+                    queue.requeue(symbol.clone(), pending);
+                    queue.status.consecutive_failures += 1;
+                    queue.status.last_error = Some(error.clone());
+                    queue.status.consecutive_failures
+                };
+                warn!(peer = %peer.url, symbol, failures, error = %error, cooldown_secs = PEER_COOLDOWN.as_secs(), "Internal peer unreachable, pausing delivery");
+                metrics::increment_counter("gossip.internal_failed", 1);
+                if !shutdown::sleep(&shutdown, PEER_COOLDOWN).await {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Fans updated bars out to every internal peer, one delivery task per peer, so a slow or
+/// unreachable peer neither blocks the worker nor holds back the others
+pub struct GossipBroadcaster {
+    peers: Vec<Arc<Peer>>,
+}
+
+impl GossipBroadcaster {
+    /// Start a delivery task per peer URL, sending with `token` until `shutdown` is cancelled
+    pub fn start(peer_urls: &[String], token: &str, client: Client, shutdown: CancellationToken) -> Self {
+        let peers: Vec<Arc<Peer>> = peer_urls.iter().enumerate().map(|(index, url)| Arc::new(Peer {
+            url: url.clone(),
+            queue: Mutex::new(PeerQueue { pending: HashMap::new(), status: PeerStatus { peer: index, ..PeerStatus::default() } }),
+            wake: Notify::new(),
+        })).collect();
+        let token = format!("Bearer {}", token);
+        for peer in &peers {
+            tokio::spawn(run_peer(peer.clone(), client.clone(), token.clone(), shutdown.clone()));
+        }
+        *registry().lock().unwrap_or_else(|e| e.into_inner()) = peers.clone();
+        info!(peers = peers.len(), "Started internal gossip broadcaster");
+        Self { peers }
+    }
+
+    /// Queue `bar` for every peer; it replaces any bar of the symbol still waiting to be sent
+    pub fn send(&self, symbol: &str, bar: &OhlcvData, observed_at: DateTime<Utc>) {
+        for peer in &self.peers {
+            peer.queue().push(symbol, bar.clone(), observed_at);
+            peer.wake.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_queue_keeps_latest_bar_per_symbol_oldest_first() {
+        let at = |secs: i64| Utc.timestamp_opt(1_754_000_000 + secs, 0).unwrap();
+        let bar = |close: f64| OhlcvData { time: at(0), open: close, high: close, low: close, close, volume: 100, symbol: None };
+        let mut queue = PeerQueue::default();
+        queue.push("VCB", bar(60.0), at(0));
+        queue.push("FPT", bar(120.0), at(5));
+        queue.push("VCB", bar(61.0), at(10));
+        let report = queue.report(at(20));
+        assert_eq!((report.pending, report.lag_secs), (2, 15));
+
+        let (symbol, pending) = queue.take_oldest().unwrap();
+        assert_eq!(symbol, "FPT");
+        // A failed send goes back, but never over a newer bar of the same symbol
+        queue.requeue(symbol, pending);
+        let (symbol, pending) = queue.take_oldest().unwrap();
+        assert_eq!((symbol.as_str(), pending.bar.close), ("FPT", 120.0));
+        queue.push("FPT", bar(121.0), at(30));
+        queue.requeue(symbol, pending);
+        let closes: Vec<f64> = std::iter::from_fn(|| queue.take_oldest()).map(|(_, pending)| pending.bar.close).collect();
+        assert_eq!(closes, vec![61.0, 121.0]);
+        assert_eq!(queue.report(at(40)).lag_secs, 0);
+
+        assert!(retryable(StatusCode::SERVICE_UNAVAILABLE) && retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!retryable(StatusCode::UNAUTHORIZED));
+    }
+}
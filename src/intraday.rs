@@ -0,0 +1,186 @@
+use crate::data_structures::{estimate_memory_usage, InMemoryData};
+use crate::vci::OhlcvData;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Candle interval. Daily bars live in `InMemoryData`; the others are kept per interval in `IntradayData`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Interval {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "1H")]
+    OneHour,
+    #[serde(rename = "1D")]
+    OneDay,
+}
+
+impl Interval {
+    /// Interval name as the providers and `/tickers?interval=` spell it
+    pub fn name(self) -> &'static str {
+        match self {
+            Interval::OneMinute => "1m",
+            Interval::FifteenMinutes => "15m",
+            Interval::OneHour => "1H",
+            Interval::OneDay => "1D",
+        }
+    }
+
+    /// Candles in one trading day (09:00-11:30 and 13:00-15:00), rounded up
+    pub fn bars_per_session(self) -> u32 {
+        match self {
+            Interval::OneMinute => 270,
+            Interval::FifteenMinutes => 18,
+            Interval::OneHour => 5,
+            Interval::OneDay => 1,
+        }
+    }
+
+    /// Days of history fetched on the first cycle; later cycles only refetch the current day
+    pub fn initial_lookback_days(self) -> i64 {
+        match self {
+            Interval::OneMinute => 3,
+            Interval::FifteenMinutes => 14,
+            Interval::OneHour => 30,
+            Interval::OneDay => 150,
+        }
+    }
+
+    fn default_memory_mb(self) -> usize {
+        match self {
+            Interval::OneMinute => 128,
+            Interval::FifteenMinutes => 64,
+            Interval::OneHour | Interval::OneDay => 32,
+        }
+    }
+}
+
+impl std::str::FromStr for Interval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "1m" => Ok(Interval::OneMinute),
+            "15m" => Ok(Interval::FifteenMinutes),
+            "1H" | "1h" => Ok(Interval::OneHour),
+            "1D" | "1d" => Ok(Interval::OneDay),
+            other => Err(format!("Invalid interval '{}'. Expected 1m, 15m, 1H or 1D", other)),
+        }
+    }
+}
+
+// Intraday intervals the core worker collects, each with its own memory budget
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct IntradayConfig {
+    #[serde(default)]
+    pub intervals: Vec<Interval>, // Empty disables intraday collection; 1D is always collected
+    #[serde(default)]
+    pub memory_mb: BTreeMap<Interval, usize>, // Per-interval budget; 1m 128, 15m 64, 1H 32 by default
+}
+
+impl IntradayConfig {
+    /// Parse `INTRADAY_INTERVALS` ("1H,15m") and `INTRADAY_MEMORY_MB` ("1H=32,15m=64"), skipping invalid entries
+    pub fn parse(intervals: &str, memory_mb: &str) -> Self {
+        let mut parsed: Vec<Interval> = intervals.split(',')
+            .filter(|s| !s.trim().is_empty())
+            .filter_map(|s| s.parse().map_err(|e| tracing::warn!(error = %e, "Ignoring invalid intraday interval")).ok())
+            .filter(|interval| *interval != Interval::OneDay)
+            .collect();
+        parsed.sort();
+        parsed.dedup();
+        let memory_mb = memory_mb.split(',')
+            .filter_map(|entry| entry.split_once('='))
+            .filter_map(|(interval, mb)| Some((interval.parse().ok()?, mb.trim().parse().ok()?)))
+            .collect();
+        Self { intervals: parsed, memory_mb }
+    }
+
+    pub fn is_enabled(&self, interval: Interval) -> bool {
+        self.intervals.contains(&interval)
+    }
+
+    pub fn memory_budget_bytes(&self, interval: Interval) -> usize {
+        self.memory_mb.get(&interval).copied().unwrap_or_else(|| interval.default_memory_mb()) * 1024 * 1024
+    }
+}
+
+// Intraday candles per interval, then per symbol, oldest first
+pub type IntradayData = HashMap<Interval, InMemoryData>;
+pub type SharedIntradayData = Arc<Mutex<IntradayData>>;
+
+/// Merge fetched candles into a time-sorted series. A candle with a stored timestamp replaces the
+/// stored one, since the latest candle keeps changing until its period closes. Returns candles added.
+pub fn merge_bars(existing: &mut Vec<OhlcvData>, fetched: Vec<OhlcvData>) -> usize {
+    let mut added = 0;
+    for bar in fetched {
+        match existing.binary_search_by_key(&bar.time, |stored| stored.time) {
+            Ok(index) => existing[index] = bar,
+            Err(index) => {
+                existing.insert(index, bar);
+                added += 1;
+            }
+        }
+    }
+    added
+}
+
+/// Drop the oldest candles of every symbol until the interval's data fits in `budget_bytes`.
+/// Each symbol keeps the same number of recent candles. Returns the candles dropped.
+pub fn enforce_budget(data: &mut InMemoryData, budget_bytes: usize) -> usize {
+    let used = estimate_memory_usage(data);
+    let points: usize = data.values().map(Vec::len).sum();
+    if used <= budget_bytes || points == 0 {
+        return 0;
+    }
+    let bytes_per_point = used.div_ceil(points);
+    let keep = budget_bytes / bytes_per_point / data.len();
+    let mut dropped = 0;
+    for series in data.values_mut() {
+        if series.len() > keep {
+            dropped += series.len() - keep;
+            series.drain(..series.len() - keep);
+            series.shrink_to_fit();
+        }
+    }
+    dropped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_bars::bar;
+    use chrono::Duration;
+
+    // A flat candle `minute` minutes after 09:00 on 2025-08-15, Ho Chi Minh time
+    fn candle(minute: i64, close: f64) -> OhlcvData {
+        let day = bar(14, close, 100);
+        OhlcvData { time: day.time + Duration::minutes(120 + minute), ..day }
+    }
+
+    #[test]
+    fn test_merge_and_budget() {
+        let config = IntradayConfig::parse("1H, 15m,1D,5m,1H", "1H=8,bad,15m=x");
+        assert_eq!(config.intervals, vec![Interval::FifteenMinutes, Interval::OneHour]);
+        assert_eq!(config.memory_budget_bytes(Interval::OneHour), 8 * 1024 * 1024);
+        assert_eq!(config.memory_budget_bytes(Interval::FifteenMinutes), 64 * 1024 * 1024);
+
+        // The still-forming candle is replaced, earlier gaps are filled in order
+        let mut series = vec![candle(0, 10.0), candle(30, 11.0)];
+        assert_eq!(merge_bars(&mut series, vec![candle(30, 12.0), candle(15, 10.5), candle(45, 13.0)]), 2);
+        let closes: Vec<f64> = series.iter().map(|bar| bar.close).collect();
+        assert_eq!(closes, vec![10.0, 10.5, 12.0, 13.0]);
+
+        let mut data: InMemoryData = HashMap::new();
+        data.insert("VCB".to_string(), (0..200).map(|i| candle(i, 10.0)).collect());
+        data.insert("FPT".to_string(), (0..100).map(|i| candle(i, 20.0)).collect());
+        assert_eq!(enforce_budget(&mut data, usize::MAX), 0);
+        let budget = estimate_memory_usage(&data) / 3;
+        assert!(enforce_budget(&mut data, budget) > 0);
+        assert!(estimate_memory_usage(&data) <= budget);
+        assert_eq!(data["VCB"].len(), data["FPT"].len());
+        assert_eq!(data["VCB"].last().unwrap().time, candle(199, 10.0).time);
+    }
+}
@@ -1,6 +1,32 @@
+pub mod aggregate;
+pub mod analysis;
 pub mod api;
+pub mod archive;
+pub mod auth;
+pub mod backfill;
+pub mod build_info;
+pub mod company;
 pub mod config;
+pub mod constituents;
+pub mod data_source;
 pub mod data_structures;
+pub mod error;
+pub mod events;
+pub mod export;
+pub mod foreign_flow;
+pub mod gossip;
+pub mod intraday;
+pub mod profile;
+pub mod quote;
+pub mod recompute;
+pub mod replay;
+pub mod schema;
+pub mod shutdown;
+pub mod standby;
+pub mod tcbs;
+pub mod ticker_info;
 pub mod utils;
 pub mod vci;
+pub mod wal;
+pub mod wire;
 pub mod worker;
\ No newline at end of file
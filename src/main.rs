@@ -1,26 +1,77 @@
+pub mod aggregate;
+pub mod analysis;
 pub mod api;
+pub mod archive;
+pub mod auth;
+pub mod backfill;
+pub mod build_info;
+pub mod company;
 pub mod config;
+pub mod constituents;
+pub mod data_source;
 pub mod data_structures;
+pub mod error;
+pub mod events;
+pub mod export;
+pub mod foreign_flow;
+pub mod gossip;
+pub mod intraday;
+pub mod profile;
+pub mod quote;
+pub mod recompute;
+pub mod replay;
+pub mod schema;
+pub mod shutdown;
+pub mod standby;
+pub mod tcbs;
+pub mod ticker_info;
 pub mod utils;
 pub mod vci;
+pub mod wal;
+pub mod wire;
 pub mod worker;
 
-use crate::config::SharedTokenConfig;
-use crate::data_structures::{InMemoryData, PublicActorReputation, LastInternalUpdate, SharedData, SharedReputation, SharedTickerGroups, SharedHealthStats, HealthStats};
-use axum::{extract::FromRef, routing::{get, post}, Router};
-use std::{net::SocketAddr, sync::Arc, time::Instant};
+use crate::company::{CompanyService, SharedCompanyService};
+use crate::quote::{QuoteService, SharedQuoteService};
+use crate::auth::{SharedTokenRegistry, TokenRegistry};
+use crate::analysis::market_cap::MarketCapSources;
+use crate::constituents::SharedIndexConstituents;
+use crate::events::SharedEventBus;
+use crate::export::{EnhancedSnapshots, SharedEnhancedSnapshots};
+use crate::intraday::SharedIntradayData;
+use crate::ticker_info::SharedTickerDirectory;
+use crate::utils::load_shed::{self, LoadShedder};
+use crate::utils::mirrors::{RawMirrors, SharedRawMirrors};
+use crate::utils::object_store::{ObjectStore, SharedObjectStore};
+use crate::data_structures::{InMemoryData, PublicActorReputation, LastInternalUpdate, SharedData, SharedReputation, SharedGossipStaging, SharedTickerGroups, SharedHealthStats, HealthStats, SharedAnalysisCache, SharedOfficeHoursConfig};
+use axum::{extract::FromRef, middleware, routing::{get, post}, Router};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::{Duration, Instant}};
 use tokio::sync::Mutex;
 use tower_governor::{governor::GovernorConfigBuilder, GovernorLayer};
 use tower_http::cors::{CorsLayer, Any};
+use tokio_util::sync::CancellationToken;
 
 #[derive(Clone)]
 struct AppState {
     data: SharedData,
     reputation: SharedReputation,
+    gossip_staging: SharedGossipStaging,
     last_update: LastInternalUpdate,
-    tokens: SharedTokenConfig,
+    tokens: SharedTokenRegistry,
     ticker_groups: SharedTickerGroups,
     health_stats: SharedHealthStats,
+    index_constituents: SharedIndexConstituents,
+    ticker_directory: SharedTickerDirectory,
+    analysis_cache: SharedAnalysisCache,
+    enhanced_snapshots: SharedEnhancedSnapshots,
+    office_hours: SharedOfficeHoursConfig,
+    raw_mirrors: SharedRawMirrors,
+    object_store: SharedObjectStore,
+    company: SharedCompanyService,
+    quote: SharedQuoteService,
+    events: SharedEventBus,
+    shutdown: CancellationToken,
+    intraday: SharedIntradayData,
 }
 
 impl FromRef<AppState> for SharedData {
@@ -35,14 +86,20 @@ impl FromRef<AppState> for SharedReputation {
     }
 }
 
+impl FromRef<AppState> for SharedGossipStaging {
+    fn from_ref(app_state: &AppState) -> SharedGossipStaging {
+        app_state.gossip_staging.clone()
+    }
+}
+
 impl FromRef<AppState> for LastInternalUpdate {
     fn from_ref(app_state: &AppState) -> LastInternalUpdate {
         app_state.last_update.clone()
     }
 }
 
-impl FromRef<AppState> for SharedTokenConfig {
-    fn from_ref(app_state: &AppState) -> SharedTokenConfig {
+impl FromRef<AppState> for SharedTokenRegistry {
+    fn from_ref(app_state: &AppState) -> SharedTokenRegistry {
         app_state.tokens.clone()
     }
 }
@@ -59,27 +116,167 @@ impl FromRef<AppState> for SharedHealthStats {
     }
 }
 
+impl FromRef<AppState> for SharedIndexConstituents {
+    fn from_ref(app_state: &AppState) -> SharedIndexConstituents {
+        app_state.index_constituents.clone()
+    }
+}
+
+impl FromRef<AppState> for SharedTickerDirectory {
+    fn from_ref(app_state: &AppState) -> SharedTickerDirectory {
+        app_state.ticker_directory.clone()
+    }
+}
+
+impl FromRef<AppState> for MarketCapSources {
+    fn from_ref(app_state: &AppState) -> MarketCapSources {
+        MarketCapSources { tickers: app_state.ticker_directory.clone(), company: app_state.company.clone() }
+    }
+}
+
+impl FromRef<AppState> for SharedEnhancedSnapshots {
+    fn from_ref(app_state: &AppState) -> SharedEnhancedSnapshots {
+        app_state.enhanced_snapshots.clone()
+    }
+}
+
+impl FromRef<AppState> for SharedAnalysisCache {
+    fn from_ref(app_state: &AppState) -> SharedAnalysisCache {
+        app_state.analysis_cache.clone()
+    }
+}
+
+impl FromRef<AppState> for SharedOfficeHoursConfig {
+    fn from_ref(app_state: &AppState) -> SharedOfficeHoursConfig {
+        app_state.office_hours.clone()
+    }
+}
+
+impl FromRef<AppState> for SharedRawMirrors {
+    fn from_ref(app_state: &AppState) -> SharedRawMirrors {
+        app_state.raw_mirrors.clone()
+    }
+}
+
+impl FromRef<AppState> for SharedObjectStore {
+    fn from_ref(app_state: &AppState) -> SharedObjectStore {
+        app_state.object_store.clone()
+    }
+}
+
+impl FromRef<AppState> for SharedCompanyService {
+    fn from_ref(app_state: &AppState) -> SharedCompanyService {
+        app_state.company.clone()
+    }
+}
+
+impl FromRef<AppState> for SharedQuoteService {
+    fn from_ref(app_state: &AppState) -> SharedQuoteService {
+        app_state.quote.clone()
+    }
+}
+
+impl FromRef<AppState> for SharedEventBus {
+    fn from_ref(app_state: &AppState) -> SharedEventBus {
+        app_state.events.clone()
+    }
+}
+
+impl FromRef<AppState> for CancellationToken {
+    fn from_ref(app_state: &AppState) -> CancellationToken {
+        app_state.shutdown.clone()
+    }
+}
+
+impl FromRef<AppState> for SharedIntradayData {
+    fn from_ref(app_state: &AppState) -> SharedIntradayData {
+        app_state.intraday.clone()
+    }
+}
+
 #[tokio::main]
 async fn main() {
-    let app_config = config::AppConfig::load();
-    
-    // Initialize tracing with node_name in all logs
+    // Subcommands run without loading server configuration
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("aggregate") => std::process::exit(aggregate::run_cli(&args[2..])),
+        Some("backfill") => std::process::exit(backfill::run_cli(&args[2..]).await),
+        Some("replay") => std::process::exit(replay::run_cli(&args[2..])),
+        Some("recompute") => std::process::exit(recompute::run_cli(&args[2..])),
+        _ => {}
+    }
+
+    // Environment-based config reads .env; load it before tracing so RUST_LOG set there applies
+    if std::env::var("CONFIG_FILE").is_err() {
+        dotenvy::dotenv().ok();
+    }
+    // Initialize tracing before loading config, so warnings about invalid settings are logged
     tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .with_target(false)
         .init();
 
+    let app_config = config::AppConfig::load();
+    
     // Set a global span with node_name for all subsequent logs
     let _span = tracing::info_span!("node", name = %app_config.node_name).entered();
     
     tracing::info!("Starting aipriceaction-proxy");
-    tracing::info!(?app_config.environment, port = app_config.port, "Loaded configuration");
+    tracing::info!(?app_config.environment, port = app_config.port, profile = %app_config.profile_name, "Loaded configuration");
+    utils::provider_quota::set_thresholds(app_config.quota_thresholds.clone());
+    utils::rate_limiter::set_budgets(app_config.rate_limits.clone());
+    utils::query_cost::set_limits(app_config.query_cost_limits.clone());
+    utils::http_client::init_shared_client(&app_config.http_pool);
+    utils::precision::set_precision(app_config.precision.clone());
+    utils::symbol_stats::init(Some(app_config.symbol_stats_path.clone()));
+    analysis::indicator_cache::init(app_config.indicator_cache_path.clone());
+    utils::transitions::set_capture_context(app_config.transition_context);
+    analysis::strength::set_weights(app_config.strength_weights.clone());
+    analysis::warmup::set_mode(app_config.warmup_mode);
+    analysis::liquidity::set_presets(app_config.liquidity_presets.clone());
+    export::set_indicator_groups(app_config.profile.indicators.clone());
+    analysis::ma_score::set_moving_averages(app_config.profile.moving_averages.clone());
+    utils::merge_policy::set_precedence(app_config.merge_precedence.clone());
+    utils::corrections::set_office_hours(app_config.office_hours_config.clone());
     
-    let shared_data: SharedData = Arc::new(Mutex::new(InMemoryData::new()));
+    // Pick up live updates from before a crash or restart; fetches and syncs refresh them as usual
+    let recovered = app_config.wal_dir.as_ref().map_or_else(InMemoryData::new, |dir| match wal::recover(dir) {
+        Ok((data, stats)) => {
+            tracing::info!(?dir, symbols = data.len(), checkpoint_bars = stats.checkpoint_bars, wal_updates = stats.wal_updates, skipped_lines = stats.skipped_lines, "Recovered live data");
+            data
+        }
+        Err(e) => {
+            tracing::warn!(?dir, error = %e, "Failed to recover live data, starting empty");
+            InMemoryData::new()
+        }
+    });
+    let shared_data: SharedData = Arc::new(Mutex::new(recovered));
+    let mut enhanced_snapshots = EnhancedSnapshots::default();
+    if let Some(dir) = &app_config.wal_dir {
+        match wal::load_enhanced(dir) {
+            Ok(Some(snapshot)) => {
+                tracing::info!(version = snapshot.version, symbols = snapshot.series.len(), built_at = %snapshot.built_at, "Restored enhanced snapshot");
+                enhanced_snapshots.restore(snapshot);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!(?dir, error = %e, "Failed to restore enhanced snapshot"),
+        }
+    }
+    let shared_enhanced_snapshots: SharedEnhancedSnapshots = Arc::new(Mutex::new(enhanced_snapshots));
     let shared_reputation: SharedReputation = Arc::new(Mutex::new(PublicActorReputation::new()));
+    // One store per collected interval; /tickers rejects intervals without one. Not persisted by the WAL.
+    let shared_intraday: SharedIntradayData = Arc::new(Mutex::new(
+        app_config.intraday.intervals.iter().map(|interval| (*interval, InMemoryData::new())).collect(),
+    ));
+    if !app_config.intraday.intervals.is_empty() {
+        tracing::info!(intervals = ?app_config.intraday.intervals, "Intraday collection enabled");
+    }
+    let shared_gossip_staging: SharedGossipStaging = Arc::new(Mutex::new(HashMap::new()));
     let last_internal_update: LastInternalUpdate = Arc::new(Mutex::new(Instant::now()));
-    let shared_tokens: SharedTokenConfig = app_config.tokens.clone();
+    let shared_tokens: SharedTokenRegistry = Arc::new(Mutex::new(TokenRegistry::from_config(&app_config.tokens)));
     let shared_ticker_groups: SharedTickerGroups = config::load_ticker_groups();
+    let shared_index_constituents: SharedIndexConstituents = constituents::load_index_constituents();
+    let shared_ticker_directory: SharedTickerDirectory = ticker_info::load_ticker_directory();
     
     // Initialize health stats with app config
     let health_stats = HealthStats {
@@ -93,24 +290,97 @@ async fn main() {
         public_peers_count: app_config.public_peers.len(),
         build_date: app_config.build_date.clone(),
         git_commit: app_config.git_commit.clone(),
+        profile: Some(app_config.profile_name.clone()),
+        strict_readiness: app_config.strict_readiness,
         ..HealthStats::default()
     };
     let shared_health_stats: SharedHealthStats = Arc::new(Mutex::new(health_stats));
 
+    let object_store: SharedObjectStore = app_config.object_store.as_ref().and_then(|store_config| {
+        match ObjectStore::new(store_config) {
+            Ok(store) => {
+                tracing::info!(endpoint = %store_config.endpoint, bucket = %store_config.bucket, "Object storage enabled");
+                Some(Arc::new(store))
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Invalid object storage configuration, continuing without it");
+                None
+            }
+        }
+    });
+
+    // Company info has its own client so /company lookups never wait behind worker batches
+    let company_client = vci::VciClient::new(true)
+        .expect("Failed to initialize VCI client for company info")
+        .with_header_overrides(app_config.header_profiles.get("vci"));
+
+    let event_bus: SharedEventBus = events::new_event_bus();
+    // Cancelled on SIGINT/SIGTERM: the server stops accepting requests and background loops wind down
+    let shutdown_token = CancellationToken::new();
+    shutdown::spawn_listener(shutdown_token.clone());
+
     let app_state = AppState {
         data: shared_data.clone(),
-        reputation: shared_reputation,
+        reputation: shared_reputation.clone(),
+        gossip_staging: shared_gossip_staging.clone(),
         last_update: last_internal_update,
         tokens: shared_tokens,
         ticker_groups: shared_ticker_groups,
         health_stats: shared_health_stats.clone(),
+        index_constituents: shared_index_constituents,
+        ticker_directory: shared_ticker_directory.clone(),
+        analysis_cache: Arc::new(Mutex::new(HashMap::new())),
+        enhanced_snapshots: shared_enhanced_snapshots.clone(),
+        office_hours: Arc::new(app_config.office_hours_config.clone()),
+        object_store: object_store.clone(),
+        quote: Arc::new(QuoteService::new(company_client.clone())),
+        company: Arc::new(CompanyService::new(company_client)),
+        events: event_bus.clone(),
+        shutdown: shutdown_token.clone(),
+        intraday: shared_intraday.clone(),
+        raw_mirrors: Arc::new(RawMirrors::new(&app_config.raw_mirror_urls, app_config.raw_checksum_manifest.clone())),
     };
 
+    if let Some(url) = app_config.ticker_info_url.clone() {
+        tracing::info!(url, "Spawning ticker info refresh");
+        tokio::spawn(ticker_info::run_refresh(shared_ticker_directory.clone(), url, app_config.ticker_info_refresh, shutdown_token.clone()));
+    }
+
+    if let Some(interval) = app_config.foreign_flow_refresh {
+        let source = match &app_config.core_network_url {
+            Some(core_url) => Some(foreign_flow::ForeignFlowSource::Core(core_url.clone())),
+            None => match tcbs::TcbsClient::new(true) {
+                Ok(client) => Some(foreign_flow::ForeignFlowSource::Tcbs(client)),
+                Err(e) => {
+                    tracing::warn!(error = ?e, "Failed to initialize TCBS client, foreign flow disabled");
+                    None
+                }
+            },
+        };
+        if let Some(source) = source {
+            tracing::info!(interval_secs = interval.as_secs(), "Spawning foreign flow refresh");
+            tokio::spawn(foreign_flow::run_refresh(shared_data.clone(), source, interval, shutdown_token.clone()));
+        }
+    }
+
+    // The log keeps recording until the worker has stopped, so its last updates make the final checkpoint
+    let wal_stop = CancellationToken::new();
+    let wal_task = app_config.wal_dir.clone().map(|dir| {
+        tokio::spawn(wal::run(shared_data.clone(), shared_enhanced_snapshots, event_bus.subscribe(), dir, app_config.wal_checkpoint_interval, wal_stop.clone()))
+    });
+
     tracing::info!("Spawning background worker");
-    tokio::spawn(worker::run(
+    let worker_task = tokio::spawn(worker::run(
         shared_data.clone(),
         app_config.clone(),
         shared_health_stats.clone(),
+        object_store,
+        shared_gossip_staging,
+        shared_reputation,
+        event_bus,
+        shared_intraday,
+        shared_ticker_directory.clone(),
+        shutdown_token.clone(),
     ));
 
     let governor_conf = Arc::new(
@@ -134,28 +404,124 @@ async fn main() {
     tracing::info!("Registering routes:");
     tracing::info!("  GET  /tickers");
     tracing::info!("  GET  /tickers/group");
+    tracing::info!("  GET  /symbols");
+    tracing::info!("  GET  /search");
     tracing::info!("  POST /gossip");
     tracing::info!("  POST /public/gossip");
     tracing::info!("  GET  /health");
+    tracing::info!("  GET  /sync/changes");
+    tracing::info!("  GET  /changes/{{symbol}}");
+    tracing::info!("  GET  /metrics");
     tracing::info!("  GET  /raw/{{*path}}");
+    tracing::info!("  GET  /index/{{name}}/constituents");
+    tracing::info!("  GET  /analysis/ma-distribution");
+    tracing::info!("  GET  /analysis/ma-streaks");
+    tracing::info!("  GET  /analysis/ma-score");
+    tracing::info!("  GET  /analysis/money-flow");
+    tracing::info!("  GET  /analysis/money-flow-divergence");
+    tracing::info!("  GET  /analysis/foreign-flow");
+    tracing::info!("  GET  /analysis/sectors");
+    tracing::info!("  GET  /screener");
+    tracing::info!("  GET  /analysis/strength");
+    tracing::info!("  GET  /leaderboard");
+    tracing::info!("  GET  /admin/tokens");
+    tracing::info!("  POST /admin/tokens/{{name}}/revoke");
+    tracing::info!("  POST /admin/tokens/{{name}}/restore");
+    tracing::info!("  GET  /admin/transitions");
+    tracing::info!("  GET  /admin/reputation");
+    tracing::info!("  POST /admin/reputation/{{ip}}/reset");
+    tracing::info!("  GET  /admin/recalculate");
+    tracing::info!("  POST /admin/recalculate");
+    tracing::info!("  GET  /stats/gaps/{{symbol}}");
+    tracing::info!("  GET  /derivatives/basis");
+    tracing::info!("  GET  /company/{{symbol}}");
+    tracing::info!("  GET  /quote/{{symbol}}");
+    tracing::info!("  GET  /symbols/{{symbol}}/stats");
+    tracing::info!("  GET  /coverage");
+    tracing::info!("  GET  /schema/enhanced.json");
+    tracing::info!("  GET  /version");
+    tracing::info!("  GET  /sse/tickers");
 
     let app = Router::new()
         .route("/tickers", get(api::get_all_tickers_handler))
         .route("/tickers/group", get(api::get_ticker_groups_handler))
+        .route("/symbols", get(api::symbols_handler))
+        .route("/search", get(api::search_handler))
         .route("/gossip", post(api::internal_gossip_handler))
         .route(
             "/public/gossip",
-            post(api::public_gossip_handler).layer(GovernorLayer::new(governor_conf)),
+            post(api::public_gossip_handler).layer(GovernorLayer::new(governor_conf).error_handler(error::governor_error_response)),
         )
         .route("/health", get(api::health_handler))
+        .route("/sync/changes", get(api::sync_changes_handler))
+        .route("/changes/{symbol}", get(api::corrections_handler))
+        .route("/metrics", get(api::metrics_handler))
         .route("/raw/{*path}", get(api::raw_proxy_handler))
-        .layer(cors)
-        .with_state(app_state);
+        .route("/index/{name}/constituents", get(api::index_constituents_handler))
+        .route("/analysis/ma-distribution", get(api::ma_distribution_handler))
+        .route("/analysis/ma-streaks", get(api::ma_streaks_handler))
+        .route("/analysis/money-flow", get(api::money_flow_handler))
+        .route("/analysis/money-flow-divergence", get(api::money_flow_divergence_handler))
+        .route("/analysis/foreign-flow", get(api::foreign_flow_handler))
+        .route("/analysis/sectors", get(api::sectors_handler))
+        .route("/screener", get(api::screener_handler))
+        .route("/analysis/ma-score", get(api::ma_score_handler))
+        .route("/analysis/strength", get(api::strength_handler))
+        .route("/leaderboard", get(api::leaderboard_handler))
+        .route("/admin/tokens", get(api::admin_tokens_handler))
+        .route("/admin/tokens/{name}/revoke", post(api::admin_revoke_token_handler))
+        .route("/admin/tokens/{name}/restore", post(api::admin_restore_token_handler))
+        .route("/admin/transitions", get(api::admin_transitions_handler))
+        .route("/admin/reputation", get(api::admin_reputation_handler))
+        .route("/admin/reputation/{ip}/reset", post(api::admin_reset_reputation_handler))
+        .route("/admin/recalculate", get(api::admin_recalculations_handler).post(api::admin_recalculate_handler))
+        .route("/stats/gaps/{symbol}", get(api::gap_stats_handler))
+        .route("/derivatives/basis", get(api::derivatives_basis_handler))
+        .route("/company/{symbol}", get(api::company_handler))
+        .route("/quote/{symbol}", get(api::quote_handler))
+        .route("/symbols/{symbol}/stats", get(api::symbol_stats_handler))
+        .route("/coverage", get(api::coverage_handler))
+        .route("/schema/enhanced.json", get(api::enhanced_schema_handler))
+        .route("/version", get(api::version_handler))
+        .route("/sse/tickers", get(api::sse_tickers_handler));
+
+    // Shed bulk requests while quote/health/gossip latency is over the SLO
+    let app = if app_config.load_shed_p95_ms > 0 {
+        tracing::info!(slo_p95_ms = app_config.load_shed_p95_ms, "Load shedding enabled");
+        let shedder = Arc::new(LoadShedder::new(Duration::from_millis(app_config.load_shed_p95_ms)));
+        app.route_layer(middleware::from_fn_with_state(shedder, load_shed::load_shed_middleware))
+    } else {
+        app
+    };
+    // Per-route request latencies for /metrics
+    let app = app.route_layer(middleware::from_fn(utils::metrics::track_requests));
+    let app = app.layer(cors).with_state(app_state);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], app_config.port));
     tracing::info!(%addr, "Server listening");
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
     axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown_token.clone().cancelled_owned())
         .await
         .unwrap();
+
+    tracing::info!(grace_secs = shutdown::SHUTDOWN_GRACE.as_secs(), "Server stopped, waiting for background tasks");
+    let background = async {
+        if let Err(e) = worker_task.await {
+            tracing::warn!(error = %e, "Background worker failed");
+        }
+        wal_stop.cancel();
+        if let Some(wal_task) = wal_task
+            && let Err(e) = wal_task.await
+        {
+            tracing::warn!(error = %e, "Write-ahead log task failed");
+        }
+    };
+    if tokio::time::timeout(shutdown::SHUTDOWN_GRACE, background).await.is_err() {
+        tracing::warn!("Background tasks did not stop in time, exiting anyway");
+    }
+    if let Err(e) = analysis::indicator_cache::save() {
+        tracing::warn!(error = %e, "Failed to save indicator cache");
+    }
+    tracing::info!("Shutdown complete");
 }
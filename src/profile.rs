@@ -0,0 +1,171 @@
+use crate::analysis::ma_score::MovingAverage;
+use crate::constituents::IndexConstituents;
+use crate::data_structures::{TickerGroups, MAX_DATA_POINTS_PER_SYMBOL};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use tracing::warn;
+
+// First cycle seeds enough history (~100 trading days) for MA50-based analysis
+pub const DEFAULT_HISTORY_DAYS: i64 = 150;
+pub const DEFAULT_PROFILE: &str = "full";
+
+/// Which symbols a node fetches, besides the VNINDEX and VN30 indices and VN30 futures it always keeps.
+/// The union of what is set; nothing set means every group in ticker_group.json.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Universe {
+    pub groups: Vec<String>,   // Named groups from ticker_group.json
+    pub index: Option<String>, // Current members of an index in index_constituents.json
+    pub symbols: Vec<String>,  // An explicit list
+}
+
+impl Universe {
+    pub fn is_all(&self) -> bool {
+        self.groups.is_empty() && self.index.is_none() && self.symbols.is_empty()
+    }
+
+    /// Symbols of the universe as of `date`; unknown groups and indices are skipped with a warning
+    pub fn symbols(&self, groups: &TickerGroups, constituents: &IndexConstituents, date: NaiveDate) -> Vec<String> {
+        if self.is_all() {
+            return groups.0.values().flatten().cloned().collect();
+        }
+        let mut symbols: Vec<String> = self.groups.iter()
+            .filter_map(|name| {
+                let found = groups.0.get(name);
+                if found.is_none() {
+                    warn!(group = %name, "Profile names a group missing from ticker_group.json");
+                }
+                found
+            })
+            .flatten()
+            .cloned()
+            .collect();
+        if let Some(index) = &self.index {
+            match constituents.symbols_at(index, date) {
+                Some(members) => symbols.extend(members),
+                None => warn!(index = %index, "Profile names an index missing from index_constituents.json"),
+            }
+        }
+        symbols.extend(self.symbols.iter().map(|s| s.trim().to_uppercase()));
+        symbols
+    }
+}
+
+/// Groups of columns computed for enhanced rows; disabled groups are served as nulls
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndicatorGroup {
+    MaScore,   // maN and maN_score columns
+    Strength,  // Composite strength score
+    Technical, // RSI, MACD and Bollinger bands
+}
+
+pub fn all_indicator_groups() -> Vec<IndicatorGroup> {
+    vec![IndicatorGroup::MaScore, IndicatorGroup::Strength, IndicatorGroup::Technical]
+}
+
+/// Settings that differ between deployments, chosen together by name with `PROFILE`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeploymentProfile {
+    pub universe: Universe,
+    pub history_days: i64,              // Days fetched on the first cycle, the range enhanced rows can cover
+    pub max_points_per_symbol: usize,   // Daily bars kept per symbol
+    pub indicators: Vec<IndicatorGroup>,
+    pub moving_averages: Vec<MovingAverage>, // Extra sma<N>/ema<N> columns of enhanced rows, with ma_score
+}
+
+impl Default for DeploymentProfile {
+    fn default() -> Self {
+        Self {
+            universe: Universe::default(),
+            history_days: DEFAULT_HISTORY_DAYS,
+            max_points_per_symbol: MAX_DATA_POINTS_PER_SYMBOL,
+            indicators: all_indicator_groups(),
+            moving_averages: Vec::new(),
+        }
+    }
+}
+
+pub type Profiles = BTreeMap<String, DeploymentProfile>;
+
+/// "full" (the whole market, the defaults) and "vn30" (VN30 members with about a year of history)
+pub fn builtin_profiles() -> Profiles {
+    BTreeMap::from([
+        (DEFAULT_PROFILE.to_string(), DeploymentProfile::default()),
+        ("vn30".to_string(), DeploymentProfile {
+            universe: Universe { index: Some("VN30".to_string()), ..Default::default() },
+            history_days: 400,
+            max_points_per_symbol: 260,
+            indicators: all_indicator_groups(),
+            moving_averages: Vec::new(),
+        }),
+    ])
+}
+
+/// Read extra profiles from a YAML map of name to profile
+pub fn load_profiles(path: &str) -> Profiles {
+    let content = fs::read_to_string(path).unwrap_or_else(|e| panic!("Failed to read profiles file {}: {}", path, e));
+    serde_yaml::from_str(&content).unwrap_or_else(|e| panic!("Failed to parse profiles file {}: {}", path, e))
+}
+
+/// The named profile from the built-ins plus `custom` (which override built-ins of the same name).
+/// An unknown name is a deployment mistake, so it stops startup rather than silently running "full".
+pub fn select(name: Option<&str>, custom: Profiles) -> (String, DeploymentProfile) {
+    let name = name.map(|n| n.trim().to_lowercase()).filter(|n| !n.is_empty()).unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+    let mut profiles = builtin_profiles();
+    profiles.extend(custom.into_iter().map(|(name, profile)| (name.to_lowercase(), profile)));
+    match profiles.remove(&name) {
+        Some(profile) => (name, profile),
+        None => panic!("Unknown profile '{}'. Available: {}", name, profiles.keys().cloned().collect::<Vec<_>>().join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constituents::{Constituent, MembershipSnapshot};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_profile_selection_and_universe() {
+        let custom: Profiles = serde_yaml::from_str("
+VN100:
+  universe: { index: VN100, symbols: [vnindex] }
+  history_days: 365
+  indicators: [ma_score, strength]
+  moving_averages: [EMA20, sma200]
+banks:
+  universe: { groups: [NGAN_HANG] }
+").unwrap();
+        assert_eq!(select(None, Profiles::new()), (DEFAULT_PROFILE.to_string(), DeploymentProfile::default()));
+        let (name, research) = select(Some(" vn100 "), custom.clone());
+        assert_eq!(name, "vn100");
+        assert_eq!(research.history_days, 365);
+        assert_eq!(research.max_points_per_symbol, MAX_DATA_POINTS_PER_SYMBOL); // Unset fields keep the defaults
+        assert_eq!(research.indicators, vec![IndicatorGroup::MaScore, IndicatorGroup::Strength]);
+        assert_eq!(research.moving_averages.iter().map(ToString::to_string).collect::<Vec<_>>(), vec!["ema20", "sma200"]);
+        assert!(serde_yaml::from_str::<Profiles>("bad: { moving_averages: [wma20] }").is_err());
+        assert_eq!(select(Some("VN30"), custom.clone()).1.universe.index.as_deref(), Some("VN30"));
+        assert!(std::panic::catch_unwind(|| select(Some("vn50"), Profiles::new())).is_err());
+
+        let groups = TickerGroups(HashMap::from([
+            ("NGAN_HANG".to_string(), vec!["VCB".to_string(), "BID".to_string()]),
+            ("CONG_NGHE".to_string(), vec!["FPT".to_string()]),
+        ]));
+        let day = NaiveDate::from_ymd_opt(2025, 8, 15).unwrap();
+        let member = |symbol: &str| Constituent { symbol: symbol.to_string(), weight: None };
+        let constituents = IndexConstituents(HashMap::from([(
+            "VN100".to_string(),
+            vec![MembershipSnapshot { effective_date: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(), constituents: vec![member("VCB"), member("HPG")] }],
+        )]));
+        let sorted = |mut symbols: Vec<String>| { symbols.sort(); symbols };
+        assert_eq!(sorted(Universe::default().symbols(&groups, &constituents, day)), vec!["BID", "FPT", "VCB"]);
+        assert_eq!(sorted(custom["banks"].universe.symbols(&groups, &constituents, day)), vec!["BID", "VCB"]);
+        assert_eq!(sorted(research.universe.symbols(&groups, &constituents, day)), vec!["HPG", "VCB", "VNINDEX"]);
+        let missing = Universe { groups: vec!["MISSING".to_string()], index: Some("VN50".to_string()), symbols: Vec::new() };
+        assert!(missing.symbols(&groups, &constituents, day).is_empty());
+    }
+}
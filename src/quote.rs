@@ -0,0 +1,111 @@
+use crate::vci::{PriceBoardQuote, VciClient, VciError};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+// Quotes younger than this are served without asking VCI; the price board moves every few seconds
+pub const QUOTE_TTL_SECS: i64 = 3;
+// Oldest quote served when VCI fails, flagged as stale
+const MAX_STALE_SECS: i64 = 60;
+
+/// A price board quote as served, with its age
+#[derive(Clone, Debug, Serialize)]
+pub struct QuoteView {
+    #[serde(flatten)]
+    pub quote: PriceBoardQuote,
+    pub fetched_at: DateTime<Utc>,
+    pub age_ms: i64,
+    pub stale: bool, // VCI failed and this is the last quote fetched
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct QuoteCacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub stale_served: u64, // Served past the TTL because VCI failed
+    pub upstream_errors: u64,
+}
+
+#[derive(Clone, Debug)]
+struct CachedQuote {
+    quote: PriceBoardQuote,
+    fetched_at: DateTime<Utc>,
+}
+
+impl CachedQuote {
+    fn view(&self, now: DateTime<Utc>, stale: bool) -> QuoteView {
+        QuoteView {
+            quote: self.quote.clone(),
+            fetched_at: self.fetched_at,
+            age_ms: (now - self.fetched_at).num_milliseconds(),
+            stale,
+        }
+    }
+}
+
+// One slot per symbol; holding its lock across the fetch makes concurrent misses share one request
+type QuoteSlot = Arc<Mutex<Option<CachedQuote>>>;
+
+/// Read-through price board quotes, so browsers polling a symbol cost VCI one request per TTL
+pub struct QuoteService {
+    client: VciClient,
+    slots: Mutex<HashMap<String, QuoteSlot>>,
+    stats: Mutex<QuoteCacheStats>,
+}
+
+pub type SharedQuoteService = Arc<QuoteService>;
+
+impl QuoteService {
+    pub fn new(client: VciClient) -> Self {
+        Self { client, slots: Mutex::new(HashMap::new()), stats: Mutex::new(QuoteCacheStats::default()) }
+    }
+
+    async fn slot(&self, symbol: &str) -> QuoteSlot {
+        Arc::clone(self.slots.lock().await.entry(symbol.to_string()).or_default())
+    }
+
+    pub async fn get(&self, symbol: &str) -> Result<QuoteView, VciError> {
+        let symbol = symbol.to_uppercase();
+        let slot = self.slot(&symbol).await;
+        let mut cached = slot.lock().await;
+
+        let now = Utc::now();
+        if let Some(fresh) = cached.as_ref().filter(|c| now - c.fetched_at < Duration::seconds(QUOTE_TTL_SECS)) {
+            self.stats.lock().await.hits += 1;
+            return Ok(fresh.view(now, false));
+        }
+        self.stats.lock().await.misses += 1;
+
+        debug!(symbol, "Quote cache miss, fetching price board");
+        let fetched = self.client.price_board(std::slice::from_ref(&symbol)).await
+            .and_then(|quotes| quotes.into_iter().find(|q| q.symbol == symbol).ok_or(VciError::NoData));
+        let now = Utc::now();
+        match fetched {
+            Ok(quote) => {
+                let entry = cached.insert(CachedQuote { quote, fetched_at: now });
+                Ok(entry.view(now, false))
+            }
+            Err(e) => {
+                let mut stats = self.stats.lock().await;
+                stats.upstream_errors += 1;
+                match cached.as_ref().filter(|c| now - c.fetched_at < Duration::seconds(MAX_STALE_SECS)) {
+                    Some(last) if !matches!(e, VciError::NoData) => {
+                        warn!(symbol, error = ?e, "Price board fetch failed, serving last quote");
+                        stats.stale_served += 1;
+                        Ok(last.view(now, true))
+                    }
+                    _ => Err(e),
+                }
+            }
+        }
+    }
+
+    pub async fn stats(&self) -> QuoteCacheStats {
+        let entries = self.slots.lock().await.len();
+        QuoteCacheStats { entries, ..self.stats.lock().await.clone() }
+    }
+}
@@ -0,0 +1,294 @@
+use crate::aggregate::read_ticker_files;
+use crate::analysis::indicator_cache;
+use crate::analysis::indicators::IndicatorPoint;
+use crate::analysis::ma_score::MaScorePoint;
+use crate::analysis::strength::{self, StrengthPoint};
+use crate::backfill::parse_history_csv;
+use crate::export::{self, CsvColumn, CsvLayout, EnhancedRow, TableFormat};
+use crate::profile::{all_indicator_groups, IndicatorGroup};
+use crate::ticker_info::TickerDirectory;
+use crate::utils::market_time::market_date;
+use crate::vci::OhlcvData;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+const USAGE: &str = "Usage: aipriceaction-proxy recompute --indicator <ma_score,strength,technical|all> --from <YYYY-MM-DD> [--input <dir>] [--output <dir>] [--cache <file>] [--symbols VCB,FPT] [--format csv|parquet|arrow] [--chunk <tickers>] [--restart]";
+const PROGRESS_FILE: &str = "recompute.progress.json";
+const DEFAULT_CHUNK: usize = 50;
+
+/// Tickers finished by an interrupted run, kept in the output directory until the run completes
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Progress {
+    indicators: Vec<IndicatorGroup>,
+    from: NaiveDate,
+    completed: BTreeSet<String>,
+}
+
+fn read_progress(path: &Path) -> Result<Option<Progress>, String> {
+    match fs::read(path) {
+        Ok(content) => serde_json::from_slice(&content).map(Some).map_err(|e| e.to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// Temp file + rename, so an interruption mid-write leaves the previous checkpoint intact
+fn write_progress(path: &Path, progress: &Progress) -> Result<(), String> {
+    let content = serde_json::to_vec_pretty(progress).map_err(|e| e.to_string())?;
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, content).map_err(|e| e.to_string())?;
+    fs::rename(&tmp_path, path).map_err(|e| e.to_string())
+}
+
+fn parse_groups(value: &str) -> Option<Vec<IndicatorGroup>> {
+    let mut groups = Vec::new();
+    for name in value.split(',').map(|name| name.trim().to_lowercase()) {
+        match name.as_str() {
+            "all" => groups.extend(all_indicator_groups()),
+            "ma_score" => groups.push(IndicatorGroup::MaScore),
+            "strength" => groups.push(IndicatorGroup::Strength),
+            "technical" | "indicators" => groups.push(IndicatorGroup::Technical),
+            _ => return None,
+        }
+    }
+    // Canonical order, so the progress file of `strength,ma_score` matches `ma_score,strength`
+    let groups: Vec<IndicatorGroup> = all_indicator_groups().into_iter().filter(|group| groups.contains(group)).collect();
+    (!groups.is_empty()).then_some(groups)
+}
+
+/// Time and OHLCV, then the columns of each recomputed group
+fn layout(groups: &[IndicatorGroup]) -> CsvLayout {
+    let mut columns = vec![CsvColumn::Time, CsvColumn::Open, CsvColumn::High, CsvColumn::Low, CsvColumn::Close, CsvColumn::Volume];
+    for group in groups {
+        columns.extend_from_slice(match group {
+            IndicatorGroup::MaScore => &[
+                CsvColumn::Ma10, CsvColumn::Ma20, CsvColumn::Ma50, CsvColumn::Ma10Score, CsvColumn::Ma20Score, CsvColumn::Ma50Score,
+                CsvColumn::DaysAboveMa10, CsvColumn::DaysAboveMa20, CsvColumn::DaysAboveMa50,
+                CsvColumn::DaysBelowMa10, CsvColumn::DaysBelowMa20, CsvColumn::DaysBelowMa50, CsvColumn::TrendScore,
+            ][..],
+            IndicatorGroup::Strength => &[CsvColumn::Strength][..],
+            IndicatorGroup::Technical => &[
+                CsvColumn::Rsi14, CsvColumn::Macd, CsvColumn::MacdSignal, CsvColumn::MacdHistogram,
+                CsvColumn::BbUpper, CsvColumn::BbMiddle, CsvColumn::BbLower, CsvColumn::Vwap20,
+            ][..],
+        });
+    }
+    CsvLayout { columns, header: true }
+}
+
+/// The selected groups over the ticker's whole history, so every MA and smoothing is warmed up by
+/// `from`, keeping the rows dated `from` or later. Goes through the indicator cache, which is what
+/// `--cache` persists.
+pub fn recompute_ticker(ticker: &str, series: &[OhlcvData], groups: &[IndicatorGroup], from: NaiveDate) -> Vec<EnhancedRow> {
+    let scores = groups.contains(&IndicatorGroup::MaScore).then(|| indicator_cache::ma_scores(ticker, series));
+    let strengths = groups.contains(&IndicatorGroup::Strength).then(|| indicator_cache::strength(ticker, series, strength::weights()));
+    let indicators = groups.contains(&IndicatorGroup::Technical).then(|| indicator_cache::indicators(ticker, series));
+    let first = series.partition_point(|bar| market_date(bar.time) < from);
+    (first..series.len()).map(|i| {
+        let bar = &series[i];
+        let date = market_date(bar.time);
+        let mut blank = IndicatorPoint::default();
+        blank.date = date;
+        EnhancedRow {
+            bar: bar.clone(),
+            score: scores.as_ref().map_or_else(|| MaScorePoint { date, close: bar.close, ..Default::default() }, |scores| scores[i].clone()),
+            strength: strengths.as_ref().map_or_else(|| StrengthPoint { date, ..Default::default() }, |strengths| strengths[i].clone()),
+            indicators: indicators.as_ref().map_or(blank, |indicators| indicators[i].clone()),
+            averages: Default::default(),
+            foreign_net_value: None,
+            basis: None,
+            basis_pct: None,
+        }
+    }).collect()
+}
+
+fn format_duration(secs: u64) -> String {
+    if secs >= 3600 { format!("{}h{:02}m", secs / 3600, secs % 3600 / 60) } else { format!("{}m{:02}s", secs / 60, secs % 60) }
+}
+
+/// `recompute` subcommand: regenerates indicator history after a formula change from per-ticker daily
+/// CSVs (the `backfill` output). Tickers are processed in chunks; after each chunk the finished tickers
+/// are checkpointed in the output directory and `--cache` is saved, so a rerun after an interruption
+/// resumes where it stopped. Returns the exit code.
+pub fn run_cli(args: &[String]) -> i32 {
+    let mut groups: Option<Vec<IndicatorGroup>> = None;
+    let mut from: Option<NaiveDate> = None;
+    let mut input = PathBuf::from("backfill");
+    let mut output = PathBuf::from("recompute");
+    let mut cache: Option<PathBuf> = None;
+    let mut symbols: Option<HashSet<String>> = None;
+    let mut format = TableFormat::Csv;
+    let mut chunk = DEFAULT_CHUNK;
+    let mut restart = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--restart" {
+            restart = true;
+            continue;
+        }
+        match (arg.as_str(), iter.next()) {
+            ("--indicator", Some(value)) if parse_groups(value).is_some() => groups = parse_groups(value),
+            ("--from", Some(value)) if NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok() => from = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok(),
+            ("--input", Some(value)) => input = PathBuf::from(value),
+            ("--output", Some(value)) => output = PathBuf::from(value),
+            ("--cache", Some(value)) => cache = Some(PathBuf::from(value)),
+            ("--symbols", Some(value)) => symbols = Some(value.split(',').map(|s| s.trim().to_uppercase()).filter(|s| !s.is_empty()).collect()),
+            ("--format", Some(value)) if value.parse::<TableFormat>().is_ok() => format = value.parse().unwrap_or(TableFormat::Csv),
+            ("--chunk", Some(value)) if value.parse::<usize>().is_ok_and(|n| n > 0) => chunk = value.parse().unwrap_or(DEFAULT_CHUNK),
+            _ => {
+                eprintln!("{}", USAGE);
+                return 2;
+            }
+        }
+    }
+    let (Some(groups), Some(from)) = (groups, from) else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+
+    let files = match read_ticker_files(&input) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", input.display(), e);
+            return 1;
+        }
+    };
+    let files: Vec<_> = files.into_iter()
+        .filter(|file| symbols.as_ref().is_none_or(|symbols| symbols.contains(&file.ticker)))
+        .collect();
+    if files.is_empty() {
+        eprintln!("No ticker files in {}", input.display());
+        return 1;
+    }
+    if let Err(e) = fs::create_dir_all(&output) {
+        eprintln!("Failed to create {}: {}", output.display(), e);
+        return 1;
+    }
+
+    let progress_path = output.join(PROGRESS_FILE);
+    let mut progress = match read_progress(&progress_path) {
+        Ok(Some(progress)) if restart => {
+            println!("Discarding progress of {} tickers", progress.completed.len());
+            Progress { indicators: groups.clone(), from, completed: BTreeSet::new() }
+        }
+        Ok(Some(progress)) if progress.indicators != groups || progress.from != from => {
+            eprintln!(
+                "{} is from a run of {:?} from {}; rerun with those options to resume, or pass --restart",
+                progress_path.display(), progress.indicators, progress.from,
+            );
+            return 1;
+        }
+        Ok(Some(progress)) => {
+            println!("Resuming: {} tickers already done", progress.completed.len());
+            progress
+        }
+        Ok(None) => Progress { indicators: groups.clone(), from, completed: BTreeSet::new() },
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", progress_path.display(), e);
+            return 1;
+        }
+    };
+    // Results of a formula version the cache was not written with are dropped on load and recomputed
+    indicator_cache::init(cache.clone());
+
+    let pending: Vec<_> = files.iter().filter(|file| !progress.completed.contains(&file.ticker)).collect();
+    let total = files.len();
+    let layout = layout(&groups);
+    let started = Instant::now();
+    let mut done = 0;
+    let mut rows_written = 0;
+    println!("Recomputing {:?} from {} for {} tickers ({} left) in chunks of {}", groups, from, total, pending.len(), chunk);
+    for tickers in pending.chunks(chunk) {
+        for file in tickers {
+            let series = parse_history_csv(&file.content, NaiveDate::MIN, NaiveDate::MAX);
+            let rows = recompute_ticker(&file.ticker, &series, &groups, from);
+            rows_written += rows.len();
+            let path = output.join(format!("{}.{}", file.ticker, format.extension()));
+            let written = export::encode_enhanced(&BTreeMap::from([(file.ticker.clone(), rows)]), &layout, &TickerDirectory::default(), format)
+                .and_then(|content| fs::write(&path, content).map_err(|e| e.to_string()));
+            if let Err(e) = written {
+                eprintln!("Failed to write {}: {}", path.display(), e);
+                return 1;
+            }
+            progress.completed.insert(file.ticker.clone());
+        }
+        if let Err(e) = indicator_cache::save() {
+            eprintln!("Failed to save indicator cache: {}", e);
+            return 1;
+        }
+        if let Err(e) = write_progress(&progress_path, &progress) {
+            eprintln!("Failed to write {}: {}", progress_path.display(), e);
+            return 1;
+        }
+        done += tickers.len();
+        let elapsed = started.elapsed().as_secs_f64();
+        let eta = elapsed / done as f64 * (pending.len() - done) as f64;
+        println!(
+            "[{}/{}] {:.1}% elapsed {} eta {}",
+            progress.completed.len(), total, progress.completed.len() as f64 * 100.0 / total as f64,
+            format_duration(elapsed as u64), format_duration(eta as u64),
+        );
+    }
+
+    if let Err(e) = fs::remove_file(&progress_path)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        eprintln!("Failed to remove {}: {}", progress_path.display(), e);
+    }
+    println!("Wrote {} rows for {} tickers to {}", rows_written, done, output.display());
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recompute_resumes_and_keeps_warm_up() {
+        let dir = std::env::temp_dir().join(format!("recompute_test_{}", std::process::id()));
+        let (input, output) = (dir.join("input"), dir.join("output"));
+        fs::create_dir_all(&input).unwrap();
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let history: String = (0..60)
+            .map(|day| format!("{},10,11,9,{},1000\n", start + chrono::Duration::days(day), 10.0 + day as f64))
+            .collect();
+        for ticker in ["RCA", "RCB"] {
+            fs::write(input.join(format!("{}.csv", ticker)), format!("time,open,high,low,close,volume\n{}", history)).unwrap();
+        }
+        let from = start + chrono::Duration::days(55);
+        let args = |extra: &[&str]| -> Vec<String> {
+            ["--indicator", "ma_score", "--from", "2024-02-25", "--input", input.to_str().unwrap(), "--output", output.to_str().unwrap()]
+                .iter().chain(extra).map(|arg| arg.to_string()).collect()
+        };
+
+        // Only the MA columns, from `from` on, with MA50 already warmed up by the earlier history
+        let series = parse_history_csv(&fs::read_to_string(input.join("RCA.csv")).unwrap(), NaiveDate::MIN, NaiveDate::MAX);
+        let rows = recompute_ticker("RCA", &series, &[IndicatorGroup::MaScore], from);
+        assert_eq!(rows.len(), 5);
+        assert!(rows[0].score.ma50.is_some() && rows[0].strength.strength.is_none());
+
+        // An interrupted run finished RCA; the rerun only writes RCB and then clears the checkpoint
+        fs::create_dir_all(&output).unwrap();
+        let progress = Progress { indicators: vec![IndicatorGroup::MaScore], from, completed: BTreeSet::from(["RCA".to_string()]) };
+        write_progress(&output.join(PROGRESS_FILE), &progress).unwrap();
+        assert_eq!(run_cli(&args(&["--chunk", "1"])), 0);
+        assert!(!output.join("RCA.csv").exists());
+        let content = fs::read_to_string(output.join("RCB.csv")).unwrap();
+        assert_eq!(content.lines().count(), 6);
+        assert!(content.starts_with("time,open,high,low,close,volume,ma10,"));
+        assert!(!output.join(PROGRESS_FILE).exists());
+
+        // A checkpoint of different options is not resumed unless restarting
+        write_progress(&output.join(PROGRESS_FILE), &Progress { from: start, ..progress }).unwrap();
+        assert_eq!(run_cli(&args(&[])), 1);
+        assert_eq!(run_cli(&args(&["--restart"])), 0);
+        assert!(output.join("RCA.csv").exists());
+        assert_eq!(run_cli(&args(&["--indicator", "bogus"])), 2);
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,383 @@
+use crate::aggregate::{column_index, read_ticker_files};
+use crate::analysis::indicators::{self, IndicatorPoint};
+use crate::analysis::ma_score::{self, MaScorePoint};
+use crate::backfill::parse_history_csv;
+use crate::data_structures::merge_and_track_changes;
+use crate::utils::corrections::CorrectionSource;
+use crate::utils::market_time::{market_date, market_day_start, MARKET_TIMEZONE};
+use crate::utils::merge_policy::{self, BarOrigin};
+use crate::vci::OhlcvData;
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+const USAGE: &str = "Usage: aipriceaction-proxy replay --date <YYYY-MM-DD> [--input <dir>] [--intraday <dir>] [--output <file>] [--symbols VCB,FPT]";
+const CSV_HEADER: &str = "time,symbol,source,open,high,low,close,volume,ma10,ma20,ma50,ma10_score,ma20_score,ma50_score,rsi14,macd,macd_signal,macd_histogram,bb_upper,bb_middle,bb_lower,flips";
+// Trading sessions (ICT) synthesized slices are spread over, as in `Interval::bars_per_session`
+const SESSIONS: [(u32, u32); 2] = [(9 * 60, 11 * 60 + 30), (13 * 60, 15 * 60)];
+
+/// One ticker's inputs for the replayed day
+#[derive(Clone, Debug, Default)]
+pub struct TickerDay {
+    pub history: Vec<OhlcvData>,       // Daily bars before the day, the cache as of the prior close
+    pub candles: Vec<OhlcvData>,       // Time-sorted minute candles of the day
+    pub synthesized: bool,             // Candles were made up from the official bar, not archived
+    pub official: Option<OhlcvData>,   // The day's final daily bar, merged after the session
+}
+
+/// Indicator state after one merge
+#[derive(Clone, Debug)]
+pub struct ReplayRow {
+    pub time: DateTime<Utc>, // End of the candle merged, or the session close for the official bar
+    pub symbol: String,
+    pub source: &'static str, // archived, synthesized or official
+    pub bar: OhlcvData,       // The day's daily bar as stored after the merge
+    pub score: MaScorePoint,
+    pub indicators: IndicatorPoint,
+    pub flips: Vec<&'static str>, // Signals that changed side since the previous row (or the prior close)
+}
+
+/// Parse a market-local (`2024-04-15 09:15:00`) or RFC 3339 candle timestamp
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    let local = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())?;
+    MARKET_TIMEZONE.from_local_datetime(&local).single().map(|time| time.with_timezone(&Utc))
+}
+
+/// Minute candles from an archived intraday CSV (`time` plus OHLCV columns) on market date `date`
+pub fn parse_intraday_csv(content: &str, date: NaiveDate) -> Vec<OhlcvData> {
+    let mut lines = content.lines();
+    let Some(header) = lines.next() else { return Vec::new() };
+    let columns: Vec<&str> = header.split(',').collect();
+    let indices = ["time|date", "open", "high", "low", "close", "volume"]
+        .map(|names| column_index(&columns, &names.split('|').collect::<Vec<_>>()));
+    let [Some(time), Some(open), Some(high), Some(low), Some(close), Some(volume)] = indices else { return Vec::new() };
+
+    let mut candles: Vec<OhlcvData> = lines
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let price = |index: usize| fields.get(index)?.parse::<f64>().ok();
+            Some(OhlcvData {
+                time: parse_time(fields.get(time)?)?,
+                open: price(open)?,
+                high: price(high)?,
+                low: price(low)?,
+                close: price(close)?,
+                volume: fields.get(volume)?.parse::<f64>().ok()? as u64,
+                symbol: None,
+            })
+        })
+        .filter(|candle| market_date(candle.time) == date)
+        .collect();
+    candles.sort_by_key(|candle| candle.time);
+    candles.dedup_by_key(|candle| candle.time);
+    candles
+}
+
+/// One-minute candles across the trading sessions that rebuild `bar` exactly: the price moves linearly
+/// open → low → high → close on up days (open → high → low → close otherwise) and volume trades evenly.
+pub fn synthesize_candles(bar: &OhlcvData, date: NaiveDate) -> Vec<OhlcvData> {
+    let minutes: Vec<u32> = SESSIONS.iter().flat_map(|(start, end)| *start..*end).collect();
+    let waypoints = if bar.close >= bar.open { [bar.open, bar.low, bar.high, bar.close] } else { [bar.open, bar.high, bar.low, bar.close] };
+    let total = minutes.len();
+    let price_at = |step: usize| {
+        let position = step as f64 * 3.0 / total as f64;
+        let leg = (position as usize).min(2);
+        waypoints[leg] + (waypoints[leg + 1] - waypoints[leg]) * (position - leg as f64)
+    };
+    let volume_at = |step: usize| (bar.volume as u128 * step as u128 / total as u128) as u64;
+
+    minutes.iter().enumerate().filter_map(|(step, minute)| {
+        let local = date.and_time(NaiveTime::from_hms_opt(minute / 60, minute % 60, 0)?);
+        let (open, close) = (price_at(step), price_at(step + 1));
+        Some(OhlcvData {
+            time: MARKET_TIMEZONE.from_local_datetime(&local).single()?.with_timezone(&Utc),
+            open,
+            high: open.max(close),
+            low: open.min(close),
+            close,
+            volume: volume_at(step + 1) - volume_at(step),
+            symbol: None,
+        })
+    }).collect()
+}
+
+// Reads one signal's value out of a session's indicator state
+type SignalValue = fn(&MaScorePoint, &IndicatorPoint) -> Option<f64>;
+
+/// Signals on opposite sides in `previous` and `current`: price vs each MA, the MACD histogram,
+/// and RSI entering or leaving overbought (70) and oversold (30)
+fn flips(previous: (&MaScorePoint, &IndicatorPoint), current: (&MaScorePoint, &IndicatorPoint)) -> Vec<&'static str> {
+    let side = |value: Option<f64>, level: f64| value.map(|v| v > level);
+    let signals: [(&'static str, SignalValue, f64); 6] = [
+        ("ma10", |score, _| score.ma10_score, 0.0),
+        ("ma20", |score, _| score.ma20_score, 0.0),
+        ("ma50", |score, _| score.ma50_score, 0.0),
+        ("macd", |_, indicators| indicators.macd_histogram, 0.0),
+        ("rsi70", |_, indicators| indicators.rsi14, 70.0),
+        ("rsi30", |_, indicators| indicators.rsi14, 30.0),
+    ];
+    signals.into_iter()
+        .filter(|(_, value, level)| {
+            let (before, after) = (side(value(previous.0, previous.1), *level), side(value(current.0, current.1), *level));
+            before.is_some() && after.is_some() && before != after
+        })
+        .map(|(name, _, _)| name)
+        .collect()
+}
+
+// A ticker's cache and indicator series while its day is replayed
+struct ReplayState {
+    series: Vec<OhlcvData>,
+    scores: Vec<MaScorePoint>,
+    indicators: Vec<IndicatorPoint>,
+    last: Option<(MaScorePoint, IndicatorPoint)>,
+    session: Option<OhlcvData>, // Candles merged so far, folded into the day's bar
+}
+
+impl ReplayState {
+    fn new(history: Vec<OhlcvData>) -> Self {
+        let scores = ma_score::calculate_ma_scores(&history);
+        let indicators = indicators::calculate_indicators(&history);
+        let last = scores.last().cloned().zip(indicators.last().cloned());
+        Self { series: history, scores, indicators, last, session: None }
+    }
+
+    /// Merge a daily bar the way the core worker merges a fetch, then recalculate from its date
+    fn merge(&mut self, symbol: &str, date: NaiveDate, bar: OhlcvData, observed_at: DateTime<Utc>, origin: BarOrigin) -> Option<(OhlcvData, MaScorePoint, IndicatorPoint, Vec<&'static str>)> {
+        let admitted = merge_policy::admit_bars(symbol, vec![bar], observed_at, |_| origin);
+        if admitted.is_empty() {
+            return None;
+        }
+        merge_and_track_changes(symbol, &mut self.series, admitted, CorrectionSource::Vci);
+        let first_changed = self.series.partition_point(|bar| market_date(bar.time) < date);
+        self.scores = ma_score::update_ma_scores(&self.series, &self.scores, first_changed);
+        self.indicators = indicators::update_indicators(&self.series, &self.indicators, first_changed);
+
+        let bar = self.series.get(first_changed)?.clone();
+        let current = (self.scores.get(first_changed)?.clone(), self.indicators.get(first_changed)?.clone());
+        let changed = self.last.as_ref().map_or_else(Vec::new, |last| flips((&last.0, &last.1), (&current.0, &current.1)));
+        self.last = Some(current.clone());
+        Some((bar, current.0, current.1, changed))
+    }
+}
+
+/// Replay `date` minute by minute: every candle is folded into the day's bar, which goes through the
+/// live merge path like a fetch during the session would, and the official bar is merged after the
+/// close. Returns the indicator state after each merge, in time order.
+pub fn replay_day(date: NaiveDate, days: BTreeMap<String, TickerDay>) -> Vec<ReplayRow> {
+    let session_close = MARKET_TIMEZONE
+        .from_local_datetime(&date.and_hms_opt(15, 0, 0).unwrap_or_default())
+        .single()
+        .map_or_else(|| market_day_start(date), |time| time.with_timezone(&Utc));
+    let mut timeline: BTreeMap<DateTime<Utc>, Vec<(String, OhlcvData)>> = BTreeMap::new();
+    let mut states: BTreeMap<String, (ReplayState, &'static str, Option<OhlcvData>)> = BTreeMap::new();
+    for (symbol, day) in days {
+        for candle in day.candles {
+            timeline.entry(candle.time).or_default().push((symbol.clone(), candle));
+        }
+        let source = if day.synthesized { "synthesized" } else { "archived" };
+        states.insert(symbol, (ReplayState::new(day.history), source, day.official));
+    }
+
+    let mut rows = Vec::new();
+    for (time, candles) in timeline {
+        // A fetch during the session observes each candle once it closed
+        let observed_at = time + Duration::minutes(1);
+        for (symbol, candle) in candles {
+            let Some((state, source, _)) = states.get_mut(&symbol) else { continue };
+            let bar = match state.session.take() {
+                Some(session) => OhlcvData {
+                    high: session.high.max(candle.high),
+                    low: session.low.min(candle.low),
+                    close: candle.close,
+                    volume: session.volume + candle.volume,
+                    ..session
+                },
+                None => OhlcvData { time: market_day_start(date), symbol: None, ..candle },
+            };
+            state.session = Some(bar.clone());
+            let origin = merge_policy::fetched_origin(date, Some(date));
+            if let Some((bar, score, indicators, flips)) = state.merge(&symbol, date, bar, observed_at, origin) {
+                rows.push(ReplayRow { time: observed_at, symbol: symbol.clone(), source, bar, score, indicators, flips });
+            }
+        }
+    }
+    for (symbol, (state, _, official)) in &mut states {
+        let Some(official) = official.take() else { continue };
+        let bar = OhlcvData { time: market_day_start(date), symbol: None, ..official };
+        if let Some((bar, score, indicators, flips)) = state.merge(symbol, date, bar, session_close, BarOrigin::Official) {
+            rows.push(ReplayRow { time: session_close, symbol: symbol.clone(), source: "official", bar, score, indicators, flips });
+        }
+    }
+    rows
+}
+
+fn format_optional(value: Option<f64>) -> String {
+    value.map(|v| format!("{:.4}", v)).unwrap_or_default()
+}
+
+/// Replay rows as CSV, times in market-local RFC 3339
+pub fn to_csv(rows: &[ReplayRow]) -> String {
+    let mut output = format!("{}\n", CSV_HEADER);
+    for row in rows {
+        let optional = [
+            row.score.ma10, row.score.ma20, row.score.ma50, row.score.ma10_score, row.score.ma20_score, row.score.ma50_score,
+            row.indicators.rsi14, row.indicators.macd, row.indicators.macd_signal, row.indicators.macd_histogram,
+            row.indicators.bb_upper, row.indicators.bb_middle, row.indicators.bb_lower,
+        ];
+        output.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{}\n",
+            row.time.with_timezone(&MARKET_TIMEZONE).to_rfc3339(),
+            row.symbol,
+            row.source,
+            row.bar.open,
+            row.bar.high,
+            row.bar.low,
+            row.bar.close,
+            row.bar.volume,
+            optional.map(format_optional).join(","),
+            row.flips.join(" "),
+        ));
+    }
+    output
+}
+
+/// `replay` subcommand: rebuilds each ticker's cache as of the prior close from per-ticker daily CSVs
+/// (the `backfill` output), replays the day from archived minute candles where `--intraday` has them
+/// and synthesized ones otherwise, and writes the indicator evolution as CSV. Returns the exit code.
+pub fn run_cli(args: &[String]) -> i32 {
+    let mut date: Option<NaiveDate> = None;
+    let mut input = PathBuf::from("backfill");
+    let mut intraday: Option<PathBuf> = None;
+    let mut output: Option<PathBuf> = None;
+    let mut symbols: Option<HashSet<String>> = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--date", Some(value)) if NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok() => date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok(),
+            ("--input", Some(value)) => input = PathBuf::from(value),
+            ("--intraday", Some(value)) => intraday = Some(PathBuf::from(value)),
+            ("--output", Some(value)) => output = Some(PathBuf::from(value)),
+            ("--symbols", Some(value)) => symbols = Some(value.split(',').map(|s| s.trim().to_uppercase()).filter(|s| !s.is_empty()).collect()),
+            _ => {
+                eprintln!("{}", USAGE);
+                return 2;
+            }
+        }
+    }
+    let Some(date) = date else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+    let output = output.unwrap_or_else(|| PathBuf::from(format!("replay_{}.csv", date)));
+    let wanted = |ticker: &str| symbols.as_ref().is_none_or(|symbols| symbols.contains(ticker));
+
+    let history_files = match read_ticker_files(&input) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", input.display(), e);
+            return 1;
+        }
+    };
+    let mut archived: BTreeMap<String, Vec<OhlcvData>> = BTreeMap::new();
+    if let Some(intraday) = &intraday {
+        match read_ticker_files(intraday) {
+            Ok(files) => archived.extend(files.into_iter()
+                .filter(|file| wanted(&file.ticker))
+                .map(|file| (file.ticker, parse_intraday_csv(&file.content, date)))
+                .filter(|(_, candles)| !candles.is_empty())),
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", intraday.display(), e);
+                return 1;
+            }
+        }
+    }
+
+    let mut days: BTreeMap<String, TickerDay> = BTreeMap::new();
+    for file in history_files.into_iter().filter(|file| wanted(&file.ticker)) {
+        let bars = parse_history_csv(&file.content, NaiveDate::MIN, date);
+        let (history, official): (Vec<OhlcvData>, Vec<OhlcvData>) = bars.into_iter().partition(|bar| market_date(bar.time) < date);
+        let official = official.into_iter().next();
+        let day = match (archived.remove(&file.ticker), &official) {
+            (Some(candles), _) => TickerDay { history, candles, synthesized: false, official },
+            (None, Some(bar)) => TickerDay { history, candles: synthesize_candles(bar, date), synthesized: true, official },
+            (None, None) => continue,
+        };
+        days.insert(file.ticker, day);
+    }
+    // Archived candles of a ticker without daily history replay onto an empty cache
+    days.extend(archived.into_iter().map(|(ticker, candles)| (ticker, TickerDay { candles, ..TickerDay::default() })));
+    if days.is_empty() {
+        eprintln!("No bars on {} in {}", date, input.display());
+        return 1;
+    }
+
+    let synthesized = days.values().filter(|day| day.synthesized).count();
+    println!("Replaying {} for {} tickers ({} from archived candles, {} synthesized)", date, days.len(), days.len() - synthesized, synthesized);
+    let rows = replay_day(date, days);
+    for row in rows.iter().filter(|row| !row.flips.is_empty()) {
+        println!("{} {} {} flipped: {}", row.time.with_timezone(&MARKET_TIMEZONE).format("%H:%M"), row.symbol, row.source, row.flips.join(", "));
+    }
+    if let Err(e) = fs::write(&output, to_csv(&rows)) {
+        eprintln!("Failed to write {}: {}", output.display(), e);
+        return 1;
+    }
+    println!("Wrote {} rows to {}", rows.len(), output.display());
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn daily(date: NaiveDate, close: f64) -> OhlcvData {
+        OhlcvData { time: market_day_start(date), open: close, high: close, low: close, close, volume: 1000, symbol: None }
+    }
+
+    #[test]
+    fn test_replay_rebuilds_the_day_and_reports_flips() {
+        let date = NaiveDate::from_ymd_opt(2024, 4, 15).unwrap();
+        let official = OhlcvData { time: market_day_start(date), open: 10.0, high: 12.0, low: 9.0, close: 11.0, volume: 1001, symbol: None };
+
+        // Synthesized candles rebuild the official bar exactly, up days dipping to the low first
+        let candles = synthesize_candles(&official, date);
+        assert_eq!(candles.len(), 270);
+        assert_eq!(candles[0].time.with_timezone(&MARKET_TIMEZONE).format("%H:%M").to_string(), "09:00");
+        assert_eq!(candles[150].time.with_timezone(&MARKET_TIMEZONE).format("%H:%M").to_string(), "13:00");
+        assert_eq!(candles.iter().map(|c| c.volume).sum::<u64>(), 1001);
+        assert_eq!(candles.iter().map(|c| c.high).fold(f64::MIN, f64::max), 12.0);
+        assert_eq!(candles.iter().map(|c| c.low).fold(f64::MAX, f64::min), 9.0);
+        assert!((candles[269].close - 11.0).abs() < 1e-9);
+        assert!(candles[10].close < 10.0);
+
+        let archived = "time,open,high,low,close,volume\n\
+            2024-04-15 09:01:00,10,10.5,10,10.5,10\n\
+            2024-04-15 09:00:00,10,10,9.5,10,20\n\
+            2024-04-15T02:05:00Z,10.5,11,10.5,11,5\n\
+            2024-04-16 09:00:00,1,1,1,1,1\n";
+        let parsed = parse_intraday_csv(archived, date);
+        assert_eq!(parsed.iter().map(|c| c.volume).collect::<Vec<_>>(), vec![20, 10, 5]);
+
+        // Sixty flat sessions at 9, so the first trade at 10 lifts price over every MA, MACD and RSI above 70
+        let history: Vec<OhlcvData> = (1..=60).map(|day| daily(date - Duration::days(61 - day), 9.0)).collect();
+        let mut days = BTreeMap::new();
+        days.insert("REPLAY".to_string(), TickerDay { history, candles: parsed, synthesized: false, official: Some(official) });
+        let rows = replay_day(date, days);
+        assert_eq!(rows.iter().map(|row| row.source).collect::<Vec<_>>(), vec!["archived", "archived", "archived", "official"]);
+        assert_eq!(rows[0].bar.close, 10.0);
+        assert_eq!(rows[0].flips, vec!["ma10", "ma20", "ma50", "macd", "rsi70"]);
+        assert_eq!((rows[2].bar.low, rows[2].bar.high, rows[2].bar.volume), (9.5, 11.0, 35));
+        assert!(rows[1].flips.is_empty());
+        assert_eq!((rows[3].bar.high, rows[3].bar.volume), (12.0, 1001));
+        assert_eq!(rows[3].score.date, date);
+        assert_eq!(to_csv(&rows).lines().count(), 5);
+        assert!(to_csv(&rows).lines().nth(1).unwrap().starts_with("2024-04-15T09:01:00+07:00,REPLAY,archived,10,10,9.5,10,20,"));
+    }
+}
@@ -0,0 +1,164 @@
+use serde_json::{json, Map, Value};
+
+// JSON type of one response field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    Date,           // Market date, YYYY-MM-DD
+    Number,
+    NullableNumber, // null until the series is long enough
+    Integer,
+    NullableInteger, // null until the series is long enough, in strict warm-up
+    NullableString,
+}
+
+impl FieldType {
+    fn schema(self) -> Value {
+        match self {
+            FieldType::Date => json!({ "type": "string", "format": "date" }),
+            FieldType::Number => json!({ "type": "number" }),
+            FieldType::NullableNumber => json!({ "type": ["number", "null"] }),
+            FieldType::Integer => json!({ "type": "integer", "minimum": 0 }),
+            FieldType::NullableInteger => json!({ "type": ["integer", "null"], "minimum": 0 }),
+            FieldType::NullableString => json!({ "type": ["string", "null"] }),
+        }
+    }
+}
+
+/// Fields of one enhanced `/tickers` row, in output order. This is the contract with frontends:
+/// renaming or retyping a field of `EnhancedRow::to_json` must change this table too.
+pub const ENHANCED_ROW_FIELDS: &[(&str, FieldType)] = &[
+    ("time", FieldType::Date),
+    ("open", FieldType::Number),
+    ("high", FieldType::Number),
+    ("low", FieldType::Number),
+    ("close", FieldType::Number),
+    ("volume", FieldType::Integer),
+    ("symbol", FieldType::NullableString),
+    ("ma10", FieldType::NullableNumber),
+    ("ma20", FieldType::NullableNumber),
+    ("ma50", FieldType::NullableNumber),
+    ("ma10_score", FieldType::NullableNumber),
+    ("ma20_score", FieldType::NullableNumber),
+    ("ma50_score", FieldType::NullableNumber),
+    ("consecutive_days_above_ma10", FieldType::NullableInteger),
+    ("consecutive_days_above_ma20", FieldType::NullableInteger),
+    ("consecutive_days_above_ma50", FieldType::NullableInteger),
+    ("consecutive_days_below_ma10", FieldType::NullableInteger),
+    ("consecutive_days_below_ma20", FieldType::NullableInteger),
+    ("consecutive_days_below_ma50", FieldType::NullableInteger),
+    ("trend_score", FieldType::NullableNumber),
+    ("strength", FieldType::NullableNumber),
+    ("rsi14", FieldType::NullableNumber),
+    ("macd", FieldType::NullableNumber),
+    ("macd_signal", FieldType::NullableNumber),
+    ("macd_histogram", FieldType::NullableNumber),
+    ("bb_upper", FieldType::NullableNumber),
+    ("bb_middle", FieldType::NullableNumber),
+    ("bb_lower", FieldType::NullableNumber),
+    ("vwap20", FieldType::NullableNumber),
+    ("foreign_net_value", FieldType::NullableNumber),
+    ("basis", FieldType::NullableNumber),
+    ("basis_pct", FieldType::NullableNumber),
+];
+
+// `age_ms` only on each symbol's latest row when no date range was requested, `anchored_vwap`
+// only when `vwap_anchor` was
+const ENHANCED_ROW_OPTIONAL_FIELDS: &[(&str, FieldType)] = &[("age_ms", FieldType::Integer), ("anchored_vwap", FieldType::NullableNumber)];
+
+/// JSON Schema (draft 2020-12) of the `/tickers?enhanced=true` response, served at `/schema/enhanced.json`
+pub fn enhanced_schema() -> Value {
+    let properties: Map<String, Value> = ENHANCED_ROW_FIELDS.iter()
+        .chain(ENHANCED_ROW_OPTIONAL_FIELDS)
+        .map(|(name, field_type)| (name.to_string(), field_type.schema()))
+        .collect();
+    let required: Vec<&str> = ENHANCED_ROW_FIELDS.iter().map(|(name, _)| *name).collect();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": "/schema/enhanced.json",
+        "title": "Enhanced tickers response",
+        "description": "GET /tickers?enhanced=true. Numbers are rounded unless precision=full.",
+        "type": "object",
+        "required": ["meta", "data"],
+        "additionalProperties": false,
+        "properties": {
+            "meta": {
+                "type": "object",
+                "required": ["snapshot_version", "built_at", "age_secs", "data_ready"],
+                "additionalProperties": false,
+                "properties": {
+                    "snapshot_version": { "type": "integer", "minimum": 0 },
+                    "built_at": { "type": "string", "format": "date-time" },
+                    "age_secs": { "type": "integer" },
+                    "data_ready": { "type": "boolean" },
+                },
+            },
+            "data": {
+                "type": "object",
+                "description": "Rows per symbol, oldest first",
+                "additionalProperties": { "type": "array", "items": { "$ref": "#/$defs/enhancedRow" } },
+            },
+        },
+        "$defs": {
+            "enhancedRow": {
+                "type": "object",
+                "required": required,
+                "additionalProperties": false,
+                "properties": properties,
+                // The deployment profile's extra moving averages, e.g. ema20 and ema20_score
+                "patternProperties": { "^(sma|ema)[0-9]+(_score)?$": FieldType::NullableNumber.schema() },
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::enhance_series;
+    use crate::vci::OhlcvData;
+    use crate::utils::test_bars::bar;
+    use chrono::NaiveDate;
+
+    fn matches(field_type: FieldType, value: &Value) -> bool {
+        match field_type {
+            FieldType::Date => value.as_str().is_some_and(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok()),
+            FieldType::Number => value.is_number(),
+            FieldType::NullableNumber => value.is_number() || value.is_null(),
+            FieldType::Integer => value.is_u64(),
+            FieldType::NullableInteger => value.is_u64() || value.is_null(),
+            FieldType::NullableString => value.is_string() || value.is_null(),
+        }
+    }
+
+    #[test]
+    fn test_enhanced_rows_match_schema() {
+        let series: Vec<OhlcvData> = (0..60).map(|day| OhlcvData {
+            open: 100.0 + (day % 7) as f64,
+            high: 102.0 + (day % 7) as f64,
+            low: 99.0 + (day % 5) as f64,
+            symbol: Some("SCHEMA".to_string()),
+            ..bar(day, 101.0 + (day % 6) as f64, 1000 + day as u64)
+        }).collect();
+        let rows = enhance_series("SCHEMA", &series, |_| true);
+        // The first row has no indicators yet, the last one has all of them
+        for row in [rows.first().unwrap(), rows.last().unwrap()] {
+            let json = row.to_json();
+            let object = json.as_object().unwrap();
+            let mut keys: Vec<&str> = object.keys().map(String::as_str).collect();
+            let mut expected: Vec<&str> = ENHANCED_ROW_FIELDS.iter().map(|(name, _)| *name).collect();
+            keys.sort();
+            expected.sort();
+            assert_eq!(keys, expected, "enhanced row fields changed; update ENHANCED_ROW_FIELDS and the frontend");
+            for (name, field_type) in ENHANCED_ROW_FIELDS {
+                assert!(matches(*field_type, &object[*name]), "{} is {} but the schema says {:?}", name, object[*name], field_type);
+            }
+        }
+        assert!(rows.last().unwrap().to_json()["rsi14"].is_number());
+
+        let schema = enhanced_schema();
+        let row_schema = &schema["$defs"]["enhancedRow"];
+        assert_eq!(row_schema["required"].as_array().unwrap().len(), ENHANCED_ROW_FIELDS.len());
+        assert_eq!(row_schema["properties"]["age_ms"]["type"], "integer");
+        assert_eq!(row_schema["properties"]["ma20_score"]["type"], json!(["number", "null"]));
+    }
+}
@@ -0,0 +1,103 @@
+use crate::utils::transitions;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+// Public node role while the core network is down or back
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StandbyMode {
+    #[default]
+    Mirroring, // Syncing from the core; the VCI fetcher is ready but paused
+    Promoted,  // Core unreachable past the threshold; fetching directly from VCI
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StandbyTransition {
+    Promote,
+    Demote,
+}
+
+/// Reported in `/health` for public nodes with standby enabled
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct StandbyStatus {
+    pub mode: StandbyMode,
+    pub promote_after_secs: u64,
+    pub core_unreachable_secs: u64, // 0 while the core answers
+    pub promotions: u64,
+}
+
+/// Tracks core reachability and decides when a public node promotes itself
+/// to fetch from VCI, and when it hands back to the core
+pub struct StandbyMonitor {
+    promote_after: Duration,
+    last_core_success: Instant,
+    mode: StandbyMode,
+    promotions: u64,
+}
+
+impl StandbyMonitor {
+    pub fn new(promote_after: Duration, now: Instant) -> Self {
+        // Startup counts as contact so a node never promotes before its first sync attempt
+        Self { promote_after, last_core_success: now, mode: StandbyMode::Mirroring, promotions: 0 }
+    }
+
+    pub fn mode(&self) -> StandbyMode {
+        self.mode
+    }
+
+    pub fn record_core_success(&mut self, now: Instant) -> Option<StandbyTransition> {
+        self.last_core_success = now;
+        if self.mode == StandbyMode::Promoted {
+            self.mode = StandbyMode::Mirroring;
+            info!("Core network reachable again, demoting to mirroring");
+            transitions::record("standby", "promoted", "mirroring", "core network reachable again");
+            return Some(StandbyTransition::Demote);
+        }
+        None
+    }
+
+    pub fn record_core_failure(&mut self, now: Instant) -> Option<StandbyTransition> {
+        let unreachable = now.saturating_duration_since(self.last_core_success);
+        if self.mode == StandbyMode::Mirroring && unreachable >= self.promote_after {
+            self.mode = StandbyMode::Promoted;
+            self.promotions += 1;
+            warn!(unreachable_secs = unreachable.as_secs(), promotions = self.promotions, "Core network unreachable, promoting to fetch from VCI");
+            transitions::record("standby", "mirroring", "promoted", format!("core network unreachable for {}s", unreachable.as_secs()));
+            return Some(StandbyTransition::Promote);
+        }
+        None
+    }
+
+    pub fn status(&self, now: Instant, core_reachable: bool) -> StandbyStatus {
+        StandbyStatus {
+            mode: self.mode,
+            promote_after_secs: self.promote_after.as_secs(),
+            core_unreachable_secs: if core_reachable { 0 } else { now.saturating_duration_since(self.last_core_success).as_secs() },
+            promotions: self.promotions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_promotes_after_threshold_and_demotes_on_recovery() {
+        let start = Instant::now();
+        let mut monitor = StandbyMonitor::new(Duration::from_secs(300), start);
+
+        assert_eq!(monitor.record_core_failure(start + Duration::from_secs(120)), None);
+        assert_eq!(monitor.record_core_failure(start + Duration::from_secs(300)), Some(StandbyTransition::Promote));
+        // Further failures keep the node promoted without re-triggering
+        assert_eq!(monitor.record_core_failure(start + Duration::from_secs(360)), None);
+        assert_eq!(monitor.mode(), StandbyMode::Promoted);
+
+        let back = start + Duration::from_secs(420);
+        assert_eq!(monitor.record_core_success(back), Some(StandbyTransition::Demote));
+        assert_eq!(monitor.record_core_failure(back + Duration::from_secs(60)), None);
+        let status = monitor.status(back + Duration::from_secs(60), false);
+        assert_eq!((status.mode, status.core_unreachable_secs, status.promotions), (StandbyMode::Mirroring, 60, 1));
+    }
+}
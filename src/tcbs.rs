@@ -2,9 +2,13 @@ use reqwest::{Client, Error as ReqwestError};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::time::{Duration, SystemTime};
+use std::time::Duration;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
 use tokio::time::sleep;
 use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use crate::analysis::basis;
+use crate::utils::rate_limiter;
 
 #[derive(Debug)]
 pub enum TcbsError {
@@ -108,17 +112,57 @@ pub struct FinancialInfo {
     pub ratios: Option<Vec<FinancialStatement>>,
 }
 
+// Foreign investors' matched trading in one symbol on one session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForeignTradingData {
+    pub date: NaiveDate,
+    pub buy_volume: u64,
+    pub sell_volume: u64,
+    pub buy_value: f64,  // VND
+    pub sell_value: f64, // VND
+}
+
+/// Sessions of a foreign trading response, oldest first. Entries without a trading date are skipped.
+fn parse_foreign_trading(response: &Value) -> Result<Vec<ForeignTradingData>, TcbsError> {
+    let items = response.get("data").and_then(|v| v.as_array()).ok_or(TcbsError::NoData)?;
+    let mut sessions: Vec<ForeignTradingData> = items.iter()
+        .filter_map(|item| {
+            let trading_date = item.get("tradingDate").and_then(|v| v.as_str())?;
+            let date = NaiveDate::parse_from_str(trading_date.split('T').next().unwrap_or(trading_date), "%Y-%m-%d").ok()?;
+            Some(ForeignTradingData {
+                date,
+                buy_volume: item.get("buyVol").and_then(|v| v.as_u64()).unwrap_or(0),
+                sell_volume: item.get("sellVol").and_then(|v| v.as_u64()).unwrap_or(0),
+                buy_value: item.get("buyVal").and_then(|v| v.as_f64()).unwrap_or(0.0),
+                sell_value: item.get("sellVal").and_then(|v| v.as_f64()).unwrap_or(0.0),
+            })
+        })
+        .collect();
+    if sessions.is_empty() {
+        return Err(TcbsError::NoData);
+    }
+    sessions.sort_by_key(|session| session.date);
+    sessions.dedup_by_key(|session| session.date);
+    Ok(sessions)
+}
+
+// Concurrent requests per client and its clones; the host's rate budget still paces them
+const MAX_IN_FLIGHT: usize = 4;
+
+/// Cheap to clone: clones share the HTTP connection pool, the in-flight limit and the host's rate budget,
+/// so one client can serve concurrent tasks
+#[derive(Clone)]
 pub struct TcbsClient {
-    client: Client,
+    client: Client, // Reference-counted handle to the pool
+    in_flight: Arc<Semaphore>, // Requests awaiting a response, at most MAX_IN_FLIGHT
     base_url: String,
-    rate_limit_per_minute: u32,
-    request_timestamps: Vec<SystemTime>,
+    host: String, // Requests are paced by the shared per-host budget
     user_agents: Vec<String>,
     random_agent: bool,
 }
 
 impl TcbsClient {
-    pub fn new(random_agent: bool, rate_limit_per_minute: u32) -> Result<Self, TcbsError> {
+    pub fn new(random_agent: bool) -> Result<Self, TcbsError> {
         let client = Client::builder()
             .timeout(Duration::from_secs(30))
             .build()?;
@@ -131,11 +175,12 @@ impl TcbsClient {
             "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.0.0".to_string(),
         ];
 
+        let base_url = "https://apipubaws.tcbs.com.vn".to_string();
         Ok(TcbsClient {
             client,
-            base_url: "https://apipubaws.tcbs.com.vn".to_string(),
-            rate_limit_per_minute,
-            request_timestamps: Vec::new(),
+            in_flight: Arc::new(Semaphore::new(MAX_IN_FLIGHT)),
+            host: rate_limiter::host_of(&base_url),
+            base_url,
             user_agents,
             random_agent,
         })
@@ -170,8 +215,8 @@ impl TcbsClient {
 
     fn get_user_agent(&self) -> String {
         if self.random_agent {
-            use rand::seq::SliceRandom;
-            self.user_agents.choose(&mut rand::thread_rng())
+            use rand::seq::IndexedRandom;
+            self.user_agents.choose(&mut rand::rng())
                 .unwrap_or(&self.user_agents[0])
                 .clone()
         } else {
@@ -179,32 +224,11 @@ impl TcbsClient {
         }
     }
 
-    async fn enforce_rate_limit(&mut self) {
-        let current_time = SystemTime::now();
-        
-        // Remove timestamps older than 1 minute
-        self.request_timestamps.retain(|&timestamp| {
-            current_time.duration_since(timestamp).unwrap_or(Duration::from_secs(0)) < Duration::from_secs(60)
-        });
-
-        // If we're at the rate limit, wait
-        if self.request_timestamps.len() >= self.rate_limit_per_minute as usize {
-            if let Some(&oldest_request) = self.request_timestamps.first() {
-                let wait_time = Duration::from_secs(60) - current_time.duration_since(oldest_request).unwrap_or(Duration::from_secs(0));
-                if !wait_time.is_zero() {
-                    sleep(wait_time + Duration::from_millis(100)).await;
-                }
-            }
-        }
-
-        self.request_timestamps.push(current_time);
-    }
-
-    async fn make_request(&mut self, url: &str, params: Option<&[(&str, &str)]>) -> Result<Value, TcbsError> {
+    async fn make_request(&self, url: &str, params: Option<&[(&str, &str)]>) -> Result<Value, TcbsError> {
         const MAX_RETRIES: u32 = 5;
         
         for attempt in 0..MAX_RETRIES {
-            self.enforce_rate_limit().await;
+            rate_limiter::acquire(&self.host).await;
 
             if attempt > 0 {
                 let delay = Duration::from_secs_f64(2.0_f64.powi(attempt as i32 - 1) + rand::random::<f64>());
@@ -212,6 +236,8 @@ impl TcbsClient {
                 sleep(delay).await;
             }
 
+            // Held until the response is read; clones of the client share the limit
+            let _permit = self.in_flight.acquire().await;
             let user_agent = self.get_user_agent();
             let mut request = self.client
                 .get(url)
@@ -263,9 +289,7 @@ impl TcbsClient {
 
     fn camel_to_snake(&self, name: &str) -> String {
         let mut result = String::new();
-        let mut chars = name.chars().peekable();
-
-        while let Some(ch) = chars.next() {
+        for ch in name.chars() {
             if ch.is_uppercase() && !result.is_empty() {
                 result.push('_');
             }
@@ -276,7 +300,7 @@ impl TcbsClient {
     }
 
     pub async fn get_history(
-        &mut self,
+        &self,
         symbol: &str,
         start: &str,
         end: Option<&str>,
@@ -302,7 +326,7 @@ impl TcbsClient {
         let end_timestamp = end_time.and_hms_opt(23, 59, 59).unwrap().and_utc().timestamp();
 
         // Determine asset type and endpoint
-        let (asset_type, base_path) = if symbol.contains("F2") {
+        let (asset_type, base_path) = if basis::is_futures(symbol) || symbol.contains("F2") {
             ("derivative", "futures-insight")
         } else {
             ("stock", "stock-insight")
@@ -347,7 +371,7 @@ impl TcbsClient {
                     let naive_date = NaiveDate::parse_from_str(date_part, "%Y-%m-%d")
                         .map_err(|_| TcbsError::InvalidResponse("Invalid trading date format".to_string()))?;
 
-                    if naive_date >= start_time.into() {
+                    if naive_date >= start_time {
                         let time = Utc.from_utc_datetime(&naive_date.and_hms_opt(0, 0, 0).unwrap());
 
                         result.push(OhlcvData {
@@ -366,7 +390,7 @@ impl TcbsClient {
             // VCI-style format with parallel arrays
             let required_keys = ["t", "o", "h", "l", "c", "v"];
             for key in &required_keys {
-                if !data.get(key).is_some() {
+                if data.get(key).is_none() {
                     return Err(TcbsError::InvalidResponse(format!("Missing key: {}", key)));
                 }
             }
@@ -401,12 +425,12 @@ impl TcbsClient {
             }
         }
 
-        result.sort_by(|a, b| a.time.cmp(&b.time));
+        result.sort_by_key(|a| a.time);
         Ok(result)
     }
 
     // pub async fn get_batch_history(
-    //     &mut self,
+    //     &self,
     //     symbols: &[String],
     //     start: &str,
     //     end: Option<&str>,
@@ -459,7 +483,7 @@ impl TcbsClient {
     //     Ok(results)
     // }
 
-    pub async fn overview(&mut self, symbol: &str) -> Result<CompanyOverview, TcbsError> {
+    pub async fn overview(&self, symbol: &str) -> Result<CompanyOverview, TcbsError> {
         let url = format!("{}/tcanalysis/v1/ticker/{}/overview", self.base_url, symbol.to_uppercase());
 
 
@@ -471,7 +495,7 @@ impl TcbsClient {
         Ok(overview)
     }
 
-    pub async fn profile(&mut self, symbol: &str) -> Result<CompanyProfile, TcbsError> {
+    pub async fn profile(&self, symbol: &str) -> Result<CompanyProfile, TcbsError> {
         let url = format!("{}/tcanalysis/v1/company/{}/overview", self.base_url, symbol.to_uppercase());
 
 
@@ -480,8 +504,15 @@ impl TcbsClient {
         // Clean HTML content if needed (simplified version without BeautifulSoup)
         let clean_html = |text: &str| -> String {
             // Simple HTML tag removal - in production you'd want a proper HTML parser
-            let re = regex::Regex::new(r"<[^>]*>").unwrap();
-            re.replace_all(text, "").replace('\n', " ")
+            let mut in_tag = false;
+            text.chars()
+                .filter(|ch| match ch {
+                    '<' => { in_tag = true; false }
+                    '>' if in_tag => { in_tag = false; false }
+                    _ => !in_tag,
+                })
+                .collect::<String>()
+                .replace('\n', " ")
         };
 
         let profile = CompanyProfile {
@@ -498,7 +529,7 @@ impl TcbsClient {
         Ok(profile)
     }
 
-    pub async fn shareholders(&mut self, symbol: &str) -> Result<Vec<ShareholderInfo>, TcbsError> {
+    pub async fn shareholders(&self, symbol: &str) -> Result<Vec<ShareholderInfo>, TcbsError> {
         let url = format!("{}/tcanalysis/v1/company/{}/large-share-holders", self.base_url, symbol.to_uppercase());
 
 
@@ -524,7 +555,7 @@ impl TcbsClient {
         Ok(shareholders)
     }
 
-    pub async fn officers(&mut self, symbol: &str) -> Result<Vec<OfficerInfo>, TcbsError> {
+    pub async fn officers(&self, symbol: &str) -> Result<Vec<OfficerInfo>, TcbsError> {
         let url = format!("{}/tcanalysis/v1/company/{}/key-officers", self.base_url, symbol.to_uppercase());
 
 
@@ -557,7 +588,7 @@ impl TcbsClient {
         Ok(officers)
     }
 
-    pub async fn get_current_price(&mut self, symbol: &str) -> Result<Option<f64>, TcbsError> {
+    pub async fn get_current_price(&self, symbol: &str) -> Result<Option<f64>, TcbsError> {
         let url = format!("{}/stock-insight/v1/stock/second-tc-price", self.base_url);
         let symbol_upper = symbol.to_uppercase();
         let params = &[("tickers", symbol_upper.as_str())];
@@ -573,10 +604,21 @@ impl TcbsClient {
         Ok(current_price)
     }
 
-    async fn make_financial_request(&mut self, url: &str, params: &[(&str, &str)]) -> Result<Value, TcbsError> {
+    /// Daily foreign buy and sell volume and value of the latest `count` sessions, oldest first
+    pub async fn foreign_trading(&self, symbol: &str, count: u32) -> Result<Vec<ForeignTradingData>, TcbsError> {
+        let url = format!("{}/tcanalysis/v1/ticker/{}/foreign-trading", self.base_url, symbol.to_uppercase());
+        let size = count.to_string();
+        let params = &[("page", "0"), ("size", size.as_str())];
+
+        let response_data = self.make_request(&url, Some(params)).await?;
+        parse_foreign_trading(&response_data)
+    }
+
+    async fn make_financial_request(&self, url: &str, params: &[(&str, &str)]) -> Result<Value, TcbsError> {
         // Use direct HTTP request like Python does for financial endpoints
-        self.enforce_rate_limit().await;
-        
+        rate_limiter::acquire(&self.host).await;
+        let _permit = self.in_flight.acquire().await;
+
         let user_agent = self.get_user_agent();
         let request = self.client
             .get(url)
@@ -605,13 +647,11 @@ impl TcbsClient {
             let data = response.json::<Value>().await?;
             Ok(data)
         } else {
-            Err(TcbsError::Http(reqwest::Error::from(
-                response.error_for_status().unwrap_err()
-            )))
+            Err(TcbsError::Http(response.error_for_status().unwrap_err()))
         }
     }
 
-    pub async fn company_info(&mut self, symbol: &str) -> Result<CompanyInfo, TcbsError> {
+    pub async fn company_info(&self, symbol: &str) -> Result<CompanyInfo, TcbsError> {
 
         let mut company_info = CompanyInfo {
             symbol: symbol.to_uppercase(),
@@ -654,26 +694,26 @@ impl TcbsClient {
         }
 
         // Calculate market cap if we have the data
-        if let Some(ref overview) = company_info.overview {
-            if let Some(outstanding_share) = overview.outstanding_share {
-                match self.get_current_price(symbol).await {
-                    Ok(Some(current_price)) => {
-                        // TCBS returns outstanding shares in millions
-                        let shares_actual = outstanding_share * 1_000_000.0;
-                        let market_cap = shares_actual * current_price;
-
-                        company_info.market_cap = Some(market_cap);
-                        company_info.current_price = Some(current_price);
+        if let Some(ref overview) = company_info.overview
+            && let Some(outstanding_share) = overview.outstanding_share
+        {
+            match self.get_current_price(symbol).await {
+                Ok(Some(current_price)) => {
+                    // TCBS returns outstanding shares in millions
+                    let shares_actual = outstanding_share * 1_000_000.0;
+                    let market_cap = shares_actual * current_price;
+
+                    company_info.market_cap = Some(market_cap);
+                    company_info.current_price = Some(current_price);
 
-                    }
-                    Ok(None) => {
-                        company_info.market_cap = None;
-                        company_info.current_price = None;
-                    }
-                    Err(_) => {
-                        company_info.market_cap = None;
-                        company_info.current_price = None;
-                    }
+                }
+                Ok(None) => {
+                    company_info.market_cap = None;
+                    company_info.current_price = None;
+                }
+                Err(_) => {
+                    company_info.market_cap = None;
+                    company_info.current_price = None;
                 }
             }
         }
@@ -681,7 +721,7 @@ impl TcbsClient {
         Ok(company_info)
     }
 
-    pub async fn financial_info(&mut self, symbol: &str, period: &str) -> Result<FinancialInfo, TcbsError> {
+    pub async fn financial_info(&self, symbol: &str, period: &str) -> Result<FinancialInfo, TcbsError> {
         let period_value = match period {
             "quarter" => "1",  // Python uses "1" as string for quarter
             "year" => "0",     // Python uses "0" as string for year
@@ -846,21 +886,35 @@ mod tests {
 
     #[tokio::test]
     async fn test_tcbs_client_creation() {
-        let client = TcbsClient::new(true, 6);
+        let client = TcbsClient::new(true);
         assert!(client.is_ok());
     }
 
     #[tokio::test]
     async fn test_interval_mapping() {
-        let client = TcbsClient::new(false, 6).unwrap();
+        let client = TcbsClient::new(false).unwrap();
         assert_eq!(client.get_interval_value("1D").unwrap(), "D");
         assert_eq!(client.get_interval_value("1H").unwrap(), "60");
         assert!(client.get_interval_value("invalid").is_err());
     }
 
+    #[test]
+    fn test_parse_foreign_trading() {
+        let response = serde_json::json!({ "data": [
+            { "tradingDate": "2025-08-15T00:00:00.000Z", "buyVol": 120000, "sellVol": 80000, "buyVal": 7.3e9, "sellVal": 4.9e9 },
+            { "tradingDate": "2025-08-14", "buyVol": 1000, "sellVal": 2.5e8 },
+            { "buyVol": 5 },
+        ] });
+        let sessions = parse_foreign_trading(&response).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0], ForeignTradingData { date: NaiveDate::from_ymd_opt(2025, 8, 14).unwrap(), buy_volume: 1000, sell_volume: 0, buy_value: 0.0, sell_value: 2.5e8 });
+        assert_eq!(sessions[1].sell_volume, 80000);
+        assert!(matches!(parse_foreign_trading(&serde_json::json!({ "data": [] })), Err(TcbsError::NoData)));
+    }
+
     #[test]
     fn test_camel_to_snake() {
-        let client = TcbsClient::new(false, 6).unwrap();
+        let client = TcbsClient::new(false).unwrap();
         assert_eq!(client.camel_to_snake("camelCase"), "camel_case");
         assert_eq!(client.camel_to_snake("PascalCase"), "pascal_case");
         assert_eq!(client.camel_to_snake("simple"), "simple");
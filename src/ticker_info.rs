@@ -0,0 +1,271 @@
+use crate::utils::cache::get_cache_dir;
+use crate::utils::http_client;
+use crate::utils::rate_limiter;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+const TICKER_INFO_PATH: &str = "ticker_info.json";
+const TICKER_INFO_CACHE_FILE: &str = "ticker_info.json";
+pub const DEFAULT_SEARCH_LIMIT: usize = 10;
+pub const MAX_SEARCH_LIMIT: usize = 50;
+
+// Names of one listed symbol. Field aliases accept the upstream ticker_info.json layout.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TickerInfo {
+    #[serde(alias = "ticker")]
+    pub symbol: String,
+    #[serde(default, alias = "en_organ_name", alias = "company_name")]
+    pub name: Option<String>, // English company name
+    #[serde(default, alias = "organ_name", alias = "vi_name")]
+    pub name_vi: Option<String>, // Vietnamese company name
+    #[serde(default)]
+    pub exchange: Option<String>,
+    #[serde(default, alias = "issue_share", alias = "outstanding_share")]
+    pub outstanding_shares: Option<f64>,
+    #[serde(default)]
+    pub market_cap: Option<f64>, // Reported market cap, used when shares are unknown
+}
+
+// ticker_info.json is either a list of entries or an object keyed by symbol
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TickerInfoFile {
+    List(Vec<TickerInfo>),
+    Map(HashMap<String, TickerInfo>),
+}
+
+/// Symbol -> names, used to label analysis output and for `/search`
+#[derive(Clone, Debug, Default)]
+pub struct TickerDirectory(HashMap<String, TickerInfo>);
+
+pub type SharedTickerDirectory = Arc<Mutex<TickerDirectory>>;
+
+/// Lowercase and strip Vietnamese diacritics, so "ngan hang" matches "Ngân hàng"
+pub fn fold(text: &str) -> String {
+    const FOLDS: [(&str, char); 7] = [
+        ("àáạảãâầấậẩẫăằắặẳẵ", 'a'),
+        ("èéẹẻẽêềếệểễ", 'e'),
+        ("ìíịỉĩ", 'i'),
+        ("òóọỏõôồốộổỗơờớợởỡ", 'o'),
+        ("ùúụủũưừứựửữ", 'u'),
+        ("ỳýỵỷỹ", 'y'),
+        ("đ", 'd'),
+    ];
+    text.to_lowercase()
+        .chars()
+        .map(|c| FOLDS.iter().find(|(accented, _)| accented.contains(c)).map_or(c, |(_, base)| *base))
+        .collect()
+}
+
+/// Call `f` with the symbol and fields of every JSON object carrying a string `symbol`, at any depth
+pub fn for_each_symbol_object<F>(value: &mut Value, f: &mut F)
+where
+    F: FnMut(&str, &mut serde_json::Map<String, Value>),
+{
+    match value {
+        Value::Object(map) => {
+            if let Some(symbol) = map.get("symbol").and_then(Value::as_str).map(str::to_string) {
+                f(&symbol, map);
+            }
+            map.values_mut().for_each(|child| for_each_symbol_object(child, f));
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| for_each_symbol_object(item, f)),
+        _ => {}
+    }
+}
+
+impl TickerDirectory {
+    pub fn parse(json: &str) -> Result<Self, serde_json::Error> {
+        let entries = match serde_json::from_str::<TickerInfoFile>(json)? {
+            TickerInfoFile::List(entries) => entries,
+            TickerInfoFile::Map(entries) => entries.into_values().collect(),
+        };
+        Ok(Self(entries.into_iter().map(|mut info| {
+            info.symbol = info.symbol.trim().to_uppercase();
+            (info.symbol.clone(), info)
+        }).collect()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&TickerInfo> {
+        self.0.get(symbol)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &TickerInfo> {
+        self.0.values()
+    }
+
+    /// Symbol -> upper-cased exchange, for symbols whose listing names one
+    pub fn exchanges(&self) -> HashMap<String, String> {
+        self.0.values()
+            .filter_map(|info| info.exchange.as_deref().map(|exchange| (info.symbol.clone(), exchange.trim().to_uppercase())))
+            .filter(|(_, exchange)| !exchange.is_empty())
+            .collect()
+    }
+
+    /// Company name, falling back to the symbol itself
+    pub fn name_for<'a>(&'a self, symbol: &'a str) -> &'a str {
+        self.0.get(symbol).and_then(|info| info.name.as_deref().or(info.name_vi.as_deref())).unwrap_or(symbol)
+    }
+
+    /// Add `name` and `name_vi` to every JSON object carrying a `symbol`, at any depth
+    pub fn annotate(&self, value: &mut Value) {
+        for_each_symbol_object(value, &mut |symbol, map| {
+            let info = self.0.get(symbol);
+            map.insert("name".to_string(), Value::from(self.name_for(symbol)));
+            map.insert("name_vi".to_string(), info.and_then(|i| i.name_vi.clone()).map_or(Value::Null, Value::from));
+        });
+    }
+
+    /// Symbols matching `query`: exact symbol, then symbol prefix, then name match.
+    /// `extra_symbols` (e.g. from ticker groups) are searchable by symbol even without names.
+    pub fn search<'a>(&'a self, query: &str, extra_symbols: impl Iterator<Item = &'a String>, limit: usize) -> Vec<TickerInfo> {
+        let query_symbol = query.trim().to_uppercase();
+        let query_name = fold(query.trim());
+        if query_symbol.is_empty() {
+            return Vec::new();
+        }
+
+        let mut candidates: HashMap<&str, Option<&TickerInfo>> = self.0.iter().map(|(symbol, info)| (symbol.as_str(), Some(info))).collect();
+        for symbol in extra_symbols {
+            candidates.entry(symbol.as_str()).or_insert(None);
+        }
+
+        let mut matches: Vec<(u8, &str, Option<&TickerInfo>)> = candidates.into_iter()
+            .filter_map(|(symbol, info)| {
+                let rank = if symbol == query_symbol {
+                    0
+                } else if symbol.starts_with(&query_symbol) {
+                    1
+                } else if info.is_some_and(|info| {
+                    [&info.name, &info.name_vi].into_iter().flatten().any(|name| fold(name).contains(&query_name))
+                }) {
+                    2
+                } else {
+                    return None;
+                };
+                Some((rank, symbol, info))
+            })
+            .collect();
+        matches.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+        matches.into_iter()
+            .take(limit)
+            .map(|(_, symbol, info)| info.cloned().unwrap_or_else(|| TickerInfo {
+                symbol: symbol.to_string(),
+                name: None,
+                name_vi: None,
+                exchange: None,
+                outstanding_shares: None,
+                market_cap: None,
+            }))
+            .collect()
+    }
+}
+
+fn cache_path() -> PathBuf {
+    get_cache_dir().join(TICKER_INFO_CACHE_FILE)
+}
+
+/// Load names from the last downloaded copy, else the bundled ticker_info.json.
+/// Both are optional; without them names fall back to symbols.
+pub fn load_ticker_directory() -> SharedTickerDirectory {
+    let cached = cache_path();
+    let directory = [cached.clone(), PathBuf::from(TICKER_INFO_PATH)].into_iter()
+        .filter(|path| path.exists())
+        .find_map(|path| match fs::read_to_string(&path).map(|content| TickerDirectory::parse(&content)) {
+            Ok(Ok(directory)) => {
+                info!(?path, symbols = directory.len(), "Loaded ticker info");
+                Some(directory)
+            }
+            Ok(Err(e)) => {
+                warn!(?path, error = %e, "Invalid ticker info file");
+                None
+            }
+            Err(e) => {
+                warn!(?path, error = %e, "Failed to read ticker info file");
+                None
+            }
+        })
+        .unwrap_or_else(|| {
+            info!("No ticker info file found, names will fall back to symbols");
+            TickerDirectory::default()
+        });
+    Arc::new(Mutex::new(directory))
+}
+
+async fn fetch_ticker_info(url: &str) -> Result<String, String> {
+    rate_limiter::acquire(&rate_limiter::host_of(url)).await;
+    let response = http_client::shared_client().get(url).send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("status {}", response.status()));
+    }
+    response.text().await.map_err(|e| e.to_string())
+}
+
+/// Download ticker_info.json from `url` now and every `interval` until shutdown, keeping the last good copy
+pub async fn run_refresh(directory: SharedTickerDirectory, url: String, interval: Duration, shutdown: CancellationToken) {
+    loop {
+        match fetch_ticker_info(&url).await.and_then(|body| TickerDirectory::parse(&body).map(|d| (body, d)).map_err(|e| e.to_string())) {
+            Ok((body, fetched)) if !fetched.is_empty() => {
+                info!(url, symbols = fetched.len(), "Refreshed ticker info");
+                *directory.lock().await = fetched;
+                if let Err(e) = fs::create_dir_all(get_cache_dir()).and_then(|_| fs::write(cache_path(), body)) {
+                    warn!(error = %e, "Failed to cache ticker info");
+                }
+            }
+            Ok(_) => warn!(url, "Ticker info download was empty, keeping current names"),
+            Err(e) => warn!(url, error = %e, "Failed to refresh ticker info, keeping current names"),
+        }
+        debug!(interval_secs = interval.as_secs(), "Sleeping before next ticker info refresh");
+        if !crate::shutdown::sleep(&shutdown, interval).await {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_annotate_and_search() {
+        let directory = TickerDirectory::parse(r#"[
+            {"ticker": "vcb", "en_organ_name": "Vietcombank", "organ_name": "Ngân hàng TMCP Ngoại thương Việt Nam", "exchange": "HOSE"},
+            {"symbol": "VCI", "name": "Vietcap Securities"}
+        ]"#).unwrap();
+        assert_eq!(directory.name_for("VCB"), "Vietcombank");
+        assert_eq!(directory.name_for("FPT"), "FPT");
+        assert_eq!(fold("Ngân Hàng Đầu tư"), "ngan hang dau tu");
+
+        let mut body = serde_json::json!({ "weeks": [{ "entries": [{ "symbol": "VCB" }, { "symbol": "FPT" }] }] });
+        directory.annotate(&mut body);
+        assert_eq!(body["weeks"][0]["entries"][0]["name"], "Vietcombank");
+        assert_eq!(body["weeks"][0]["entries"][0]["name_vi"], "Ngân hàng TMCP Ngoại thương Việt Nam");
+        assert_eq!(body["weeks"][0]["entries"][1]["name"], "FPT");
+
+        let groups = ["VCG".to_string()];
+        let symbols = |query: &str| directory.search(query, groups.iter(), 10).into_iter().map(|i| i.symbol).collect::<Vec<_>>();
+        assert_eq!(symbols("vc"), vec!["VCB", "VCG", "VCI"]);
+        assert_eq!(symbols("VCI"), vec!["VCI"]);
+        assert_eq!(symbols("ngoai thuong"), vec!["VCB"]);
+        assert!(symbols(" ").is_empty());
+
+        let keyed = TickerDirectory::parse(r#"{"FPT": {"symbol": "FPT", "name": "FPT Corp"}}"#).unwrap();
+        assert_eq!(keyed.name_for("FPT"), "FPT Corp");
+    }
+}
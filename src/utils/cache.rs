@@ -4,11 +4,14 @@ use std::path::PathBuf;
 use std::time::{SystemTime, Duration};
 use tracing::{debug, warn};
 
-const CACHE_TTL_SECS: u64 = 60; // 1 minute
+pub const CACHE_TTL_SECS: u64 = 60; // 1 minute
 
-/// Get the cache directory path
-fn get_cache_dir() -> PathBuf {
-    std::env::temp_dir().join("aipriceaction-proxy-cache")
+/// Get the cache directory path (CACHE_DIR, or a folder in the system temp dir)
+pub fn get_cache_dir() -> PathBuf {
+    match std::env::var("CACHE_DIR") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => std::env::temp_dir().join("aipriceaction-proxy-cache"),
+    }
 }
 
 /// Initialize cache directory
@@ -24,7 +27,7 @@ pub fn init_cache_dir() -> io::Result<()> {
 /// Convert a path to a safe cache filename
 fn path_to_cache_filename(path: &str) -> String {
     // Replace path separators and special characters with underscores
-    path.replace('/', "_").replace('\\', "_").replace("..", "_")
+    path.replace(['/', '\\'], "_").replace("..", "_")
 }
 
 /// Get the full path to a cached file
@@ -115,16 +118,13 @@ pub fn cleanup_old_cache() -> io::Result<()> {
         let entry = entry?;
         let path = entry.path();
 
-        if let Ok(metadata) = fs::metadata(&path) {
-            if let Ok(modified) = metadata.modified() {
-                if let Ok(age) = now.duration_since(modified) {
-                    if age > Duration::from_secs(CACHE_TTL_SECS) {
-                        if fs::remove_file(&path).is_ok() {
-                            removed_count += 1;
-                        }
-                    }
-                }
-            }
+        if let Ok(metadata) = fs::metadata(&path)
+            && let Ok(modified) = metadata.modified()
+            && let Ok(age) = now.duration_since(modified)
+            && age > Duration::from_secs(CACHE_TTL_SECS)
+            && fs::remove_file(&path).is_ok()
+        {
+            removed_count += 1;
         }
     }
 
@@ -0,0 +1,117 @@
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+// Changes retained for incremental sync; a replica further behind than this resyncs in full
+const MAX_ENTRIES: usize = 50_000;
+
+/// First market date of a symbol that changed in memory
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SymbolChange {
+    pub symbol: String,
+    pub from: NaiveDate,
+}
+
+/// Answer to "what changed since version N", served by `/sync/changes`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChangeSet {
+    pub epoch: u64, // Changes when the process restarts, which invalidates every version
+    pub version: u64,
+    pub full_resync: bool, // The requested version is unknown or no longer retained
+    pub changes: Vec<SymbolChange>,
+}
+
+struct ChangeLog {
+    epoch: u64,
+    version: u64,
+    evicted_through: u64, // Highest version no longer in `entries`
+    entries: VecDeque<(u64, String, NaiveDate)>,
+}
+
+impl ChangeLog {
+    fn new(epoch: u64) -> Self {
+        Self { epoch, version: 0, evicted_through: 0, entries: VecDeque::new() }
+    }
+
+    fn record(&mut self, symbol: &str, from: NaiveDate) {
+        self.version += 1;
+        self.entries.push_back((self.version, symbol.to_string(), from));
+        if self.entries.len() > MAX_ENTRIES
+            && let Some((version, _, _)) = self.entries.pop_front()
+        {
+            self.evicted_through = version;
+        }
+    }
+
+    fn since(&self, epoch: Option<u64>, since: Option<u64>) -> ChangeSet {
+        let known = since.filter(|since| epoch == Some(self.epoch) && *since >= self.evicted_through && *since <= self.version);
+        let changes = match known {
+            Some(since) => {
+                // Earliest changed date per symbol, so one fetch per symbol covers all of its changes
+                let mut earliest: BTreeMap<&str, NaiveDate> = BTreeMap::new();
+                let start = self.entries.partition_point(|(version, _, _)| *version <= since);
+                for (_, symbol, from) in self.entries.range(start..) {
+                    earliest.entry(symbol.as_str()).and_modify(|date| *date = (*date).min(*from)).or_insert(*from);
+                }
+                earliest.into_iter().map(|(symbol, from)| SymbolChange { symbol: symbol.to_string(), from }).collect()
+            }
+            None => Vec::new(),
+        };
+        ChangeSet { epoch: self.epoch, version: self.version, full_resync: known.is_none(), changes }
+    }
+}
+
+fn log() -> &'static Mutex<ChangeLog> {
+    static LOG: OnceLock<Mutex<ChangeLog>> = OnceLock::new();
+    LOG.get_or_init(|| Mutex::new(ChangeLog::new(Utc::now().timestamp_millis() as u64)))
+}
+
+/// Note that `symbol` changed in memory from market date `from` onwards
+pub fn record(symbol: &str, from: NaiveDate) {
+    log().lock().unwrap_or_else(|e| e.into_inner()).record(symbol, from);
+}
+
+/// Symbols changed after `since` in `epoch`. Without both, only the current position is returned.
+pub fn since(epoch: Option<u64>, since: Option<u64>) -> ChangeSet {
+    log().lock().unwrap_or_else(|e| e.into_inner()).since(epoch, since)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 8, day).unwrap()
+    }
+
+    #[test]
+    fn test_changes_since_version() {
+        let mut log = ChangeLog::new(7);
+        log.record("VCB", date(15));
+        log.record("FPT", date(15));
+        log.record("VCB", date(12)); // Dividend adjustment rewrote earlier bars
+
+        let all = log.since(Some(7), Some(0));
+        assert!(!all.full_resync);
+        assert_eq!(all.version, 3);
+        assert_eq!(all.changes, vec![
+            SymbolChange { symbol: "FPT".to_string(), from: date(15) },
+            SymbolChange { symbol: "VCB".to_string(), from: date(12) },
+        ]);
+        assert_eq!(log.since(Some(7), Some(2)).changes, vec![SymbolChange { symbol: "VCB".to_string(), from: date(12) }]);
+        assert!(log.since(Some(7), Some(3)).changes.is_empty());
+
+        // Another epoch, a version from the future, or no position at all needs a full resync
+        assert!(log.since(Some(8), Some(1)).full_resync);
+        assert!(log.since(Some(7), Some(4)).full_resync);
+        assert!(log.since(None, None).full_resync);
+
+        // So does a version whose changes were evicted
+        for _ in 0..MAX_ENTRIES {
+            log.record("HPG", date(16));
+        }
+        assert!(log.since(Some(7), Some(2)).full_resync);
+        assert!(!log.since(Some(7), Some(3)).full_resync);
+    }
+}
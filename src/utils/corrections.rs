@@ -0,0 +1,158 @@
+use crate::config::OfficeHoursConfig;
+use crate::data_source::SourceKind;
+use crate::data_structures::get_provisional_date;
+use crate::utils::market_time::market_date;
+use crate::vci::OhlcvData;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use tracing::{info, warn};
+
+// Corrections kept per symbol, oldest dropped first
+const RETAINED_PER_SYMBOL: usize = 500;
+
+/// Where the replacing value of a corrected bar came from
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CorrectionSource {
+    Vci,            // Core node fetch
+    Tcbs,           // Core node fetch from the fallback source
+    Core,           // Public node sync from the core network
+    InternalGossip, // Trusted peer
+    Synthetic,      // Rebuilt from constituents, such as a synthetic index
+    Recalculation,  // Refetched by an `/admin/recalculate` job
+}
+
+impl From<SourceKind> for CorrectionSource {
+    fn from(kind: SourceKind) -> Self {
+        match kind {
+            SourceKind::Vci => CorrectionSource::Vci,
+            SourceKind::Tcbs => CorrectionSource::Tcbs,
+        }
+    }
+}
+
+/// One field of a settled bar that changed in memory, as served by `/changes/{symbol}`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Correction {
+    pub date: NaiveDate,
+    pub field: &'static str, // open, high, low, close or volume
+    pub old: f64,
+    pub new: f64,
+    pub source: CorrectionSource,
+    pub corrected_at: DateTime<Utc>,
+}
+
+static OFFICE_HOURS: OnceLock<OfficeHoursConfig> = OnceLock::new();
+
+/// Set the office hours that decide which session is still trading; call once at startup.
+/// Without them every session counts as settled.
+pub fn set_office_hours(config: OfficeHoursConfig) {
+    if OFFICE_HOURS.set(config).is_err() {
+        warn!("Correction office hours already set, ignoring new settings");
+    } else {
+        info!("Configured office hours for the correction feed");
+    }
+}
+
+fn feed() -> &'static Mutex<HashMap<String, VecDeque<Correction>>> {
+    static FEED: OnceLock<Mutex<HashMap<String, VecDeque<Correction>>>> = OnceLock::new();
+    FEED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Fields of `new` that differ from `old`
+fn changed_fields(old: &OhlcvData, new: &OhlcvData) -> Vec<(&'static str, f64, f64)> {
+    [
+        ("open", old.open, new.open),
+        ("high", old.high, new.high),
+        ("low", old.low, new.low),
+        ("close", old.close, new.close),
+        ("volume", old.volume as f64, new.volume as f64),
+    ]
+    .into_iter()
+    .filter(|(_, old, new)| old != new)
+    .collect()
+}
+
+fn push(symbol: &str, corrections: Vec<Correction>) {
+    if corrections.is_empty() {
+        return;
+    }
+    let mut feed = feed().lock().unwrap_or_else(|e| e.into_inner());
+    let entries = feed.entry(symbol.to_string()).or_default();
+    entries.extend(corrections);
+    while entries.len() > RETAINED_PER_SYMBOL {
+        entries.pop_front();
+    }
+}
+
+/// Note the fields of `old` that `new` replaces, unless its session is still trading, where every
+/// fetch moves the bar and none of it is a correction
+pub fn record_bar(symbol: &str, old: &OhlcvData, new: &OhlcvData, source: CorrectionSource) {
+    let date = market_date(old.time);
+    let provisional_date = OFFICE_HOURS.get().and_then(get_provisional_date);
+    if provisional_date.is_some_and(|provisional| date >= provisional) {
+        return;
+    }
+    let corrected_at = Utc::now();
+    let corrections = changed_fields(old, new).into_iter()
+        .map(|(field, old, new)| Correction { date, field, old, new, source, corrected_at })
+        .collect();
+    push(symbol, corrections);
+}
+
+/// Note every bar of `before` that `after` holds a different bar for on the same date. Both are
+/// sorted by time; bars only one of them has are additions or removals, not corrections.
+pub fn record_changes(symbol: &str, before: &[OhlcvData], after: &[OhlcvData], source: CorrectionSource) {
+    for old in before {
+        let date = market_date(old.time);
+        let i = after.partition_point(|bar| market_date(bar.time) < date);
+        if let Some(new) = after.get(i).filter(|new| market_date(new.time) == date && *new != old) {
+            record_bar(symbol, old, new, source);
+        }
+    }
+}
+
+/// Corrections of `symbol` made at or after `since`, oldest first
+pub fn since(symbol: &str, since: Option<DateTime<Utc>>) -> Vec<Correction> {
+    feed().lock().unwrap_or_else(|e| e.into_inner()).get(symbol)
+        .map(|entries| entries.iter().filter(|c| since.is_none_or(|since| c.corrected_at >= since)).cloned().collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::market_time::market_day_start;
+
+    #[test]
+    fn test_records_changed_fields_of_matching_dates() {
+        let date = |day: u32| NaiveDate::from_ymd_opt(2025, 8, day).unwrap();
+        let bar = |day: u32, close: f64, volume: u64| OhlcvData { time: market_day_start(date(day)), open: 10.0, high: 12.0, low: 9.0, close, volume, symbol: None };
+        let started = Utc::now();
+        let before = vec![bar(4, 11.0, 100), bar(5, 11.5, 200), bar(6, 11.0, 300)];
+        // The 5th's close and volume are revised, the 6th is unchanged and the 7th is new
+        let after = vec![bar(5, 11.6, 250), bar(6, 11.0, 300), bar(7, 12.0, 400)];
+        record_changes("CRTEST", &before, &after, CorrectionSource::Vci);
+        record_bar("CRTEST", &after[2], &bar(7, 12.1, 400), CorrectionSource::Recalculation);
+
+        let corrections = since("CRTEST", Some(started));
+        let summary: Vec<_> = corrections.iter().map(|c| (c.date, c.field, c.old, c.new, c.source)).collect();
+        assert_eq!(summary, vec![
+            (date(5), "close", 11.5, 11.6, CorrectionSource::Vci),
+            (date(5), "volume", 200.0, 250.0, CorrectionSource::Vci),
+            (date(7), "close", 12.0, 12.1, CorrectionSource::Recalculation),
+        ]);
+        assert!(since("CRTEST", Some(Utc::now() + chrono::Duration::seconds(1))).is_empty());
+        assert!(since("NOSUCH", None).is_empty());
+
+        for _ in 0..RETAINED_PER_SYMBOL {
+            record_bar("CRTRIM", &bar(4, 1.0, 1), &bar(4, 2.0, 1), CorrectionSource::Core);
+        }
+        record_bar("CRTRIM", &bar(5, 1.0, 1), &bar(5, 3.0, 1), CorrectionSource::Core);
+        let kept = since("CRTRIM", None);
+        assert_eq!(kept.len(), RETAINED_PER_SYMBOL);
+        assert_eq!(kept.last().map(|c| c.date), Some(date(5)));
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+/// When each symbol's latest bar was last ingested (fetched, synced or gossiped into memory)
+#[derive(Default)]
+struct Freshness {
+    ingested: HashMap<String, DateTime<Utc>>,
+    last_ingest: Option<DateTime<Utc>>, // Most recent ingestion of any symbol
+}
+
+impl Freshness {
+    fn record(&mut self, symbol: &str, at: DateTime<Utc>) {
+        self.ingested.insert(symbol.to_string(), at);
+        self.last_ingest = self.last_ingest.max(Some(at));
+    }
+
+    // The next refresh is expected one interval after the last one; poll just after it
+    fn refresh_after_secs(&self, now: DateTime<Utc>, interval_secs: u64) -> u64 {
+        let interval_secs = interval_secs.max(1);
+        match self.last_ingest {
+            Some(last) => {
+                let elapsed = (now - last).num_seconds().max(0) as u64;
+                interval_secs.saturating_sub(elapsed % interval_secs).max(1)
+            }
+            None => interval_secs,
+        }
+    }
+}
+
+fn freshness() -> &'static Mutex<Freshness> {
+    static FRESHNESS: OnceLock<Mutex<Freshness>> = OnceLock::new();
+    FRESHNESS.get_or_init(|| Mutex::new(Freshness::default()))
+}
+
+/// Note that `symbol`'s latest bar was ingested at `at`
+pub fn record(symbol: &str, at: DateTime<Utc>) {
+    freshness().lock().unwrap_or_else(|e| e.into_inner()).record(symbol, at);
+}
+
+/// When `symbol`'s latest bar was ingested; `None` for bars only restored from disk
+pub fn ingested_at(symbol: &str) -> Option<DateTime<Utc>> {
+    freshness().lock().unwrap_or_else(|e| e.into_inner()).ingested.get(symbol).copied()
+}
+
+/// Milliseconds between ingestion and `now`
+pub fn age_ms(ingested_at: DateTime<Utc>, now: DateTime<Utc>) -> u64 {
+    (now - ingested_at).num_milliseconds().max(0) as u64
+}
+
+/// Seconds a client should wait before polling again, given the current worker interval
+pub fn refresh_after_secs(now: DateTime<Utc>, interval_secs: u64) -> u64 {
+    freshness().lock().unwrap_or_else(|e| e.into_inner()).refresh_after_secs(now, interval_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_refresh_after_follows_last_ingest() {
+        let now = Utc::now();
+        let mut freshness = Freshness::default();
+        assert_eq!(freshness.refresh_after_secs(now, 300), 300);
+
+        freshness.record("VCB", now - Duration::seconds(100));
+        freshness.record("FPT", now - Duration::seconds(200)); // Older ingest does not move the schedule back
+        assert_eq!(freshness.refresh_after_secs(now, 300), 200);
+        // A missed cycle points at the next expected one rather than the past
+        assert_eq!(freshness.refresh_after_secs(now + Duration::seconds(300), 300), 200);
+        assert_eq!(freshness.refresh_after_secs(now + Duration::seconds(200), 300), 300);
+
+        assert_eq!(age_ms(now - Duration::milliseconds(1500), now), 1500);
+        assert_eq!(age_ms(now + Duration::seconds(1), now), 0);
+    }
+}
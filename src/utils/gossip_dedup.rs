@@ -0,0 +1,160 @@
+use crate::utils::metrics;
+use crate::vci::OhlcvData;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// Optional header naming one gossip request, so a retried request is applied once
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+// How long a request key is remembered; retries later than this are applied again
+const KEY_TTL: Duration = Duration::from_secs(600);
+// Keys remembered across all senders; beyond this the oldest are forgotten first
+const MAX_KEYS: usize = 10_000;
+// Senders with duplicate counts; beyond this the one with the fewest is dropped
+const MAX_SENDERS: usize = 1024;
+
+/// Recently applied gossip requests per sender, and how many duplicates each sender sent
+#[derive(Default)]
+struct GossipDedup {
+    keys: HashMap<(String, String), Instant>,
+    order: VecDeque<(Instant, (String, String))>, // Keys in insertion order; stale once re-remembered or forgotten
+    duplicates: HashMap<String, u64>,
+}
+
+impl GossipDedup {
+    fn seen(&self, sender: &str, key: &str, now: Instant) -> bool {
+        self.keys.get(&(sender.to_string(), key.to_string())).is_some_and(|at| now.duration_since(*at) < KEY_TTL)
+    }
+
+    fn remember(&mut self, sender: &str, key: &str, now: Instant) {
+        // Drop expired and stale entries from the front, then the oldest live keys while full
+        while let Some((at, oldest)) = self.order.front() {
+            let live = self.keys.get(oldest) == Some(at);
+            if live && now.duration_since(*at) < KEY_TTL && self.keys.len() < MAX_KEYS && self.order.len() < 2 * MAX_KEYS {
+                break;
+            }
+            if let Some((_, oldest)) = self.order.pop_front()
+                && live
+            {
+                self.keys.remove(&oldest);
+            }
+        }
+        let entry = (sender.to_string(), key.to_string());
+        self.keys.insert(entry.clone(), now);
+        self.order.push_back((now, entry));
+    }
+
+    fn check_and_remember(&mut self, sender: &str, key: &str, now: Instant) -> bool {
+        if self.seen(sender, key, now) {
+            return true;
+        }
+        self.remember(sender, key, now);
+        false
+    }
+
+    fn forget(&mut self, sender: &str, key: &str) {
+        self.keys.remove(&(sender.to_string(), key.to_string()));
+    }
+
+    fn record_duplicate(&mut self, sender: &str) {
+        if !self.duplicates.contains_key(sender)
+            && self.duplicates.len() >= MAX_SENDERS
+            && let Some(quietest) = self.duplicates.iter().min_by_key(|(_, count)| **count).map(|(sender, _)| sender.clone())
+        {
+            self.duplicates.remove(&quietest);
+        }
+        *self.duplicates.entry(sender.to_string()).or_default() += 1;
+    }
+}
+
+fn dedup() -> &'static Mutex<GossipDedup> {
+    static DEDUP: OnceLock<Mutex<GossipDedup>> = OnceLock::new();
+    DEDUP.get_or_init(|| Mutex::new(GossipDedup::default()))
+}
+
+/// Key of a gossiped bar without an `Idempotency-Key`: its symbol, timestamp and values, so resending
+/// the same bar is a duplicate while a still-forming bar with new values under the same timestamp is not
+pub fn bar_key(bar: &OhlcvData) -> String {
+    format!(
+        "{}@{}:{}/{}/{}/{}/{}",
+        bar.symbol.as_deref().unwrap_or_default(),
+        bar.time.to_rfc3339(),
+        bar.open,
+        bar.high,
+        bar.low,
+        bar.close,
+        bar.volume
+    )
+}
+
+/// Whether `sender` had a request with `key` accepted within the last ten minutes; if not, the
+/// request is remembered as accepted. Checking and remembering under one lock means concurrent
+/// retries of the same request are applied once.
+pub fn check_and_remember(sender: &str, key: &str) -> bool {
+    dedup().lock().unwrap_or_else(|e| e.into_inner()).check_and_remember(sender, key, Instant::now())
+}
+
+/// Forget `sender`'s request with `key` when it ended up not being applied, so a retry is processed again
+pub fn forget(sender: &str, key: &str) {
+    dedup().lock().unwrap_or_else(|e| e.into_inner()).forget(sender, key);
+}
+
+/// Count a duplicate request from `sender` under the `counter` metric and per sender
+pub fn record_duplicate(counter: &str, sender: &str) {
+    metrics::increment_counter(counter, 1);
+    dedup().lock().unwrap_or_else(|e| e.into_inner()).record_duplicate(sender);
+}
+
+/// Duplicate requests per sender (peer account or public IP) since process start
+pub fn duplicates_by_sender() -> BTreeMap<String, u64> {
+    dedup().lock().unwrap_or_else(|e| e.into_inner()).duplicates.iter().map(|(sender, count)| (sender.clone(), *count)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_bars::bar;
+
+    #[test]
+    fn test_keys_expire_and_duplicates_are_counted_per_sender() {
+        let now = Instant::now();
+        let mut dedup = GossipDedup::default();
+        assert!(!dedup.seen("core-a", "req-1", now));
+        dedup.remember("core-a", "req-1", now);
+        assert!(dedup.seen("core-a", "req-1", now + Duration::from_secs(60)));
+        // Keys are scoped to their sender and forgotten after the TTL
+        assert!(!dedup.seen("core-b", "req-1", now));
+        assert!(!dedup.seen("core-a", "req-1", now + KEY_TTL));
+        // Checking remembers the key, and a forgotten key is accepted again
+        assert!(!dedup.check_and_remember("core-b", "req-2", now));
+        assert!(dedup.check_and_remember("core-b", "req-2", now));
+        dedup.forget("core-b", "req-2");
+        assert!(!dedup.check_and_remember("core-b", "req-2", now));
+        dedup.forget("core-b", "req-2");
+
+        for i in 0..MAX_KEYS + 5 {
+            dedup.remember("core-a", &i.to_string(), now + Duration::from_millis(i as u64));
+        }
+        assert_eq!(dedup.keys.len(), MAX_KEYS);
+        assert!(!dedup.seen("core-a", "0", now + Duration::from_secs(1)));
+        // A re-remembered key moves to the back, so the next oldest is evicted instead
+        dedup.remember("core-a", "5", now + Duration::from_secs(1));
+        dedup.remember("core-a", "new", now + Duration::from_secs(1));
+        assert!(dedup.seen("core-a", "5", now + Duration::from_secs(1)) && !dedup.seen("core-a", "6", now + Duration::from_secs(1)));
+        assert_eq!(dedup.keys.len(), MAX_KEYS);
+        // Expired keys are dropped from the front once the next key arrives
+        dedup.remember("core-a", "late", now + KEY_TTL + Duration::from_secs(20));
+        assert_eq!((dedup.keys.len(), dedup.order.len()), (1, 1));
+
+        dedup.record_duplicate("core-a");
+        dedup.record_duplicate("core-a");
+        dedup.record_duplicate("10.0.0.1");
+        assert_eq!((dedup.duplicates["core-a"], dedup.duplicates["10.0.0.1"]), (2, 1));
+
+        let mut bar = OhlcvData { open: 60.0, high: 61.0, low: 59.5, ..bar(14, 60.5, 1000) };
+        let key = bar_key(&bar);
+        assert_eq!(key, "VCB@2025-08-15T00:00:00+00:00:60/61/59.5/60.5/1000");
+        bar.close = 60.7;
+        assert_ne!(bar_key(&bar), key);
+    }
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Browser user agents sent to upstream providers unless overridden in config
+pub const DEFAULT_USER_AGENTS: [&str; 5] = [
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:120.0) Gecko/20100101 Firefox/120.0",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.3 Safari/605.1.15",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.0.0",
+];
+const DEFAULT_ACCEPT_LANGUAGE: &str = "en-US,en;q=0.9,vi-VN;q=0.8,vi;q=0.7";
+
+// How a client picks the next user agent from its pool
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserAgentRotation {
+    #[default]
+    Random,
+    RoundRobin,
+    Fixed, // Always the first entry
+}
+
+impl std::str::FromStr for UserAgentRotation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "random" => Ok(Self::Random),
+            "round_robin" => Ok(Self::RoundRobin),
+            "fixed" => Ok(Self::Fixed),
+            other => Err(format!("unknown user agent rotation: {}", other)),
+        }
+    }
+}
+
+// Per-provider overrides from config; unset fields keep the provider's defaults
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HeaderProfileConfig {
+    pub user_agents: Option<Vec<String>>,
+    pub referer: Option<String>,
+    pub origin: Option<String>,
+    pub accept_language: Option<String>,
+    pub rotation: Option<UserAgentRotation>,
+}
+
+/// Browser-like headers a client sends to one provider
+#[derive(Clone, Debug)]
+pub struct HeaderProfile {
+    pub user_agents: Vec<String>,
+    pub referer: String,
+    pub origin: String,
+    pub accept_language: String,
+    pub rotation: UserAgentRotation,
+    next_index: Arc<AtomicUsize>, // Round-robin position, shared by clones
+}
+
+impl HeaderProfile {
+    pub fn new(referer: &str, origin: &str) -> Self {
+        Self {
+            user_agents: DEFAULT_USER_AGENTS.iter().map(|s| s.to_string()).collect(),
+            referer: referer.to_string(),
+            origin: origin.to_string(),
+            accept_language: DEFAULT_ACCEPT_LANGUAGE.to_string(),
+            rotation: UserAgentRotation::default(),
+            next_index: Arc::default(),
+        }
+    }
+
+    pub fn with_overrides(mut self, overrides: &HeaderProfileConfig) -> Self {
+        if let Some(user_agents) = overrides.user_agents.as_ref().filter(|agents| !agents.is_empty()) {
+            self.user_agents = user_agents.clone();
+        }
+        if let Some(referer) = &overrides.referer {
+            self.referer = referer.clone();
+        }
+        if let Some(origin) = &overrides.origin {
+            self.origin = origin.clone();
+        }
+        if let Some(accept_language) = &overrides.accept_language {
+            self.accept_language = accept_language.clone();
+        }
+        if let Some(rotation) = overrides.rotation {
+            self.rotation = rotation;
+        }
+        self
+    }
+
+    /// Next user agent according to the rotation policy
+    pub fn next_user_agent(&self) -> String {
+        let index = match self.rotation {
+            UserAgentRotation::Fixed => 0,
+            UserAgentRotation::Random => rand::random_range(0..self.user_agents.len()),
+            UserAgentRotation::RoundRobin => {
+                self.next_index.fetch_add(1, Ordering::Relaxed) % self.user_agents.len()
+            }
+        };
+        self.user_agents[index].clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_and_rotation() {
+        let overrides = HeaderProfileConfig {
+            user_agents: Some(vec!["agent-a".to_string(), "agent-b".to_string()]),
+            referer: Some("https://example.com/".to_string()),
+            rotation: Some(UserAgentRotation::RoundRobin),
+            ..HeaderProfileConfig::default()
+        };
+        let profile = HeaderProfile::new("https://provider/", "https://provider").with_overrides(&overrides);
+        assert_eq!(profile.referer, "https://example.com/");
+        assert_eq!(profile.origin, "https://provider");
+        assert_eq!(profile.accept_language, DEFAULT_ACCEPT_LANGUAGE);
+        let agents: Vec<String> = (0..3).map(|_| profile.next_user_agent()).collect();
+        assert_eq!(agents, vec!["agent-a", "agent-b", "agent-a"]);
+        // Clones, e.g. of a client shared across tasks, continue the same rotation
+        assert_eq!(profile.clone().next_user_agent(), "agent-b");
+        assert_eq!(profile.next_user_agent(), "agent-a");
+
+        // An empty pool in config keeps the defaults
+        let empty = HeaderProfileConfig { user_agents: Some(Vec::new()), ..HeaderProfileConfig::default() };
+        let profile = HeaderProfile::new("r", "o").with_overrides(&empty);
+        assert_eq!(profile.user_agents.len(), DEFAULT_USER_AGENTS.len());
+        assert_eq!("round_robin".parse(), Ok(UserAgentRotation::RoundRobin));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::utils::metrics;
+use reqwest::{Client, Version};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::{info, warn};
+
+// Connection pool settings shared by all upstream provider clients
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HttpPoolConfig {
+    pub pool_max_idle_per_host: usize,
+    pub pool_idle_timeout_secs: u64,
+    pub tcp_keepalive_secs: u64,
+    pub tcp_nodelay: bool,
+    pub http2_prior_knowledge: bool, // Skip ALPN and speak HTTP/2 directly (only for providers known to support it)
+    pub timeout_secs: u64,
+}
+
+impl Default for HttpPoolConfig {
+    fn default() -> Self {
+        Self {
+            pool_max_idle_per_host: 16,
+            pool_idle_timeout_secs: 90,
+            tcp_keepalive_secs: 60,
+            tcp_nodelay: true,
+            http2_prior_knowledge: false, // HTTP/2 is still negotiated via ALPN over TLS
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// Build a pooled client; clones share the same connection pool
+pub fn build_client(config: &HttpPoolConfig) -> reqwest::Result<Client> {
+    let mut builder = Client::builder()
+        .pool_max_idle_per_host(config.pool_max_idle_per_host)
+        .pool_idle_timeout(Duration::from_secs(config.pool_idle_timeout_secs))
+        .tcp_keepalive(Duration::from_secs(config.tcp_keepalive_secs))
+        .tcp_nodelay(config.tcp_nodelay)
+        .timeout(Duration::from_secs(config.timeout_secs));
+    if config.http2_prior_knowledge {
+        builder = builder.http2_prior_knowledge();
+    }
+    builder.build()
+}
+
+static SHARED_CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// Build the process-wide provider client from config; call once at startup before any client is used
+pub fn init_shared_client(config: &HttpPoolConfig) {
+    match build_client(config) {
+        Ok(client) => {
+            if SHARED_CLIENT.set(client).is_err() {
+                warn!("Shared HTTP client already initialized, ignoring new pool settings");
+            } else {
+                info!(?config, "Initialized shared HTTP client");
+            }
+        }
+        Err(e) => warn!(error = ?e, "Invalid HTTP pool settings, using defaults"),
+    }
+}
+
+/// The shared provider client, with default pool settings if not initialized
+pub fn shared_client() -> Client {
+    SHARED_CLIENT
+        .get_or_init(|| build_client(&HttpPoolConfig::default()).unwrap_or_default())
+        .clone()
+}
+
+fn version_label(version: Version) -> &'static str {
+    match version {
+        Version::HTTP_09 => "http0.9",
+        Version::HTTP_10 => "http1.0",
+        Version::HTTP_11 => "http1.1",
+        Version::HTTP_2 => "http2",
+        Version::HTTP_3 => "http3",
+        _ => "other",
+    }
+}
+
+/// Count a provider response by negotiated HTTP version, e.g. `http.vci.http2`
+pub fn record_response_version(provider: &str, version: Version) {
+    metrics::increment_counter(&format!("http.{}.{}", provider, version_label(version)), 1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_config_builds_and_labels_versions() {
+        assert!(build_client(&HttpPoolConfig::default()).is_ok());
+        let http2 = HttpPoolConfig { http2_prior_knowledge: true, ..HttpPoolConfig::default() };
+        assert!(build_client(&http2).is_ok());
+
+        record_response_version("test", Version::HTTP_2);
+        record_response_version("test", Version::HTTP_2);
+        assert_eq!(metrics::get_performance_report().counters["http.test.http2"], 2);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::utils::integrity::sha256_hex;
+use chrono::{DateTime, Utc};
+use std::ops::Range;
+
+/// Result of evaluating a `Range` header against a body of known length
+#[derive(Debug, PartialEq)]
+pub enum RangeRequest {
+    /// No usable range requested - serve the whole body
+    Full,
+    /// A single satisfiable byte range (end exclusive)
+    Partial(Range<usize>),
+    /// The requested range lies outside the body
+    Unsatisfiable,
+}
+
+/// Compute a strong ETag for a body so clients can resume against the same snapshot. SHA-256
+/// rather than `DefaultHasher`, so the tag survives a restart on a newer toolchain.
+pub fn compute_etag(content: &[u8]) -> String {
+    format!("\"{}-{:x}\"", &sha256_hex(content)[..16], content.len())
+}
+
+/// Parse a `Range` header value (e.g. `bytes=100-199`, `bytes=100-`, `bytes=-500`).
+/// Only single ranges are supported; anything else falls back to a full response.
+pub fn parse_range(header_value: &str, content_len: usize) -> RangeRequest {
+    let spec = match header_value.trim().strip_prefix("bytes=") {
+        Some(spec) => spec.trim(),
+        None => return RangeRequest::Full,
+    };
+
+    // Multi-range requests are allowed to be ignored per RFC 9110
+    if spec.contains(',') {
+        return RangeRequest::Full;
+    }
+
+    let (start_str, end_str) = match spec.split_once('-') {
+        Some(parts) => parts,
+        None => return RangeRequest::Full,
+    };
+
+    let (start, end) = if start_str.is_empty() {
+        // Suffix range: last N bytes
+        let suffix_len: usize = match end_str.parse() {
+            Ok(len) => len,
+            Err(_) => return RangeRequest::Full,
+        };
+        if suffix_len == 0 {
+            return RangeRequest::Unsatisfiable;
+        }
+        (content_len.saturating_sub(suffix_len), content_len)
+    } else {
+        let start: usize = match start_str.parse() {
+            Ok(start) => start,
+            Err(_) => return RangeRequest::Full,
+        };
+        let end = if end_str.is_empty() {
+            content_len
+        } else {
+            match end_str.parse::<usize>() {
+                // Saturate: a last byte of usize::MAX must not wrap the end below the start
+                Ok(last) if last >= start => last.saturating_add(1).min(content_len),
+                _ => return RangeRequest::Full,
+            }
+        };
+        (start, end)
+    };
+
+    if start >= content_len {
+        return RangeRequest::Unsatisfiable;
+    }
+
+    RangeRequest::Partial(start..end)
+}
+
+/// Whether an `If-Range` precondition allows serving a partial response
+pub fn if_range_matches(if_range: Option<&str>, etag: &str) -> bool {
+    match if_range {
+        Some(value) => value.trim() == etag,
+        None => true,
+    }
+}
+
+/// Whether an `If-None-Match` header matches the current ETag, allowing a 304 response
+pub fn if_none_match_matches(if_none_match: Option<&str>, etag: &str) -> bool {
+    match if_none_match {
+        Some(value) => value.split(',').map(str::trim).any(|tag| tag == "*" || tag == etag || tag.strip_prefix("W/") == Some(etag)),
+        None => false,
+    }
+}
+
+/// `Last-Modified` value (IMF-fixdate, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`)
+pub fn http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// Whether a GET can be answered with 304. `If-None-Match` decides when present; otherwise the
+/// response is unmodified if it last changed no later than `If-Modified-Since` (one-second resolution).
+pub fn not_modified(if_none_match: Option<&str>, if_modified_since: Option<&str>, etag: &str, last_modified: Option<DateTime<Utc>>) -> bool {
+    if if_none_match.is_some() {
+        return if_none_match_matches(if_none_match, etag);
+    }
+    let since = if_modified_since.and_then(|value| DateTime::parse_from_rfc2822(value.trim()).ok());
+    matches!((since, last_modified), (Some(since), Some(modified)) if modified.timestamp() <= since.timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range_forms() {
+        assert_eq!(parse_range("bytes=0-9", 100), RangeRequest::Partial(0..10));
+        assert_eq!(parse_range("bytes=90-", 100), RangeRequest::Partial(90..100));
+        assert_eq!(parse_range("bytes=-10", 100), RangeRequest::Partial(90..100));
+        assert_eq!(parse_range("bytes=50-500", 100), RangeRequest::Partial(50..100));
+        assert_eq!(parse_range("bytes=1-18446744073709551615", 100), RangeRequest::Partial(1..100));
+        assert_eq!(parse_range("bytes=0-18446744073709551615", 0), RangeRequest::Unsatisfiable);
+        assert_eq!(parse_range("bytes=100-", 100), RangeRequest::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-1,5-6", 100), RangeRequest::Full);
+        assert_eq!(parse_range("items=0-1", 100), RangeRequest::Full);
+    }
+
+    #[test]
+    fn test_if_range_and_etag() {
+        let etag = compute_etag(b"hello");
+        assert_eq!(etag, "\"2cf24dba5fb0a30e-5\"");
+        assert_ne!(etag, compute_etag(b"hello!"));
+        assert!(if_range_matches(None, &etag));
+        assert!(if_range_matches(Some(&etag), &etag));
+        assert!(!if_range_matches(Some("\"stale\""), &etag));
+        assert!(if_none_match_matches(Some(&format!("\"stale\", {}", etag)), &etag));
+        assert!(if_none_match_matches(Some("*"), &etag));
+        assert!(!if_none_match_matches(None, &etag));
+
+        let modified = DateTime::parse_from_rfc3339("2025-08-15T07:30:00.250Z").unwrap().with_timezone(&Utc);
+        let date = http_date(modified);
+        assert_eq!(date, "Fri, 15 Aug 2025 07:30:00 GMT");
+        assert!(not_modified(None, Some(&date), &etag, Some(modified)));
+        assert!(!not_modified(None, Some("Fri, 15 Aug 2025 07:29:59 GMT"), &etag, Some(modified)));
+        assert!(!not_modified(None, Some(&date), &etag, None));
+        // A stale ETag wins over a current date
+        assert!(!not_modified(Some("\"stale\""), Some(&date), &etag, Some(modified)));
+    }
+}
@@ -0,0 +1,164 @@
+use crate::utils::cache;
+use crate::utils::mirrors::RawMirrors;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use tracing::{debug, warn};
+
+// A new CSV with fewer rows than this share of the cached copy is treated as truncated
+const MIN_ROW_RATIO: f64 = 0.8;
+
+#[derive(Debug, PartialEq)]
+pub enum IntegrityError {
+    Empty,
+    RaggedRow { line: usize, expected: usize, found: usize },
+    TooFewRows { rows: usize, previous_rows: usize },
+    ChecksumMismatch { expected: String, actual: String },
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityError::Empty => write!(f, "file is empty or has no data rows"),
+            IntegrityError::RaggedRow { line, expected, found } => {
+                write!(f, "line {} has {} columns, expected {}", line, found, expected)
+            }
+            IntegrityError::TooFewRows { rows, previous_rows } => {
+                write!(f, "{} rows is too few compared to {} in the cached copy", rows, previous_rows)
+            }
+            IntegrityError::ChecksumMismatch { expected, actual } => {
+                write!(f, "sha256 {} does not match manifest {}", actual, expected)
+            }
+        }
+    }
+}
+
+/// Count columns in a CSV line, ignoring commas inside quoted fields
+fn column_count(line: &str) -> usize {
+    let mut in_quotes = false;
+    let mut columns = 1;
+    for c in line.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => columns += 1,
+            _ => {}
+        }
+    }
+    columns
+}
+
+fn data_rows(content: &str) -> impl Iterator<Item = &str> {
+    content.lines().skip(1).filter(|line| !line.trim().is_empty())
+}
+
+/// Check a CSV is structurally complete: a header, consistent column counts,
+/// and not drastically shorter than the copy it would replace. Returns the row count.
+pub fn validate_csv(content: &[u8], previous: Option<&[u8]>) -> Result<usize, IntegrityError> {
+    let text = String::from_utf8_lossy(content);
+    let header = text.lines().next().ok_or(IntegrityError::Empty)?;
+    let expected = column_count(header);
+
+    let mut rows = 0;
+    for (i, line) in data_rows(&text).enumerate() {
+        let found = column_count(line);
+        if found != expected {
+            return Err(IntegrityError::RaggedRow { line: i + 2, expected, found });
+        }
+        rows += 1;
+    }
+    if rows == 0 {
+        return Err(IntegrityError::Empty);
+    }
+
+    if let Some(previous) = previous {
+        let previous_rows = data_rows(&String::from_utf8_lossy(previous)).count();
+        if (rows as f64) < previous_rows as f64 * MIN_ROW_RATIO {
+            return Err(IntegrityError::TooFewRows { rows, previous_rows });
+        }
+    }
+
+    Ok(rows)
+}
+
+pub fn sha256_hex(content: &[u8]) -> String {
+    Sha256::digest(content).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Parse a `sha256sum`-style manifest: `<hex digest>  <path>` per line
+pub fn parse_manifest(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| {
+            let (digest, path) = line.trim().split_once(char::is_whitespace)?;
+            let path = path.trim().trim_start_matches('*').trim_start_matches("./");
+            Some((path.to_string(), digest.to_lowercase()))
+        })
+        .collect()
+}
+
+/// Load the checksum manifest through the raw cache, or None if it is unavailable
+async fn load_manifest(mirrors: &RawMirrors, manifest_path: &str) -> Option<HashMap<String, String>> {
+    let content = match cache::is_cache_valid(manifest_path).then(|| cache::read_cache(manifest_path).ok()).flatten() {
+        Some(content) => content,
+        None => match mirrors.fetch(manifest_path).await {
+            Ok(content) => {
+                if let Err(e) = cache::write_cache(manifest_path, &content) {
+                    warn!(manifest_path, ?e, "Failed to cache checksum manifest");
+                }
+                content
+            }
+            Err(e) => {
+                warn!(manifest_path, ?e, "Checksum manifest unavailable, skipping checksum validation");
+                return None;
+            }
+        },
+    };
+    Some(parse_manifest(&String::from_utf8_lossy(&content)))
+}
+
+/// Validate a downloaded file before it replaces the cached copy
+pub async fn validate_download(mirrors: &RawMirrors, path: &str, content: &[u8], previous: Option<&[u8]>) -> Result<(), IntegrityError> {
+    if path.ends_with(".csv") {
+        let rows = validate_csv(content, previous)?;
+        debug!(path, rows, "CSV structure validated");
+    }
+
+    if let Some(manifest_path) = mirrors.checksum_manifest()
+        && manifest_path != path
+        && let Some(manifest) = load_manifest(mirrors, manifest_path).await
+        && let Some(expected) = manifest.get(path)
+    {
+        let actual = sha256_hex(content);
+        if &actual != expected {
+            return Err(IntegrityError::ChecksumMismatch { expected: expected.clone(), actual });
+        }
+        debug!(path, "Checksum validated");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_csv_detects_truncation() {
+        let full = b"ticker,time,close\nVCB,2025-08-14,60.1\nVCB,2025-08-15,61.0\nACB,2025-08-14,\"25,5\"\nACB,2025-08-15,26.0\n";
+        assert_eq!(validate_csv(full, None), Ok(4));
+
+        let cut_mid_row = b"ticker,time,close\nVCB,2025-08-14,60.1\nVCB,2025-";
+        assert_eq!(validate_csv(cut_mid_row, None), Err(IntegrityError::RaggedRow { line: 3, expected: 3, found: 2 }));
+
+        let cut_at_row = b"ticker,time,close\nVCB,2025-08-14,60.1\n";
+        assert_eq!(validate_csv(cut_at_row, Some(full)), Err(IntegrityError::TooFewRows { rows: 1, previous_rows: 4 }));
+        assert_eq!(validate_csv(b"", None), Err(IntegrityError::Empty));
+    }
+
+    #[test]
+    fn test_manifest_parsing() {
+        let manifest = parse_manifest("ABCDEF  ticker_60_days.csv\n123456 *./market_data/VCB.csv\n\n");
+        assert_eq!(manifest.get("ticker_60_days.csv").map(String::as_str), Some("abcdef"));
+        assert_eq!(manifest.get("market_data/VCB.csv").map(String::as_str), Some("123456"));
+        assert_eq!(sha256_hex(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+}
@@ -0,0 +1,44 @@
+use futures_util::stream::{self, Stream};
+use serde_json::Value;
+use std::convert::Infallible;
+
+/// `prefix`, a JSON object of `entries`, then `suffix`, as a body stream. Each entry is rendered and
+/// serialized only when the stream reaches it, so a response over hundreds of symbols is never held
+/// in memory as one document.
+pub fn object<T, F>(prefix: String, entries: Vec<(String, T)>, suffix: &'static str, mut render: F) -> impl Stream<Item = Result<String, Infallible>> + Send
+where
+    T: Send + 'static,
+    F: FnMut(&str, T) -> Value + Send + 'static,
+{
+    let members = entries.into_iter().enumerate().map(move |(i, (key, value))| {
+        let separator = if i == 0 { "" } else { "," };
+        let value = render(&key, value);
+        format!("{}{}:{}", separator, Value::String(key), value)
+    });
+    let chunks = std::iter::once(format!("{}{{", prefix))
+        .chain(members)
+        .chain(std::iter::once(format!("}}{}", suffix)));
+    stream::iter(chunks.map(Ok))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_object_streams_one_entry_per_chunk() {
+        let entries = vec![("FPT".to_string(), 1), ("V\"X".to_string(), 2)];
+        let chunks: Vec<String> = object("{\"meta\":{},\"data\":".to_string(), entries, "}", |key, n| json!([key, n]))
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 4);
+        let body: Value = serde_json::from_str(&chunks.concat()).unwrap();
+        assert_eq!(body, json!({"meta": {}, "data": {"FPT": ["FPT", 1], "V\"X": ["V\"X", 2]}}));
+
+        let empty: Vec<String> = object(String::new(), Vec::<(String, u8)>::new(), "", |_, _| Value::Null).map(|chunk| chunk.unwrap()).collect().await;
+        assert_eq!(empty.concat(), "{}");
+    }
+}
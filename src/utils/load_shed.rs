@@ -0,0 +1,164 @@
+use crate::error::{ApiError, ErrorCode};
+use crate::export::TableFormat;
+use crate::utils::metrics;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+// Samples older than this no longer count towards a route's p95
+const LATENCY_WINDOW: Duration = Duration::from_secs(60);
+// A route's p95 is only trusted once it has this many recent samples
+const MIN_SAMPLES: usize = 20;
+const MAX_SAMPLES_PER_ROUTE: usize = 1000;
+const SHED_RETRY_AFTER_SECS: u64 = 10;
+// Appended to a route for its tabular exports, which are tracked and shed apart from its JSON responses
+const EXPORT_SUFFIX: &str = "?format=export";
+
+/// Key a request is tracked and shed under: its route, or the route's export key for `format=csv|parquet|arrow`
+pub fn route_key(route: &str, query: Option<&str>) -> String {
+    let export = query.is_some_and(|query| {
+        query.split('&').any(|pair| pair.split_once('=').is_some_and(|(name, value)| name == "format" && value.parse::<TableFormat>().is_ok()))
+    });
+    if export { format!("{}{}", route, EXPORT_SUFFIX) } else { route.to_string() }
+}
+
+/// Bulk downloads, exports, screens and analysis are shed first; everything else (quotes, health, gossip) is protected
+pub fn is_low_priority(route: &str) -> bool {
+    route.starts_with("/raw/")
+        || route.starts_with("/analysis/")
+        || route == "/leaderboard"
+        || route == "/screener"
+        || route.ends_with(EXPORT_SUFFIX)
+}
+
+#[derive(Default)]
+struct ShedState {
+    samples: HashMap<String, VecDeque<(Instant, Duration)>>,
+    shedding: bool,
+}
+
+impl ShedState {
+    fn p95(&mut self, route: &str, now: Instant) -> Option<Duration> {
+        let samples = self.samples.get_mut(route)?;
+        while samples.front().is_some_and(|(at, _)| now.duration_since(*at) > LATENCY_WINDOW) {
+            samples.pop_front();
+        }
+        if samples.len() < MIN_SAMPLES {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = samples.iter().map(|(_, elapsed)| *elapsed).collect();
+        sorted.sort();
+        let rank = ((sorted.len() as f64) * 0.95).ceil() as usize;
+        Some(sorted[rank.saturating_sub(1)])
+    }
+}
+
+/// Tracks per-route p95 latency and sheds low-priority requests while a protected route is over the SLO
+pub struct LoadShedder {
+    slo: Duration,
+    state: Mutex<ShedState>,
+}
+
+pub type SharedLoadShedder = Arc<LoadShedder>;
+
+impl LoadShedder {
+    pub fn new(slo: Duration) -> Self {
+        Self { slo, state: Mutex::new(ShedState::default()) }
+    }
+
+    fn record_at(&self, route: &str, at: Instant, elapsed: Duration) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let samples = state.samples.entry(route.to_string()).or_default();
+        samples.push_back((at, elapsed));
+        if samples.len() > MAX_SAMPLES_PER_ROUTE {
+            samples.pop_front();
+        }
+    }
+
+    /// Whether low-priority requests should be rejected right now
+    fn should_shed(&self, now: Instant) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let protected_routes: Vec<String> = state.samples.keys().filter(|route| !is_low_priority(route)).cloned().collect();
+        let worst = protected_routes.iter()
+            .filter_map(|route| state.p95(route, now).map(|p95| (route.clone(), p95)))
+            .max_by_key(|(_, p95)| *p95);
+        let over_slo = worst.as_ref().is_some_and(|(_, p95)| *p95 > self.slo);
+
+        if over_slo != state.shedding {
+            state.shedding = over_slo;
+            match &worst {
+                Some((route, p95)) if over_slo => {
+                    metrics::increment_counter("load_shed.activations", 1);
+                    warn!(route, p95_ms = p95.as_millis() as u64, slo_ms = self.slo.as_millis() as u64, "Latency over SLO, shedding low-priority requests");
+                }
+                _ => info!(slo_ms = self.slo.as_millis() as u64, "Latency back under SLO, no longer shedding"),
+            }
+        }
+        over_slo
+    }
+}
+
+/// Middleware: reject low-priority requests with 503 while shedding, and record latency for every route
+pub async fn load_shed_middleware(State(shedder): State<SharedLoadShedder>, request: Request, next: Next) -> Response {
+    let path = request.extensions().get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let route = route_key(&path, request.uri().query());
+
+    if is_low_priority(&route) && shedder.should_shed(Instant::now()) {
+        metrics::increment_counter("load_shed.rejected", 1);
+        metrics::increment_counter(&format!("load_shed.rejected:{}", route), 1);
+        warn!(route, "Shedding low-priority request");
+        return ApiError::new(ErrorCode::Overloaded, "Server is under heavy load, retry later")
+            .with_retry_after(SHED_RETRY_AFTER_SECS)
+            .into_response();
+    }
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    shedder.record_at(&route, start, start.elapsed());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sheds_only_while_protected_p95_over_slo() {
+        let shedder = LoadShedder::new(Duration::from_millis(500));
+        let start = Instant::now();
+
+        // Slow low-priority routes alone never trigger shedding
+        for _ in 0..MIN_SAMPLES {
+            shedder.record_at("/raw/{*path}", start, Duration::from_secs(3));
+            shedder.record_at("/tickers", start, Duration::from_millis(50));
+        }
+        assert!(!shedder.should_shed(start));
+
+        // Push /tickers p95 over the SLO
+        for _ in 0..MIN_SAMPLES {
+            shedder.record_at("/tickers", start, Duration::from_millis(900));
+        }
+        assert!(shedder.should_shed(start));
+
+        // Once the slow samples age out of the window, shedding stops
+        let later = start + LATENCY_WINDOW + Duration::from_secs(1);
+        for _ in 0..MIN_SAMPLES {
+            shedder.record_at("/tickers", later, Duration::from_millis(50));
+        }
+        assert!(!shedder.should_shed(later));
+        assert!(is_low_priority("/analysis/ma-distribution") && is_low_priority("/screener") && !is_low_priority("/health"));
+
+        // Exports are bulk downloads, shed and tracked apart from the route's JSON responses
+        assert_eq!(route_key("/tickers", Some("symbol=VCB&format=PARQUET")), "/tickers?format=export");
+        assert_eq!(route_key("/tickers", Some("format=json")), "/tickers");
+        assert!(is_low_priority(&route_key("/tickers", Some("format=csv"))) && !is_low_priority(&route_key("/tickers", None)));
+    }
+}
@@ -0,0 +1,56 @@
+use crate::data_structures::get_current_time;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+
+// Vietnamese exchanges trade on ICT (UTC+7, no DST). Trading dates are always derived
+// in this zone: a bar stamped 17:00 UTC already belongs to the next market day.
+pub const MARKET_TIMEZONE: Tz = chrono_tz::Asia::Ho_Chi_Minh;
+
+/// Market (ICT) calendar date of an instant
+pub fn market_date(time: DateTime<Utc>) -> NaiveDate {
+    time.with_timezone(&MARKET_TIMEZONE).date_naive()
+}
+
+/// Today's market date, honouring the debug time override
+pub fn market_today() -> NaiveDate {
+    market_date(get_current_time())
+}
+
+/// `YYYY-MM-DD` market date of an instant, as used in API output, CSV rows and partitions
+pub fn format_market_date(time: DateTime<Utc>) -> String {
+    market_date(time).format("%Y-%m-%d").to_string()
+}
+
+/// First instant of a market date (00:00 ICT)
+pub fn market_day_start(date: NaiveDate) -> DateTime<Utc> {
+    MARKET_TIMEZONE
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+        .single()
+        .map(|local| local.with_timezone(&Utc))
+        .unwrap_or_else(|| date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+}
+
+/// Last second of a market date (23:59:59 ICT), for inclusive range ends
+pub fn market_day_end(date: NaiveDate) -> DateTime<Utc> {
+    market_day_start(date + chrono::Duration::days(1)) - chrono::Duration::seconds(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dates_roll_over_at_ict_midnight_not_utc() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        // 16:59 UTC is 23:59 ICT, 17:00 UTC is already the next market day
+        assert_eq!(market_date(Utc.with_ymd_and_hms(2025, 8, 14, 16, 59, 59).unwrap()), date(2025, 8, 14));
+        assert_eq!(market_date(Utc.with_ymd_and_hms(2025, 8, 14, 17, 0, 0).unwrap()), date(2025, 8, 15));
+        assert_eq!(format_market_date(Utc.with_ymd_and_hms(2025, 12, 31, 18, 0, 0).unwrap()), "2026-01-01");
+
+        assert_eq!(market_day_start(date(2025, 8, 15)), Utc.with_ymd_and_hms(2025, 8, 14, 17, 0, 0).unwrap());
+        assert_eq!(market_day_end(date(2025, 8, 15)), Utc.with_ymd_and_hms(2025, 8, 15, 16, 59, 59).unwrap());
+        // An evening (UTC) fetch of a bar stamped at ICT midnight falls inside that day's range
+        let bar_time = Utc.with_ymd_and_hms(2025, 8, 14, 17, 0, 0).unwrap();
+        assert!(bar_time >= market_day_start(date(2025, 8, 15)) && bar_time <= market_day_end(date(2025, 8, 15)));
+    }
+}
@@ -0,0 +1,199 @@
+use crate::utils::market_time::market_date;
+use crate::utils::metrics;
+use crate::vci::OhlcvData;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, OnceLock};
+use tracing::{debug, info, warn};
+
+// Header carrying when a gossiped bar was fetched, as RFC 3339
+pub const OBSERVED_AT_HEADER: &str = "x-observed-at";
+
+// Stamped dates kept per symbol; older bars are settled and no longer contested
+const RETAINED_DATES: usize = 30;
+
+/// Kind of source a bar was observed from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BarOrigin {
+    Official, // Fetched from a provider after its session closed
+    Live,     // Fetched from a provider while its session is still trading
+    Gossip,   // Pushed by an internal peer
+}
+
+impl std::str::FromStr for BarOrigin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "official" => Ok(BarOrigin::Official),
+            "live" => Ok(BarOrigin::Live),
+            "gossip" => Ok(BarOrigin::Gossip),
+            other => Err(format!("Invalid bar origin '{}'. Expected official, live or gossip", other)),
+        }
+    }
+}
+
+/// Origin of a fetched bar: live while its date is the session still trading, official once it closed
+pub fn fetched_origin(date: NaiveDate, provisional_date: Option<NaiveDate>) -> BarOrigin {
+    if provisional_date.is_none_or(|provisional| date < provisional) {
+        BarOrigin::Official
+    } else {
+        BarOrigin::Live
+    }
+}
+
+/// Origins from highest to lowest precedence
+pub fn default_precedence() -> Vec<BarOrigin> {
+    vec![BarOrigin::Official, BarOrigin::Live, BarOrigin::Gossip]
+}
+
+/// The precedence if it names every origin exactly once, the default otherwise
+pub fn checked_precedence(precedence: Vec<BarOrigin>) -> Vec<BarOrigin> {
+    let complete = precedence.len() == 3 && default_precedence().iter().all(|origin| precedence.contains(origin));
+    if complete {
+        precedence
+    } else {
+        warn!(?precedence, "Merge precedence must list official, live and gossip once each, using the default");
+        default_precedence()
+    }
+}
+
+/// Parse `MERGE_PRECEDENCE` ("official,live,gossip", highest first)
+pub fn parse_precedence(value: &str) -> Vec<BarOrigin> {
+    match value.split(',').map(str::parse).collect::<Result<Vec<BarOrigin>, _>>() {
+        Ok(precedence) => checked_precedence(precedence),
+        Err(e) => {
+            warn!(error = %e, "Invalid MERGE_PRECEDENCE, using the default");
+            default_precedence()
+        }
+    }
+}
+
+// The observation a stored bar came from
+#[derive(Clone, Copy, Debug)]
+struct Stamp {
+    origin: BarOrigin,
+    observed_at: DateTime<Utc>,
+}
+
+struct MergePolicy {
+    precedence: Vec<BarOrigin>,
+    stamps: HashMap<String, BTreeMap<NaiveDate, Stamp>>,
+}
+
+impl MergePolicy {
+    fn new(precedence: Vec<BarOrigin>) -> Self {
+        Self { precedence, stamps: HashMap::new() }
+    }
+
+    fn rank(&self, origin: BarOrigin) -> usize {
+        self.precedence.iter().rev().position(|o| *o == origin).unwrap_or(0)
+    }
+
+    /// Whether an observation may replace the stored bar for `date`, recording it as the stored one if so.
+    /// A higher origin always wins; between equal origins the later observation does, whatever order
+    /// they arrive in. Bars without a stamp (restored from disk, synced from the core) give way to anything.
+    fn admit(&mut self, symbol: &str, date: NaiveDate, incoming: Stamp) -> bool {
+        if let Some(stored) = self.stamps.get(symbol).and_then(|stamps| stamps.get(&date)) {
+            let (incoming_rank, stored_rank) = (self.rank(incoming.origin), self.rank(stored.origin));
+            if incoming_rank < stored_rank || (incoming_rank == stored_rank && incoming.observed_at < stored.observed_at) {
+                return false;
+            }
+        }
+        let stamps = self.stamps.entry(symbol.to_string()).or_default();
+        stamps.insert(date, incoming);
+        while stamps.len() > RETAINED_DATES {
+            stamps.pop_first();
+        }
+        true
+    }
+}
+
+static PRECEDENCE: OnceLock<Vec<BarOrigin>> = OnceLock::new();
+
+/// Set the precedence between bar origins; call once at startup, before any bar is merged
+pub fn set_precedence(precedence: Vec<BarOrigin>) {
+    if PRECEDENCE.set(precedence.clone()).is_err() {
+        warn!("Merge precedence already set, ignoring new settings");
+    } else {
+        info!(?precedence, "Configured merge precedence");
+    }
+}
+
+fn policy() -> &'static Mutex<MergePolicy> {
+    static POLICY: OnceLock<Mutex<MergePolicy>> = OnceLock::new();
+    POLICY.get_or_init(|| Mutex::new(MergePolicy::new(PRECEDENCE.get_or_init(default_precedence).clone())))
+}
+
+/// Keep the bars that may replace what is stored for their dates, given each date's origin and
+/// when the source produced them. Admitted bars become the stored observation for their dates.
+pub fn admit_bars(symbol: &str, bars: Vec<OhlcvData>, observed_at: DateTime<Utc>, origin: impl Fn(NaiveDate) -> BarOrigin) -> Vec<OhlcvData> {
+    let mut policy = policy().lock().unwrap_or_else(|e| e.into_inner());
+    let offered = bars.len();
+    let admitted: Vec<OhlcvData> = bars.into_iter()
+        .filter(|bar| {
+            let date = market_date(bar.time);
+            policy.admit(symbol, date, Stamp { origin: origin(date), observed_at })
+        })
+        .collect();
+    let rejected = offered - admitted.len();
+    if rejected > 0 {
+        metrics::increment_counter("merge_policy.rejected", rejected as u64);
+        debug!(symbol, rejected, "Kept stored bars over lower-precedence or older observations");
+    }
+    admitted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 8, day).unwrap()
+    }
+
+    fn stamp(origin: BarOrigin, second: u32) -> Stamp {
+        Stamp { origin, observed_at: Utc.with_ymd_and_hms(2025, 8, 15, 8, 0, second).unwrap() }
+    }
+
+    #[test]
+    fn test_precedence_and_out_of_order_arrivals() {
+        assert_eq!(parse_precedence("Live, official,gossip"), vec![BarOrigin::Live, BarOrigin::Official, BarOrigin::Gossip]);
+        assert_eq!(parse_precedence("official,live"), default_precedence());
+        assert_eq!(parse_precedence("official,live,peer"), default_precedence());
+        assert_eq!(fetched_origin(date(14), Some(date(15))), BarOrigin::Official);
+        assert_eq!(fetched_origin(date(15), Some(date(15))), BarOrigin::Live);
+        assert_eq!(fetched_origin(date(15), None), BarOrigin::Official);
+
+        let mut policy = MergePolicy::new(default_precedence());
+        // Gossip fills a date nothing was stored for; the live fetch then replaces it
+        assert!(policy.admit("VCB", date(15), stamp(BarOrigin::Gossip, 5)));
+        assert!(policy.admit("VCB", date(15), stamp(BarOrigin::Live, 3)));
+        // A live bar observed earlier than the stored one arrives late and is dropped
+        assert!(policy.admit("VCB", date(15), stamp(BarOrigin::Live, 20)));
+        assert!(!policy.admit("VCB", date(15), stamp(BarOrigin::Live, 10)));
+        // The official close wins over anything later from a lower origin
+        assert!(policy.admit("VCB", date(15), stamp(BarOrigin::Official, 30)));
+        assert!(!policy.admit("VCB", date(15), stamp(BarOrigin::Live, 40)));
+        assert!(!policy.admit("VCB", date(15), stamp(BarOrigin::Gossip, 50)));
+        // Reapplying the same observation is harmless, and other symbols and dates are independent
+        assert!(policy.admit("VCB", date(15), stamp(BarOrigin::Official, 30)));
+        assert!(policy.admit("FPT", date(15), stamp(BarOrigin::Gossip, 50)));
+        assert!(policy.admit("VCB", date(14), stamp(BarOrigin::Gossip, 50)));
+
+        // Configured precedence, e.g. trusting peers over this node's own live fetch
+        let mut policy = MergePolicy::new(vec![BarOrigin::Official, BarOrigin::Gossip, BarOrigin::Live]);
+        assert!(policy.admit("VCB", date(15), stamp(BarOrigin::Gossip, 5)));
+        assert!(!policy.admit("VCB", date(15), stamp(BarOrigin::Live, 10)));
+
+        let mut policy = MergePolicy::new(default_precedence());
+        for day in 1..=31 {
+            assert!(policy.admit("VCB", date(day), stamp(BarOrigin::Official, 0)));
+        }
+        assert_eq!(policy.stamps["VCB"].len(), RETAINED_DATES);
+        assert!(!policy.stamps["VCB"].contains_key(&date(1)));
+    }
+}
@@ -0,0 +1,225 @@
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+// Prefix of every exported Prometheus metric
+const PROMETHEUS_NAMESPACE: &str = "aipriceaction";
+
+// Process-wide registry of named timings and counters
+#[derive(Default)]
+struct Registry {
+    histograms: BTreeMap<String, HistogramStats>,
+    counters: BTreeMap<String, u64>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct HistogramStats {
+    pub count: u64,
+    pub total_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    pub mean_ms: f64,
+    pub last_ms: f64,
+}
+
+impl HistogramStats {
+    fn record(&mut self, elapsed_ms: f64) {
+        if self.count == 0 || elapsed_ms < self.min_ms {
+            self.min_ms = elapsed_ms;
+        }
+        if elapsed_ms > self.max_ms {
+            self.max_ms = elapsed_ms;
+        }
+        self.count += 1;
+        self.total_ms += elapsed_ms;
+        self.mean_ms = self.total_ms / self.count as f64;
+        self.last_ms = elapsed_ms;
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct PerformanceReport {
+    pub timings: BTreeMap<String, HistogramStats>,
+    pub counters: BTreeMap<String, u64>,
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(Registry::default()))
+}
+
+/// Record a duration under a named timing
+pub fn record_duration(name: &str, elapsed: Duration) {
+    let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+    let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    registry.histograms.entry(name.to_string()).or_default().record(elapsed_ms);
+}
+
+/// Add to a named counter
+pub fn increment_counter(name: &str, by: u64) {
+    let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    *registry.counters.entry(name.to_string()).or_default() += by;
+}
+
+/// Snapshot of all recorded timings and counters
+pub fn get_performance_report() -> PerformanceReport {
+    let registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    PerformanceReport {
+        timings: registry.histograms.clone(),
+        counters: registry.counters.clone(),
+    }
+}
+
+/// Times a stage and records it in the registry when stopped or dropped
+pub struct Timer {
+    name: &'static str,
+    start: Instant,
+    recorded: bool,
+}
+
+impl Timer {
+    pub fn start(name: &'static str) -> Self {
+        Self { name, start: Instant::now(), recorded: false }
+    }
+
+    /// Stop the timer and return the elapsed time
+    pub fn stop(mut self) -> Duration {
+        self.finish()
+    }
+
+    fn finish(&mut self) -> Duration {
+        let elapsed = self.start.elapsed();
+        if !self.recorded {
+            self.recorded = true;
+            record_duration(self.name, elapsed);
+            debug!(stage = self.name, elapsed_ms = elapsed.as_millis() as u64, "Stage timing");
+        }
+        elapsed
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Middleware: time every request as `http.request:<route>` and count 4xx/5xx as `http.errors:<route>`
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let route = request.extensions().get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string()); // Raw paths would explode label cardinality
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    record_duration(&format!("http.request:{}", route), start.elapsed());
+    if response.status().is_client_error() || response.status().is_server_error() {
+        increment_counter(&format!("http.errors:{}", route), 1);
+    }
+    response
+}
+
+/// Registry keys are `name` or `name:route`; the route becomes a label
+fn prometheus_labels(key: &str) -> Vec<(&'static str, String)> {
+    match key.split_once(':') {
+        Some((name, route)) => vec![("name", name.to_string()), ("route", route.to_string())],
+        None => vec![("name", key.to_string())],
+    }
+}
+
+/// Prometheus text exposition format (version 0.0.4), one metric family at a time
+#[derive(Default)]
+pub struct PrometheusText(String);
+
+impl PrometheusText {
+    /// Write a family; each sample is (name suffix such as "_sum", labels, value)
+    pub fn family<I>(&mut self, name: &str, kind: &str, help: &str, samples: I)
+    where
+        I: IntoIterator<Item = (&'static str, Vec<(&'static str, String)>, f64)>,
+    {
+        let _ = writeln!(self.0, "# HELP {}_{} {}", PROMETHEUS_NAMESPACE, name, help);
+        let _ = writeln!(self.0, "# TYPE {}_{} {}", PROMETHEUS_NAMESPACE, name, kind);
+        for (suffix, labels, value) in samples {
+            let labels: Vec<String> = labels.iter()
+                .map(|(label, value)| format!("{}=\"{}\"", label, value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")))
+                .collect();
+            let labels = if labels.is_empty() { String::new() } else { format!("{{{}}}", labels.join(",")) };
+            let _ = writeln!(self.0, "{}_{}{}{} {}", PROMETHEUS_NAMESPACE, name, suffix, labels, value);
+        }
+    }
+
+    pub fn finish(self) -> String {
+        self.0
+    }
+}
+
+impl PerformanceReport {
+    /// Counters as one counter family and timings as summaries (sum and count, in seconds) plus their maximum
+    pub fn write_prometheus(&self, out: &mut PrometheusText) {
+        out.family("events_total", "counter", "Named event counters",
+            self.counters.iter().map(|(key, value)| ("", prometheus_labels(key), *value as f64)));
+        out.family("duration_seconds", "summary", "Named stage and request durations",
+            self.timings.iter().flat_map(|(key, stats)| [
+                ("_sum", prometheus_labels(key), stats.total_ms / 1000.0),
+                ("_count", prometheus_labels(key), stats.count as f64),
+            ]));
+        out.family("duration_max_seconds", "gauge", "Longest recorded duration",
+            self.timings.iter().map(|(key, stats)| ("", prometheus_labels(key), stats.max_ms / 1000.0)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer_and_counters_feed_report() {
+        let timer = Timer::start("test.stage");
+        timer.stop();
+        {
+            let _timer = Timer::start("test.stage");
+        }
+        record_duration("test.fixed", Duration::from_millis(5));
+        record_duration("test.fixed", Duration::from_millis(15));
+        increment_counter("test.counter", 2);
+        increment_counter("test.counter", 3);
+
+        let report = get_performance_report();
+        assert_eq!(report.timings["test.stage"].count, 2);
+        let fixed = &report.timings["test.fixed"];
+        assert_eq!((fixed.count, fixed.min_ms, fixed.max_ms, fixed.mean_ms), (2, 5.0, 15.0, 10.0));
+        assert_eq!(report.counters["test.counter"], 5);
+    }
+
+    #[test]
+    fn test_prometheus_text() {
+        let mut report = PerformanceReport::default();
+        report.counters.insert("worker.iterations".to_string(), 3);
+        report.counters.insert("http.errors:/stats/gaps/{symbol}".to_string(), 1);
+        let mut stats = HistogramStats::default();
+        stats.record(250.0);
+        stats.record(750.0);
+        report.timings.insert("http.request:/tickers".to_string(), stats);
+
+        let mut out = PrometheusText::default();
+        report.write_prometheus(&mut out);
+        out.family("up", "gauge", "Quote \"test\"", [("", vec![("node", "a\"b".to_string())], 1.0)]);
+        let text = out.finish();
+
+        assert!(text.contains("# TYPE aipriceaction_events_total counter\n"));
+        assert!(text.contains("aipriceaction_events_total{name=\"worker.iterations\"} 3\n"));
+        assert!(text.contains("aipriceaction_events_total{name=\"http.errors\",route=\"/stats/gaps/{symbol}\"} 1\n"));
+        assert!(text.contains("aipriceaction_duration_seconds_sum{name=\"http.request\",route=\"/tickers\"} 1\n"));
+        assert!(text.contains("aipriceaction_duration_seconds_count{name=\"http.request\",route=\"/tickers\"} 2\n"));
+        assert!(text.contains("aipriceaction_duration_max_seconds{name=\"http.request\",route=\"/tickers\"} 0.75\n"));
+        assert!(text.contains("aipriceaction_up{node=\"a\\\"b\"} 1\n"));
+    }
+}
@@ -0,0 +1,230 @@
+use reqwest::header::{ETAG, IF_RANGE, RANGE, RETRY_AFTER};
+use reqwest::{Client, StatusCode};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use crate::utils::http_client;
+use crate::utils::metrics::{self, Timer};
+use crate::utils::rate_limiter;
+use tracing::{debug, info, warn};
+
+pub const DEFAULT_RAW_MIRRORS: [&str; 2] = [
+    "https://raw.githubusercontent.com/quanhua92/aipriceaction-data/refs/heads/main/",
+    "https://cdn.jsdelivr.net/gh/quanhua92/aipriceaction-data@main/",
+];
+
+// A mirror is skipped for a cooldown after this many consecutive failures
+const FAILURE_THRESHOLD: u32 = 3;
+const FAILURE_COOLDOWN: Duration = Duration::from_secs(300);
+// Cap on a server-provided Retry-After so a bad header cannot disable a mirror for long
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(900);
+
+#[derive(Clone, Debug)]
+pub struct MirrorState {
+    pub base_url: String,
+    pub consecutive_failures: u32,
+    pub total_failures: u64,
+    pub total_successes: u64,
+    pub cooldown_until: Option<Instant>,
+}
+
+impl MirrorState {
+    fn new(base_url: String) -> Self {
+        Self { base_url, consecutive_failures: 0, total_failures: 0, total_successes: 0, cooldown_until: None }
+    }
+
+    fn is_cooling_down(&self, now: Instant) -> bool {
+        self.cooldown_until.is_some_and(|until| until > now)
+    }
+}
+
+#[derive(Debug)]
+pub enum MirrorFetchError {
+    NotFound,
+    AllMirrorsFailed,
+}
+
+/// Ordered list of base URLs for the data repository, tried in turn
+pub struct RawMirrors {
+    client: Client,
+    mirrors: Mutex<Vec<MirrorState>>,
+    checksum_manifest: Option<String>, // sha256sum-style manifest path in the data repo
+}
+
+pub type SharedRawMirrors = Arc<RawMirrors>;
+
+impl RawMirrors {
+    pub fn new(base_urls: &[String], checksum_manifest: Option<String>) -> Self {
+        let mirrors = base_urls.iter()
+            .map(|url| if url.ends_with('/') { url.clone() } else { format!("{}/", url) })
+            .map(MirrorState::new)
+            .collect();
+        Self { client: http_client::shared_client(), mirrors: Mutex::new(mirrors), checksum_manifest }
+    }
+
+    pub fn checksum_manifest(&self) -> Option<&str> {
+        self.checksum_manifest.as_deref()
+    }
+
+    /// Mirror indices to try, in configured order, with cooling-down mirrors moved last
+    fn attempt_order(mirrors: &[MirrorState], now: Instant) -> Vec<usize> {
+        let (ready, cooling): (Vec<usize>, Vec<usize>) = (0..mirrors.len()).partition(|&i| !mirrors[i].is_cooling_down(now));
+        ready.into_iter().chain(cooling).collect()
+    }
+
+    fn record_success(mirror: &mut MirrorState) {
+        mirror.consecutive_failures = 0;
+        mirror.total_successes += 1;
+        mirror.cooldown_until = None;
+    }
+
+    fn record_failure(mirror: &mut MirrorState, retry_after: Option<Duration>, now: Instant) {
+        mirror.consecutive_failures += 1;
+        mirror.total_failures += 1;
+        if let Some(retry_after) = retry_after {
+            mirror.cooldown_until = Some(now + retry_after.min(MAX_RETRY_AFTER));
+        } else if mirror.consecutive_failures >= FAILURE_THRESHOLD {
+            mirror.cooldown_until = Some(now + FAILURE_COOLDOWN);
+        }
+    }
+
+    /// Fetch a file from the first mirror that serves it.
+    /// A 404 is authoritative; throttling and server errors fall through to the next mirror.
+    pub async fn fetch(&self, path: &str) -> Result<Vec<u8>, MirrorFetchError> {
+        let order = {
+            let mirrors = self.mirrors.lock().await;
+            Self::attempt_order(&mirrors, Instant::now())
+                .into_iter()
+                .map(|i| (i, mirrors[i].base_url.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        for (index, base_url) in order {
+            let url = format!("{}{}", base_url, path);
+            debug!(path, url, "Fetching from mirror");
+
+            let timer = Timer::start("raw.mirror_fetch");
+            let result = self.fetch_resumable(&url).await;
+            timer.stop();
+
+            match result {
+                Ok(content) => {
+                    Self::record_success(&mut self.mirrors.lock().await[index]);
+                    info!(path, mirror = %base_url, content_size = content.len(), "Fetched from mirror");
+                    return Ok(content);
+                }
+                Err(MirrorAttemptError::NotFound) => {
+                    Self::record_success(&mut self.mirrors.lock().await[index]);
+                    return Err(MirrorFetchError::NotFound);
+                }
+                Err(MirrorAttemptError::Failed { reason, retry_after }) => {
+                    metrics::increment_counter("raw.mirror_failures", 1);
+                    let mut mirrors = self.mirrors.lock().await;
+                    Self::record_failure(&mut mirrors[index], retry_after, Instant::now());
+                    warn!(
+                        path,
+                        mirror = %base_url,
+                        reason,
+                        consecutive_failures = mirrors[index].consecutive_failures,
+                        "Mirror fetch failed, trying next mirror"
+                    );
+                }
+            }
+        }
+
+        Err(MirrorFetchError::AllMirrorsFailed)
+    }
+
+    /// Download a URL, resuming once with a Range request if the body is cut off mid-transfer
+    async fn fetch_resumable(&self, url: &str) -> Result<Vec<u8>, MirrorAttemptError> {
+        let host = rate_limiter::host_of(url);
+        rate_limiter::acquire(&host).await;
+        let mut response = self.client.get(url).send().await
+            .map_err(|e| MirrorAttemptError::failed(format!("request error: {}", e)))?;
+        http_client::record_response_version("raw", response.version());
+        check_status(&response)?;
+
+        let etag = response.headers().get(ETAG).cloned();
+        let mut content = Vec::new();
+        let mut resumed = false;
+
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => content.extend_from_slice(&chunk),
+                Ok(None) => return Ok(content),
+                Err(e) if !resumed && !content.is_empty() && etag.is_some() => {
+                    warn!(url, received = content.len(), error = %e, "Download interrupted, resuming");
+                    resumed = true;
+
+                    rate_limiter::acquire(&host).await;
+                    let mut request = self.client.get(url).header(RANGE, format!("bytes={}-", content.len()));
+                    if let Some(etag) = &etag {
+                        request = request.header(IF_RANGE, etag.clone());
+                    }
+                    response = request.send().await
+                        .map_err(|e| MirrorAttemptError::failed(format!("resume request error: {}", e)))?;
+                    check_status(&response)?;
+
+                    // If-Range mismatch means the file changed - start over with the full body
+                    if response.status() != StatusCode::PARTIAL_CONTENT {
+                        content.clear();
+                    }
+                }
+                Err(e) => return Err(MirrorAttemptError::failed(format!("body error: {}", e))),
+            }
+        }
+    }
+}
+
+enum MirrorAttemptError {
+    NotFound,
+    Failed { reason: String, retry_after: Option<Duration> },
+}
+
+impl MirrorAttemptError {
+    fn failed(reason: String) -> Self {
+        Self::Failed { reason, retry_after: None }
+    }
+}
+
+fn check_status(response: &reqwest::Response) -> Result<(), MirrorAttemptError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    if status == StatusCode::NOT_FOUND {
+        return Err(MirrorAttemptError::NotFound);
+    }
+
+    let retry_after = response.headers().get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs);
+    Err(MirrorAttemptError::Failed { reason: format!("status {}", status), retry_after })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failing_mirror_moves_last_after_threshold() {
+        let mut mirrors = vec![MirrorState::new("a/".to_string()), MirrorState::new("b/".to_string())];
+        let now = Instant::now();
+
+        for _ in 0..FAILURE_THRESHOLD - 1 {
+            RawMirrors::record_failure(&mut mirrors[0], None, now);
+        }
+        assert_eq!(RawMirrors::attempt_order(&mirrors, now), vec![0, 1]);
+
+        RawMirrors::record_failure(&mut mirrors[0], None, now);
+        assert_eq!(RawMirrors::attempt_order(&mirrors, now), vec![1, 0]);
+        assert_eq!(RawMirrors::attempt_order(&mirrors, now + FAILURE_COOLDOWN), vec![0, 1]);
+
+        // Throttling with Retry-After cools down immediately
+        RawMirrors::record_success(&mut mirrors[0]);
+        RawMirrors::record_failure(&mut mirrors[1], Some(Duration::from_secs(60)), now);
+        assert_eq!(RawMirrors::attempt_order(&mirrors, now), vec![0, 1]);
+        assert!(mirrors[1].is_cooling_down(now + Duration::from_secs(59)));
+    }
+}
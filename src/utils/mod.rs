@@ -1 +1,25 @@
 pub mod cache;
+pub mod change_log;
+pub mod corrections;
+pub mod freshness;
+pub mod gossip_dedup;
+pub mod header_profile;
+pub mod http_client;
+pub mod http_range;
+pub mod integrity;
+pub mod json_stream;
+pub mod market_time;
+pub mod load_shed;
+pub mod merge_policy;
+pub mod metrics;
+pub mod mirrors;
+pub mod object_store;
+pub mod precision;
+pub mod provider_quota;
+pub mod query_cost;
+pub mod rate_limiter;
+pub mod recalculation;
+pub mod symbol_stats;
+#[cfg(test)]
+pub mod test_bars;
+pub mod transitions;
@@ -0,0 +1,228 @@
+use chrono::{DateTime, Utc};
+use reqwest::{Client, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tracing::debug;
+
+// Connection settings for an S3-compatible bucket (AWS S3, MinIO, R2, ...)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ObjectStoreConfig {
+    pub endpoint: String, // e.g. "https://s3.ap-southeast-1.amazonaws.com" or "http://minio:9000"
+    pub bucket: String,
+    pub region: Option<String>,
+    pub access_key: String,
+    pub secret_key: String,
+    pub prefix: Option<String>, // Key prefix, e.g. "aipriceaction/"
+}
+
+#[derive(Debug)]
+pub enum ObjectStoreError {
+    InvalidEndpoint(String),
+    Request(reqwest::Error),
+    Status(StatusCode),
+}
+
+impl std::fmt::Display for ObjectStoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObjectStoreError::InvalidEndpoint(endpoint) => write!(f, "invalid endpoint: {}", endpoint),
+            ObjectStoreError::Request(e) => write!(f, "request failed: {}", e),
+            ObjectStoreError::Status(status) => write!(f, "unexpected status {}", status),
+        }
+    }
+}
+
+impl From<reqwest::Error> for ObjectStoreError {
+    fn from(e: reqwest::Error) -> Self {
+        ObjectStoreError::Request(e)
+    }
+}
+
+pub struct StoredObject {
+    pub content: Vec<u8>,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// Minimal S3 client (path-style GET/PUT signed with AWS Signature V4)
+pub struct ObjectStore {
+    client: Client,
+    endpoint: Url,
+    host: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    prefix: String,
+}
+
+pub type SharedObjectStore = Option<Arc<ObjectStore>>;
+
+const UNRESERVED: &[u8] = b"-_.~";
+
+/// URI-encode a key per SigV4 rules, keeping `/` separators
+fn uri_encode_path(path: &str) -> String {
+    path.bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || UNRESERVED.contains(&b) || b == b'/' {
+                (b as char).to_string()
+            } else {
+                format!("%{:02X}", b)
+            }
+        })
+        .collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(block.map(|b| b ^ 0x36));
+    inner.update(message);
+    let inner_hash = inner.finalize();
+
+    let mut outer = Sha256::new();
+    outer.update(block.map(|b| b ^ 0x5c));
+    outer.update(inner_hash);
+    outer.finalize().into()
+}
+
+fn derive_signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> [u8; 32] {
+    let k_date = hmac_sha256(format!("AWS4{}", secret_key).as_bytes(), date.as_bytes());
+    let k_region = hmac_sha256(&k_date, region.as_bytes());
+    let k_service = hmac_sha256(&k_region, service.as_bytes());
+    hmac_sha256(&k_service, b"aws4_request")
+}
+
+impl ObjectStore {
+    pub fn new(config: &ObjectStoreConfig) -> Result<Self, ObjectStoreError> {
+        let endpoint = Url::parse(config.endpoint.trim_end_matches('/'))
+            .map_err(|_| ObjectStoreError::InvalidEndpoint(config.endpoint.clone()))?;
+        let host = match (endpoint.host_str(), endpoint.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(ObjectStoreError::InvalidEndpoint(config.endpoint.clone())),
+        };
+
+        Ok(Self {
+            client: Client::new(),
+            endpoint,
+            host,
+            bucket: config.bucket.clone(),
+            region: config.region.clone().unwrap_or_else(|| "us-east-1".to_string()),
+            access_key: config.access_key.clone(),
+            secret_key: config.secret_key.clone(),
+            prefix: config.prefix.clone().unwrap_or_default(),
+        })
+    }
+
+    fn object_path(&self, key: &str) -> String {
+        uri_encode_path(&format!("/{}/{}{}", self.bucket, self.prefix, key.trim_start_matches('/')))
+    }
+
+    /// Headers for a signed request: (x-amz-date, x-amz-content-sha256, authorization)
+    fn sign(&self, method: &str, path: &str, payload: &[u8], now: DateTime<Utc>) -> (String, String, String) {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex(&Sha256::digest(payload));
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, self.host, payload_hash, amz_date, signed_headers, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date, scope, hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let signature = hex(&hmac_sha256(&derive_signing_key(&self.secret_key, &date, &self.region, "s3"), string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key, scope, signed_headers, signature
+        );
+
+        (amz_date, payload_hash, authorization)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.endpoint.as_str().trim_end_matches('/'), path)
+    }
+
+    /// Fetch an object, or None if it does not exist
+    pub async fn get(&self, key: &str) -> Result<Option<StoredObject>, ObjectStoreError> {
+        let path = self.object_path(key);
+        let (amz_date, payload_hash, authorization) = self.sign("GET", &path, b"", Utc::now());
+
+        let response = self.client.get(self.url(&path))
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("authorization", authorization)
+            .send()
+            .await?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => {
+                let last_modified = response.headers().get("last-modified")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| DateTime::parse_from_rfc2822(v).ok())
+                    .map(|t| t.with_timezone(&Utc));
+                let content = response.bytes().await?.to_vec();
+                debug!(key, content_size = content.len(), "Fetched object from store");
+                Ok(Some(StoredObject { content, last_modified }))
+            }
+            status => Err(ObjectStoreError::Status(status)),
+        }
+    }
+
+    pub async fn put(&self, key: &str, content: Vec<u8>) -> Result<(), ObjectStoreError> {
+        let path = self.object_path(key);
+        let (amz_date, payload_hash, authorization) = self.sign("PUT", &path, &content, Utc::now());
+
+        let response = self.client.put(self.url(&path))
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("authorization", authorization)
+            .body(content)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            debug!(key, "Stored object");
+            Ok(())
+        } else {
+            Err(ObjectStoreError::Status(response.status()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sigv4_primitives() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // AWS SigV4 documentation example
+        assert_eq!(
+            hex(&derive_signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam")),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+        assert_eq!(uri_encode_path("/bucket/market data/VCB.csv"), "/bucket/market%20data/VCB.csv");
+    }
+}
@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+// Decimal places per field class when rounding JSON output
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PrecisionConfig {
+    pub price_decimals: u32,   // open/high/low/close, moving averages, quoted prices
+    pub percent_decimals: u32, // Returns, gaps, fill rates, holdings
+    pub score_decimals: u32,   // MA scores and their distribution statistics
+}
+
+impl Default for PrecisionConfig {
+    fn default() -> Self {
+        Self { price_decimals: 2, percent_decimals: 2, score_decimals: 2 }
+    }
+}
+
+// Decimals beyond this are already past f64 precision for market values
+const MAX_DECIMALS: u32 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FieldClass {
+    Price,
+    Percent,
+    Score,
+}
+
+/// Classify a JSON field by name; unclassified fields are never rounded
+fn classify(field: &str) -> Option<FieldClass> {
+    match field {
+        "open" | "high" | "low" | "close" | "prev_close" | "ma10" | "ma20" | "ma50" | "current_price" | "prior_extreme"
+        | "net_flow" | "market_cap" | "macd" | "macd_signal" | "macd_histogram" | "bb_upper" | "bb_middle" | "bb_lower"
+        | "vwap20" | "anchored_vwap" | "foreign_net_value" | "buy_value" | "sell_value" | "net_value" | "net_value_window"
+        | "futures_close" | "index_close" | "basis" | "dollar_flow" => Some(FieldClass::Price),
+        "gap_pct" | "intraday_return_pct" | "min_gap_pct" | "avg_gap_pct" | "avg_abs_gap_pct" | "gap_up_fill_rate"
+        | "gap_down_fill_rate" | "percent_positive" | "percentage" | "percentile" | "forward_return_1w_pct"
+        | "forward_return_4w_pct" | "basis_pct" => Some(FieldClass::Percent),
+        "ma10_score" | "ma20_score" | "ma50_score" | "min" | "q1" | "median" | "q3" | "max" | "mean" | "strength" | "money_flow"
+        | "ma_score" | "relative_volume" | "trend" | "metric_value" | "trend_score" | "weighted_mean" | "rsi14" | "zscore" | "inflow_share" => Some(FieldClass::Score),
+        _ => None,
+    }
+}
+
+// Rounded per config by default; `precision=full` opts out
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PrecisionMode {
+    #[default]
+    Rounded,
+    Full,
+}
+
+impl std::str::FromStr for PrecisionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "default" | "rounded" => Ok(Self::Rounded),
+            "full" => Ok(Self::Full),
+            other => Err(format!("Invalid precision '{}'. Expected full or default", other)),
+        }
+    }
+}
+
+static PRECISION: OnceLock<PrecisionConfig> = OnceLock::new();
+
+/// Set the rounding applied to API output; call once at startup
+pub fn set_precision(config: PrecisionConfig) {
+    if PRECISION.set(config.clone()).is_err() {
+        warn!("Output precision already set, ignoring new settings");
+    } else {
+        info!(?config, "Configured output precision");
+    }
+}
+
+fn current() -> &'static PrecisionConfig {
+    PRECISION.get_or_init(PrecisionConfig::default)
+}
+
+fn round_to(value: f64, decimals: u32) -> f64 {
+    let factor = 10f64.powi(decimals.min(MAX_DECIMALS) as i32);
+    let rounded = (value * factor).round() / factor;
+    if rounded.is_finite() { rounded } else { value }
+}
+
+/// Round classified float fields in place, at any depth
+pub fn round_value(value: &mut Value, config: &PrecisionConfig) {
+    match value {
+        Value::Object(map) => {
+            for (field, entry) in map.iter_mut() {
+                let decimals = classify(field).map(|class| match class {
+                    FieldClass::Price => config.price_decimals,
+                    FieldClass::Percent => config.percent_decimals,
+                    FieldClass::Score => config.score_decimals,
+                });
+                match (decimals, entry.as_f64()) {
+                    (Some(decimals), Some(number)) if entry.is_f64() => {
+                        *entry = serde_json::json!(round_to(number, decimals));
+                    }
+                    _ => round_value(entry, config),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| round_value(item, config)),
+        _ => {}
+    }
+}
+
+/// Serialize for an API response with the configured rounding unless `mode` is full
+pub fn to_json<T: Serialize>(value: &T, mode: PrecisionMode) -> Value {
+    let mut json = serde_json::to_value(value).unwrap_or(Value::Null);
+    if mode == PrecisionMode::Rounded {
+        round_value(&mut json, current());
+    }
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rounds_by_field_class() {
+        let config = PrecisionConfig { price_decimals: 1, ..PrecisionConfig::default() };
+        let mut value = json!({
+            "VCB": [{ "close": 3.0000000000000004, "high": 60.25, "volume": 1200 }],
+            "distribution": [{ "median": -1.23456, "count": 3, "percent_positive": 66.66666 }],
+            "coverage": 0.123456,
+            "largest_gap_up": null,
+        });
+        round_value(&mut value, &config);
+        assert_eq!(value, json!({
+            "VCB": [{ "close": 3.0, "high": 60.3, "volume": 1200 }],
+            "distribution": [{ "median": -1.23, "count": 3, "percent_positive": 66.67 }],
+            "coverage": 0.123456,
+            "largest_gap_up": null,
+        }));
+        assert_eq!("full".parse(), Ok(PrecisionMode::Full));
+        assert!("exact".parse::<PrecisionMode>().is_err());
+    }
+}
@@ -0,0 +1,217 @@
+use crate::utils::transitions;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+const MINUTE: Duration = Duration::from_secs(60);
+const HOUR: Duration = Duration::from_secs(3600);
+
+// Usage levels that trigger a warning before the provider starts blocking us
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuotaThresholds {
+    pub requests_per_minute: usize,
+    pub blocked_per_hour: usize, // 403 + 429 responses
+}
+
+impl Default for QuotaThresholds {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: 25, // VCI client self-limits at 30/min
+            blocked_per_hour: 3,
+        }
+    }
+}
+
+// Request accounting for one upstream provider, as reported in /health and /metrics
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProviderUsage {
+    pub requests_last_minute: usize,
+    pub requests_last_hour: usize,
+    pub blocked_last_hour: usize,
+    pub total_requests: u64,
+    pub status_403: u64,
+    pub status_429: u64,
+    pub server_errors: u64,
+    pub transport_errors: u64,
+    pub in_backoff: bool,
+    pub backoff_remaining_secs: f64,
+    pub under_pressure: bool, // A warning threshold is currently exceeded
+}
+
+#[derive(Default)]
+struct ProviderState {
+    requests: VecDeque<Instant>,
+    blocked: VecDeque<Instant>,
+    total_requests: u64,
+    status_403: u64,
+    status_429: u64,
+    server_errors: u64,
+    transport_errors: u64,
+    backoff_until: Option<Instant>,
+    under_pressure: bool,
+}
+
+impl ProviderState {
+    fn prune(&mut self, now: Instant) {
+        for timestamps in [&mut self.requests, &mut self.blocked] {
+            while timestamps.front().is_some_and(|at| now.duration_since(*at) > HOUR) {
+                timestamps.pop_front();
+            }
+        }
+    }
+
+    fn usage(&self, now: Instant, thresholds: &QuotaThresholds) -> ProviderUsage {
+        let requests_last_minute = self.requests.iter().filter(|at| now.duration_since(**at) <= MINUTE).count();
+        let backoff_remaining = self.backoff_until.map(|until| until.saturating_duration_since(now)).unwrap_or_default();
+        ProviderUsage {
+            requests_last_minute,
+            requests_last_hour: self.requests.len(),
+            blocked_last_hour: self.blocked.len(),
+            total_requests: self.total_requests,
+            status_403: self.status_403,
+            status_429: self.status_429,
+            server_errors: self.server_errors,
+            transport_errors: self.transport_errors,
+            in_backoff: !backoff_remaining.is_zero(),
+            backoff_remaining_secs: backoff_remaining.as_secs_f64(),
+            under_pressure: requests_last_minute >= thresholds.requests_per_minute || self.blocked.len() >= thresholds.blocked_per_hour,
+        }
+    }
+
+    /// Log when the provider crosses a warning threshold in either direction
+    fn check_pressure(&mut self, provider: &str, now: Instant, thresholds: &QuotaThresholds) {
+        self.prune(now);
+        let usage = self.usage(now, thresholds);
+        if usage.under_pressure == self.under_pressure {
+            return;
+        }
+        self.under_pressure = usage.under_pressure;
+        if usage.under_pressure {
+            warn!(
+                provider,
+                requests_last_minute = usage.requests_last_minute,
+                blocked_last_hour = usage.blocked_last_hour,
+                requests_per_minute_threshold = thresholds.requests_per_minute,
+                blocked_per_hour_threshold = thresholds.blocked_per_hour,
+                "Provider quota pressure building, risk of being blocked"
+            );
+            transitions::record(
+                "provider_quota",
+                "normal",
+                "under_pressure",
+                format!("{}: {} requests in the last minute, {} blocked in the last hour", provider, usage.requests_last_minute, usage.blocked_last_hour),
+            );
+        } else {
+            info!(provider, "Provider quota pressure cleared");
+            transitions::record("provider_quota", "under_pressure", "normal", format!("{}: pressure cleared", provider));
+        }
+    }
+
+    fn record_status(&mut self, status: u16, now: Instant) {
+        match status {
+            403 => self.status_403 += 1,
+            429 => self.status_429 += 1,
+            500..=599 => self.server_errors += 1,
+            _ => {}
+        }
+        if status == 403 || status == 429 {
+            self.blocked.push_back(now);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    thresholds: QuotaThresholds,
+    providers: BTreeMap<String, ProviderState>,
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(Registry::default()))
+}
+
+fn with_provider(provider: &str, update: impl FnOnce(&mut ProviderState, Instant)) {
+    let now = Instant::now();
+    let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let thresholds = registry.thresholds.clone();
+    let state = registry.providers.entry(provider.to_string()).or_default();
+    update(state, now);
+    state.check_pressure(provider, now, &thresholds);
+}
+
+pub fn set_thresholds(thresholds: QuotaThresholds) {
+    registry().lock().unwrap_or_else(|e| e.into_inner()).thresholds = thresholds;
+}
+
+/// Count an outgoing request to a provider
+pub fn record_request(provider: &str) {
+    with_provider(provider, |state, now| {
+        state.total_requests += 1;
+        state.requests.push_back(now);
+    });
+}
+
+/// Record a response status; 403/429 count towards the blocked threshold
+pub fn record_status(provider: &str, status: u16) {
+    with_provider(provider, |state, now| state.record_status(status, now));
+}
+
+pub fn record_transport_error(provider: &str) {
+    with_provider(provider, |state, _| state.transport_errors += 1);
+}
+
+/// Record that requests to a provider are paused for `delay`
+pub fn record_backoff(provider: &str, delay: Duration) {
+    with_provider(provider, |state, now| state.backoff_until = Some(now + delay));
+}
+
+/// Current usage for every provider seen so far
+pub fn usage_report() -> BTreeMap<String, ProviderUsage> {
+    let now = Instant::now();
+    let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let thresholds = registry.thresholds.clone();
+    registry.providers.iter_mut()
+        .map(|(provider, state)| {
+            state.prune(now);
+            (provider.clone(), state.usage(now, &thresholds))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_windows_and_pressure() {
+        let thresholds = QuotaThresholds { requests_per_minute: 3, blocked_per_hour: 2 };
+        let start = Instant::now();
+        let mut state = ProviderState::default();
+
+        for _ in 0..2 {
+            state.total_requests += 1;
+            state.requests.push_back(start);
+        }
+        state.record_status(429, start);
+        state.check_pressure("vci", start, &thresholds);
+        assert!(!state.under_pressure);
+
+        state.record_status(403, start);
+        state.backoff_until = Some(start + Duration::from_secs(4));
+        state.check_pressure("vci", start, &thresholds);
+        let usage = state.usage(start, &thresholds);
+        assert!(state.under_pressure && usage.in_backoff);
+        assert_eq!((usage.status_403, usage.status_429, usage.blocked_last_hour), (1, 1, 2));
+
+        // Two minutes later the minute window is empty but blocks still count; an hour later all clear
+        let later = start + Duration::from_secs(120);
+        let usage = state.usage(later, &thresholds);
+        assert_eq!((usage.requests_last_minute, usage.requests_last_hour, usage.in_backoff), (0, 2, false));
+        state.check_pressure("vci", start + HOUR + MINUTE, &thresholds);
+        assert!(!state.under_pressure);
+        assert_eq!(state.usage(start + HOUR + MINUTE, &thresholds).total_requests, 2);
+    }
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+// Per-key budgets cover this sliding window
+const BUDGET_WINDOW: Duration = Duration::from_secs(60);
+// Fields per bar in plain JSON ticker output (time, open, high, low, close, volume, symbol)
+pub const OHLCV_FIELDS: usize = 7;
+
+// Ceilings on the number of cells (rows x columns) a client may request; 0 disables a ceiling
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QueryCostLimits {
+    pub max_cells_per_request: u64,
+    pub max_cells_per_key_per_minute: u64,
+}
+
+impl Default for QueryCostLimits {
+    fn default() -> Self {
+        Self {
+            max_cells_per_request: 5_000_000,         // ~300 symbols x 2 years x all CSV columns
+            max_cells_per_key_per_minute: 20_000_000,
+        }
+    }
+}
+
+/// Size of a response before it is serialized
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct QueryCost {
+    pub symbols: usize,
+    pub rows: usize,
+    pub columns: usize,
+}
+
+impl QueryCost {
+    pub fn cells(&self) -> u64 {
+        self.rows as u64 * self.columns as u64
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CostRejection {
+    TooLarge { cells: u64, limit: u64 },
+    BudgetExceeded { used: u64, limit: u64, retry_after_secs: u64 },
+}
+
+#[derive(Default)]
+struct Registry {
+    limits: QueryCostLimits,
+    usage: HashMap<String, VecDeque<(Instant, u64)>>,
+}
+
+impl Registry {
+    fn check_at(&mut self, key: &str, cells: u64, now: Instant) -> Result<(), CostRejection> {
+        let limits = self.limits.clone();
+        if limits.max_cells_per_request > 0 && cells > limits.max_cells_per_request {
+            return Err(CostRejection::TooLarge { cells, limit: limits.max_cells_per_request });
+        }
+
+        // Drop expired entries everywhere so idle clients do not accumulate
+        self.usage.retain(|_, entries| {
+            while entries.front().is_some_and(|(at, _)| now.duration_since(*at) >= BUDGET_WINDOW) {
+                entries.pop_front();
+            }
+            !entries.is_empty()
+        });
+        if limits.max_cells_per_key_per_minute == 0 {
+            return Ok(());
+        }
+
+        let entries = self.usage.entry(key.to_string()).or_default();
+        let used: u64 = entries.iter().map(|(_, cells)| cells).sum();
+        if used + cells > limits.max_cells_per_key_per_minute {
+            let retry_after_secs = entries.front()
+                .map(|(at, _)| BUDGET_WINDOW.saturating_sub(now.duration_since(*at)).as_secs().max(1))
+                .unwrap_or(BUDGET_WINDOW.as_secs());
+            return Err(CostRejection::BudgetExceeded { used, limit: limits.max_cells_per_key_per_minute, retry_after_secs });
+        }
+        entries.push_back((now, cells));
+        Ok(())
+    }
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(Registry::default()))
+}
+
+pub fn set_limits(limits: QueryCostLimits) {
+    info!(?limits, "Configured query cost limits");
+    registry().lock().unwrap_or_else(|e| e.into_inner()).limits = limits;
+}
+
+/// Admit a query for `key` (the client), charging its cost against the key's budget
+pub fn check(key: &str, cost: &QueryCost) -> Result<(), CostRejection> {
+    let result = registry().lock().unwrap_or_else(|e| e.into_inner()).check_at(key, cost.cells(), Instant::now());
+    if let Err(rejection) = &result {
+        warn!(key, ?cost, ?rejection, "Rejected query over cost limit");
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_and_key_ceilings() {
+        let mut registry = Registry {
+            limits: QueryCostLimits { max_cells_per_request: 100, max_cells_per_key_per_minute: 150 },
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let cost = QueryCost { symbols: 2, rows: 10, columns: 8 };
+        assert_eq!(cost.cells(), 80);
+
+        assert_eq!(registry.check_at("a", 120, start), Err(CostRejection::TooLarge { cells: 120, limit: 100 }));
+        assert!(registry.check_at("a", cost.cells(), start).is_ok());
+        assert_eq!(
+            registry.check_at("a", 80, start + Duration::from_secs(20)),
+            Err(CostRejection::BudgetExceeded { used: 80, limit: 150, retry_after_secs: 40 })
+        );
+        // Other keys have their own budget, and spent budget frees up after the window
+        assert!(registry.check_at("b", 80, start).is_ok());
+        assert!(registry.check_at("a", 80, start + BUDGET_WINDOW).is_ok());
+
+        registry.limits = QueryCostLimits { max_cells_per_request: 0, max_cells_per_key_per_minute: 0 };
+        assert!(registry.check_at("a", u64::MAX, start + BUDGET_WINDOW).is_ok());
+    }
+}
@@ -0,0 +1,159 @@
+use crate::utils::metrics;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+// Hosts without a configured budget get this many requests a minute
+const DEFAULT_REQUESTS_PER_MINUTE: u32 = 30;
+
+/// Request budget for one upstream host, shared by every client and task that calls it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HostBudget {
+    pub requests_per_minute: u32,
+    pub burst: u32, // Requests that may go out back to back after an idle spell
+}
+
+impl HostBudget {
+    pub fn new(requests_per_minute: u32) -> Self {
+        Self { requests_per_minute, burst: (requests_per_minute / 6).max(1) } // About ten seconds' worth
+    }
+}
+
+/// Budgets keyed by host name, e.g. "trading.vietcap.com.vn"
+pub type HostBudgets = BTreeMap<String, HostBudget>;
+
+/// Built-in budgets: the VCI and TCBS APIs at 30 a minute, the raw data mirrors at 120
+pub fn default_budgets() -> HostBudgets {
+    BTreeMap::from([
+        ("trading.vietcap.com.vn".to_string(), HostBudget::new(30)),
+        ("apipubaws.tcbs.com.vn".to_string(), HostBudget::new(30)),
+        ("raw.githubusercontent.com".to_string(), HostBudget::new(120)),
+        ("cdn.jsdelivr.net".to_string(), HostBudget::new(120)),
+    ])
+}
+
+/// Parse `RATE_LIMITS` entries `host=requests_per_minute[:burst]`, separated by commas
+pub fn parse_budgets(value: &str) -> HostBudgets {
+    value.split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .filter_map(|entry| {
+            let parsed = entry.split_once('=').and_then(|(host, spec)| {
+                let (per_minute, burst) = spec.split_once(':').map_or((spec, None), |(per_minute, burst)| (per_minute, Some(burst)));
+                let mut budget = HostBudget::new(per_minute.trim().parse().ok().filter(|n| *n > 0)?);
+                if let Some(burst) = burst {
+                    budget.burst = burst.trim().parse().ok().filter(|n| *n > 0)?;
+                }
+                Some((host.trim().to_lowercase(), budget))
+            });
+            if parsed.is_none() {
+                warn!(entry, "Ignoring invalid rate limit");
+            }
+            parsed
+        })
+        .collect()
+}
+
+// Token bucket that hands out future slots: a caller takes a token even when none is left and
+// waits until it would have refilled, so concurrent callers queue in order without holding a lock
+#[derive(Debug)]
+struct TokenBucket {
+    budget: HostBudget,
+    tokens: f64, // Negative while callers are waiting for refills
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(budget: HostBudget, now: Instant) -> Self {
+        Self { tokens: budget.burst as f64, budget, updated: now }
+    }
+
+    fn refill_per_sec(&self) -> f64 {
+        self.budget.requests_per_minute as f64 / 60.0
+    }
+
+    /// Take a token, returning how long to wait before using it
+    fn reserve(&mut self, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec()).min(self.budget.burst as f64);
+        self.updated = now;
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.refill_per_sec())
+        }
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    budgets: HostBudgets,
+    buckets: HashMap<String, TokenBucket>,
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(Registry { budgets: default_budgets(), buckets: HashMap::new() }))
+}
+
+/// Set the per-host budgets; call once at startup, before any request goes out
+pub fn set_budgets(budgets: HostBudgets) {
+    info!(?budgets, "Configured upstream rate limits");
+    let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    registry.budgets = budgets;
+    registry.buckets.clear();
+}
+
+/// Host part of a URL, the key budgets are looked up by
+pub fn host_of(url: &str) -> String {
+    reqwest::Url::parse(url).ok().and_then(|url| url.host_str().map(str::to_lowercase)).unwrap_or_default()
+}
+
+/// Wait for a slot to send one request to `host`
+pub async fn acquire(host: &str) {
+    let wait = {
+        let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+        let budget = registry.budgets.get(host).cloned().unwrap_or_else(|| HostBudget::new(DEFAULT_REQUESTS_PER_MINUTE));
+        let now = Instant::now();
+        registry.buckets.entry(host.to_string()).or_insert_with(|| TokenBucket::new(budget, now)).reserve(now)
+    };
+    metrics::increment_counter(&format!("rate_limiter.{}_acquired", host), 1);
+    if !wait.is_zero() {
+        metrics::increment_counter(&format!("rate_limiter.{}_delayed", host), 1);
+        metrics::record_duration(&format!("rate_limiter.{}_wait", host), wait);
+        debug!(host, wait_ms = wait.as_millis() as u64, "Waiting for upstream rate limit");
+        tokio::time::sleep(wait).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_parsing_and_shared_bucket() {
+        let budgets = parse_budgets("Trading.Vietcap.com.vn=60, apipubaws.tcbs.com.vn=12:4,bad=x,zero=0");
+        assert_eq!(budgets.len(), 2);
+        assert_eq!(budgets["trading.vietcap.com.vn"], HostBudget { requests_per_minute: 60, burst: 10 });
+        assert_eq!(budgets["apipubaws.tcbs.com.vn"], HostBudget { requests_per_minute: 12, burst: 4 });
+        assert_eq!(host_of("https://trading.vietcap.com.vn/api/chart/OHLCChart/gap"), "trading.vietcap.com.vn");
+
+        // 12 a minute with a burst of 4: four go out at once, then one every 5 seconds, in order
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(budgets["apipubaws.tcbs.com.vn"].clone(), start);
+        for _ in 0..4 {
+            assert_eq!(bucket.reserve(start), Duration::ZERO);
+        }
+        let waits: Vec<u64> = (0..3).map(|_| bucket.reserve(start).as_millis() as u64).collect();
+        assert_eq!(waits, vec![5_000, 10_000, 15_000]);
+
+        // Once the queue drains, idle time refills only up to the burst
+        let later = start + Duration::from_secs(600);
+        for _ in 0..4 {
+            assert_eq!(bucket.reserve(later), Duration::ZERO);
+        }
+        assert_eq!(bucket.reserve(later).as_millis(), 5_000);
+    }
+}
@@ -0,0 +1,186 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+
+// Jobs kept for `/admin/recalculate`; the oldest finished ones are dropped beyond this
+const MAX_JOBS: usize = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,     // Waiting for the next worker cycle
+    Refetching, // The worker is refetching its symbols
+    Completed,  // Every symbol was refetched
+    Failed,     // Some symbols could not be refetched; their bars were left as they were
+}
+
+/// A request to refetch and recalculate specific dates of specific symbols
+#[derive(Clone, Debug, Serialize)]
+pub struct RecalculationJob {
+    pub id: u64,
+    pub requested_by: String, // Admin account name
+    pub requested_at: DateTime<Utc>,
+    pub dates: Vec<NaiveDate>, // Sorted
+    pub symbols: Vec<String>,
+    pub status: JobStatus,
+    pub cache_entries_invalidated: usize,
+    pub pending: Vec<String>, // Symbols not refetched yet
+    pub refetched: Vec<String>,
+    pub failed: Vec<String>,
+    pub bars_replaced: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl RecalculationJob {
+    pub fn is_finished(&self) -> bool {
+        matches!(self.status, JobStatus::Completed | JobStatus::Failed)
+    }
+
+    /// First and last requested date, the range to refetch
+    pub fn date_range(&self) -> Option<(NaiveDate, NaiveDate)> {
+        self.dates.first().copied().zip(self.dates.last().copied())
+    }
+
+    fn finish(&mut self) {
+        self.status = if self.failed.is_empty() { JobStatus::Completed } else { JobStatus::Failed };
+        self.finished_at = Some(Utc::now());
+    }
+}
+
+#[derive(Default)]
+struct Queue {
+    next_id: u64,
+    jobs: VecDeque<RecalculationJob>,
+}
+
+impl Queue {
+    fn push(&mut self, job: RecalculationJob) {
+        if self.jobs.len() >= MAX_JOBS
+            && let Some(oldest) = self.jobs.iter().position(RecalculationJob::is_finished)
+        {
+            self.jobs.remove(oldest);
+        }
+        self.jobs.push_back(job);
+    }
+
+    fn job_mut(&mut self, id: u64) -> Option<&mut RecalculationJob> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+}
+
+fn queue() -> &'static Mutex<Queue> {
+    static QUEUE: OnceLock<Mutex<Queue>> = OnceLock::new();
+    QUEUE.get_or_init(|| Mutex::new(Queue::default()))
+}
+
+/// Queue a refetch of `dates` for `symbols`, whose cached indicators were already invalidated
+pub fn schedule(requested_by: &str, mut dates: Vec<NaiveDate>, symbols: Vec<String>, cache_entries_invalidated: usize) -> RecalculationJob {
+    dates.sort();
+    dates.dedup();
+    let mut queue = queue().lock().unwrap_or_else(|e| e.into_inner());
+    queue.next_id += 1;
+    let job = RecalculationJob {
+        id: queue.next_id,
+        requested_by: requested_by.to_string(),
+        requested_at: Utc::now(),
+        dates,
+        pending: symbols.clone(),
+        symbols,
+        status: JobStatus::Queued,
+        cache_entries_invalidated,
+        refetched: Vec::new(),
+        failed: Vec::new(),
+        bars_replaced: 0,
+        note: None,
+        finished_at: None,
+    };
+    queue.push(job.clone());
+    job
+}
+
+/// Mark queued jobs as refetching and return them, oldest first
+pub fn start_queued() -> Vec<RecalculationJob> {
+    let mut queue = queue().lock().unwrap_or_else(|e| e.into_inner());
+    queue.jobs.iter_mut()
+        .filter(|job| job.status == JobStatus::Queued)
+        .map(|job| {
+            job.status = JobStatus::Refetching;
+            job.clone()
+        })
+        .collect()
+}
+
+/// Record the refetch of one symbol: the bars it replaced, or why it failed.
+/// The job finishes once no symbol is pending.
+pub fn record_symbol(id: u64, symbol: &str, result: Result<usize, String>) {
+    let mut queue = queue().lock().unwrap_or_else(|e| e.into_inner());
+    let Some(job) = queue.job_mut(id) else { return };
+    job.pending.retain(|pending| pending != symbol);
+    match result {
+        Ok(replaced) => {
+            job.bars_replaced += replaced;
+            job.refetched.push(symbol.to_string());
+        }
+        Err(reason) => {
+            job.failed.push(symbol.to_string());
+            job.note = Some(reason);
+        }
+    }
+    if job.pending.is_empty() {
+        job.finish();
+    }
+}
+
+/// Finish queued jobs without refetching, on nodes that take their data from elsewhere
+pub fn complete_queued(note: &str) -> usize {
+    let mut queue = queue().lock().unwrap_or_else(|e| e.into_inner());
+    let mut completed = 0;
+    for job in queue.jobs.iter_mut().filter(|job| job.status == JobStatus::Queued) {
+        job.pending.clear();
+        job.note = Some(note.to_string());
+        job.finish();
+        completed += 1;
+    }
+    completed
+}
+
+/// Recent jobs, oldest first
+pub fn jobs() -> Vec<RecalculationJob> {
+    queue().lock().unwrap_or_else(|e| e.into_inner()).jobs.iter().cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_progress_and_bounded_queue() {
+        let date = |day: u32| NaiveDate::from_ymd_opt(2025, 8, day).unwrap();
+        let job = schedule("ops", vec![date(6), date(4), date(6)], vec!["VCB".to_string(), "FPT".to_string()], 3);
+        assert_eq!(job.date_range(), Some((date(4), date(6))));
+        assert_eq!(job.status, JobStatus::Queued);
+
+        let started = start_queued();
+        assert!(started.iter().any(|started| started.id == job.id && started.status == JobStatus::Refetching));
+        record_symbol(job.id, "VCB", Ok(2));
+        let progress = jobs().into_iter().find(|j| j.id == job.id).unwrap();
+        assert_eq!((progress.status, progress.pending.clone(), progress.bars_replaced), (JobStatus::Refetching, vec!["FPT".to_string()], 2));
+        record_symbol(job.id, "FPT", Err("batch failed".to_string()));
+        let finished = jobs().into_iter().find(|j| j.id == job.id).unwrap();
+        assert_eq!(finished.status, JobStatus::Failed);
+        assert!(finished.finished_at.is_some());
+
+        // Finished jobs make room for new ones
+        let mut queue = Queue::default();
+        for id in 0..MAX_JOBS as u64 + 5 {
+            let mut job = finished.clone();
+            job.id = id;
+            queue.push(job);
+        }
+        assert_eq!(queue.jobs.len(), MAX_JOBS);
+        assert_eq!(queue.jobs.front().unwrap().id, 5);
+    }
+}
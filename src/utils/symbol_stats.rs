@@ -0,0 +1,180 @@
+use crate::utils::market_time::market_date;
+use crate::vci::OhlcvData;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use tracing::{debug, info, warn};
+
+// Bars averaged for the daily volume figure
+const VOLUME_WINDOW: usize = 20;
+// No trade for this long and a symbol is treated as dormant (suspended, delisted, illiquid)
+pub const DORMANT_AFTER_DAYS: i64 = 30;
+// A symbol needs this many attempts before its failure rate is trusted
+const FLAKY_MIN_ATTEMPTS: u64 = 5;
+const FLAKY_FAILURE_RATE: f64 = 0.5;
+// Counts are halved past this so the failure rate follows recent behaviour
+const MAX_TRACKED_ATTEMPTS: u64 = 1000;
+// Bars kept for dormant symbols when memory cleanup runs
+pub const DORMANT_RETENTION_POINTS: usize = 60;
+
+// Long-run statistics for one symbol, persisted across restarts
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SymbolStats {
+    pub avg_daily_volume: f64,
+    pub last_trade_date: Option<NaiveDate>, // Latest bar with non-zero volume
+    pub fetch_attempts: u64,
+    pub fetch_failures: u64, // Batch failed or returned no data for the symbol
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl SymbolStats {
+    pub fn failure_rate(&self) -> f64 {
+        if self.fetch_attempts == 0 { 0.0 } else { self.fetch_failures as f64 / self.fetch_attempts as f64 }
+    }
+
+    /// Fails often enough that it should not share a batch with healthy symbols
+    pub fn is_flaky(&self) -> bool {
+        self.fetch_attempts >= FLAKY_MIN_ATTEMPTS && self.failure_rate() >= FLAKY_FAILURE_RATE
+    }
+
+    pub fn is_dormant(&self, today: NaiveDate) -> bool {
+        self.last_trade_date.is_some_and(|date| (today - date).num_days() > DORMANT_AFTER_DAYS)
+    }
+}
+
+/// Stats as served by `/symbols/{symbol}/stats`
+#[derive(Clone, Debug, Serialize)]
+pub struct SymbolStatsView {
+    pub symbol: String,
+    #[serde(flatten)]
+    pub stats: SymbolStats,
+    pub failure_rate: f64,
+    pub flaky: bool,
+    pub dormant: bool,
+}
+
+#[derive(Default)]
+struct Registry {
+    path: Option<PathBuf>,
+    stats: HashMap<String, SymbolStats>,
+    version: u64,       // Bumped on every change
+    saved_version: u64, // Version last written to `path`
+}
+
+static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+
+fn registry() -> &'static Mutex<Registry> {
+    REGISTRY.get_or_init(|| Mutex::new(Registry::default()))
+}
+
+/// Load persisted stats; `None` keeps them in memory only. Call once at startup.
+pub fn init(path: Option<PathBuf>) {
+    let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(path) = &path
+        && path.exists()
+    {
+        match fs::read_to_string(path).map(|content| serde_json::from_str::<HashMap<String, SymbolStats>>(&content)) {
+            Ok(Ok(stats)) => {
+                info!(?path, symbols = stats.len(), "Loaded symbol statistics");
+                registry.stats = stats;
+            }
+            Ok(Err(e)) => warn!(?path, error = %e, "Symbol statistics file is invalid, starting fresh"),
+            Err(e) => warn!(?path, error = %e, "Failed to read symbol statistics, starting fresh"),
+        }
+    }
+    registry.path = path;
+}
+
+pub fn record_fetch(symbol: &str, ok: bool) {
+    let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let stats = registry.stats.entry(symbol.to_string()).or_default();
+    stats.fetch_attempts += 1;
+    if !ok {
+        stats.fetch_failures += 1;
+    }
+    if stats.fetch_attempts > MAX_TRACKED_ATTEMPTS {
+        stats.fetch_attempts /= 2;
+        stats.fetch_failures /= 2;
+    }
+    registry.version += 1;
+}
+
+/// Refresh volume and last trade date from a symbol's current series
+pub fn record_series(symbol: &str, series: &[OhlcvData]) {
+    let traded: Vec<&OhlcvData> = series.iter().filter(|bar| bar.volume > 0).collect();
+    let Some(latest) = traded.last() else { return };
+    let window = &traded[traded.len().saturating_sub(VOLUME_WINDOW)..];
+
+    let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let stats = registry.stats.entry(symbol.to_string()).or_default();
+    stats.avg_daily_volume = window.iter().map(|bar| bar.volume as f64).sum::<f64>() / window.len() as f64;
+    stats.last_trade_date = Some(market_date(latest.time));
+    stats.updated_at = Some(Utc::now());
+    registry.version += 1;
+}
+
+pub fn get(symbol: &str, today: NaiveDate) -> Option<SymbolStatsView> {
+    let registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    registry.stats.get(symbol).map(|stats| SymbolStatsView {
+        symbol: symbol.to_string(),
+        stats: stats.clone(),
+        failure_rate: stats.failure_rate(),
+        flaky: stats.is_flaky(),
+        dormant: stats.is_dormant(today),
+    })
+}
+
+pub fn snapshot() -> HashMap<String, SymbolStats> {
+    registry().lock().unwrap_or_else(|e| e.into_inner()).stats.clone()
+}
+
+/// Write the stats if they changed since the last save (temp file + rename)
+pub fn save() -> io::Result<()> {
+    let (path, version, content) = {
+        let registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+        let Some(path) = registry.path.clone() else { return Ok(()) };
+        if registry.version == registry.saved_version {
+            return Ok(());
+        }
+        (path, registry.version, serde_json::to_vec(&registry.stats).map_err(io::Error::other)?)
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, content)?;
+    fs::rename(&tmp_path, &path)?;
+    // Only a completed write counts as saved, so a failed one is retried on the next save
+    let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    registry.saved_version = registry.saved_version.max(version);
+    debug!(?path, "Saved symbol statistics");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_bars;
+
+    #[test]
+    fn test_volume_failure_rate_and_dormancy() {
+        let bar = |day: i64, volume: u64| test_bars::bar(day, 10.0, volume);
+        record_series("TSTA", &[bar(0, 100), bar(1, 300), bar(2, 0)]);
+        for ok in [true, false, false, true, false] {
+            record_fetch("TSTA", ok);
+        }
+
+        let today = NaiveDate::from_ymd_opt(2025, 8, 10).unwrap();
+        let view = get("TSTA", today).unwrap();
+        assert_eq!(view.stats.avg_daily_volume, 200.0);
+        assert_eq!(view.stats.last_trade_date, NaiveDate::from_ymd_opt(2025, 8, 2));
+        assert!((view.failure_rate - 0.6).abs() < 1e-9);
+        assert!(view.flaky && !view.dormant);
+        assert!(view.stats.is_dormant(NaiveDate::from_ymd_opt(2025, 9, 10).unwrap()));
+        assert!(get("TSTB", today).is_none());
+    }
+}
@@ -0,0 +1,29 @@
+use crate::vci::OhlcvData;
+use chrono::{Duration, TimeZone, Utc};
+
+/// A flat VCB bar (open = high = low = close) `day` sessions after 2025-08-01
+pub fn bar(day: i64, close: f64, volume: u64) -> OhlcvData {
+    OhlcvData {
+        time: Utc.with_ymd_and_hms(2025, 8, 1, 0, 0, 0).unwrap() + Duration::days(day),
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume,
+        symbol: Some("VCB".to_string()),
+    }
+}
+
+/// Flat daily bars with the given closes, one per day from 2025-01-01
+pub fn series(closes: &[f64]) -> Vec<OhlcvData> {
+    let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+    closes.iter().enumerate().map(|(i, close)| OhlcvData {
+        time: start + Duration::days(i as i64),
+        open: *close,
+        high: *close,
+        low: *close,
+        close: *close,
+        volume: 1000,
+        symbol: Some("TEST".to_string()),
+    }).collect()
+}
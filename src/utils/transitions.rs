@@ -0,0 +1,106 @@
+use crate::utils::change_log;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+
+// Transitions kept for post-mortems; older ones are dropped
+const MAX_TRANSITIONS: usize = 100;
+
+/// What the node was doing when a transition happened
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TransitionContext {
+    pub iteration: u64,         // Worker cycle number
+    pub data_version: u64,      // Sync change log version, bumped by every data change
+    pub pending_symbols: usize, // Symbols due for a fetch this cycle
+    pub interval_secs: u64,     // Current worker interval
+}
+
+/// One change of state in one of the node's state machines
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Transition {
+    pub at: DateTime<Utc>,
+    pub machine: String, // standby, data_source, office_hours or provider_quota
+    pub from: String,
+    pub to: String,
+    pub reason: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<TransitionContext>,
+}
+
+#[derive(Default)]
+struct History {
+    capture_context: bool,
+    context: TransitionContext,
+    transitions: VecDeque<Transition>,
+}
+
+impl History {
+    fn record(&mut self, transition: Transition) {
+        if self.transitions.len() == MAX_TRANSITIONS {
+            self.transitions.pop_front();
+        }
+        self.transitions.push_back(transition);
+    }
+}
+
+fn history() -> &'static Mutex<History> {
+    static HISTORY: OnceLock<Mutex<History>> = OnceLock::new();
+    HISTORY.get_or_init(|| Mutex::new(History::default()))
+}
+
+/// Attach a context snapshot to every transition recorded from now on
+pub fn set_capture_context(enabled: bool) {
+    history().lock().unwrap_or_else(|e| e.into_inner()).capture_context = enabled;
+}
+
+/// Update the worker state that context snapshots are taken from; called once per cycle
+pub fn set_worker_state(iteration: u64, pending_symbols: usize, interval_secs: u64) {
+    let mut history = history().lock().unwrap_or_else(|e| e.into_inner());
+    history.context = TransitionContext { iteration, pending_symbols, interval_secs, ..history.context };
+}
+
+/// Record that `machine` moved from `from` to `to`
+pub fn record(machine: &str, from: &str, to: &str, reason: impl Into<String>) {
+    let data_version = change_log::since(None, None).version;
+    let mut history = history().lock().unwrap_or_else(|e| e.into_inner());
+    let context = history.capture_context.then(|| TransitionContext { data_version, ..history.context });
+    history.record(Transition {
+        at: Utc::now(),
+        machine: machine.to_string(),
+        from: from.to_string(),
+        to: to.to_string(),
+        reason: reason.into(),
+        context,
+    });
+}
+
+/// Recorded transitions, oldest first
+pub fn export() -> Vec<Transition> {
+    history().lock().unwrap_or_else(|e| e.into_inner()).transitions.iter().cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_is_bounded_and_keeps_context() {
+        let mut history = History { capture_context: true, ..History::default() };
+        for i in 0..MAX_TRANSITIONS + 5 {
+            history.context.iteration = i as u64;
+            let context = Some(history.context.clone());
+            history.record(Transition {
+                at: Utc::now(),
+                machine: "standby".to_string(),
+                from: "mirroring".to_string(),
+                to: "promoted".to_string(),
+                reason: format!("cycle {}", i),
+                context,
+            });
+        }
+        assert_eq!(history.transitions.len(), MAX_TRANSITIONS);
+        assert_eq!(history.transitions.front().unwrap().reason, "cycle 5");
+        assert_eq!(history.transitions.back().unwrap().context.as_ref().unwrap().iteration, MAX_TRANSITIONS as u64 + 4);
+    }
+}
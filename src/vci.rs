@@ -2,8 +2,16 @@ use reqwest::{Client, Error as ReqwestError};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::time::{Duration as StdDuration, SystemTime};
+use std::time::Duration as StdDuration;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
 use tokio::time::sleep;
+use crate::utils::header_profile::{HeaderProfile, HeaderProfileConfig, UserAgentRotation};
+use crate::utils::http_client;
+use crate::utils::market_time;
+use crate::utils::metrics::{self, Timer};
+use crate::utils::provider_quota;
+use crate::utils::rate_limiter;
 use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, Utc, Weekday, TimeZone, Datelike};
 
 #[derive(Debug)]
@@ -28,9 +36,9 @@ impl From<serde_json::Error> for VciError {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OhlcvData {
-    #[serde(serialize_with = "serialize_time_as_date")]
+    #[serde(serialize_with = "serialize_time_as_date", deserialize_with = "deserialize_time")]
     pub time: DateTime<Utc>,
     pub open: f64,
     pub high: f64,
@@ -44,10 +52,24 @@ fn serialize_time_as_date<S>(time: &DateTime<Utc>, serializer: S) -> Result<S::O
 where
     S: serde::Serializer,
 {
-    let date_string = time.format("%Y-%m-%d").to_string();
+    let date_string = market_time::format_market_date(*time);
     serializer.serialize_str(&date_string)
 }
 
+// Accepts the market dates served by /tickers as well as full timestamps
+fn deserialize_time<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    if let Ok(date) = NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
+        return Ok(market_time::market_day_start(date));
+    }
+    DateTime::parse_from_rfc3339(&value)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(serde::de::Error::custom)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompanyInfo {
     pub symbol: String,
@@ -90,41 +112,108 @@ pub struct FinancialRatio {
     pub eps: Option<f64>,
 }
 
+/// One price level of the order book
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceLevel {
+    pub price: f64,
+    pub volume: u64,
+}
+
+/// A symbol's row on the VCI price board: daily price limits, the latest match and the best levels
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceBoardQuote {
+    pub symbol: String,
+    pub ceiling: Option<f64>,
+    pub floor: Option<f64>,
+    pub reference: Option<f64>, // Previous close the limits are set from
+    pub open: Option<f64>,
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+    pub match_price: Option<f64>,      // Price of the latest match
+    pub match_volume: Option<u64>,     // Shares of the latest match
+    pub total_volume: Option<u64>,     // Shares matched so far this session
+    pub total_value: Option<f64>,      // Their value
+    pub bids: Vec<PriceLevel>,         // Best first
+    pub asks: Vec<PriceLevel>,         // Best first
+}
+
+fn price_levels(levels: Option<&Value>) -> Vec<PriceLevel> {
+    levels.and_then(|v| v.as_array()).map(|levels| levels.iter()
+        .filter_map(|level| Some(PriceLevel {
+            price: level.get("price").and_then(|v| v.as_f64()).filter(|price| *price > 0.0)?,
+            volume: level.get("volume").and_then(|v| v.as_f64()).map_or(0, |volume| volume as u64),
+        }))
+        .collect())
+        .unwrap_or_default()
+}
+
+/// Rows of a price board response. Entries without a symbol are skipped.
+fn parse_price_board(response: &Value) -> Result<Vec<PriceBoardQuote>, VciError> {
+    let items = response.as_array().ok_or(VciError::NoData)?;
+    let quotes: Vec<PriceBoardQuote> = items.iter()
+        .filter_map(|item| {
+            let listing = item.get("listingInfo");
+            let matched = item.get("matchPrice");
+            let bid_ask = item.get("bidAsk");
+            let number = |section: Option<&Value>, key: &str| section.and_then(|s| s.get(key)).and_then(|v| v.as_f64());
+            let symbol = [listing, matched, bid_ask].into_iter().flatten()
+                .find_map(|section| section.get("symbol").and_then(|v| v.as_str()))?;
+            Some(PriceBoardQuote {
+                symbol: symbol.to_uppercase(),
+                ceiling: number(listing, "ceiling"),
+                floor: number(listing, "floor"),
+                reference: number(listing, "refPrice"),
+                open: number(matched, "openPrice"),
+                high: number(matched, "highest"),
+                low: number(matched, "lowest"),
+                match_price: number(matched, "matchPrice"),
+                match_volume: number(matched, "matchVol").map(|volume| volume as u64),
+                total_volume: number(matched, "accumulatedVolume").map(|volume| volume as u64),
+                total_value: number(matched, "accumulatedValue"),
+                bids: price_levels(bid_ask.and_then(|s| s.get("bidPrices"))),
+                asks: price_levels(bid_ask.and_then(|s| s.get("askPrices"))),
+            })
+        })
+        .collect();
+    if quotes.is_empty() {
+        return Err(VciError::NoData);
+    }
+    Ok(quotes)
+}
+
+// Name used for VCI in provider quota accounting
+const QUOTA_PROVIDER: &str = "vci";
+// Concurrent requests per client and its clones; the host's rate budget still paces them
+const MAX_IN_FLIGHT: usize = 4;
+
+/// Cheap to clone: clones share the HTTP connection pool, the in-flight limit and the host's rate budget,
+/// so one client can serve concurrent tasks
+#[derive(Clone)]
 pub struct VciClient {
-    client: Client,
+    client: Client, // Reference-counted handle to the pool
+    in_flight: Arc<Semaphore>, // Requests awaiting a response, at most MAX_IN_FLIGHT
     base_url: String,
-    rate_limit_per_minute: u32,
-    request_timestamps: Vec<SystemTime>,
-    user_agents: Vec<String>,
-    random_agent: bool,
+    host: String, // Requests are paced by the shared per-host budget
+    headers: HeaderProfile,
     resample_map: HashMap<String, String>,
 }
 
 impl VciClient {
-    pub fn new(random_agent: bool, rate_limit_per_minute: u32) -> Result<Self, VciError> {
-        let client = Client::builder()
-            .timeout(StdDuration::from_secs(30))
-            .build()?;
-
-        let user_agents = vec![
-            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36".to_string(),
-            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36".to_string(),
-            "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:120.0) Gecko/20100101 Firefox/120.0".to_string(),
-            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.3 Safari/605.1.15".to_string(),
-            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.0.0".to_string(),
-        ];
+    pub fn new(random_agent: bool) -> Result<Self, VciError> {
+        // Pooled client shared with the other provider clients
+        let client = http_client::shared_client();
 
         let mut resample_map = HashMap::new();
         resample_map.insert("1W".to_string(), "1W".to_string());
         resample_map.insert("1M".to_string(), "1M".to_string());
 
+        let base_url = "https://trading.vietcap.com.vn/api/".to_string();
         Ok(VciClient {
             client,
-            base_url: "https://trading.vietcap.com.vn/api/".to_string(),
-            rate_limit_per_minute,
-            request_timestamps: Vec::new(),
-            user_agents,
-            random_agent,
+            in_flight: Arc::new(Semaphore::new(MAX_IN_FLIGHT)),
+            host: rate_limiter::host_of(&base_url),
+            base_url,
+            headers: Self::default_header_profile(random_agent),
             resample_map,
         })
     }
@@ -146,58 +235,54 @@ impl VciClient {
             .ok_or_else(|| VciError::InvalidInterval(interval.to_string()))
     }
 
-    fn get_user_agent(&self) -> String {
-        if self.random_agent {
-            use rand::prelude::IndexedRandom;
-            self.user_agents.choose(&mut rand::rng())
-                .unwrap_or(&self.user_agents[0])
-                .clone()
-        } else {
-            self.user_agents[0].clone()
-        }
+    /// Default VCI headers; `random_agent` picks a random user agent per request, otherwise the first
+    pub fn default_header_profile(random_agent: bool) -> HeaderProfile {
+        let mut profile = HeaderProfile::new("https://trading.vietcap.com.vn/", "https://trading.vietcap.com.vn");
+        profile.rotation = if random_agent { UserAgentRotation::Random } else { UserAgentRotation::Fixed };
+        profile
     }
 
-    async fn enforce_rate_limit(&mut self) {
-        let current_time = SystemTime::now();
-        
-        // Remove timestamps older than 1 minute
-        self.request_timestamps.retain(|&timestamp| {
-            current_time.duration_since(timestamp).unwrap_or(StdDuration::from_secs(0)) < StdDuration::from_secs(60)
-        });
+    /// Replace the default headers, e.g. with a profile from config
+    pub fn with_header_profile(mut self, headers: HeaderProfile) -> Self {
+        self.headers = headers;
+        self
+    }
 
-        // If we're at the rate limit, wait
-        if self.request_timestamps.len() >= self.rate_limit_per_minute as usize {
-            if let Some(&oldest_request) = self.request_timestamps.first() {
-                let wait_time = StdDuration::from_secs(60) - current_time.duration_since(oldest_request).unwrap_or(StdDuration::from_secs(0));
-                if !wait_time.is_zero() {
-                    sleep(wait_time + StdDuration::from_millis(100)).await;
-                }
+    /// Apply configured header overrides, if any, on top of the current profile
+    pub fn with_header_overrides(self, overrides: Option<&HeaderProfileConfig>) -> Self {
+        match overrides {
+            Some(overrides) => {
+                let headers = self.headers.clone().with_overrides(overrides);
+                self.with_header_profile(headers)
             }
+            None => self,
         }
-
-        self.request_timestamps.push(current_time);
     }
 
-    async fn make_request(&mut self, url: &str, payload: &Value) -> Result<Value, VciError> {
+    async fn make_request(&self, url: &str, payload: &Value) -> Result<Value, VciError> {
         const MAX_RETRIES: u32 = 5;
         
         for attempt in 0..MAX_RETRIES {
-            self.enforce_rate_limit().await;
+            rate_limiter::acquire(&self.host).await;
 
             if attempt > 0 {
                 let delay = StdDuration::from_secs_f64(2.0_f64.powi(attempt as i32 - 1) + rand::random::<f64>());
                 let delay = delay.min(StdDuration::from_secs(60));
+                provider_quota::record_backoff(QUOTA_PROVIDER, delay);
                 tracing::info!("VCI API retry backoff: attempt {}/{}, waiting {:.1}s before retry", attempt + 1, MAX_RETRIES, delay.as_secs_f64());
                 sleep(delay).await;
             }
 
-            let user_agent = self.get_user_agent();
+            // Held until the response is read; clones of the client share the limit
+            let _permit = self.in_flight.acquire().await;
+            let user_agent = self.headers.next_user_agent();
             
             
+            let request_timer = Timer::start("vci.request");
             let response = self.client
                 .post(url)
                 .header("Accept", "application/json, text/plain, */*")
-                .header("Accept-Language", "en-US,en;q=0.9,vi-VN;q=0.8,vi;q=0.7")
+                .header("Accept-Language", &self.headers.accept_language)
                 .header("Accept-Encoding", "gzip, deflate, br")
                 .header("Connection", "keep-alive")
                 .header("Content-Type", "application/json")
@@ -211,22 +296,33 @@ impl VciClient {
                 .header("sec-ch-ua-mobile", "?0")
                 .header("sec-ch-ua-platform", "\"Windows\"")
                 .header("User-Agent", user_agent)
-                .header("Referer", "https://trading.vietcap.com.vn/")
-                .header("Origin", "https://trading.vietcap.com.vn")
+                .header("Referer", &self.headers.referer)
+                .header("Origin", &self.headers.origin)
                 .json(payload)
                 .send()
                 .await;
+            request_timer.stop();
+            provider_quota::record_request(QUOTA_PROVIDER);
 
             match response {
                 Ok(resp) => {
                     let status = resp.status();
+                    provider_quota::record_status(QUOTA_PROVIDER, status.as_u16());
+                    http_client::record_response_version(QUOTA_PROVIDER, resp.version());
                     
                     if status.is_success() {
                         match resp.json::<Value>().await {
-                            Ok(data) => return Ok(data),
-                            Err(_) => continue,
+                            Ok(data) => {
+                                metrics::increment_counter("vci.requests_ok", 1);
+                                return Ok(data);
+                            }
+                            Err(_) => {
+                                metrics::increment_counter("vci.requests_failed", 1);
+                                continue;
+                            }
                         }
                     } else {
+                        metrics::increment_counter("vci.requests_failed", 1);
                         if status == 403 || status == 429 || status.is_server_error() {
                             continue;
                         } else if status.is_client_error() {
@@ -236,7 +332,11 @@ impl VciClient {
                         }
                     }
                 }
-                Err(_) => continue,
+                Err(_) => {
+                    provider_quota::record_transport_error(QUOTA_PROVIDER);
+                    metrics::increment_counter("vci.requests_failed", 1);
+                    continue;
+                }
             }
         }
 
@@ -267,7 +367,7 @@ impl VciClient {
         let start_date = NaiveDate::parse_from_str(start, "%Y-%m-%d").expect("Invalid start date");
         let end_date = match end {
             Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d").expect("Invalid end date"),
-            None => Utc::now().date_naive(),
+            None => market_time::market_date(Utc::now()),
         };
 
         // Calculate actual business days (exclude weekends) - matching Python pd.bdate_range
@@ -297,7 +397,7 @@ impl VciClient {
     }
 
     pub async fn get_history(
-        &mut self,
+        &self,
         symbol: &str,
         start: &str,
         end: Option<&str>,
@@ -328,7 +428,7 @@ impl VciClient {
         let required_keys = ["o", "h", "l", "c", "v", "t"];
         
         for key in &required_keys {
-            if !data_item.get(key).is_some() {
+            if data_item.get(key).is_none() {
                 return Err(VciError::InvalidResponse(format!("Missing key: {}", key)));
             }
         }
@@ -364,7 +464,7 @@ impl VciClient {
                 VciError::InvalidResponse(format!("Cannot convert timestamp {} to DateTime at index {}", timestamp, i))
             })?;
 
-            if time.date_naive() >= start_date {
+            if market_time::market_date(time) >= start_date {
                 result.push(OhlcvData {
                     time,
                     open: opens[i].as_f64().unwrap_or(0.0),
@@ -377,7 +477,7 @@ impl VciClient {
             }
         }
 
-        result.sort_by(|a, b| a.time.cmp(&b.time));
+        result.sort_by_key(|a| a.time);
         
         // Apply resampling if needed
         if self.resample_map.contains_key(interval) && !["1m", "1H", "1D"].contains(&interval) {
@@ -388,7 +488,7 @@ impl VciClient {
     }
 
     pub async fn get_batch_history(
-        &mut self,
+        &self,
         symbols: &[String],
         start: &str,
         end: Option<&str>,
@@ -431,7 +531,7 @@ impl VciClient {
 
         // Create a mapping from response data using symbol field
         let mut response_map = HashMap::new();
-        for (_i, data_item) in response_array.iter().enumerate() {
+        for data_item in response_array.iter() {
             if let Some(obj) = data_item.as_object() {
                 // Find symbol identifier in response
                 let symbol_fields = ["symbol", "ticker", "Symbol", "Ticker", "s"];
@@ -463,7 +563,7 @@ impl VciClient {
             
             let mut valid = true;
             for key in &required_keys {
-                if !data_item.get(key).is_some() {
+                if data_item.get(key).is_none() {
                     valid = false;
                     break;
                 }
@@ -498,11 +598,11 @@ impl VciClient {
             
             // Debug: Show all timestamps in raw VCI response
             tracing::debug!("Symbol {}: Raw VCI timestamps from API:", symbol);
-            for j in 0..length.min(10) { // Show first 10 timestamps
-                let timestamp = if let Some(ts_str) = times[j].as_str() {
+            for (j, raw_time) in times.iter().enumerate().take(10) { // Show first 10 timestamps
+                let timestamp = if let Some(ts_str) = raw_time.as_str() {
                     ts_str.parse::<i64>().unwrap_or(0)
                 } else {
-                    times[j].as_i64().unwrap_or(0)
+                    raw_time.as_i64().unwrap_or(0)
                 };
                 let time = DateTime::<Utc>::from_timestamp(timestamp, 0).unwrap_or_default();
                 tracing::debug!("  Raw timestamp[{}]: {} -> {}", j, timestamp, time.format("%Y-%m-%d %H:%M:%S"));
@@ -519,7 +619,7 @@ impl VciClient {
                 };
                 let time = DateTime::<Utc>::from_timestamp(timestamp, 0).unwrap_or_default();
 
-                if time.date_naive() >= start_date {
+                if market_time::market_date(time) >= start_date {
                     filtered_data_points += 1;
                     symbol_data.push(OhlcvData {
                         time,
@@ -536,13 +636,13 @@ impl VciClient {
             tracing::debug!("Symbol {}: VCI returned {} data points, filtered to {} (start_date: {})", 
                 symbol, total_data_points, filtered_data_points, start_date);
 
-            symbol_data.sort_by(|a, b| a.time.cmp(&b.time));
+            symbol_data.sort_by_key(|a| a.time);
             
             // Apply resampling if needed
-            if self.resample_map.contains_key(interval) && !["1m", "1H", "1D"].contains(&interval) {
-                if let Ok(resampled) = self.resample_ohlcv(symbol_data.clone(), interval) {
-                    symbol_data = resampled;
-                }
+            if self.resample_map.contains_key(interval) && !["1m", "1H", "1D"].contains(&interval)
+                && let Ok(resampled) = self.resample_ohlcv(symbol_data.clone(), interval)
+            {
+                symbol_data = resampled;
             }
             
             results.insert(symbol.clone(), Some(symbol_data));
@@ -551,7 +651,17 @@ impl VciClient {
         Ok(results)
     }
 
-    pub async fn company_info(&mut self, symbol: &str) -> Result<CompanyInfo, VciError> {
+    /// Price board rows of `symbols`; symbols VCI doesn't list are left out
+    pub async fn price_board(&self, symbols: &[String]) -> Result<Vec<PriceBoardQuote>, VciError> {
+        let url = format!("{}price/symbols/getList", self.base_url);
+        let symbols: Vec<String> = symbols.iter().map(|symbol| symbol.to_uppercase()).collect();
+        let payload = serde_json::json!({ "symbols": symbols });
+
+        let response_data = self.make_request(&url, &payload).await?;
+        parse_price_board(&response_data)
+    }
+
+    pub async fn company_info(&self, symbol: &str) -> Result<CompanyInfo, VciError> {
         let url = self.base_url.replace("/api/", "/data-mt/") + "graphql";
         
         let graphql_query = r#"query Query($ticker: String!, $lang: String!) {
@@ -773,7 +883,7 @@ impl VciClient {
         }
         
         let mut result: Vec<OhlcvData> = weekly_data.into_values().collect();
-        result.sort_by(|a, b| a.time.cmp(&b.time));
+        result.sort_by_key(|a| a.time);
         Ok(result)
     }
     
@@ -804,7 +914,7 @@ impl VciClient {
         }
         
         let mut result: Vec<OhlcvData> = monthly_data.into_values().collect();
-        result.sort_by(|a, b| a.time.cmp(&b.time));
+        result.sort_by_key(|a| a.time);
         Ok(result)
     }
     
@@ -841,15 +951,58 @@ mod tests {
 
     #[tokio::test]
     async fn test_vci_client_creation() {
-        let client = VciClient::new(true, 6);
+        let client = VciClient::new(true);
         assert!(client.is_ok());
     }
 
     #[tokio::test]
     async fn test_interval_mapping() {
-        let client = VciClient::new(false, 6).unwrap();
+        let client = VciClient::new(false).unwrap();
         assert_eq!(client.get_interval_value("1D").unwrap(), "ONE_DAY");
         assert_eq!(client.get_interval_value("1H").unwrap(), "ONE_HOUR");
         assert!(client.get_interval_value("invalid").is_err());
     }
+
+    #[test]
+    fn test_ohlcv_time_round_trip() {
+        let bar = OhlcvData {
+            time: market_time::market_day_start(NaiveDate::from_ymd_opt(2025, 8, 15).unwrap()),
+            open: 60.0,
+            high: 61.0,
+            low: 59.5,
+            close: 60.5,
+            volume: 1000,
+            symbol: Some("VCB".to_string()),
+        };
+        let json = serde_json::to_string(&bar).unwrap();
+        assert!(json.contains("\"time\":\"2025-08-15\""));
+        assert_eq!(serde_json::from_str::<OhlcvData>(&json).unwrap(), bar);
+
+        let gossip: OhlcvData = serde_json::from_str(r#"{"time":"2025-08-15T02:00:00Z","open":1,"high":1,"low":1,"close":1,"volume":1,"symbol":null}"#).unwrap();
+        assert_eq!(gossip.time, Utc.with_ymd_and_hms(2025, 8, 15, 2, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_parse_price_board() {
+        let response = serde_json::json!([
+            {
+                "listingInfo": { "symbol": "VCB", "ceiling": 65400, "floor": 57000, "refPrice": 61200 },
+                "matchPrice": { "symbol": "VCB", "matchPrice": 61500, "matchVol": 300, "accumulatedVolume": 1250400, "accumulatedValue": 76.8e9,
+                    "openPrice": 61200, "highest": 61800, "lowest": 61000 },
+                "bidAsk": { "symbol": "VCB",
+                    "bidPrices": [{ "price": 61400, "volume": 12000 }, { "price": 61300, "volume": 5400 }, { "price": 0, "volume": 0 }],
+                    "askPrices": [{ "price": 61500, "volume": 800 }] }
+            },
+            { "listingInfo": { "ceiling": 1 } }
+        ]);
+        let quotes = parse_price_board(&response).unwrap();
+        assert_eq!(quotes.len(), 1);
+        let vcb = &quotes[0];
+        assert_eq!((vcb.ceiling, vcb.floor, vcb.reference), (Some(65400.0), Some(57000.0), Some(61200.0)));
+        assert_eq!((vcb.match_price, vcb.match_volume, vcb.total_volume), (Some(61500.0), Some(300), Some(1250400)));
+        // Empty levels (price 0) are dropped
+        assert_eq!(vcb.bids, vec![PriceLevel { price: 61400.0, volume: 12000 }, PriceLevel { price: 61300.0, volume: 5400 }]);
+        assert_eq!(vcb.asks.len(), 1);
+        assert!(matches!(parse_price_board(&serde_json::json!([])), Err(VciError::NoData)));
+    }
 }
\ No newline at end of file
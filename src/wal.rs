@@ -0,0 +1,322 @@
+use crate::data_structures::{merge_and_deduplicate_data, InMemoryData, SharedData};
+use crate::events::TickerUpdate;
+use crate::export::{EnhancedSnapshot, SharedEnhancedSnapshots};
+use crate::utils::metrics;
+use crate::vci::OhlcvData;
+use chrono::{DateTime, Utc};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+const CHECKPOINT_FILE: &str = "checkpoint.jsonl.gz";
+const LEGACY_CHECKPOINT_FILE: &str = "checkpoint.jsonl"; // Uncompressed, from older versions
+const ENHANCED_FILE: &str = "enhanced.json.gz";
+const WAL_FILE: &str = "updates.wal";
+// Appended updates reach disk at least this often, bounding what a crash can lose
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+// One bar per line. Unlike OhlcvData's JSON, the time keeps full precision.
+#[derive(Debug, Serialize, Deserialize)]
+struct BarRecord {
+    symbol: String,
+    time: DateTime<Utc>,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: u64,
+}
+
+impl BarRecord {
+    fn new(symbol: &str, bar: &OhlcvData) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            time: bar.time,
+            open: bar.open,
+            high: bar.high,
+            low: bar.low,
+            close: bar.close,
+            volume: bar.volume,
+        }
+    }
+
+    fn into_bar(self) -> (String, OhlcvData) {
+        let bar = OhlcvData {
+            time: self.time,
+            open: self.open,
+            high: self.high,
+            low: self.low,
+            close: self.close,
+            volume: self.volume,
+            symbol: Some(self.symbol.clone()),
+        };
+        (self.symbol, bar)
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct RecoveryStats {
+    pub checkpoint_bars: usize,
+    pub wal_updates: usize,
+    pub skipped_lines: usize, // Unparseable lines, such as a record torn by the crash
+}
+
+/// Read JSON-lines records, gzip-compressed when the file name ends in `.gz`, skipping lines that do not parse
+fn read_records(path: &Path, stats: &mut RecoveryStats) -> io::Result<Vec<BarRecord>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file = File::open(path)?;
+    let reader: Box<dyn Read> = if path.extension().is_some_and(|ext| ext == "gz") { Box::new(GzDecoder::new(file)) } else { Box::new(file) };
+    let mut records = Vec::new();
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(record) => records.push(record),
+            Err(_) => stats.skipped_lines += 1,
+        }
+    }
+    Ok(records)
+}
+
+/// Rebuild in-memory data from the last checkpoint, then replay live updates logged after it
+pub fn recover(dir: &Path) -> io::Result<(InMemoryData, RecoveryStats)> {
+    let mut stats = RecoveryStats::default();
+    let mut data = InMemoryData::new();
+
+    let checkpoint_path = Some(dir.join(CHECKPOINT_FILE)).filter(|path| path.exists()).unwrap_or_else(|| dir.join(LEGACY_CHECKPOINT_FILE));
+    for record in read_records(&checkpoint_path, &mut stats)? {
+        let (symbol, bar) = record.into_bar();
+        data.entry(symbol).or_default().push(bar);
+        stats.checkpoint_bars += 1;
+    }
+    data.values_mut().for_each(|series| series.sort_by_key(|bar| bar.time));
+
+    // Same merge as a live fetch, so replaying an update already in the checkpoint is harmless
+    for record in read_records(&dir.join(WAL_FILE), &mut stats)? {
+        let (symbol, bar) = record.into_bar();
+        merge_and_deduplicate_data(data.entry(symbol).or_default(), vec![bar]);
+        stats.wal_updates += 1;
+    }
+    Ok((data, stats))
+}
+
+/// Write `content` to `path` atomically, so a crash mid-write keeps the previous file
+fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(content)?;
+    file.sync_data()?;
+    fs::rename(&tmp_path, path)
+}
+
+/// Replace the checkpoint with `data` and start an empty log
+fn checkpoint(dir: &Path, data: &InMemoryData) -> io::Result<BufWriter<File>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+    for (symbol, series) in data {
+        for bar in series {
+            serde_json::to_writer(&mut encoder, &BarRecord::new(symbol, bar))?;
+            encoder.write_all(b"\n")?;
+        }
+    }
+    write_atomic(&dir.join(CHECKPOINT_FILE), &encoder.finish()?)?;
+    match fs::remove_file(dir.join(LEGACY_CHECKPOINT_FILE)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+
+    // Unlink rather than truncate, so late writes through the previous log's handle never land in the new one
+    let wal_path = dir.join(WAL_FILE);
+    match fs::remove_file(&wal_path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    Ok(BufWriter::new(File::create(wal_path)?))
+}
+
+/// Take a checkpoint of the current data, retrying on the next interval if it fails.
+/// Compression and the file writes run on a blocking thread.
+async fn checkpoint_now(dir: &Path, data: &SharedData) -> Option<BufWriter<File>> {
+    let snapshot = data.lock().await.clone();
+    let symbols = snapshot.len();
+    let dir = dir.to_path_buf();
+    match tokio::task::spawn_blocking(move || checkpoint(&dir, &snapshot)).await {
+        Ok(Ok(wal)) => {
+            debug!(symbols, "Wrote live data checkpoint");
+            metrics::increment_counter("wal.checkpoints", 1);
+            Some(wal)
+        }
+        Ok(Err(e)) => {
+            warn!(error = %e, "Failed to write live data checkpoint");
+            None
+        }
+        Err(e) => {
+            warn!(error = %e, "Checkpoint task panicked");
+            None
+        }
+    }
+}
+
+/// The enhanced snapshot saved by the last checkpoint, if any
+pub fn load_enhanced(dir: &Path) -> io::Result<Option<EnhancedSnapshot>> {
+    let path = dir.join(ENHANCED_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let snapshot = serde_json::from_reader(BufReader::new(GzDecoder::new(File::open(path)?)))?;
+    Ok(Some(snapshot))
+}
+
+fn save_enhanced(dir: &Path, snapshot: &EnhancedSnapshot) -> io::Result<()> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+    serde_json::to_writer(&mut encoder, snapshot)?;
+    write_atomic(&dir.join(ENHANCED_FILE), &encoder.finish()?)
+}
+
+/// Save the current enhanced snapshot when it changed since the last save
+async fn save_enhanced_now(dir: &Path, snapshots: &SharedEnhancedSnapshots, saved_version: &mut Option<u64>) {
+    let Some(snapshot) = snapshots.lock().await.current() else { return };
+    if *saved_version == Some(snapshot.version) {
+        return;
+    }
+    let (version, symbols) = (snapshot.version, snapshot.series.len());
+    let dir = dir.to_path_buf();
+    match tokio::task::spawn_blocking(move || save_enhanced(&dir, &snapshot)).await {
+        Ok(Ok(())) => {
+            debug!(version, symbols, "Saved enhanced snapshot");
+            *saved_version = Some(version);
+        }
+        Ok(Err(e)) => warn!(error = %e, "Failed to save enhanced snapshot"),
+        Err(e) => warn!(error = %e, "Enhanced snapshot task panicked"),
+    }
+}
+
+fn append(wal: &mut Option<BufWriter<File>>, update: &TickerUpdate) {
+    let Some(writer) = wal else { return };
+    let result = serde_json::to_writer(&mut *writer, &BarRecord::new(&update.symbol, &update.bar))
+        .map_err(io::Error::from)
+        .and_then(|_| writer.write_all(b"\n"));
+    if let Err(e) = result {
+        warn!(error = %e, symbol = update.symbol, "Failed to append to write-ahead log");
+    }
+}
+
+/// Log every live update to `dir` until `stop` is cancelled or the event bus closes. Updates are
+/// flushed every second; every `checkpoint_interval` the in-memory data is checkpointed, the log
+/// starts over and the enhanced snapshot is saved alongside. Stopping takes a final checkpoint.
+pub async fn run(data: SharedData, snapshots: SharedEnhancedSnapshots, mut updates: broadcast::Receiver<TickerUpdate>, dir: PathBuf, checkpoint_interval: Duration, stop: CancellationToken) {
+    if let Err(e) = fs::create_dir_all(&dir) {
+        warn!(?dir, error = %e, "Failed to create write-ahead log directory, live updates will not survive a restart");
+        return;
+    }
+    info!(?dir, checkpoint_interval_secs = checkpoint_interval.as_secs(), "Write-ahead log started");
+
+    // Updates published while a checkpoint is taken are already in it and get logged again, which replays harmlessly
+    let mut wal = checkpoint_now(&dir, &data).await;
+    let mut dirty = false;
+    let mut saved_enhanced_version = None;
+    let mut flush_timer = tokio::time::interval(FLUSH_INTERVAL);
+    let mut checkpoint_timer = tokio::time::interval(checkpoint_interval);
+    checkpoint_timer.reset();
+
+    loop {
+        tokio::select! {
+            _ = stop.cancelled() => break,
+            update = updates.recv() => match update {
+                Ok(update) => {
+                    append(&mut wal, &update);
+                    dirty = true;
+                    metrics::increment_counter("wal.appended", 1);
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    // Skipped updates are already in memory, so a checkpoint recovers them
+                    warn!(skipped, "Write-ahead log fell behind, checkpointing");
+                    metrics::increment_counter("wal.lagged", skipped);
+                    wal = checkpoint_now(&dir, &data).await.or(wal);
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = flush_timer.tick(), if dirty => {
+                if let Some(writer) = wal.as_mut()
+                    && let Err(e) = writer.flush().and_then(|_| writer.get_ref().sync_data())
+                {
+                    warn!(error = %e, "Failed to flush write-ahead log");
+                }
+                dirty = false;
+            }
+            _ = checkpoint_timer.tick() => {
+                wal = checkpoint_now(&dir, &data).await.or(wal);
+                save_enhanced_now(&dir, &snapshots, &mut saved_enhanced_version).await;
+            }
+        }
+    }
+    if let Some(mut writer) = wal {
+        let _ = writer.flush();
+    }
+    // Everything logged is in memory, so one checkpoint leaves nothing to replay on the next start
+    if checkpoint_now(&dir, &data).await.is_some() {
+        info!(?dir, "Write-ahead log stopped after a final checkpoint");
+    }
+    save_enhanced_now(&dir, &snapshots, &mut saved_enhanced_version).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_bars;
+
+    // A bar on `day` of August 2025
+    fn bar(day: u32, close: f64) -> OhlcvData {
+        test_bars::bar(day as i64 - 1, close, 100)
+    }
+
+    #[test]
+    fn test_checkpoint_then_replay() {
+        let dir = std::env::temp_dir().join(format!("aipriceaction-wal-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut data = InMemoryData::new();
+        data.insert("VCB".to_string(), vec![bar(4, 60.0), bar(5, 61.0)]);
+        let mut wal = Some(checkpoint(&dir, &data).unwrap());
+
+        // Intraday update of the last bar, a new session, and a record torn by a crash
+        for (symbol, bar) in [("VCB", bar(5, 61.5)), ("VCB", bar(6, 62.0)), ("FPT", bar(6, 120.0))] {
+            append(&mut wal, &TickerUpdate { symbol: symbol.to_string(), source: crate::events::UpdateSource::Vci, bar, ingested_at: None });
+        }
+        let mut writer = wal.unwrap();
+        writer.write_all(b"{\"symbol\":\"VCB\",\"ti").unwrap();
+        writer.flush().unwrap();
+
+        let (recovered, stats) = recover(&dir).unwrap();
+        assert_eq!(stats, RecoveryStats { checkpoint_bars: 2, wal_updates: 3, skipped_lines: 1 });
+        let closes: Vec<f64> = recovered["VCB"].iter().map(|bar| bar.close).collect();
+        assert_eq!(closes, vec![60.0, 61.5, 62.0]);
+        assert_eq!(recovered["FPT"].len(), 1);
+
+        // A new checkpoint absorbs the log
+        drop(writer);
+        checkpoint(&dir, &recovered).unwrap();
+        let (again, stats) = recover(&dir).unwrap();
+        assert_eq!(stats.wal_updates, 0);
+        assert_eq!(again["VCB"].len(), 3);
+
+        let snapshot = EnhancedSnapshot::build(&again, 3);
+        save_enhanced(&dir, &snapshot).unwrap();
+        let loaded = load_enhanced(&dir).unwrap().unwrap();
+        assert_eq!((loaded.version, loaded.built_at, loaded.series["VCB"].len()), (3, snapshot.built_at, 3));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,188 @@
+use crate::data_structures::InMemoryData;
+use crate::utils::market_time;
+use crate::vci::OhlcvData;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Media types of the binary `/tickers` format, negotiated with `Accept`
+pub const BARS_MEDIA_TYPE: &str = "application/vnd.aipriceaction.bars";
+pub const BARS_ZSTD_MEDIA_TYPE: &str = "application/vnd.aipriceaction.bars+zstd";
+// What public nodes send: binary if the core has it, JSON from older cores
+pub const SYNC_ACCEPT: &str = "application/vnd.aipriceaction.bars+zstd, application/vnd.aipriceaction.bars;q=0.9, application/json;q=0.5";
+
+// Every body starts with MAGIC, the format version and the compression (0 none, 1 zstd)
+const MAGIC: &[u8; 4] = b"APXB";
+pub const FORMAT_VERSION: u8 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 2;
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Plain,
+    Zstd,
+}
+
+impl Encoding {
+    pub fn media_type(self) -> &'static str {
+        match self {
+            Encoding::Plain => BARS_MEDIA_TYPE,
+            Encoding::Zstd => BARS_ZSTD_MEDIA_TYPE,
+        }
+    }
+}
+
+/// The binary encoding an `Accept` header asks for, zstd first; None means JSON
+pub fn negotiate(accept: Option<&str>) -> Option<Encoding> {
+    let accepted: Vec<&str> = accept.unwrap_or_default().split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';').map(str::trim);
+            let media_type = parts.next()?;
+            let refused = parts.any(|param| param.strip_prefix("q=").and_then(|q| q.trim().parse::<f32>().ok()) == Some(0.0));
+            (!refused).then_some(media_type)
+        })
+        .collect();
+    if accepted.iter().any(|t| t.eq_ignore_ascii_case(BARS_ZSTD_MEDIA_TYPE)) {
+        Some(Encoding::Zstd)
+    } else if accepted.iter().any(|t| t.eq_ignore_ascii_case(BARS_MEDIA_TYPE)) {
+        Some(Encoding::Plain)
+    } else {
+        None
+    }
+}
+
+/// Whether a response `Content-Type` is the binary format
+pub fn is_bars_content_type(content_type: &str) -> bool {
+    let media_type = content_type.split(';').next().unwrap_or_default().trim();
+    media_type.eq_ignore_ascii_case(BARS_MEDIA_TYPE) || media_type.eq_ignore_ascii_case(BARS_ZSTD_MEDIA_TYPE)
+}
+
+// One bar as sent: the market date as days since 1970-01-01, like JSON's date-only `time`
+#[derive(Serialize, Deserialize)]
+struct WireBar {
+    day: i32,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: u64,
+}
+
+fn epoch() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
+}
+
+/// Encode bars at full precision. As in JSON, each bar's time is sent as its market date.
+pub fn encode(data: &InMemoryData, encoding: Encoding) -> Result<Vec<u8>, String> {
+    let bars: BTreeMap<&str, Vec<WireBar>> = data.iter()
+        .map(|(symbol, series)| {
+            let bars = series.iter().map(|bar| WireBar {
+                day: (market_time::market_date(bar.time) - epoch()).num_days() as i32,
+                open: bar.open,
+                high: bar.high,
+                low: bar.low,
+                close: bar.close,
+                volume: bar.volume,
+            }).collect();
+            (symbol.as_str(), bars)
+        })
+        .collect();
+    let payload = bincode::serialize(&bars).map_err(|e| format!("Failed to encode bars: {}", e))?;
+
+    let mut body = Vec::with_capacity(HEADER_LEN + payload.len());
+    body.extend_from_slice(MAGIC);
+    body.push(FORMAT_VERSION);
+    match encoding {
+        Encoding::Plain => {
+            body.push(0);
+            body.extend_from_slice(&payload);
+        }
+        Encoding::Zstd => {
+            body.push(1);
+            body.extend(zstd::encode_all(payload.as_slice(), ZSTD_LEVEL).map_err(|e| format!("Failed to compress bars: {}", e))?);
+        }
+    }
+    Ok(body)
+}
+
+/// Decode a body produced by `encode`, rejecting other formats and versions
+pub fn decode(body: &[u8]) -> Result<InMemoryData, String> {
+    if body.len() < HEADER_LEN || &body[..MAGIC.len()] != MAGIC {
+        return Err("Not a binary bars body".to_string());
+    }
+    let (version, compression, payload) = (body[MAGIC.len()], body[MAGIC.len() + 1], &body[HEADER_LEN..]);
+    if version != FORMAT_VERSION {
+        return Err(format!("Unsupported binary bars version {}, expected {}", version, FORMAT_VERSION));
+    }
+    let decompressed;
+    let payload = match compression {
+        0 => payload,
+        1 => {
+            decompressed = zstd::decode_all(payload).map_err(|e| format!("Failed to decompress bars: {}", e))?;
+            decompressed.as_slice()
+        }
+        other => return Err(format!("Unknown binary bars compression {}", other)),
+    };
+    let bars: BTreeMap<String, Vec<WireBar>> = bincode::deserialize(payload).map_err(|e| format!("Failed to decode bars: {}", e))?;
+    Ok(bars.into_iter()
+        .map(|(symbol, bars)| {
+            let series = bars.into_iter().map(|bar| OhlcvData {
+                time: market_time::market_day_start(epoch() + chrono::Duration::days(bar.day as i64)),
+                open: bar.open,
+                high: bar.high,
+                low: bar.low,
+                close: bar.close,
+                volume: bar.volume,
+                symbol: Some(symbol.clone()),
+            }).collect();
+            (symbol, series)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_bars::bar;
+
+    #[test]
+    fn test_binary_round_trip_and_negotiation() {
+        let mut data = InMemoryData::new();
+        for (symbol, base) in [("VCB", 60_000.0), ("FPT", 123_456.789)] {
+            data.insert(symbol.to_string(), (0..50).map(|day| OhlcvData {
+                open: base + day as f64 / 3.0,
+                high: base + 100.0,
+                low: base - 100.0,
+                symbol: Some(symbol.to_string()),
+                ..bar(day, base + day as f64 / 7.0, 1_000_000 + day as u64)
+            }).collect());
+        }
+        // Like JSON, times become market dates; unlike it, prices round-trip exactly
+        let expected: InMemoryData = data.iter()
+            .map(|(symbol, series)| {
+                let series = series.iter().map(|bar| OhlcvData { time: market_time::market_day_start(market_time::market_date(bar.time)), ..bar.clone() }).collect();
+                (symbol.clone(), series)
+            })
+            .collect();
+
+        let plain = encode(&data, Encoding::Plain).unwrap();
+        let compressed = encode(&data, Encoding::Zstd).unwrap();
+        assert!(compressed.len() < plain.len());
+        assert!(plain.len() < serde_json::to_vec(&data).unwrap().len());
+        assert_eq!(decode(&plain).unwrap(), expected);
+        assert_eq!(decode(&compressed).unwrap(), expected);
+
+        let mut future = plain.clone();
+        future[MAGIC.len()] = FORMAT_VERSION + 1;
+        assert!(decode(&future).unwrap_err().contains("Unsupported binary bars version"));
+        assert!(decode(b"{\"VCB\":[]}").is_err());
+
+        assert_eq!(negotiate(Some(SYNC_ACCEPT)), Some(Encoding::Zstd));
+        assert_eq!(negotiate(Some("application/vnd.aipriceaction.bars, application/json")), Some(Encoding::Plain));
+        assert_eq!(negotiate(Some("application/vnd.aipriceaction.bars+zstd; q=0, application/vnd.aipriceaction.bars")), Some(Encoding::Plain));
+        assert_eq!(negotiate(Some("application/json, */*")), None);
+        assert_eq!(negotiate(None), None);
+        assert!(is_bars_content_type("application/vnd.aipriceaction.bars+zstd"));
+        assert!(!is_bars_content_type("application/json"));
+    }
+}
@@ -1,26 +1,271 @@
-use crate::config::{AppConfig, load_ticker_groups};
-use crate::data_structures::{InMemoryData, SharedData, SharedOfficeHoursState, OfficeHoursState, is_within_office_hours, get_current_interval, SharedHealthStats, get_time_info, get_current_time};
-use std::time::Duration;
+use crate::analysis::indicator_cache;
+use crate::analysis::market_cap::MarketCaps;
+use crate::analysis::synthetic_index;
+use crate::constituents::SharedIndexConstituents;
+use crate::config::{AppConfig, OfficeHoursConfig, load_ticker_groups};
+use crate::events::{self, SharedEventBus, UpdateSource};
+use crate::gossip::GossipBroadcaster;
+use crate::utils::change_log::{ChangeSet, SymbolChange};
+use crate::utils::corrections::{self, CorrectionSource};
+use crate::utils::http_client;
+use crate::utils::market_time;
+use crate::utils::merge_policy;
+use crate::utils::metrics::{self, Timer};
+use crate::utils::recalculation;
+use crate::utils::symbol_stats;
+use crate::utils::transitions;
+use crate::utils::object_store::SharedObjectStore;
+use crate::shutdown;
+use crate::wire;
+use crate::standby::{StandbyMode, StandbyMonitor, StandbyTransition};
+use crate::vci::{OhlcvData, VciClient};
+use crate::data_source::{DataSource, DataSources, SourceKind};
+use crate::intraday::{self, IntradayConfig, Interval, SharedIntradayData};
+use crate::profile::Universe;
+use crate::ticker_info::SharedTickerDirectory;
+use crate::data_structures::{InMemoryData, SharedData, SharedGossipStaging, SharedReputation, StagingOutcome, check_staged_contributions, record_staging_outcome, SharedOfficeHoursState, OfficeHoursState, is_within_office_hours, is_symbol_in_session, open_sessions, get_current_interval, SharedHealthStats, get_time_info, get_provisional_date};
+use std::time::{Duration, Instant};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
-use reqwest::Client as ReqwestClient;
 use rand::prelude::SliceRandom;
 use tokio::sync::Mutex;
-use chrono::Utc;
+use tokio_util::sync::CancellationToken;
+use chrono::{NaiveDate, Utc};
+use reqwest::StatusCode;
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
 use tracing::{info, debug, warn, error, instrument};
 
-#[instrument(skip(data, config, health_stats))]
-pub async fn run(data: SharedData, config: AppConfig, health_stats: SharedHealthStats) {
+const BATCH_SIZE: usize = 10;
+const REGULAR_LOOKBACK_DAYS: i64 = 7;
+
+/// Every ticker of the profile's universe plus the VNINDEX and VN30 indices and VN30 futures, shuffled.
+/// Synthetic indices are built from their constituents rather than fetched.
+fn load_all_tickers(universe: &Universe, synthetic: &[String]) -> Vec<String> {
+    let ticker_groups = load_ticker_groups();
+    let constituents = crate::constituents::load_index_constituents();
+    let mut all_tickers = universe.symbols(&ticker_groups, &constituents, market_time::market_today());
+    if all_tickers.is_empty() {
+        warn!(?universe, "Profile universe has no symbols, fetching the indices only");
+    }
+    
+    // Add VNINDEX and VN30 (Vietnam stock market indices) to the ticker list
+    all_tickers.push("VNINDEX".to_string());
+    all_tickers.push("VN30".to_string());
+    // VN30 futures for the basis, even when the universe leaves out their group
+    all_tickers.extend(crate::analysis::basis::FUTURES_SYMBOLS.iter().map(|symbol| symbol.to_string()));
+    
+    // Remove duplicates and shuffle
+    all_tickers.sort();
+    all_tickers.dedup();
+    all_tickers.retain(|ticker| !synthetic.contains(ticker));
+    all_tickers.shuffle(&mut rand::rng());
+    all_tickers
+}
+
+/// Indices rebuilt from their constituents after every fetch cycle and stored like fetched tickers
+struct SyntheticIndices {
+    names: Vec<String>,
+    constituents: SharedIndexConstituents,
+    caps: MarketCaps,
+}
+
+impl SyntheticIndices {
+    async fn load(names: &[String]) -> Self {
+        let constituents = crate::constituents::load_index_constituents();
+        for name in names.iter().filter(|name| !constituents.0.contains_key(*name)) {
+            warn!(index = %name, "Synthetic index has no constituents in index_constituents.json, it will stay empty");
+        }
+        let caps = if names.is_empty() {
+            MarketCaps::default()
+        } else {
+            MarketCaps::from_directory(&*crate::ticker_info::load_ticker_directory().lock().await)
+        };
+        Self { names: names.to_vec(), constituents, caps }
+    }
+
+    async fn update(&self, data: &SharedData, event_bus: &SharedEventBus) {
+        if self.names.is_empty() {
+            return;
+        }
+        let mut data = data.lock().await;
+        for name in &self.names {
+            let series = synthetic_index::build(name, &self.constituents, &data, &self.caps);
+            if series.is_empty() {
+                debug!(index = %name, "No constituent bars for synthetic index");
+                continue;
+            }
+            let sessions = series.len();
+            let existing = data.entry(name.clone()).or_default();
+            if crate::data_structures::replace_series(name, existing, series) {
+                debug!(index = %name, sessions, "Rebuilt synthetic index");
+                if let Some(latest) = existing.last() {
+                    events::publish(event_bus, name, UpdateSource::Synthetic, latest.clone());
+                }
+            }
+        }
+    }
+}
+
+// Shared state for reconciling staged public contributions after each authoritative refresh
+#[derive(Clone)]
+pub struct GossipReconciler {
+    pub staging: SharedGossipStaging,
+    pub reputation: SharedReputation,
+}
+
+impl GossipReconciler {
+    /// Check staged contributions for refreshed symbols; call with the data lock held. Staged bars
+    /// are never merged: contributions newer than the refreshed data stay staged for a later fetch.
+    async fn reconcile(&self, data: &InMemoryData, symbols: &[String]) -> StagingOutcome {
+        let mut staging = self.staging.lock().await;
+        let mut outcome = StagingOutcome::default();
+        for symbol in symbols {
+            let Some(staged) = staging.remove(symbol) else { continue };
+            let Some(series) = data.get(symbol) else { continue };
+            let symbol_outcome = check_staged_contributions(series, staged);
+            debug!(symbol, confirmed = symbol_outcome.confirmed.len(), rejected = symbol_outcome.rejected.len(), pending = symbol_outcome.pending.len(), "Reconciled staged contributions");
+            outcome.confirmed.extend(symbol_outcome.confirmed);
+            outcome.rejected.extend(symbol_outcome.rejected);
+            if !symbol_outcome.pending.is_empty() {
+                staging.insert(symbol.clone(), symbol_outcome.pending);
+            }
+        }
+        outcome
+    }
+
+    /// Apply the reputation changes from a reconciliation; call after releasing the data lock
+    async fn record(&self, outcome: &StagingOutcome) {
+        if outcome.confirmed.is_empty() && outcome.rejected.is_empty() {
+            return;
+        }
+        metrics::increment_counter("gossip.staged_confirmed", outcome.confirmed.len() as u64);
+        metrics::increment_counter("gossip.staged_rejected", outcome.rejected.len() as u64);
+        record_staging_outcome(&mut *self.reputation.lock().await, outcome);
+        info!(confirmed = outcome.confirmed.len(), rejected = outcome.rejected.len(), "Reconciled staged public contributions");
+    }
+}
+
+// Intraday candle store and the intervals collected into it
+pub struct IntradayCollector {
+    pub data: SharedIntradayData,
+    pub config: IntradayConfig,
+}
+
+impl IntradayCollector {
+    /// Fetch each collected interval for a batch whose daily bars were just fetched. A batch with a
+    /// symbol that has no candles yet fetches the interval's initial lookback, otherwise only today.
+    async fn fetch_batch(&self, sources: &mut DataSources, batch: &[String]) {
+        let today = market_time::market_today();
+        for &interval in &self.config.intervals {
+            let seeded = self.data.lock().await.get(&interval)
+                .is_some_and(|interval_data| batch.iter().all(|symbol| interval_data.get(symbol).is_some_and(|series| !series.is_empty())));
+            let lookback_days = if seeded { 0 } else { interval.initial_lookback_days() };
+            let start_date = (today - chrono::Duration::days(lookback_days)).format("%Y-%m-%d").to_string();
+            let end_date = today.format("%Y-%m-%d").to_string();
+            let (source, fetch_result) = sources.get_batch_history(batch, &start_date, &end_date, interval).await;
+            match fetch_result {
+                Ok(batch_data) => {
+                    let added = self.merge(interval, batch_data.into_iter().filter_map(|(symbol, bars)| Some((symbol, bars?)))).await;
+                    debug!(interval = interval.name(), source = source.name(), symbols = batch.len(), added, "Fetched intraday candles");
+                }
+                Err(e) => warn!(interval = interval.name(), source = source.name(), error = %e, "Failed to fetch intraday candles"),
+            }
+        }
+    }
+
+    /// Pull the latest session of each collected interval from the core node
+    async fn sync_from_core(&self, client: &reqwest::Client, core_url: &str) -> Result<usize, String> {
+        let mut added = 0;
+        for &interval in &self.config.intervals {
+            let response = client.get(format!("{}/tickers", core_url))
+                .query(&[("interval", interval.name()), ("precision", "full")])
+                .header(ACCEPT, wire::SYNC_ACCEPT)
+                .send().await
+                .map_err(|e| format!("Intraday {} request failed: {}", interval.name(), e))?;
+            if !response.status().is_success() {
+                return Err(format!("Core network responded with {} to the intraday {} request", response.status(), interval.name()));
+            }
+            let core_data = read_bars(response).await.map_err(|e| format!("Invalid intraday {} response: {}", interval.name(), e))?;
+            added += self.merge(interval, core_data).await;
+        }
+        Ok(added)
+    }
+
+    /// Merge candles into one interval's store and trim it back to the interval's memory budget
+    async fn merge(&self, interval: Interval, fetched: impl IntoIterator<Item = (String, Vec<OhlcvData>)>) -> usize {
+        let mut intraday_guard = self.data.lock().await;
+        let interval_data = intraday_guard.entry(interval).or_default();
+        let added = fetched.into_iter()
+            .map(|(symbol, bars)| intraday::merge_bars(interval_data.entry(symbol).or_default(), bars))
+            .sum();
+        let dropped = intraday::enforce_budget(interval_data, self.config.memory_budget_bytes(interval));
+        if dropped > 0 {
+            metrics::increment_counter(&format!("intraday.{}_trimmed", interval.name()), dropped as u64);
+            debug!(interval = interval.name(), dropped, "Trimmed intraday candles to the memory budget");
+        }
+        added
+    }
+}
+
+/// Run the core or public node worker until `shutdown` is cancelled. A fetch cycle in progress
+/// stops after its current batch.
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip(data, config, health_stats, object_store, staging, reputation, event_bus, intraday, tickers, shutdown))]
+pub async fn run(data: SharedData, config: AppConfig, health_stats: SharedHealthStats, object_store: SharedObjectStore, staging: SharedGossipStaging, reputation: SharedReputation, event_bus: SharedEventBus, intraday: SharedIntradayData, tickers: SharedTickerDirectory, shutdown: CancellationToken) {
+    let reconciler = GossipReconciler { staging, reputation };
+    let intraday = IntradayCollector { data: intraday, config: config.intraday.clone() };
     if let Some(core_url) = &config.core_network_url {
-        info!(%core_url, "Starting as public node worker");
-        run_public_node_worker(data, core_url.clone(), config.public_refresh_interval, health_stats).await;
+        info!(%core_url, standby = config.standby_promote_after.is_some(), "Starting as public node worker");
+        let standby = match config.standby_promote_after {
+            Some(promote_after) => StandbyFetcher::new(promote_after, &config, SyntheticIndices::load(&config.synthetic_indices).await),
+            None => None,
+        };
+        run_public_node_worker(data, core_url.clone(), config.public_refresh_interval, health_stats, reconciler, event_bus, standby, intraday, shutdown).await;
     } else {
         info!(environment = %config.environment, "Starting as core node worker");
-        run_core_node_worker(data, config, health_stats).await;
+        run_core_node_worker(data, config, health_stats, object_store, reconciler, event_bus, intraday, tickers, shutdown).await;
+    }
+}
+
+/// Refetch the dates of queued `/admin/recalculate` jobs and replace the stored bars with the
+/// fetched ones. Indicators then recompute from the earliest replaced bar on their next lookup.
+async fn refetch_recalculations(sources: &mut DataSources, data: &SharedData) {
+    for job in recalculation::start_queued() {
+        let Some((first, last)) = job.date_range() else {
+            continue;
+        };
+        info!(job = job.id, symbols = job.symbols.len(), dates = job.dates.len(), "Refetching dates for recalculation");
+        let (start_date, end_date) = (first.format("%Y-%m-%d").to_string(), last.format("%Y-%m-%d").to_string());
+        for batch in job.symbols.chunks(BATCH_SIZE) {
+            let (source, fetch_result) = sources.get_batch_history(batch, &start_date, &end_date, Interval::OneDay).await;
+            match fetch_result {
+                Ok(mut batch_data) => {
+                    let mut data_guard = data.lock().await;
+                    for symbol in batch {
+                        let result = match (batch_data.remove(symbol).flatten(), data_guard.get_mut(symbol)) {
+                            (Some(fetched), Some(series)) => Ok(crate::data_structures::replace_bars(symbol, series, fetched, &job.dates)),
+                            (Some(_), None) => Err(format!("{} has no stored bars", symbol)),
+                            (None, _) => Err(format!("{} returned no data for {}", source.name(), symbol)),
+                        };
+                        debug!(job = job.id, symbol, ?result, "Refetched symbol for recalculation");
+                        recalculation::record_symbol(job.id, symbol, result);
+                    }
+                }
+                Err(e) => {
+                    warn!(job = job.id, source = source.name(), error = %e, "Failed to refetch batch for recalculation");
+                    for symbol in batch {
+                        recalculation::record_symbol(job.id, symbol, Err(e.clone()));
+                    }
+                }
+            }
+        }
+        metrics::increment_counter("worker.recalculations", 1);
     }
 }
 
-#[instrument(skip(data, config, health_stats))]
-async fn run_core_node_worker(data: SharedData, config: AppConfig, health_stats: SharedHealthStats) {
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip(data, config, health_stats, object_store, reconciler, event_bus, intraday, tickers, shutdown))]
+async fn run_core_node_worker(data: SharedData, config: AppConfig, health_stats: SharedHealthStats, object_store: SharedObjectStore, reconciler: GossipReconciler, event_bus: SharedEventBus, intraday: IntradayCollector, tickers: SharedTickerDirectory, shutdown: CancellationToken) {
     info!("Initializing core node worker");
     
     // Initialize office hours state
@@ -36,37 +281,51 @@ async fn run_core_node_worker(data: SharedData, config: AppConfig, health_stats:
         "Office hours configuration loaded"
     );
     
-    let mut vci_client = match crate::vci::VciClient::new(true, 30) {
-        Ok(client) => {
-            info!("VCI client initialized successfully");
-            client
-        }
-        Err(e) => {
-            error!(?e, "Failed to initialize VCI client");
-            return;
-        }
-    };
-    
-    // Load ticker groups and combine all tickers into a single array
-    let ticker_groups = load_ticker_groups();
-    let mut all_tickers: Vec<String> = ticker_groups.0.values()
-        .flat_map(|group_tickers| group_tickers.iter().cloned())
+    let clients: Vec<DataSource> = config.data_sources.sources.iter()
+        .filter_map(|kind| match kind {
+            SourceKind::Vci => match crate::vci::VciClient::new(true) {
+                Ok(client) => {
+                    info!(header_overrides = config.header_profiles.contains_key("vci"), "VCI client initialized successfully");
+                    Some(DataSource::Vci(client.with_header_overrides(config.header_profiles.get("vci"))))
+                }
+                Err(e) => {
+                    error!(?e, "Failed to initialize VCI client");
+                    None
+                }
+            },
+            SourceKind::Tcbs => match crate::tcbs::TcbsClient::new(true) {
+                Ok(client) => {
+                    info!("TCBS client initialized successfully");
+                    Some(DataSource::Tcbs(client))
+                }
+                Err(e) => {
+                    error!(?e, "Failed to initialize TCBS client");
+                    None
+                }
+            },
+        })
         .collect();
+    if clients.is_empty() {
+        error!("No data source could be initialized");
+        return;
+    }
+    let mut sources = DataSources::new(clients, &config.data_sources);
+    health_stats.lock().await.data_sources = Some(sources.status());
     
-    // Add VNINDEX and VN30 (Vietnam stock market indices) to the ticker list
-    all_tickers.push("VNINDEX".to_string());
-    all_tickers.push("VN30".to_string());
-    
-    // Remove duplicates and shuffle
-    all_tickers.sort();
-    all_tickers.dedup();
-    all_tickers.shuffle(&mut rand::rng());
+    // Load ticker groups and combine all tickers into a single array
+    let mut all_tickers = load_all_tickers(&config.profile.universe, &config.synthetic_indices);
+    let synthetic = SyntheticIndices::load(&config.synthetic_indices).await;
     
-    info!(total_tickers = all_tickers.len(), "Loaded and shuffled all tickers from ticker groups");
+    info!(total_tickers = all_tickers.len(), profile = %config.profile_name, "Loaded and shuffled all tickers of the profile universe");
     debug!(first_10_tickers = ?all_tickers.iter().take(10).collect::<Vec<_>>(), "First 10 tickers after shuffle");
     
-    let gossip_client = ReqwestClient::new();
-    const BATCH_SIZE: usize = 10;
+    let gossip_client = http_client::shared_client();
+    // Internal peers get every update with retries; public peers stay best-effort
+    let broadcaster = GossipBroadcaster::start(&config.internal_peers, config.tokens.outbound(), gossip_client.clone(), shutdown.clone());
+    // Last successful fetch per symbol, so closed sessions are refreshed at the off-hours pace
+    let mut last_fetched: HashMap<String, Instant> = HashMap::new();
+    // Last market date archived through, so finalized days are written once per date
+    let mut last_archived: Option<NaiveDate> = None;
     let mut iteration_count = 0;
     let start_time = std::time::Instant::now();
 
@@ -97,6 +356,8 @@ async fn run_core_node_worker(data: SharedData, config: AppConfig, health_stats:
                     current_interval_secs = current_interval.as_secs(),
                     "Office hours status changed"
                 );
+                let (from, to) = if is_office_hours { ("closed", "open") } else { ("open", "closed") };
+                transitions::record("office_hours", from, to, format!("interval now {}s", current_interval.as_secs()));
             }
         }
         
@@ -107,11 +368,12 @@ async fn run_core_node_worker(data: SharedData, config: AppConfig, health_stats:
             let (current_time, debug_override) = get_time_info();
             
             // Calculate memory usage
-            let memory_bytes = crate::data_structures::estimate_memory_usage(&*data_guard);
+            let memory_bytes = crate::data_structures::estimate_memory_usage(&data_guard);
             let memory_mb = memory_bytes as f64 / (1024.0 * 1024.0);
             let memory_percent = (memory_bytes as f64 / crate::data_structures::MAX_MEMORY_BYTES as f64) * 100.0;
             
             health.is_office_hours = is_office_hours;
+            health.open_sessions = open_sessions(&config.office_hours_config);
             health.current_interval_secs = current_interval.as_secs();
             health.iteration_count = iteration_count;
             health.uptime_secs = start_time.elapsed().as_secs();
@@ -142,65 +404,113 @@ async fn run_core_node_worker(data: SharedData, config: AppConfig, health_stats:
             "Starting data fetch cycle"
         );
         
-        // Calculate date range for VCI API call (current date and 7 days ago)
-        let current_date = get_current_time();
+        // Calculate date range for VCI API call (longer lookback on the first cycle, then 7 days)
+        let lookback_days = if iteration_count == 1 { config.profile.history_days } else { REGULAR_LOOKBACK_DAYS };
+        let current_date = market_time::market_today();
         let end_date = current_date.format("%Y-%m-%d").to_string();
-        let start_date = (current_date - chrono::Duration::days(7)).format("%Y-%m-%d").to_string();
+        let start_date = (current_date - chrono::Duration::days(lookback_days)).format("%Y-%m-%d").to_string();
         
         debug!(
             iteration = iteration_count,
+            lookback_days,
             start_date = %start_date,
             end_date = %end_date,
             "Using dynamic date range for VCI API calls"
         );
 
-        // Process all tickers in batches of 10
-        for (batch_idx, ticker_batch) in all_tickers.chunks(BATCH_SIZE).enumerate() {
+        let cycle_timer = Timer::start("worker.cycle");
+        metrics::increment_counter("worker.iterations", 1);
+
+        // Each symbol follows its own exchange session: while another session keeps the cycle
+        // on the fast interval, symbols whose session is closed wait for the off-hours interval.
+        // Dormant symbols (no trades for weeks) always run at the off-hours pace.
+        let today = market_time::market_today();
+        let stats = symbol_stats::snapshot();
+        let is_dormant = |symbol: &str| stats.get(symbol).is_some_and(|s| s.is_dormant(today));
+        let is_flaky = |symbol: &str| stats.get(symbol).is_some_and(|s| s.is_flaky());
+        let exchanges = tickers.lock().await.exchanges();
+        let mut due_tickers: Vec<String> = all_tickers.iter()
+            .filter(|symbol| {
+                let in_session = !config.enable_office_hours || is_symbol_in_session(&config.office_hours_config, symbol, exchanges.get(*symbol).map(String::as_str));
+                (in_session && !is_dormant(symbol))
+                    || last_fetched.get(*symbol).is_none_or(|at| at.elapsed() >= config.non_office_hours_interval)
+            })
+            .cloned()
+            .collect();
+        if due_tickers.len() < all_tickers.len() {
+            debug!(iteration = iteration_count, due = due_tickers.len(), skipped = all_tickers.len() - due_tickers.len(), "Skipping symbols whose session is closed or that are dormant");
+        }
+        // Flaky symbols go last so they share batches and a bad one can't fail a healthy batch
+        due_tickers.sort_by_key(|symbol| is_flaky(symbol));
+        transitions::set_worker_state(iteration_count, due_tickers.len(), current_interval.as_secs());
+
+        // Process due tickers in batches of 10
+        sources.begin_cycle();
+        refetch_recalculations(&mut sources, &data).await;
+        for (batch_idx, ticker_batch) in due_tickers.chunks(BATCH_SIZE).enumerate() {
             let batch_num = batch_idx + 1;
+            if shutdown.is_cancelled() {
+                info!(iteration = iteration_count, batch = batch_num, "Shutdown requested, stopping fetch cycle");
+                break;
+            }
             info!(iteration = iteration_count, batch = batch_num, batch_size = ticker_batch.len(), "Processing ticker batch");
             
-            match vci_client.get_batch_history(ticker_batch, &start_date, Some(&end_date), "1D").await {
+            let (source, fetch_result) = sources.get_batch_history(ticker_batch, &start_date, &end_date, Interval::OneDay).await;
+            health_stats.lock().await.data_sources = Some(sources.status());
+
+            match fetch_result {
                 Ok(batch_data) => {
-                    info!(iteration = iteration_count, batch = batch_num, symbols_count = batch_data.len(), "Successfully fetched batch data from VCI");
+                    let fetched_at = Instant::now();
+                    let observed_at = Utc::now();
+                    let provisional_date = get_provisional_date(&config.office_hours_config);
+                    last_fetched.extend(ticker_batch.iter().map(|symbol| (symbol.clone(), fetched_at)));
+                    info!(iteration = iteration_count, batch = batch_num, symbols_count = batch_data.len(), source = source.name(), "Successfully fetched batch data");
                     
                     let mut data_guard = data.lock().await;
                     let mut updated_symbols = Vec::new();
                     let mut batch_stats = Vec::new();
                     
                     for (symbol, ohlcv_data_vec) in batch_data {
+                        symbol_stats::record_fetch(&symbol, ohlcv_data_vec.is_some());
                         if let Some(data_vec) = ohlcv_data_vec {
                             let data_points = data_vec.len();
                             let latest_data = data_vec.last().cloned();
                             let date_range = if !data_vec.is_empty() {
                                 format!("{} to {}", 
-                                    data_vec.first().unwrap().time.format("%Y-%m-%d"),
-                                    data_vec.last().unwrap().time.format("%Y-%m-%d"))
+                                    market_time::format_market_date(data_vec.first().unwrap().time),
+                                    market_time::format_market_date(data_vec.last().unwrap().time))
                             } else {
                                 "empty".to_string()
                             };
                             
                             // Limit data points per symbol to prevent memory bloat
                             let mut limited_data_vec = data_vec;
-                            if limited_data_vec.len() > crate::data_structures::MAX_DATA_POINTS_PER_SYMBOL {
+                            if limited_data_vec.len() > config.profile.max_points_per_symbol {
                                 // Sort by time and keep only the most recent data points
-                                limited_data_vec.sort_by(|a, b| b.time.cmp(&a.time)); // Newest first
-                                limited_data_vec.truncate(crate::data_structures::MAX_DATA_POINTS_PER_SYMBOL);
+                                limited_data_vec.sort_by_key(|d| std::cmp::Reverse(d.time)); // Newest first
+                                limited_data_vec.truncate(config.profile.max_points_per_symbol);
                                 debug!(symbol, original_points = data_points, limited_points = limited_data_vec.len(), "Limited data points per symbol");
                             }
                             
+                            // Bars that may not replace what is stored (e.g. a live bar over the official close) are dropped
+                            let limited_data_vec = merge_policy::admit_bars(&symbol, limited_data_vec, observed_at, |date| merge_policy::fetched_origin(date, provisional_date));
+
                             // Use dividend-aware deduplication instead of direct replacement
                             let existing_entry = data_guard.entry(symbol.clone()).or_default();
                             let existing_count = existing_entry.len();
-                            let added_count = crate::data_structures::merge_and_deduplicate_data(existing_entry, limited_data_vec);
+                            let added_count = crate::data_structures::merge_and_track_changes(&symbol, existing_entry, limited_data_vec, source.into());
                             let final_count = existing_entry.len();
+                            symbol_stats::record_series(&symbol, existing_entry);
                             
+                            if let Some(latest) = existing_entry.last() {
+                                events::publish(&event_bus, &symbol, source.update_source(), latest.clone());
+                            }
                             updated_symbols.push(symbol.clone());
                             batch_stats.push(format!("{}:{}→{}", symbol, existing_count, final_count));
                             debug!(symbol, existing_count, added_count, final_count, date_range, "Applied dividend-aware deduplication");
 
                             if let Some(gossip_payload) = latest_data {
                                 // --- 1. Broadcast to INTERNAL peers (trusted, with token) ---
-                                let auth_token = format!("Bearer {}", config.tokens.primary);
                                 let internal_peer_count = config.internal_peers.len();
                                 
                                 // During non-office hours, reduce internal peer broadcasting frequency (only if office hours are enabled)
@@ -213,28 +523,7 @@ async fn run_core_node_worker(data: SharedData, config: AppConfig, health_stats:
                                 
                                 if should_broadcast_internal {
                                     debug!(symbol, internal_peers = internal_peer_count, is_office_hours, "Broadcasting to internal peers");
-                                    for peer_url in config.internal_peers.iter() {
-                                    let client = gossip_client.clone();
-                                    let token = auth_token.clone();
-                                    let payload = gossip_payload.clone();
-                                    let url = format!("{}/gossip", peer_url);
-                                    let peer_url_clone = peer_url.clone();
-                                    
-                                    tokio::spawn(async move {
-                                        match client.post(&url).header("Authorization", token).json(&payload).send().await {
-                                            Ok(response) => {
-                                                if response.status().is_success() {
-                                                    debug!(peer = %peer_url_clone, "Successfully sent to internal peer");
-                                                } else {
-                                                    warn!(peer = %peer_url_clone, status = %response.status(), "Internal peer responded with error");
-                                                }
-                                            }
-                                            Err(e) => {
-                                                warn!(peer = %peer_url_clone, error = ?e, "Failed to send to internal peer");
-                                            }
-                                        }
-                                    });
-                                    }
+                                    broadcaster.send(&symbol, &gossip_payload, observed_at);
                                 } else {
                                     debug!(symbol, is_office_hours, iteration = iteration_count, "Skipping internal peer broadcast (non-office hours throttling)");
                                 }
@@ -274,31 +563,87 @@ async fn run_core_node_worker(data: SharedData, config: AppConfig, health_stats:
                                 }
                             }
                         } else {
-                            warn!(symbol, "No data available for symbol");
+                            // Known-flaky symbols miss often; keep them out of the warning stream
+                            if is_flaky(&symbol) {
+                                debug!(symbol, "No data available for flaky symbol");
+                            } else {
+                                warn!(symbol, "No data available for symbol");
+                            }
                             batch_stats.push(format!("{}:0→0", symbol));
                         }
                     }
                     
+                    let staging_outcome = reconciler.reconcile(&data_guard, &updated_symbols).await;
                     drop(data_guard);
+                    reconciler.record(&staging_outcome).await;
                     info!(iteration = iteration_count, batch = batch_num, symbols_with_data = batch_stats.join(", "), "Completed batch processing");
+                    intraday.fetch_batch(&mut sources, ticker_batch).await;
                 }
                 Err(e) => {
-                    error!(iteration = iteration_count, batch = batch_num, error = ?e, "Failed to fetch batch data from VCI");
+                    for symbol in ticker_batch {
+                        symbol_stats::record_fetch(symbol, false);
+                    }
+                    error!(iteration = iteration_count, batch = batch_num, source = source.name(), error = %e, "Failed to fetch batch data");
                 }
             }
             
             // Sleep 1-2 seconds between batches
             let sleep_duration = Duration::from_millis(1000 + (rand::random::<u64>() % 1000));
             debug!(batch = batch_num, sleep_ms = sleep_duration.as_millis(), "Sleeping between batches");
-            tokio::time::sleep(sleep_duration).await;
+            shutdown::sleep(&shutdown, sleep_duration).await;
         }
         
-        info!(iteration = iteration_count, "Completed full cycle of all ticker batches");
+        synthetic.update(&data, &event_bus).await;
+        let cycle_elapsed = cycle_timer.stop();
+        info!(iteration = iteration_count, elapsed_secs = cycle_elapsed.as_secs(), "Completed full cycle of all ticker batches");
+        if let Err(e) = symbol_stats::save() {
+            warn!(iteration = iteration_count, error = ?e, "Failed to save symbol statistics");
+        }
+        if let Err(e) = indicator_cache::save() {
+            warn!(iteration = iteration_count, error = ?e, "Failed to save indicator cache");
+        }
+        if shutdown.is_cancelled() {
+            info!(iteration = iteration_count, "Core node worker stopped");
+            return;
+        }
+
+        if iteration_count == 1 {
+            health_stats.lock().await.initial_load_complete = true;
+            info!(report = ?metrics::get_performance_report(), "Initial history load complete");
+        }
+
+        // Archive days whose bars are final (everything before the still-open session),
+        // once per newly finalized market date and off the async runtime
+        if let Some(archive_dir) = &config.archive_dir {
+            let provisional_date = get_provisional_date(&config.office_hours_config);
+            let finalized_through = provisional_date
+                .and_then(|provisional| provisional.pred_opt())
+                .unwrap_or_else(market_time::market_today);
+            if last_archived != Some(finalized_through) {
+                let snapshot = data.lock().await.clone();
+                let dir = std::path::PathBuf::from(archive_dir);
+                let archived = tokio::task::spawn_blocking(move || {
+                    crate::archive::archive_finalized_days(&snapshot, &dir, |date| date <= finalized_through)
+                }).await;
+                match archived {
+                    Ok(Ok(written)) => {
+                        last_archived = Some(finalized_through);
+                        if let Some(store) = &object_store
+                            && !written.is_empty()
+                        {
+                            crate::archive::upload_partitions(store, std::path::Path::new(archive_dir), &written).await;
+                        }
+                    }
+                    Ok(Err(e)) => error!(iteration = iteration_count, archive_dir, error = ?e, "Failed to archive finalized days"),
+                    Err(e) => error!(iteration = iteration_count, archive_dir, error = ?e, "Archive task panicked"),
+                }
+            }
+        }
         
         // Check memory usage and cleanup if needed
         {
             let mut data_guard = data.lock().await;
-            let memory_bytes = crate::data_structures::estimate_memory_usage(&*data_guard);
+            let memory_bytes = crate::data_structures::estimate_memory_usage(&data_guard);
             let memory_mb = memory_bytes as f64 / (1024.0 * 1024.0);
             
             if memory_bytes > crate::data_structures::MAX_MEMORY_BYTES {
@@ -308,8 +653,19 @@ async fn run_core_node_worker(data: SharedData, config: AppConfig, health_stats:
                     "Memory limit exceeded, cleaning up old data"
                 );
                 
-                let (cleaned_symbols, cleaned_data_points) = crate::data_structures::cleanup_old_data(&mut *data_guard);
-                let new_memory_bytes = crate::data_structures::estimate_memory_usage(&*data_guard);
+                // Dormant symbols give up most of their history first
+                let mut dormant_trimmed = 0;
+                for (symbol, series) in data_guard.iter_mut() {
+                    if is_dormant(symbol) && series.len() > symbol_stats::DORMANT_RETENTION_POINTS {
+                        dormant_trimmed += series.len() - symbol_stats::DORMANT_RETENTION_POINTS;
+                        series.drain(..series.len() - symbol_stats::DORMANT_RETENTION_POINTS);
+                    }
+                }
+                if dormant_trimmed > 0 {
+                    info!(dormant_trimmed, "Trimmed history of dormant symbols");
+                }
+                let (cleaned_symbols, cleaned_data_points) = crate::data_structures::cleanup_old_data(&mut data_guard, config.profile.max_points_per_symbol);
+                let new_memory_bytes = crate::data_structures::estimate_memory_usage(&data_guard);
                 let new_memory_mb = new_memory_bytes as f64 / (1024.0 * 1024.0);
                 
                 info!(
@@ -329,7 +685,10 @@ async fn run_core_node_worker(data: SharedData, config: AppConfig, health_stats:
         }
         
         debug!(interval = ?current_interval, "Sleeping before next full cycle");
-        tokio::time::sleep(current_interval).await;
+        if !shutdown::sleep(&shutdown, current_interval).await {
+            info!(iteration = iteration_count, "Core node worker stopped");
+            return;
+        }
         
         // Re-shuffle for next iteration
         all_tickers.shuffle(&mut rand::rng());
@@ -337,62 +696,312 @@ async fn run_core_node_worker(data: SharedData, config: AppConfig, health_stats:
     }
 }
 
-#[instrument(skip(data, _health_stats), fields(core_url = %core_network_url, refresh_interval = ?refresh_interval))]
-async fn run_public_node_worker(data: SharedData, core_network_url: String, refresh_interval: Duration, _health_stats: SharedHealthStats) {
+/// VCI fetcher a standby public node keeps ready while mirroring and only runs once promoted
+struct StandbyFetcher {
+    monitor: StandbyMonitor,
+    vci_client: VciClient,
+    tickers: Vec<String>,
+    office_hours: OfficeHoursConfig, // Tells live bars from official ones
+    history_days: i64,
+    max_points_per_symbol: usize,
+    synthetic: SyntheticIndices,
+}
+
+impl StandbyFetcher {
+    fn new(promote_after: Duration, config: &AppConfig, synthetic: SyntheticIndices) -> Option<Self> {
+        match VciClient::new(true) {
+            Ok(client) => {
+                let tickers = load_all_tickers(&config.profile.universe, &config.synthetic_indices);
+                info!(promote_after_secs = promote_after.as_secs(), total_tickers = tickers.len(), "Standby VCI fetcher ready");
+                Some(Self {
+                    monitor: StandbyMonitor::new(promote_after, Instant::now()),
+                    vci_client: client.with_header_overrides(config.header_profiles.get("vci")),
+                    tickers,
+                    office_hours: config.office_hours_config.clone(),
+                    history_days: config.profile.history_days,
+                    max_points_per_symbol: config.profile.max_points_per_symbol,
+                    synthetic,
+                })
+            }
+            Err(e) => {
+                error!(?e, "Failed to initialize standby VCI client, standby disabled");
+                None
+            }
+        }
+    }
+
+    /// One pass over all tickers, merged like the core worker does but never gossiped:
+    /// a promoted public node is a stand-in, not an authoritative source
+    async fn fetch_cycle(&mut self, data: &SharedData, event_bus: &SharedEventBus, reconciler: &GossipReconciler) -> usize {
+        let lookback_days = if data.lock().await.is_empty() { self.history_days } else { REGULAR_LOOKBACK_DAYS };
+        let current_date = market_time::market_today();
+        let end_date = current_date.format("%Y-%m-%d").to_string();
+        let start_date = (current_date - chrono::Duration::days(lookback_days)).format("%Y-%m-%d").to_string();
+        let cycle_timer = Timer::start("worker.standby_cycle");
+        let mut updated_count = 0;
+
+        self.tickers.shuffle(&mut rand::rng());
+        for ticker_batch in self.tickers.chunks(BATCH_SIZE) {
+            // The client's own rate limiter and quota tracking still apply
+            match self.vci_client.get_batch_history(ticker_batch, &start_date, Some(&end_date), "1D").await {
+                Ok(batch_data) => {
+                    metrics::increment_counter("worker.standby_batches_ok", 1);
+                    let observed_at = Utc::now();
+                    let provisional_date = get_provisional_date(&self.office_hours);
+                    let mut data_guard = data.lock().await;
+                    let mut updated_symbols = Vec::new();
+                    for (symbol, ohlcv_data_vec) in batch_data {
+                        let Some(mut data_vec) = ohlcv_data_vec else { continue };
+                        if data_vec.len() > self.max_points_per_symbol {
+                            data_vec.sort_by_key(|d| std::cmp::Reverse(d.time));
+                            data_vec.truncate(self.max_points_per_symbol);
+                        }
+                        let data_vec = merge_policy::admit_bars(&symbol, data_vec, observed_at, |date| merge_policy::fetched_origin(date, provisional_date));
+                        let entry = data_guard.entry(symbol.clone()).or_default();
+                        crate::data_structures::merge_and_track_changes(&symbol, entry, data_vec, CorrectionSource::Vci);
+                        if let Some(latest) = entry.last() {
+                            events::publish(event_bus, &symbol, UpdateSource::Vci, latest.clone());
+                        }
+                        updated_symbols.push(symbol);
+                    }
+                    updated_count += updated_symbols.len();
+                    let staging_outcome = reconciler.reconcile(&data_guard, &updated_symbols).await;
+                    drop(data_guard);
+                    reconciler.record(&staging_outcome).await;
+                }
+                Err(e) => {
+                    metrics::increment_counter("worker.standby_batches_failed", 1);
+                    warn!(error = ?e, "Standby batch fetch from VCI failed");
+                }
+            }
+
+            let sleep_duration = Duration::from_millis(1000 + (rand::random::<u64>() % 1000));
+            tokio::time::sleep(sleep_duration).await;
+        }
+
+        self.synthetic.update(data, event_bus).await;
+        let elapsed = cycle_timer.stop();
+        info!(updated_symbols = updated_count, elapsed_secs = elapsed.as_secs(), "Completed standby fetch cycle from VCI");
+        updated_count
+    }
+}
+
+// Symbols per /tickers request when fetching changes, keeping URLs short
+const SYNC_FETCH_CHUNK: usize = 50;
+
+/// Position in the core's change log as of the last successful sync
+#[derive(Clone, Copy, Debug)]
+struct SyncCursor {
+    epoch: u64,
+    version: u64,
+}
+
+impl SyncCursor {
+    fn of(change_set: &ChangeSet) -> Self {
+        Self { epoch: change_set.epoch, version: change_set.version }
+    }
+}
+
+enum CoreSync {
+    Full(InMemoryData),        // Latest bar of every symbol
+    Incremental(InMemoryData), // Changed symbols from their first changed date
+}
+
+/// The core's changes since `cursor`, or `None` when the core has no change log
+async fn fetch_change_set(client: &reqwest::Client, core_url: &str, cursor: Option<SyncCursor>) -> Result<Option<ChangeSet>, String> {
+    let mut request = client.get(format!("{}/sync/changes", core_url));
+    if let Some(cursor) = cursor {
+        request = request.query(&[("epoch", cursor.epoch), ("since", cursor.version)]);
+    }
+    let response = request.send().await.map_err(|e| format!("Change log request failed: {}", e))?;
+    match response.status() {
+        StatusCode::NOT_FOUND => Ok(None),
+        status if status.is_success() => response.json().await.map(Some).map_err(|e| format!("Invalid change log response: {}", e)),
+        status => Err(format!("Core network responded with {} to the change log request", status)),
+    }
+}
+
+/// Bars from a `/tickers` response, binary or JSON depending on what the core node supports
+async fn read_bars(response: reqwest::Response) -> Result<InMemoryData, String> {
+    let binary = response.headers().get(CONTENT_TYPE).and_then(|value| value.to_str().ok()).is_some_and(wire::is_bars_content_type);
+    if binary {
+        let body = response.bytes().await.map_err(|e| e.to_string())?;
+        metrics::increment_counter("worker.core_sync_binary_bytes", body.len() as u64);
+        wire::decode(&body)
+    } else {
+        response.json().await.map_err(|e| e.to_string())
+    }
+}
+
+/// Fetch changed symbols at full precision, one request per first changed date
+async fn fetch_changed(client: &reqwest::Client, core_url: &str, changes: &[SymbolChange]) -> Result<InMemoryData, String> {
+    let mut by_date: BTreeMap<NaiveDate, Vec<&str>> = BTreeMap::new();
+    for change in changes {
+        by_date.entry(change.from).or_default().push(&change.symbol);
+    }
+    let mut data = InMemoryData::new();
+    for (from, symbols) in by_date {
+        for chunk in symbols.chunks(SYNC_FETCH_CHUNK) {
+            let mut query = vec![("precision", "full".to_string()), ("start_date", from.format("%Y-%m-%d").to_string())];
+            query.extend(chunk.iter().map(|symbol| ("symbol", symbol.to_string())));
+            let response = client.get(format!("{}/tickers", core_url)).query(&query).header(ACCEPT, wire::SYNC_ACCEPT).send().await
+                .map_err(|e| format!("Changed symbols request failed: {}", e))?;
+            if !response.status().is_success() {
+                return Err(format!("Core network responded with {} to the changed symbols request", response.status()));
+            }
+            let chunk_data = read_bars(response).await.map_err(|e| format!("Invalid changed symbols response: {}", e))?;
+            data.extend(chunk_data);
+        }
+    }
+    Ok(data)
+}
+
+/// Fetch what changed on the core since `cursor`, falling back to a full sync on first run,
+/// after a core restart, when too far behind, or when the core has no change log
+async fn sync_from_core(client: &reqwest::Client, core_url: &str, cursor: &mut Option<SyncCursor>) -> Result<CoreSync, String> {
+    let change_set = fetch_change_set(client, core_url, *cursor).await?;
+    if let Some(change_set) = change_set.as_ref().filter(|change_set| !change_set.full_resync) {
+        let data = fetch_changed(client, core_url, &change_set.changes).await?;
+        *cursor = Some(SyncCursor::of(change_set));
+        return Ok(CoreSync::Incremental(data));
+    }
+
+    // Replicas keep the core node's exact values. Changes made during this fetch are fetched again next time.
+    let response = client.get(format!("{}/tickers?precision=full", core_url)).header(ACCEPT, wire::SYNC_ACCEPT).send().await
+        .map_err(|e| format!("Tickers request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Core network responded with {} to the tickers request", response.status()));
+    }
+    let data = read_bars(response).await.map_err(|e| format!("Failed to parse core network response: {}", e))?;
+    *cursor = change_set.as_ref().map(SyncCursor::of);
+    Ok(CoreSync::Full(data))
+}
+
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip(data, health_stats, reconciler, event_bus, standby, intraday, shutdown), fields(core_url = %core_network_url, refresh_interval = ?refresh_interval))]
+async fn run_public_node_worker(data: SharedData, core_network_url: String, refresh_interval: Duration, health_stats: SharedHealthStats, reconciler: GossipReconciler, event_bus: SharedEventBus, mut standby: Option<StandbyFetcher>, intraday: IntradayCollector, shutdown: CancellationToken) {
     info!("Initializing public node worker");
-    let http_client = ReqwestClient::new();
+    let core_client = http_client::shared_client();
+    let mut sync_cursor = None;
     let mut iteration_count = 0;
     
     loop {
         iteration_count += 1;
+        let mut core_synced = false;
+        transitions::set_worker_state(iteration_count, 0, refresh_interval.as_secs());
+        // Bars come from the core node, which refetches them; the caches were already invalidated here
+        let skipped = recalculation::complete_queued("Refetch runs on the core node; cached indicators were invalidated");
+        if skipped > 0 {
+            debug!(iteration = iteration_count, jobs = skipped, "Completed recalculation jobs without refetching");
+        }
         debug!(iteration = iteration_count, "Starting core data sync cycle");
         
-        let core_tickers_url = format!("{}/tickers", core_network_url);
-        
-        match http_client.get(&core_tickers_url).send().await {
-            Ok(response) => {
-                if response.status().is_success() {
-                    match response.json::<InMemoryData>().await {
-                        Ok(core_data) => {
-                            info!(iteration = iteration_count, symbols_count = core_data.len(), "Successfully fetched data from core network");
-                            
-                            let mut local_data_guard = data.lock().await;
-                            let mut updated_symbols = Vec::new();
-                            let mut new_symbols = Vec::new();
-                            
-                            for (symbol, core_ohlcv_vec) in core_data {
-                                let local_entry = local_data_guard.entry(symbol.clone()).or_default();
-                                
-                                if let (Some(core_last), Some(local_last)) = (core_ohlcv_vec.last(), local_entry.last()) {
-                                    if core_last.time > local_last.time {
-                                        *local_entry = core_ohlcv_vec;
-                                        updated_symbols.push(symbol.clone());
-                                        debug!(symbol = %symbol, "Updated existing symbol with newer data");
-                                    }
-                                } else if local_entry.is_empty() {
+        let sync_timer = Timer::start("worker.core_sync_request");
+        let sync_result = sync_from_core(&core_client, &core_network_url, &mut sync_cursor).await;
+        sync_timer.stop();
+
+        match sync_result {
+            Ok(core_sync) => {
+                let mut local_data_guard = data.lock().await;
+                let mut updated_symbols = Vec::new();
+                let mut new_symbols = Vec::new();
+
+                match core_sync {
+                    CoreSync::Full(core_data) => {
+                        metrics::increment_counter("worker.core_sync_full", 1);
+                        info!(iteration = iteration_count, symbols_count = core_data.len(), "Successfully fetched data from core network");
+                        for (symbol, core_ohlcv_vec) in core_data {
+                            let local_entry = local_data_guard.entry(symbol.clone()).or_default();
+
+                            if let (Some(core_last), Some(local_last)) = (core_ohlcv_vec.last(), local_entry.last()) {
+                                if core_last.time > local_last.time {
+                                    corrections::record_changes(&symbol, local_entry, &core_ohlcv_vec, CorrectionSource::Core);
                                     *local_entry = core_ohlcv_vec;
-                                    new_symbols.push(symbol.clone());
-                                    debug!(symbol = %symbol, "Added new symbol data");
+                                    updated_symbols.push(symbol.clone());
+                                    debug!(symbol = %symbol, "Updated existing symbol with newer data");
                                 }
+                            } else if local_entry.is_empty() {
+                                *local_entry = core_ohlcv_vec;
+                                new_symbols.push(symbol.clone());
+                                debug!(symbol = %symbol, "Added new symbol data");
                             }
-                            
-                            drop(local_data_guard);
-                            info!(iteration = iteration_count, updated = ?updated_symbols, new = ?new_symbols, "Completed core data sync");
                         }
-                        Err(e) => {
-                            error!(iteration = iteration_count, error = ?e, "Failed to parse core network response as JSON");
+                    }
+                    CoreSync::Incremental(changed_data) => {
+                        metrics::increment_counter("worker.core_sync_incremental", 1);
+                        debug!(iteration = iteration_count, changed_symbols = changed_data.len(), "Fetched changed symbols from core network");
+                        for (symbol, core_ohlcv_vec) in changed_data {
+                            let local_entry = local_data_guard.entry(symbol.clone()).or_default();
+                            if local_entry.is_empty() {
+                                new_symbols.push(symbol.clone());
+                            } else {
+                                updated_symbols.push(symbol.clone());
+                            }
+                            let before = local_entry.clone();
+                            crate::data_structures::merge_and_deduplicate_data(local_entry, core_ohlcv_vec);
+                            corrections::record_changes(&symbol, &before, local_entry, CorrectionSource::Core);
                         }
                     }
-                } else {
-                    warn!(iteration = iteration_count, status = %response.status(), "Core network responded with error status");
                 }
+
+                let refreshed: Vec<String> = updated_symbols.iter().chain(new_symbols.iter()).cloned().collect();
+                for symbol in &refreshed {
+                    if let Some(latest) = local_data_guard.get(symbol).and_then(|series| series.last()) {
+                        events::publish(&event_bus, symbol, UpdateSource::Core, latest.clone());
+                    }
+                }
+                let staging_outcome = reconciler.reconcile(&local_data_guard, &refreshed).await;
+                drop(local_data_guard);
+                reconciler.record(&staging_outcome).await;
+                let mut health = health_stats.lock().await;
+                if !health.initial_load_complete {
+                    health.initial_load_complete = true;
+                    info!(report = ?metrics::get_performance_report(), "Initial sync from core complete");
+                }
+                drop(health);
+                core_synced = true;
+                info!(iteration = iteration_count, updated = ?updated_symbols, new = ?new_symbols, "Completed core data sync");
             }
             Err(e) => {
-                error!(iteration = iteration_count, error = ?e, core_url = %core_tickers_url, "Failed to fetch data from core network");
+                error!(iteration = iteration_count, error = %e, core_url = %core_network_url, "Failed to sync data from core network");
+            }
+        }
+        if core_synced {
+            match intraday.sync_from_core(&core_client, &core_network_url).await {
+                Ok(added) => debug!(iteration = iteration_count, added, "Synced intraday candles from core network"),
+                Err(e) => warn!(iteration = iteration_count, error = %e, "Failed to sync intraday candles from core network"),
+            }
+        }
+
+        if let Some(standby) = standby.as_mut() {
+            let now = Instant::now();
+            let transition = if core_synced {
+                standby.monitor.record_core_success(now)
+            } else {
+                standby.monitor.record_core_failure(now)
+            };
+            match transition {
+                Some(StandbyTransition::Promote) => metrics::increment_counter("standby.promotions", 1),
+                Some(StandbyTransition::Demote) => metrics::increment_counter("standby.demotions", 1),
+                None => {}
+            }
+            health_stats.lock().await.standby = Some(standby.monitor.status(now, core_synced));
+
+            if standby.monitor.mode() == StandbyMode::Promoted
+                && standby.fetch_cycle(&data, &event_bus, &reconciler).await > 0
+            {
+                let mut health = health_stats.lock().await;
+                if !health.initial_load_complete {
+                    health.initial_load_complete = true;
+                    info!("Initial load complete from standby VCI fetch");
+                }
             }
         }
         
         debug!(refresh_interval = ?refresh_interval, "Sleeping before next sync cycle");
-        tokio::time::sleep(refresh_interval).await;
+        if !shutdown::sleep(&shutdown, refresh_interval).await {
+            info!(iteration = iteration_count, "Public node worker stopped");
+            return;
+        }
     }
 }
\ No newline at end of file